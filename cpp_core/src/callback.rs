@@ -0,0 +1,47 @@
+use std::ffi::c_void;
+use std::fmt;
+
+/// Beginning of the C++ object holding a closure
+/// (`ritual::Closure` in the generated C++ code).
+#[repr(C)]
+struct RawClosure {
+    release: Option<unsafe extern "C" fn(*mut c_void)>,
+}
+
+/// Owns a Rust closure registered as a callback of a C++ library.
+///
+/// Functions generated by `ritual` return `CallbackGuard` when they pass a closure
+/// to a C++ function that has a paired unregister function.
+/// Dropping the guard unregisters the callback and frees the closure.
+/// Use `std::mem::forget` to keep the callback registered forever.
+pub struct CallbackGuard {
+    closure: *mut c_void,
+}
+
+impl CallbackGuard {
+    /// Creates a guard for a closure object returned by a generated FFI function.
+    ///
+    /// ### Safety
+    ///
+    /// `closure` must be a pointer returned by a generated FFI function
+    /// that registers a callback. The closure must not be released by other means.
+    pub unsafe fn from_raw(closure: *mut c_void) -> Self {
+        CallbackGuard { closure }
+    }
+}
+
+impl Drop for CallbackGuard {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(release) = (*(self.closure as *mut RawClosure)).release {
+                release(self.closure);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for CallbackGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CallbackGuard({:?})", self.closure)
+    }
+}
@@ -82,6 +82,18 @@ where
     }
 }
 
+impl<T, U> CastFrom<Option<Ref<U>>> for Ptr<T>
+where
+    U: StaticUpcast<T>,
+{
+    unsafe fn cast_from(value: Option<Ref<U>>) -> Self {
+        match value {
+            Some(value) => Self::cast_from(value),
+            None => Self::null(),
+        }
+    }
+}
+
 impl<T> CastFrom<NullPtr> for Ptr<T> {
     unsafe fn cast_from(_value: NullPtr) -> Self {
         Self::null()
@@ -1,7 +1,6 @@
-use crate::ops::{Begin, BeginMut, End, EndMut, Increment, Indirection};
 use crate::vector_ops::{Data, DataMut, Size};
-use crate::{cpp_iter, CppIterator, DynamicCast, Ptr, Ref, StaticDowncast, StaticUpcast};
-use std::ops::Deref;
+use crate::{DynamicCast, Ptr, Ref, StaticDowncast, StaticUpcast};
+use std::ops::{Deref, DerefMut};
 use std::{fmt, mem, ptr, slice};
 
 /// Objects that can be deleted using C++'s `delete` operator.
@@ -18,6 +17,20 @@ pub trait CppDeletable: Sized {
     unsafe fn delete(&self);
 }
 
+/// Objects that can be copied using C++'s copy constructor.
+///
+/// This trait is automatically implemented by `ritual` for class types
+/// that have a public copy constructor. It allows `CppBox<T>` to implement `Clone`.
+pub trait CppClone: CppDeletable {
+    /// Calls C++'s copy constructor on `self` and returns the new object.
+    ///
+    /// # Safety
+    ///
+    /// The caller must make sure `self` contains a valid pointer. This function
+    /// may invoke arbitrary foreign code, so no safety guarantees can be made.
+    unsafe fn clone(&self) -> CppBox<Self>;
+}
+
 /// An owning pointer to a C++ object.
 ///
 /// `CppBox` is automatically used in places where C++ class objects are passed by value
@@ -45,8 +58,8 @@ pub trait CppDeletable: Sized {
 /// thanks to nested `Deref` implementations.
 ///
 /// If the object provides an iterator interface through `begin()` and `end()` functions,
-/// `&CppBox<T>` and `&mut CppBox<T>` will implement `IntoIterator`,
-/// so you can iterate on them directly.
+/// its `iter()` and `iter_mut()` methods generated by `ritual` can be called
+/// through the pointer.
 ///
 /// ### Safety
 ///
@@ -260,46 +273,6 @@ where
     }
 }
 
-impl<T, T1, T2> CppBox<T>
-where
-    T: Begin<Output = CppBox<T1>> + End<Output = CppBox<T2>> + CppDeletable,
-    T1: CppDeletable + PartialEq<Ref<T2>> + Increment + Indirection,
-    T2: CppDeletable,
-{
-    /// Returns an iterator over the content of the object,
-    /// based on `begin()` and `end()` methods.
-    ///
-    /// # Safety
-    ///
-    /// The caller must make sure `self` contains a valid pointer. The content must
-    /// not be read or modified through other ways while the returned slice exists.
-    /// This function
-    /// may invoke arbitrary foreign code, so no safety guarantees can be made.
-    pub unsafe fn iter(&self) -> CppIterator<T1, T2> {
-        cpp_iter(self.begin(), self.end())
-    }
-}
-
-impl<T, T1, T2> CppBox<T>
-where
-    T: BeginMut<Output = CppBox<T1>> + EndMut<Output = CppBox<T2>> + CppDeletable,
-    T1: CppDeletable + PartialEq<Ref<T2>> + Increment + Indirection,
-    T2: CppDeletable,
-{
-    /// Returns a mutable iterator over the content of the object,
-    /// based on `begin()` and `end()` methods.
-    ///
-    /// # Safety
-    ///
-    /// The caller must make sure `self` contains a valid pointer. The content must
-    /// not be read or modified through other ways while the returned slice exists.
-    /// This function
-    /// may invoke arbitrary foreign code, so no safety guarantees can be made.
-    pub unsafe fn iter_mut(&self) -> CppIterator<T1, T2> {
-        cpp_iter(self.begin_mut(), self.end_mut())
-    }
-}
-
 /// Allows to call member functions of `T` and its base classes directly on the pointer.
 impl<T: CppDeletable> Deref for CppBox<T> {
     type Target = T;
@@ -308,6 +281,24 @@ impl<T: CppDeletable> Deref for CppBox<T> {
     }
 }
 
+/// Allows to call member functions of `T` that take `&mut self`.
+/// Such functions are generated when borrow checking is enabled
+/// in the configuration of the crate.
+impl<T: CppDeletable> DerefMut for CppBox<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { self.0.as_mut() }
+    }
+}
+
+/// `CppBox` can be sent to another thread if the object itself can be moved
+/// to another thread. Generated types are `Send` only if it's enabled
+/// in the configuration of the crate.
+unsafe impl<T: CppDeletable + Send> Send for CppBox<T> {}
+
+/// `CppBox` can be shared between threads if the object itself can be used
+/// from multiple threads.
+unsafe impl<T: CppDeletable + Sync> Sync for CppBox<T> {}
+
 /// Deletes the stored object using C++'s `delete` operator.
 impl<T: CppDeletable> Drop for CppBox<T> {
     fn drop(&mut self) {
@@ -317,6 +308,13 @@ impl<T: CppDeletable> Drop for CppBox<T> {
     }
 }
 
+/// Copies the stored object using C++'s copy constructor.
+impl<T: CppClone> Clone for CppBox<T> {
+    fn clone(&self) -> Self {
+        unsafe { CppClone::clone(&**self) }
+    }
+}
+
 impl<T: CppDeletable> fmt::Debug for CppBox<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "CppBox({:?})", self.0)
@@ -325,8 +323,8 @@ impl<T: CppDeletable> fmt::Debug for CppBox<T> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{CppBox, CppDeletable, Ptr};
-    use std::cell::RefCell;
+    use crate::{CppBox, CppClone, CppDeletable, Ptr};
+    use std::cell::{Cell, RefCell};
     use std::rc::Rc;
 
     struct Struct1 {
@@ -355,4 +353,70 @@ mod tests {
         }
         assert!(*value1.borrow() == 42);
     }
+
+    struct Struct2 {
+        value: i32,
+    }
+
+    impl CppDeletable for Struct2 {
+        unsafe fn delete(&self) {
+            drop(Box::from_raw(self as *const Struct2 as *mut Struct2));
+        }
+    }
+
+    impl CppClone for Struct2 {
+        unsafe fn clone(&self) -> CppBox<Self> {
+            let copy = Box::new(Struct2 { value: self.value });
+            CppBox::from_raw(Box::into_raw(copy)).unwrap()
+        }
+    }
+
+    struct Struct3 {
+        deleted: Rc<Cell<i32>>,
+    }
+
+    impl CppDeletable for Struct3 {
+        unsafe fn delete(&self) {
+            self.deleted.set(self.deleted.get() + 1);
+            drop(Box::from_raw(self as *const Struct3 as *mut Struct3));
+        }
+    }
+
+    fn new_struct3(deleted: &Rc<Cell<i32>>) -> CppBox<Struct3> {
+        let object = Box::new(Struct3 {
+            deleted: deleted.clone(),
+        });
+        unsafe { CppBox::from_raw(Box::into_raw(object)).unwrap() }
+    }
+
+    #[test]
+    fn test_drop_deletes_once() {
+        let deleted = Rc::new(Cell::new(0));
+        let object = new_struct3(&deleted);
+        let moved = object;
+        assert_eq!(deleted.get(), 0);
+        drop(moved);
+        assert_eq!(deleted.get(), 1);
+    }
+
+    #[test]
+    fn test_raw_ptr_round_trip() {
+        let deleted = Rc::new(Cell::new(0));
+        let ptr = new_struct3(&deleted).into_raw_ptr();
+        assert_eq!(deleted.get(), 0);
+        let object = unsafe { CppBox::from_raw(ptr) }.unwrap();
+        assert_eq!(object.as_raw_ptr(), ptr as *const Struct3);
+        drop(object);
+        assert_eq!(deleted.get(), 1);
+        assert!(unsafe { CppBox::<Struct3>::from_raw(std::ptr::null()) }.is_none());
+    }
+
+    #[test]
+    fn test_clone_calls_copy_constructor() {
+        let object1 =
+            unsafe { CppBox::from_raw(Box::into_raw(Box::new(Struct2 { value: 10 }))).unwrap() };
+        let object2 = object1.clone();
+        assert_eq!(object2.value, 10);
+        assert_ne!(object1.as_raw_ptr(), object2.as_raw_ptr());
+    }
 }
@@ -1,12 +1,12 @@
 use crate::ops::{Decrement, Increment, Indirection};
 use crate::{CppBox, CppDeletable, Ref};
+use std::marker::PhantomData;
 use std::os::raw::c_char;
 
 /// `Iterator` and `DoubleEndedIterator` backed by C++ iterators.
 ///
-/// This object is produced by `IntoIterator` implementations on  pointer types
-/// (`&CppBox`, `&mut CppBox`, `Ptr`, `Ref`). You can also use
-/// `cpp_iter` function to construct it manually from two C++ iterator objects.
+/// Use `cpp_iter` function to construct it from two C++ iterator objects.
+/// `RefIter` and `RefIterMut` wrap it to produce references to the elements.
 pub struct CppIterator<T1, T2>
 where
     T1: CppDeletable,
@@ -73,6 +73,175 @@ where
     }
 }
 
+/// Converts a value returned by `operator*` of a C++ iterator to a Rust reference.
+pub trait ElementRef {
+    /// Type of the element.
+    type Element;
+    /// Returns a reference to the element.
+    ///
+    /// ### Safety
+    ///
+    /// The element must be valid for the lifetime `'a`.
+    unsafe fn element<'a>(self) -> &'a Self::Element;
+    /// Returns a mutable reference to the element.
+    ///
+    /// ### Safety
+    ///
+    /// The element must be valid for the lifetime `'a` and must not be aliased.
+    unsafe fn element_mut<'a>(self) -> &'a mut Self::Element;
+}
+
+impl<T> ElementRef for Ref<T> {
+    type Element = T;
+    unsafe fn element<'a>(self) -> &'a T {
+        self.as_raw_ref()
+    }
+    unsafe fn element_mut<'a>(self) -> &'a mut T {
+        self.as_mut_raw_ref()
+    }
+}
+
+impl<T> ElementRef for *const T {
+    type Element = T;
+    unsafe fn element<'a>(self) -> &'a T {
+        &*self
+    }
+    unsafe fn element_mut<'a>(self) -> &'a mut T {
+        &mut *(self as *mut T)
+    }
+}
+
+impl<T> ElementRef for *mut T {
+    type Element = T;
+    unsafe fn element<'a>(self) -> &'a T {
+        &*self
+    }
+    unsafe fn element_mut<'a>(self) -> &'a mut T {
+        &mut *self
+    }
+}
+
+/// Iterator over references to the elements of a C++ collection.
+///
+/// This type is returned by `iter()` methods generated for C++ classes
+/// that provide `begin() const` and `end() const` functions.
+pub struct RefIter<'a, T1, T2>
+where
+    T1: CppDeletable,
+    T2: CppDeletable,
+{
+    inner: CppIterator<T1, T2>,
+    _marker: PhantomData<&'a ()>,
+}
+
+impl<'a, T1, T2> RefIter<'a, T1, T2>
+where
+    T1: CppDeletable,
+    T2: CppDeletable,
+{
+    /// Wraps `inner` iterator.
+    ///
+    /// ### Safety
+    ///
+    /// The collection must outlive `'a` and must not be modified while the iterator is used.
+    pub unsafe fn new(inner: CppIterator<T1, T2>) -> Self {
+        RefIter {
+            inner,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T1, T2> Iterator for RefIter<'a, T1, T2>
+where
+    T1: CppDeletable + PartialEq<Ref<T2>> + Indirection + Increment,
+    T2: CppDeletable,
+    <T1 as Indirection>::Output: ElementRef,
+    <<T1 as Indirection>::Output as ElementRef>::Element: 'a,
+{
+    type Item = &'a <<T1 as Indirection>::Output as ElementRef>::Element;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|value| unsafe { value.element() })
+    }
+}
+
+impl<'a, T1, T2> DoubleEndedIterator for RefIter<'a, T1, T2>
+where
+    T1: CppDeletable + PartialEq<Ref<T2>> + Indirection + Increment,
+    T2: CppDeletable + Decrement + Indirection<Output = <T1 as Indirection>::Output>,
+    <T1 as Indirection>::Output: ElementRef,
+    <<T1 as Indirection>::Output as ElementRef>::Element: 'a,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next_back()
+            .map(|value| unsafe { value.element() })
+    }
+}
+
+/// Iterator over mutable references to the elements of a C++ collection.
+///
+/// This type is returned by `iter_mut()` methods generated for C++ classes
+/// that provide non-const `begin()` and `end()` functions.
+pub struct RefIterMut<'a, T1, T2>
+where
+    T1: CppDeletable,
+    T2: CppDeletable,
+{
+    inner: CppIterator<T1, T2>,
+    _marker: PhantomData<&'a mut ()>,
+}
+
+impl<'a, T1, T2> RefIterMut<'a, T1, T2>
+where
+    T1: CppDeletable,
+    T2: CppDeletable,
+{
+    /// Wraps `inner` iterator.
+    ///
+    /// ### Safety
+    ///
+    /// The collection must outlive `'a` and must not be accessed by other means
+    /// while the iterator is used.
+    pub unsafe fn new(inner: CppIterator<T1, T2>) -> Self {
+        RefIterMut {
+            inner,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T1, T2> Iterator for RefIterMut<'a, T1, T2>
+where
+    T1: CppDeletable + PartialEq<Ref<T2>> + Indirection + Increment,
+    T2: CppDeletable,
+    <T1 as Indirection>::Output: ElementRef,
+    <<T1 as Indirection>::Output as ElementRef>::Element: 'a,
+{
+    type Item = &'a mut <<T1 as Indirection>::Output as ElementRef>::Element;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|value| unsafe { value.element_mut() })
+    }
+}
+
+impl<'a, T1, T2> DoubleEndedIterator for RefIterMut<'a, T1, T2>
+where
+    T1: CppDeletable + PartialEq<Ref<T2>> + Indirection + Increment,
+    T2: CppDeletable + Decrement + Indirection<Output = <T1 as Indirection>::Output>,
+    <T1 as Indirection>::Output: ElementRef,
+    <<T1 as Indirection>::Output as ElementRef>::Element: 'a,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next_back()
+            .map(|value| unsafe { value.element_mut() })
+    }
+}
+
 /// A convenience trait that provides `end_ptr()` method for slices.
 pub trait EndPtr {
     /// Type of item.
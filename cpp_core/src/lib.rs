@@ -28,7 +28,8 @@
 //! thanks to nested `Deref` implementations.
 //!
 //! If the object provides an iterator interface through `begin()` and `end()` functions,
-//! pointer types will implement `IntoIterator`, so you can iterate on them directly.
+//! `ritual` generates `iter()` and `iter_mut()` methods returning Rust iterators
+//! over references to the elements.
 //!
 //! # Casts
 //!
@@ -53,14 +54,17 @@
 
 #![deny(missing_docs)]
 
+pub use crate::callback::CallbackGuard;
 pub use crate::casts::{DynamicCast, StaticDowncast, StaticUpcast};
 pub use crate::convert::{CastFrom, CastInto};
-pub use crate::cpp_box::{CppBox, CppDeletable};
-pub use crate::iterator::{cpp_iter, CppIterator, EndPtr};
+pub use crate::cpp_box::{CppBox, CppClone, CppDeletable};
+pub use crate::iterator::{cpp_iter, CppIterator, ElementRef, EndPtr, RefIter, RefIterMut};
 pub use crate::ptr::{NullPtr, Ptr};
 pub use crate::ref_::Ref;
+pub use crate::string_arg::{FromUtf8, StringArg};
 pub use libc::wchar_t;
 
+mod callback;
 mod casts;
 pub mod cmp;
 mod convert;
@@ -70,6 +74,7 @@ pub mod ops;
 mod ops_impls;
 mod ptr;
 mod ref_;
+mod string_arg;
 pub mod vector_ops;
 
 // C++ doesn't guarantee these types to be exactly u16 and u32,
@@ -1,8 +1,5 @@
-use crate::ops::{Begin, BeginMut, End, EndMut, Increment, Indirection};
 use crate::vector_ops::{Data, DataMut, Size};
-use crate::{
-    cpp_iter, CppBox, CppDeletable, CppIterator, DynamicCast, Ref, StaticDowncast, StaticUpcast,
-};
+use crate::{CppBox, CppDeletable, DynamicCast, Ref, StaticDowncast, StaticUpcast};
 use std::ops::Deref;
 use std::{fmt, slice};
 
@@ -27,7 +24,8 @@ use std::{fmt, slice};
 /// a null pointer.
 ///
 /// If the object provides an iterator interface through `begin()` and `end()` functions,
-/// `Ptr` will implement `IntoIterator`, so you can iterate on it directly.
+/// its `iter()` method generated by `ritual` can be called through the pointer.
+/// `iter_mut()` requires a mutable reference that can be obtained with `as_mut_raw_ref()`.
 ///
 /// ### Safety
 ///
@@ -208,46 +206,6 @@ where
     }
 }
 
-impl<T, T1, T2> Ptr<T>
-where
-    T: Begin<Output = CppBox<T1>> + End<Output = CppBox<T2>>,
-    T1: CppDeletable + PartialEq<Ref<T2>> + Increment + Indirection,
-    T2: CppDeletable,
-{
-    /// Returns an iterator over the content of the object,
-    /// based on `begin()` and `end()` methods.
-    ///
-    /// # Safety
-    ///
-    /// The caller must make sure `self` contains a valid pointer. The content must
-    /// not be read or modified through other ways while the returned slice exists.
-    /// This function
-    /// may invoke arbitrary foreign code, so no safety guarantees can be made.
-    pub unsafe fn iter(self) -> CppIterator<T1, T2> {
-        cpp_iter(self.begin(), self.end())
-    }
-}
-
-impl<T, T1, T2> Ptr<T>
-where
-    T: BeginMut<Output = CppBox<T1>> + EndMut<Output = CppBox<T2>>,
-    T1: CppDeletable + PartialEq<Ref<T2>> + Increment + Indirection,
-    T2: CppDeletable,
-{
-    /// Returns a mutable iterator over the content of the object,
-    /// based on `begin()` and `end()` methods.
-    ///
-    /// # Safety
-    ///
-    /// The caller must make sure `self` contains a valid pointer. The content must
-    /// not be read or modified through other ways while the returned slice exists.
-    /// This function
-    /// may invoke arbitrary foreign code, so no safety guarantees can be made.
-    pub unsafe fn iter_mut(self) -> CppIterator<T1, T2> {
-        cpp_iter(self.begin_mut(), self.end_mut())
-    }
-}
-
 /// Allows to call member functions of `T` and its base classes directly on the pointer.
 ///
 /// Panics if the pointer is null.
@@ -284,6 +242,19 @@ impl<T: CppDeletable> Ptr<T> {
 /// as well, but that would require a type annotation.
 pub struct NullPtr;
 
+#[test]
+fn ptr_from_option_ref() {
+    use crate::{CastInto, Ref};
+
+    let i = 42;
+    unsafe {
+        let ptr: Ptr<i32> = Ref::from_raw(&i).cast_into();
+        assert_eq!(*ptr, 42);
+        let ptr: Ptr<i32> = None::<Ref<i32>>.cast_into();
+        assert!(ptr.is_null());
+    }
+}
+
 #[test]
 fn ptr_deref() {
     let i = 42;
@@ -1,8 +1,5 @@
-use crate::ops::{Begin, BeginMut, End, EndMut, Increment, Indirection};
 use crate::vector_ops::{Data, DataMut, Size};
-use crate::{
-    cpp_iter, CppBox, CppDeletable, CppIterator, DynamicCast, Ptr, StaticDowncast, StaticUpcast,
-};
+use crate::{DynamicCast, Ptr, StaticDowncast, StaticUpcast};
 use std::ops::Deref;
 use std::{fmt, ptr, slice};
 
@@ -23,7 +20,8 @@ use std::{fmt, ptr, slice};
 /// thanks to nested `Deref` implementations.
 ///
 /// If the object provides an iterator interface through `begin()` and `end()` functions,
-/// `Ref` will implement `IntoIterator`, so you can iterate on it directly.
+/// its `iter()` method generated by `ritual` can be called through the pointer.
+/// `iter_mut()` requires a mutable reference that can be obtained with `as_mut_raw_ref()`.
 ///
 /// ### Safety
 ///
@@ -202,46 +200,6 @@ where
     }
 }
 
-impl<T, T1, T2> Ref<T>
-where
-    T: Begin<Output = CppBox<T1>> + End<Output = CppBox<T2>>,
-    T1: CppDeletable + PartialEq<Ref<T2>> + Increment + Indirection,
-    T2: CppDeletable,
-{
-    /// Returns an iterator over the content of the object,
-    /// based on `begin()` and `end()` methods.
-    ///
-    /// # Safety
-    ///
-    /// The caller must make sure `self` contains a valid pointer. The content must
-    /// not be read or modified through other ways while the returned slice exists.
-    /// This function
-    /// may invoke arbitrary foreign code, so no safety guarantees can be made.
-    pub unsafe fn iter(self) -> CppIterator<T1, T2> {
-        cpp_iter(self.begin(), self.end())
-    }
-}
-
-impl<T, T1, T2> Ref<T>
-where
-    T: BeginMut<Output = CppBox<T1>> + EndMut<Output = CppBox<T2>>,
-    T1: CppDeletable + PartialEq<Ref<T2>> + Increment + Indirection,
-    T2: CppDeletable,
-{
-    /// Returns a mutable iterator over the content of the object,
-    /// based on `begin()` and `end()` methods.
-    ///
-    /// # Safety
-    ///
-    /// The caller must make sure `self` contains a valid pointer. The content must
-    /// not be read or modified through other ways while the returned slice exists.
-    /// This function
-    /// may invoke arbitrary foreign code, so no safety guarantees can be made.
-    pub unsafe fn iter_mut(self) -> CppIterator<T1, T2> {
-        cpp_iter(self.begin_mut(), self.end_mut())
-    }
-}
-
 /// Allows to call member functions of `T` and its base classes directly on the pointer.
 impl<T> Deref for Ref<T> {
     type Target = T;
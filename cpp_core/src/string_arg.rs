@@ -0,0 +1,70 @@
+use crate::{CppBox, CppDeletable, Ref};
+
+/// C++ string types that can be created from UTF-8 text.
+///
+/// This trait is automatically implemented by `ritual` for string types
+/// added with `Config::add_string_type`.
+pub trait FromUtf8: CppDeletable {
+    /// Creates a new string object containing `text`.
+    fn from_utf8(text: &str) -> CppBox<Self>;
+}
+
+/// An argument of a C++ function accepting a string type `T`
+/// by value or by const reference.
+///
+/// Functions generated by `ritual` accept `impl Into<StringArg<T>>`, so both
+/// Rust strings (`&str`, `String`) and existing string objects
+/// (`Ref<T>`, `&CppBox<T>`, `CppBox<T>`) can be passed.
+pub enum StringArg<T: CppDeletable> {
+    /// An existing string object
+    Borrowed(Ref<T>),
+    /// A string object created for this argument
+    Owned(CppBox<T>),
+}
+
+impl<T: CppDeletable> StringArg<T> {
+    /// Returns constant raw pointer to the string object.
+    pub fn as_raw_ptr(&self) -> *const T {
+        match self {
+            StringArg::Borrowed(value) => value.as_raw_ptr(),
+            StringArg::Owned(value) => value.as_raw_ptr(),
+        }
+    }
+}
+
+impl<T: CppDeletable> From<Ref<T>> for StringArg<T> {
+    fn from(value: Ref<T>) -> Self {
+        StringArg::Borrowed(value)
+    }
+}
+
+impl<'a, T: CppDeletable> From<&'a CppBox<T>> for StringArg<T> {
+    fn from(value: &'a CppBox<T>) -> Self {
+        // `CppBox` is never null
+        StringArg::Borrowed(unsafe { value.as_ref() })
+    }
+}
+
+impl<T: CppDeletable> From<CppBox<T>> for StringArg<T> {
+    fn from(value: CppBox<T>) -> Self {
+        StringArg::Owned(value)
+    }
+}
+
+impl<'a, T: FromUtf8> From<&'a str> for StringArg<T> {
+    fn from(value: &'a str) -> Self {
+        StringArg::Owned(T::from_utf8(value))
+    }
+}
+
+impl<'a, T: FromUtf8> From<&'a String> for StringArg<T> {
+    fn from(value: &'a String) -> Self {
+        StringArg::Owned(T::from_utf8(value))
+    }
+}
+
+impl<T: FromUtf8> From<String> for StringArg<T> {
+    fn from(value: String) -> Self {
+        StringArg::Owned(T::from_utf8(&value))
+    }
+}
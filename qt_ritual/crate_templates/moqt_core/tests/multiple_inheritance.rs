@@ -0,0 +1,27 @@
+use moqt_core::{MultipleBaseA, MultipleBaseB, MultipleDerived};
+use std::ptr;
+
+#[test]
+fn multiple_inheritance() {
+    unsafe {
+        let mut derived = MultipleDerived::new();
+
+        // `Deref` converts to the first public base
+        let base_a: &MultipleBaseA = &derived;
+        assert_eq!(base_a.a_value(), 1);
+        assert!(ptr::eq(base_a, derived.base_a().as_raw_ptr()));
+
+        // the second base is at a non-zero offset
+        let base_b: &MultipleBaseB = derived.as_multiple_base_b();
+        assert_eq!(base_b.b_value(), 2);
+        assert!(ptr::eq(base_b, derived.base_b().as_raw_ptr()));
+        assert_ne!(
+            base_b as *const MultipleBaseB as usize,
+            &*derived as *const MultipleDerived as usize
+        );
+
+        derived.as_multiple_base_b_mut().set_b_value(3);
+        assert_eq!(derived.as_multiple_base_b().b_value(), 3);
+        assert_eq!(derived.base_b().b_value(), 3);
+    }
+}
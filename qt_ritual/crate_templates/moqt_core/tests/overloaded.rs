@@ -17,3 +17,21 @@ fn overloaded() {
         a.match_1a(42);
     }
 }
+
+#[test]
+fn const_overloads() {
+    use std::ops::Index;
+
+    unsafe {
+        let a = Overloaded::from_int(1);
+        *a.begin_mut() = 1;
+        *a.begin_mut().add(1) = 5;
+        assert_eq!(*a.begin(), 1);
+        assert_eq!(*a.index(1), 5);
+
+        // `value_mut` is taken by `valueMut`, so the pair gets a fallback suffix
+        *a.value_0a_mut() = 2;
+        assert_eq!(*a.value_0a(), 2);
+        assert_eq!(a.value_mut(), -1);
+    }
+}
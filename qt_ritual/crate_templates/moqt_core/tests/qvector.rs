@@ -33,18 +33,18 @@ fn qvector_class() {
 #[test]
 fn qvector_iter() {
     unsafe {
-        let vec = QVectorOfInt::new_0a();
+        let mut vec = QVectorOfInt::new_0a();
         vec.append_int(&10);
         vec.append_int(&12);
         vec.append_int(&14);
         vec.append_int(&16);
 
-        let collected: Vec<c_int> = cpp_iter(vec.begin(), vec.end())
+        let collected: Vec<c_int> = cpp_iter(vec.begin_mut(), vec.end_mut())
             .map(|mut_ref| *mut_ref)
             .collect();
         assert_eq!(collected, [10, 12, 14, 16]);
 
-        let reversed: Vec<c_int> = cpp_iter(vec.begin(), vec.end())
+        let reversed: Vec<c_int> = cpp_iter(vec.begin_mut(), vec.end_mut())
             .map(|mut_ref| *mut_ref)
             .rev()
             .collect();
@@ -53,8 +53,34 @@ fn qvector_iter() {
         for x in vec.iter_mut() {
             let _ = *x;
         }
-        for x in vec.as_ptr().iter_mut() {
+        for x in vec.as_ptr().as_mut_raw_ref().unwrap().iter_mut() {
             let _ = *x;
         }
     }
 }
+
+#[test]
+fn qvector_ref_iter() {
+    unsafe {
+        let mut vec = QVectorOfInt::new_0a();
+        vec.append_int(&1);
+        vec.append_int(&2);
+        vec.append_int(&3);
+
+        let collected: Vec<c_int> = vec.iter().cloned().collect();
+        assert_eq!(collected, [1, 2, 3]);
+
+        for x in vec.iter_mut() {
+            *x *= 10;
+        }
+        let reversed: Vec<c_int> = vec.iter().rev().cloned().collect();
+        assert_eq!(reversed, [30, 20, 10]);
+
+        let vec = QVectorOfBasicClassField::new_0a();
+        let f = BasicClassField::new();
+        f.set(5);
+        vec.append_basic_class_field(f.as_ref());
+        let values: Vec<c_int> = vec.iter().map(|field| field.get()).collect();
+        assert_eq!(values, [5]);
+    }
+}
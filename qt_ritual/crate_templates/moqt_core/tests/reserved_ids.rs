@@ -10,13 +10,13 @@ fn reserved_ids() {
 
     unsafe {
         let obj = Unsafe::new();
-        assert_eq!(obj.loop_(), 1);
-        obj.yield_(1);
-        assert_eq!(obj.pub_().loop_(), 1);
+        assert_eq!(obj.r#loop(), 1);
+        obj.r#yield(1);
+        assert_eq!(obj.r#pub().r#loop(), 1);
 
         obj.set_super(2.4);
         assert_eq!(obj.super_(), 2.4);
 
-        self_::box_(22);
+        self_::r#box(22);
     }
 }
@@ -1,5 +1,11 @@
-use moqt_core::{moqt_abs, moqt_core_version};
+use moqt_core::{
+    moqt_abs, moqt_add_handler, moqt_core_version, moqt_div_mod, moqt_min_max, moqt_notify,
+    moqt_parse_int, moqt_transform,
+};
+use std::cell::RefCell;
 use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::rc::Rc;
 
 #[test]
 fn abs() {
@@ -18,3 +24,32 @@ fn version() {
         assert_eq!(str, "0.0.1");
     }
 }
+
+#[test]
+fn out_params() {
+    unsafe {
+        assert_eq!(moqt_parse_int(b"42\0".as_ptr() as *const c_char), Some(42));
+        assert_eq!(moqt_parse_int(b"4x2\0".as_ptr() as *const c_char), None);
+        assert_eq!(moqt_div_mod(17, 5), (3, 2));
+        assert_eq!(moqt_min_max(4, -1), (-1, 4));
+    }
+}
+
+#[test]
+fn closure_callbacks() {
+    unsafe {
+        let values = Rc::new(RefCell::new(Vec::new()));
+        let values2 = Rc::clone(&values);
+        let guard = moqt_add_handler(move |value: i32| values2.borrow_mut().push(value));
+        assert_eq!(moqt_notify(1), 1);
+        assert_eq!(moqt_notify(2), 1);
+        drop(guard);
+        assert_eq!(moqt_notify(3), 0);
+        assert_eq!(*values.borrow(), vec![1, 2]);
+        // the closure is freed along with the guard
+        assert_eq!(Rc::strong_count(&values), 1);
+
+        let offset = 10;
+        assert_eq!(moqt_transform(5, move |value: i32| value + offset), 15);
+    }
+}
@@ -0,0 +1,22 @@
+use cpp_core::CppBox;
+use moqt_core::QString;
+use moqt_gui::QLabel;
+
+#[test]
+fn string_arguments() {
+    unsafe {
+        let label = QLabel::new();
+        label.set_text("hello");
+        assert_eq!(label.text().to_std_string(), "hello");
+
+        label.set_text(String::from("world"));
+        assert_eq!(label.text().to_std_string(), "world");
+
+        let text: CppBox<QString> = label.text();
+        label.set_prefixed_text("hello, ", &text);
+        assert_eq!(label.text().to_std_string(), "hello, world");
+
+        label.set_text(text.as_ref());
+        assert_eq!(label.text().to_std_string(), "world");
+    }
+}
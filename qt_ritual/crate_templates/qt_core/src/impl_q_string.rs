@@ -17,16 +17,6 @@ impl QString {
         let slice = s.as_ref().as_bytes();
         unsafe { QString::from_utf8_char_int(slice.as_ptr() as *mut c_char, slice.len() as c_int) }
     }
-
-    /// Creates an `std` string from a Qt string.
-    pub fn to_std_string(&self) -> String {
-        unsafe {
-            let buf = self.to_utf8();
-            let bytes =
-                std::slice::from_raw_parts(buf.const_data() as *const u8, buf.size() as usize);
-            std::str::from_utf8_unchecked(bytes).to_string()
-        }
-    }
 }
 
 /// Creates a `QString` from a Rust string.
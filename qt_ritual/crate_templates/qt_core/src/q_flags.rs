@@ -1,6 +1,6 @@
 use std::fmt;
 use std::marker::PhantomData;
-use std::ops::{BitAnd, BitOr, BitXor};
+use std::ops::{BitAnd, BitOr, BitXor, Not};
 use std::os::raw::c_int;
 
 /// An OR-combination of integer values of the enum type `E`.
@@ -75,6 +75,16 @@ impl<E, T: Into<QFlags<E>>> BitXor<T> for QFlags<E> {
     }
 }
 
+impl<E> Not for QFlags<E> {
+    type Output = QFlags<E>;
+    fn not(self) -> QFlags<E> {
+        Self {
+            value: !self.value,
+            _phantom_data: PhantomData,
+        }
+    }
+}
+
 impl<E> Default for QFlags<E> {
     fn default() -> Self {
         QFlags {
@@ -1,7 +1,6 @@
 #![forbid(unsafe_code)]
 
 mod detect_signal_argument_types;
-mod detect_signals_and_slots;
 mod doc_decoder;
 mod doc_parser;
 pub mod lib_configs;
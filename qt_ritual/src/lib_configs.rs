@@ -1,18 +1,17 @@
 //! Generator configurations specific for each Qt module.
 
-use crate::detect_signals_and_slots::detect_signals_and_slots;
 use crate::doc_parser::{parse_docs, set_crate_root_doc};
 use crate::slot_wrappers::add_signal_slot_wrappers;
 use log::info;
 use qt_ritual_common::{all_crate_names, get_full_build_config, lib_dependencies, lib_folder_name};
 use ritual::config::{Config, CrateDependencyKind, GlobalConfig};
-use ritual::config::{CrateDependencySource, CrateProperties};
+use ritual::config::{CrateDependencySource, CrateProperties, StringConversion};
+use ritual::cpp_macros::extract_qt_property;
 use ritual_common::cpp_build_config::CppLibraryType;
 use ritual_common::cpp_build_config::{CppBuildConfigData, CppBuildPaths};
 use ritual_common::errors::{bail, format_err, Result, ResultExt};
 use ritual_common::file_utils::repo_dir_path;
 use ritual_common::target;
-use ritual_common::toml;
 use std::path::{Path, PathBuf};
 
 mod _3d;
@@ -31,6 +30,7 @@ use crate::lib_configs::qml::qml_config;
 use crate::lib_configs::ui_tools::ui_tools_config;
 use ritual::cpp_data::{CppItem, CppPath};
 use ritual::cpp_type::CppType;
+use ritual::rust_type::RustPath;
 use std::env;
 
 pub const MOQT_INSTALL_DIR_ENV_VAR_NAME: &str = "MOQT_INSTALL_DIR";
@@ -48,45 +48,23 @@ pub fn create_config(
 ) -> Result<Config> {
     let crate_name = crate_properties.name().to_string();
     info!("Preparing generator config for crate: {}", crate_name);
-    let mut custom_fields = toml::value::Table::new();
-    let mut package_data = toml::value::Table::new();
-    package_data.insert(
-        "authors".to_string(),
-        toml::Value::Array(vec![toml::Value::String(
-            "Pavel Strakhov <ri@idzaaus.org>".to_string(),
-        )]),
-    );
-    let description = format!("Bindings for {} C++ library", lib_folder_name(&crate_name));
-    package_data.insert("description".to_string(), toml::Value::String(description));
-    let doc_url = format!("https://docs.rs/{}", &crate_name);
-    package_data.insert("documentation".to_string(), toml::Value::String(doc_url));
-    package_data.insert(
-        "repository".to_string(),
-        toml::Value::String("https://github.com/rust-qt/ritual".to_string()),
-    );
-    package_data.insert(
-        "license".to_string(),
-        toml::Value::String("MIT OR Apache-2.0".to_string()),
-    );
-    package_data.insert(
-        "keywords".to_string(),
-        toml::Value::Array(vec![
-            toml::Value::String("gui".to_string()),
-            toml::Value::String("ffi".to_string()),
-            toml::Value::String("qt".to_string()),
-            toml::Value::String("ritual".to_string()),
-        ]),
-    );
-    package_data.insert(
-        "categories".to_string(),
-        toml::Value::Array(vec![
-            toml::Value::String("external-ffi-bindings".to_string()),
-            toml::Value::String("gui".to_string()),
-        ]),
-    );
-
-    custom_fields.insert("package".to_string(), toml::Value::Table(package_data));
-    crate_properties.set_custom_fields(custom_fields);
+    crate_properties.add_author("Pavel Strakhov <ri@idzaaus.org>");
+    crate_properties.set_description(format!(
+        "Bindings for {} C++ library",
+        lib_folder_name(&crate_name)
+    ));
+    crate_properties.add_custom_toml(&format!(
+        "[package]\ndocumentation = \"https://docs.rs/{}\"",
+        crate_name
+    ))?;
+    crate_properties.set_repository("https://github.com/rust-qt/ritual");
+    crate_properties.set_license("MIT OR Apache-2.0");
+    for &keyword in &["gui", "ffi", "qt", "ritual"] {
+        crate_properties.add_keyword(keyword)?;
+    }
+    for &category in &["external-ffi-bindings", "gui"] {
+        crate_properties.add_category(category)?;
+    }
 
     for &dependency in lib_dependencies(&crate_name)? {
         crate_properties.add_dependency(
@@ -191,7 +169,8 @@ pub fn create_config(
         if target::current_os() != target::OS::Windows {
             config.add_cpp_parser_argument("-fPIC");
         }
-        config.add_cpp_parser_argument("-fcxx-exceptions");
+        // clang disables C++ exceptions by default for MSVC targets
+        config.add_cpp_parser_only_argument("-fcxx-exceptions");
 
         let steps = config.processing_steps_mut();
         let crate_name_clone = crate_name.to_string();
@@ -213,7 +192,28 @@ pub fn create_config(
         config.add_cpp_parser_argument("-std=gnu++11");
     }
 
-    config.add_after_cpp_parser_hook(detect_signals_and_slots);
+    config.set_signal_macro_sections(true);
+    // mark `signals:` and `slots:` sections with annotations
+    config
+        .add_cpp_parser_argument("-DQT_ANNOTATE_ACCESS_SPECIFIER(a)=__attribute__((annotate(#a)))");
+    config.add_macro_extractor(extract_qt_property);
+    // Qt types are printed with `qDebug()`, not `std::ostream`
+    config.set_debug_stream_type(CppPath::from_good_str("QDebug"));
+    // allow passing Rust strings to functions accepting `QString`
+    config.add_string_type(
+        "QString",
+        StringConversion {
+            to_utf8_method: Some("toUtf8".into()),
+            from_utf8_ctor: "QString::fromUtf8".into(),
+        },
+    )?;
+    // `QFlags<E>` is passed as `int` and represented by `QFlags` of qt_core
+    let qt_core = if crate_name.starts_with("moqt_") {
+        "moqt_core"
+    } else {
+        "qt_core"
+    };
+    config.map_flags_template("QFlags", RustPath::from_good_str(qt_core).join("QFlags"))?;
 
     let steps = config.processing_steps_mut();
     for cpp_parser_stage in &["cpp_parser", "cpp_parser_stage2"] {
@@ -1,5 +1,5 @@
 use itertools::Itertools;
-use ritual::config::{Config, CrateDependencyKind, CrateDependencySource};
+use ritual::config::{Config, CrateDependencyKind, CrateDependencySource, OutParams};
 use ritual::cpp_checker::{PreliminaryTest, Snippet};
 use ritual::cpp_data::{CppItem, CppPath, CppPathItem, CppTypeDeclaration, CppTypeDeclarationKind};
 use ritual::cpp_ffi_data::CppFfiFunctionKind;
@@ -291,6 +291,20 @@ pub fn core_config(config: &mut Config) -> Result<()> {
         Ok(())
     });
 
+    if config.crate_properties().name().starts_with("moqt") {
+        config.set_out_params(
+            CppPath::from_good_str("moqt_parse_int"),
+            OutParams::SuccessFlag,
+        );
+        config.set_out_params(CppPath::from_good_str("moqt_div_mod"), OutParams::Tuple);
+        config.set_out_params(CppPath::from_good_str("moqt_min_max"), OutParams::Tuple);
+        config.add_callback_user_data_name("user_data");
+        config.set_callback_unregister_function(
+            CppPath::from_good_str("moqt_add_handler"),
+            CppPath::from_good_str("moqt_remove_handler"),
+        );
+    }
+
     let tests = if config.crate_properties().name().starts_with("moqt") {
         vec![PreliminaryTest::new(
             "moqt_abs",
@@ -402,7 +416,15 @@ fn add_qpointer(data: &mut ProcessorData<'_>) -> Result<()> {
                             "QObject",
                         ))]),
                     }),
-                    kind: CppTypeDeclarationKind::Class,
+                    kind: CppTypeDeclarationKind::Class {
+                        is_final: false,
+                        layout: None,
+                        using_declarations: Vec::new(),
+                        template_argument_defaults: Vec::new(),
+                        properties: Vec::new(),
+                    },
+                    deprecated: None,
+                    doc_comment: None,
                 }),
             )?;
         }
@@ -430,5 +452,9 @@ fn connection_to_bool_function() -> CppFunction {
         operator: None,
         declaration_code: None,
         return_type: CppType::BuiltInNumeric(CppBuiltInNumericType::Bool),
+        is_noexcept: false,
+        deprecated: None,
+        doc_comment: None,
+        is_extern_c: false,
     }
 }
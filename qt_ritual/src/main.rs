@@ -4,10 +4,34 @@
 
 #![allow(clippy::collapsible_if)]
 
+use log::info;
 use qt_ritual::lib_configs::global_config;
 use ritual::cli;
+use ritual::config::GlobalConfig;
 use ritual_common::errors::FancyUnwrap;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Installs a Ctrl-C handler that requests to interrupt the processing.
+/// The second Ctrl-C terminates the process immediately.
+fn set_ctrlc_handler(config: &mut GlobalConfig) {
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&interrupted);
+    let result = ctrlc::set_handler(move || {
+        if flag.swap(true, Ordering::SeqCst) {
+            process::exit(130);
+        }
+        info!("Interrupted. Press Ctrl-C again to exit immediately.");
+    });
+    match result {
+        Ok(()) => config.set_interruption_flag(interrupted),
+        Err(err) => eprintln!("failed to set Ctrl-C handler: {}", err),
+    }
+}
 
 pub fn main() {
-    cli::run_from_args(global_config()).fancy_unwrap();
+    let mut config = global_config();
+    set_ctrlc_handler(&mut config);
+    cli::run_from_args(config).fancy_unwrap();
 }
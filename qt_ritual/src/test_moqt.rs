@@ -64,6 +64,7 @@ fn build_moqt() -> Result<TempTestDir> {
         capture_output: false,
         skip_cmake: false,
         skip_cmake_after_first_run: false,
+        timeout: None,
     }
     .run()?;
 
@@ -125,8 +126,12 @@ fn test_moqt() {
             crates: vec!["moqt_core".into(), "moqt_gui".into()],
             operations: vec!["discard".into(), "main".into()],
             output_crates_version: "0.0.0".into(),
+            apply_suggested_version: false,
+            check: false,
             cluster: None,
             trace: None,
+            full: false,
+            progress_json: None,
         },
         global_config(),
     )
@@ -16,3 +16,26 @@ void Overloaded::setPos(QPoint pos, bool flag) {}
 
 void Overloaded::match() {}
 void Overloaded::match(int x) {}
+
+int* Overloaded::begin() {
+    return m_values;
+}
+const int* Overloaded::begin() const {
+    return m_values;
+}
+int& Overloaded::operator[](int index) {
+    return m_values[index];
+}
+const int& Overloaded::operator[](int index) const {
+    return m_values[index];
+}
+
+int& Overloaded::value() {
+    return m_values[0];
+}
+const int& Overloaded::value() const {
+    return m_values[0];
+}
+int Overloaded::valueMut() const {
+    return -1;
+}
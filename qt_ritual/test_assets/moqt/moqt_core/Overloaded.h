@@ -21,6 +21,21 @@ public:
 
     void match();
     void match(int x);
+
+    // const and non-const pairs
+    int* begin();
+    const int* begin() const;
+    int& operator[](int index);
+    const int& operator[](int index) const;
+
+    // `valueMut` takes the name that would be used
+    // for the non-const `value` overload
+    int& value();
+    const int& value() const;
+    int valueMut() const;
+
+private:
+    int m_values[3];
 };
 
 #endif //OVERLOADED_H
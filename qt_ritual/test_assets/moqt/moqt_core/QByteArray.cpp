@@ -1,25 +1,25 @@
 #include "QByteArray.h"
 
 char* QByteArray::data() {
-    return nullptr;
+    return &m_data[0];
 }
 
 const char* QByteArray::data() const {
-    return nullptr;
+    return m_data.data();
 }
 
 const char* QByteArray::constData() const {
-    return nullptr;
+    return m_data.data();
 }
 
 int QByteArray::size() const {
-    return 0;
+    return static_cast<int>(m_data.size());
 }
 
 const char* QByteArray::begin() const {
-    return 0;
+    return m_data.data();
 }
 
 const char* QByteArray::end() const {
-    return 0;
+    return m_data.data() + m_data.size();
 }
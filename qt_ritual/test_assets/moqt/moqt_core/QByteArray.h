@@ -2,11 +2,12 @@
 #define QBYTEARRAY_H
 
 #include "moqt_core_exports.h"
+#include <string>
 
 class MOQT_CORE_EXPORT QByteArray {
 public:
-    QByteArray(int, char) {}
-    QByteArray(const char*, int) {}
+    QByteArray(int size, char ch) : m_data(size, ch) {}
+    QByteArray(const char* data, int size) : m_data(data, size) {}
     QByteArray() {}
     char* data();
     const char* data() const;
@@ -14,6 +15,9 @@ public:
     int size() const;
     const char* begin() const;
     const char* end() const;
+
+private:
+    std::string m_data;
 };
 
 #endif //QBYTEARRAY_H
@@ -1,17 +1,22 @@
 #include "QString.h"
+#include <cstring>
 
 QString QString::fromUtf8(const char *str, int size) {
-    return QString();
+    QString result;
+    result.m_utf8 = QByteArray(str, size < 0 ? static_cast<int>(std::strlen(str)) : size);
+    return result;
 }
 
 QString QString::fromUtf8(const QByteArray& str) {
-    return QString();
+    QString result;
+    result.m_utf8 = str;
+    return result;
 }
 
 QByteArray QString::toUtf8() const {
-    return QByteArray();
+    return m_utf8;
 }
 
 QByteArray QString::toLocal8Bit() const {
-    return QByteArray();
+    return m_utf8;
 }
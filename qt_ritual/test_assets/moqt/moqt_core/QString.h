@@ -20,6 +20,9 @@ public:
     static QString fromUtf8(const QByteArray& str);
     QByteArray toUtf8() const;
     QByteArray toLocal8Bit() const;
+
+private:
+    QByteArray m_utf8;
 };
 
 
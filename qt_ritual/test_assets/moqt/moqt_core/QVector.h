@@ -58,12 +58,43 @@ public:
         friend class QVector<T>;
     };
 
+    class MOQT_CORE_EXPORT ConstIterator {
+    public:
+        bool operator==(const ConstIterator& other) const {
+            return m_pos == other.m_pos;
+        }
+        bool operator!=(const ConstIterator& other) const {
+            return m_pos != other.m_pos;
+        }
+        const T& operator*() const {
+            return *m_pos;
+        }
+        void operator++() {
+            m_pos++;
+        }
+        void operator--() {
+            m_pos--;
+        }
+
+    private:
+        ConstIterator(const T* pos) : m_pos(pos) {}
+        const T* m_pos;
+
+        friend class QVector<T>;
+    };
+
     Iterator begin() {
         return Iterator(&m_data[0]);
     }
     Iterator end() {
         return Iterator(&m_data[m_size]);
     }
+    ConstIterator begin() const {
+        return ConstIterator(&m_data[0]);
+    }
+    ConstIterator end() const {
+        return ConstIterator(&m_data[m_size]);
+    }
 
 private:
     T m_data[32];
@@ -3,6 +3,7 @@
 #include "reserved_ids.h"
 #include "namespaces.h"
 #include "base_and_derived.h"
+#include "multiple_inheritance.h"
 #include "destructors.h"
 #include "QPoint.h"
 #include "QFlags.h"
@@ -0,0 +1 @@
+#include "multiple_inheritance.h"
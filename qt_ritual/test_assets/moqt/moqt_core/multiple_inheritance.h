@@ -0,0 +1,27 @@
+#include "moqt_core_exports.h"
+
+class MOQT_CORE_EXPORT MultipleBaseA {
+public:
+    MultipleBaseA() {}
+    int aValue() const { return m_aValue; }
+
+private:
+    int m_aValue = 1;
+};
+
+class MOQT_CORE_EXPORT MultipleBaseB {
+public:
+    MultipleBaseB() {}
+    int bValue() const { return m_bValue; }
+    void setBValue(int value) { m_bValue = value; }
+
+private:
+    int m_bValue = 2;
+};
+
+class MOQT_CORE_EXPORT MultipleDerived : public MultipleBaseA, public MultipleBaseB {
+public:
+    MultipleDerived() {}
+    const MultipleBaseA& baseA() const { return *this; }
+    const MultipleBaseB& baseB() const { return *this; }
+};
@@ -1,4 +1,7 @@
 #include "utils.h"
+#include <cstdlib>
+#include <utility>
+#include <vector>
 
 int moqt_abs(int x) {
     if (x >= 0) {
@@ -11,3 +14,56 @@ int moqt_abs(int x) {
 const char *moqt_core_version() {
     return "0.0.1";
 }
+
+bool moqt_parse_int(const char* text, int* result) {
+    char* end = nullptr;
+    long value = std::strtol(text, &end, 10);
+    if (end == text || *end != '\0') {
+        return false;
+    }
+    *result = static_cast<int>(value);
+    return true;
+}
+
+int moqt_div_mod(int x, int y, int* remainder) {
+    *remainder = x % y;
+    return x / y;
+}
+
+void moqt_min_max(int a, int b, int* min, int* max) {
+    *min = a < b ? a : b;
+    *max = a < b ? b : a;
+}
+
+typedef void (*moqt_handler)(int value, void* user_data);
+
+static std::vector<std::pair<moqt_handler, void*> >& moqt_handlers() {
+    static std::vector<std::pair<moqt_handler, void*> > handlers;
+    return handlers;
+}
+
+void moqt_add_handler(moqt_handler handler, void* user_data) {
+    moqt_handlers().push_back(std::make_pair(handler, user_data));
+}
+
+void moqt_remove_handler(moqt_handler handler, void* user_data) {
+    std::vector<std::pair<moqt_handler, void*> >& handlers = moqt_handlers();
+    for (size_t i = 0; i < handlers.size(); i++) {
+        if (handlers[i].first == handler && handlers[i].second == user_data) {
+            handlers.erase(handlers.begin() + i);
+            return;
+        }
+    }
+}
+
+int moqt_notify(int value) {
+    std::vector<std::pair<moqt_handler, void*> > handlers = moqt_handlers();
+    for (size_t i = 0; i < handlers.size(); i++) {
+        handlers[i].first(value, handlers[i].second);
+    }
+    return static_cast<int>(handlers.size());
+}
+
+int moqt_transform(int value, int (*transform)(void* user_data, int value), void* user_data) {
+    return transform(user_data, value);
+}
@@ -7,4 +7,13 @@ MOQT_CORE_EXPORT const char* moqt_core_version();
 
 MOQT_CORE_EXPORT int moqt_abs(int x);
 
+MOQT_CORE_EXPORT bool moqt_parse_int(const char* text, int* result);
+MOQT_CORE_EXPORT int moqt_div_mod(int x, int y, int* remainder);
+MOQT_CORE_EXPORT void moqt_min_max(int a, int b, int* min, int* max);
+
+MOQT_CORE_EXPORT void moqt_add_handler(void (*handler)(int value, void* user_data), void* user_data);
+MOQT_CORE_EXPORT void moqt_remove_handler(void (*handler)(int value, void* user_data), void* user_data);
+MOQT_CORE_EXPORT int moqt_notify(int value);
+MOQT_CORE_EXPORT int moqt_transform(int value, int (*transform)(void* user_data, int value), void* user_data);
+
 #endif // CTRT1_UTILS_H
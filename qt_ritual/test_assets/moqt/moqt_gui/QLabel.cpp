@@ -0,0 +1,17 @@
+#include "QLabel.h"
+
+QString QLabel::text() const {
+    return m_text;
+}
+
+void QLabel::setText(const QString& text) {
+    m_text = text;
+}
+
+void QLabel::setPrefixedText(const QString& prefix, QString text) {
+    QByteArray prefix_utf8 = prefix.toUtf8();
+    QByteArray text_utf8 = text.toUtf8();
+    std::string utf8(prefix_utf8.constData(), prefix_utf8.size());
+    utf8.append(text_utf8.constData(), text_utf8.size());
+    m_text = QString::fromUtf8(utf8.data(), static_cast<int>(utf8.size()));
+}
@@ -0,0 +1,19 @@
+#ifndef QLABEL_H
+#define QLABEL_H
+
+#include "moqt_gui_exports.h"
+#include "QString.h"
+
+class MOQT_GUI_EXPORT QLabel {
+public:
+    QLabel() {}
+
+    QString text() const;
+    void setText(const QString& text);
+    void setPrefixedText(const QString& prefix, QString text);
+
+private:
+    QString m_text;
+};
+
+#endif //QLABEL_H
@@ -1,2 +1,3 @@
 #include "QWindow.h"
 #include "QGuiApplication.h"
+#include "QLabel.h"
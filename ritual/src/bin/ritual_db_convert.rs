@@ -0,0 +1,28 @@
+//! Converts a ritual database file between the JSON and binary formats.
+
+use ritual::database::Database;
+use ritual_common::errors::{FancyUnwrap, Result};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+/// Converts a ritual database file to the format selected by the output file's
+/// extension (`.bin` for the binary format, JSON otherwise).
+struct Options {
+    #[structopt(parse(from_os_str))]
+    /// Database file in any format
+    input: PathBuf,
+    #[structopt(parse(from_os_str))]
+    /// Output file
+    output: PathBuf,
+}
+
+fn run(options: Options) -> Result<()> {
+    let database = Database::load(&options.input)?;
+    database.save(&options.output, None)?;
+    Ok(())
+}
+
+fn main() {
+    run(Options::from_args()).fancy_unwrap();
+}
@@ -0,0 +1,38 @@
+//! Compares databases of a crate produced by two generation runs
+//! and reports changes of the generated API.
+
+use ritual::database::Database;
+use ritual::database_diff;
+use ritual_common::errors::{FancyUnwrap, Result};
+use ritual_common::file_utils::save_json;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+/// Compares two ritual database files.
+struct Options {
+    #[structopt(parse(from_os_str))]
+    /// Database file of the previous run
+    old: PathBuf,
+    #[structopt(parse(from_os_str))]
+    /// Database file of the new run
+    new: PathBuf,
+    #[structopt(long = "json", parse(from_os_str))]
+    /// Write the report in JSON format to this file
+    json: Option<PathBuf>,
+}
+
+fn run(options: Options) -> Result<()> {
+    let old = Database::load(&options.old)?;
+    let new = Database::load(&options.new)?;
+    let diff = database_diff::diff(&old, &new);
+    print!("{}", diff.to_text());
+    if let Some(path) = &options.json {
+        save_json(path, &diff, None)?;
+    }
+    Ok(())
+}
+
+fn main() {
+    run(Options::from_args()).fancy_unwrap();
+}
@@ -0,0 +1,47 @@
+//! Merges databases of a crate produced on different platforms
+//! into a single database.
+
+use ritual::database::{Database, DatabaseClient, IndexedDatabase};
+use ritual_common::errors::{bail, FancyUnwrap, Result};
+use ritual_common::ReadOnly;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+/// Merges ritual database files produced on different platforms.
+struct Options {
+    #[structopt(short = "o", long = "output", parse(from_os_str))]
+    /// Path to the merged database file
+    output: PathBuf,
+    #[structopt(parse(from_os_str))]
+    /// Database files to merge
+    inputs: Vec<PathBuf>,
+}
+
+fn run(options: Options) -> Result<()> {
+    if options.inputs.len() < 2 {
+        bail!("at least two input databases are required");
+    }
+    let base = Database::load(&options.inputs[0])?;
+    let mut client = DatabaseClient::new(
+        IndexedDatabase::new(base, options.output.clone()),
+        ReadOnly::new(Vec::new()),
+    );
+    for path in &options.inputs[1..] {
+        let report = client.merge(&Database::load(path)?)?;
+        println!("merged {}", path.display());
+        for path in &report.conflicting_types {
+            println!("  conflicting type: {}", path.to_cpp_pseudo_code());
+        }
+        println!(
+            "  deleted FFI items: {}, different FFI items: {}",
+            report.deleted_ffi_items, report.different_ffi_items
+        );
+    }
+    client.data().save(&options.output, None)?;
+    Ok(())
+}
+
+fn main() {
+    run(Options::from_args()).fancy_unwrap();
+}
@@ -0,0 +1,105 @@
+//! Looks up items of a crate database by C++ name or Rust path
+//! and prints the corresponding C++, FFI and Rust items.
+
+use ritual::database::{Database, DatabaseClient, DatabaseItemKind, IndexedDatabase, ItemView};
+use ritual::rust_type::RustPath;
+use ritual_common::errors::{bail, FancyUnwrap, Result};
+use ritual_common::ReadOnly;
+use std::path::PathBuf;
+use std::str::FromStr;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+/// Queries a ritual database file.
+struct Options {
+    #[structopt(parse(from_os_str))]
+    /// Database file
+    database: PathBuf,
+    #[structopt(long = "cpp")]
+    /// Qualified C++ name, optionally with argument types (e.g. "QIODevice::read(char*, qint64)")
+    cpp: Vec<String>,
+    #[structopt(long = "rust")]
+    /// Full Rust path (e.g. "qt_core::QIODevice")
+    rust: Vec<String>,
+    #[structopt(long = "search")]
+    /// Case-insensitive substring of a C++ or Rust path
+    search: Vec<String>,
+    #[structopt(long = "module")]
+    /// Rust module path whose items should be listed with their C++ sources
+    module: Vec<String>,
+    #[structopt(long = "kind")]
+    /// List all items of a kind (cpp, ffi, rust)
+    kind: Option<String>,
+}
+
+fn print_view(view: &ItemView<'_>) -> Result<()> {
+    println!("{}: {}", view.id(), view.item().item.short_text());
+    if let Some(cpp_item) = view.original_cpp_item()? {
+        if cpp_item.id != *view.id() {
+            println!("    C++: {}", cpp_item.item.short_text());
+        }
+    }
+    if view.kind() != DatabaseItemKind::FfiItem {
+        if let Some(ffi_item) = view.ffi_item()? {
+            println!("    FFI: {}", ffi_item.item.short_text());
+        }
+    }
+    for ffi_item in view.ffi_items() {
+        println!("    FFI: {}", ffi_item.item.short_text());
+    }
+    for rust_item in view.rust_items() {
+        println!("    Rust: {}", rust_item.item.short_text());
+    }
+    Ok(())
+}
+
+fn run(options: Options) -> Result<()> {
+    let db = Database::load(&options.database)?;
+    let client = DatabaseClient::new(
+        IndexedDatabase::new(db, options.database.clone()),
+        ReadOnly::new(Vec::new()),
+    );
+
+    for name in &options.cpp {
+        let views = client.lookup_cpp_name(name)?;
+        if views.is_empty() {
+            println!("C++ item not found: {}", name);
+        }
+        for view in views {
+            print_view(&view)?;
+        }
+    }
+    for path in &options.rust {
+        match client.lookup_rust_path(path)? {
+            Some(view) => print_view(&view)?,
+            None => println!("Rust item not found: {}", path),
+        }
+    }
+    for pattern in &options.search {
+        for view in client.search(pattern) {
+            print_view(&view)?;
+        }
+    }
+    for path in &options.module {
+        let path = RustPath::from_str(path)?;
+        for item in client.rust_children(&path) {
+            print_view(&client.view(&item.id)?)?;
+        }
+    }
+    if let Some(kind) = &options.kind {
+        let kind = match kind.as_str() {
+            "cpp" => DatabaseItemKind::CppItem,
+            "ffi" => DatabaseItemKind::FfiItem,
+            "rust" => DatabaseItemKind::RustItem,
+            _ => bail!("unknown item kind: {}", kind),
+        };
+        for view in client.items_of_kind(kind) {
+            print_view(&view)?;
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    run(Options::from_args()).fancy_unwrap();
+}
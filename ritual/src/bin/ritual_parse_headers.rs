@@ -0,0 +1,61 @@
+//! Runs the C++ parser on a set of headers and prints the parsed items as JSON.
+
+use ritual::config::{Config, CrateProperties};
+use ritual::processor;
+use ritual::workspace::Workspace;
+use ritual_common::cpp_build_config::CppBuildPaths;
+use ritual_common::errors::{err_msg, FancyUnwrap, Result};
+use ritual_common::file_utils::{canonicalize, create_dir};
+use std::io;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+/// Parses C++ headers and prints the result as JSON
+/// (see `CppData` for the format).
+struct Options {
+    #[structopt(parse(from_os_str))]
+    /// Directory for the database and temporary files
+    workspace: PathBuf,
+    #[structopt(long = "crate-name", default_value = "cpp_data")]
+    /// Name of the crate in the workspace database
+    crate_name: String,
+    #[structopt(short = "I", long = "include-path", parse(from_os_str))]
+    /// Additional include path for the C++ compiler
+    include_paths: Vec<PathBuf>,
+    #[structopt(parse(from_os_str), required = true)]
+    /// Headers to parse
+    headers: Vec<PathBuf>,
+}
+
+fn run(options: Options) -> Result<()> {
+    if !options.workspace.exists() {
+        create_dir(&options.workspace)?;
+    }
+    let mut workspace = Workspace::new(canonicalize(&options.workspace)?)?;
+
+    let mut paths = CppBuildPaths::new();
+    for path in &options.include_paths {
+        paths.add_include_path(canonicalize(path)?);
+    }
+    let mut config = Config::new(CrateProperties::new(options.crate_name, "0.0.0"));
+    for header in &options.headers {
+        let header = canonicalize(header)?;
+        let parent = header
+            .parent()
+            .ok_or_else(|| err_msg("header path has no parent directory"))?;
+        paths.add_include_path(parent);
+        config.add_include_directive(&header);
+        config.add_target_include_path(header);
+    }
+    config.set_cpp_build_paths(paths);
+
+    let data = processor::parse_only(&mut workspace, &config)?;
+    data.to_json(io::stdout().lock())?;
+    println!();
+    Ok(())
+}
+
+fn main() {
+    run(Options::from_args()).fancy_unwrap();
+}
@@ -46,6 +46,14 @@ fn add_vector_from_pointers_functions(data: &mut ProcessorData<'_>) -> Result<()
                 visibility: CppVisibility::Public,
                 is_signal: false,
                 is_slot: false,
+                is_deleted: false,
+                is_defaulted: false,
+                is_final: false,
+                is_override: false,
+                is_explicit: false,
+                is_implicit: false,
+                inherited_from: None,
+                is_ambiguous: false,
             }),
             operator: None,
             return_type: CppType::Void,
@@ -69,6 +77,10 @@ fn add_vector_from_pointers_functions(data: &mut ProcessorData<'_>) -> Result<()
             allows_variadic_arguments: false,
             cast: None,
             declaration_code: None,
+            is_noexcept: false,
+            deprecated: None,
+            doc_comment: None,
+            is_extern_c: false,
         }),
     )?;
     data.add_cpp_item(
@@ -96,6 +108,14 @@ fn add_vector_from_pointers_functions(data: &mut ProcessorData<'_>) -> Result<()
                 visibility: CppVisibility::Public,
                 is_signal: false,
                 is_slot: false,
+                is_deleted: false,
+                is_defaulted: false,
+                is_final: false,
+                is_override: false,
+                is_explicit: false,
+                is_implicit: false,
+                inherited_from: None,
+                is_ambiguous: false,
             }),
             operator: None,
             return_type: CppType::Void,
@@ -114,6 +134,10 @@ fn add_vector_from_pointers_functions(data: &mut ProcessorData<'_>) -> Result<()
             allows_variadic_arguments: false,
             cast: None,
             declaration_code: None,
+            is_noexcept: false,
+            deprecated: None,
+            doc_comment: None,
+            is_extern_c: false,
         }),
     )?;
     Ok(())
@@ -193,11 +217,19 @@ fn add_vector_instantiations(data: &mut ProcessorData<'_>) -> Result<()> {
         data.add_cpp_item(
             None,
             CppItem::Type(CppTypeDeclaration {
-                kind: CppTypeDeclarationKind::Class,
+                kind: CppTypeDeclarationKind::Class {
+                    is_final: false,
+                    layout: None,
+                    using_declarations: Vec::new(),
+                    template_argument_defaults: Vec::new(),
+                    properties: Vec::new(),
+                },
                 path: CppPath::from_good_str("std").join(CppPathItem {
                     name: "vector".into(),
                     template_arguments: Some(vec![arg.clone(), allocator_type]),
                 }),
+                deprecated: None,
+                doc_comment: None,
             }),
         )?;
     }
@@ -1,3 +1,4 @@
+use log::info;
 use ritual::cli;
 use ritual::config::{Config, CrateProperties, GlobalConfig};
 use ritual::cpp_data::CppPath;
@@ -8,53 +9,27 @@ use ritual_common::cpp_build_config::{CppBuildConfigData, CppLibraryType};
 use ritual_common::errors::{bail, err_msg, FancyUnwrap, Result, ResultExt};
 use ritual_common::file_utils::repo_dir_path;
 use ritual_common::string_utils::CaseOperations;
-use ritual_common::{target, toml};
+use ritual_common::target;
 use std::env;
 use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 mod after_cpp_parser;
 
 pub const STD_HEADERS_PATH_ENV_VAR_NAME: &str = "RITUAL_STD_HEADERS";
 
 fn create_config(mut crate_properties: CrateProperties) -> Result<Config> {
-    let mut custom_fields = toml::value::Table::new();
-    let mut package_data = toml::value::Table::new();
-    package_data.insert(
-        "authors".to_string(),
-        toml::Value::Array(vec![toml::Value::String(
-            "Pavel Strakhov <ri@idzaaus.org>".to_string(),
-        )]),
-    );
-    package_data.insert(
-        "description".to_string(),
-        toml::Value::String("Bindings for C++ standard library".into()),
-    );
+    crate_properties.add_author("Pavel Strakhov <ri@idzaaus.org>");
+    crate_properties.set_description("Bindings for C++ standard library");
     // TODO: doc url
-    //package_data.insert("documentation".to_string(), toml::Value::String(doc_url));
-    package_data.insert(
-        "repository".to_string(),
-        toml::Value::String("https://github.com/rust-qt/ritual".to_string()),
-    );
-    package_data.insert(
-        "license".to_string(),
-        toml::Value::String("MIT OR Apache-2.0".to_string()),
-    );
-    package_data.insert(
-        "keywords".to_string(),
-        toml::Value::Array(vec![
-            toml::Value::String("ffi".to_string()),
-            toml::Value::String("ritual".to_string()),
-        ]),
-    );
-    package_data.insert(
-        "categories".to_string(),
-        toml::Value::Array(vec![toml::Value::String(
-            "external-ffi-bindings".to_string(),
-        )]),
-    );
-
-    custom_fields.insert("package".to_string(), toml::Value::Table(package_data));
-    crate_properties.set_custom_fields(custom_fields);
+    crate_properties.set_repository("https://github.com/rust-qt/ritual");
+    crate_properties.set_license("MIT OR Apache-2.0");
+    for &keyword in &["ffi", "ritual"] {
+        crate_properties.add_keyword(keyword)?;
+    }
+    crate_properties.add_category("external-ffi-bindings")?;
 
     let mut config = Config::new(crate_properties);
     config.set_cpp_lib_version("11");
@@ -158,10 +133,28 @@ fn create_config(mut crate_properties: CrateProperties) -> Result<Config> {
     Ok(config)
 }
 
+/// Installs a Ctrl-C handler that requests to interrupt the processing.
+/// The second Ctrl-C terminates the process immediately.
+fn set_ctrlc_handler(config: &mut GlobalConfig) {
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&interrupted);
+    let result = ctrlc::set_handler(move || {
+        if flag.swap(true, Ordering::SeqCst) {
+            process::exit(130);
+        }
+        info!("Interrupted. Press Ctrl-C again to exit immediately.");
+    });
+    match result {
+        Ok(()) => config.set_interruption_flag(interrupted),
+        Err(err) => eprintln!("failed to set Ctrl-C handler: {}", err),
+    }
+}
+
 fn main() {
     let mut config = GlobalConfig::new();
     config.set_all_crate_names(vec!["cpp_std".into()]);
     config.set_create_config_hook(create_config);
+    set_ctrlc_handler(&mut config);
 
     cli::run_from_args(config).fancy_unwrap();
 }
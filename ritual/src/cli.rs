@@ -3,17 +3,20 @@
 //! See [README](https://github.com/rust-qt/ritual)
 //! for more information.
 
-use crate::config::{CrateProperties, GlobalConfig};
+use crate::config::{CrateProperties, GlobalConfig, VersionSuggestionMode};
 use crate::database::ItemId;
+use crate::logging::{self, LogFormat, LogSettings};
 use crate::processor;
+use crate::progress::{JsonLinesProgressHandler, LogProgressHandler};
 use crate::workspace::Workspace;
-use flexi_logger::{Duplicate, LevelFilter, LogSpecification, Logger};
 use itertools::Itertools;
-use log::{error, info};
-use ritual_common::errors::{bail, err_msg, Result};
-use ritual_common::file_utils::{canonicalize, create_dir, load_json, path_to_str};
+use log::{error, info, LevelFilter};
+use ritual_common::errors::{bail, err_msg, format_err, Result};
+use ritual_common::file_utils::{canonicalize, create_dir, load_json};
 use ritual_common::target::current_target;
+use ritual_common::utils::MapIfOk;
 use std::path::PathBuf;
+use std::sync::Arc;
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
@@ -30,17 +33,57 @@ pub struct Options {
     /// Crates to process (e.g. `qt_core`)
     pub crates: Vec<String>,
     #[structopt(short = "o", long = "operations", required = true)]
-    /// Operations to perform
+    /// Operations to perform: names of processing steps, ranges of steps
+    /// (e.g. `[cpp_ffi_generator..]`), `main` (all steps), `changed`
+    /// (steps that were not completed or whose inputs changed)
+    /// or `resume` (continue an interrupted run)
     pub operations: Vec<String>,
     #[structopt(short = "v", long = "version")]
     /// Version of the output crates.
     pub output_crates_version: String,
+    #[structopt(long = "apply-suggested-version")]
+    /// Use the version suggested from changes of the API
+    /// if it's greater than the specified version
+    pub apply_suggested_version: bool,
+    #[structopt(long = "check", conflicts_with = "apply_suggested_version")]
+    /// Fail if the specified version is lower than the version
+    /// suggested from changes of the API
+    pub check: bool,
     #[structopt(long = "cluster")]
     /// Cluster configuration
     pub cluster: Option<PathBuf>,
     #[structopt(long = "trace")]
     /// ID of item to trace
     pub trace: Option<String>,
+    #[structopt(long = "full")]
+    /// Parse all headers, even if they didn't change since the previous run
+    pub full: bool,
+    #[structopt(long = "progress-json")]
+    /// Write progress events as JSON lines to the specified file (`-` for stdout)
+    pub progress_json: Option<String>,
+    #[structopt(long = "log-level")]
+    /// Maximal level of log messages of a category (e.g. `templates=error`).
+    /// Categories: parser, templates, inheritance, checker, rust_names, integrity, general
+    pub log_levels: Vec<String>,
+    #[structopt(long = "log-format", default_value = "text")]
+    /// Format of log messages: `text` or `json` (JSON lines)
+    pub log_format: LogFormat,
+    #[structopt(long = "fail-under", parse(try_from_str = "parse_percentage"))]
+    /// Fail if less than the specified percentage of parsed C++ items
+    /// is available in the Rust API (e.g. `80%`)
+    pub fail_under: Option<f64>,
+}
+
+/// Parses a percentage with an optional `%` sign, e.g. `80%`.
+fn parse_percentage(text: &str) -> Result<f64> {
+    let number = text.trim().trim_end_matches('%');
+    let value = number
+        .parse::<f64>()
+        .map_err(|_| format_err!("invalid percentage: {:?}", text))?;
+    if !(0.0..=100.0).contains(&value) {
+        bail!("percentage must be between 0 and 100: {:?}", text);
+    }
+    Ok(value)
 }
 
 pub fn run_from_args(config: GlobalConfig) -> Result<()> {
@@ -55,15 +98,15 @@ pub fn run(options: Options, mut config: GlobalConfig) -> Result<()> {
 
     let mut workspace = Workspace::new(workspace_path.clone())?;
 
-    Logger::with(LogSpecification::default(LevelFilter::Trace).build())
-        .log_to_file()
-        .directory(path_to_str(&workspace.log_path())?)
-        .suppress_timestamp()
-        .append()
-        .print_message()
-        .duplicate_to_stderr(Duplicate::Info)
-        .start()
-        .unwrap_or_else(|e| panic!("Logger initialization failed: {}", e));
+    logging::init(LogSettings {
+        stderr_level: LevelFilter::Info,
+        file: Some(workspace.log_path().join("ritual.log")),
+        format: options.log_format,
+        category_levels: options
+            .log_levels
+            .iter()
+            .map_if_ok(|text| logging::parse_category_level(text))?,
+    })?;
 
     info!("");
     info!("Workspace: {}", workspace_path.display());
@@ -106,6 +149,14 @@ pub fn run(options: Options, mut config: GlobalConfig) -> Result<()> {
         None
     };
 
+    let progress_json = match &options.progress_json {
+        Some(path) if path == "-" => Some(Arc::new(JsonLinesProgressHandler::to_stdout())),
+        Some(path) => Some(Arc::new(JsonLinesProgressHandler::to_file(path)?)),
+        None => None,
+    };
+
+    let interruption_flag = config.interruption_flag().cloned();
+    let mut configs = Vec::new();
     for crate_name in &final_crates {
         let create_config = config
             .create_config_hook()
@@ -124,10 +175,42 @@ pub fn run(options: Options, mut config: GlobalConfig) -> Result<()> {
             config.set_write_dependencies_local_paths(local_paths);
         }
 
+        if options.full {
+            config.set_incremental_cpp_parser(false);
+        }
+
+        if options.fail_under.is_some() {
+            config.set_min_coverage(options.fail_under);
+        }
+
+        if options.check {
+            config.set_version_suggestion_mode(VersionSuggestionMode::Check);
+        } else if options.apply_suggested_version {
+            config.set_version_suggestion_mode(VersionSuggestionMode::Apply);
+        }
+
+        if let Some(progress_json) = &progress_json {
+            config.set_progress_handler((LogProgressHandler, Arc::clone(progress_json)));
+        }
+
+        if let Some(flag) = &interruption_flag {
+            config.set_interruption_flag(Arc::clone(flag));
+        }
+
+        configs.push(config);
+    }
+
+    if !configs.is_empty() {
         was_any_action = true;
-        processor::process(&mut workspace, &config, &operations, trace_item_id.as_ref())?;
+        processor::process_all(
+            &mut workspace,
+            &configs,
+            &operations,
+            trace_item_id.as_ref(),
+        )?;
     }
 
+    logging::report_warning_summary();
     if was_any_action {
         info!("ritual finished");
     } else {
@@ -1,5 +1,5 @@
 use crate::config::ClusterConfig;
-use crate::cpp_checker::{LocalSnippetTask, Snippet, CHUNK_SIZE};
+use crate::cpp_checker::{LocalSnippetTask, Snippet};
 use amqp::{protocol::basic::BasicProperties, Basic, Channel, Session, Table};
 use log::{info, warn};
 use ritual_common::cpp_lib_builder::CppLibBuilderOutput;
@@ -26,7 +26,11 @@ pub struct GroupItem {
     pub id: u64,
 }
 
-pub fn run_checks(config: &ClusterConfig, tasks: &mut [LocalSnippetTask]) -> Result<()> {
+pub fn run_checks(
+    config: &ClusterConfig,
+    tasks: &mut [LocalSnippetTask],
+    batch_size: usize,
+) -> Result<()> {
     if config.protocol_version != PROTOCOL_VERSION {
         bail!("unsupported cluster protocol version");
     }
@@ -78,7 +82,7 @@ pub fn run_checks(config: &ClusterConfig, tasks: &mut [LocalSnippetTask]) -> Res
         )?;
 
         for (key, items) in group {
-            for chunk in items.chunks(CHUNK_SIZE) {
+            for chunk in items.chunks(batch_size) {
                 let task = Task {
                     launch_id: launch_id.clone(),
                     group_key: key.clone(),
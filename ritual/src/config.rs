@@ -1,17 +1,38 @@
 //! Interface for configuring and running the generator.
 
-use crate::cpp_checker::PreliminaryTest;
-use crate::cpp_data::{CppItem, CppPath};
+use crate::cpp_checker::{PreliminaryTest, CHUNK_SIZE};
+use crate::cpp_data::{CppItem, CppPath, CppTypeDeclaration};
+use crate::cpp_function::CppFunction;
+use crate::cpp_macros::CppMacroInstance;
 use crate::cpp_parser::CppParserOutput;
+use crate::cpp_type::{is_flags_type, CppBuiltInNumericType, CppType};
+use crate::database::DatabaseCache;
+use crate::logging::LogCategory;
 use crate::processor::{ProcessingSteps, ProcessorData};
+use crate::progress::{LogProgressHandler, ProgressHandler};
 use crate::rust_info::{NameType, RustItem, RustPathScope};
 use crate::rust_type::RustPath;
-use ritual_common::cpp_build_config::{CppBuildConfig, CppBuildPaths};
-use ritual_common::errors::{bail, Result};
+use crate::type_allocation_places::override_matches;
+use crate::user_hooks::{ItemEditor, UserHook};
+use crate::versions::parse_version;
+use itertools::Itertools;
+use log::LevelFilter;
+use regex::Regex;
+use ritual_common::cpp_build_config::{CppBuildConfig, CppBuildPaths, CppLibraryDiscovery};
+use ritual_common::errors::{bail, err_msg, format_err, Error, Result, ResultExt};
+use ritual_common::file_utils::crate_name;
+use ritual_common::string_utils::DEFAULT_ACRONYMS;
 use ritual_common::target::Target;
 use ritual_common::toml;
+use ritual_common::utils::recursive_merge_toml;
 use serde_derive::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::fmt;
+use std::panic::Location;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum CrateDependencyKind {
@@ -21,9 +42,18 @@ pub enum CrateDependencyKind {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum CrateDependencySource {
-    CratesIo { version: String },
-    Local { path: PathBuf },
+    CratesIo {
+        version: String,
+    },
+    Local {
+        path: PathBuf,
+    },
     CurrentWorkspace,
+    /// Database file of a ritual-generated crate. The crate itself
+    /// is taken from crates.io.
+    Database {
+        path: PathBuf,
+    },
 }
 
 /// Information about a crate dependency.
@@ -49,6 +79,25 @@ impl CrateDependency {
     }
 }
 
+/// Cargo feature of the generated crate (see `Config::add_feature`).
+#[derive(Debug, Clone)]
+pub struct CrateFeature {
+    name: String,
+    headers: Vec<String>,
+}
+
+impl CrateFeature {
+    /// Name of the feature (as in `Cargo.toml`)
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// File names of the headers whose items are enabled by the feature.
+    pub fn headers(&self) -> &[String] {
+        &self.headers
+    }
+}
+
 /// Information about the crate being generated.
 /// Most of information in this object will be used in
 /// the output `Cargo.toml`.
@@ -68,6 +117,22 @@ pub struct CrateProperties {
     remove_default_dependencies: bool,
     /// Don't add default build dependencies to `Cargo.toml`
     remove_default_build_dependencies: bool,
+    /// Authors of the crate
+    authors: Vec<String>,
+    /// Short description of the crate
+    description: Option<String>,
+    /// License of the crate (SPDX expression)
+    license: Option<String>,
+    /// URL of the source repository
+    repository: Option<String>,
+    /// Keywords for crates.io
+    keywords: Vec<String>,
+    /// Category slugs for crates.io
+    categories: Vec<String>,
+    /// Value of `links` key of `Cargo.toml`
+    links: Option<String>,
+    /// Minimal supported Rust version
+    rust_version: Option<String>,
 }
 
 impl CrateProperties {
@@ -81,6 +146,14 @@ impl CrateProperties {
             build_dependencies: Vec::new(),
             remove_default_dependencies: false,
             remove_default_build_dependencies: false,
+            authors: Vec::new(),
+            description: None,
+            license: None,
+            repository: None,
+            keywords: Vec::new(),
+            categories: Vec::new(),
+            links: None,
+            rust_version: None,
         }
     }
 
@@ -139,6 +212,112 @@ impl CrateProperties {
         self.custom_fields = value;
     }
 
+    /// Parses `text` as TOML and merges it into custom fields of output `Cargo.toml`
+    /// (see `CrateProperties::set_custom_fields`). Arrays are appended, so `text`
+    /// can add items to generated features, e.g.
+    /// `[features]\ndefault = ["extra"]\nextra = []`.
+    ///
+    /// Returns an error if `text` is not valid TOML or contains invalid
+    /// features or dependencies.
+    pub fn add_custom_toml(&mut self, text: &str) -> Result<()> {
+        let value = text
+            .parse::<toml::Value>()
+            .with_context(|_| "failed to parse custom Cargo.toml fields")?;
+        let table = match value {
+            toml::Value::Table(table) => table,
+            _ => bail!("custom Cargo.toml fields must be a table"),
+        };
+        check_custom_fields(&table)?;
+        let custom_fields = std::mem::take(&mut self.custom_fields);
+        match recursive_merge_toml(toml::Value::Table(custom_fields), toml::Value::Table(table)) {
+            toml::Value::Table(table) => self.custom_fields = table,
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    /// Adds an author of the crate.
+    pub fn add_author(&mut self, author: impl Into<String>) {
+        self.authors.push(author.into());
+    }
+
+    /// Sets description of the crate.
+    pub fn set_description(&mut self, description: impl Into<String>) {
+        self.description = Some(description.into());
+    }
+
+    /// Sets license of the crate (e.g. `MIT OR Apache-2.0`).
+    pub fn set_license(&mut self, license: impl Into<String>) {
+        self.license = Some(license.into());
+    }
+
+    /// Sets URL of the source repository of the crate.
+    pub fn set_repository(&mut self, repository: impl Into<String>) {
+        self.repository = Some(repository.into());
+    }
+
+    /// Adds a crates.io keyword. Returns an error if the keyword
+    /// doesn't satisfy crates.io requirements.
+    pub fn add_keyword(&mut self, keyword: impl Into<String>) -> Result<()> {
+        let keyword = keyword.into();
+        if self.keywords.len() >= 5 {
+            bail!("crates.io allows at most 5 keywords");
+        }
+        let is_valid = keyword.len() <= 20
+            && keyword.starts_with(|c: char| c.is_ascii_alphanumeric())
+            && keyword
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '+');
+        if !is_valid {
+            bail!("invalid crates.io keyword: {:?}", keyword);
+        }
+        self.keywords.push(keyword);
+        Ok(())
+    }
+
+    /// Adds a crates.io category slug (e.g. `external-ffi-bindings`).
+    /// Returns an error if the value is not a valid slug.
+    pub fn add_category(&mut self, category: impl Into<String>) -> Result<()> {
+        let category = category.into();
+        if self.categories.len() >= 5 {
+            bail!("crates.io allows at most 5 categories");
+        }
+        let is_valid = !category.is_empty()
+            && category.split("::").all(|part| {
+                !part.is_empty()
+                    && part
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            });
+        if !is_valid {
+            bail!("invalid crates.io category: {:?}", category);
+        }
+        self.categories.push(category);
+        Ok(())
+    }
+
+    /// Sets value of `links` key of output `Cargo.toml`. By default, name of
+    /// the C++ wrapper library (`<crate name>_c`) is used. Note that build scripts of
+    /// dependent ritual crates expect the default value.
+    pub fn set_links(&mut self, links: impl Into<String>) {
+        self.links = Some(links.into());
+    }
+
+    /// Sets minimal supported Rust version (e.g. `1.52`).
+    pub fn set_rust_version(&mut self, version: impl Into<String>) -> Result<()> {
+        let version = version.into();
+        let parts = version.split('.').collect::<Vec<_>>();
+        let is_valid = (2..=3).contains(&parts.len())
+            && parts
+                .iter()
+                .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+        if !is_valid {
+            bail!("invalid Rust version: {:?}", version);
+        }
+        self.rust_version = Some(version);
+        Ok(())
+    }
+
     /// Name of the crate
     pub fn name(&self) -> &str {
         &self.name
@@ -168,6 +347,88 @@ impl CrateProperties {
     pub fn custom_fields(&self) -> &toml::value::Table {
         &self.custom_fields
     }
+
+    /// Authors of the crate
+    pub fn authors(&self) -> &[String] {
+        &self.authors
+    }
+    /// Description of the crate
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+    /// License of the crate
+    pub fn license(&self) -> Option<&str> {
+        self.license.as_deref()
+    }
+    /// URL of the source repository
+    pub fn repository(&self) -> Option<&str> {
+        self.repository.as_deref()
+    }
+    /// Keywords for crates.io
+    pub fn keywords(&self) -> &[String] {
+        &self.keywords
+    }
+    /// Category slugs for crates.io
+    pub fn categories(&self) -> &[String] {
+        &self.categories
+    }
+    /// Value of `links` key, if it was changed
+    pub fn links(&self) -> Option<&str> {
+        self.links.as_deref()
+    }
+    /// Minimal supported Rust version
+    pub fn rust_version(&self) -> Option<&str> {
+        self.rust_version.as_deref()
+    }
+}
+
+/// Checks features and dependencies in custom fields of `Cargo.toml`.
+fn check_custom_fields(table: &toml::value::Table) -> Result<()> {
+    for (key, value) in table {
+        match key.as_str() {
+            "package" => {
+                if !value.is_table() {
+                    bail!("`package` must be a table");
+                }
+            }
+            "features" => {
+                let features = value
+                    .as_table()
+                    .ok_or_else(|| err_msg("`features` must be a table"))?;
+                for (name, items) in features {
+                    let is_valid = items
+                        .as_array()
+                        .map_or(false, |items| items.iter().all(|item| item.is_str()));
+                    if !is_valid {
+                        bail!("feature `{}` must be an array of strings", name);
+                    }
+                }
+            }
+            "dependencies" | "build-dependencies" | "dev-dependencies" => {
+                let dependencies = value
+                    .as_table()
+                    .ok_or_else(|| format_err!("`{}` must be a table", key))?;
+                for (name, dependency) in dependencies {
+                    let version = match dependency {
+                        toml::Value::String(version) => Some(version),
+                        toml::Value::Table(table) => match table.get("version") {
+                            Some(toml::Value::String(version)) => Some(version),
+                            Some(_) => bail!("version of dependency `{}` must be a string", name),
+                            None => None,
+                        },
+                        _ => bail!("dependency `{}` must be a string or a table", name),
+                    };
+                    if let Some(version) = version {
+                        semver::VersionReq::parse(version).with_context(|_| {
+                            format!("invalid version requirement of dependency `{}`", name)
+                        })?;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
 }
 
 pub type RustPathScopeHook = dyn Fn(&CppPath) -> Result<Option<RustPathScope>> + 'static;
@@ -177,6 +438,9 @@ pub type RustItemHook = dyn Fn(&mut RustItem, &ProcessorData<'_>) -> Result<()>
 pub type AfterCppParserHook =
     dyn Fn(&mut ProcessorData<'_>, &CppParserOutput) -> Result<()> + 'static;
 pub type CppItemFilterHook = dyn Fn(&CppItem) -> Result<bool> + 'static;
+pub type MethodFilter = dyn Fn(&CppFunction) -> bool + 'static;
+pub type MacroExtractor =
+    dyn Fn(&CppMacroInstance, &mut CppTypeDeclaration) -> Result<()> + 'static;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkerLibraryConfig {
@@ -205,23 +469,100 @@ pub struct Config {
     // see setters documentation for information about these properties
     crate_properties: CrateProperties,
     cpp_lib_version: Option<String>,
+    cpp_lib_versions: Vec<CppLibVersion>,
     crate_template_path: Option<PathBuf>,
+    crate_overlay_path: Option<PathBuf>,
     include_directives: Vec<PathBuf>,
     target_include_paths: Vec<PathBuf>,
+    include_directive_path_filters: Vec<HeaderPathFilter>,
     cpp_build_config: CppBuildConfig,
     cpp_build_paths: CppBuildPaths,
+    cpp_library_discovery: Option<CppLibraryDiscovery>,
     cpp_parser_arguments: Vec<String>,
+    cpp_parser_only_arguments: Vec<String>,
+    target_triple: Option<String>,
+    sysroot: Option<PathBuf>,
     processing_steps: ProcessingSteps,
     movable_types_hook: Option<Box<dyn Fn(&CppPath) -> Result<MovableTypesHookOutput>>>,
     cpp_parser_path_hook: Option<Box<dyn Fn(&CppPath) -> Result<bool>>>,
+    cpp_parser_blocked_name_patterns: Vec<Regex>,
+    cpp_parser_allowed_name_patterns: Vec<Regex>,
     rust_path_scope_hook: Option<Box<RustPathScopeHook>>,
     rust_path_hook: Option<Box<RustPathHook>>,
     rust_item_hook: Option<Box<RustItemHook>>,
     after_cpp_parser_hooks: Vec<Box<AfterCppParserHook>>,
     cpp_item_filter_hook: Option<Box<CppItemFilterHook>>,
+    macro_extractors: Vec<Box<MacroExtractor>>,
+    cpp_data_filters: Vec<UserHook>,
+    ffi_filters: Vec<UserHook>,
+    rust_info_hooks: Vec<UserHook>,
     cluster_config: Option<ClusterConfig>,
     cpp_checker_tests: Vec<PreliminaryTest>,
     write_dependencies_local_paths: bool,
+    debug_stream_type: CppPath,
+    subclassing_classes: Vec<CppPath>,
+    implicit_methods: Vec<(CppPath, ImplicitMethod, bool)>,
+    method_exclusions: Vec<MethodExclusion>,
+    class_member_exclusions: Vec<CppPath>,
+    method_filters: Vec<(Box<MethodFilter>, &'static Location<'static>)>,
+    template_instantiations: Vec<(CppPath, Vec<TemplateArgument>)>,
+    method_instantiations: Vec<(CppPath, Vec<TemplateArgument>)>,
+    non_null_functions: Vec<CppPath>,
+    return_ownership: Vec<(CppPath, ReturnOwnership)>,
+    borrow_checking: bool,
+    total_order_impls: bool,
+    signal_macro_sections: bool,
+    unbound_return_lifetime_functions: Vec<CppPath>,
+    type_allocation_places: Vec<(CppPath, TypeAllocationPlace)>,
+    type_mappings: Vec<(CppPath, RustTypeMapping)>,
+    opaque_types: Vec<CppPath>,
+    flags_templates: Vec<CppPath>,
+    flags_type_mappings: Vec<(CppPath, RustPath)>,
+    string_types: Vec<(CppPath, StringConversion)>,
+    out_params_functions: Vec<(CppPath, OutParams)>,
+    out_params_excluded_functions: Vec<CppPath>,
+    detect_out_params: bool,
+    callback_user_data_names: Vec<String>,
+    callback_unregister_functions: Vec<(CppPath, CppPath)>,
+    overloading_strategy: OverloadingStrategy,
+    inherited_methods_strategy: InheritedMethodsStrategy,
+    default_arguments_strategy: DefaultArgumentsStrategy,
+    omitted_arguments_variants_limit: Option<usize>,
+    reset_rust_names: bool,
+    rename_rules: Vec<RenameRule>,
+    module_strategy: ModuleStrategy,
+    group_members_by_class_header: bool,
+    nested_types_strategy: NestedTypesStrategy,
+    flattened_namespaces: Vec<CppPath>,
+    crate_root_namespace: Option<CppPath>,
+    acronyms: Vec<String>,
+    prelude_collision_suffix: String,
+    closed_enums: Vec<CppPath>,
+    thread_safety_rules: Vec<(Regex, ThreadSafety)>,
+    default_thread_safety: ThreadSafety,
+    max_stack_allocated_size: usize,
+    cpp_glue_file_count: Option<usize>,
+    write_source_annotations: bool,
+    write_smoke_tests: bool,
+    write_coverage_report: bool,
+    min_coverage: Option<f64>,
+    write_layout_assertions: bool,
+    smoke_test_blocked_name_patterns: Vec<Regex>,
+    features: Vec<CrateFeature>,
+    doc_url_template: Option<String>,
+    function_doc_url_template: Option<String>,
+    enum_doc_url_template: Option<String>,
+    cpp_checker_batch_size: usize,
+    cpp_checker_job_count: Option<usize>,
+    cpp_checker_timeout: Option<Duration>,
+    checkpoint_interval: usize,
+    progress_handler: Arc<dyn ProgressHandler>,
+    interruption_flag: Option<Arc<AtomicBool>>,
+    log_levels: Vec<(LogCategory, LevelFilter)>,
+    incremental_cpp_parser: bool,
+    type_layout_check: Option<(TypeLayoutCheckMode, TypeLayoutMismatchAction)>,
+    target_cfg_expressions: Vec<(Target, String)>,
+    version_suggestion_mode: VersionSuggestionMode,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -231,6 +572,474 @@ pub enum MovableTypesHookOutput {
     Unknown,
 }
 
+/// Glob pattern of header paths added by `Config::add_include_directive_path_filter`.
+#[derive(Debug, Clone)]
+pub struct HeaderPathFilter {
+    pattern: String,
+    regex: Regex,
+}
+
+impl HeaderPathFilter {
+    /// Creates a filter from a glob pattern. `*` and `?` match any characters
+    /// except `/`, and `**` matches any sequence of characters.
+    pub fn new(pattern: &str) -> Result<Self> {
+        if pattern.is_empty() {
+            bail!("empty header path filter");
+        }
+        let mut regex = if pattern.starts_with('/') || !pattern.contains('/') {
+            "^".to_string()
+        } else {
+            "(?:^|/)".to_string()
+        };
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    regex.push_str(".*");
+                }
+                '*' => regex.push_str("[^/]*"),
+                '?' => regex.push_str("[^/]"),
+                _ => regex.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        regex.push('$');
+        Ok(HeaderPathFilter {
+            pattern: pattern.to_string(),
+            regex: Regex::new(&regex)
+                .with_context(|_| format!("invalid header path filter: {:?}", pattern))?,
+        })
+    }
+
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Checks if `path` matches the pattern. Patterns without `/` are matched
+    /// against the file name, other patterns are matched against the end
+    /// of the path (or the whole path if the pattern starts with `/`).
+    pub fn matches(&self, path: &Path) -> bool {
+        let text = if self.pattern.contains('/') {
+            path.to_string_lossy().replace('\\', "/")
+        } else {
+            match path.file_name() {
+                Some(name) => name.to_string_lossy().into_owned(),
+                None => return false,
+            }
+        };
+        self.regex.is_match(&text)
+    }
+}
+
+/// Special member function that C++ declares implicitly
+/// if the class doesn't declare it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImplicitMethod {
+    DefaultConstructor,
+    CopyConstructor,
+    CopyAssignment,
+    Destructor,
+}
+
+/// Method excluded by `Config::blacklist_method`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodExclusion {
+    /// Class containing the method
+    pub class_path: CppPath,
+    /// Name of the method
+    pub name: String,
+    /// Argument types and qualifiers of the excluded overload
+    /// (e.g. `(int, bool) const`), or `None` to exclude all overloads
+    pub signature: Option<String>,
+}
+
+impl MethodExclusion {
+    /// Checks if `function` is excluded by this rule.
+    /// Whitespace in the signature is not significant.
+    pub fn matches(&self, function: &CppFunction) -> bool {
+        if function.path.last().name != self.name {
+            return false;
+        }
+        match function.class_path() {
+            Ok(path) if override_matches(&self.class_path, &path) => {}
+            _ => return false,
+        }
+        let signature = match &self.signature {
+            Some(signature) => signature,
+            None => return true,
+        };
+        let full_signature = function.signature_text();
+        let path_text = function.path.to_cpp_pseudo_code();
+        let function_signature = full_signature
+            .strip_prefix(path_text.as_str())
+            .unwrap_or(&full_signature);
+        let without_spaces = |s: &str| s.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+        without_spaces(function_signature) == without_spaces(signature)
+    }
+}
+
+impl fmt::Display for MethodExclusion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}::{}{}",
+            self.class_path.to_cpp_pseudo_code(),
+            self.name,
+            self.signature.as_deref().unwrap_or("")
+        )
+    }
+}
+
+/// Argument of a template instantiation requested by
+/// `Config::add_template_instantiation`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateArgument {
+    /// Fully specified type
+    Type(CppType),
+    /// Name of a type resolved against the parsed types, e.g. `"MyType"`,
+    /// `"ns::MyType*"`, `"const MyType*"` or a built-in type like `"int"`
+    Name(String),
+}
+
+impl From<CppType> for TemplateArgument {
+    fn from(value: CppType) -> Self {
+        TemplateArgument::Type(value)
+    }
+}
+
+impl From<&str> for TemplateArgument {
+    fn from(value: &str) -> Self {
+        TemplateArgument::Name(value.to_string())
+    }
+}
+
+impl fmt::Display for TemplateArgument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateArgument::Type(t) => write!(f, "{}", t.to_cpp_pseudo_code()),
+            TemplateArgument::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Parsed name of a type specified in `TemplateArgument::Name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateArgumentName {
+    BuiltInNumeric(CppBuiltInNumericType),
+    /// Class or enum type that should be looked up in the parsed types
+    Path(CppPath),
+    Pointer {
+        is_const: bool,
+        target: Box<TemplateArgumentName>,
+    },
+}
+
+impl FromStr for TemplateArgumentName {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        let name = name.trim();
+        if let Some(target) = name.strip_suffix('*') {
+            let target = target.trim_end();
+            let (is_const, target) = match target.strip_prefix("const ") {
+                Some(target) if !target.ends_with('*') => (true, target),
+                _ => (false, target),
+            };
+            return Ok(TemplateArgumentName::Pointer {
+                is_const,
+                target: Box::new(target.parse()?),
+            });
+        }
+        let built_in = CppBuiltInNumericType::all()
+            .iter()
+            .find(|t| t.to_cpp_code() == name);
+        if let Some(built_in) = built_in {
+            return Ok(TemplateArgumentName::BuiltInNumeric(built_in.clone()));
+        }
+        if name.is_empty() || name.contains(|c: char| c == '&' || c == ' ') {
+            bail!("unsupported type name: {:?}", name);
+        }
+        Ok(TemplateArgumentName::Path(CppPath::from_str(name)?))
+    }
+}
+
+/// Checks that `argument` can be used as a template argument.
+fn check_template_argument(argument: &TemplateArgument) -> Result<()> {
+    match argument {
+        TemplateArgument::Type(t) => {
+            if *t == CppType::Void || t.is_or_contains_template_parameter() {
+                bail!("unsupported argument: {}", argument);
+            }
+        }
+        TemplateArgument::Name(name) => {
+            name.parse::<TemplateArgumentName>()?;
+        }
+    }
+    Ok(())
+}
+
+/// Allocation place of objects of a C++ class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TypeAllocationPlace {
+    /// Objects are allocated by C++ code and owned by a `CppBox`.
+    Heap,
+    /// Objects are stored in Rust values with the same size and alignment
+    /// as the C++ class.
+    Stack,
+}
+
+/// Ownership of an object returned by pointer from a C++ function.
+/// C++ signatures don't express it, so it's configured with
+/// `Config::set_return_ownership`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReturnOwnership {
+    /// The object is owned by someone else, and the caller must not delete it.
+    /// The returned pointer is represented as `Option<Ref<T>>` or `QPtr<T>`.
+    Borrowed,
+    /// The caller takes ownership of the object and is responsible for deleting it.
+    /// The returned pointer is represented as `Option<CppBox<T>>` or `QBox<T>`,
+    /// so the object is deleted exactly once when the box is dropped.
+    Owned,
+}
+
+/// Way of passing values of a C++ class mapped to a hand-written Rust type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassBy {
+    /// The Rust type has the same layout as the C++ class, and its values
+    /// are moved between C++ and Rust. The class is stack-allocated.
+    ByValue,
+    /// The Rust type is opaque, and it's only used behind pointers
+    /// and references. Functions returning the class by value are skipped.
+    ByPointer,
+}
+
+/// Hand-written Rust type used instead of a C++ class.
+/// See `Config::map_type`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RustTypeMapping {
+    /// Full path of the Rust type (e.g. `mymath::Vec3`)
+    pub rust_path: RustPath,
+    pub pass_by: PassBy,
+    /// Expected size of the C++ class in bytes. If set, the processing fails
+    /// if the class has a different size.
+    pub size: Option<usize>,
+}
+
+impl RustTypeMapping {
+    pub fn new(rust_path: RustPath, pass_by: PassBy) -> Self {
+        RustTypeMapping {
+            rust_path,
+            pass_by,
+            size: None,
+        }
+    }
+}
+
+/// Describes how a C++ string class is converted from and to UTF-8 text.
+/// See `Config::add_string_type`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringConversion {
+    /// Method returning UTF-8 bytes of the string in a container with
+    /// `data()` and `size()` methods (e.g. `toUtf8` for `QString`).
+    /// If `None`, the string itself is assumed to contain UTF-8 bytes
+    /// (e.g. `std::string`).
+    pub to_utf8_method: Option<String>,
+    /// Function or constructor creating the string from a `const char*`
+    /// and a length (e.g. `QString::fromUtf8` or `std::string`).
+    pub from_utf8_ctor: String,
+}
+
+/// Describes how the Rust wrapper of a function returns values of
+/// its out-parameters. See `Config::set_out_params`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutParams {
+    /// The wrapper returns a tuple of the original return value and
+    /// the out values, or only the out values if the function returns `void`.
+    Tuple,
+    /// The function returns `bool` indicating success. The wrapper returns
+    /// `Some` of the out values on success and `None` otherwise.
+    SuccessFlag,
+}
+
+/// Way of exposing overloaded C++ functions in the Rust API.
+/// See `Config::set_overloading_strategy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverloadingStrategy {
+    /// Each overload is a separate Rust function with a name derived
+    /// from its arguments (e.g. `load_int` and `load_q_string`).
+    Suffixes,
+    /// A single generic Rust function accepts arguments of any overload
+    /// as a tuple (e.g. `load(())`, `load(1)` and `load((1, 2))`).
+    /// The argument types implement a generated `ArgsFor...` trait.
+    /// Overloads are still available as separate functions with suffixes.
+    Traits,
+}
+
+/// Way of adding methods of public base classes to derived classes.
+/// See `Config::set_inherited_methods_strategy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InheritedMethodsStrategy {
+    /// Methods of base classes are only available through
+    /// conversions to base classes.
+    Disabled,
+    /// A method of a base class is added to the derived class unless
+    /// the derived class declares a method with the same signature.
+    /// Other overloads with the same name are still added.
+    Signatures,
+    /// Emulates C++ name hiding: a method of a base class is not added
+    /// if the derived class declares any method with the same name.
+    NameHiding,
+}
+
+/// Way of handling C++ arguments with default values.
+/// See `Config::set_default_arguments_strategy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefaultArgumentsStrategy {
+    /// A variant of the function is generated for each number of omitted
+    /// trailing arguments (e.g. `f(a)` and `f(a, b)` for `f(a, b = 1, c = 2)`).
+    Variants,
+    /// No variants are generated. Arguments with default values keep
+    /// the `has_default_value` mark, so that the default values can be supplied
+    /// by the Rust API once they're available. Until then, all arguments
+    /// must be passed to the wrappers.
+    Keep,
+}
+
+/// Layout of the modules of the generated crate.
+/// See `Config::set_module_strategy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleStrategy {
+    /// Each C++ namespace becomes a Rust module
+    /// (e.g. `crate::mylib::detail::Helper` for `mylib::detail::Helper`).
+    Namespaces,
+    /// Each C++ header becomes a Rust module containing all top level
+    /// items declared in it (e.g. `crate::helper::Helper` for `helper.h`).
+    /// Namespaces are ignored.
+    Headers,
+}
+
+/// Placement of Rust types generated for C++ nested types.
+/// See `Config::set_nested_types_strategy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NestedTypesStrategy {
+    /// `Outer::Inner` becomes `outer::Inner` next to the `Outer` type.
+    Modules,
+    /// `Outer::Inner` becomes `OuterInner` in the module of the `Outer` type.
+    Prefixed,
+}
+
+/// Thread safety of the Rust wrapper of a C++ class.
+/// See `Config::set_thread_safety`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThreadSafety {
+    /// The type is neither `Send` nor `Sync`.
+    None,
+    /// The type is `Send`, but not `Sync`.
+    Send,
+    /// The type is `Send` and `Sync`.
+    SendSync,
+}
+
+/// Rule that changes Rust names generated for C++ items.
+/// See `Config::rename`, `Config::add_rename_pattern` and `Config::rename_module`.
+#[derive(Debug, Clone)]
+pub enum RenameRule {
+    /// Sets the Rust name of the C++ type, enum value or function
+    /// (all overloads of the function are renamed).
+    Exact {
+        cpp_path: CppPath,
+        rust_name: String,
+    },
+    /// Replaces matches of `regex` in the C++ names of items
+    /// before their conversion to the Rust case.
+    Pattern { regex: Regex, replacement: String },
+    /// Sets the name of the Rust module generated for the C++ namespace.
+    Module {
+        cpp_path: CppPath,
+        rust_name: String,
+    },
+}
+
+impl fmt::Display for RenameRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenameRule::Exact {
+                cpp_path,
+                rust_name,
+            } => write!(
+                f,
+                "rename {} to {}",
+                cpp_path.to_cpp_pseudo_code(),
+                rust_name
+            ),
+            RenameRule::Pattern { regex, replacement } => {
+                write!(f, "replace {:?} with {:?}", regex.as_str(), replacement)
+            }
+            RenameRule::Module {
+                cpp_path,
+                rust_name,
+            } => write!(
+                f,
+                "rename module {} to {}",
+                cpp_path.to_cpp_pseudo_code(),
+                rust_name
+            ),
+        }
+    }
+}
+
+/// Way of comparing layouts of stack-allocated classes recorded by the parser
+/// with the layouts produced by the C++ compiler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeLayoutCheckMode {
+    /// Build and run a program that compares `sizeof` and `alignof` values.
+    Run,
+    /// Compare the values with `static_assert` without running any programs.
+    /// Suitable for cross-compilation.
+    StaticAssert,
+}
+
+/// Action taken if the layout of a stack-allocated class doesn't match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeLayoutMismatchAction {
+    /// Abort the processing.
+    Fail,
+    /// Allocate the class on the heap instead.
+    UseHeap,
+}
+
+/// Way of using the crate version suggested from changes of the API.
+/// See `Config::set_version_suggestion_mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionSuggestionMode {
+    /// Only write the report. The configured version is used.
+    Report,
+    /// Use the suggested version if it's greater than the configured version.
+    Apply,
+    /// Fail if the configured version is lower than the suggested version.
+    Check,
+}
+
+/// A version of the C++ library the crate is generated for.
+/// See `Config::add_cpp_lib_version`.
+#[derive(Debug, Clone)]
+pub struct CppLibVersion {
+    version: String,
+    build_paths: CppBuildPaths,
+}
+
+impl CppLibVersion {
+    /// Returns the version of the library.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Returns paths to the installation of this version of the library.
+    pub fn build_paths(&self) -> &CppBuildPaths {
+        &self.build_paths
+    }
+}
+
 impl Config {
     /// Creates a `Config`.
     /// `crate_properties` are used in Cargo.toml of the generated crate.
@@ -238,23 +1047,100 @@ impl Config {
         Config {
             crate_properties,
             crate_template_path: Default::default(),
+            crate_overlay_path: Default::default(),
             cpp_build_paths: Default::default(),
+            cpp_library_discovery: Default::default(),
             target_include_paths: Default::default(),
+            include_directive_path_filters: Vec::new(),
             include_directives: Default::default(),
             cpp_parser_arguments: Default::default(),
+            cpp_parser_only_arguments: Default::default(),
+            target_triple: Default::default(),
+            sysroot: Default::default(),
             cpp_build_config: Default::default(),
             movable_types_hook: Default::default(),
             processing_steps: Default::default(),
             cpp_lib_version: Default::default(),
+            cpp_lib_versions: Vec::new(),
             cpp_parser_path_hook: Default::default(),
+            cpp_parser_blocked_name_patterns: Default::default(),
+            cpp_parser_allowed_name_patterns: Default::default(),
             rust_path_scope_hook: Default::default(),
             rust_path_hook: Default::default(),
             rust_item_hook: Default::default(),
             after_cpp_parser_hooks: Default::default(),
             cpp_item_filter_hook: Default::default(),
+            macro_extractors: Vec::new(),
+            cpp_data_filters: Vec::new(),
+            ffi_filters: Vec::new(),
+            rust_info_hooks: Vec::new(),
             cluster_config: None,
             cpp_checker_tests: Default::default(),
             write_dependencies_local_paths: true,
+            debug_stream_type: CppPath::from_good_str("std::basic_ostream"),
+            subclassing_classes: Default::default(),
+            implicit_methods: Vec::new(),
+            method_exclusions: Vec::new(),
+            class_member_exclusions: Vec::new(),
+            method_filters: Vec::new(),
+            template_instantiations: Vec::new(),
+            method_instantiations: Vec::new(),
+            non_null_functions: Default::default(),
+            return_ownership: Vec::new(),
+            borrow_checking: false,
+            total_order_impls: false,
+            signal_macro_sections: false,
+            unbound_return_lifetime_functions: Vec::new(),
+            type_allocation_places: Default::default(),
+            type_mappings: Vec::new(),
+            flags_templates: Vec::new(),
+            flags_type_mappings: Vec::new(),
+            opaque_types: Vec::new(),
+            string_types: Vec::new(),
+            out_params_functions: Vec::new(),
+            out_params_excluded_functions: Vec::new(),
+            detect_out_params: false,
+            callback_user_data_names: Vec::new(),
+            callback_unregister_functions: Vec::new(),
+            overloading_strategy: OverloadingStrategy::Suffixes,
+            inherited_methods_strategy: InheritedMethodsStrategy::Disabled,
+            default_arguments_strategy: DefaultArgumentsStrategy::Variants,
+            omitted_arguments_variants_limit: None,
+            reset_rust_names: false,
+            rename_rules: Vec::new(),
+            module_strategy: ModuleStrategy::Namespaces,
+            group_members_by_class_header: true,
+            nested_types_strategy: NestedTypesStrategy::Modules,
+            flattened_namespaces: Vec::new(),
+            crate_root_namespace: None,
+            acronyms: DEFAULT_ACRONYMS.iter().map(|s| s.to_string()).collect(),
+            prelude_collision_suffix: "_".to_string(),
+            closed_enums: Vec::new(),
+            thread_safety_rules: Vec::new(),
+            default_thread_safety: ThreadSafety::None,
+            max_stack_allocated_size: 64,
+            cpp_glue_file_count: None,
+            write_source_annotations: false,
+            write_smoke_tests: false,
+            write_coverage_report: false,
+            min_coverage: None,
+            write_layout_assertions: true,
+            smoke_test_blocked_name_patterns: Vec::new(),
+            features: Vec::new(),
+            doc_url_template: None,
+            function_doc_url_template: None,
+            enum_doc_url_template: None,
+            cpp_checker_batch_size: CHUNK_SIZE,
+            cpp_checker_job_count: None,
+            cpp_checker_timeout: Some(Duration::from_secs(300)),
+            checkpoint_interval: 2000,
+            progress_handler: Arc::new(LogProgressHandler),
+            interruption_flag: None,
+            log_levels: Vec::new(),
+            incremental_cpp_parser: true,
+            type_layout_check: None,
+            target_cfg_expressions: Vec::new(),
+            version_suggestion_mode: VersionSuggestionMode::Report,
         }
     }
 
@@ -281,8 +1167,25 @@ impl Config {
         self.crate_template_path = Some(path.into());
     }
 
+    /// Sets the directory containing hand-written files that are copied
+    /// to the generated crate's directory after generation. Unlike files of the
+    /// crate template, overlay files can't replace generated files. If any overlay file
+    /// has the same path as a generated file, the generation fails.
+    ///
+    /// `src/extra/` directory and `README.md` file of the generated crate are never
+    /// deleted on regeneration, so they may also be edited in place.
+    /// If `src/extra/mod.rs` is present in the overlay or in the generated crate,
+    /// the generated `src/lib.rs` declares `pub mod extra;` to include it.
+    pub fn set_crate_overlay_path<P: Into<PathBuf>>(&mut self, path: P) {
+        self.crate_overlay_path = Some(path.into());
+    }
+
     /// Adds a command line argument for clang C++ parser.
+    /// Arguments are appended after the built-in parser flags, so they can
+    /// override them (e.g. `-std=c++17`).
     ///
+    /// The same arguments are passed to the C++ compiler by `cpp_checker`,
+    /// so parse-time and check-time configurations don't diverge.
     /// Note that this value is not used when building the wrapper library.
     /// Use `Config::cpp_build_config_mut` or a similar method to
     /// configure building the wrapper library.
@@ -302,6 +1205,30 @@ impl Config {
         }
     }
 
+    /// Adds a command line argument for clang C++ parser that is not passed
+    /// to the C++ compiler by `cpp_checker`. Use it for clang-specific flags
+    /// that other compilers don't accept.
+    pub fn add_cpp_parser_only_argument<P: Into<String>>(&mut self, arg: P) {
+        self.cpp_parser_only_arguments.push(arg.into());
+    }
+
+    /// Sets target triple (e.g. `armv7-linux-androideabi`) used for cross-compilation.
+    /// It's passed to clang as `-target` argument and to the C++ compiler
+    /// used by `cpp_checker`. By default, the host target is used.
+    ///
+    /// The triple is recorded in the database, and processing is refused
+    /// if the database contains C++ data parsed for another target.
+    pub fn set_target_triple<S: Into<String>>(&mut self, triple: S) {
+        self.target_triple = Some(triple.into());
+    }
+
+    /// Sets sysroot directory used for cross-compilation.
+    /// It's passed to clang as `--sysroot` argument and to the C++ compiler
+    /// used by `cpp_checker`.
+    pub fn set_sysroot<P: Into<PathBuf>>(&mut self, path: P) {
+        self.sysroot = Some(path.into());
+    }
+
     /// Sets `CppBuildPaths` value for this config. These paths
     /// are used for testing C++ methods while processing the library,
     /// but they are not used when building the generated crate.
@@ -322,6 +1249,25 @@ impl Config {
         self.target_include_paths.push(path.into());
     }
 
+    /// Adds a glob pattern of header files whose types and methods
+    /// will be parsed and used, in addition to the headers selected by
+    /// `add_target_include_path` (e.g. `"q*.h"` or `"QtCore/*.h"`).
+    /// This is useful when the target library shares an include directory
+    /// with other libraries. Patterns without `/` are matched against
+    /// the file name, other patterns are matched against the end of
+    /// the canonical path of the header. `*` and `?` don't match `/`,
+    /// and `**` matches any sequence of characters.
+    pub fn add_include_directive_path_filter(&mut self, pattern: &str) -> Result<()> {
+        self.include_directive_path_filters
+            .push(HeaderPathFilter::new(pattern)?);
+        Ok(())
+    }
+
+    /// Returns patterns added by `add_include_directive_path_filter`.
+    pub fn include_directive_path_filters(&self) -> &[HeaderPathFilter] {
+        &self.include_directive_path_filters
+    }
+
     /// Adds an include directive. Each directive will be added
     /// as `#include <path>` to the input file for the C++ parser.
     /// File name only paths or relative paths should be used in this method.
@@ -341,6 +1287,19 @@ impl Config {
         &mut self.cpp_build_config
     }
 
+    /// Sets information required to locate the target C++ library
+    /// in the build script of the generated crate. If set, the build script
+    /// fails if the library is not found, and the generated crate gets
+    /// the `static_link` feature for static linking of the library.
+    pub fn set_cpp_library_discovery(&mut self, discovery: CppLibraryDiscovery) {
+        self.cpp_library_discovery = Some(discovery);
+    }
+
+    /// Returns value set by `Config::set_cpp_library_discovery`.
+    pub fn cpp_library_discovery(&self) -> Option<&CppLibraryDiscovery> {
+        self.cpp_library_discovery.as_ref()
+    }
+
     pub fn set_cpp_lib_version<S: Into<String>>(&mut self, version: S) {
         self.cpp_lib_version = Some(version.into());
     }
@@ -349,6 +1308,42 @@ impl Config {
         self.cpp_lib_version.as_deref()
     }
 
+    /// Adds a version of the C++ library the crate is generated for.
+    /// If multiple versions are added, the C++ parser and `cpp_checker`
+    /// are run once for each version, using `build_paths` instead of
+    /// `Config::cpp_build_paths`. Items that are not available in all versions
+    /// are only compiled if the build script of the generated crate
+    /// detects a version that contains them.
+    ///
+    /// `version` must be a valid semver version (e.g. `5.12.0`).
+    /// Target include paths of all versions must be added with
+    /// `Config::add_target_include_path`. This can't be combined
+    /// with `Config::set_cpp_lib_version`.
+    pub fn add_cpp_lib_version(
+        &mut self,
+        version: impl Into<String>,
+        build_paths: CppBuildPaths,
+    ) -> Result<()> {
+        let version = version.into();
+        parse_version(&version)?;
+        if self.cpp_lib_versions.iter().any(|v| v.version == version) {
+            bail!("C++ library version {} is already added", version);
+        }
+        self.cpp_lib_versions.push(CppLibVersion {
+            version,
+            build_paths,
+        });
+        self.cpp_lib_versions
+            .sort_by_cached_key(|v| parse_version(&v.version).expect("checked above"));
+        Ok(())
+    }
+
+    /// Returns versions added with `Config::add_cpp_lib_version`,
+    /// from oldest to newest.
+    pub fn cpp_lib_versions(&self) -> &[CppLibVersion] {
+        &self.cpp_lib_versions
+    }
+
     pub fn processing_steps(&self) -> &ProcessingSteps {
         &self.processing_steps
     }
@@ -357,6 +1352,40 @@ impl Config {
         &mut self.processing_steps
     }
 
+    /// Adds a dependency on another ritual-generated crate.
+    /// `path` is either the directory of the crate or its database file.
+    /// C++ types of the dependency are known to the parser and are mapped to
+    /// Rust types of the dependency crate, and no wrappers are generated
+    /// for the dependency's items.
+    pub fn add_dependency(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let (name, source) = if path.is_dir() {
+            let name = crate_name(path)?;
+            let source = CrateDependencySource::Local {
+                path: path.to_path_buf(),
+            };
+            (name, source)
+        } else {
+            // the database is loaded once and kept in the cache
+            // until the processing starts
+            let mut cache = DatabaseCache::global().lock().unwrap();
+            let database = cache
+                .get(path, &path.display().to_string(), true, false)
+                .with_context(|_| format!("failed to load dependency: {}", path.display()))?;
+            let name = database.database().crate_name().to_string();
+            cache.put(database);
+            let source = CrateDependencySource::Database {
+                path: path.to_path_buf(),
+            };
+            (name, source)
+        };
+        if name == self.crate_properties.name() {
+            bail!("crate {} can't depend on itself", name);
+        }
+        self.crate_properties
+            .add_dependency(name, CrateDependencyKind::Ritual, source)
+    }
+
     /// Returns crate properties passed to `Config::new`.
     pub fn crate_properties(&self) -> &CrateProperties {
         &self.crate_properties
@@ -372,12 +1401,22 @@ impl Config {
         self.crate_template_path.as_ref()
     }
 
+    /// Returns value set by `Config::set_crate_overlay_path`.
+    pub fn crate_overlay_path(&self) -> Option<&PathBuf> {
+        self.crate_overlay_path.as_ref()
+    }
+
     /// Returns names added with `Config::add_cpp_parser_argument`
     /// and similar methods.
     pub fn cpp_parser_arguments(&self) -> &[String] {
         &self.cpp_parser_arguments
     }
 
+    /// Returns arguments added with `Config::add_cpp_parser_only_argument`.
+    pub fn cpp_parser_only_arguments(&self) -> &[String] {
+        &self.cpp_parser_only_arguments
+    }
+
     /// Returns values added by `Config::set_cpp_build_paths`.
     pub fn cpp_build_paths(&self) -> &CppBuildPaths {
         &self.cpp_build_paths
@@ -393,6 +1432,16 @@ impl Config {
         &self.include_directives
     }
 
+    /// Returns value set by `Config::set_target_triple`.
+    pub fn target_triple(&self) -> Option<&str> {
+        self.target_triple.as_deref()
+    }
+
+    /// Returns value set by `Config::set_sysroot`.
+    pub fn sysroot(&self) -> Option<&PathBuf> {
+        self.sysroot.as_ref()
+    }
+
     /// Returns current `CppBuildConfig` value.
     pub fn cpp_build_config(&self) -> &CppBuildConfig {
         &self.cpp_build_config
@@ -437,6 +1486,40 @@ impl Config {
         self.cpp_parser_path_hook.as_deref()
     }
 
+    /// Adds a regular expression that blocks C++ entities by name.
+    /// The pattern is matched against the full name without template arguments
+    /// (e.g. `ns::QVector::append`) and must match the whole name,
+    /// so `Internal::.*` skips everything inside the `Internal` namespace
+    /// and `.*Private` skips all entities with names ending with `Private`.
+    /// As with `set_cpp_parser_path_hook`, all entities inside a blocked
+    /// entity are also skipped.
+    pub fn add_cpp_parser_blocked_name_pattern(&mut self, pattern: &str) -> Result<()> {
+        self.cpp_parser_blocked_name_patterns
+            .push(compile_name_pattern(pattern)?);
+        Ok(())
+    }
+
+    pub fn cpp_parser_blocked_name_patterns(&self) -> &[Regex] {
+        &self.cpp_parser_blocked_name_patterns
+    }
+
+    /// Adds a regular expression that selects C++ entities by name.
+    /// Patterns are matched in the same way as in `add_cpp_parser_blocked_name_pattern`.
+    /// If at least one allowed pattern is added, the C++ parser only keeps
+    /// functions and types matching one of the patterns (methods of a matching class
+    /// are kept as well) and the types these entities depend on
+    /// (argument and return types, base classes and field types).
+    /// All other entities are discarded after parsing.
+    pub fn add_cpp_parser_allowed_name_pattern(&mut self, pattern: &str) -> Result<()> {
+        self.cpp_parser_allowed_name_patterns
+            .push(compile_name_pattern(pattern)?);
+        Ok(())
+    }
+
+    pub fn cpp_parser_allowed_name_patterns(&self) -> &[Regex] {
+        &self.cpp_parser_allowed_name_patterns
+    }
+
     pub fn set_rust_path_scope_hook(
         &mut self,
         hook: impl Fn(&CppPath) -> Result<Option<RustPathScope>> + 'static,
@@ -499,6 +1582,76 @@ impl Config {
         self.cpp_item_filter_hook.as_deref()
     }
 
+    /// Adds a hook that receives macro expansions found at class scope
+    /// (e.g. `Q_PROPERTY(...)`) and can record information from them
+    /// in the class declaration. Such macros usually expand to nothing,
+    /// so the information they carry is otherwise lost. Each hook is called
+    /// for every macro expansion in every parsed class.
+    ///
+    /// `cpp_macros::extract_qt_property` is a built-in extractor
+    /// for `Q_PROPERTY`.
+    ///
+    /// Adding an extractor enables the detailed preprocessing record
+    /// in the C++ parser, which makes parsing slower.
+    pub fn add_macro_extractor(
+        &mut self,
+        extractor: impl Fn(&CppMacroInstance, &mut CppTypeDeclaration) -> Result<()> + 'static,
+    ) {
+        self.macro_extractors.push(Box::new(extractor));
+    }
+
+    pub fn macro_extractors(&self) -> &[Box<MacroExtractor>] {
+        &self.macro_extractors
+    }
+
+    /// Adds a hook that can change or delete C++ items right after they are parsed.
+    /// Hooks run in the order of registration. Changed items are marked
+    /// as modified by the user in the database.
+    ///
+    /// Hooks run on each parser run, including incremental ones,
+    /// so they should skip items they have already changed.
+    #[track_caller]
+    pub fn add_cpp_data_filter(
+        &mut self,
+        hook: impl Fn(&mut ItemEditor<'_>) -> Result<()> + 'static,
+    ) {
+        self.cpp_data_filters
+            .push(UserHook::new(hook, Location::caller()));
+    }
+
+    pub fn cpp_data_filters(&self) -> &[UserHook] {
+        &self.cpp_data_filters
+    }
+
+    /// Adds a hook that can change or delete FFI items after they are generated,
+    /// before they are checked and the C++ wrapper code is generated.
+    /// See also `add_cpp_data_filter`.
+    #[track_caller]
+    pub fn add_ffi_filter(&mut self, hook: impl Fn(&mut ItemEditor<'_>) -> Result<()> + 'static) {
+        self.ffi_filters
+            .push(UserHook::new(hook, Location::caller()));
+    }
+
+    pub fn ffi_filters(&self) -> &[UserHook] {
+        &self.ffi_filters
+    }
+
+    /// Adds a hook that can change or delete Rust items after they are generated,
+    /// before the Rust code is generated.
+    /// See also `add_cpp_data_filter`.
+    #[track_caller]
+    pub fn add_rust_info_hook(
+        &mut self,
+        hook: impl Fn(&mut ItemEditor<'_>) -> Result<()> + 'static,
+    ) {
+        self.rust_info_hooks
+            .push(UserHook::new(hook, Location::caller()));
+    }
+
+    pub fn rust_info_hooks(&self) -> &[UserHook] {
+        &self.rust_info_hooks
+    }
+
     pub fn set_cluster_config(&mut self, cluster_config: ClusterConfig) {
         self.cluster_config = Some(cluster_config);
     }
@@ -515,22 +1668,1312 @@ impl Config {
         &self.cpp_checker_tests
     }
 
-    pub fn set_write_dependencies_local_paths(&mut self, value: bool) {
-        self.write_dependencies_local_paths = value;
+    /// Sets the maximal number of items `cpp_checker` compiles in one program.
+    /// If a program fails to build or run, its items are split in halves and
+    /// checked again, so results don't depend on the batch size.
+    /// Larger batches need fewer compiler invocations when most of the items
+    /// are valid. The default value is 64.
+    pub fn set_cpp_checker_batch_size(&mut self, size: usize) -> Result<()> {
+        if size == 0 {
+            bail!("cpp_checker batch size must be positive");
+        }
+        self.cpp_checker_batch_size = size;
+        Ok(())
     }
 
-    pub fn write_dependencies_local_paths(&self) -> bool {
-        self.write_dependencies_local_paths
+    /// Returns the maximal number of items `cpp_checker` compiles in one program.
+    pub fn cpp_checker_batch_size(&self) -> usize {
+        self.cpp_checker_batch_size
     }
-}
 
-#[derive(Default)]
-pub struct GlobalConfig {
-    create_config_hook: Option<Box<dyn FnMut(CrateProperties) -> Result<Config>>>,
-    all_crate_names: Vec<String>,
-}
+    /// Sets the number of batches `cpp_checker` builds concurrently.
+    /// Each concurrent job uses a separate build directory.
+    /// By default, the number of logical CPUs is used.
+    pub fn set_cpp_checker_job_count(&mut self, count: usize) -> Result<()> {
+        if count == 0 {
+            bail!("cpp_checker job count must be positive");
+        }
+        self.cpp_checker_job_count = Some(count);
+        Ok(())
+    }
 
-impl GlobalConfig {
+    /// Returns the number of concurrent `cpp_checker` jobs set by `set_cpp_checker_job_count`.
+    pub fn cpp_checker_job_count(&self) -> Option<usize> {
+        self.cpp_checker_job_count
+    }
+
+    /// Sets the maximal duration of each build performed by `cpp_checker`.
+    /// Builds exceeding it are killed, and the checked items are recorded
+    /// as failed. `None` disables the limit. The default value is 5 minutes.
+    pub fn set_cpp_checker_timeout(&mut self, timeout: Option<Duration>) {
+        self.cpp_checker_timeout = timeout;
+    }
+
+    /// Returns the build timeout of `cpp_checker`.
+    pub fn cpp_checker_timeout(&self) -> Option<Duration> {
+        self.cpp_checker_timeout
+    }
+
+    /// Sets the number of items processed by long steps (e.g. `cpp_checker`)
+    /// between checkpoints. On each checkpoint, the database is saved, so that
+    /// an interrupted run can be resumed without losing the processed items.
+    /// The default value is 2000.
+    pub fn set_checkpoint_interval(&mut self, interval: usize) -> Result<()> {
+        if interval == 0 {
+            bail!("checkpoint interval must be positive");
+        }
+        self.checkpoint_interval = interval;
+        Ok(())
+    }
+
+    /// Returns the number of items processed by long steps between checkpoints.
+    pub fn checkpoint_interval(&self) -> usize {
+        self.checkpoint_interval
+    }
+
+    /// Sets the handler that receives progress events of the processing
+    /// (started and finished steps, processed items and warnings).
+    /// The handler only observes the processing and can't affect its results.
+    /// By default, `LogProgressHandler` is used. Use a tuple of handlers
+    /// to send events to multiple handlers.
+    pub fn set_progress_handler(&mut self, handler: impl ProgressHandler + 'static) {
+        self.progress_handler = Arc::new(handler);
+    }
+
+    /// Returns the handler of progress events.
+    pub fn progress_handler(&self) -> &Arc<dyn ProgressHandler> {
+        &self.progress_handler
+    }
+
+    /// Sets the flag that requests to interrupt the processing. If the flag is set,
+    /// `cpp_checker` cancels the remaining batches and saves results of completed ones,
+    /// and the processing stops after the current step. The interrupted run can be
+    /// continued with the "resume" operation.
+    ///
+    /// `ritual` doesn't handle signals itself. The executable is expected to set
+    /// the flag, e.g. from its Ctrl-C handler.
+    pub fn set_interruption_flag(&mut self, flag: Arc<AtomicBool>) {
+        self.interruption_flag = Some(flag);
+    }
+
+    /// Returns the flag that requests to interrupt the processing.
+    pub fn interruption_flag(&self) -> Option<&Arc<AtomicBool>> {
+        self.interruption_flag.as_ref()
+    }
+
+    /// Sets the maximal level of log messages of `category` while this crate
+    /// is processed (e.g. `LevelFilter::Error` for `LogCategory::Templates`
+    /// hides warnings about template instantiation). Hidden warnings are still
+    /// counted in the summary printed at the end of the run.
+    /// This only affects the logger installed by `logging::init`.
+    pub fn set_log_level(&mut self, category: LogCategory, level: LevelFilter) {
+        self.log_levels.retain(|(c, _)| *c != category);
+        self.log_levels.push((category, level));
+    }
+
+    /// Returns levels set by `set_log_level`.
+    pub fn log_levels(&self) -> &[(LogCategory, LevelFilter)] {
+        &self.log_levels
+    }
+
+    /// Enables or disables incremental parsing. If enabled, the C++ parser
+    /// only processes declarations from the header files that were added or changed
+    /// since the previous run and reuses the previously parsed items of other headers.
+    /// Items of removed or changed headers are deleted from the database.
+    ///
+    /// Incremental parsing is enabled by default. It can be disabled
+    /// with the `--full` command line option.
+    pub fn set_incremental_cpp_parser(&mut self, value: bool) {
+        self.incremental_cpp_parser = value;
+    }
+
+    /// Returns true if incremental parsing is enabled.
+    pub fn incremental_cpp_parser(&self) -> bool {
+        self.incremental_cpp_parser
+    }
+
+    pub fn set_write_dependencies_local_paths(&mut self, value: bool) {
+        self.write_dependencies_local_paths = value;
+    }
+
+    pub fn write_dependencies_local_paths(&self) -> bool {
+        self.write_dependencies_local_paths
+    }
+
+    /// Sets the stream class used to produce `Debug` implementations.
+    /// A free `operator<<` accepting this stream and a class of the current
+    /// crate produces a `Debug` implementation for that class.
+    /// Template arguments of the stream class are ignored.
+    /// Default is `std::basic_ostream`. Qt crates use `QDebug`.
+    pub fn set_debug_stream_type(&mut self, path: CppPath) {
+        self.debug_stream_type = path;
+    }
+
+    /// Returns the stream class used to produce `Debug` implementations.
+    pub fn debug_stream_type(&self) -> &CppPath {
+        &self.debug_stream_type
+    }
+
+    /// Enables subclassing of class `path`. A C++ subclass is generated
+    /// for this class. Its virtual functions can be overridden
+    /// by Rust closures using `set_<function>_override` methods, and
+    /// `base_<function>` methods call the original implementation.
+    pub fn enable_subclassing(&mut self, path: CppPath) {
+        self.subclassing_classes.push(path);
+    }
+
+    /// Returns classes for which subclassing is enabled.
+    pub fn subclassing_classes(&self) -> &[CppPath] {
+        &self.subclassing_classes
+    }
+
+    /// Overrides detection of the implicitly declared `method` of class `path`.
+    ///
+    /// By default, implicit special members are added according to the C++ rules,
+    /// e.g. the default constructor is only added if the class doesn't declare
+    /// any constructors. The detection is conservative and may miss some cases.
+    /// If `is_available` is false, the method is never added. If it's true,
+    /// the method is added regardless of the detection. In both cases,
+    /// the added methods are only exposed if the C++ checker can compile them.
+    pub fn set_implicit_method_available(
+        &mut self,
+        path: CppPath,
+        method: ImplicitMethod,
+        is_available: bool,
+    ) {
+        self.implicit_methods
+            .retain(|(p, m, _)| p != &path || *m != method);
+        self.implicit_methods.push((path, method, is_available));
+    }
+
+    /// Returns the override set by `set_implicit_method_available`
+    /// for `method` of class `path`.
+    pub fn implicit_method_override(&self, path: &CppPath, method: ImplicitMethod) -> Option<bool> {
+        self.implicit_methods
+            .iter()
+            .find(|(p, m, _)| p == path && *m == method)
+            .map(|(_, _, is_available)| *is_available)
+    }
+
+    /// Excludes method `name` of class `class_path` (e.g. `"ns::MyClass"`) from the API.
+    /// If `signature` is specified (e.g. `"(int, bool) const"`), only the overload
+    /// with these argument types and qualifiers is excluded. Otherwise, all overloads
+    /// are excluded. If `class_path` doesn't have template arguments, the rule
+    /// also applies to all instantiations of the class template.
+    ///
+    /// Excluded methods are never added to the database, so they are not inherited
+    /// and their variants with omitted arguments are not generated. Each exclusion
+    /// is recorded as a rejection of the `"user config"` step. A warning is
+    /// reported if the rule doesn't match any method.
+    pub fn blacklist_method(
+        &mut self,
+        class_path: &str,
+        name: &str,
+        signature: Option<&str>,
+    ) -> Result<()> {
+        let exclusion = MethodExclusion {
+            class_path: CppPath::from_str(class_path)?,
+            name: name.to_string(),
+            signature: signature.map(|s| s.trim().to_string()),
+        };
+        if let Some(signature) = &exclusion.signature {
+            if !signature.starts_with('(') {
+                bail!(
+                    "method signature must start with the argument list: {:?}",
+                    signature
+                );
+            }
+        }
+        if !self.method_exclusions.contains(&exclusion) {
+            self.method_exclusions.push(exclusion);
+        }
+        Ok(())
+    }
+
+    /// Returns methods excluded by `blacklist_method`.
+    pub fn method_exclusions(&self) -> &[MethodExclusion] {
+        &self.method_exclusions
+    }
+
+    /// Excludes all methods and fields of class `class_path` from the API,
+    /// including methods inherited from its bases. The class itself is kept.
+    /// Exclusions are recorded in the same way as in `blacklist_method`.
+    pub fn blacklist_class_members(&mut self, class_path: &str) -> Result<()> {
+        let path = CppPath::from_str(class_path)?;
+        if !self.class_member_exclusions.contains(&path) {
+            self.class_member_exclusions.push(path);
+        }
+        Ok(())
+    }
+
+    /// Returns classes excluded by `blacklist_class_members`.
+    pub fn class_member_exclusions(&self) -> &[CppPath] {
+        &self.class_member_exclusions
+    }
+
+    /// Adds a filter that is called for every C++ function and method
+    /// added to the database. If the filter returns false, the function is
+    /// excluded in the same way as in `blacklist_method`.
+    #[track_caller]
+    pub fn add_method_filter(&mut self, filter: impl Fn(&CppFunction) -> bool + 'static) {
+        self.method_filters
+            .push((Box::new(filter), Location::caller()));
+    }
+
+    /// Returns the reason `item` is excluded by `blacklist_method`,
+    /// `blacklist_class_members` or `add_method_filter`,
+    /// or `None` if the item is not excluded.
+    pub fn cpp_item_exclusion_reason(&self, item: &CppItem) -> Option<String> {
+        let class_path = match item {
+            CppItem::Function(function) => function.class_path().ok(),
+            CppItem::ClassField(field) => field.path.parent().ok(),
+            _ => return None,
+        };
+        if let Some(class_path) = class_path {
+            if let Some(excluded) = self
+                .class_member_exclusions
+                .iter()
+                .find(|path| override_matches(path, &class_path))
+            {
+                return Some(class_members_exclusion_reason(excluded));
+            }
+        }
+        let function = item.as_function_ref()?;
+        if let Some(exclusion) = self.method_exclusions.iter().find(|e| e.matches(function)) {
+            return Some(method_exclusion_reason(exclusion));
+        }
+        self.method_filters
+            .iter()
+            .find(|(filter, _)| !filter(function))
+            .map(|(_, location)| format!("excluded by method filter registered at {}", location))
+    }
+
+    /// Requests an instantiation of class template `template_path` with `arguments`,
+    /// e.g. `QList<MyType>`. By default, only instantiations used in the parsed API
+    /// are processed, so this method allows to generate wrappers for other
+    /// instantiations. Arguments can be specified as `CppType` values or as type names
+    /// resolved after parsing (see `TemplateArgument`).
+    ///
+    /// Returns an error if `template_path` or any of the type names can't be parsed.
+    /// The template and the argument types are checked when the instantiation
+    /// is processed, and processing fails if they are not found.
+    pub fn add_template_instantiation<A: Into<TemplateArgument>>(
+        &mut self,
+        template_path: &str,
+        arguments: Vec<A>,
+    ) -> Result<()> {
+        let arguments = arguments.into_iter().map(Into::into).collect::<Vec<_>>();
+        let text = format!(
+            "{}<{}>",
+            template_path,
+            arguments.iter().map(ToString::to_string).join(", ")
+        );
+        let template_path = CppPath::from_str(template_path)
+            .with_context(|_| format!("invalid template instantiation: {}", text))?;
+        if arguments.is_empty() {
+            bail!(
+                "invalid template instantiation: {}: no template arguments",
+                text
+            );
+        }
+        for argument in &arguments {
+            check_template_argument(argument)
+                .with_context(|_| format!("invalid template instantiation: {}", text))?;
+        }
+        self.template_instantiations
+            .push((template_path, arguments));
+        Ok(())
+    }
+
+    /// Returns instantiations requested by `add_template_instantiation`.
+    pub fn template_instantiations(&self) -> &[(CppPath, Vec<TemplateArgument>)] {
+        &self.template_instantiations
+    }
+
+    /// Requests instantiations of member function template `method_path`
+    /// (e.g. `QVariant::value`) with each of `types` as its template argument.
+    /// Member function templates are not instantiated by default.
+    /// Types can be specified as `CppType` values or as type names resolved after
+    /// parsing (see `TemplateArgument`). Instantiated methods are checked by
+    /// the C++ checker, and their Rust names contain captions of the types
+    /// (e.g. `value_int`) unless the arguments can be inferred by the C++ compiler.
+    ///
+    /// Returns an error if the path or any of the type names can't be parsed.
+    /// Processing fails if the method is not found or is not a template
+    /// with a single template parameter.
+    pub fn instantiate_method<A: Into<TemplateArgument>>(
+        &mut self,
+        method_path: &str,
+        types: Vec<A>,
+    ) -> Result<()> {
+        let types = types.into_iter().map(Into::into).collect::<Vec<_>>();
+        let text = format!(
+            "{} with {}",
+            method_path,
+            types.iter().map(ToString::to_string).join(", ")
+        );
+        let method_path = CppPath::from_str(method_path)
+            .with_context(|_| format!("invalid method instantiation: {}", text))?;
+        if !method_path.has_parent() {
+            bail!(
+                "invalid method instantiation: {}: not a path of a class member",
+                text
+            );
+        }
+        if types.is_empty() {
+            bail!("invalid method instantiation: {}: no types", text);
+        }
+        for argument in &types {
+            check_template_argument(argument)
+                .with_context(|_| format!("invalid method instantiation: {}", text))?;
+        }
+        self.method_instantiations.push((method_path, types));
+        Ok(())
+    }
+
+    /// Returns instantiations requested by `instantiate_method`.
+    pub fn method_instantiations(&self) -> &[(CppPath, Vec<TemplateArgument>)] {
+        &self.method_instantiations
+    }
+
+    /// Marks function `function_path` as never returning a null pointer.
+    /// By default, a function returning a pointer to a class returns
+    /// `Option<Ref<T>>` in the Rust API. Functions marked with this method
+    /// return `Ref<T>` instead and panic if a null pointer is returned.
+    pub fn set_non_null(&mut self, function_path: CppPath) {
+        self.non_null_functions.push(function_path);
+    }
+
+    /// Returns functions marked as never returning a null pointer.
+    pub fn non_null_functions(&self) -> &[CppPath] {
+        &self.non_null_functions
+    }
+
+    /// Sets ownership of the object returned by pointer from function `function_path`.
+    /// By default, returned pointers are considered borrowed. Functions returning
+    /// owned pointers (e.g. factory functions) should be marked as
+    /// `ReturnOwnership::Owned`, so that the returned object is deleted
+    /// when the Rust value is dropped. If the function is also marked with
+    /// `set_non_null`, it returns `CppBox<T>` instead of `Option<CppBox<T>>`.
+    ///
+    /// The setting has no effect on functions returning references or
+    /// values of classes that can't be deleted.
+    pub fn set_return_ownership(&mut self, function_path: CppPath, ownership: ReturnOwnership) {
+        self.return_ownership
+            .retain(|(path, _)| path != &function_path);
+        self.return_ownership.push((function_path, ownership));
+    }
+
+    /// Returns ownership of the object returned by pointer from function `function_path`.
+    pub fn return_ownership(&self, function_path: &CppPath) -> ReturnOwnership {
+        self.return_ownership
+            .iter()
+            .find(|(path, _)| path == function_path)
+            .map_or(ReturnOwnership::Borrowed, |(_, ownership)| *ownership)
+    }
+
+    /// Enables or disables borrow checking in the generated API.
+    /// If enabled, C++ references are represented as Rust references:
+    ///
+    /// - methods take `&self` if they are const and `&mut self` otherwise;
+    /// - reference arguments become `&T` or `&mut T` instead of pointers;
+    /// - a reference returned by a method becomes `&T` or `&mut T` bound to
+    ///   the lifetime of `self`, so that it can't be used after the object is dropped;
+    /// - a reference returned by a free function or a static method becomes a raw pointer
+    ///   because its lifetime is unknown.
+    ///
+    /// Borrow checking is disabled by default because it changes signatures
+    /// of most methods.
+    pub fn set_borrow_checking(&mut self, value: bool) {
+        self.borrow_checking = value;
+    }
+
+    /// Returns true if borrow checking is enabled.
+    pub fn borrow_checking(&self) -> bool {
+        self.borrow_checking
+    }
+
+    /// Enables or disables `Eq`, `PartialOrd` and `Ord` implementations
+    /// for classes that declare all six comparison operators
+    /// (`==`, `!=`, `<`, `<=`, `>`, `>=`) with the same class on both sides.
+    ///
+    /// The implementations assume that the C++ operators define a total order.
+    /// C++ doesn't guarantee that, so this is disabled by default. Without it,
+    /// such classes only get `PartialEq` and `cpp_core::cmp` traits.
+    pub fn set_total_order_impls(&mut self, value: bool) {
+        self.total_order_impls = value;
+    }
+
+    /// Returns true if total order implementations are enabled.
+    pub fn total_order_impls(&self) -> bool {
+        self.total_order_impls
+    }
+
+    /// Enables detection of Qt signals and slots by the `signals:`, `Q_SIGNALS:`,
+    /// `slots:` and `Q_SLOTS:` sections of classes. Detected methods are marked
+    /// as signals or slots in the database, and the Rust API gets signal and slot
+    /// accessors for them that allow connecting signals to receivers.
+    ///
+    /// Without this option, only signals having a `QPrivateSignal`
+    /// argument are detected.
+    pub fn set_signal_macro_sections(&mut self, value: bool) {
+        self.signal_macro_sections = value;
+    }
+
+    /// Returns true if signals and slots are detected by class sections.
+    pub fn signal_macro_sections(&self) -> bool {
+        self.signal_macro_sections
+    }
+
+    /// Marks method `function_path` as returning a reference that is not bound
+    /// to the lifetime of the object, e.g. a reference to a global object.
+    /// When borrow checking is enabled, the returned value of such methods
+    /// is represented in the same way as when borrow checking is disabled.
+    pub fn set_unbound_return_lifetime(&mut self, function_path: CppPath) {
+        self.unbound_return_lifetime_functions.push(function_path);
+    }
+
+    /// Returns methods marked with `set_unbound_return_lifetime`.
+    pub fn unbound_return_lifetime_functions(&self) -> &[CppPath] {
+        &self.unbound_return_lifetime_functions
+    }
+
+    /// Overrides the allocation place of class `path`. If `path` doesn't
+    /// have template arguments, the override also applies to all
+    /// instantiations of the class template.
+    ///
+    /// By default, a class is stack-allocated only if it's a POD type
+    /// no larger than `max_stack_allocated_size`. All other classes
+    /// are heap-allocated.
+    pub fn set_type_allocation_place(&mut self, place: TypeAllocationPlace, path: CppPath) {
+        self.type_allocation_places.retain(|(p, _)| p != &path);
+        self.type_allocation_places.push((path, place));
+    }
+
+    /// Returns allocation place overrides set by `set_type_allocation_place`.
+    pub fn type_allocation_places(&self) -> &[(CppPath, TypeAllocationPlace)] {
+        &self.type_allocation_places
+    }
+
+    /// Maps C++ class `cpp_path` (e.g. `Vector3f`) to a hand-written Rust type.
+    /// The Rust type is used in all signatures instead of the class, and no wrapper
+    /// type or methods are generated for the class. The crate containing the Rust type
+    /// must be added to the dependencies.
+    ///
+    /// With `PassBy::ByValue`, the Rust type must have the same layout as the C++ class
+    /// (e.g. a `#[repr(C)]` struct with the same fields).
+    pub fn map_type(&mut self, cpp_path: &str, mapping: RustTypeMapping) -> Result<()> {
+        let cpp_path = CppPath::from_str(cpp_path)?;
+        if self.type_mappings.iter().any(|(path, _)| path == &cpp_path) {
+            bail!(
+                "type mapping for {} is already set",
+                cpp_path.to_cpp_pseudo_code()
+            );
+        }
+        if mapping.rust_path.parts.len() < 2 {
+            bail!("Rust type path must include crate name");
+        }
+        self.type_mappings.push((cpp_path, mapping));
+        Ok(())
+    }
+
+    /// Returns the Rust type mapping of C++ class `path`, if any.
+    pub fn type_mapping(&self, path: &CppPath) -> Option<&RustTypeMapping> {
+        self.type_mappings
+            .iter()
+            .find(|(mapped_path, _)| mapped_path == path)
+            .map(|(_, mapping)| mapping)
+    }
+
+    /// Returns all type mappings added with `map_type`.
+    pub fn type_mappings(&self) -> &[(CppPath, RustTypeMapping)] {
+        &self.type_mappings
+    }
+
+    /// Makes C++ class `cpp_path` opaque. The Rust API contains an opaque struct
+    /// for the class that can only be used through pointers and references,
+    /// like classes that are only forward-declared in the headers.
+    /// No wrappers are generated for constructors, methods and fields
+    /// of the class, and functions that take or return the class by value
+    /// are rejected. If `cpp_path` doesn't have template arguments,
+    /// all instantiations of the class template are also opaque.
+    pub fn set_opaque(&mut self, cpp_path: &str) -> Result<()> {
+        let cpp_path = CppPath::from_str(cpp_path)?;
+        if !self.opaque_types.contains(&cpp_path) {
+            self.opaque_types.push(cpp_path);
+        }
+        Ok(())
+    }
+
+    /// Returns classes added with `set_opaque`.
+    pub fn opaque_types(&self) -> &[CppPath] {
+        &self.opaque_types
+    }
+
+    /// Checks if class `path` is made opaque by `set_opaque`.
+    pub fn is_opaque(&self, path: &CppPath) -> bool {
+        self.opaque_types
+            .iter()
+            .any(|opaque_path| override_matches(opaque_path, path))
+    }
+
+    /// Adds C++ class template `cpp_path` (e.g. `QFlags`) representing
+    /// an OR-combination of values of the enum type passed as its only
+    /// template argument. Instantiations of the template are passed
+    /// across FFI as `int`, so the template must be constructible from
+    /// and convertible to `int`.
+    ///
+    /// A Rust type with the same name is generated in the crate declaring
+    /// the template. It supports bitwise operators, and wrappers of
+    /// the enums used with the template get a `BitOr` implementation
+    /// producing the flags type. Crates using the template declared in
+    /// a dependency must also add it.
+    pub fn set_flags_template(&mut self, cpp_path: &str) -> Result<()> {
+        let cpp_path = CppPath::from_str(cpp_path)?;
+        if cpp_path.last().template_arguments.is_some() {
+            bail!(
+                "flags template can't have template arguments: {}",
+                cpp_path.to_cpp_pseudo_code()
+            );
+        }
+        if self.flags_templates.contains(&cpp_path) {
+            bail!(
+                "flags template {} is already added",
+                cpp_path.to_cpp_pseudo_code()
+            );
+        }
+        self.flags_templates.push(cpp_path);
+        Ok(())
+    }
+
+    /// Adds flags template `cpp_path` like `set_flags_template`, but uses
+    /// the hand-written generic Rust type `rust_path` (e.g. `qt_core::QFlags`)
+    /// instead of generating one. The Rust type must implement
+    /// `From<c_int>`, and `c_int` must implement `From` for it.
+    pub fn map_flags_template(&mut self, cpp_path: &str, rust_path: RustPath) -> Result<()> {
+        if rust_path.parts.len() < 2 {
+            bail!("Rust type path must include crate name");
+        }
+        self.set_flags_template(cpp_path)?;
+        let cpp_path = self.flags_templates.last().unwrap().clone();
+        self.flags_type_mappings.push((cpp_path, rust_path));
+        Ok(())
+    }
+
+    /// Returns templates added with `set_flags_template` and `map_flags_template`.
+    pub fn flags_templates(&self) -> &[CppPath] {
+        &self.flags_templates
+    }
+
+    /// Returns the Rust type of flags template `cpp_path`, if it was added
+    /// with `map_flags_template`.
+    pub fn flags_type_mapping(&self, cpp_path: &CppPath) -> Option<&RustPath> {
+        self.flags_type_mappings
+            .iter()
+            .find(|(path, _)| path == cpp_path)
+            .map(|(_, rust_path)| rust_path)
+    }
+
+    /// Checks if class `path` is an instantiation of a flags template.
+    pub fn is_flags_type(&self, path: &CppPath) -> bool {
+        is_flags_type(path, &self.flags_templates)
+    }
+
+    /// Adds C++ string class `cpp_path` (e.g. `QString`). Arguments of this type
+    /// passed by value or by const reference accept Rust strings as well as
+    /// objects of the class. Wrapper type of the class gets
+    /// a `to_std_string()` method.
+    pub fn add_string_type(&mut self, cpp_path: &str, conversion: StringConversion) -> Result<()> {
+        let cpp_path = CppPath::from_str(cpp_path)?;
+        if self.string_types.iter().any(|(path, _)| path == &cpp_path) {
+            bail!(
+                "string type {} is already added",
+                cpp_path.to_cpp_pseudo_code()
+            );
+        }
+        if conversion.from_utf8_ctor.is_empty() {
+            bail!("from_utf8_ctor can't be empty");
+        }
+        self.string_types.push((cpp_path, conversion));
+        Ok(())
+    }
+
+    /// Returns the conversion of string type `path`, if it was added
+    /// with `add_string_type`.
+    pub fn string_type(&self, path: &CppPath) -> Option<&StringConversion> {
+        self.string_types
+            .iter()
+            .find(|(string_path, _)| string_path == path)
+            .map(|(_, conversion)| conversion)
+    }
+
+    /// Returns all string types added with `add_string_type`.
+    pub fn string_types(&self) -> &[(CppPath, StringConversion)] {
+        &self.string_types
+    }
+
+    /// Makes the Rust wrapper of function `function_path` return values
+    /// of its trailing out-parameters instead of taking pointers to them.
+    ///
+    /// An out-parameter is a non-const pointer to a built-in numeric type,
+    /// an enum or a stack-allocated class. The wrapper allocates the value
+    /// and passes its address to the C++ function.
+    pub fn set_out_params(&mut self, function_path: CppPath, mode: OutParams) {
+        self.out_params_functions
+            .retain(|(p, _)| p != &function_path);
+        self.out_params_functions.push((function_path, mode));
+    }
+
+    /// Enables or disables treating trailing out-parameters of all functions
+    /// as described in `set_out_params` (with `OutParams::Tuple` mode).
+    /// Disabled by default.
+    pub fn set_detect_out_params(&mut self, value: bool) {
+        self.detect_out_params = value;
+    }
+
+    /// Returns true if out-parameters of all functions are detected.
+    pub fn detect_out_params(&self) -> bool {
+        self.detect_out_params
+    }
+
+    /// Keeps pointer arguments of function `function_path` as is, e.g. if
+    /// null is a meaningful value for them. This overrides `set_out_params`
+    /// and `set_detect_out_params`.
+    pub fn exclude_out_params(&mut self, function_path: CppPath) {
+        self.out_params_excluded_functions.push(function_path);
+    }
+
+    /// Returns the way the Rust wrapper of function `function_path` returns
+    /// its out-parameters, or `None` if out-parameters are kept as arguments.
+    pub fn out_params(&self, function_path: &CppPath) -> Option<OutParams> {
+        if self.out_params_excluded_functions.contains(function_path) {
+            return None;
+        }
+        if let Some((_, mode)) = self
+            .out_params_functions
+            .iter()
+            .find(|(p, _)| p == function_path)
+        {
+            return Some(*mode);
+        }
+        if self.detect_out_params {
+            Some(OutParams::Tuple)
+        } else {
+            None
+        }
+    }
+
+    /// Adds a name of `void*` arguments that carry user data of callbacks.
+    ///
+    /// If a function pointer argument is followed by a `void*` argument with
+    /// one of these names, and the function pointer takes exactly one `void*`
+    /// argument, the generated Rust function accepts a closure instead of
+    /// these two arguments. The closure is called by a generated C++ function
+    /// that receives the user data pointer.
+    pub fn add_callback_user_data_name(&mut self, name: impl Into<String>) {
+        self.callback_user_data_names.push(name.into());
+    }
+
+    /// Returns names of `void*` arguments that carry user data of callbacks.
+    pub fn callback_user_data_names(&self) -> &[String] {
+        &self.callback_user_data_names
+    }
+
+    /// Sets the function that unregisters callbacks registered by function
+    /// `register_path`. The Rust wrapper of `register_path` returns
+    /// `cpp_core::CallbackGuard` that calls `unregister_path` and frees
+    /// the closure when dropped. Without an unregister function, closures
+    /// are never freed.
+    ///
+    /// `register_path` must return `void`. Each argument of `unregister_path`
+    /// must be the function pointer or the user data pointer passed
+    /// to `register_path`.
+    pub fn set_callback_unregister_function(
+        &mut self,
+        register_path: CppPath,
+        unregister_path: CppPath,
+    ) {
+        self.callback_unregister_functions
+            .retain(|(p, _)| p != &register_path);
+        self.callback_unregister_functions
+            .push((register_path, unregister_path));
+    }
+
+    /// Returns the function that unregisters callbacks registered by
+    /// function `register_path`.
+    pub fn callback_unregister_function(&self, register_path: &CppPath) -> Option<&CppPath> {
+        self.callback_unregister_functions
+            .iter()
+            .find(|(p, _)| p == register_path)
+            .map(|(_, unregister_path)| unregister_path)
+    }
+
+    /// Sets the way of exposing overloaded C++ functions.
+    /// Default is `OverloadingStrategy::Suffixes`.
+    ///
+    /// With `OverloadingStrategy::Traits`, overloads that can't be accepted
+    /// by a single generic function (e.g. overloads taking closures or
+    /// references, or methods with different kinds of `self`) still use
+    /// suffixes. If argument types of two overloads can be the same type
+    /// on some platform (e.g. `c_int` and `c_long`), only the first of them
+    /// is accepted by the generic function.
+    pub fn set_overloading_strategy(&mut self, strategy: OverloadingStrategy) {
+        self.overloading_strategy = strategy;
+    }
+
+    /// Returns the way of exposing overloaded C++ functions.
+    pub fn overloading_strategy(&self) -> OverloadingStrategy {
+        self.overloading_strategy
+    }
+
+    /// Sets the way of adding methods of public base classes to derived classes.
+    /// Default is `InheritedMethodsStrategy::Disabled`.
+    ///
+    /// Constructors, destructors and assignment operators are never inherited.
+    pub fn set_inherited_methods_strategy(&mut self, strategy: InheritedMethodsStrategy) {
+        self.inherited_methods_strategy = strategy;
+    }
+
+    /// Returns the way of adding methods of base classes to derived classes.
+    pub fn inherited_methods_strategy(&self) -> InheritedMethodsStrategy {
+        self.inherited_methods_strategy
+    }
+
+    /// Sets the way of handling C++ arguments with default values.
+    /// Default is `DefaultArgumentsStrategy::Variants`.
+    pub fn set_default_arguments_strategy(&mut self, strategy: DefaultArgumentsStrategy) {
+        self.default_arguments_strategy = strategy;
+    }
+
+    /// Returns the way of handling C++ arguments with default values.
+    pub fn default_arguments_strategy(&self) -> DefaultArgumentsStrategy {
+        self.default_arguments_strategy
+    }
+
+    /// Sets the maximal number of variants with omitted arguments generated
+    /// for a single function. Variants omitting more arguments are preferred.
+    /// `None` (the default) means no limit.
+    pub fn set_omitted_arguments_variants_limit(&mut self, limit: Option<usize>) {
+        self.omitted_arguments_variants_limit = limit;
+    }
+
+    /// Returns the maximal number of variants with omitted arguments per function.
+    pub fn omitted_arguments_variants_limit(&self) -> Option<usize> {
+        self.omitted_arguments_variants_limit
+    }
+
+    /// Sets whether recorded names of Rust wrappers should be discarded.
+    /// Default is `false`.
+    ///
+    /// Names of wrappers of C++ functions are recorded in the database,
+    /// and each function keeps its name in the following runs, even if
+    /// new overloads are added to the C++ library. Names of removed functions
+    /// are never given to other functions. Discarding the names allows
+    /// `rust_generator` to assign all names from scratch, which is a breaking
+    /// change of the generated API. It's only useful for a new major version
+    /// of the crate, and it should be disabled again after the run.
+    pub fn set_reset_rust_names(&mut self, value: bool) {
+        self.reset_rust_names = value;
+    }
+
+    /// Returns whether recorded names of Rust wrappers should be discarded.
+    pub fn reset_rust_names(&self) -> bool {
+        self.reset_rust_names
+    }
+
+    /// Sets the Rust name of a C++ type, enum value or function
+    /// (e.g. `rename("QFile::open", "open_file")`). The name is used as is,
+    /// without case conversion. All overloads of a function are renamed,
+    /// and suffixes are still added to tell them apart.
+    pub fn rename(&mut self, cpp_path: &str, rust_name: &str) -> Result<()> {
+        self.rename_rules.push(RenameRule::Exact {
+            cpp_path: CppPath::from_str(cpp_path)?,
+            rust_name: rust_name.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Adds a rule that replaces matches of a regular expression in the last part
+    /// of C++ names (e.g. `QFile` or `qAbs`) with `replacement` before
+    /// the names are converted to the Rust case. `replacement` can refer to
+    /// the groups of the pattern (see `regex::Regex::replace`).
+    /// Pattern rules are applied in the order they were added.
+    /// Names set by `rename` and special names of functions
+    /// (e.g. `new` for constructors) are not affected.
+    pub fn add_rename_pattern(&mut self, pattern: &str, replacement: &str) -> Result<()> {
+        let regex =
+            Regex::new(pattern).with_context(|_| format!("invalid pattern: {:?}", pattern))?;
+        self.rename_rules.push(RenameRule::Pattern {
+            regex,
+            replacement: replacement.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Adds a rule that removes `prefix` (case-insensitive) from C++ names
+    /// if it's followed by an uppercase letter or an underscore
+    /// (e.g. `strip_prefix("q")` turns `qAbs` into `abs` and `q_check_ptr`
+    /// into `check_ptr`, but keeps `quit` as is).
+    pub fn strip_prefix(&mut self, prefix: &str) -> Result<()> {
+        let pattern = format!("^(?i:{})(?:_(.)|([[:upper:]]))", regex::escape(prefix));
+        self.add_rename_pattern(&pattern, "${1}${2}")
+    }
+
+    /// Sets the name of the Rust module generated for a C++ namespace
+    /// (e.g. `rename_module("Qt3DCore", "core")`).
+    pub fn rename_module(&mut self, cpp_path: &str, rust_name: &str) -> Result<()> {
+        self.rename_rules.push(RenameRule::Module {
+            cpp_path: CppPath::from_str(cpp_path)?,
+            rust_name: rust_name.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Returns rules added by `rename`, `add_rename_pattern`, `strip_prefix`
+    /// and `rename_module`.
+    pub fn rename_rules(&self) -> &[RenameRule] {
+        &self.rename_rules
+    }
+
+    /// Sets the layout of the modules of the generated crate.
+    /// Default is `ModuleStrategy::Namespaces`.
+    pub fn set_module_strategy(&mut self, strategy: ModuleStrategy) {
+        self.module_strategy = strategy;
+    }
+
+    /// Returns the layout of the modules of the generated crate.
+    pub fn module_strategy(&self) -> ModuleStrategy {
+        self.module_strategy
+    }
+
+    /// Sets whether class members declared in another header than their class
+    /// (e.g. in a header with out-of-class member definitions) are grouped
+    /// with the class when items are split by headers: into features
+    /// (see `add_feature`), files of the C++ wrapper library and sections
+    /// of the coverage report. Free functions are always grouped by
+    /// their own header.
+    ///
+    /// Enabled by default.
+    pub fn set_group_members_by_class_header(&mut self, value: bool) {
+        self.group_members_by_class_header = value;
+    }
+
+    /// Returns true if class members are grouped with their class's header.
+    pub fn group_members_by_class_header(&self) -> bool {
+        self.group_members_by_class_header
+    }
+
+    /// Sets the placement of Rust types generated for C++ nested types.
+    /// Default is `NestedTypesStrategy::Modules`.
+    pub fn set_nested_types_strategy(&mut self, strategy: NestedTypesStrategy) {
+        self.nested_types_strategy = strategy;
+    }
+
+    /// Returns the placement of Rust types generated for C++ nested types.
+    pub fn nested_types_strategy(&self) -> NestedTypesStrategy {
+        self.nested_types_strategy
+    }
+
+    /// Removes the Rust module of a C++ namespace. Items of the namespace
+    /// are placed in the module of the parent namespace instead
+    /// (or in the crate root for a top level namespace).
+    /// Only used with `ModuleStrategy::Namespaces`.
+    pub fn flatten_namespace(&mut self, cpp_path: &str) -> Result<()> {
+        self.flattened_namespaces.push(CppPath::from_str(cpp_path)?);
+        Ok(())
+    }
+
+    /// Returns namespaces added with `flatten_namespace`.
+    pub fn flattened_namespaces(&self) -> &[CppPath] {
+        &self.flattened_namespaces
+    }
+
+    /// Sets the namespace whose content is re-exported from the crate root,
+    /// so that e.g. `mylib::Helper` is available as both `crate::mylib::Helper`
+    /// and `crate::Helper`. Only used with `ModuleStrategy::Namespaces`.
+    pub fn set_crate_root_namespace(&mut self, cpp_path: &str) -> Result<()> {
+        self.crate_root_namespace = Some(CppPath::from_str(cpp_path)?);
+        Ok(())
+    }
+
+    /// Returns the namespace set by `set_crate_root_namespace`.
+    pub fn crate_root_namespace(&self) -> Option<&CppPath> {
+        self.crate_root_namespace.as_ref()
+    }
+
+    /// Adds an acronym that is kept as a single word when C++ names are converted
+    /// to the snake case (e.g. with `add_acronym("SQL")`, `QSQLDriver` becomes
+    /// `q_sql_driver` instead of `q_s_q_l_driver`). Acronyms are matched
+    /// against sequences of uppercase letters, so `acronym` is converted to the uppercase.
+    /// `IO`, `GL`, `ES`, `XML` and `HTTP` are added by default.
+    pub fn add_acronym(&mut self, acronym: &str) {
+        let acronym = acronym.to_uppercase();
+        if !self.acronyms.contains(&acronym) {
+            self.acronyms.push(acronym);
+        }
+    }
+
+    /// Returns acronyms used for converting C++ names to the snake case.
+    pub fn acronyms(&self) -> &[String] {
+        &self.acronyms
+    }
+
+    /// Sets the suffix added to names of generated types that would be the same as
+    /// names of the Rust prelude items (e.g. a C++ class `Option` becomes `Option_`).
+    /// Default is `"_"`.
+    pub fn set_prelude_collision_suffix(&mut self, suffix: impl Into<String>) {
+        self.prelude_collision_suffix = suffix.into();
+    }
+
+    /// Returns the suffix added to names of types colliding with the Rust prelude.
+    pub fn prelude_collision_suffix(&self) -> &str {
+        &self.prelude_collision_suffix
+    }
+
+    /// Generates a Rust enum for C++ enum `cpp_path` instead of a wrapper struct
+    /// with associated constants.
+    ///
+    /// C++ code can use any value of the underlying type as an enum value,
+    /// but receiving a value that doesn't match any variant of a Rust enum
+    /// is undefined behavior. Only use this for enums that never receive such values
+    /// (e.g. no flag combinations or values added in newer versions of the library).
+    pub fn add_closed_enum(&mut self, cpp_path: &str) -> Result<()> {
+        self.closed_enums.push(CppPath::from_str(cpp_path)?);
+        Ok(())
+    }
+
+    /// Returns enums added with `add_closed_enum`.
+    pub fn closed_enums(&self) -> &[CppPath] {
+        &self.closed_enums
+    }
+
+    /// Sets thread safety of the Rust wrapper of C++ class `cpp_path`
+    /// (e.g. `set_thread_safety("QColor", ThreadSafety::SendSync)`).
+    /// `cpp_path` may contain `*` wildcards (e.g. `"mylib::*Data"`).
+    /// If multiple rules match a class, the last added rule is used.
+    ///
+    /// Wrappers of C++ classes are neither `Send` nor `Sync` by default.
+    /// `Send` and `Sync` are implemented based on the user's claim that
+    /// the C++ class is thread-safe, which can't be verified by ritual.
+    pub fn set_thread_safety(&mut self, cpp_path: &str, thread_safety: ThreadSafety) {
+        let pattern = cpp_path
+            .split('*')
+            .map(regex::escape)
+            .collect::<Vec<_>>()
+            .join(".*");
+        let regex = Regex::new(&format!("^{}$", pattern)).expect("escaped pattern must be valid");
+        self.thread_safety_rules.push((regex, thread_safety));
+    }
+
+    /// Sets thread safety of classes that don't match any of the rules
+    /// added with `set_thread_safety`. Default is `ThreadSafety::None`.
+    pub fn set_default_thread_safety(&mut self, thread_safety: ThreadSafety) {
+        self.default_thread_safety = thread_safety;
+    }
+
+    /// Returns thread safety set for C++ class `cpp_path` with `set_thread_safety`.
+    pub fn thread_safety_rule(&self, cpp_path: &CppPath) -> Option<ThreadSafety> {
+        let templateless_path = cpp_path.to_templateless_string();
+        let full_path = cpp_path.to_cpp_pseudo_code();
+        self.thread_safety_rules
+            .iter()
+            .rev()
+            .find(|(regex, _)| regex.is_match(&templateless_path) || regex.is_match(&full_path))
+            .map(|(_, thread_safety)| *thread_safety)
+    }
+
+    /// Returns thread safety of the Rust wrapper of C++ class `cpp_path`.
+    pub fn thread_safety(&self, cpp_path: &CppPath) -> ThreadSafety {
+        self.thread_safety_rule(cpp_path)
+            .unwrap_or(self.default_thread_safety)
+    }
+
+    /// Sets the maximal size (in bytes) of a class that can be automatically
+    /// chosen for stack allocation. Setting it to 0 disables automatic
+    /// stack allocation. The default value is 64.
+    ///
+    /// This limit doesn't apply to classes configured with `set_type_allocation_place`.
+    pub fn set_max_stack_allocated_size(&mut self, size: usize) {
+        self.max_stack_allocated_size = size;
+    }
+
+    /// Returns the maximal size of an automatically stack-allocated class.
+    pub fn max_stack_allocated_size(&self) -> usize {
+        self.max_stack_allocated_size
+    }
+
+    /// Enables checking layouts of stack-allocated classes. Sizes and alignments
+    /// reported by the parser are compared with the values produced by
+    /// the C++ compiler, and `on_mismatch` is applied to the classes that
+    /// fail the check. The check is disabled by default.
+    pub fn set_type_layout_check(
+        &mut self,
+        mode: TypeLayoutCheckMode,
+        on_mismatch: TypeLayoutMismatchAction,
+    ) {
+        self.type_layout_check = Some((mode, on_mismatch));
+    }
+
+    /// Returns the layout check settings set by `set_type_layout_check`.
+    pub fn type_layout_check(&self) -> Option<(TypeLayoutCheckMode, TypeLayoutMismatchAction)> {
+        self.type_layout_check
+    }
+
+    /// Sets the `cfg` expression used in the generated crate for items
+    /// available only on some of the targets of a merged database
+    /// (e.g. `target_os = "linux"`).
+    ///
+    /// By default, the expression lists all properties of the target,
+    /// as returned by `Target::cfg_expression`.
+    pub fn set_target_cfg_expression(&mut self, target: Target, expression: impl Into<String>) {
+        self.target_cfg_expressions.retain(|(t, _)| t != &target);
+        self.target_cfg_expressions
+            .push((target, expression.into()));
+    }
+
+    /// Returns the `cfg` expression for `target`.
+    pub fn target_cfg_expression(&self, target: &Target) -> String {
+        self.target_cfg_expressions
+            .iter()
+            .find(|(t, _)| t == target)
+            .map_or_else(
+                || target.cfg_expression(),
+                |(_, expression)| expression.clone(),
+            )
+    }
+
+    /// Sets how the crate version suggested by comparing the API with
+    /// the previously generated crate is used. The suggestion and the changes
+    /// it's based on are always written to `log/<crate>.api_changes.txt`
+    /// in the workspace. Default is `VersionSuggestionMode::Report`.
+    pub fn set_version_suggestion_mode(&mut self, mode: VersionSuggestionMode) {
+        self.version_suggestion_mode = mode;
+    }
+
+    /// Returns the value set by `set_version_suggestion_mode`.
+    pub fn version_suggestion_mode(&self) -> VersionSuggestionMode {
+        self.version_suggestion_mode
+    }
+
+    /// Sets the number of source files the generated C++ wrapper library
+    /// is split into. Wrappers are distributed between files based on
+    /// the include file that declares the wrapped item, so each file
+    /// can be compiled in parallel with others.
+    ///
+    /// By default, a separate source file is generated for each include file.
+    pub fn set_cpp_glue_file_count(&mut self, count: usize) -> Result<()> {
+        if count == 0 {
+            bail!("C++ glue file count must be positive");
+        }
+        self.cpp_glue_file_count = Some(count);
+        Ok(())
+    }
+
+    /// Returns the number of source files of the C++ wrapper library
+    /// set by `set_cpp_glue_file_count`.
+    pub fn cpp_glue_file_count(&self) -> Option<usize> {
+        self.cpp_glue_file_count
+    }
+
+    /// Enables or disables source annotations in the generated code.
+    /// If enabled, each C++ wrapper function is preceded by the original C++
+    /// signature and a `#line` directive pointing at the original declaration,
+    /// so that debuggers and compiler messages refer to the library's headers.
+    /// Documentation of the Rust wrappers also mentions the declaration place.
+    ///
+    /// Annotations are disabled by default because they make the generated code
+    /// change whenever the headers are edited.
+    pub fn set_write_source_annotations(&mut self, value: bool) {
+        self.write_source_annotations = value;
+    }
+
+    /// Returns true if source annotations are enabled.
+    pub fn write_source_annotations(&self) -> bool {
+        self.write_source_annotations
+    }
+
+    /// Enables or disables compile-time checks of the layouts of stack-allocated
+    /// classes in the generated crate. If enabled, the C++ wrapper library contains
+    /// `static_assert`s comparing the size and alignment of each class with
+    /// the layout recorded by the C++ parser, and the Rust crate contains similar
+    /// checks for the wrapper types. If the crate is built in an environment
+    /// where the layout differs, the build fails instead of producing
+    /// wrapper types with an unexpected layout.
+    ///
+    /// Layout assertions are enabled by default. They should be disabled
+    /// if the crate is expected to be built for targets that were not
+    /// used for generating it.
+    pub fn set_write_layout_assertions(&mut self, value: bool) {
+        self.write_layout_assertions = value;
+    }
+
+    /// Returns true if layout assertions are enabled.
+    pub fn write_layout_assertions(&self) -> bool {
+        self.write_layout_assertions
+    }
+
+    /// Enables or disables generation of smoke tests in `tests/` directory
+    /// of the generated crate. For each class with a public default constructor,
+    /// a test creates the object, calls its const getters without arguments and
+    /// drops the object. For each enum, a test converts its values to integers
+    /// and back. Only the items that passed `cpp_checker` in all environments
+    /// are used in the tests.
+    ///
+    /// Smoke tests are disabled by default.
+    pub fn set_write_smoke_tests(&mut self, value: bool) {
+        self.write_smoke_tests = value;
+    }
+
+    /// Returns true if smoke tests are enabled.
+    pub fn write_smoke_tests(&self) -> bool {
+        self.write_smoke_tests
+    }
+
+    /// Enables or disables writing the API coverage report to `COVERAGE.md`
+    /// in the generated crate. The report is always saved in the workspace
+    /// after the Rust API is generated.
+    ///
+    /// The report is not written to the crate by default.
+    pub fn set_write_coverage_report(&mut self, value: bool) {
+        self.write_coverage_report = value;
+    }
+
+    /// Returns true if the coverage report is written to the crate.
+    pub fn write_coverage_report(&self) -> bool {
+        self.write_coverage_report
+    }
+
+    /// Sets the minimal percentage of parsed C++ items that must be available
+    /// in the Rust API. If the coverage is lower, processing fails after
+    /// the coverage report is saved.
+    pub fn set_min_coverage(&mut self, percentage: Option<f64>) {
+        self.min_coverage = percentage;
+    }
+
+    /// Returns the minimal coverage set by `set_min_coverage`.
+    pub fn min_coverage(&self) -> Option<f64> {
+        self.min_coverage
+    }
+
+    /// Adds a regular expression that excludes C++ classes and enums from
+    /// the smoke tests. Patterns are matched in the same way as in
+    /// `add_cpp_parser_blocked_name_pattern`.
+    pub fn add_smoke_test_blocked_name_pattern(&mut self, pattern: &str) -> Result<()> {
+        self.smoke_test_blocked_name_patterns
+            .push(compile_name_pattern(pattern)?);
+        Ok(())
+    }
+
+    pub fn smoke_test_blocked_name_patterns(&self) -> &[Regex] {
+        &self.smoke_test_blocked_name_patterns
+    }
+
+    /// Adds Cargo feature `name` to the generated crate. Items declared in
+    /// `headers` (file names without directories, e.g. `"widget.h"`) are only
+    /// compiled if the feature is enabled. All features are enabled by default.
+    ///
+    /// If an item of a feature uses a type of another feature, the first feature
+    /// enables the second one. Items that don't belong to any feature are always
+    /// compiled, unless they use types of a feature. In that case, they are compiled
+    /// when all features of the used types are enabled.
+    pub fn add_feature(&mut self, name: &str, headers: &[&str]) -> Result<()> {
+        if name.is_empty() || name == "default" || name.starts_with("ritual_") {
+            bail!("invalid feature name: {:?}", name);
+        }
+        if self.features.iter().any(|feature| feature.name == name) {
+            bail!("feature {:?} is already added", name);
+        }
+        for header in headers {
+            if let Some(feature) = self.header_feature(header) {
+                bail!(
+                    "header {:?} already belongs to feature {:?}",
+                    header,
+                    feature
+                );
+            }
+        }
+        self.features.push(CrateFeature {
+            name: name.to_string(),
+            headers: headers.iter().map(|header| header.to_string()).collect(),
+        });
+        Ok(())
+    }
+
+    pub fn features(&self) -> &[CrateFeature] {
+        &self.features
+    }
+
+    /// Returns name of the feature containing items declared in `include_file`.
+    /// On Windows, file names are compared case-insensitively.
+    pub fn header_feature(&self, include_file: &str) -> Option<&str> {
+        let is_same = |header: &String| {
+            if cfg!(windows) {
+                header.eq_ignore_ascii_case(include_file)
+            } else {
+                header == include_file
+            }
+        };
+        self.features
+            .iter()
+            .find(|feature| feature.headers.iter().any(is_same))
+            .map(|feature| feature.name.as_str())
+    }
+
+    /// Sets the URL of the online documentation of C++ classes and their methods,
+    /// e.g. `"https://docs.example.com/{class}.html#{method}"`. Documentation
+    /// of the generated types and methods links to this URL.
+    ///
+    /// Supported placeholders are `{class}` (full name of the class),
+    /// `{method}` (name of the method) and `{header}` (file name of the header).
+    /// Substituted values are percent-encoded. If a placeholder in the fragment
+    /// (the part after `#`) can't be filled, e.g. `{method}` for a class,
+    /// the fragment is omitted.
+    pub fn set_doc_url_template(&mut self, template: &str) -> Result<()> {
+        check_doc_url_template(template, &["class", "method", "header"])?;
+        self.doc_url_template = Some(template.to_string());
+        Ok(())
+    }
+
+    pub fn doc_url_template(&self) -> Option<&str> {
+        self.doc_url_template.as_deref()
+    }
+
+    /// Sets the URL of the online documentation of free C++ functions.
+    /// Supported placeholders are `{function}` (full name of the function)
+    /// and `{header}`. See also `set_doc_url_template`.
+    pub fn set_function_doc_url_template(&mut self, template: &str) -> Result<()> {
+        check_doc_url_template(template, &["function", "header"])?;
+        self.function_doc_url_template = Some(template.to_string());
+        Ok(())
+    }
+
+    pub fn function_doc_url_template(&self) -> Option<&str> {
+        self.function_doc_url_template.as_deref()
+    }
+
+    /// Sets the URL of the online documentation of C++ enums and their values.
+    /// Supported placeholders are `{enum}` (full name of the enum),
+    /// `{value}` (name of the enum value) and `{header}`.
+    /// See also `set_doc_url_template`.
+    pub fn set_enum_doc_url_template(&mut self, template: &str) -> Result<()> {
+        check_doc_url_template(template, &["enum", "value", "header"])?;
+        self.enum_doc_url_template = Some(template.to_string());
+        Ok(())
+    }
+
+    pub fn enum_doc_url_template(&self) -> Option<&str> {
+        self.enum_doc_url_template.as_deref()
+    }
+}
+
+#[derive(Default)]
+pub struct GlobalConfig {
+    create_config_hook: Option<Box<dyn FnMut(CrateProperties) -> Result<Config>>>,
+    all_crate_names: Vec<String>,
+    interruption_flag: Option<Arc<AtomicBool>>,
+}
+
+impl GlobalConfig {
     pub fn new() -> Self {
         Self::default()
     }
@@ -559,4 +3002,63 @@ impl GlobalConfig {
     pub fn all_crate_names(&self) -> &[String] {
         &self.all_crate_names
     }
+
+    /// Sets the flag that requests to interrupt the processing
+    /// (see `Config::set_interruption_flag`). It's passed to configs of all crates.
+    pub fn set_interruption_flag(&mut self, flag: Arc<AtomicBool>) {
+        self.interruption_flag = Some(flag);
+    }
+
+    pub fn interruption_flag(&self) -> Option<&Arc<AtomicBool>> {
+        self.interruption_flag.as_ref()
+    }
+}
+
+/// Checks that `template` only contains placeholders from `allowed`.
+fn check_doc_url_template(template: &str, allowed: &[&str]) -> Result<()> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(index) => start + index,
+            None => bail!("unclosed placeholder in URL template: {:?}", template),
+        };
+        let name = &rest[start + 1..end];
+        if !allowed.contains(&name) {
+            bail!(
+                "unknown placeholder {{{}}} in URL template {:?} (supported: {})",
+                name,
+                template,
+                allowed
+                    .iter()
+                    .map(|name| format!("{{{}}}", name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        rest = &rest[end + 1..];
+    }
+    if rest.contains('}') {
+        bail!("unmatched '}}' in URL template: {:?}", template);
+    }
+    Ok(())
+}
+
+/// Returns the rejection reason of items excluded by `exclusion`.
+pub fn method_exclusion_reason(exclusion: &MethodExclusion) -> String {
+    format!("excluded by user config: blacklist_method({})", exclusion)
+}
+
+/// Returns the rejection reason of members of class `path` excluded by
+/// `Config::blacklist_class_members`.
+pub fn class_members_exclusion_reason(path: &CppPath) -> String {
+    format!(
+        "excluded by user config: blacklist_class_members({})",
+        path.to_cpp_pseudo_code()
+    )
+}
+
+fn compile_name_pattern(pattern: &str) -> Result<Regex> {
+    let regex = Regex::new(&format!("^(?:{})$", pattern))
+        .with_context(|_| format!("invalid name pattern: {:?}", pattern))?;
+    Ok(regex)
 }
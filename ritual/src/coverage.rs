@@ -0,0 +1,319 @@
+//! Report of the part of the C++ API that is available in the generated crate.
+
+use crate::config::Config;
+use crate::cpp_data::{CppItem, CppPath, CppTypeDeclarationKind};
+use crate::cpp_type::CppType;
+use crate::database::DatabaseClient;
+use itertools::Itertools;
+use ritual_common::errors::Result;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+
+/// Maximal number of rejection reasons of a step listed in the Markdown report
+const MAX_LISTED_REASONS: usize = 10;
+
+/// Numbers of items that are available and not available in the Rust API
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverageCounts {
+    pub bound: usize,
+    pub unbound: usize,
+}
+
+impl CoverageCounts {
+    pub fn total(&self) -> usize {
+        self.bound + self.unbound
+    }
+
+    /// Returns the percentage of bound items. An empty set is fully covered.
+    pub fn percentage(&self) -> f64 {
+        if self.total() == 0 {
+            100.0
+        } else {
+            self.bound as f64 * 100.0 / self.total() as f64
+        }
+    }
+
+    fn add(&mut self, is_bound: bool) {
+        if is_bound {
+            self.bound += 1;
+        } else {
+            self.unbound += 1;
+        }
+    }
+
+    fn add_counts(&mut self, other: CoverageCounts) {
+        self.bound += other.bound;
+        self.unbound += other.unbound;
+    }
+}
+
+/// Coverage of a header, a class or the whole library
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CoverageSection {
+    /// Parsed functions and methods
+    pub functions: CoverageCounts,
+    /// Parsed classes
+    pub types: CoverageCounts,
+    /// Parsed enums
+    pub enums: CoverageCounts,
+    /// Functions and types added by ritual: implicit methods, variants with
+    /// omitted arguments, inherited methods and template instantiations.
+    /// They are not included in the other counts.
+    pub synthesized: CoverageCounts,
+    /// Number of parsed templates. Only their instantiations can be bound,
+    /// so templates are not included in the other counts.
+    pub templates: usize,
+    /// Numbers of unbound parsed items by the step that rejected them
+    pub unbound_by_step: BTreeMap<String, usize>,
+}
+
+impl CoverageSection {
+    /// Returns the sum of the counts of parsed functions, types and enums.
+    pub fn parsed(&self) -> CoverageCounts {
+        let mut counts = self.functions;
+        counts.add_counts(self.types);
+        counts.add_counts(self.enums);
+        counts
+    }
+}
+
+/// Coverage of the C++ API by the generated crate.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub crate_name: String,
+    pub total: CoverageSection,
+    /// Coverage of each header, by include file name
+    pub headers: BTreeMap<String, CoverageSection>,
+    /// Coverage of members of each class, by the class name
+    pub classes: BTreeMap<String, CoverageSection>,
+    /// Numbers of entries of the rejection log, by step and reason.
+    /// Entities rejected by the C++ parser and exclusions of the user config
+    /// are only recorded here.
+    pub rejections: BTreeMap<String, BTreeMap<String, usize>>,
+}
+
+/// Category of a counted C++ item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ItemKind {
+    Function,
+    Type,
+    Enum,
+}
+
+fn has_template_parameters(path: &CppPath) -> bool {
+    path.items().iter().any(|item| {
+        item.template_arguments.as_ref().map_or(false, |args| {
+            args.iter().any(CppType::is_or_contains_template_parameter)
+        })
+    })
+}
+
+fn item_kind(item: &CppItem) -> Option<(ItemKind, &CppPath)> {
+    match item {
+        CppItem::Function(function) => Some((ItemKind::Function, &function.path)),
+        CppItem::Type(type1) => match type1.kind {
+            CppTypeDeclarationKind::Enum { .. } => Some((ItemKind::Enum, &type1.path)),
+            CppTypeDeclarationKind::Class { .. } | CppTypeDeclarationKind::Opaque => {
+                Some((ItemKind::Type, &type1.path))
+            }
+        },
+        _ => None,
+    }
+}
+
+impl CoverageReport {
+    /// Computes the coverage of the C++ items of the current crate of `db`.
+    /// A C++ item is bound if a Rust item was generated from it.
+    /// Items are assigned to headers according to
+    /// `Config::group_members_by_class_header`.
+    pub fn new(db: &DatabaseClient, config: &Config) -> Result<Self> {
+        let mut bound_ids = HashSet::new();
+        for rust_item in db.rust_items() {
+            if let Some(cpp_item) = db.source_cpp_item(&rust_item.id)? {
+                bound_ids.insert(cpp_item.id);
+            }
+        }
+        let ffi_source_ids = db
+            .ffi_items()
+            .filter_map(|item| item.source_id)
+            .collect::<HashSet<_>>();
+        let mut rejection_steps = HashMap::new();
+        let mut rejections = BTreeMap::<String, BTreeMap<String, usize>>::new();
+        for rejection in db.rejections() {
+            rejection_steps
+                .entry(rejection.cpp_name.as_str())
+                .or_insert_with(|| rejection.step.as_str());
+            *rejections
+                .entry(rejection.step.clone())
+                .or_default()
+                .entry(rejection.reason.clone())
+                .or_default() += 1;
+        }
+
+        let mut report = CoverageReport {
+            crate_name: db.crate_name().to_string(),
+            rejections,
+            ..CoverageReport::default()
+        };
+        for item in db.cpp_items() {
+            let (kind, path) = match item_kind(item.item) {
+                Some(value) => value,
+                None => continue,
+            };
+            let header = match db.root_item_origin(&item.id)? {
+                Some(origin) => origin
+                    .grouping_include_file(config.group_members_by_class_header())
+                    .to_string(),
+                None => continue,
+            };
+            let class_name = match item.item {
+                CppItem::Function(function) if function.member.is_some() => {
+                    Some(function.class_path()?.to_cpp_pseudo_code())
+                }
+                _ => None,
+            };
+            let is_bound = bound_ids.contains(&item.id);
+            let unbound_step = if is_bound {
+                None
+            } else if let Some(step) = rejection_steps.get(item.item.short_text().as_str()) {
+                Some(step.to_string())
+            } else if ffi_source_ids.contains(&item.id) {
+                // FFI wrappers only make it into the Rust API
+                // if they pass the checks
+                Some("cpp_checker".to_string())
+            } else {
+                Some("other".to_string())
+            };
+
+            let mut sections = vec![&mut report.total];
+            sections.push(report.headers.entry(header).or_default());
+            if let Some(class_name) = class_name {
+                sections.push(report.classes.entry(class_name).or_default());
+            }
+            for section in sections {
+                if has_template_parameters(path) {
+                    section.templates += 1;
+                    continue;
+                }
+                if item.source_id.is_some() {
+                    if kind != ItemKind::Enum {
+                        section.synthesized.add(is_bound);
+                    }
+                    continue;
+                }
+                match kind {
+                    ItemKind::Function => section.functions.add(is_bound),
+                    ItemKind::Type => section.types.add(is_bound),
+                    ItemKind::Enum => section.enums.add(is_bound),
+                }
+                if let Some(step) = &unbound_step {
+                    *section.unbound_by_step.entry(step.clone()).or_default() += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Returns the percentage of parsed items available in the Rust API.
+    pub fn percentage(&self) -> f64 {
+        self.total.parsed().percentage()
+    }
+
+    /// Formats the report as a Markdown document.
+    pub fn to_markdown(&self) -> String {
+        let mut text = String::new();
+        let total = self.total.parsed();
+        writeln!(text, "# API coverage of {}\n", self.crate_name).unwrap();
+        writeln!(
+            text,
+            "{} of {} parsed C++ items ({:.1}%) are available in the Rust API.\n",
+            total.bound,
+            total.total(),
+            total.percentage()
+        )
+        .unwrap();
+        writeln!(text, "| Kind | Bound | Unbound | Coverage |").unwrap();
+        writeln!(text, "|---|---|---|---|").unwrap();
+        for (title, counts) in &[
+            ("Functions", self.total.functions),
+            ("Types", self.total.types),
+            ("Enums", self.total.enums),
+            ("Synthesized", self.total.synthesized),
+        ] {
+            writeln!(
+                text,
+                "| {} | {} | {} | {:.1}% |",
+                title,
+                counts.bound,
+                counts.unbound,
+                counts.percentage()
+            )
+            .unwrap();
+        }
+        writeln!(
+            text,
+            "\nSynthesized items (implicit methods, variants with omitted arguments, \
+             inherited methods and template instantiations) are not included in the total. \
+             Templates ({}) are not counted either.\n",
+            self.total.templates
+        )
+        .unwrap();
+
+        if !self.total.unbound_by_step.is_empty() {
+            writeln!(text, "## Unbound items by step\n").unwrap();
+            for (step, count) in &self.total.unbound_by_step {
+                writeln!(text, "- {}: {}", step, count).unwrap();
+            }
+            writeln!(text).unwrap();
+        }
+
+        if !self.rejections.is_empty() {
+            writeln!(text, "## Rejections\n").unwrap();
+            for (step, reasons) in &self.rejections {
+                writeln!(text, "### {} ({})\n", step, reasons.values().sum::<usize>()).unwrap();
+                let sorted_reasons = reasons
+                    .iter()
+                    .sorted_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)))
+                    .collect_vec();
+                for (reason, count) in sorted_reasons.iter().take(MAX_LISTED_REASONS) {
+                    writeln!(text, "- {}: {}", reason, count).unwrap();
+                }
+                if sorted_reasons.len() > MAX_LISTED_REASONS {
+                    writeln!(
+                        text,
+                        "- ({} other reasons)",
+                        sorted_reasons.len() - MAX_LISTED_REASONS
+                    )
+                    .unwrap();
+                }
+                writeln!(text).unwrap();
+            }
+        }
+
+        for (title, sections) in &[("Headers", &self.headers), ("Classes", &self.classes)] {
+            if sections.is_empty() {
+                continue;
+            }
+            writeln!(text, "## {}\n", title).unwrap();
+            writeln!(text, "| Name | Bound | Unbound | Coverage | Synthesized |").unwrap();
+            writeln!(text, "|---|---|---|---|---|").unwrap();
+            for (name, section) in sections.iter() {
+                let counts = section.parsed();
+                writeln!(
+                    text,
+                    "| `{}` | {} | {} | {:.1}% | {}/{} |",
+                    name,
+                    counts.bound,
+                    counts.unbound,
+                    counts.percentage(),
+                    section.synthesized.bound,
+                    section.synthesized.total()
+                )
+                .unwrap();
+            }
+            writeln!(text).unwrap();
+        }
+        text
+    }
+}
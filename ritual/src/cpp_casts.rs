@@ -1,6 +1,7 @@
-use crate::cpp_data::{CppBaseSpecifier, CppItem, CppPath, CppPathItem};
+use crate::cpp_data::{CppBaseSpecifier, CppItem, CppPath, CppPathItem, CppVisibility};
 use crate::cpp_ffi_data::CppCast;
 use crate::cpp_function::{CppFunction, CppFunctionArgument};
+use crate::cpp_inheritance::is_polymorphic;
 use crate::cpp_type::{CppPointerLikeTypeKind, CppType};
 use crate::database::ItemWithSource;
 use crate::processor::ProcessorData;
@@ -27,6 +28,10 @@ fn create_cast_method(cast: CppCast, from: &CppType, to: &CppType) -> Result<Cpp
         allows_variadic_arguments: false,
         declaration_code: None,
         cast: Some(cast),
+        is_noexcept: false,
+        deprecated: None,
+        doc_comment: None,
+        is_extern_c: false,
     };
     Ok(CppItem::Function(function))
 }
@@ -34,11 +39,15 @@ fn create_cast_method(cast: CppCast, from: &CppType, to: &CppType) -> Result<Cpp
 /// Performs a portion of `generate_casts` operation.
 /// Adds casts between `target_type` and `base_type` and calls
 /// `generate_casts_one` recursively to add casts between `target_type`
-/// and base types of `base_type`.
+/// and public base types of `base_type`.
+/// `is_virtual_base` should be true if `base_type` is reachable
+/// through a virtual base. `static_cast` from a virtual base
+/// to a derived class is not allowed, so only `dynamic_cast` is added in this case.
 fn generate_casts_one(
     target_type: &CppPath,
     base_type: &CppPath,
     direct_base_index: Option<usize>,
+    is_virtual_base: bool,
     data: &ProcessorData<'_>,
 ) -> Result<Vec<CppItem>> {
     let target_ptr_type = CppType::PointerLike {
@@ -51,32 +60,43 @@ fn generate_casts_one(
         kind: CppPointerLikeTypeKind::Pointer,
         target: Box::new(CppType::Class(base_type.clone())),
     };
-    let mut new_methods = vec![
-        create_cast_method(
+    let mut new_methods = Vec::new();
+    if !is_virtual_base {
+        new_methods.push(create_cast_method(
             CppCast::Static {
                 is_unsafe: true,
                 base_index: direct_base_index,
             },
             &base_ptr_type,
             &target_ptr_type,
-        )?,
-        create_cast_method(
-            CppCast::Static {
-                is_unsafe: false,
-                base_index: direct_base_index,
-            },
-            &target_ptr_type,
+        )?);
+    }
+    new_methods.push(create_cast_method(
+        CppCast::Static {
+            is_unsafe: false,
+            base_index: direct_base_index,
+        },
+        &target_ptr_type,
+        &base_ptr_type,
+    )?);
+    // `dynamic_cast` doesn't compile for non-polymorphic types
+    if is_polymorphic(base_type, data) {
+        new_methods.push(create_cast_method(
+            CppCast::Dynamic,
             &base_ptr_type,
-        )?,
-        create_cast_method(CppCast::Dynamic, &base_ptr_type, &target_ptr_type)?,
-    ];
+            &target_ptr_type,
+        )?);
+    }
 
-    for item in data.db.all_cpp_items().filter_map(|i| i.item.as_base_ref()) {
-        if &item.derived_class_type == base_type {
+    for item in data.db.all_cpp_bases_of(base_type) {
+        let item = item.item;
+        // conversion to a non-public base is not accessible
+        if item.visibility == CppVisibility::Public {
             new_methods.extend(generate_casts_one(
                 target_type,
                 &item.base_class_type,
                 None,
+                is_virtual_base || item.is_virtual,
                 data,
             )?);
         }
@@ -88,10 +108,23 @@ fn generate_casts_one(
 /// Adds `static_cast` and `dynamic_cast` functions for all appropriate pairs of types
 /// in this `CppData`.
 fn generate_casts(base: &CppBaseSpecifier, data: &ProcessorData<'_>) -> Result<Vec<CppItem>> {
+    if base.visibility != CppVisibility::Public {
+        return Ok(Vec::new());
+    }
+    // non-public bases are skipped, so that the first public base gets `Deref`
+    let public_base_index = data
+        .db
+        .all_cpp_bases_of(&base.derived_class_type)
+        .filter(|other| {
+            other.item.visibility == CppVisibility::Public
+                && other.item.base_index < base.base_index
+        })
+        .count();
     generate_casts_one(
         &base.derived_class_type,
         &base.base_class_type,
-        Some(base.base_index),
+        Some(public_base_index),
+        base.is_virtual,
         data,
     )
 }
@@ -1,38 +1,114 @@
 use crate::config::Config;
-use crate::cpp_checks::CppChecksItem;
+use crate::cpp_checks::{CppCheckFailure, CppChecksItem};
 use crate::cpp_code_generator::{all_include_directives, write_include_directives};
 use crate::cpp_data::{CppItem, CppPath};
 use crate::cpp_ffi_data::CppFfiItem;
+use crate::cpp_ffi_generator::stable_hash;
 use crate::cpp_type::CppType;
-use crate::database::{DatabaseClient, DbItem, ItemId};
+use crate::database::{DatabaseClient, DbItem, ItemId, Rejection};
 use crate::processor::ProcessorData;
 use crate::{cluster_api, cpp_code_generator};
 use itertools::Itertools;
-use log::{debug, error, info, trace};
-use rayon::iter::ParallelIterator;
+use log::{debug, info, trace};
+use rayon::iter::{IndexedParallelIterator, ParallelIterator};
 use rayon::slice::ParallelSliceMut;
 use ritual_common::cpp_build_config::{CppBuildConfigData, CppBuildPaths};
 use ritual_common::cpp_lib_builder::{
-    BuildType, CMakeConfigData, CppLibBuilder, CppLibBuilderOutput,
+    BuildType, CMakeConfigData, CMakeVar, CppLibBuilder, CppLibBuilderOutput,
 };
 use ritual_common::errors::{bail, err_msg, Result};
 use ritual_common::file_utils::{
-    copy_recursively, create_dir_all, create_file, os_str_to_str, remove_dir_all,
+    copy_recursively, create_dir_all, create_file, os_str_to_str, path_to_str, remove_dir_all,
 };
 use ritual_common::target::{current_target, LibraryTarget};
-use ritual_common::utils::ProgressBar;
+use ritual_common::utils::{AbnormalTermination, CommandOutput, ProgressBar};
 use serde_derive::{Deserialize, Serialize};
-use std::collections::{hash_map::Entry, HashMap};
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+use std::env;
 use std::io::Write;
 use std::iter::once;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread::ThreadId;
-use std::time::Instant;
-use std::{iter, thread};
+use std::time::{Duration, Instant};
+use std::{iter, mem, thread};
 
 pub const CHUNK_SIZE: usize = 64;
 
+/// Messages printed when the compiler crashes
+const COMPILER_CRASH_MESSAGES: &[&str] = &[
+    "internal compiler error",
+    "clang frontend command failed",
+    "PLEASE submit a bug report",
+];
+
+/// Returns the reason of `output`'s failure if it wasn't caused
+/// by the checked code itself.
+fn abnormal_failure(output: &CppLibBuilderOutput) -> Option<CppCheckFailure> {
+    let output = match output {
+        CppLibBuilderOutput::Success => return None,
+        CppLibBuilderOutput::Fail(output) => output,
+    };
+    match output.abnormal_termination {
+        Some(AbnormalTermination::Timeout) => Some(CppCheckFailure::Timeout),
+        Some(AbnormalTermination::Signal) => Some(CppCheckFailure::CompilerCrash),
+        None => {
+            if COMPILER_CRASH_MESSAGES
+                .iter()
+                .any(|message| output.stderr.contains(message) || output.stdout.contains(message))
+            {
+                Some(CppCheckFailure::CompilerCrash)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Returns a short description of the failure of `output`
+/// for the rejection record.
+fn failure_reason(output: &CommandOutput, failure: Option<CppCheckFailure>) -> String {
+    match failure {
+        Some(CppCheckFailure::Timeout) => "check timed out".to_string(),
+        Some(CppCheckFailure::CompilerCrash) => "compiler crashed".to_string(),
+        None => output
+            .stderr
+            .lines()
+            .chain(output.stdout.lines())
+            .find(|line| line.contains("error"))
+            .map_or_else(
+                || format!("check failed with status {}", output.status),
+                |line| format!("check failed: {}", line.trim()),
+            ),
+    }
+}
+
+/// Returns a description of everything besides the snippet itself
+/// that can affect the result of a check.
+fn environment_fingerprint(data: &ProcessorData<'_>) -> Result<String> {
+    let config = data.config;
+    let mut build_paths = data.cpp_build_paths().clone();
+    build_paths.apply_env();
+    Ok(format!(
+        "{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}",
+        all_include_directives(config)?,
+        config.cpp_build_config(),
+        build_paths,
+        config.cpp_parser_arguments(),
+        config.target_triple(),
+        config.sysroot(),
+        data.cpp_lib_version(),
+        env::var("CXX").ok(),
+    ))
+}
+
+/// Returns the hash stored with a check result. Any change of the snippet
+/// or the environment fingerprint results in a different hash.
+pub fn snippet_hash(environment_fingerprint: &str, snippet: &Snippet) -> u64 {
+    stable_hash(&format!("{}\n{:?}", environment_fingerprint, snippet))
+}
+
 fn snippet_for_item(item: DbItem<&CppFfiItem>, database: &DatabaseClient) -> Result<Snippet> {
     match &item.item {
         CppFfiItem::Function(_) => {
@@ -59,14 +135,22 @@ fn snippet_for_item(item: DbItem<&CppFfiItem>, database: &DatabaseClient) -> Res
             item.item.source_item_cpp_code(database)?,
             true,
         )),
+        CppFfiItem::SubclassShim(_) => Ok(Snippet::new_global(
+            item.item.source_item_cpp_code(database)?,
+            false,
+        )),
     }
 }
 
 pub struct CppCheckerInstance {
+    /// Name of the instance's directory, used to tag log messages
+    id: String,
     main_cpp_path: PathBuf,
     crate_name: String,
     builder: CppLibBuilder,
     tests: Vec<PreliminaryTest>,
+    /// Number of programs built by this instance
+    build_count: usize,
 }
 
 impl CppCheckerInstance {
@@ -114,8 +198,9 @@ impl CppCheckerInstance {
         drop(file);
 
         let instant = Instant::now();
+        self.build_count += 1;
         let result = self.builder.run();
-        trace!("cpp builder time: {:?}", instant.elapsed());
+        trace!("[{}] cpp builder time: {:?}", self.id, instant.elapsed());
         result
     }
 
@@ -200,6 +285,10 @@ impl CppCheckerInstance {
 struct CppChecker<'b, 'a> {
     data: &'b mut ProcessorData<'a>,
     force: bool,
+    /// Number of results reused from the previous runs
+    reused_count: usize,
+    /// Set when the user requests to interrupt the processing
+    interrupted: Arc<AtomicBool>,
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
@@ -226,6 +315,7 @@ pub struct SnippetTaskLocalData {
     pub ffi_item_id: ItemId,
     pub crate_name: String,
     pub library_target: LibraryTarget,
+    pub snippet_hash: u64,
 }
 
 pub type LocalSnippetTask = SnippetTask<SnippetTaskLocalData>;
@@ -274,6 +364,8 @@ pub struct LocalCppChecker {
     cpp_build_paths: CppBuildPaths,
     crate_template_path: Option<PathBuf>,
     tests: Vec<PreliminaryTest>,
+    run_programs: bool,
+    timeout: Option<Duration>,
 }
 
 impl LocalCppChecker {
@@ -291,11 +383,41 @@ impl LocalCppChecker {
                 data.apply_env();
                 data
             },
-            cpp_build_config: config.cpp_build_config().eval(&current_target())?,
+            cpp_build_config: {
+                let mut data = config.cpp_build_config().eval(&current_target())?;
+                data.add_compiler_flags(config.cpp_parser_arguments());
+                if let Some(triple) = config.target_triple() {
+                    data.add_cmake_var(CMakeVar::new("CMAKE_CXX_COMPILER_TARGET", triple));
+                }
+                if let Some(sysroot) = config.sysroot() {
+                    data.add_cmake_var(CMakeVar::new("CMAKE_SYSROOT", path_to_str(sysroot)?));
+                }
+                data
+            },
             tests,
+            run_programs: true,
+            timeout: config.cpp_checker_timeout(),
         })
     }
 
+    /// Returns the build configuration used for checked programs.
+    pub fn cpp_build_config(&self) -> &CppBuildConfigData {
+        &self.cpp_build_config
+    }
+
+    /// Sets paths to the C++ library used instead of `Config::cpp_build_paths`.
+    pub fn set_cpp_build_paths(&mut self, paths: &CppBuildPaths) {
+        let mut paths = paths.clone();
+        paths.apply_env();
+        self.cpp_build_paths = paths;
+    }
+
+    /// Sets whether the compiled programs are executed. If disabled,
+    /// a snippet only fails if it can't be compiled or linked.
+    pub fn set_run_programs(&mut self, value: bool) {
+        self.run_programs = value;
+    }
+
     pub fn get(&self, id: &str) -> Result<CppCheckerInstance> {
         let root_path = self.parent_path.join(id);
         if root_path.exists() {
@@ -333,25 +455,34 @@ impl LocalCppChecker {
             cpp_build_paths: &self.cpp_build_paths,
             library_type: None,
             cpp_library_version: None,
+            target: None,
         };
 
+        let mut cmake_vars = cmake_config.cmake_vars()?;
+        if !self.run_programs {
+            cmake_vars.push(CMakeVar::new("RITUAL_CHECKER_NO_RUN", "1"));
+        }
+
         let builder = CppLibBuilder {
             cmake_source_dir: src_path.clone(),
             build_dir: root_path.join("build"),
             install_dir: None,
             num_jobs: Some(1),
             build_type: BuildType::Debug,
-            cmake_vars: cmake_config.cmake_vars()?,
+            cmake_vars,
             capture_output: true,
             skip_cmake: false,
             skip_cmake_after_first_run: true,
+            timeout: self.timeout,
         };
 
         Ok(CppCheckerInstance {
+            id: id.to_string(),
             builder,
             main_cpp_path: src_path.join("main.cpp"),
             crate_name: self.crate_name.clone(),
             tests: self.tests.clone(),
+            build_count: 0,
         })
     }
 }
@@ -380,6 +511,16 @@ impl InstanceStorage {
         };
         Ok(Arc::clone(instance))
     }
+
+    /// Returns the total number of programs built by all instances.
+    fn build_count(&self) -> usize {
+        self.instances
+            .lock()
+            .unwrap()
+            .values()
+            .map(|instance| instance.lock().unwrap().build_count)
+            .sum()
+    }
 }
 
 fn builtin_tests() -> Vec<PreliminaryTest> {
@@ -434,7 +575,7 @@ impl CppChecker<'_, '_> {
     fn env(&self) -> LibraryTarget {
         LibraryTarget {
             target: current_target(),
-            cpp_library_version: self.data.config.cpp_lib_version().map(ToString::to_string),
+            cpp_library_version: self.data.cpp_lib_version().map(ToString::to_string),
         }
     }
 
@@ -474,7 +615,11 @@ impl CppChecker<'_, '_> {
             return Ok(());
         }
 
-        cluster_api::run_checks(cluster_config, &mut snippets)?;
+        cluster_api::run_checks(
+            cluster_config,
+            &mut snippets,
+            self.data.config.cpp_checker_batch_size(),
+        )?;
 
         self.save_results(snippets)?;
 
@@ -482,10 +627,11 @@ impl CppChecker<'_, '_> {
     }
 
     fn run_local(&mut self) -> Result<()> {
-        let instance_provider = LocalCppChecker::new(
+        let mut instance_provider = LocalCppChecker::new(
             self.data.workspace.tmp_path().join("cpp_checker"),
             &self.data.config,
         )?;
+        instance_provider.set_cpp_build_paths(self.data.cpp_build_paths());
 
         let env = self.env();
 
@@ -499,46 +645,133 @@ impl CppChecker<'_, '_> {
         let mut instance = instance_provider.get("tests")?;
         instance.check_preliminary_tests()?;
 
-        let progress_bar = ProgressBar::new(snippets.len() as u64, "Checking items");
-
+        let total_count = snippets.len();
+        let progress_bar = ProgressBar::new(total_count as u64, "Checking items");
         let instances = InstanceStorage::new(instance_provider);
+        let started_time = Instant::now();
+
+        let result = self.check_tasks(snippets, |batch_index, chunk| {
+            let instance = instances.current()?;
+            let mut instance = instance.lock().unwrap();
+            trace!(
+                "[{}] checking batch {} ({} items)",
+                instance.id,
+                batch_index,
+                chunk.len()
+            );
+            instance.binary_check(chunk, Some(&progress_bar))
+        });
 
-        snippets
-            .par_chunks_mut(CHUNK_SIZE)
-            .map(|chunk| {
-                let progress_bar = progress_bar.clone();
-                let instance = instances.current()?;
-                let mut instance = instance.lock().unwrap();
-                instance.binary_check(chunk, Some(&progress_bar))
-            })
-            .collect::<Result<_>>()?;
-        self.save_results(snippets)?;
-
+        let processed_count = result?;
+        let build_count = instances.build_count();
+        let elapsed = started_time.elapsed();
+        info!(
+            "Checked {} items with {} builds ({:.1} items per build) in {:?}",
+            processed_count,
+            build_count,
+            processed_count as f64 / build_count.max(1) as f64,
+            elapsed
+        );
+        if self.reused_count > 0 && processed_count == total_count {
+            let time_per_item = elapsed.as_secs_f64() / total_count as f64;
+            info!(
+                "Reusing {} results saved approximately {:?}",
+                self.reused_count,
+                Duration::from_secs_f64(time_per_item * self.reused_count as f64)
+            );
+        }
         Ok(())
     }
 
-    fn create_tasks(&self, library_targets: &[LibraryTarget]) -> Result<Vec<LocalSnippetTask>> {
+    /// Checks `snippets` by calling `check_batch` for each batch concurrently.
+    /// `check_batch` receives the batch index and must set outputs of the tasks.
+    ///
+    /// Items are checked in groups, and the results are saved after each group,
+    /// so that an interrupted run can be resumed without checking them again.
+    /// Results of completed batches are saved even if another batch fails
+    /// or the interruption flag is set.
+    ///
+    /// Returns the number of checked items.
+    fn check_tasks(
+        &mut self,
+        mut snippets: Vec<LocalSnippetTask>,
+        check_batch: impl Fn(usize, &mut [LocalSnippetTask]) -> Result<()> + Sync,
+    ) -> Result<usize> {
+        let mut thread_pool = rayon::ThreadPoolBuilder::new();
+        if let Some(count) = self.data.config.cpp_checker_job_count() {
+            thread_pool = thread_pool.num_threads(count);
+        }
+        let thread_pool = thread_pool.build()?;
+
+        let batch_size = self.data.config.cpp_checker_batch_size();
+        let checkpoint_interval = self.data.config.checkpoint_interval();
+        let total_count = snippets.len();
+        let mut processed_count = 0;
+        let checked_count = AtomicUsize::new(0);
+        let progress = self.data.progress.clone();
+        let interrupted = Arc::clone(&self.interrupted);
+        let is_interrupted = || interrupted.load(Ordering::SeqCst);
+        while !snippets.is_empty() {
+            let other_snippets = snippets.split_off(checkpoint_interval.min(snippets.len()));
+            let mut group = mem::replace(&mut snippets, other_snippets);
+            let result = thread_pool.install(|| {
+                group
+                    .par_chunks_mut(batch_size)
+                    .enumerate()
+                    .map(|(batch_index, chunk)| {
+                        if is_interrupted() {
+                            return Ok(());
+                        }
+                        let result = check_batch(batch_index, chunk);
+                        if is_interrupted() {
+                            // the compiler received the signal too,
+                            // so results of this batch can't be trusted
+                            for task in &mut *chunk {
+                                task.output = None;
+                            }
+                            return result;
+                        }
+                        let checked = checked_count.fetch_add(chunk.len(), Ordering::SeqCst);
+                        progress.items(
+                            (checked + chunk.len()) as u64,
+                            Some(total_count as u64),
+                            chunk.first().map(|task| task.data.ffi_item_id.to_string()),
+                        );
+                        result
+                    })
+                    .collect::<Result<()>>()
+            });
+            processed_count += group.iter().filter(|task| task.output.is_some()).count();
+            // results of completed batches are saved even if some of the batches failed
+            self.save_results(group)?;
+            result?;
+            if is_interrupted() {
+                info!("Interrupted. Results of completed batches are saved.");
+                bail!("cpp_checker was interrupted");
+            }
+            if !snippets.is_empty() {
+                self.data.checkpoint(processed_count)?;
+            }
+        }
+        Ok(processed_count)
+    }
+
+    fn create_tasks(&mut self, library_targets: &[LibraryTarget]) -> Result<Vec<LocalSnippetTask>> {
         let crate_name = self.data.db.crate_name().to_string();
+        let fingerprint = environment_fingerprint(self.data)?;
 
         let mut snippets = Vec::new();
         let mut old_items_count = 0;
 
         for ffi_item in self.data.db.ffi_items() {
-            let checks = if self.force {
-                None
-            } else {
-                let checks = self.data.db.cpp_checks(&ffi_item.id)?;
-                if checks.has_all_envs(library_targets) {
-                    old_items_count += 1;
-                    continue;
-                }
-                Some(checks)
-            };
+            let checks = self.data.db.cpp_checks(&ffi_item.id)?;
 
             match snippet_for_item(ffi_item.clone(), &self.data.db) {
                 Ok(snippet) => {
+                    let snippet_hash = snippet_hash(&fingerprint, &snippet);
                     for library_target in library_targets {
-                        if !self.force && checks.as_ref().unwrap().has_env(library_target) {
+                        if !self.force && checks.is_up_to_date(library_target, snippet_hash) {
+                            old_items_count += 1;
                             continue;
                         }
                         snippets.push(SnippetTask {
@@ -546,6 +779,7 @@ impl CppChecker<'_, '_> {
                                 ffi_item_id: ffi_item.id.clone(),
                                 crate_name: crate_name.clone(),
                                 library_target: library_target.clone(),
+                                snippet_hash,
                             },
                             snippet: snippet.clone(),
                             output: None,
@@ -567,14 +801,15 @@ impl CppChecker<'_, '_> {
                 info!("Checking {} items", snippets.len());
             }
         } else if snippets.is_empty() {
-            info!("Ignoring {} old items", old_items_count);
+            info!("Reusing {} up-to-date results", old_items_count);
         } else {
             info!(
-                "Checking {} items, ignoring {} old items",
+                "Checking {} items, reusing {} up-to-date results",
                 snippets.len(),
                 old_items_count
             );
         }
+        self.reused_count = old_items_count;
 
         Ok(snippets)
     }
@@ -582,6 +817,21 @@ impl CppChecker<'_, '_> {
     fn save_results(&mut self, snippets: Vec<LocalSnippetTask>) -> Result<()> {
         let mut success_count = 0;
         let mut error_count = 0;
+        let mut unchecked_count = 0;
+        let mut abnormal_failures = Vec::new();
+
+        // rejections of all checked items are cleared at once
+        let checked_names = snippets
+            .iter()
+            .filter(|snippet| snippet.output.is_some())
+            .map(|snippet| {
+                let ffi_item = self.data.db.ffi_item(&snippet.data.ffi_item_id)?;
+                Ok(ffi_item.item.short_text())
+            })
+            .collect::<Result<HashSet<_>>>()?;
+        self.data
+            .db
+            .clear_item_rejections("cpp_checker", &checked_names);
 
         for snippet in snippets {
             let ffi_item = self.data.db.ffi_item_mut(&snippet.data.ffi_item_id)?;
@@ -594,15 +844,26 @@ impl CppChecker<'_, '_> {
                     debug!("error: {} {}: {:?}", ffi_item.id, short_text, output);
                     error_count += 1;
                 }
+                let failure = abnormal_failure(&output);
+                if let Some(failure) = failure {
+                    abnormal_failures.push((failure, short_text.clone()));
+                }
                 let ffi_item_id = ffi_item.id;
                 let ffi_item_source_id = ffi_item.source_id;
                 let env = snippet.data.library_target;
+                if let CppLibBuilderOutput::Fail(output) = &output {
+                    self.data.db.add_rejection(Rejection {
+                        cpp_name: short_text.clone(),
+                        step: "cpp_checker".to_string(),
+                        reason: failure_reason(output, failure),
+                        include_file: None,
+                    });
+                }
 
-                if self.force {
-                    let old_checks = self.data.db.cpp_checks(&ffi_item_id)?;
-                    if old_checks.has_env(&env)
-                        && old_checks.is_success(&env) != output.is_success()
-                    {
+                // the old result is either outdated or rechecked by force
+                let old_checks = self.data.db.cpp_checks(&ffi_item_id)?;
+                if old_checks.has_env(&env) {
+                    if old_checks.is_success(&env) != output.is_success() {
                         let source_text = ffi_item_source_id
                             .as_ref()
                             .and_then(|id| self.data.db.item(id).ok())
@@ -622,24 +883,27 @@ impl CppChecker<'_, '_> {
                             short_text,
                             source_text
                         );
-                        self.data.db.delete_items(|item| {
-                            item.source_id.as_ref() == Some(&ffi_item_id)
-                                && item
-                                    .item
-                                    .as_cpp_checks_item()
-                                    .map_or(false, |item| item.env == env)
-                        });
                     }
+                    self.data.db.delete_items(|item| {
+                        item.source_id.as_ref() == Some(&ffi_item_id)
+                            && item
+                                .item
+                                .as_cpp_checks_item()
+                                .map_or(false, |item| item.env == env)
+                    });
                 }
                 self.data.db.add_cpp_checks_item(
                     ffi_item_id,
                     CppChecksItem {
                         env,
                         is_success: output.is_success(),
+                        snippet_hash: Some(snippet.data.snippet_hash),
+                        failure,
                     },
                 );
             } else {
-                error!("no output for item: {}", ffi_item.item.short_text());
+                trace!("no output for item: {}", ffi_item.item.short_text());
+                unchecked_count += 1;
             }
             trace!("snippet: {:?}", snippet.snippet);
         }
@@ -648,17 +912,59 @@ impl CppChecker<'_, '_> {
             "Success: {} items; error: {} items",
             success_count, error_count
         );
+        if unchecked_count > 0 {
+            self.data
+                .progress
+                .warning(format!("{} items were not checked", unchecked_count));
+        }
+        for (failure, short_text) in abnormal_failures {
+            self.data
+                .progress
+                .warning(format!("{:?}: {}", failure, short_text));
+        }
 
         Ok(())
     }
 }
 
+/// Checks FFI items. If the interruption flag of the config is set
+/// while items are checked, the remaining batches are cancelled, and
+/// results of completed batches are saved.
 pub fn run(data: &mut ProcessorData<'_>, force: bool) -> Result<()> {
-    let mut checker = CppChecker { data, force };
+    let interrupted = data.config.interruption_flag().cloned().unwrap_or_default();
+    let mut checker = CppChecker {
+        data,
+        force,
+        reused_count: 0,
+        interrupted,
+    };
     checker.run()?;
     Ok(())
 }
 
+/// Checks FFI items of the current environment by calling `check_batch`
+/// for each batch instead of building them. `check_batch` receives
+/// the batch index and must set outputs of the tasks.
+/// Remaining batches are cancelled when `interrupted` is set.
+pub fn run_with(
+    data: &mut ProcessorData<'_>,
+    force: bool,
+    interrupted: Arc<AtomicBool>,
+    check_batch: impl Fn(usize, &mut [LocalSnippetTask]) -> Result<()> + Sync,
+) -> Result<()> {
+    let mut checker = CppChecker {
+        data,
+        force,
+        reused_count: 0,
+        interrupted,
+    };
+    let env = checker.env();
+    checker.data.db.add_environment(env.clone());
+    let snippets = checker.create_tasks(&[env])?;
+    checker.check_tasks(snippets, check_batch)?;
+    Ok(())
+}
+
 fn type_paths(type1: &CppType) -> Vec<&CppPath> {
     match type1 {
         CppType::Void
@@ -674,6 +980,7 @@ fn type_paths(type1: &CppType) -> Vec<&CppPath> {
             .flat_map(|type1| type_paths(type1))
             .collect(),
         CppType::PointerLike { target, .. } => type_paths(target),
+        CppType::Array { element_type, .. } => type_paths(element_type),
     }
 }
 
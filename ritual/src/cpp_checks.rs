@@ -1,11 +1,27 @@
 use itertools::Itertools;
-use ritual_common::target::{Arch, Endian, Env, Family, LibraryTarget, PointerWidth, OS};
+use ritual_common::target::{Arch, Endian, Env, Family, LibraryTarget, PointerWidth, Target, OS};
 use serde_derive::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CppChecksItem {
     pub env: LibraryTarget,
     pub is_success: bool,
+    /// Hash of the checked snippet and the build environment it was checked in.
+    /// A result is only reused if the hash is still the same.
+    #[serde(default)]
+    pub snippet_hash: Option<u64>,
+    /// Reason of the failure if it wasn't caused by a compilation error
+    #[serde(default)]
+    pub failure: Option<CppCheckFailure>,
+}
+
+/// Failure of a check that prevented the compiler from reporting a result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CppCheckFailure {
+    /// The build didn't finish in time
+    Timeout,
+    /// The compiler crashed
+    CompilerCrash,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -31,6 +47,14 @@ impl CppChecks {
             .map_or(false, |item| item.is_success)
     }
 
+    /// Returns true if there is a result for `env` that was obtained
+    /// for the snippet with `snippet_hash`.
+    pub fn is_up_to_date(&self, env: &LibraryTarget, snippet_hash: u64) -> bool {
+        self.0
+            .iter()
+            .any(|item| &item.env == env && item.snippet_hash == Some(snippet_hash))
+    }
+
     pub fn has_all_envs(&self, environments: &[LibraryTarget]) -> bool {
         environments.iter().all(|env| self.has_env(env))
     }
@@ -54,6 +78,11 @@ impl CppChecks {
         })
     }
 
+    /// Returns abnormal failures of the checks.
+    pub fn failures(&self) -> impl Iterator<Item = CppCheckFailure> + '_ {
+        self.0.iter().filter_map(|item| item.failure)
+    }
+
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
@@ -65,6 +94,13 @@ impl CppChecks {
         if self.all_success(environments) {
             return Condition::True;
         }
+        // databases merged from different platforms
+        if environments
+            .iter()
+            .any(|env| env.target != environments[0].target)
+        {
+            return Condition::Or(self.successful_envs().map(env_condition).collect());
+        }
         if self
             .0
             .iter()
@@ -103,9 +139,21 @@ impl CppChecks {
     }
 }
 
+/// Returns the condition that is true only in `env`.
+fn env_condition(env: &LibraryTarget) -> Condition {
+    let target = Condition::Target(env.target.clone());
+    if let Some(version) = &env.cpp_library_version {
+        Condition::And(vec![target, Condition::CppLibraryVersion(version.clone())])
+    } else {
+        target
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Condition {
     CppLibraryVersion(String),
+    /// All properties of the target are the same
+    Target(Target),
     Arch(Arch),
     OS(OS),
     Family(Family),
@@ -1,21 +1,27 @@
 use crate::config::Config;
 use crate::cpp_checks::Condition;
 use crate::cpp_ffi_data::{
-    CppFfiArgumentMeaning, CppFfiFunctionKind, CppFfiType, CppFieldAccessorType,
-    CppToFfiTypeConversion, QtSignalWrapper, QtSlotWrapper,
+    CppCallbackUnregisterArgument, CppClosureCallback, CppFfiArgumentMeaning, CppFfiFunctionKind,
+    CppFfiType, CppFieldAccessorType, CppSubclassShim, CppToFfiTypeConversion, QtSignalWrapper,
+    QtSlotWrapper,
 };
 use crate::cpp_ffi_data::{CppFfiFunction, CppFfiItem};
+use crate::cpp_ffi_generator::stable_hash;
 use crate::cpp_function::{CppFunction, ReturnValueAllocationPlace};
 use crate::cpp_type::CppPointerLikeTypeKind;
-use crate::cpp_type::CppType;
-use crate::database::{DatabaseClient, DbItem};
+use crate::cpp_type::{CppFunctionPointerType, CppType};
+use crate::database::{DatabaseClient, DbItem, ItemId};
 use crate::rust_info::{RustItem, RustStructKind};
+use crate::type_allocation_places::layout_conditions;
 use itertools::Itertools;
+use log::warn;
 use ritual_common::cpp_lib_builder::version_to_number;
 use ritual_common::errors::{bail, err_msg, format_err, Result};
 use ritual_common::file_utils::{create_file, os_str_to_str, path_to_str, read_dir};
+use ritual_common::target::cpp_definition;
 use ritual_common::utils::MapIfOk;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
 use std::io::Write;
 use std::iter::once;
 use std::path::{Path, PathBuf};
@@ -31,13 +37,10 @@ impl Generator<'_> {
             arg_texts.push(arg.to_cpp_code()?);
         }
         let name_with_args = format!("{}({})", method.path.to_cpp_code()?, arg_texts.join(", "));
-        let return_type = method.return_type.ffi_type();
-        let r = if let CppType::FunctionPointer(..) = return_type {
-            return_type.to_cpp_code(Some(&name_with_args))?
-        } else {
-            format!("{} {}", return_type.to_cpp_code(None)?, name_with_args)
-        };
-        Ok(r)
+        method
+            .return_type
+            .ffi_type()
+            .to_cpp_declaration(&name_with_args)
     }
 
     /// Generates code for a Qt slot wrapper
@@ -66,7 +69,7 @@ impl Generator<'_> {
             include_str!("../templates/c_lib/qt_slot_wrapper.h"),
             class_name = wrapper.class_path.to_cpp_code()?,
             callback_arg = func_type.to_cpp_code(Some("callback"))?,
-            callback_type = func_type.to_cpp_code(Some(""))?,
+            callback_type = func_type.to_cpp_code(None)?,
             method_args = method_args,
             func_args = func_args
         ))
@@ -96,6 +99,214 @@ impl Generator<'_> {
         ))
     }
 
+    /// Generates code for a subclass shim
+    fn subclass_shim(&self, shim: &CppSubclassShim) -> Result<String> {
+        let class_name = shim.class_path.to_cpp_code()?;
+        let base_class = shim.base_class_path.to_cpp_code()?;
+        let mut constructors = String::new();
+        for constructor in &shim.constructors {
+            let args = constructor
+                .arguments
+                .iter()
+                .enumerate()
+                .map_if_ok(|(num, arg)| {
+                    arg.argument_type.to_cpp_code(Some(&format!("arg{}", num)))
+                })?
+                .join(", ");
+            let arg_names = (0..constructor.arguments.len())
+                .map(|num| format!("arg{}", num))
+                .join(", ");
+            constructors.push_str(&format!(
+                "    {}({}) : {}({}) {{}}\n",
+                class_name, args, base_class, arg_names
+            ));
+        }
+
+        let mut functions = String::new();
+        let mut fields = String::new();
+        for (index, function) in shim.functions.iter().enumerate() {
+            let func_type = CppType::FunctionPointer(function.function_type.clone());
+            let field = format!("m_callback{}", index);
+            let cpp_name = function.function.path.last().name.clone();
+            let member = function
+                .function
+                .member
+                .as_ref()
+                .ok_or_else(|| err_msg("subclass shim function is not a class member"))?;
+            let const_text = if member.is_const { " const" } else { "" };
+            let args = function
+                .function
+                .arguments
+                .iter()
+                .enumerate()
+                .map_if_ok(|(num, arg)| {
+                    arg.argument_type.to_cpp_code(Some(&format!("arg{}", num)))
+                })?
+                .join(", ");
+            let arg_names = (0..function.function.arguments.len())
+                .map(|num| format!("arg{}", num))
+                .join(", ");
+            let callback_args = once(format!("{}.data()", field))
+                .chain(
+                    function
+                        .arguments
+                        .iter()
+                        .enumerate()
+                        .map_if_ok(|(num, t)| self.convert_type_to_ffi(t, format!("arg{}", num)))?,
+                )
+                .join(", ");
+            let callback_call = format!("callback({})", callback_args);
+            let return_type = function.return_type.original_type();
+            let call_text = if return_type.is_void() {
+                format!("{};\n            return;", callback_call)
+            } else {
+                match function.return_type.conversion() {
+                    CppToFfiTypeConversion::NoChange => format!("return {};", callback_call),
+                    CppToFfiTypeConversion::QFlagsToInt => format!(
+                        "return {}({});",
+                        return_type.to_cpp_code(None)?,
+                        callback_call
+                    ),
+                    CppToFfiTypeConversion::ValueToPointer { .. } => {
+                        let type_text = return_type.to_cpp_code(None)?;
+                        format!(
+                            "{type}* result = {call};\n            \
+                             {type} value(std::move(*result));\n            \
+                             delete result;\n            \
+                             return value;",
+                            type = type_text,
+                            call = callback_call
+                        )
+                    }
+                    other => bail!("unsupported return type conversion: {:?}", other),
+                }
+            };
+            let fallback = if member.is_pure_virtual {
+                format!("ritual::pure_virtual_called(\"{}\");", cpp_name)
+            } else {
+                format!("return {}::{}({});", base_class, cpp_name, arg_names)
+            };
+
+            functions.push_str(&format!(
+                "    void set_{name}_override({callback_arg}, void (*deleter)(void*), void* data) {{\n        \
+                 {field}.set(callback, deleter, data);\n    \
+                 }}\n",
+                name = function.name,
+                callback_arg = func_type.to_cpp_code(Some("callback"))?,
+                field = field
+            ));
+            let declaration = return_type.to_cpp_declaration(&format!("{}({})", cpp_name, args))?;
+            functions.push_str(&format!(
+                "    {declaration}{const_text} override {{\n        \
+                 auto callback = {field}.get();\n        \
+                 if (callback) {{\n            \
+                 {call_text}\n        \
+                 }}\n        \
+                 {fallback}\n    \
+                 }}\n",
+                declaration = declaration,
+                const_text = const_text,
+                field = field,
+                call_text = call_text,
+                fallback = fallback
+            ));
+            if !member.is_pure_virtual {
+                let base_declaration =
+                    return_type.to_cpp_declaration(&format!("base_{}({})", function.name, args))?;
+                functions.push_str(&format!(
+                    "    {}{} {{\n        return {}::{}({});\n    }}\n",
+                    base_declaration, const_text, base_class, cpp_name, arg_names
+                ));
+            }
+            fields.push_str(&format!(
+                "    ritual::Callback<{}> {};\n",
+                func_type.to_cpp_code(None)?,
+                field
+            ));
+        }
+
+        // protected members are only accessible inside the subclass,
+        // so the shim exposes them through public forwarding functions
+        for function in &shim.protected_functions {
+            let cpp_name = function.path.last().name.clone();
+            let member = function
+                .member
+                .as_ref()
+                .ok_or_else(|| err_msg("protected function is not a class member"))?;
+            let args = function
+                .arguments
+                .iter()
+                .enumerate()
+                .map_if_ok(|(num, arg)| {
+                    arg.argument_type.to_cpp_code(Some(&format!("arg{}", num)))
+                })?
+                .join(", ");
+            let arg_names = (0..function.arguments.len())
+                .map(|num| format!("arg{}", num))
+                .join(", ");
+            let declaration = function
+                .return_type
+                .to_cpp_declaration(&format!("{}({})", cpp_name, args))?;
+            let (static_text, const_text) = if member.is_static {
+                ("static ", "")
+            } else if member.is_const {
+                ("", " const")
+            } else {
+                ("", "")
+            };
+            functions.push_str(&format!(
+                "    {}{}{} {{\n        return {}::{}({});\n    }}\n",
+                static_text, declaration, const_text, base_class, cpp_name, arg_names
+            ));
+        }
+        for field in &shim.protected_fields {
+            let name = &field.path.last().name;
+            let static_text = if field.is_static { "static " } else { "" };
+            let const_text = if field.is_static { "" } else { " const" };
+            let getter = field
+                .field_type
+                .to_cpp_declaration(&format!("{}()", name))?;
+            functions.push_str(&format!(
+                "    {}{}{} {{\n        return {}::{};\n    }}\n",
+                static_text, getter, const_text, base_class, name
+            ));
+            if !field.is_const {
+                functions.push_str(&format!(
+                    "    {}void set_{}({}) {{\n        {}::{} = value;\n    }}\n",
+                    static_text,
+                    name,
+                    field.field_type.to_cpp_code(Some("value"))?,
+                    base_class,
+                    name
+                ));
+            }
+        }
+
+        Ok(format!(
+            include_str!("../templates/c_lib/subclass_shim.h"),
+            class_name = class_name,
+            base_class = base_class,
+            constructors = constructors,
+            functions = functions,
+            fields = fields
+        ))
+    }
+
+    /// Checks if inherited `function` is brought into its class
+    /// by a `using` declaration, so it can be called through the class.
+    fn is_brought_by_using(&self, function: &CppFunction) -> bool {
+        let class_path = match function.class_path() {
+            Ok(path) => path,
+            Err(_) => return false,
+        };
+        self.0
+            .all_cpp_items()
+            .filter_map(|item| item.item.as_type_ref())
+            .filter(|t| t.path == class_path)
+            .flat_map(|t| t.kind.using_declarations())
+            .any(|u| u.name == function.path.last().name)
+    }
+
     /// Generates code that wraps `expression` of type `type1.original_type` and
     /// converts it to type `type1.ffi_type`
     fn convert_type_to_ffi(&self, type1: &CppFfiType, expression: String) -> Result<String> {
@@ -109,6 +320,9 @@ impl Generator<'_> {
                 expression
             ),
             CppToFfiTypeConversion::ReferenceToPointer => format!("&{}", expression),
+            CppToFfiTypeConversion::RValueReferenceToPointer => {
+                bail!("rvalue references can't be converted to FFI values");
+            }
             CppToFfiTypeConversion::QFlagsToInt => format!("int({})", expression),
         })
     }
@@ -158,6 +372,9 @@ impl Generator<'_> {
             CppToFfiTypeConversion::ReferenceToPointer => {
                 result = format!("&{}", result);
             }
+            CppToFfiTypeConversion::RValueReferenceToPointer => {
+                bail!("rvalue references can't be returned");
+            }
             CppToFfiTypeConversion::QFlagsToInt => {
                 result = format!("int({})", result);
             }
@@ -178,15 +395,21 @@ impl Generator<'_> {
 
     /// Generates code for values passed to the original C++ method.
     fn arguments_values(&self, method: &CppFfiFunction) -> Result<String> {
-        let r = method
+        let mut r = method
             .arguments
             .iter()
-            .filter(|arg| arg.meaning.is_argument())
-            .map_if_ok(|argument| -> Result<_> {
+            .filter_map(|arg| match arg.meaning {
+                CppFfiArgumentMeaning::Argument(index) => Some((index, arg)),
+                _ => None,
+            })
+            .map_if_ok(|(index, argument)| -> Result<_> {
                 let mut result = argument.name.clone();
                 match argument.argument_type.conversion() {
                     CppToFfiTypeConversion::ValueToPointer { .. }
                     | CppToFfiTypeConversion::ReferenceToPointer => result = format!("*{}", result),
+                    CppToFfiTypeConversion::RValueReferenceToPointer => {
+                        result = format!("std::move(*{})", result);
+                    }
                     CppToFfiTypeConversion::NoChange
                     | CppToFfiTypeConversion::ImplicitCast { .. } => {}
                     CppToFfiTypeConversion::QFlagsToInt => {
@@ -207,9 +430,15 @@ impl Generator<'_> {
                         result = format!("{}({})", type_text, result);
                     }
                 }
-                Ok(result)
+                Ok((index, result))
             })?;
-        Ok(r.join(", "))
+        if let CppFfiFunctionKind::ClosureCallback { callback } = &method.kind {
+            // values declared by `closure_callback_body`
+            r.push((callback.callback_index, "&Trampoline::call".to_string()));
+            r.push((callback.user_data_index, "closure".to_string()));
+            r.sort_by_key(|(index, _)| *index);
+        }
+        Ok(r.into_iter().map(|(_, value)| value).join(", "))
     }
 
     /// Generates code for the value returned by the FFI method.
@@ -269,12 +498,32 @@ impl Generator<'_> {
                     err_msg("cpp item (function or field) expected to have a path")
                 })?;
 
+                let inherited_from = cpp_item
+                    .item
+                    .as_function_ref()
+                    .filter(|f| !self.is_brought_by_using(f))
+                    .and_then(|f| Some((f.inherited_from()?, f.member.as_ref()?.is_const)));
+
                 if let Some(arg) = method
                     .arguments
                     .iter()
                     .find(|x| x.meaning == CppFfiArgumentMeaning::This)
                 {
-                    format!("{}->{}", arg.name, path.last().to_cpp_code()?)
+                    if let Some((base, is_const)) = inherited_from {
+                        // the method may be hidden by a method of the derived class,
+                        // so it's called through a pointer to the base class
+                        format!(
+                            "static_cast<{}{}*>({})->{}",
+                            if is_const { "const " } else { "" },
+                            base.to_cpp_code()?,
+                            arg.name,
+                            path.last().to_cpp_code()?
+                        )
+                    } else {
+                        format!("{}->{}", arg.name, path.last().to_cpp_code()?)
+                    }
+                } else if let Some((base, _)) = inherited_from {
+                    base.join(path.last().clone()).to_cpp_code()?
                 } else {
                     path.to_cpp_code()?
                 }
@@ -302,17 +551,40 @@ impl Generator<'_> {
 
     /// Generates body of the FFI method implementation.
     fn source_body(&self, item: DbItem<&CppFfiFunction>) -> Result<String> {
-        let cpp_item = self
-            .0
-            .source_cpp_item(&item.id)?
-            .ok_or_else(|| format_err!("failed to find original cpp item for {:?}", item))?;
+        let cpp_item = self.0.source_cpp_item(&item.id)?;
 
+        let method = item.item;
+        match &method.kind {
+            CppFfiFunctionKind::StreamOutput => {
+                let function = cpp_item
+                    .as_ref()
+                    .and_then(|item| item.item.as_function_ref())
+                    .ok_or_else(|| err_msg("stream output must have a source function"))?;
+                return self.stream_output_body(function);
+            }
+            CppFfiFunctionKind::StringFromUtf8 { constructor } => {
+                let string_type = method.return_type.ffi_type().pointer_like_to_target()?;
+                return Ok(format!(
+                    "return new {}({}(data, len));\n",
+                    string_type.to_cpp_code(None)?,
+                    constructor
+                ));
+            }
+            CppFfiFunctionKind::StringToUtf8 { method } => {
+                return Ok(string_to_utf8_body(method.as_ref().map(String::as_str)));
+            }
+            CppFfiFunctionKind::ClosureCallback { callback } => {
+                return self.closure_callback_body(item.clone(), callback);
+            }
+            _ => {}
+        }
+
+        let cpp_item = cpp_item
+            .ok_or_else(|| format_err!("failed to find original cpp item for {:?}", item))?;
         let is_destructor = cpp_item
             .item
             .as_function_ref()
             .map_or(false, |f| f.is_destructor());
-
-        let method = item.item;
         if is_destructor && method.allocation_place == ReturnValueAllocationPlace::Heap {
             if let Some(arg) = method
                 .arguments
@@ -336,12 +608,181 @@ impl Generator<'_> {
         }
     }
 
+    /// Generates body of a function passing a closure to a C++ function that takes
+    /// a function pointer and a user data pointer. The closure is stored in
+    /// `ritual::Closure` passed as the user data, and a static function of a local
+    /// class that calls the closure is passed as the function pointer.
+    fn closure_callback_body(
+        &self,
+        item: DbItem<&CppFfiFunction>,
+        callback: &CppClosureCallback,
+    ) -> Result<String> {
+        let function_type = &callback.function_type;
+        let mut closure_type_arguments = callback.closure_arguments();
+        closure_type_arguments.insert(0, CppType::new_pointer(false, CppType::Void));
+        let closure_type = CppType::FunctionPointer(CppFunctionPointerType {
+            return_type: function_type.return_type.clone(),
+            arguments: closure_type_arguments,
+            allows_variadic_arguments: false,
+        });
+        let trampoline_arguments = function_type
+            .arguments
+            .iter()
+            .enumerate()
+            .map_if_ok(|(index, arg)| arg.to_cpp_declaration(&format!("arg{}", index)))?
+            .join(", ");
+        let closure_arguments = (0..function_type.arguments.len())
+            .filter(|index| *index != callback.callback_user_data_index)
+            .map(|index| format!(", arg{}", index))
+            .join("");
+        let mut code = format!(
+            "typedef ritual::Closure<{closure_type}> Closure;\n    \
+             struct Trampoline {{\n      \
+             static {signature} {{\n        \
+             Closure* closure = static_cast<Closure*>(arg{user_data});\n        \
+             {return_keyword}closure->callback(closure->data{arguments});\n      \
+             }}\n    \
+             }};\n    \
+             Closure* closure = new Closure(callback, deleter, data);\n    ",
+            closure_type = closure_type.to_cpp_code(None)?,
+            signature = function_type
+                .return_type
+                .to_cpp_declaration(&format!("call({})", trampoline_arguments))?,
+            user_data = callback.callback_user_data_index,
+            return_keyword = if function_type.return_type.is_void() {
+                ""
+            } else {
+                "return "
+            },
+            arguments = closure_arguments,
+        );
+        match &callback.unregister {
+            Some((unregister_path, unregister_arguments)) => {
+                let values = unregister_arguments
+                    .iter()
+                    .map(|arg| match arg {
+                        CppCallbackUnregisterArgument::Callback => "&Trampoline::call",
+                        CppCallbackUnregisterArgument::UserData => "closure",
+                    })
+                    .join(", ");
+                code.push_str(&format!(
+                    "struct Release {{\n      \
+                     static void call(void* object) {{\n        \
+                     Closure* closure = static_cast<Closure*>(object);\n        \
+                     {}({});\n        \
+                     delete closure;\n      \
+                     }}\n    \
+                     }};\n    \
+                     closure->release = &Release::call;\n    \
+                     {};\n    \
+                     return closure;\n",
+                    unregister_path.to_cpp_code()?,
+                    values,
+                    self.returned_expression(item)?
+                ));
+            }
+            None => {
+                // without an unregister function, the closure is never freed
+                code.push_str(&format!(
+                    "{}{};\n",
+                    if item.item.return_type.ffi_type().is_void() {
+                        ""
+                    } else {
+                        "return "
+                    },
+                    self.returned_expression(item)?
+                ));
+            }
+        }
+        Ok(code)
+    }
+
+    /// Generates body of a stream output function. The value is printed
+    /// using the original `operator<<` and the resulting text is
+    /// passed to the `write` callback.
+    fn stream_output_body(&self, function: &CppFunction) -> Result<String> {
+        let stream_type = match &function.arguments[0].argument_type {
+            CppType::PointerLike { target, .. } => &**target,
+            other => other,
+        };
+        let operator = function.path.to_cpp_code()?;
+        let is_qdebug = matches!(stream_type, CppType::Class(path) if path.to_templateless_string() == "QDebug");
+        if is_qdebug {
+            Ok(format!(
+                "QString text;\n    \
+                 {{\n      QDebug stream(&text);\n      {}(stream, *value);\n    }}\n    \
+                 QByteArray bytes = text.toUtf8();\n    \
+                 write(data, bytes.constData(), static_cast<size_t>(bytes.size()));\n",
+                operator
+            ))
+        } else {
+            Ok(format!(
+                "std::ostringstream stream;\n    \
+                 {}(stream, *value);\n    \
+                 std::string text = stream.str();\n    \
+                 write(data, text.data(), text.size());\n",
+                operator
+            ))
+        }
+    }
+
     /// Generates implementation of the FFI method for the source file.
+    ///
+    /// Exceptions must not propagate to the Rust side, so the body is wrapped
+    /// in a try/catch shim unless the original function is `noexcept`.
+    /// Functions that don't have an original C++ item are never wrapped.
     fn function_implementation(&self, method: DbItem<&CppFfiFunction>) -> Result<String> {
+        let cpp_item = self.0.source_cpp_item(&method.id)?;
+        let cpp_function = cpp_item
+            .as_ref()
+            .and_then(|item| item.item.as_function_ref());
+        if method.item.is_direct_binding {
+            let function = cpp_function
+                .ok_or_else(|| err_msg("direct binding must have a source function"))?;
+            return self.direct_binding_reference(method.item, function);
+        }
+        // Items without a source C++ item are generated by ritual
+        // and don't call any library code that could throw.
+        let is_noexcept = cpp_item.is_none() || cpp_function.map_or(false, |f| f.is_noexcept);
+
+        let signature = self.function_signature(method.item)?;
+        let body = self.source_body(method)?;
+        let comment = format!("// {}\n", method.item.descriptive_name);
+        if is_noexcept {
+            Ok(format!(
+                "{}RITUAL_EXPORT {} {{\n  {}}}\n\n",
+                comment, signature, body
+            ))
+        } else {
+            Ok(format!(
+                "{}RITUAL_EXPORT {} {{\n  RITUAL_TRY {{\n    {}  }} RITUAL_CATCH_ALL\n}}\n\n",
+                comment, signature, body
+            ))
+        }
+    }
+
+    /// Generates a reference to a function that is called directly by Rust code.
+    /// No wrapper is needed, but the reference makes sure the function is available
+    /// and resolves the correct overload.
+    fn direct_binding_reference(
+        &self,
+        method: &CppFfiFunction,
+        function: &CppFunction,
+    ) -> Result<String> {
+        let pointer_type = CppType::FunctionPointer(CppFunctionPointerType {
+            return_type: Box::new(function.return_type.clone()),
+            arguments: function
+                .arguments
+                .iter()
+                .map(|arg| arg.argument_type.clone())
+                .collect(),
+            allows_variadic_arguments: false,
+        });
+        let variable_name = format!("ritual_direct_binding_{}", method.path.last().name);
         Ok(format!(
-            "RITUAL_EXPORT {} {{\n  {}}}\n\n",
-            self.function_signature(method.item)?,
-            self.source_body(method)?
+            "{} = &{};\n\n",
+            pointer_type.to_cpp_code(Some(&variable_name))?,
+            function.path.to_cpp_code()?
         ))
     }
 
@@ -351,12 +792,19 @@ impl Generator<'_> {
                 let value = version_to_number(version).expect("version_to_number failed");
                 format!("RITUAL_CPP_LIB_VERSION == {}", value)
             }
-            Condition::Arch(_) => unimplemented!(),
-            Condition::OS(_) => unimplemented!(),
-            Condition::Family(_) => unimplemented!(),
-            Condition::Env(_) => unimplemented!(),
-            Condition::PointerWidth(_) => unimplemented!(),
-            Condition::Endian(_) => unimplemented!(),
+            Condition::Target(target) => target
+                .cpp_definitions()
+                .iter()
+                .map(|name| format!("defined({})", name))
+                .join("&&"),
+            Condition::Arch(arch) => target_condition("target_arch", arch.cfg_value()),
+            Condition::OS(os) => target_condition("target_os", os.cfg_value()),
+            Condition::Family(family) => target_condition("target_family", family.cfg_value()),
+            Condition::Env(env) => target_condition("target_env", env.cfg_value()),
+            Condition::PointerWidth(width) => {
+                target_condition("target_pointer_width", width.cfg_value())
+            }
+            Condition::Endian(endian) => target_condition("target_endian", endian.cfg_value()),
             Condition::And(conditions) => conditions
                 .iter()
                 .map(|c| format!("({})", self.condition_expression(c)))
@@ -382,11 +830,14 @@ impl Generator<'_> {
         )
     }
 
-    /// Generates a source file with the specified FFI methods.
-    fn generate_cpp_file(&self, file_path: &Path, global_header_name: &str) -> Result<()> {
-        let mut cpp_file = create_file(file_path)?;
-        writeln!(cpp_file, "#include \"{}\"", global_header_name)?;
-
+    /// Generates source files of the C++ wrapper library in `dir`,
+    /// distributing used FFI items between them.
+    fn generate_cpp_files(
+        &self,
+        dir: &Path,
+        global_header_name: &str,
+        config: &Config,
+    ) -> Result<()> {
         let used_ffi_functions = self
             .0
             .rust_items()
@@ -395,17 +846,119 @@ impl Generator<'_> {
             .map(|item| item.path.last())
             .collect::<HashSet<&str>>();
 
-        let ffi_items = self
-            .0
-            .ffi_items()
-            .filter(|item| {
-                !item.item.is_function()
-                    || used_ffi_functions.contains(item.item.path().last().name.as_str())
-            })
-            .collect_vec();
+        let mut files = BTreeMap::<String, Vec<_>>::new();
+        for item in self.0.ffi_items() {
+            if item.item.is_function()
+                && !used_ffi_functions.contains(item.item.path().last().name.as_str())
+            {
+                continue;
+            }
+            let include_file = self
+                .0
+                .root_item_origin(&item.id)?
+                .map(|origin| origin.grouping_include_file(config.group_members_by_class_header()));
+            let file_name = cpp_file_name(include_file, config.cpp_glue_file_count());
+            files.entry(file_name).or_default().push(item);
+        }
+        if files.is_empty() {
+            files.insert(
+                cpp_file_name(None, config.cpp_glue_file_count()),
+                Vec::new(),
+            );
+        }
+
+        for (file_name, ffi_items) in &files {
+            self.generate_cpp_file(
+                &dir.join(file_name),
+                global_header_name,
+                ffi_items,
+                config.write_source_annotations(),
+            )?;
+        }
+        if config.write_layout_assertions() {
+            self.generate_layout_assertions(
+                &dir.join("layout_assertions.cpp"),
+                global_header_name,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Generates a source file that fails to compile if layouts of
+    /// stack-allocated classes differ from the layouts recorded by the C++ parser.
+    fn generate_layout_assertions(&self, file_path: &Path, global_header_name: &str) -> Result<()> {
+        let mut output = create_file(file_path)?;
+        writeln!(output, "#include \"{}\"\n", global_header_name)?;
+        for item in self.0.rust_items() {
+            let sized_type = match item.item.as_struct_ref().map(|s| &s.kind) {
+                Some(RustStructKind::SizedType(sized_type)) => sized_type,
+                _ => continue,
+            };
+            let layout = match self.0.source_class_layout(&item.id)? {
+                Some(layout) => layout,
+                None => {
+                    warn!(
+                        "layout of stack-allocated class {} is unknown",
+                        sized_type.cpp_path.to_cpp_pseudo_code()
+                    );
+                    continue;
+                }
+            };
+            for condition in layout_conditions(&sized_type.cpp_path, layout)? {
+                writeln!(
+                    output,
+                    "static_assert({}, \"layout of {} doesn't match the generated crate\");",
+                    condition,
+                    sized_type.cpp_path.to_cpp_pseudo_code()
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Generates a comment containing the original C++ signature and
+    /// a `#line` directive pointing at the declaration FFI item `id` originates from.
+    fn source_annotation(&self, id: &ItemId) -> Result<Option<String>> {
+        let origin = match self.0.root_item_origin(id)? {
+            Some(origin) => origin,
+            None => return Ok(None),
+        };
+        let mut code = String::new();
+        if let Some(cpp_item) = self.0.source_cpp_item(id)? {
+            code.push_str(&format!("// {}\n", cpp_item.item.short_text()));
+        }
+        code.push_str(&format!(
+            "// declared at {}:{}:{}\n{}",
+            origin.location.include_file_path,
+            origin.location.line,
+            origin.location.column,
+            line_directive(origin.location.line, &origin.location.include_file_path)
+        ));
+        Ok(Some(code))
+    }
+
+    /// Generates a source file with the specified FFI items.
+    fn generate_cpp_file(
+        &self,
+        file_path: &Path,
+        global_header_name: &str,
+        ffi_items: &[DbItem<&CppFfiItem>],
+        write_source_annotations: bool,
+    ) -> Result<()> {
+        let file_name = file_path
+            .file_name()
+            .ok_or_else(|| err_msg("failed to get file name"))?;
+        let file_name = os_str_to_str(file_name)?;
+
+        // the file is generated in memory first so that lines can be counted
+        // for resetting `#line` directives
+        let mut output = Vec::<u8>::new();
+        let mut counted_bytes = 0;
+        let mut counted_lines = 0;
+        writeln!(output, "#include \"{}\"", global_header_name)?;
 
         let mut needs_moc = false;
-        for ffi_item in &ffi_items {
+        for ffi_item in ffi_items {
             match &ffi_item.item {
                 CppFfiItem::QtSlotWrapper(qt_slot_wrapper) => {
                     let checks = self.0.cpp_checks(&ffi_item.id)?;
@@ -415,7 +968,7 @@ impl Generator<'_> {
                     needs_moc = true;
                     let condition = checks.condition(self.0.environments());
                     let code = self.qt_slot_wrapper(qt_slot_wrapper)?;
-                    write!(cpp_file, "{}", self.wrap_with_condition(&code, &condition))?;
+                    write!(output, "{}", self.wrap_with_condition(&code, &condition))?;
                 }
                 CppFfiItem::QtSignalWrapper(qt_signal_wrapper) => {
                     let checks = self.0.cpp_checks(&ffi_item.id)?;
@@ -425,32 +978,60 @@ impl Generator<'_> {
                     needs_moc = true;
                     let condition = checks.condition(self.0.environments());
                     let code = self.qt_signal_wrapper(qt_signal_wrapper)?;
-                    write!(cpp_file, "{}", self.wrap_with_condition(&code, &condition))?;
+                    write!(output, "{}", self.wrap_with_condition(&code, &condition))?;
+                }
+                CppFfiItem::SubclassShim(shim) => {
+                    let checks = self.0.cpp_checks(&ffi_item.id)?;
+                    if !checks.any_success() {
+                        continue;
+                    }
+                    let condition = checks.condition(self.0.environments());
+                    let code = self.subclass_shim(shim)?;
+                    write!(output, "{}", self.wrap_with_condition(&code, &condition))?;
                 }
                 _ => {}
             }
         }
 
-        writeln!(cpp_file, "extern \"C\" {{")?;
-        for ffi_item in &ffi_items {
+        writeln!(output, "extern \"C\" {{")?;
+        for ffi_item in ffi_items {
             if let Some(item) = ffi_item.clone().filter_map(|item| item.as_function_ref()) {
                 let checks = self.0.cpp_checks(&ffi_item.id)?;
                 if !checks.any_success() {
                     continue;
                 }
                 let condition = checks.condition(self.0.environments());
-                let code = self.function_implementation(item)?;
-                writeln!(cpp_file, "{}", self.wrap_with_condition(&code, &condition))?;
+                let mut code = self.function_implementation(item)?;
+                let annotation = if write_source_annotations {
+                    self.source_annotation(&ffi_item.id)?
+                } else {
+                    None
+                };
+                let is_annotated = annotation.is_some();
+                if let Some(annotation) = annotation {
+                    code = format!("{}{}", annotation, code);
+                }
+                writeln!(output, "{}", self.wrap_with_condition(&code, &condition))?;
+                if is_annotated {
+                    counted_lines += output[counted_bytes..]
+                        .iter()
+                        .filter(|&&c| c == b'\n')
+                        .count();
+                    counted_bytes = output.len();
+                    // the line after the directive has number `counted_lines + 2`
+                    write!(output, "{}", line_directive(counted_lines + 2, file_name))?;
+                }
             }
         }
-        writeln!(cpp_file, "}} // extern \"C\"")?;
+        writeln!(output, "}} // extern \"C\"")?;
 
         if needs_moc && !self.0.crate_name().starts_with("moqt_") {
             let stem = file_path
                 .file_stem()
                 .ok_or_else(|| err_msg("failed to get file stem"))?;
-            writeln!(cpp_file, "#include \"{}.moc\"", os_str_to_str(stem)?)?;
+            writeln!(output, "#include \"{}.moc\"", os_str_to_str(stem)?)?;
         }
+        create_file(file_path)?.write_all(&output)?;
         Ok(())
     }
 
@@ -494,6 +1075,19 @@ impl Generator<'_> {
     }
 }
 
+/// Generates body of a function passing UTF-8 text of a string object to the `write`
+/// callback. If `method` is set, it's used to convert the string to UTF-8 bytes first.
+fn string_to_utf8_body(method: Option<&str>) -> String {
+    match method {
+        Some(method) => format!(
+            "auto bytes = value->{}();\n    \
+             write(data, bytes.data(), static_cast<size_t>(bytes.size()));\n",
+            method
+        ),
+        None => "write(data, value->data(), static_cast<size_t>(value->size()));\n".to_string(),
+    }
+}
+
 pub fn function_implementation(
     db: &DatabaseClient,
     method: DbItem<&CppFfiFunction>,
@@ -509,12 +1103,60 @@ pub fn qt_signal_wrapper(db: &DatabaseClient, wrapper: &QtSignalWrapper) -> Resu
     Generator(db).qt_signal_wrapper(wrapper)
 }
 
-pub fn generate_cpp_file(
+pub fn subclass_shim(db: &DatabaseClient, shim: &CppSubclassShim) -> Result<String> {
+    Generator(db).subclass_shim(shim)
+}
+
+/// Returns the name of the source file containing wrappers of items
+/// declared in `include_file`. If `file_count` is specified, wrappers
+/// are distributed between that many files. Otherwise, a file is
+/// generated for each include file.
+///
+/// The result only depends on the arguments, so the wrappers of unchanged
+/// include files stay in the same file after regeneration.
+pub fn cpp_file_name(include_file: Option<&str>, file_count: Option<usize>) -> String {
+    match file_count {
+        Some(count) => {
+            let index = include_file.map_or(0, |name| stable_hash(name) % count as u64);
+            format!("glue_{}.cpp", index)
+        }
+        None => {
+            let stem = include_file
+                .map(|name| name.split('.').next().unwrap_or(name))
+                .filter(|stem| !stem.is_empty())
+                .unwrap_or("other");
+            let stem = stem
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect::<String>();
+            format!("glue_{}.cpp", stem.to_lowercase())
+        }
+    }
+}
+
+pub fn generate_cpp_files(
     db: &DatabaseClient,
-    file_path: &Path,
+    dir: &Path,
     global_header_name: &str,
+    config: &Config,
 ) -> Result<()> {
-    Generator(db).generate_cpp_file(file_path, global_header_name)
+    Generator(db).generate_cpp_files(dir, global_header_name, config)
+}
+
+/// Generates a `#line` directive that sets the number of the next line to `line`
+/// and the current file name to `file_path`.
+/// Returns the preprocessor condition that is true if `cfg` option `name`
+/// has `value` on the target the library is built for.
+fn target_condition(name: &str, value: &str) -> String {
+    format!("defined({})", cpp_definition(name, value))
+}
+
+fn line_directive(line: impl fmt::Display, file_path: &str) -> String {
+    format!(
+        "#line {} \"{}\"\n",
+        line,
+        file_path.replace('\\', "\\\\").replace('"', "\\\"")
+    )
 }
 
 pub fn generate_cpp_type_size_requester(
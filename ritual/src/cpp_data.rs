@@ -5,10 +5,11 @@ pub use crate::cpp_operator::CppOperator;
 use crate::cpp_type::{CppTemplateParameter, CppType};
 use crate::database::DatabaseClient;
 use itertools::Itertools;
-use ritual_common::errors::{bail, ensure, Error, Result};
+use ritual_common::errors::{bail, ensure, err_msg, Error, Result};
 use ritual_common::utils::MapIfOk;
 use serde_derive::{Deserialize, Serialize};
 use std::fmt;
+use std::io::{Read, Write};
 use std::str::FromStr;
 
 /// One item of a C++ enum declaration
@@ -58,6 +59,8 @@ pub struct CppClassField {
     /// Visibility
     pub visibility: CppVisibility,
     pub is_static: bool,
+    /// True if the field is declared `const` and can't be assigned
+    pub is_const: bool,
 }
 
 impl CppClassField {
@@ -66,6 +69,7 @@ impl CppClassField {
             && self.field_type == other.field_type
             && self.visibility == other.visibility
             && self.is_static == other.is_static
+            && self.is_const == other.is_const
     }
 
     pub fn short_text(&self) -> String {
@@ -75,8 +79,9 @@ impl CppClassField {
             CppVisibility::Private => "private ",
         };
         format!(
-            "{}{} {}",
+            "{}{}{} {}",
             visibility_text,
+            if self.is_const { "const " } else { "" },
             self.field_type.to_cpp_pseudo_code(),
             self.path.to_cpp_pseudo_code(),
         )
@@ -111,6 +116,32 @@ pub struct CppOriginLocation {
     pub column: u32,
 }
 
+/// Declaration place of an item found by the C++ parser.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
+pub struct CppItemOrigin {
+    /// File name of the include file (without full path)
+    pub include_file: String,
+    /// Exact location of the declaration
+    pub location: CppOriginLocation,
+    /// File name of the include file of the class containing the item,
+    /// if the item is a class member. It differs from `include_file`
+    /// if the member is declared outside of the class's header.
+    #[serde(default)]
+    pub class_include_file: Option<String>,
+}
+
+impl CppItemOrigin {
+    /// Returns the include file used to group items by headers.
+    /// If `use_class_header` is true, class members are grouped
+    /// with their class, even if they are declared in another header.
+    pub fn grouping_include_file(&self, use_class_header: bool) -> &str {
+        match &self.class_include_file {
+            Some(class_include_file) if use_class_header => class_include_file,
+            _ => &self.include_file,
+        }
+    }
+}
+
 /// Visibility of a C++ entity. Defaults to `Public`
 /// for entities that can't have visibility (like free functions)
 #[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
@@ -366,11 +397,96 @@ impl fmt::Debug for CppPathItem {
     }
 }
 
+/// Information about a deprecated C++ entity
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, Hash)]
+pub struct CppDeprecation {
+    /// Message specified in the deprecation attribute, if any
+    pub message: Option<String>,
+}
+
+/// Memory layout of a class as reported by clang
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, Hash)]
+pub struct CppClassLayout {
+    /// Size of the class in bytes
+    pub size: usize,
+    /// True if the class is a POD type (and therefore trivially copyable)
+    pub is_pod: bool,
+    /// Alignment of the class in bytes
+    #[serde(default)]
+    pub align: Option<usize>,
+}
+
+/// A `using Base::name;` declaration inside a class. It makes all overloads of
+/// `name` declared in `Base` members of the class, or inherits constructors of `Base`
+/// if `name` is the name of `Base`.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, Hash)]
+pub struct CppUsingDeclaration {
+    /// Base class containing the members
+    pub base_class_path: CppPath,
+    /// Name of the members
+    pub name: String,
+    /// Visibility of the `using` declaration. Members brought into the class
+    /// have this visibility, except for inherited constructors that keep
+    /// the visibility they have in the base class.
+    pub visibility: CppVisibility,
+}
+
+impl CppUsingDeclaration {
+    /// Returns true if the declaration inherits constructors of the base class.
+    pub fn is_inheriting_constructors(&self) -> bool {
+        self.base_class_path.last().name == self.name
+    }
+}
+
+/// A property of a class declared with a macro (e.g. `Q_PROPERTY`).
+/// Accessor functions are referenced by their names.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, Hash)]
+pub struct CppProperty {
+    /// Name of the property
+    pub name: String,
+    /// Spelling of the property type
+    pub property_type: String,
+    /// Name of the method returning the value of the property
+    pub getter: Option<String>,
+    /// Name of the method changing the value of the property
+    pub setter: Option<String>,
+    /// Name of the signal emitted when the value of the property changes
+    pub notify: Option<String>,
+}
+
 /// Information about a C++ type declaration
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, Hash)]
 pub enum CppTypeDeclarationKind {
-    Enum,
-    Class,
+    Enum {
+        /// Integer type used to pass values of the enum.
+        /// Unscoped enums whose values fit in `int` use `int`.
+        underlying_type: CppType,
+    },
+    Class {
+        /// True if the class is marked as `final`
+        is_final: bool,
+        /// Layout of the class, if clang could compute it
+        /// (it's not available for class templates)
+        #[serde(default)]
+        layout: Option<CppClassLayout>,
+        /// `using` declarations that bring members of base classes
+        /// into the class
+        #[serde(default)]
+        using_declarations: Vec<CppUsingDeclaration>,
+        /// Default arguments of template parameters of a class template.
+        /// Contains `None` for parameters without a default argument
+        /// and for default arguments that couldn't be parsed.
+        /// Empty if the class is not a template or has no default arguments.
+        #[serde(default)]
+        template_argument_defaults: Vec<Option<CppType>>,
+        /// Properties declared with macros like `Q_PROPERTY`.
+        /// See `Config::add_macro_extractor`.
+        #[serde(default)]
+        properties: Vec<CppProperty>,
+    },
+    /// A class that is only forward-declared in the processed headers.
+    /// It can only be used through pointers and references.
+    Opaque,
 }
 
 /// Information about a C++ type declaration
@@ -379,6 +495,10 @@ pub struct CppTypeDeclaration {
     /// Identifier, including namespaces and nested classes
     pub path: CppPath,
     pub kind: CppTypeDeclarationKind,
+    /// Deprecation info if the type is marked as deprecated
+    pub deprecated: Option<CppDeprecation>,
+    /// Raw documentation comment attached to the declaration
+    pub doc_comment: Option<String>,
 }
 
 impl CppTypeDeclaration {
@@ -393,8 +513,47 @@ impl CppTypeDeclarationKind {
         matches!(self, CppTypeDeclarationKind::Class { .. })
     }
 
+    /// Returns `using` declarations of the class.
+    pub fn using_declarations(&self) -> &[CppUsingDeclaration] {
+        match self {
+            CppTypeDeclarationKind::Class {
+                using_declarations, ..
+            } => using_declarations,
+            _ => &[],
+        }
+    }
+
+    /// Returns default arguments of template parameters of the class template.
+    pub fn template_argument_defaults(&self) -> &[Option<CppType>] {
+        match self {
+            CppTypeDeclarationKind::Class {
+                template_argument_defaults,
+                ..
+            } => template_argument_defaults,
+            _ => &[],
+        }
+    }
+
+    /// Returns properties of the class declared with macros.
+    pub fn properties(&self) -> &[CppProperty] {
+        match self {
+            CppTypeDeclarationKind::Class { properties, .. } => properties,
+            _ => &[],
+        }
+    }
+
+    /// Checks if the type is a class marked as `final`.
+    pub fn is_final_class(&self) -> bool {
+        matches!(self, CppTypeDeclarationKind::Class { is_final: true, .. })
+    }
+
     pub fn is_enum(&self) -> bool {
-        matches!(self, CppTypeDeclarationKind::Enum)
+        matches!(self, CppTypeDeclarationKind::Enum { .. })
+    }
+
+    /// Checks if the type is a class without a known definition.
+    pub fn is_opaque(&self) -> bool {
+        matches!(self, CppTypeDeclarationKind::Opaque)
     }
 }
 
@@ -479,10 +638,12 @@ impl CppItem {
     pub fn all_involved_types(&self) -> Vec<CppType> {
         match self {
             CppItem::Type(t) => match t.kind {
-                CppTypeDeclarationKind::Enum => vec![CppType::Enum {
+                CppTypeDeclarationKind::Enum { .. } => vec![CppType::Enum {
                     path: t.path.clone(),
                 }],
-                CppTypeDeclarationKind::Class { .. } => vec![CppType::Class(t.path.clone())],
+                CppTypeDeclarationKind::Class { .. } | CppTypeDeclarationKind::Opaque => {
+                    vec![CppType::Class(t.path.clone())]
+                }
             },
             CppItem::EnumValue(enum_value) => vec![CppType::Enum {
                 path: enum_value
@@ -573,10 +734,15 @@ impl fmt::Display for CppItem {
                 format!("namespace {}", namespace.path.to_cpp_pseudo_code())
             }
             CppItem::Type(type1) => match type1.kind {
-                CppTypeDeclarationKind::Enum => format!("enum {}", type1.path.to_cpp_pseudo_code()),
+                CppTypeDeclarationKind::Enum { .. } => {
+                    format!("enum {}", type1.path.to_cpp_pseudo_code())
+                }
                 CppTypeDeclarationKind::Class { .. } => {
                     format!("class {}", type1.path.to_cpp_pseudo_code())
                 }
+                CppTypeDeclarationKind::Opaque => {
+                    format!("opaque class {}", type1.path.to_cpp_pseudo_code())
+                }
             },
             CppItem::Function(method) => method.short_text(),
             CppItem::EnumValue(value) => format!(
@@ -636,3 +802,70 @@ pub fn inherits(
     }
     false
 }
+
+/// Version of the JSON representation of `CppData`. It's incremented whenever
+/// the representation changes incompatibly.
+pub const CPP_DATA_SCHEMA_VERSION: u32 = 1;
+
+/// A C++ item found by the parser, together with its declaration place.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CppDataItem {
+    pub item: CppItem,
+    pub origin: Option<CppItemOrigin>,
+}
+
+/// Output of the C++ parser in a self-contained form suitable for external tools.
+/// See `processor::parse_only`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CppData {
+    /// Version of the JSON representation (`CPP_DATA_SCHEMA_VERSION`)
+    pub schema_version: u32,
+    /// Name of the crate the data was parsed for
+    pub crate_name: String,
+    /// Parsed items in the order of the database
+    pub items: Vec<CppDataItem>,
+}
+
+impl CppData {
+    /// Collects items of the current crate added by the C++ parser.
+    /// Items derived from other items (e.g. template instantiations) are not included.
+    pub fn from_database(db: &DatabaseClient) -> Self {
+        let items = db
+            .cpp_items()
+            .filter(|item| item.source_id.is_none())
+            .map(|item| CppDataItem {
+                item: item.item.clone(),
+                origin: db.item_origin(&item.id).cloned(),
+            })
+            .collect();
+        CppData {
+            schema_version: CPP_DATA_SCHEMA_VERSION,
+            crate_name: db.crate_name().to_string(),
+            items,
+        }
+    }
+
+    /// Writes the data as JSON.
+    pub fn to_json<W: Write>(&self, writer: W) -> Result<()> {
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    /// Reads data written by `to_json`. Fails if the data was written
+    /// with a different schema version.
+    pub fn from_json<R: Read>(reader: R) -> Result<Self> {
+        let value: serde_json::Value = serde_json::from_reader(reader)?;
+        let version = value
+            .get("schema_version")
+            .and_then(serde_json::Value::as_u64)
+            .ok_or_else(|| err_msg("schema_version is missing"))?;
+        if version != u64::from(CPP_DATA_SCHEMA_VERSION) {
+            bail!(
+                "unsupported C++ data schema version: {} (expected {})",
+                version,
+                CPP_DATA_SCHEMA_VERSION
+            );
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+}
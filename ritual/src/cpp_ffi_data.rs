@@ -1,6 +1,6 @@
 use crate::cpp_code_generator;
-use crate::cpp_data::CppPath;
-use crate::cpp_function::ReturnValueAllocationPlace;
+use crate::cpp_data::{CppClassField, CppPath};
+use crate::cpp_function::{CppFunction, ReturnValueAllocationPlace};
 use crate::cpp_type::{CppBuiltInNumericType, CppFunctionPointerType, CppType};
 use crate::database::DatabaseClient;
 use itertools::Itertools;
@@ -26,8 +26,10 @@ pub enum CppCast {
         /// If true, this is an unsafe (from base to derived) `static_cast` wrapper.
         is_unsafe: bool,
 
-        /// Contains index of the base (e.g. 0 for the first base; always
-        /// 0 if the class only has one base).
+        /// Contains index of the base among public bases of the class
+        /// (e.g. 0 for the first public base; always 0 if the class
+        /// only has one public base). The first public base is used for `Deref`.
+        /// `None` for indirect bases.
         base_index: Option<usize>,
     },
     Dynamic,
@@ -69,6 +71,62 @@ pub enum CppFfiFunctionKind {
         /// Type of the accessor
         accessor_type: CppFieldAccessorType,
     },
+    /// This is a wrapper that prints the value using `operator<<`
+    /// and passes the resulting text to a callback.
+    StreamOutput,
+    /// This is a function that creates a heap-allocated string object
+    /// from UTF-8 text.
+    StringFromUtf8 {
+        /// Function or constructor called with the text and its length
+        constructor: String,
+    },
+    /// This is a function that passes UTF-8 text of a string object
+    /// to a callback.
+    StringToUtf8 {
+        /// Method that converts the string to UTF-8 bytes, if needed
+        method: Option<String>,
+    },
+    /// This is a wrapper that passes a closure to a C++ function
+    /// taking a function pointer and a user data pointer.
+    ClosureCallback {
+        /// Information about the replaced arguments
+        callback: CppClosureCallback,
+    },
+}
+
+/// Argument of a function that unregisters a callback
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum CppCallbackUnregisterArgument {
+    /// The function pointer passed to the registering function
+    Callback,
+    /// The user data pointer passed to the registering function
+    UserData,
+}
+
+/// Function pointer argument of a C++ function paired with a user data argument.
+/// The FFI function replaces them with a closure.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct CppClosureCallback {
+    /// Index of the function pointer argument of the C++ function
+    pub callback_index: usize,
+    /// Index of the user data argument of the C++ function
+    pub user_data_index: usize,
+    /// Type of the function pointer argument
+    pub function_type: CppFunctionPointerType,
+    /// Index of the argument of the function pointer receiving the user data
+    pub callback_user_data_index: usize,
+    /// Function called when the closure is released, and its arguments
+    pub unregister: Option<(CppPath, Vec<CppCallbackUnregisterArgument>)>,
+}
+
+impl CppClosureCallback {
+    /// Returns types of the closure's arguments, i.e. arguments
+    /// of the function pointer except the user data.
+    pub fn closure_arguments(&self) -> Vec<CppType> {
+        let mut arguments = self.function_type.arguments.clone();
+        arguments.remove(self.callback_user_data_index);
+        arguments
+    }
 }
 
 /// Relation between original C++ method's argument value
@@ -83,8 +141,12 @@ pub enum CppToFfiTypeConversion {
     /// C++ argument is a reference (like QPoint&)
     /// and FFI argument is a pointer (like QPoint*)
     ReferenceToPointer,
-    /// C++ argument is QFlags<T>
-    /// and FFI argument is uint
+    /// C++ argument is an rvalue reference (like QPoint&&)
+    /// and FFI argument is a pointer (like QPoint*).
+    /// The pointed object is moved into the original function.
+    RValueReferenceToPointer,
+    /// C++ argument is a flags type (e.g. `QFlags<T>`,
+    /// see `Config::set_flags_template`) and FFI argument is int
     QFlagsToInt,
     /// Implicit conversion is used.
     ImplicitCast { ffi_type: CppType },
@@ -104,6 +166,12 @@ pub enum CppFfiArgumentMeaning {
     /// This argument receives pointer to the buffer where
     /// the return value should be transferred to using placement new.
     ReturnValue,
+    /// This argument is a part of the callback receiving
+    /// the text produced by a stream output function.
+    OutputCallback,
+    /// This argument is a part of the closure replacing
+    /// a function pointer and a user data pointer.
+    ClosureCallback,
 }
 
 impl CppFfiArgumentMeaning {
@@ -129,15 +197,7 @@ impl CppFfiFunctionArgument {
     /// Generates C++ code for the part of FFI function signature
     /// corresponding to this argument
     pub fn to_cpp_code(&self) -> Result<String> {
-        if let CppType::FunctionPointer(..) = self.argument_type.ffi_type {
-            Ok(self.argument_type.ffi_type.to_cpp_code(Some(&self.name))?)
-        } else {
-            Ok(format!(
-                "{} {}",
-                self.argument_type.ffi_type.to_cpp_code(None)?,
-                self.name
-            ))
-        }
+        self.argument_type.ffi_type.to_cpp_declaration(&self.name)
     }
 }
 
@@ -158,7 +218,16 @@ pub struct CppFfiFunction {
     /// Final name of FFI method
     pub path: CppPath,
 
+    /// Descriptive name of FFI method. Unless this is a direct binding,
+    /// `path` contains a short name derived from a hash of this name.
+    pub descriptive_name: String,
+
     pub kind: CppFfiFunctionKind,
+
+    /// True if the original C++ function has C linkage and an FFI-compatible
+    /// signature, so it's called directly by its symbol name (stored in `path`)
+    /// instead of a generated wrapper.
+    pub is_direct_binding: bool,
 }
 
 impl CppFfiFunction {
@@ -191,6 +260,18 @@ impl CppFfiFunction {
                     false
                 }
             }
+            CppFfiFunctionKind::StreamOutput => {
+                matches!(&other.kind, CppFfiFunctionKind::StreamOutput)
+            }
+            CppFfiFunctionKind::StringFromUtf8 { .. } => {
+                matches!(&other.kind, CppFfiFunctionKind::StringFromUtf8 { .. })
+            }
+            CppFfiFunctionKind::StringToUtf8 { .. } => {
+                matches!(&other.kind, CppFfiFunctionKind::StringToUtf8 { .. })
+            }
+            CppFfiFunctionKind::ClosureCallback { .. } => {
+                matches!(&other.kind, CppFfiFunctionKind::ClosureCallback { .. })
+            }
         }
     }
 }
@@ -220,7 +301,8 @@ impl CppFfiType {
                 original_type,
                 conversion,
             }),
-            CppToFfiTypeConversion::ReferenceToPointer => {
+            CppToFfiTypeConversion::ReferenceToPointer
+            | CppToFfiTypeConversion::RValueReferenceToPointer => {
                 let target = original_type.pointer_like_to_target()?;
                 let is_const = original_type.pointer_like_is_const()?;
                 Ok(CppFfiType {
@@ -266,7 +348,7 @@ impl CppFfiType {
 
 /// Information about a Qt signal wrapper with
 /// certain signal arguments
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct QtSignalWrapper {
     pub signal_arguments: Vec<CppType>,
     /// Generated name of the wrapper class
@@ -275,7 +357,7 @@ pub struct QtSignalWrapper {
 
 /// Information about a Qt slot wrapper with
 /// certain slot arguments
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct QtSlotWrapper {
     pub signal_arguments: Vec<CppType>,
     /// Generated name of the wrapper class
@@ -286,12 +368,60 @@ pub struct QtSlotWrapper {
     pub function_type: CppFunctionPointerType,
 }
 
+/// Virtual function that can be overridden using a subclass shim
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct CppSubclassFunction {
+    /// Original virtual function
+    pub function: CppFunction,
+    /// Name used in `set_<name>_override` and `base_<name>` functions of the shim
+    pub name: String,
+    /// Arguments of the callback (excluding the data pointer)
+    pub arguments: Vec<CppFfiType>,
+    /// Return type of the callback
+    pub return_type: CppFfiType,
+    /// The function pointer type accepted by the shim
+    pub function_type: CppFunctionPointerType,
+}
+
+/// Information about a generated subclass that forwards
+/// virtual functions of its base class to callbacks
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct CppSubclassShim {
+    /// Class that is subclassed
+    pub base_class_path: CppPath,
+    /// Generated name of the subclass
+    pub class_path: CppPath,
+    /// Constructors of the base class available to the subclass
+    pub constructors: Vec<CppFunction>,
+    /// Overridable virtual functions
+    pub functions: Vec<CppSubclassFunction>,
+    /// Protected non-virtual functions of the base class. They are forwarded by
+    /// public functions of the shim and can only be called through the subclass.
+    #[serde(default)]
+    pub protected_functions: Vec<CppFunction>,
+    /// Protected fields of the base class. The shim provides public accessors
+    /// for them, so they can only be accessed through the subclass.
+    #[serde(default)]
+    pub protected_fields: Vec<CppClassField>,
+}
+
+impl CppSubclassShim {
+    /// Returns the virtual function that has `set_<name>_override`
+    /// setter named `setter_name`.
+    pub fn function_by_setter_name(&self, setter_name: &str) -> Option<&CppSubclassFunction> {
+        self.functions
+            .iter()
+            .find(|f| format!("set_{}_override", f.name) == setter_name)
+    }
+}
+
 #[allow(clippy::large_enum_variant)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum CppFfiItem {
     Function(CppFfiFunction),
     QtSlotWrapper(QtSlotWrapper),
     QtSignalWrapper(QtSignalWrapper),
+    SubclassShim(CppSubclassShim),
 }
 
 impl CppFfiItem {
@@ -323,6 +453,14 @@ impl CppFfiItem {
         }
     }
 
+    pub fn as_subclass_shim_ref(&self) -> Option<&CppSubclassShim> {
+        if let CppFfiItem::SubclassShim(data) = self {
+            Some(data)
+        } else {
+            None
+        }
+    }
+
     pub fn is_slot_wrapper(&self) -> bool {
         matches!(self, CppFfiItem::QtSlotWrapper(_))
     }
@@ -331,6 +469,10 @@ impl CppFfiItem {
         matches!(self, CppFfiItem::QtSignalWrapper(_))
     }
 
+    pub fn is_subclass_shim(&self) -> bool {
+        matches!(self, CppFfiItem::SubclassShim(_))
+    }
+
     pub fn short_text(&self) -> String {
         match self {
             CppFfiItem::Function(function) => function.path.to_cpp_pseudo_code(),
@@ -350,6 +492,10 @@ impl CppFfiItem {
                     .map(CppType::to_cpp_pseudo_code)
                     .join(", ")
             ),
+            CppFfiItem::SubclassShim(shim) => format!(
+                "subclass shim for {}",
+                shim.base_class_path.to_cpp_pseudo_code()
+            ),
         }
     }
 
@@ -376,6 +522,13 @@ impl CppFfiItem {
                     false
                 }
             }
+            CppFfiItem::SubclassShim(shim) => {
+                if let CppFfiItem::SubclassShim(other_shim) = other {
+                    shim.base_class_path == other_shim.base_class_path
+                } else {
+                    false
+                }
+            }
         }
     }
 
@@ -384,13 +537,16 @@ impl CppFfiItem {
             CppFfiItem::Function(f) => &f.path,
             CppFfiItem::QtSlotWrapper(s) => &s.class_path,
             CppFfiItem::QtSignalWrapper(s) => &s.class_path,
+            CppFfiItem::SubclassShim(s) => &s.class_path,
         }
     }
 
     pub fn is_source_item(&self) -> bool {
         match self {
             CppFfiItem::Function(_) => false,
-            CppFfiItem::QtSlotWrapper(_) | CppFfiItem::QtSignalWrapper(_) => true,
+            CppFfiItem::QtSlotWrapper(_)
+            | CppFfiItem::QtSignalWrapper(_)
+            | CppFfiItem::SubclassShim(_) => true,
         }
     }
 
@@ -403,6 +559,7 @@ impl CppFfiItem {
             CppFfiItem::QtSignalWrapper(signal_wrapper) => {
                 cpp_code_generator::qt_signal_wrapper(db, signal_wrapper)
             }
+            CppFfiItem::SubclassShim(shim) => cpp_code_generator::subclass_shim(db, shim),
         }
     }
 }
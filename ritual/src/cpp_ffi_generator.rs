@@ -1,30 +1,41 @@
+use crate::config::{Config, StringConversion};
 use crate::cpp_data::CppClassField;
 use crate::cpp_data::CppItem;
+use crate::cpp_data::CppOperator;
 use crate::cpp_data::CppPath;
 use crate::cpp_data::CppPathItem;
 use crate::cpp_data::CppVisibility;
 use crate::cpp_ffi_data::CppFfiType;
+use crate::cpp_ffi_data::{CppCallbackUnregisterArgument, CppClosureCallback};
 use crate::cpp_ffi_data::{CppFfiArgumentMeaning, CppToFfiTypeConversion};
 use crate::cpp_ffi_data::{CppFfiFunction, CppFfiFunctionKind, CppFieldAccessorType};
 use crate::cpp_ffi_data::{CppFfiFunctionArgument, CppFfiItem};
 use crate::cpp_function::ReturnValueAllocationPlace;
 use crate::cpp_function::{CppFunction, CppFunctionArgument, CppFunctionKind};
+use crate::cpp_type::CppBuiltInNumericType;
 use crate::cpp_type::CppPointerLikeTypeKind;
 use crate::cpp_type::CppType;
 use crate::cpp_type::CppTypeRole;
-use crate::cpp_type::{is_qflags, CppFunctionPointerType};
-use crate::database::DbItem;
+use crate::cpp_type::{is_flags_type, CppFunctionPointerType};
+use crate::database::{DbItem, Rejection};
 use crate::processor::ProcessorData;
+use crate::user_hooks;
 use itertools::Itertools;
 use log::{debug, trace};
-use ritual_common::errors::{bail, Result, ResultExt};
-use std::collections::HashSet;
+use ritual_common::errors::{bail, format_err, Result, ResultExt};
+use ritual_common::utils::MapIfOk;
+use std::collections::{BTreeMap, HashSet};
 
 /// Converts this C++ type to its adaptation for FFI interface,
 /// removing all features not supported by C ABI
 /// (e.g. references and passing objects by value).
+/// Instantiations of `flags_templates` are passed as `int`.
 #[allow(clippy::collapsible_if)]
-pub fn ffi_type(original_type: &CppType, role: CppTypeRole) -> Result<CppFfiType> {
+pub fn ffi_type(
+    original_type: &CppType,
+    role: CppTypeRole,
+    flags_templates: &[CppPath],
+) -> Result<CppFfiType> {
     let inner = || -> Result<CppFfiType> {
         if original_type.is_or_contains_template_parameter() {
             bail!("template parameters cannot be expressed in FFI");
@@ -62,8 +73,21 @@ pub fn ffi_type(original_type: &CppType, role: CppTypeRole) -> Result<CppFfiType
                 }
                 CppToFfiTypeConversion::NoChange
             }
+            CppType::Array {
+                element_type,
+                is_const,
+                ..
+            } => {
+                if role == CppTypeRole::ReturnType {
+                    bail!("arrays can't be returned by value");
+                }
+                // array arguments decay to pointers
+                CppToFfiTypeConversion::ImplicitCast {
+                    ffi_type: CppType::new_pointer(*is_const, (**element_type).clone()),
+                }
+            }
             CppType::Class(path) => {
-                if is_qflags(&path) {
+                if is_flags_type(&path, flags_templates) {
                     CppToFfiTypeConversion::QFlagsToInt
                 } else {
                     CppToFfiTypeConversion::ValueToPointer {
@@ -80,7 +104,9 @@ pub fn ffi_type(original_type: &CppType, role: CppTypeRole) -> Result<CppFfiType
                     CppPointerLikeTypeKind::Pointer => CppToFfiTypeConversion::NoChange,
                     CppPointerLikeTypeKind::Reference => {
                         match &**target {
-                            CppType::Class(path) if *is_const && is_qflags(path) => {
+                            CppType::Class(path)
+                                if *is_const && is_flags_type(path, flags_templates) =>
+                            {
                                 // TODO: use a separate conversion type (QFlagsConstRefToUInt)?
                                 CppToFfiTypeConversion::QFlagsToInt
                             }
@@ -88,7 +114,10 @@ pub fn ffi_type(original_type: &CppType, role: CppTypeRole) -> Result<CppFfiType
                         }
                     }
                     CppPointerLikeTypeKind::RValueReference => {
-                        bail!("rvalue references are not supported");
+                        if role == CppTypeRole::ReturnType {
+                            bail!("rvalue references can't be returned");
+                        }
+                        CppToFfiTypeConversion::RValueReferenceToPointer
                     }
                 }
             }
@@ -99,26 +128,60 @@ pub fn ffi_type(original_type: &CppType, role: CppTypeRole) -> Result<CppFfiType
     Ok(inner().with_context(|_| format!("Can't express type to FFI: {:?}", original_type))?)
 }
 
+/// 64-bit FNV-1a hash. Unlike `DefaultHasher`, its output is guaranteed
+/// to be the same across Rust versions and platforms.
+pub fn stable_hash(text: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in text.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
 pub struct FfiNameProvider {
     names: HashSet<String>,
+    /// Descriptive names of FFI functions by their short names
+    function_names: BTreeMap<String, String>,
+    collisions: Vec<(String, String)>,
     prefix: String,
 }
 
 impl FfiNameProvider {
     pub fn new(data: &ProcessorData<'_>) -> Self {
         let prefix = format!("ctr_{}_ffi", &data.config.crate_properties().name());
-        let names = data
+        let mut names: HashSet<String> = data
             .db
             .ffi_items()
             .map(|f| f.item.path().to_cpp_code().unwrap())
             .collect();
+        let mut function_names = BTreeMap::new();
+        for function in data
+            .db
+            .ffi_items()
+            .filter_map(|f| f.item.as_function_ref())
+            .filter(|f| !f.is_direct_binding)
+        {
+            names.insert(function.descriptive_name.clone());
+            function_names.insert(
+                function.path.last().name.clone(),
+                function.descriptive_name.clone(),
+            );
+        }
 
-        FfiNameProvider { names, prefix }
+        FfiNameProvider {
+            names,
+            function_names,
+            collisions: Vec::new(),
+            prefix,
+        }
     }
 
     pub fn testing() -> Self {
         FfiNameProvider {
             names: HashSet::new(),
+            function_names: BTreeMap::new(),
+            collisions: Vec::new(),
             prefix: String::new(),
         }
     }
@@ -141,16 +204,51 @@ impl FfiNameProvider {
         self.names.insert(full_name);
         CppPath::from_item(item)
     }
+
+    /// Creates a path for an FFI function. The exported name is
+    /// the prefix followed by a hash of the descriptive name,
+    /// so it stays short for functions of nested template types.
+    /// Returns the path and the descriptive name.
+    pub fn create_function_path(&mut self, name: &str) -> (CppPath, String) {
+        let descriptive_name = self.create_path(name).last().name.clone();
+        let short_name = format!("{}_{:016x}", self.prefix, stable_hash(&descriptive_name));
+        match self.function_names.get(&short_name) {
+            Some(other) if other != &descriptive_name => {
+                self.collisions
+                    .push((other.clone(), descriptive_name.clone()));
+            }
+            _ => {
+                self.function_names
+                    .insert(short_name.clone(), descriptive_name.clone());
+            }
+        }
+        (CppPath::from_good_str(&short_name), descriptive_name)
+    }
+
+    /// Fails if the hashes of any two descriptive names were the same.
+    pub fn check_collisions(&self) -> Result<()> {
+        if let Some((first, second)) = self.collisions.first() {
+            bail!(
+                "FFI function names {} and {} produce the same hash",
+                first,
+                second
+            );
+        }
+        Ok(())
+    }
 }
 
 /// Runs the FFI generator
 pub fn run(data: &mut ProcessorData<'_>) -> Result<()> {
-    // movable types are not supported for now
-    let movable_types = Vec::new();
+    let movable_types = data.db.stack_allocated_types();
 
     let mut name_provider = FfiNameProvider::new(data);
+    let debug_stream_type = data.config.debug_stream_type().clone();
 
     let all_cpp_item_ids = data.db.cpp_item_ids().collect_vec();
+    // rejections are updated once after processing all items
+    let mut processed_names = HashSet::new();
+    let mut rejections = Vec::new();
 
     for cpp_item_id in all_cpp_item_ids {
         let item = data.db.cpp_item(&cpp_item_id)?;
@@ -161,14 +259,63 @@ pub fn run(data: &mut ProcessorData<'_>) -> Result<()> {
             trace!("skipping {}: {}", item.item, err);
             continue;
         }
+        let class_path = match &item.item {
+            CppItem::Function(function) if function.member.is_some() => function.class_path().ok(),
+            CppItem::ClassField(field) => field.path.parent().ok(),
+            _ => None,
+        };
+        if class_path
+            .as_ref()
+            .map_or(false, |path| data.db.is_dependency_type(path))
+        {
+            // wrappers for members of the dependency's classes
+            // are provided by the dependency
+            trace!("skipping {}: class belongs to a dependency", item.item);
+            continue;
+        }
+        if class_path
+            .as_ref()
+            .map_or(false, |path| data.config.type_mapping(path).is_some())
+        {
+            trace!("skipping {}: class is mapped to a Rust type", item.item);
+            continue;
+        }
+        if class_path
+            .as_ref()
+            .map_or(false, |path| data.config.is_opaque(path))
+        {
+            trace!("skipping {}: class is opaque", item.item);
+            continue;
+        }
+        let cpp_name = item.item.short_text();
         let result = match &item.item {
-            CppItem::Function(method) => {
-                generate_ffi_methods_for_method(method, &movable_types, &mut name_provider)
-                    .map(|v| v.into_iter().collect_vec())
-            }
-            CppItem::ClassField(field) => {
-                generate_field_accessors(field, &movable_types, &mut name_provider)
-                    .map(|v| v.into_iter().collect_vec())
+            CppItem::Function(method) => check_opaque_types(method, data.config)
+                .and_then(|()| detect_closure_callback(method, data))
+                .and_then(|closure_callback| {
+                    generate_ffi_methods_for_method(
+                        method,
+                        closure_callback,
+                        &movable_types,
+                        data.config.flags_templates(),
+                        &debug_stream_type,
+                        &mut name_provider,
+                    )
+                })
+                .map(|v| v.into_iter().collect_vec()),
+            CppItem::ClassField(field) => generate_field_accessors(
+                field,
+                &movable_types,
+                data.config.flags_templates(),
+                &mut name_provider,
+            )
+            .map(|v| v.into_iter().collect_vec()),
+            CppItem::Type(type1) if type1.kind.is_class() => {
+                match data.config.string_type(&type1.path) {
+                    Some(conversion) => {
+                        generate_string_conversions(&type1.path, conversion, &mut name_provider)
+                    }
+                    None => continue,
+                }
             }
             CppItem::ClassBase(_)
             | CppItem::Type(_)
@@ -185,30 +332,353 @@ pub fn run(data: &mut ProcessorData<'_>) -> Result<()> {
                     "failed to add FFI item for {} {}: {}",
                     item.id, item.item, error
                 );
+                processed_names.insert(cpp_name.clone());
+                rejections.push(Rejection {
+                    cpp_name,
+                    step: "cpp_ffi_generator".to_string(),
+                    reason: error.iter_chain().join(": "),
+                    include_file: None,
+                });
             }
             Ok(r) => {
                 let source_id = item.id;
+                processed_names.insert(cpp_name);
                 for new_item in r {
                     data.db.add_ffi_item(Some(source_id.clone()), new_item)?;
                 }
             }
         }
     }
-    Ok(())
+    data.db
+        .clear_item_rejections("cpp_ffi_generator", &processed_names);
+    for rejection in rejections {
+        data.db.add_rejection(rejection);
+    }
+    name_provider.check_collisions()?;
+
+    let config = data.config;
+    user_hooks::run(data, config.ffi_filters())
 }
 
 fn generate_ffi_methods_for_method(
     method: &CppFunction,
+    closure_callback: Option<CppClosureCallback>,
     movable_types: &[CppPath],
+    flags_templates: &[CppPath],
+    debug_stream_type: &CppPath,
     name_provider: &mut FfiNameProvider,
 ) -> Result<Vec<CppFfiItem>> {
-    Ok(vec![CppFfiItem::Function(to_ffi_method(
+    let mut function = to_ffi_method(
         NewFfiFunctionKind::Function {
             cpp_function: method.clone(),
         },
         movable_types,
+        flags_templates,
         name_provider,
-    )?)])
+    )?;
+    if let Some(callback) = closure_callback {
+        function = convert_to_closure_callback(function, callback)?;
+    }
+    let mut methods = vec![CppFfiItem::Function(function)];
+    if let Some(class_path) = stream_output_class(method, debug_stream_type) {
+        methods.push(CppFfiItem::Function(generate_stream_output_function(
+            &class_path,
+            name_provider,
+        )?));
+    }
+    Ok(methods)
+}
+
+/// Detects a function pointer argument of `function` followed by a `void*`
+/// argument named as configured with `Config::add_callback_user_data_name`.
+fn detect_closure_callback(
+    function: &CppFunction,
+    data: &ProcessorData<'_>,
+) -> Result<Option<CppClosureCallback>> {
+    let names = data.config.callback_user_data_names();
+    if names.is_empty() || function.is_constructor() || function.is_destructor() {
+        return Ok(None);
+    }
+    let void_ptr = CppType::new_pointer(false, CppType::Void);
+    for (index, pair) in function.arguments.windows(2).enumerate() {
+        let function_type = match &pair[0].argument_type {
+            CppType::FunctionPointer(function_type) => function_type,
+            _ => continue,
+        };
+        if pair[1].argument_type != void_ptr || !names.contains(&pair[1].name) {
+            continue;
+        }
+        let user_data_indexes = function_type
+            .arguments
+            .iter()
+            .positions(|arg| arg == &void_ptr)
+            .collect_vec();
+        if user_data_indexes.len() != 1 {
+            bail!(
+                "callback argument {} must take exactly one void* argument",
+                pair[0].name
+            );
+        }
+        let unregister = match data.config.callback_unregister_function(&function.path) {
+            Some(path) => {
+                if !function.return_type.is_void() {
+                    bail!("function with an unregister function must return void");
+                }
+                let unregister_function = data
+                    .db
+                    .cpp_items()
+                    .filter_map(|item| item.item.as_function_ref())
+                    .find(|f| &f.path == path)
+                    .ok_or_else(|| {
+                        format_err!(
+                            "unregister function not found: {}",
+                            path.to_cpp_pseudo_code()
+                        )
+                    })?;
+                if unregister_function.member.is_some() && !unregister_function.is_static_member() {
+                    bail!("unregister function can't be a non-static member function");
+                }
+                let arguments =
+                    unregister_function
+                        .arguments
+                        .iter()
+                        .map_if_ok(|arg| -> Result<_> {
+                            if arg.argument_type == pair[0].argument_type {
+                                Ok(CppCallbackUnregisterArgument::Callback)
+                            } else if arg.argument_type == void_ptr {
+                                Ok(CppCallbackUnregisterArgument::UserData)
+                            } else {
+                                bail!("unsupported argument of unregister function: {}", arg.name)
+                            }
+                        })?;
+                Some((path.clone(), arguments))
+            }
+            None => None,
+        };
+        return Ok(Some(CppClosureCallback {
+            callback_index: index,
+            user_data_index: index + 1,
+            function_type: function_type.clone(),
+            callback_user_data_index: user_data_indexes[0],
+            unregister,
+        }));
+    }
+    Ok(None)
+}
+
+/// Replaces the function pointer and the user data arguments of `function`
+/// with `callback`, `deleter` and `data` arguments receiving a closure.
+/// If the callback has an unregister function, the FFI function returns
+/// the object owning the closure.
+pub fn convert_to_closure_callback(
+    mut function: CppFfiFunction,
+    callback: CppClosureCallback,
+) -> Result<CppFfiFunction> {
+    let void_ptr = CppType::new_pointer(false, CppType::Void);
+    let mut callback_arguments = callback.closure_arguments();
+    callback_arguments.insert(0, void_ptr.clone());
+    let callback_type = CppType::FunctionPointer(CppFunctionPointerType {
+        return_type: callback.function_type.return_type.clone(),
+        arguments: callback_arguments,
+        allows_variadic_arguments: false,
+    });
+    let deleter_type = CppType::FunctionPointer(CppFunctionPointerType {
+        return_type: Box::new(CppType::Void),
+        arguments: vec![void_ptr.clone()],
+        allows_variadic_arguments: false,
+    });
+
+    function.arguments.retain(|arg| {
+        arg.meaning != CppFfiArgumentMeaning::Argument(callback.callback_index)
+            && arg.meaning != CppFfiArgumentMeaning::Argument(callback.user_data_index)
+    });
+    let new_arguments = vec![
+        ("callback", callback_type),
+        ("deleter", deleter_type),
+        ("data", void_ptr.clone()),
+    ];
+    for (name, argument_type) in new_arguments {
+        function.arguments.push(CppFfiFunctionArgument {
+            name: name.to_string(),
+            argument_type: CppFfiType::new(argument_type, CppToFfiTypeConversion::NoChange)?,
+            meaning: CppFfiArgumentMeaning::ClosureCallback,
+        });
+    }
+    if callback.unregister.is_some() {
+        function.return_type = CppFfiType::new(void_ptr, CppToFfiTypeConversion::NoChange)?;
+    }
+    function.kind = CppFfiFunctionKind::ClosureCallback { callback };
+    Ok(function)
+}
+
+/// Returns the printed class if `function` is a free `operator<<`
+/// writing to `stream_type`, e.g. `std::ostream& operator<<(std::ostream&, const T&)`
+/// or `QDebug operator<<(QDebug, const T&)`.
+pub fn stream_output_class(function: &CppFunction, stream_type: &CppPath) -> Option<CppPath> {
+    if function.member.is_some()
+        || function.operator != Some(CppOperator::BitwiseLeftShift)
+        || function.arguments.len() != 2
+    {
+        return None;
+    }
+    let stream_type = stream_type.to_templateless_string();
+    let stream_class = match &function.arguments[0].argument_type {
+        CppType::PointerLike {
+            kind: CppPointerLikeTypeKind::Reference,
+            is_const: false,
+            target,
+        } => &**target,
+        // `QDebug` is passed by value
+        other => other,
+    };
+    match stream_class {
+        CppType::Class(path) if path.to_templateless_string() == stream_type => {}
+        _ => return None,
+    }
+    let value_type = match &function.arguments[1].argument_type {
+        CppType::PointerLike {
+            kind: CppPointerLikeTypeKind::Reference,
+            is_const: true,
+            target,
+        } => &**target,
+        other => other,
+    };
+    match value_type {
+        CppType::Class(path) if !value_type.is_or_contains_template_parameter() => {
+            Some(path.clone())
+        }
+        _ => None,
+    }
+}
+
+fn size_t_type() -> CppType {
+    CppType::PointerSizedInteger {
+        path: CppPath::from_good_str("size_t"),
+        is_signed: false,
+    }
+}
+
+/// Creates an FFI function that passes UTF-8 text produced from a `class_path` value
+/// to the `write` callback:
+/// `void f(const T* value, void* data, void (*write)(void*, const char*, size_t))`.
+fn generate_text_output_function(
+    class_path: &CppPath,
+    name_suffix: &str,
+    kind: CppFfiFunctionKind,
+    name_provider: &mut FfiNameProvider,
+) -> Result<CppFfiFunction> {
+    let write_type = CppType::FunctionPointer(CppFunctionPointerType {
+        return_type: Box::new(CppType::Void),
+        arguments: vec![
+            CppType::new_pointer(false, CppType::Void),
+            CppType::new_pointer(true, CppType::BuiltInNumeric(CppBuiltInNumericType::Char)),
+            size_t_type(),
+        ],
+        allows_variadic_arguments: false,
+    });
+    let value_type = CppType::new_pointer(true, CppType::Class(class_path.clone()));
+    let (path, descriptive_name) = name_provider.create_function_path(&format!(
+        "{}_{}",
+        class_path.ascii_caption(),
+        name_suffix
+    ));
+    Ok(CppFfiFunction {
+        arguments: vec![
+            CppFfiFunctionArgument {
+                name: "value".to_string(),
+                argument_type: CppFfiType::new(value_type, CppToFfiTypeConversion::NoChange)?,
+                meaning: CppFfiArgumentMeaning::Argument(1),
+            },
+            CppFfiFunctionArgument {
+                name: "data".to_string(),
+                argument_type: CppFfiType::new(
+                    CppType::new_pointer(false, CppType::Void),
+                    CppToFfiTypeConversion::NoChange,
+                )?,
+                meaning: CppFfiArgumentMeaning::OutputCallback,
+            },
+            CppFfiFunctionArgument {
+                name: "write".to_string(),
+                argument_type: CppFfiType::new(write_type, CppToFfiTypeConversion::NoChange)?,
+                meaning: CppFfiArgumentMeaning::OutputCallback,
+            },
+        ],
+        return_type: CppFfiType::void(),
+        path,
+        descriptive_name,
+        allocation_place: ReturnValueAllocationPlace::NotApplicable,
+        kind,
+        is_direct_binding: false,
+    })
+}
+
+/// Creates an FFI function that prints a `class_path` value using `operator<<`
+/// and passes the resulting UTF-8 text to the `write` callback.
+fn generate_stream_output_function(
+    class_path: &CppPath,
+    name_provider: &mut FfiNameProvider,
+) -> Result<CppFfiFunction> {
+    generate_text_output_function(
+        class_path,
+        "debug",
+        CppFfiFunctionKind::StreamOutput,
+        name_provider,
+    )
+}
+
+/// Creates FFI functions converting string class `class_path` from and to UTF-8:
+/// `T* f(const char* data, size_t len)` returning a new object and
+/// `void f(const T* value, void* data, void (*write)(void*, const char*, size_t))`.
+fn generate_string_conversions(
+    class_path: &CppPath,
+    conversion: &StringConversion,
+    name_provider: &mut FfiNameProvider,
+) -> Result<Vec<CppFfiItem>> {
+    let (path, descriptive_name) =
+        name_provider.create_function_path(&format!("{}_from_utf8", class_path.ascii_caption()));
+    let from_utf8 = CppFfiFunction {
+        arguments: vec![
+            CppFfiFunctionArgument {
+                name: "data".to_string(),
+                argument_type: CppFfiType::new(
+                    CppType::new_pointer(
+                        true,
+                        CppType::BuiltInNumeric(CppBuiltInNumericType::Char),
+                    ),
+                    CppToFfiTypeConversion::NoChange,
+                )?,
+                meaning: CppFfiArgumentMeaning::Argument(0),
+            },
+            CppFfiFunctionArgument {
+                name: "len".to_string(),
+                argument_type: CppFfiType::new(size_t_type(), CppToFfiTypeConversion::NoChange)?,
+                meaning: CppFfiArgumentMeaning::Argument(1),
+            },
+        ],
+        return_type: CppFfiType::new(
+            CppType::new_pointer(false, CppType::Class(class_path.clone())),
+            CppToFfiTypeConversion::NoChange,
+        )?,
+        path,
+        descriptive_name,
+        allocation_place: ReturnValueAllocationPlace::Heap,
+        kind: CppFfiFunctionKind::StringFromUtf8 {
+            constructor: conversion.from_utf8_ctor.clone(),
+        },
+        is_direct_binding: false,
+    };
+    let to_utf8 = generate_text_output_function(
+        class_path,
+        "to_utf8",
+        CppFfiFunctionKind::StringToUtf8 {
+            method: conversion.to_utf8_method.clone(),
+        },
+        name_provider,
+    )?;
+    Ok(vec![
+        CppFfiItem::Function(from_utf8),
+        CppFfiItem::Function(to_utf8),
+    ])
 }
 
 pub enum NewFfiFunctionKind {
@@ -229,6 +699,7 @@ pub enum NewFfiFunctionKind {
 pub fn to_ffi_method(
     kind: NewFfiFunctionKind,
     movable_types: &[CppPath],
+    flags_templates: &[CppPath],
     name_provider: &mut FfiNameProvider,
 ) -> Result<CppFfiFunction> {
     let ascii_caption = match &kind {
@@ -248,10 +719,25 @@ pub fn to_ffi_method(
         }
     };
 
+    let direct_binding_name = match &kind {
+        NewFfiFunctionKind::Function { cpp_function }
+            if can_bind_directly(cpp_function, flags_templates)? =>
+        {
+            Some(cpp_function.path.last().name.clone())
+        }
+        _ => None,
+    };
+
+    let (path, descriptive_name) = match &direct_binding_name {
+        Some(name) => (CppPath::from_good_str(name), name.clone()),
+        None => name_provider.create_function_path(&ascii_caption),
+    };
+
     let mut r = CppFfiFunction {
         arguments: Vec::new(),
         return_type: CppFfiType::void(),
-        path: name_provider.create_path(&ascii_caption),
+        path,
+        descriptive_name,
         allocation_place: ReturnValueAllocationPlace::NotApplicable,
         kind: match kind {
             NewFfiFunctionKind::Function { .. } => CppFfiFunctionKind::Function,
@@ -259,6 +745,7 @@ pub fn to_ffi_method(
                 CppFfiFunctionKind::FieldAccessor { accessor_type }
             }
         },
+        is_direct_binding: direct_binding_name.is_some(),
     };
 
     let this_arg_type = match &kind {
@@ -289,7 +776,7 @@ pub fn to_ffi_method(
     if let Some(this_arg_type) = this_arg_type {
         r.arguments.push(CppFfiFunctionArgument {
             name: "this_ptr".to_string(),
-            argument_type: ffi_type(&this_arg_type, CppTypeRole::NotReturnType)?,
+            argument_type: ffi_type(&this_arg_type, CppTypeRole::NotReturnType, flags_templates)?,
             meaning: CppFfiArgumentMeaning::This,
         });
     }
@@ -330,7 +817,11 @@ pub fn to_ffi_method(
     };
 
     for (index, arg) in normal_args.iter().enumerate() {
-        let c_type = ffi_type(&arg.argument_type, CppTypeRole::NotReturnType)?;
+        let c_type = ffi_type(
+            &arg.argument_type,
+            CppTypeRole::NotReturnType,
+            flags_templates,
+        )?;
         r.arguments.push(CppFfiFunctionArgument {
             name: arg.name.clone(),
             argument_type: c_type,
@@ -359,10 +850,11 @@ pub fn to_ffi_method(
             CppFieldAccessorType::Setter => CppType::Void,
         },
     };
-    let real_return_type_ffi = ffi_type(&real_return_type, CppTypeRole::ReturnType)?;
+    let real_return_type_ffi =
+        ffi_type(&real_return_type, CppTypeRole::ReturnType, flags_templates)?;
     match &real_return_type {
-        // QFlags is converted to uint in FFI
-        CppType::Class(path) if !is_qflags(path) => {
+        // flags types are converted to int in FFI
+        CppType::Class(path) if !is_flags_type(path, flags_templates) => {
             if movable_types.iter().any(|t| t == path) {
                 r.arguments.push(CppFfiFunctionArgument {
                     name: "output".to_string(),
@@ -383,10 +875,47 @@ pub fn to_ffi_method(
     Ok(r)
 }
 
+/// Checks if `function` can be called from Rust directly by its symbol name,
+/// i.e. it has C linkage and its signature doesn't need any FFI conversion.
+/// Functions with C linkage are assumed not to throw exceptions,
+/// so they don't need an exception shim either.
+fn can_bind_directly(function: &CppFunction, flags_templates: &[CppPath]) -> Result<bool> {
+    if !function.is_extern_c
+        || function.member.is_some()
+        || function.allows_variadic_arguments
+        || function.path.last().template_arguments.is_some()
+        || function.return_type.is_function_pointer()
+    {
+        return Ok(false);
+    }
+    for arg in &function.arguments {
+        // function pointer types can't be spelled in the generated reference
+        // to the function, so a wrapper is used instead
+        if arg.argument_type.is_function_pointer() {
+            return Ok(false);
+        }
+        let ffi_type = ffi_type(
+            &arg.argument_type,
+            CppTypeRole::NotReturnType,
+            flags_templates,
+        )?;
+        if ffi_type.conversion() != &CppToFfiTypeConversion::NoChange {
+            return Ok(false);
+        }
+    }
+    let return_type = ffi_type(
+        &function.return_type,
+        CppTypeRole::ReturnType,
+        flags_templates,
+    )?;
+    Ok(return_type.conversion() == &CppToFfiTypeConversion::NoChange)
+}
+
 /// Adds fictional getter and setter methods for each known public field of each class.
 fn generate_field_accessors(
     field: &CppClassField,
     movable_types: &[CppPath],
+    flags_templates: &[CppPath],
     name_provider: &mut FfiNameProvider,
 ) -> Result<Vec<CppFfiItem>> {
     let mut new_methods = Vec::new();
@@ -395,25 +924,48 @@ fn generate_field_accessors(
             field: field.clone(),
             accessor_type,
         };
-        let ffi_function = to_ffi_method(kind, movable_types, name_provider)?;
+        let ffi_function = to_ffi_method(kind, movable_types, flags_templates, name_provider)?;
         Ok(CppFfiItem::Function(ffi_function))
     };
 
     if field.visibility == CppVisibility::Public {
         // Classes may be non-copyable, so copy getters may not be possible for them,
         // so we generate reference getters instead.
-        if field.field_type.is_class() {
+        if field.field_type.is_class() || field.field_type.is_array() {
             new_methods.push(create_method(CppFieldAccessorType::ConstRefGetter)?);
-            new_methods.push(create_method(CppFieldAccessorType::MutRefGetter)?);
+            if !field.is_const {
+                new_methods.push(create_method(CppFieldAccessorType::MutRefGetter)?);
+            }
         } else {
             new_methods.push(create_method(CppFieldAccessorType::CopyGetter)?);
         }
-        new_methods.push(create_method(CppFieldAccessorType::Setter)?);
+        // arrays and const fields are not assignable
+        if !field.field_type.is_array() && !field.is_const {
+            new_methods.push(create_method(CppFieldAccessorType::Setter)?);
+        }
     }
 
     Ok(new_methods)
 }
 
+/// Fails if `function` takes or returns by value a class
+/// made opaque by `Config::set_opaque`.
+fn check_opaque_types(function: &CppFunction, config: &Config) -> Result<()> {
+    let types = function
+        .arguments
+        .iter()
+        .map(|arg| &arg.argument_type)
+        .chain(Some(&function.return_type));
+    for type1 in types {
+        if let CppType::Class(path) = type1 {
+            if config.is_opaque(path) {
+                bail!("opaque type {} is used by value", path.to_cpp_pseudo_code());
+            }
+        }
+    }
+    Ok(())
+}
+
 fn check_preconditions(item: &CppItem, source_ffi_item: Option<DbItem<&CppFfiItem>>) -> Result<()> {
     match item {
         CppItem::Function(function) => {
@@ -424,6 +976,9 @@ fn check_preconditions(item: &CppItem, source_ffi_item: Option<DbItem<&CppFfiIte
                 if membership.visibility == CppVisibility::Protected {
                     bail!("function is protected");
                 }
+                if membership.is_deleted {
+                    bail!("function is deleted");
+                }
                 if membership.is_signal {
                     if source_ffi_item.map_or(false, |item| item.item.is_signal_wrapper()) {
                         // Allow signal of the signal wrapper class.
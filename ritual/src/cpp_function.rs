@@ -1,6 +1,6 @@
 //! Types for handling information about C++ methods.
 
-use crate::cpp_data::{CppPath, CppPathItem, CppVisibility};
+use crate::cpp_data::{CppDeprecation, CppPath, CppPathItem, CppVisibility};
 use crate::cpp_ffi_data::CppCast;
 pub use crate::cpp_operator::{CppOperator, CppOperatorInfo};
 use crate::cpp_type::{CppPointerLikeTypeKind, CppType};
@@ -28,15 +28,7 @@ pub struct CppFunctionArgument {
 impl CppFunctionArgument {
     /// Generates C++ code for the argument declaration
     pub fn to_cpp_code(&self) -> Result<String> {
-        if let CppType::FunctionPointer(..) = self.argument_type {
-            Ok(self.argument_type.to_cpp_code(Some(&self.name))?)
-        } else {
-            Ok(format!(
-                "{} {}",
-                self.argument_type.to_cpp_code(None)?,
-                self.name
-            ))
-        }
+        self.argument_type.to_cpp_declaration(&self.name)
     }
 }
 
@@ -71,6 +63,29 @@ pub struct CppFunctionMemberData {
     pub is_signal: bool,
     /// True if the method is a Qt slot
     pub is_slot: bool,
+    /// True if the method is explicitly deleted (`= delete`)
+    pub is_deleted: bool,
+    /// True if the method is explicitly defaulted (`= default`)
+    pub is_defaulted: bool,
+    /// True if the method is marked as `final`
+    pub is_final: bool,
+    /// True if the method overrides a virtual method of a base class
+    /// (with or without the `override` specifier)
+    pub is_override: bool,
+    /// True if the constructor or the conversion operator is marked as `explicit`
+    pub is_explicit: bool,
+    /// True if the method is not declared in the class and is
+    /// implicitly declared by the compiler
+    #[serde(default)]
+    pub is_implicit: bool,
+    /// Base class that declares the method if the method was copied
+    /// from the base class to a derived class
+    #[serde(default)]
+    pub inherited_from: Option<CppPath>,
+    /// True if the method is inherited with the same signature from multiple
+    /// unrelated base classes, so its name is ambiguous in the derived class
+    #[serde(default)]
+    pub is_ambiguous: bool,
 }
 
 impl CppFunctionMemberData {
@@ -78,6 +93,7 @@ impl CppFunctionMemberData {
         self.kind == other.kind
             && self.is_const == other.is_const
             && self.is_static == other.is_static
+            && self.inherited_from == other.inherited_from
     }
 }
 
@@ -121,6 +137,17 @@ pub struct CppFunction {
     /// C++ code of the method's declaration.
     /// None if the method was not explicitly declared.
     pub declaration_code: Option<String>,
+    /// True if the function is declared with an unconditional `noexcept`
+    /// (or `throw()`) exception specification. Conditional `noexcept(expr)`
+    /// is treated as potentially throwing.
+    pub is_noexcept: bool,
+    /// Deprecation info if the function is marked as deprecated
+    pub deprecated: Option<CppDeprecation>,
+    /// Raw documentation comment attached to the declaration
+    pub doc_comment: Option<String>,
+    /// True if the function has C language linkage (e.g. it's declared
+    /// inside an `extern "C"` block)
+    pub is_extern_c: bool,
 }
 
 /// Chosen type allocation place for the method
@@ -155,7 +182,9 @@ impl CppFunctionKind {
 }
 
 impl CppFunction {
-    /// Checks if two methods have exactly the same set of input argument types
+    /// Checks if two methods have exactly the same set of input argument types.
+    /// Argument names and default values are ignored. Top-level `const` qualifiers
+    /// of arguments are not a part of the signature and are not stored in `CppType`.
     pub fn argument_types_equal(&self, other: &CppFunction) -> bool {
         if self.arguments.len() != other.arguments.len() {
             return false;
@@ -250,6 +279,31 @@ impl CppFunction {
         s.trim().to_string()
     }
 
+    /// Returns text identifying the function among all functions of the library:
+    /// its full name, types of arguments and constness (e.g. `A::f(int, bool) const`).
+    /// Unlike `short_text`, it doesn't change when other properties
+    /// of the function (e.g. names of arguments) change.
+    pub fn signature_text(&self) -> String {
+        let mut arguments = self
+            .arguments
+            .iter()
+            .map(|arg| arg.argument_type.to_cpp_pseudo_code())
+            .collect_vec();
+        if self.allows_variadic_arguments {
+            arguments.push("...".to_string());
+        }
+        format!(
+            "{}({}){}",
+            self.path.to_cpp_pseudo_code(),
+            arguments.join(", "),
+            if self.member.as_ref().map_or(false, |info| info.is_const) {
+                " const"
+            } else {
+                ""
+            }
+        )
+    }
+
     /// Returns short text representing values in this method
     /// (only for debugging output).
     pub fn short_text(&self) -> String {
@@ -277,6 +331,30 @@ impl CppFunction {
             if info.is_slot {
                 s = format!("{} [slot]", s);
             }
+            if info.is_deleted {
+                s = format!("{} [deleted]", s);
+            }
+            if info.is_defaulted {
+                s = format!("{} [defaulted]", s);
+            }
+            if info.is_override {
+                s = format!("{} [override]", s);
+            }
+            if info.is_final {
+                s = format!("{} [final]", s);
+            }
+            if info.is_explicit {
+                s = format!("{} [explicit]", s);
+            }
+            if info.is_implicit {
+                s = format!("{} [implicit]", s);
+            }
+            if let Some(base) = &info.inherited_from {
+                s = format!("{} [inherited from {}]", s, base.to_cpp_pseudo_code());
+            }
+            if info.is_ambiguous {
+                s = format!("{} [ambiguous]", s);
+            }
             match info.kind {
                 CppFunctionKind::Constructor => s = format!("{} [constructor]", s),
                 CppFunctionKind::Destructor => s = format!("{} [destructor]", s),
@@ -310,6 +388,9 @@ impl CppFunction {
                 s = format!("{} const", s);
             }
         }
+        if self.is_noexcept {
+            s = format!("{} noexcept", s);
+        }
         s.trim().to_string()
     }
 
@@ -332,6 +413,24 @@ impl CppFunction {
         arg == self.arguments[0].argument_type
     }
 
+    pub fn is_move_constructor(&self) -> bool {
+        if !self.is_constructor() {
+            return false;
+        }
+        if self.arguments.len() != 1 {
+            return false;
+        }
+        let arg = CppType::new_rvalue_reference(CppType::Class(self.class_path().unwrap()));
+        arg == self.arguments[0].argument_type
+    }
+
+    /// Returns true if any of the arguments is an rvalue reference.
+    pub fn has_rvalue_reference_arguments(&self) -> bool {
+        self.arguments
+            .iter()
+            .any(|arg| arg.argument_type.is_rvalue_reference())
+    }
+
     /// Returns true if this method is a destructor.
     pub fn is_destructor(&self) -> bool {
         match &self.member {
@@ -362,6 +461,73 @@ impl CppFunction {
         }
     }
 
+    pub fn is_protected(&self) -> bool {
+        match &self.member {
+            Some(info) => info.visibility == CppVisibility::Protected,
+            None => false,
+        }
+    }
+
+    /// Returns true if this method is implicitly declared by the compiler.
+    pub fn is_implicit(&self) -> bool {
+        match &self.member {
+            Some(info) => info.is_implicit,
+            None => false,
+        }
+    }
+
+    /// Returns the base class that declares this method if the method
+    /// was copied from the base class.
+    pub fn inherited_from(&self) -> Option<&CppPath> {
+        self.member.as_ref().and_then(|m| m.inherited_from.as_ref())
+    }
+
+    /// Returns true if this method is inherited from multiple base classes
+    /// with the same signature.
+    pub fn is_ambiguous(&self) -> bool {
+        self.member.as_ref().map_or(false, |m| m.is_ambiguous)
+    }
+
+    /// Returns true if this method is explicitly deleted.
+    pub fn is_deleted(&self) -> bool {
+        match &self.member {
+            Some(info) => info.is_deleted,
+            None => false,
+        }
+    }
+
+    /// Returns true if this method is explicitly defaulted.
+    pub fn is_defaulted(&self) -> bool {
+        match &self.member {
+            Some(info) => info.is_defaulted,
+            None => false,
+        }
+    }
+
+    /// Returns true if this method is marked as `final`.
+    pub fn is_final(&self) -> bool {
+        match &self.member {
+            Some(info) => info.is_final,
+            None => false,
+        }
+    }
+
+    /// Returns true if this method overrides a virtual method of a base class.
+    pub fn is_override(&self) -> bool {
+        match &self.member {
+            Some(info) => info.is_override,
+            None => false,
+        }
+    }
+
+    /// Returns true if this constructor or conversion operator is marked as `explicit`.
+    pub fn is_explicit(&self) -> bool {
+        match &self.member {
+            Some(info) => info.is_explicit,
+            None => false,
+        }
+    }
+
     pub fn is_signal(&self) -> bool {
         match &self.member {
             Some(info) => info.is_signal,
@@ -1,16 +1,242 @@
-use crate::cpp_data::{CppItem, CppPathItem, CppVisibility};
+use crate::config::ImplicitMethod;
+use crate::cpp_data::{CppItem, CppPath, CppPathItem, CppVisibility};
 use crate::cpp_function::{
     CppFunction, CppFunctionArgument, CppFunctionKind, CppFunctionMemberData,
 };
 use crate::cpp_operator::CppOperator;
-use crate::cpp_type::CppType;
+use crate::cpp_type::{CppPointerLikeTypeKind, CppType};
 use crate::database::ItemWithSource;
 use crate::processor::ProcessorData;
+use log::trace;
 use ritual_common::errors::Result;
 
-/// Adds constructors and destructors for every class that does not have explicitly
-/// defined constructor or destructor, allowing to create wrappings for
-/// constructors and destructors implicitly available in C++.
+/// Creates a public member of `class_path` that is implicitly declared by the compiler.
+fn implicit_method(
+    class_path: &CppPath,
+    name: &str,
+    kind: CppFunctionKind,
+    operator: Option<CppOperator>,
+    return_type: CppType,
+    arguments: Vec<CppFunctionArgument>,
+) -> CppFunction {
+    CppFunction {
+        path: class_path.join(CppPathItem::from_good_str(name)),
+        member: Some(CppFunctionMemberData {
+            is_virtual: false, // the destructor can actually be virtual but we don't care about it here
+            is_pure_virtual: false,
+            is_const: false,
+            is_static: false,
+            visibility: CppVisibility::Public,
+            is_signal: false,
+            is_slot: false,
+            kind,
+            is_deleted: false,
+            is_defaulted: false,
+            is_final: false,
+            is_override: false,
+            is_explicit: false,
+            is_implicit: true,
+            inherited_from: None,
+            is_ambiguous: false,
+        }),
+        operator,
+        return_type,
+        arguments,
+        allows_variadic_arguments: false,
+        cast: None,
+        declaration_code: None,
+        is_noexcept: false,
+        deprecated: None,
+        doc_comment: None,
+        is_extern_c: false,
+    }
+}
+
+/// Returns the type of the single argument of the copy constructor and
+/// the copy assignment operator of `class_path`.
+fn copy_argument_type(class_path: &CppPath) -> CppType {
+    CppType::new_reference(true, CppType::Class(class_path.clone()))
+}
+
+/// Checks if `function` is a user-declared copy or move assignment operator of its class.
+fn is_assignment(function: &CppFunction, is_move: bool) -> bool {
+    if function.operator != Some(CppOperator::Assignment) || function.arguments.len() != 1 {
+        return false;
+    }
+    let class_type = match function.class_path() {
+        Ok(path) => CppType::Class(path),
+        Err(_) => return false,
+    };
+    match &function.arguments[0].argument_type {
+        CppType::PointerLike { kind, target, .. } => {
+            **target == class_type
+                && (*kind == CppPointerLikeTypeKind::RValueReference) == is_move
+                && *kind != CppPointerLikeTypeKind::Pointer
+        }
+        other => !is_move && *other == class_type,
+    }
+}
+
+/// Checks if `function` is a user-declared copy constructor of its class,
+/// with or without `const` qualifier of the argument.
+fn is_any_copy_constructor(function: &CppFunction) -> bool {
+    if !function.is_constructor() || function.arguments.len() != 1 {
+        return false;
+    }
+    let class_type = match function.class_path() {
+        Ok(path) => CppType::Class(path),
+        Err(_) => return false,
+    };
+    match &function.arguments[0].argument_type {
+        CppType::PointerLike {
+            kind: CppPointerLikeTypeKind::Reference,
+            target,
+            ..
+        } => **target == class_type,
+        _ => false,
+    }
+}
+
+/// Checks if `function` declares `method` of its class.
+fn declares(function: &CppFunction, method: ImplicitMethod) -> bool {
+    match method {
+        ImplicitMethod::DefaultConstructor => {
+            function.is_constructor() && function.arguments.iter().all(|arg| arg.has_default_value)
+        }
+        ImplicitMethod::CopyConstructor => is_any_copy_constructor(function),
+        ImplicitMethod::CopyAssignment => is_assignment(function, false),
+        ImplicitMethod::Destructor => function.is_destructor(),
+    }
+}
+
+/// Checks if `function` is a move constructor or a move assignment operator.
+fn is_move_operation(function: &CppFunction) -> bool {
+    function.is_move_constructor() || is_assignment(function, true)
+}
+
+/// Information about a class relevant for detecting its implicitly declared members.
+struct ClassMembers<'a> {
+    /// Functions explicitly declared in the class
+    functions: Vec<&'a CppFunction>,
+    /// Types of non-static fields
+    field_types: Vec<&'a CppType>,
+    /// True if any non-static field is `const`
+    has_const_fields: bool,
+    /// Direct base classes
+    bases: Vec<&'a CppPath>,
+}
+
+impl<'a> ClassMembers<'a> {
+    fn new(class_path: &CppPath, data: &'a ProcessorData<'_>) -> Self {
+        let functions = data
+            .db
+            .all_cpp_methods_of(class_path)
+            .map(|f| f.item)
+            .filter(|f| !f.is_implicit())
+            .collect();
+        let fields = data
+            .db
+            .all_cpp_items_by_parent_path(class_path)
+            .filter_map(|item| item.item.as_field_ref())
+            .filter(|f| !f.is_static)
+            .collect::<Vec<_>>();
+        let bases = data
+            .db
+            .all_cpp_bases_of(class_path)
+            .map(|base| &base.item.base_class_type)
+            .collect();
+        ClassMembers {
+            functions,
+            field_types: fields.iter().map(|f| &f.field_type).collect(),
+            has_const_fields: fields.iter().any(|f| f.is_const),
+            bases,
+        }
+    }
+
+    /// Checks if `method` is declared in the class and can't be used
+    /// by a derived class or a class containing it as a field.
+    fn is_unusable(&self, method: ImplicitMethod) -> bool {
+        let declared = self
+            .functions
+            .iter()
+            .filter(|f| declares(f, method))
+            .collect::<Vec<_>>();
+        if declared.is_empty() {
+            return match method {
+                // declaring any constructor removes the implicit default constructor
+                ImplicitMethod::DefaultConstructor => {
+                    self.functions.iter().any(|f| f.is_constructor())
+                }
+                // declaring a move operation deletes implicit copy operations
+                ImplicitMethod::CopyConstructor | ImplicitMethod::CopyAssignment => {
+                    self.functions.iter().any(|f| is_move_operation(f))
+                }
+                ImplicitMethod::Destructor => false,
+            };
+        }
+        declared.iter().all(|f| f.is_deleted() || f.is_private())
+    }
+
+    /// Returns paths of base classes and types of class fields.
+    fn subobject_classes(&self) -> impl Iterator<Item = &'a CppPath> + '_ {
+        let field_classes = self.field_types.iter().filter_map(|t| {
+            let mut t: &'a CppType = *t;
+            while let CppType::Array { element_type, .. } = t {
+                t = element_type;
+            }
+            if let CppType::Class(path) = t {
+                Some(path)
+            } else {
+                None
+            }
+        });
+        self.bases.iter().cloned().chain(field_classes)
+    }
+
+    fn has_reference_fields(&self, kind: CppPointerLikeTypeKind) -> bool {
+        self.field_types
+            .iter()
+            .any(|t| matches!(t, CppType::PointerLike { kind: k, .. } if *k == kind))
+    }
+
+    /// Checks if C++ implicitly declares `method` for this class and it's not deleted.
+    /// The check is conservative and doesn't cover all C++ rules,
+    /// so the C++ checker still verifies that the method can be used.
+    fn has_implicit(&self, method: ImplicitMethod, data: &ProcessorData<'_>) -> bool {
+        if self.functions.iter().any(|f| declares(f, method)) {
+            return false;
+        }
+        let is_allowed = match method {
+            ImplicitMethod::DefaultConstructor => {
+                !self.functions.iter().any(|f| f.is_constructor())
+                    && !self.has_reference_fields(CppPointerLikeTypeKind::Reference)
+                    && !self.has_reference_fields(CppPointerLikeTypeKind::RValueReference)
+                    && !self.has_const_fields
+            }
+            ImplicitMethod::CopyConstructor => {
+                !self.functions.iter().any(|f| is_move_operation(f))
+                    && !self.has_reference_fields(CppPointerLikeTypeKind::RValueReference)
+            }
+            ImplicitMethod::CopyAssignment => {
+                !self.functions.iter().any(|f| is_move_operation(f))
+                    && !self.has_reference_fields(CppPointerLikeTypeKind::Reference)
+                    && !self.has_reference_fields(CppPointerLikeTypeKind::RValueReference)
+                    && !self.has_const_fields
+            }
+            ImplicitMethod::Destructor => true,
+        };
+        is_allowed
+            && !self
+                .subobject_classes()
+                .any(|path| ClassMembers::new(path, data).is_unusable(method))
+    }
+}
+
+/// Adds implicitly declared default constructors, copy constructors,
+/// copy assignment operators and destructors of classes, allowing to
+/// create wrappings for special members implicitly available in C++.
+/// The added methods are marked as implicit, and they are only exposed
+/// if the C++ checker confirms that they are available.
 pub fn run(data: &mut ProcessorData<'_>) -> Result<()> {
     let mut methods = Vec::new();
 
@@ -21,110 +247,68 @@ pub fn run(data: &mut ProcessorData<'_>) -> Result<()> {
         .filter(|item| item.item.kind.is_class());
 
     for type1 in classes {
-        if type1.item.kind.is_class() {
-            let class_path = &type1.item.path;
-
-            let destructor = CppFunction {
-                path: class_path.join(CppPathItem::from_good_str(&format!(
-                    "~{}",
-                    class_path.last().name
-                ))),
-                member: Some(CppFunctionMemberData {
-                    is_virtual: false, // the destructor can actually be virtual but we don't care about it here
-                    is_pure_virtual: false,
-                    is_const: false,
-                    is_static: false,
-                    visibility: CppVisibility::Public,
-                    is_signal: false,
-                    is_slot: false,
-                    kind: CppFunctionKind::Destructor,
-                }),
-                operator: None,
-                return_type: CppType::Void,
-                arguments: vec![],
-                allows_variadic_arguments: false,
-                cast: None,
-                declaration_code: None,
-            };
-            methods.push(ItemWithSource::new(&type1.id, destructor));
-
-            let default_constructor = CppFunction {
-                path: type1
-                    .item
-                    .path
-                    .join(CppPathItem::from_good_str(&class_path.last().name)),
-                member: Some(CppFunctionMemberData {
-                    is_virtual: false,
-                    is_pure_virtual: false,
-                    is_const: false,
-                    is_static: false,
-                    visibility: CppVisibility::Public,
-                    is_signal: false,
-                    is_slot: false,
-                    kind: CppFunctionKind::Constructor,
-                }),
-                operator: None,
-                return_type: CppType::Void,
-                arguments: vec![],
-                allows_variadic_arguments: false,
-                declaration_code: None,
-                cast: None,
-            };
-            methods.push(ItemWithSource::new(&type1.id, default_constructor));
-
+        let class_path = &type1.item.path;
+        let class_name = &class_path.last().name;
+        let members = ClassMembers::new(class_path, data);
+        let all_methods = [
+            ImplicitMethod::Destructor,
+            ImplicitMethod::DefaultConstructor,
+            ImplicitMethod::CopyConstructor,
+            ImplicitMethod::CopyAssignment,
+        ];
+        for &method in &all_methods {
+            let is_available = data
+                .config
+                .implicit_method_override(class_path, method)
+                .unwrap_or_else(|| members.has_implicit(method, data));
+            if !is_available {
+                trace!(
+                    "no implicit {:?} in {}",
+                    method,
+                    class_path.to_cpp_pseudo_code()
+                );
+                continue;
+            }
             let copy_arg = CppFunctionArgument {
-                argument_type: CppType::new_reference(true, CppType::Class(class_path.clone())),
+                argument_type: copy_argument_type(class_path),
                 name: "other".to_string(),
                 has_default_value: false,
             };
-
-            let copy_constructor = CppFunction {
-                path: type1
-                    .item
-                    .path
-                    .join(CppPathItem::from_good_str(&class_path.last().name)),
-                member: Some(CppFunctionMemberData {
-                    is_virtual: false,
-                    is_pure_virtual: false,
-                    is_const: false,
-                    is_static: false,
-                    visibility: CppVisibility::Public,
-                    is_signal: false,
-                    is_slot: false,
-                    kind: CppFunctionKind::Constructor,
-                }),
-                operator: None,
-                return_type: CppType::Void,
-                arguments: vec![copy_arg.clone()],
-                allows_variadic_arguments: false,
-                cast: None,
-                declaration_code: None,
-            };
-            methods.push(ItemWithSource::new(&type1.id, copy_constructor));
-
-            let assignment_operator = CppFunction {
-                path: type1
-                    .item
-                    .path
-                    .join(CppPathItem::from_good_str("operator=")),
-                member: Some(CppFunctionMemberData {
-                    is_virtual: false,
-                    is_pure_virtual: false,
-                    is_const: false,
-                    is_static: false,
-                    visibility: CppVisibility::Public,
-                    is_signal: false,
-                    is_slot: false,
-                    kind: CppFunctionKind::Regular,
-                }),
-                operator: Some(CppOperator::Assignment),
-                return_type: CppType::new_reference(false, CppType::Class(class_path.clone())),
-                arguments: vec![copy_arg],
-                allows_variadic_arguments: false,
-                cast: None,
-                declaration_code: None,
+            let function = match method {
+                ImplicitMethod::Destructor => implicit_method(
+                    class_path,
+                    &format!("~{}", class_name),
+                    CppFunctionKind::Destructor,
+                    None,
+                    CppType::Void,
+                    vec![],
+                ),
+                ImplicitMethod::DefaultConstructor => implicit_method(
+                    class_path,
+                    class_name,
+                    CppFunctionKind::Constructor,
+                    None,
+                    CppType::Void,
+                    vec![],
+                ),
+                ImplicitMethod::CopyConstructor => implicit_method(
+                    class_path,
+                    class_name,
+                    CppFunctionKind::Constructor,
+                    None,
+                    CppType::Void,
+                    vec![copy_arg],
+                ),
+                ImplicitMethod::CopyAssignment => implicit_method(
+                    class_path,
+                    "operator=",
+                    CppFunctionKind::Regular,
+                    Some(CppOperator::Assignment),
+                    CppType::new_reference(false, CppType::Class(class_path.clone())),
+                    vec![copy_arg],
+                ),
             };
-            methods.push(ItemWithSource::new(&type1.id, assignment_operator));
+            methods.push(ItemWithSource::new(&type1.id, function));
         }
     }
     for item in methods {
@@ -1,50 +1,94 @@
 #![allow(dead_code)]
 
-use crate::cpp_data::{CppPath, CppVisibility};
+use crate::config::InheritedMethodsStrategy;
+use crate::cpp_data::{CppClassField, CppItem, CppPath, CppPathItem, CppVisibility};
+use crate::cpp_ffi_data::{
+    CppFfiItem, CppSubclassFunction, CppSubclassShim, CppToFfiTypeConversion,
+};
+use crate::cpp_ffi_generator::ffi_type;
 use crate::cpp_function::CppFunction;
+use crate::cpp_operator::CppOperator;
+use crate::cpp_type::{CppFunctionPointerType, CppPointerLikeTypeKind, CppType, CppTypeRole};
+use crate::database::ItemWithSource;
 use crate::processor::ProcessorData;
 use itertools::Itertools;
-use log::trace;
+use log::{trace, warn};
 use ritual_common::errors::*;
+use ritual_common::utils::MapIfOk;
+use std::collections::{HashMap, HashSet};
+use std::iter::once;
 
 /// Checks if `class_name` types inherits `base_name` type directly or indirectly.
 pub fn inherits(class_name: &CppPath, base_name: &CppPath, data: &ProcessorData<'_>) -> bool {
-    let bases = data
+    data.db.all_cpp_bases_of(class_name).any(|base| {
+        &base.item.base_class_type == base_name
+            || inherits(&base.item.base_class_type, base_name, data)
+    })
+}
+
+/// Checks if `class_name` type is polymorphic, i.e. it has a virtual function
+/// or inherits one. Only polymorphic types can be used in `dynamic_cast`.
+pub fn is_polymorphic(class_name: &CppPath, data: &ProcessorData<'_>) -> bool {
+    let has_virtual_functions = data
         .db
-        .all_cpp_items()
-        .filter_map(|item| item.filter_map(|item| item.as_base_ref()))
-        .map(|item| item.item);
+        .all_cpp_methods_of(class_name)
+        .any(|f| f.item.is_virtual());
+    if has_virtual_functions {
+        return true;
+    }
+    data.db
+        .all_cpp_bases_of(class_name)
+        .any(|base| is_polymorphic(&base.item.base_class_type, data))
+}
 
-    for base in bases {
-        if &base.derived_class_type == class_name {
-            if &base.base_class_type == base_name {
-                return true;
-            }
-            if inherits(&base.base_class_type, base_name, data) {
-                return true;
-            }
-        }
+/// Checks if `method` can be overridden in a subclass, i.e. it's virtual and
+/// neither the method nor its class is marked as `final`.
+pub fn is_overridable(method: &CppFunction, data: &ProcessorData<'_>) -> bool {
+    if !method.is_virtual() || method.is_final() {
+        return false;
     }
-    false
+    let class_path = match method.class_path() {
+        Ok(path) => path,
+        Err(_) => return false,
+    };
+    !data
+        .db
+        .all_cpp_items_by_path(&class_path)
+        .filter_map(|item| item.item.as_type_ref())
+        .any(|t| t.kind.is_final_class())
+}
+
+/// Checks if `method` of a base class can be copied to derived classes.
+fn is_inheritable(method: &CppFunction) -> bool {
+    method
+        .member
+        .as_ref()
+        .map_or(false, |m| m.kind.is_regular())
+        && !method.is_private()
+        && method.operator != Some(CppOperator::Assignment)
 }
 
-fn detect_inherited_methods2(data: &ProcessorData<'_>) -> Result<Vec<CppFunction>> {
+/// Copies methods of public base classes to derived classes according to
+/// `using` declarations of derived classes and `Config::inherited_methods_strategy`.
+fn detect_inherited_methods(data: &ProcessorData<'_>) -> Result<Vec<ItemWithSource<CppFunction>>> {
+    let strategy = data.config.inherited_methods_strategy();
     let mut remaining_classes = data
         .db
         .all_cpp_items()
         .filter_map(|item| item.item.as_base_ref())
-        .filter(|b| b.visibility != CppVisibility::Private)
+        .filter(|b| b.visibility == CppVisibility::Public)
         .collect_vec();
 
     let mut ordered_classes = Vec::new();
     while !remaining_classes.is_empty() {
         let mut any_added = false;
         let mut remaining_classes2 = Vec::new();
+        let derived_classes = remaining_classes
+            .iter()
+            .map(|c| &c.derived_class_type)
+            .collect::<HashSet<_>>();
         for class in &remaining_classes {
-            if remaining_classes
-                .iter()
-                .any(|c| c.derived_class_type == class.base_class_type)
-            {
+            if derived_classes.contains(&class.base_class_type) {
                 remaining_classes2.push(*class);
             } else {
                 ordered_classes.push(*class);
@@ -57,24 +101,500 @@ fn detect_inherited_methods2(data: &ProcessorData<'_>) -> Result<Vec<CppFunction
         }
     }
 
-    let mut result = Vec::new();
+    let mut result = Vec::<ItemWithSource<CppFunction>>::new();
+    // indexes of `result` items, by class path
+    let mut result_by_class = HashMap::<CppPath, Vec<usize>>::new();
+    let mut imported = HashSet::new();
     for class in ordered_classes {
+        let derived_type = data
+            .db
+            .cpp_items_by_path(&class.derived_class_type)
+            .find_map(|item| item.item.as_type_ref());
+        let derived_type = match derived_type {
+            Some(t) => t,
+            // only classes of the current crate receive inherited methods
+            None => continue,
+        };
+        let using_declarations = derived_type
+            .kind
+            .using_declarations()
+            .iter()
+            .filter(|u| u.base_class_path == class.base_class_type)
+            .collect_vec();
         trace!("Detecting inherited methods for {:?}\n", class);
-        let methods = data
+        let db_methods = data
+            .db
+            .all_cpp_methods_of(&class.base_class_type)
+            .map(|m| ItemWithSource::new(&m.id, m.item.clone()));
+        // methods the base class inherited from its own bases
+        let inherited_methods = result_by_class
+            .get(&class.base_class_type)
+            .into_iter()
+            .flatten()
+            .map(|&index| &result[index])
+            .map(|m| ItemWithSource::new(&m.source_id, m.item.clone()));
+        let methods = db_methods.chain(inherited_methods).collect_vec();
+        let derived_methods = data
             .db
-            .all_cpp_items()
-            .filter_map(|item| item.item.as_function_ref())
-            .filter(|m| m.class_path_parts().ok() == Some(class.base_class_type.items()));
+            .all_cpp_methods_of(&class.derived_class_type)
+            .map(|m| m.item.clone())
+            .collect_vec();
 
         for method in methods {
-            let mut new_method = (*method).clone();
-            new_method.path = class.base_class_type.join(method.path.last().clone());
+            let mut new_method = method.item.clone();
+            if method.item.is_constructor() {
+                // `using Base::Base;` inherits constructors except for copy and move constructors
+                let is_inherited = using_declarations
+                    .iter()
+                    .any(|u| u.is_inheriting_constructors())
+                    && !method.item.is_private()
+                    && !method.item.is_deleted()
+                    && !method.item.is_copy_constructor()
+                    && !method.item.is_move_constructor()
+                    && !derived_methods
+                        .iter()
+                        .any(|m| m.is_constructor() && m.argument_types_equal(&method.item));
+                if !is_inherited {
+                    continue;
+                }
+                new_method.path = class.derived_class_type.join(CppPathItem::from_good_str(
+                    &class.derived_class_type.last().name,
+                ));
+            } else {
+                let name = &method.item.path.last().name;
+                let using_declaration = using_declarations
+                    .iter()
+                    .find(|u| &u.name == name && !u.is_inheriting_constructors());
+                if let Some(using_declaration) = using_declaration {
+                    // all overloads are brought into the derived class,
+                    // except for the ones it redeclares
+                    if method.item.is_private()
+                        || derived_methods
+                            .iter()
+                            .any(|m| has_same_signature(m, &method.item))
+                    {
+                        continue;
+                    }
+                    if let Some(member) = &mut new_method.member {
+                        member.visibility = using_declaration.visibility.clone();
+                    }
+                } else {
+                    if strategy == InheritedMethodsStrategy::Disabled
+                        || !is_inheritable(&method.item)
+                    {
+                        continue;
+                    }
+                    let is_hidden = derived_methods.iter().any(|m| match strategy {
+                        InheritedMethodsStrategy::Disabled => true,
+                        InheritedMethodsStrategy::Signatures => has_same_signature(m, &method.item),
+                        InheritedMethodsStrategy::NameHiding => m.path.last().name == *name,
+                    });
+                    if is_hidden {
+                        continue;
+                    }
+                }
+                new_method.path = class
+                    .derived_class_type
+                    .join(method.item.path.last().clone());
+            }
             new_method.declaration_code = None;
-            //new_method.is_fake_inherited_method = true;
+            if let Some(member) = &mut new_method.member {
+                if member.inherited_from.is_none() {
+                    member.inherited_from = Some(class.base_class_type.clone());
+                }
+            }
+            // a method of a shared base may be reachable through multiple paths,
+            // but it's only imported once
+            let key = (
+                new_method.path.clone(),
+                new_method.inherited_from().cloned(),
+                new_method
+                    .arguments
+                    .iter()
+                    .map(|arg| arg.argument_type.clone())
+                    .collect_vec(),
+                new_method.member.as_ref().map_or(false, |m| m.is_const),
+            );
+            if !imported.insert(key) {
+                continue;
+            }
             trace!("Method added: {}", new_method.short_text());
-            trace!("Base method: {}\n", method.short_text(),);
-            result.push(new_method);
+            trace!("Base method: {}\n", method.item.short_text());
+            result_by_class
+                .entry(class.derived_class_type.clone())
+                .or_default()
+                .push(result.len());
+            result.push(ItemWithSource::new(&method.source_id, new_method));
+        }
+    }
+
+    // the same signature inherited from unrelated bases is ambiguous in C++,
+    // so all such methods are kept and marked
+    let mut result_by_path = HashMap::<&CppPath, Vec<&ItemWithSource<CppFunction>>>::new();
+    for method in &result {
+        result_by_path
+            .entry(&method.item.path)
+            .or_default()
+            .push(method);
+    }
+    let ambiguous_indexes = result
+        .iter()
+        .positions(|method| {
+            !method.item.is_constructor()
+                && result_by_path[&method.item.path].iter().any(|other| {
+                    has_same_signature(&other.item, &method.item)
+                        && other.item.inherited_from() != method.item.inherited_from()
+                })
+        })
+        .collect_vec();
+    for index in ambiguous_indexes {
+        if let Some(member) = &mut result[index].item.member {
+            member.is_ambiguous = true;
         }
     }
     Ok(result)
 }
+
+/// Adds methods of base classes brought into derived classes by `using` declarations,
+/// and other methods of base classes if enabled by
+/// `Config::set_inherited_methods_strategy`.
+pub fn add_inherited_methods(data: &mut ProcessorData<'_>) -> Result<()> {
+    for method in detect_inherited_methods(data)? {
+        data.add_cpp_item(Some(method.source_id), CppItem::Function(method.item))?;
+    }
+    Ok(())
+}
+
+/// Checks if `function` has the same signature as `other`,
+/// so one of them overrides the other.
+fn has_same_signature(function: &CppFunction, other: &CppFunction) -> bool {
+    function.path.last().name == other.path.last().name
+        && function.argument_types_equal(other)
+        && function.member.as_ref().map(|m| m.is_const) == other.member.as_ref().map(|m| m.is_const)
+}
+
+/// Collects virtual functions of `class_path` and its public bases that can be
+/// overridden in a subclass. Functions overridden in a derived class
+/// are only reported once.
+fn overridable_functions(class_path: &CppPath, data: &ProcessorData<'_>) -> Vec<CppFunction> {
+    let mut result = Vec::<CppFunction>::new();
+    let mut classes = vec![class_path.clone()];
+    while !classes.is_empty() {
+        let mut bases = Vec::new();
+        for class in &classes {
+            for function in data.db.all_cpp_methods_of(class) {
+                let function = function.item;
+                let member = match &function.member {
+                    Some(member) => member,
+                    None => continue,
+                };
+                if !member.is_virtual || member.kind.is_destructor() {
+                    continue;
+                }
+                if result.iter().any(|f| has_same_signature(f, function)) {
+                    continue;
+                }
+                // a final function still hides base functions with the same signature
+                result.push(function.clone());
+            }
+            bases.extend(
+                data.db
+                    .all_cpp_bases_of(class)
+                    .filter(|base| base.item.visibility == CppVisibility::Public)
+                    .map(|base| base.item.base_class_type.clone()),
+            );
+        }
+        classes = bases;
+    }
+    result.retain(|f| is_overridable(f, data) && !f.is_private());
+    result
+}
+
+/// Returns `class_path` and its public bases, grouped by inheritance depth.
+fn public_class_hierarchy(class_path: &CppPath, data: &ProcessorData<'_>) -> Vec<Vec<CppPath>> {
+    let mut result = Vec::new();
+    let mut classes = vec![class_path.clone()];
+    while !classes.is_empty() {
+        let bases = classes
+            .iter()
+            .flat_map(|class| {
+                data.db
+                    .all_cpp_bases_of(class)
+                    .filter(|base| base.item.visibility == CppVisibility::Public)
+                    .map(|base| base.item.base_class_type.clone())
+            })
+            .collect_vec();
+        result.push(classes);
+        classes = bases;
+    }
+    result
+}
+
+/// Collects protected functions of `class_path` and its public bases that
+/// a subclass shim can forward. Overridable virtual functions are excluded
+/// because the shim already exposes them as `base_<name>` functions.
+/// Functions hidden by a function with the same name in a derived class
+/// are skipped.
+fn protected_functions(class_path: &CppPath, data: &ProcessorData<'_>) -> Vec<CppFunction> {
+    let mut result = Vec::<CppFunction>::new();
+    let mut hidden_names = HashSet::<String>::new();
+    for classes in public_class_hierarchy(class_path, data) {
+        let mut names = HashSet::new();
+        for class in &classes {
+            for function in data.db.all_cpp_methods_of(class) {
+                let function = function.item;
+                let name = &function.path.last().name;
+                if hidden_names.contains(name) {
+                    continue;
+                }
+                names.insert(name.clone());
+                let member = match &function.member {
+                    Some(member) => member,
+                    None => continue,
+                };
+                if !function.is_protected()
+                    || !member.kind.is_regular()
+                    || member.is_signal
+                    || member.is_deleted
+                    || function.is_operator()
+                    || function.allows_variadic_arguments
+                    || function.has_rvalue_reference_arguments()
+                    || function.path.last().template_arguments.is_some()
+                    || is_overridable(function, data)
+                {
+                    continue;
+                }
+                if result.iter().any(|f| has_same_signature(f, function)) {
+                    continue;
+                }
+                result.push(function.clone());
+            }
+        }
+        hidden_names.extend(names);
+    }
+    result
+}
+
+/// Checks if a field of `field_type` can be accessed by value
+/// through generated accessors.
+fn is_field_type_forwardable(field_type: &CppType) -> bool {
+    match field_type {
+        CppType::Void
+        | CppType::Class(_)
+        | CppType::TemplateParameter(_)
+        | CppType::Array { .. } => false,
+        CppType::PointerLike { kind, .. } => *kind == CppPointerLikeTypeKind::Pointer,
+        _ => true,
+    }
+}
+
+/// Collects protected fields of `class_path` and its public bases
+/// that a subclass shim can provide accessors for. Fields which accessors
+/// would clash with names of other members are skipped.
+fn protected_fields(
+    class_path: &CppPath,
+    protected_functions: &[CppFunction],
+    data: &ProcessorData<'_>,
+) -> Vec<CppClassField> {
+    let classes = public_class_hierarchy(class_path, data)
+        .into_iter()
+        .flatten()
+        .collect_vec();
+    let function_names = classes
+        .iter()
+        .flat_map(|class| data.db.all_cpp_methods_of(class))
+        .map(|f| f.item.path.last().name.clone())
+        .chain(
+            protected_functions
+                .iter()
+                .map(|f| f.path.last().name.clone()),
+        )
+        .collect::<HashSet<_>>();
+
+    let mut result = Vec::<CppClassField>::new();
+    for class in &classes {
+        let fields = data
+            .db
+            .all_cpp_items_by_parent_path(class)
+            .filter_map(|item| item.item.as_field_ref());
+        for field in fields {
+            let name = &field.path.last().name;
+            if field.visibility != CppVisibility::Protected
+                || !is_field_type_forwardable(&field.field_type)
+                || function_names.contains(name)
+                || function_names.contains(&format!("set_{}", name))
+                || result.iter().any(|f| &f.path.last().name == name)
+            {
+                continue;
+            }
+            result.push(field.clone());
+        }
+    }
+    result
+}
+
+/// Converts `function` to a virtual function of a subclass shim.
+fn subclass_function(
+    function: &CppFunction,
+    name: String,
+    flags_templates: &[CppPath],
+) -> Result<CppSubclassFunction> {
+    if function.allows_variadic_arguments {
+        bail!("variadic functions are not supported");
+    }
+    let arguments = function.arguments.iter().map_if_ok(|arg| {
+        ffi_type(
+            &arg.argument_type,
+            CppTypeRole::NotReturnType,
+            flags_templates,
+        )
+    })?;
+    for arg in &arguments {
+        match arg.conversion() {
+            CppToFfiTypeConversion::NoChange
+            | CppToFfiTypeConversion::ReferenceToPointer
+            | CppToFfiTypeConversion::QFlagsToInt => {}
+            other => bail!("unsupported argument conversion: {:?}", other),
+        }
+    }
+    let return_type = ffi_type(
+        &function.return_type,
+        CppTypeRole::ReturnType,
+        flags_templates,
+    )?;
+    match return_type.conversion() {
+        CppToFfiTypeConversion::NoChange
+        | CppToFfiTypeConversion::ValueToPointer { .. }
+        | CppToFfiTypeConversion::QFlagsToInt => {}
+        other => bail!("unsupported return type conversion: {:?}", other),
+    }
+    let function_type = CppFunctionPointerType {
+        return_type: Box::new(return_type.ffi_type().clone()),
+        arguments: once(CppType::new_pointer(false, CppType::Void))
+            .chain(arguments.iter().map(|t| t.ffi_type().clone()))
+            .collect(),
+        allows_variadic_arguments: false,
+    };
+    Ok(CppSubclassFunction {
+        function: function.clone(),
+        name,
+        arguments,
+        return_type,
+        function_type,
+    })
+}
+
+/// Generates a subclass shim for `base_class_path`.
+fn generate_subclass_shim(
+    base_class_path: &CppPath,
+    data: &ProcessorData<'_>,
+) -> Result<CppSubclassShim> {
+    let base_class = data
+        .db
+        .all_cpp_items_by_path(base_class_path)
+        .filter_map(|item| item.item.as_type_ref())
+        .find(|t| t.kind.is_class())
+        .ok_or_else(|| err_msg("class not found"))?;
+    if base_class.kind.is_final_class() {
+        bail!("class is final");
+    }
+    let class_path =
+        CppPath::from_good_str(&format!("{}Subclass", base_class_path.ascii_caption()));
+    if data
+        .db
+        .all_cpp_items_by_path(&class_path)
+        .any(|item| item.item.as_type_ref().is_some())
+    {
+        bail!("{} already exists", class_path.to_cpp_pseudo_code());
+    }
+
+    let constructors = data
+        .db
+        .all_cpp_methods_of(base_class_path)
+        .map(|f| f.item)
+        .filter(|f| {
+            f.is_constructor()
+                && !f.is_private()
+                && !f.is_deleted()
+                && !f.is_copy_constructor()
+                && !f.has_rvalue_reference_arguments()
+                && !f.allows_variadic_arguments
+        })
+        .cloned()
+        .collect_vec();
+
+    let mut functions = Vec::<CppSubclassFunction>::new();
+    for function in overridable_functions(base_class_path, data) {
+        let base_name = function.path.last().name.clone();
+        let overloads = functions
+            .iter()
+            .filter(|f| f.function.path.last().name == base_name)
+            .count();
+        let name = if overloads == 0 {
+            base_name
+        } else {
+            format!("{}_{}", base_name, overloads + 1)
+        };
+        match subclass_function(&function, name, data.config.flags_templates()) {
+            Ok(function) => functions.push(function),
+            Err(err) => {
+                let is_pure_virtual = function
+                    .member
+                    .as_ref()
+                    .map_or(false, |m| m.is_pure_virtual);
+                if is_pure_virtual {
+                    bail!(
+                        "pure virtual function can't be overridden: {}: {}",
+                        function.short_text(),
+                        err
+                    );
+                }
+                trace!("can't override {}: {}", function.short_text(), err);
+            }
+        }
+    }
+
+    let protected_functions = protected_functions(base_class_path, data);
+    let protected_fields = protected_fields(base_class_path, &protected_functions, data);
+
+    Ok(CppSubclassShim {
+        base_class_path: base_class_path.clone(),
+        class_path,
+        constructors,
+        functions,
+        protected_functions,
+        protected_fields,
+    })
+}
+
+/// Adds subclass shims for classes enabled by `Config::enable_subclassing`.
+pub fn add_subclass_shims(data: &mut ProcessorData<'_>) -> Result<()> {
+    let config = data.config;
+    for base_class_path in config.subclassing_classes() {
+        let found = data
+            .db
+            .all_ffi_items()
+            .filter_map(|item| item.item.as_subclass_shim_ref())
+            .any(|item| &item.base_class_path == base_class_path);
+        if found {
+            trace!(
+                "subclass shim already exists: {}",
+                base_class_path.to_cpp_pseudo_code()
+            );
+            continue;
+        }
+        match generate_subclass_shim(base_class_path, data) {
+            Ok(shim) => {
+                data.db.add_ffi_item(None, CppFfiItem::SubclassShim(shim))?;
+            }
+            Err(err) => {
+                warn!(
+                    "can't enable subclassing for {}: {}",
+                    base_class_path.to_cpp_pseudo_code(),
+                    err
+                );
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,168 @@
+//! Extraction of metadata from macros used inside class declarations,
+//! e.g. `Q_PROPERTY`. Such macros usually expand to nothing, so the information
+//! they carry is only available through the preprocessing record.
+//! See `Config::add_macro_extractor`.
+
+use crate::cpp_data::{CppProperty, CppTypeDeclaration, CppTypeDeclarationKind};
+use ritual_common::errors::{bail, Result};
+
+/// Expansion of a macro at class scope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CppMacroInstance {
+    /// Name of the macro
+    pub name: String,
+    /// Spellings of tokens between the parentheses of a function-like macro
+    pub tokens: Vec<String>,
+    /// Spellings of the arguments of a function-like macro.
+    /// Arguments are separated by commas that are not nested in parentheses,
+    /// brackets or braces, so an argument can contain a comma inside angle brackets.
+    pub arguments: Vec<String>,
+}
+
+impl CppMacroInstance {
+    /// Creates a macro instance from the spellings of all tokens of the expansion,
+    /// starting with the macro name.
+    pub fn from_tokens(tokens: &[String]) -> Result<Self> {
+        let name = match tokens.first() {
+            Some(name) => name.clone(),
+            None => bail!("empty macro expansion"),
+        };
+        if tokens.get(1).map(String::as_str) != Some("(") {
+            return Ok(CppMacroInstance {
+                name,
+                tokens: Vec::new(),
+                arguments: Vec::new(),
+            });
+        }
+        let mut level = 0;
+        let mut inner_tokens = Vec::new();
+        let mut arguments = Vec::new();
+        let mut current_argument = Vec::new();
+        for token in &tokens[2..] {
+            match token.as_str() {
+                "(" | "[" | "{" => level += 1,
+                ")" | "]" | "}" if level == 0 => {
+                    if !current_argument.is_empty() || !arguments.is_empty() {
+                        arguments.push(join_tokens(&current_argument));
+                    }
+                    return Ok(CppMacroInstance {
+                        name,
+                        tokens: inner_tokens,
+                        arguments,
+                    });
+                }
+                ")" | "]" | "}" => level -= 1,
+                "," if level == 0 => {
+                    arguments.push(join_tokens(&current_argument));
+                    current_argument.clear();
+                    inner_tokens.push(token.clone());
+                    continue;
+                }
+                _ => {}
+            }
+            inner_tokens.push(token.clone());
+            current_argument.push(token.clone());
+        }
+        bail!("unterminated macro arguments: {}", tokens.join(" "));
+    }
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Joins token spellings, only adding spaces between identifiers and numbers
+/// (e.g. `const QList<int>&`).
+pub fn join_tokens<S: AsRef<str>>(tokens: &[S]) -> String {
+    let mut result = String::new();
+    for token in tokens {
+        let token = token.as_ref();
+        let needs_space = result.chars().last().map_or(false, is_identifier_char)
+            && token.chars().next().map_or(false, is_identifier_char);
+        if needs_space {
+            result.push(' ');
+        }
+        result.push_str(token);
+    }
+    result
+}
+
+/// Attributes of `Q_PROPERTY` that are followed by a value
+const QT_PROPERTY_ATTRIBUTES_WITH_VALUES: &[&str] = &[
+    "READ",
+    "WRITE",
+    "MEMBER",
+    "RESET",
+    "NOTIFY",
+    "REVISION",
+    "DESIGNABLE",
+    "SCRIPTABLE",
+    "STORED",
+    "USER",
+    "BINDABLE",
+];
+
+/// Attributes of `Q_PROPERTY` without a value
+const QT_PROPERTY_FLAGS: &[&str] = &["CONSTANT", "FINAL", "REQUIRED"];
+
+/// Parses the contents of `Q_PROPERTY(type name READ getter WRITE setter ...)`.
+pub fn parse_qt_property(tokens: &[String]) -> Result<CppProperty> {
+    let is_attribute = |token: &String| {
+        QT_PROPERTY_ATTRIBUTES_WITH_VALUES.contains(&token.as_str())
+            || QT_PROPERTY_FLAGS.contains(&token.as_str())
+    };
+    let attributes_start = tokens
+        .iter()
+        .position(is_attribute)
+        .unwrap_or_else(|| tokens.len());
+    if attributes_start < 2 {
+        bail!("property type or name is missing: {}", tokens.join(" "));
+    }
+    let mut property = CppProperty {
+        name: tokens[attributes_start - 1].clone(),
+        property_type: join_tokens(&tokens[..attributes_start - 1]),
+        getter: None,
+        setter: None,
+        notify: None,
+    };
+    let mut attributes = tokens[attributes_start..].iter();
+    while let Some(attribute) = attributes.next() {
+        if QT_PROPERTY_FLAGS.contains(&attribute.as_str()) {
+            continue;
+        }
+        if !QT_PROPERTY_ATTRIBUTES_WITH_VALUES.contains(&attribute.as_str()) {
+            bail!("unexpected token in property declaration: {}", attribute);
+        }
+        let value = match attributes.next() {
+            Some(value) if !is_attribute(value) => value.clone(),
+            _ => bail!("missing value of property attribute {}", attribute),
+        };
+        match attribute.as_str() {
+            "READ" => property.getter = Some(value),
+            "WRITE" => property.setter = Some(value),
+            "NOTIFY" => property.notify = Some(value),
+            _ => {}
+        }
+    }
+    Ok(property)
+}
+
+/// Built-in macro extractor that records `Q_PROPERTY` declarations
+/// in the `properties` of the class.
+pub fn extract_qt_property(
+    instance: &CppMacroInstance,
+    declaration: &mut CppTypeDeclaration,
+) -> Result<()> {
+    if instance.name != "Q_PROPERTY" {
+        return Ok(());
+    }
+    let property = parse_qt_property(&instance.tokens)?;
+    if let CppTypeDeclarationKind::Class { properties, .. } = &mut declaration.kind {
+        if properties.iter().all(|p| p.name != property.name) {
+            properties.push(property);
+        }
+        Ok(())
+    } else {
+        bail!("Q_PROPERTY outside of class: {}", instance.tokens.join(" "));
+    }
+}
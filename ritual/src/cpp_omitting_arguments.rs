@@ -1,28 +1,101 @@
-use crate::cpp_data::CppItem;
+use crate::config::DefaultArgumentsStrategy;
+use crate::cpp_data::{CppItem, CppPath};
+use crate::cpp_function::CppFunction;
 use crate::database::ItemWithSource;
 use crate::processor::ProcessorData;
+use log::info;
 use ritual_common::errors::Result;
+use std::collections::HashMap;
 
+/// Returns variants of `function` with omitted trailing arguments that have
+/// default values. Variants omitting more arguments come first.
+fn omitted_arguments_variants(function: &CppFunction) -> Vec<CppFunction> {
+    let mut variants = Vec::new();
+    let mut function_copy = function.clone();
+    while let Some(arg) = function_copy.arguments.pop() {
+        if !arg.has_default_value {
+            break;
+        }
+        variants.push(function_copy.clone());
+    }
+    variants.reverse();
+    variants
+}
+
+/// Adds variants of functions with omitted arguments that have default values.
+///
+/// Variants are not added if the same signature is already declared or if
+/// variants of multiple functions have the same signature, because calling such
+/// a variant is ambiguous in C++.
 pub fn run(data: &mut ProcessorData<'_>) -> Result<()> {
-    let mut results = Vec::new();
+    if data.config.default_arguments_strategy() == DefaultArgumentsStrategy::Keep {
+        return Ok(());
+    }
+    let limit = data.config.omitted_arguments_variants_limit();
+
+    let mut existing = HashMap::<&CppPath, Vec<&CppFunction>>::new();
+    let mut candidates = Vec::new();
+    let mut limited_count = 0;
     for item in data.db.cpp_items() {
         let function = if let Some(f) = item.item.as_function_ref() {
             f
         } else {
             continue;
         };
+        existing.entry(&function.path).or_default().push(function);
 
-        if function.arguments.iter().any(|arg| arg.has_default_value) {
-            let mut function_copy = function.clone();
-            while let Some(arg) = function_copy.arguments.pop() {
-                if !arg.has_default_value {
-                    break;
-                }
-                results.push(ItemWithSource::new(&item.id, function_copy.clone()));
+        let mut variants = omitted_arguments_variants(function);
+        if let Some(limit) = limit {
+            if variants.len() > limit {
+                limited_count += variants.len() - limit;
+                variants.truncate(limit);
             }
         }
+        candidates.extend(
+            variants
+                .into_iter()
+                .map(|variant| ItemWithSource::new(&item.id, variant)),
+        );
     }
 
+    let candidates_count = candidates.len();
+    candidates.retain(|candidate| {
+        !existing
+            .get(&candidate.item.path)
+            .map_or(false, |functions| {
+                functions.iter().any(|f| f.is_same(&candidate.item))
+            })
+    });
+    let existing_count = candidates_count - candidates.len();
+
+    let mut by_path = HashMap::<&CppPath, Vec<&ItemWithSource<CppFunction>>>::new();
+    for candidate in &candidates {
+        by_path
+            .entry(&candidate.item.path)
+            .or_default()
+            .push(candidate);
+    }
+    let is_ambiguous = |candidate: &ItemWithSource<CppFunction>| {
+        by_path[&candidate.item.path].iter().any(|other| {
+            other.source_id != candidate.source_id && other.item.is_same(&candidate.item)
+        })
+    };
+    let ambiguous_flags = candidates.iter().map(is_ambiguous).collect::<Vec<_>>();
+    let results = candidates
+        .into_iter()
+        .zip(ambiguous_flags)
+        .filter(|(_, ambiguous)| !ambiguous)
+        .map(|(candidate, _)| candidate)
+        .collect::<Vec<_>>();
+
+    info!(
+        "Omitted arguments: {} variants added, {} already declared, {} ambiguous, {} over the limit",
+        results.len(),
+        existing_count,
+        candidates_count - existing_count - results.len(),
+        limited_count
+    );
+
     for item in results {
         data.add_cpp_item(Some(item.source_id), CppItem::Function(item.item))?;
     }
@@ -1,32 +1,40 @@
-use crate::config::Config;
+use crate::config::{Config, HeaderPathFilter};
 use crate::cpp_code_generator::{all_include_directives, write_include_directives};
 use crate::cpp_data::{
-    CppBaseSpecifier, CppClassField, CppEnumValue, CppItem, CppNamespace, CppOriginLocation,
-    CppPath, CppPathItem, CppTypeDeclaration, CppTypeDeclarationKind, CppVisibility,
+    CppBaseSpecifier, CppClassField, CppClassLayout, CppDeprecation, CppEnumValue, CppItem,
+    CppItemOrigin, CppNamespace, CppOriginLocation, CppPath, CppPathItem, CppTypeDeclaration,
+    CppTypeDeclarationKind, CppUsingDeclaration, CppVisibility,
 };
+use crate::cpp_ffi_generator::stable_hash;
 use crate::cpp_function::{
     CppFunction, CppFunctionArgument, CppFunctionKind, CppFunctionMemberData,
 };
+use crate::cpp_macros::CppMacroInstance;
 use crate::cpp_operator::CppOperator;
 use crate::cpp_type::{
     CppBuiltInNumericType, CppFunctionPointerType, CppPointerLikeTypeKind, CppSpecificNumericType,
     CppSpecificNumericTypeKind, CppTemplateParameter, CppType,
 };
-use crate::database::ItemId;
-use crate::processor::ProcessorData;
-use clang::diagnostic::{Diagnostic, Severity};
+use crate::database::{ItemId, Rejection};
+use crate::processor::{ProcessorData, USER_CONFIG_STEP};
+use crate::user_hooks;
+use clang::diagnostic::Severity;
 use clang::*;
 use itertools::Itertools;
-use log::{debug, trace, warn};
+use log::{debug, info, trace};
 use regex::Regex;
+use ritual_common::cpp_build_config::CppBuildPaths;
 use ritual_common::env_var_names;
-use ritual_common::errors::{bail, err_msg, format_err, print_trace, Result, ResultExt};
+use ritual_common::errors::{bail, err_msg, format_err, print_trace, Error, Result, ResultExt};
 use ritual_common::file_utils::{
-    canonicalize, copy_recursively, create_file, open_file, os_str_to_str, path_to_str,
-    remove_dir_all, remove_file,
+    canonicalize, copy_recursively, create_file, normalize_path, open_file, os_str_to_str,
+    path_to_str, remove_dir_all, remove_file,
 };
 use ritual_common::target::{current_env, current_target, Env, LibraryTarget};
-use ritual_common::utils::MapIfOk;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fs;
 use std::io::Write;
 use std::mem;
 use std::path::{Path, PathBuf};
@@ -77,6 +85,181 @@ struct CppParser<'b, 'a> {
     current_target_paths: Vec<PathBuf>,
     source_id: Option<ItemId>,
     output: CppParserOutput,
+    /// Classes that are only forward-declared in the processed headers
+    forward_declarations: Vec<ForwardDeclaration>,
+    /// Headers that didn't change since the previous run
+    unchanged_headers: HashSet<PathBuf>,
+    /// Macro expansions sorted by location. Only collected
+    /// if the config has macro extractors.
+    macro_expansions: Vec<MacroExpansion>,
+    /// Normalized paths of files containing entities, by presumed path
+    normalized_paths: RefCell<HashMap<String, PathBuf>>,
+}
+
+/// A macro expansion recorded by clang's detailed preprocessing record.
+struct MacroExpansion {
+    file_path: PathBuf,
+    /// Offset of the expansion in the file
+    offset: u32,
+    instance: CppMacroInstance,
+}
+
+/// Returns the file path and the offset of `location`.
+fn get_file_offset(location: SourceLocation<'_>) -> Option<(PathBuf, u32)> {
+    let location = location.get_file_location();
+    Some((location.file?.get_path(), location.offset))
+}
+
+/// Collects macro expansions in `translation_unit`, sorted by location.
+fn get_macro_expansions(translation_unit: Entity<'_>) -> Vec<MacroExpansion> {
+    let mut expansions = translation_unit
+        .get_children()
+        .into_iter()
+        .filter(|child| child.get_kind() == EntityKind::MacroExpansion)
+        .filter_map(|child| {
+            let range = child.get_range()?;
+            let (file_path, offset) = get_file_offset(range.get_start())?;
+            let tokens = range
+                .tokenize()
+                .iter()
+                .map(|token| token.get_spelling())
+                .collect_vec();
+            match CppMacroInstance::from_tokens(&tokens) {
+                Ok(instance) => Some(MacroExpansion {
+                    file_path,
+                    offset,
+                    instance,
+                }),
+                Err(err) => {
+                    trace!("failed to parse macro expansion: {}", err);
+                    None
+                }
+            }
+        })
+        .collect_vec();
+    expansions.sort_by(|a, b| (&a.file_path, a.offset).cmp(&(&b.file_path, b.offset)));
+    expansions
+}
+
+/// A class declaration without a definition.
+struct ForwardDeclaration {
+    path: CppPath,
+    include_file: String,
+    origin_location: CppOriginLocation,
+}
+
+/// Returns true if `entity` is declared inside an `extern "C"` block
+/// or with an `extern "C"` specifier.
+fn has_c_linkage(entity: Entity<'_>) -> bool {
+    let mut current = entity.get_lexical_parent();
+    while let Some(parent) = current {
+        match parent.get_kind() {
+            // older libclang versions report linkage specifications as unexposed declarations
+            EntityKind::LinkageSpec | EntityKind::UnexposedDecl => {
+                if let Some(range) = parent.get_range() {
+                    let tokens = range.tokenize();
+                    if tokens.len() >= 2 && tokens[0].get_spelling() == "extern" {
+                        return tokens[1].get_spelling() == "\"C\"";
+                    }
+                }
+            }
+            EntityKind::TranslationUnit => return false,
+            _ => {}
+        }
+        current = parent.get_lexical_parent();
+    }
+    false
+}
+
+/// Returns true if the declaration of `entity` has the `explicit` specifier.
+fn has_explicit_specifier(entity: Entity<'_>) -> bool {
+    if let Some(range) = entity.get_range() {
+        // the specifier must appear before the argument list
+        range
+            .tokenize()
+            .iter()
+            .map(|token| token.get_spelling())
+            .take_while(|spelling| spelling != "(")
+            .any(|spelling| spelling == "explicit")
+    } else {
+        false
+    }
+}
+
+/// Returns true if `entity` has a direct child of `kind`.
+fn has_child_of_kind(entity: Entity<'_>, kind: EntityKind) -> bool {
+    entity.get_children().iter().any(|c| c.get_kind() == kind)
+}
+
+/// Section of a Qt class declared with `signals:` or `slots:`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QtSection {
+    Signals,
+    Slots,
+    Other,
+}
+
+/// Determines the section declared by access specifier `entity`.
+/// `signals` and `slots` macros expand to plain access specifiers, so the source
+/// tokens of the specifier are checked. If `QT_ANNOTATE_ACCESS_SPECIFIER`
+/// is defined to produce annotations, they are used as well.
+fn get_qt_section_of_access_specifier(entity: Entity<'_>) -> QtSection {
+    for child in entity.get_children() {
+        if child.get_kind() == EntityKind::AnnotateAttr {
+            match child.get_name().as_deref() {
+                Some("qt_signal") => return QtSection::Signals,
+                Some("qt_slot") => return QtSection::Slots,
+                _ => {}
+            }
+        }
+    }
+    let tokens = entity.get_range().map_or_else(Vec::new, |range| {
+        range
+            .tokenize()
+            .iter()
+            .map(|token| token.get_spelling())
+            .collect()
+    });
+    if tokens.iter().any(|t| t == "signals" || t == "Q_SIGNALS") {
+        QtSection::Signals
+    } else if tokens.iter().any(|t| t == "slots" || t == "Q_SLOTS") {
+        QtSection::Slots
+    } else {
+        QtSection::Other
+    }
+}
+
+/// Returns the section of the class `method` is declared in.
+fn get_qt_section(method: Entity<'_>) -> QtSection {
+    let class = match method.get_lexical_parent() {
+        Some(parent) => parent,
+        None => return QtSection::Other,
+    };
+    let mut section = QtSection::Other;
+    for child in class.get_children() {
+        if child == method {
+            break;
+        }
+        if child.get_kind() == EntityKind::AccessSpecifier {
+            section = get_qt_section_of_access_specifier(child);
+        }
+    }
+    section
+}
+
+/// Returns layout of class `entity`, or `None` if clang can't compute it
+/// (e.g. for class templates).
+fn get_class_layout(entity: Entity<'_>) -> Option<CppClassLayout> {
+    if entity.get_kind() == EntityKind::ClassTemplate {
+        return None;
+    }
+    let clang_type = entity.get_type()?;
+    let size = clang_type.get_sizeof().ok()?;
+    Some(CppClassLayout {
+        size,
+        is_pod: clang_type.is_pod(),
+        align: clang_type.get_alignof().ok(),
+    })
 }
 
 /// Print representation of `entity` and its children to the log.
@@ -99,8 +282,14 @@ fn get_origin_location(entity: Entity<'_>) -> Result<CppOriginLocation> {
     match entity.get_location() {
         Some(loc) => {
             let location = loc.get_presumed_location();
+            // clang may report paths with mixed separators on Windows,
+            // so the path is normalized in the same way as in `entity_include_path`
+            let include_file_path = canonicalize(&location.0)
+                .ok()
+                .and_then(|path| path.to_str().map(ToString::to_string))
+                .unwrap_or(location.0);
             Ok(CppOriginLocation {
-                include_file_path: location.0,
+                include_file_path,
                 line: location.1,
                 column: location.2,
             })
@@ -146,6 +335,36 @@ fn get_template_arguments(entity: Entity<'_>) -> Option<Vec<CppType>> {
     }
 }
 
+/// Returns source code of default arguments of template type parameters of `entity`,
+/// or `None` for parameters without a default argument.
+fn get_template_default_spellings(entity: Entity<'_>) -> Vec<Option<String>> {
+    entity
+        .get_children()
+        .into_iter()
+        .filter(|c| c.get_kind() == EntityKind::TemplateTypeParameter)
+        .map(|c| {
+            let tokens = c.get_range()?.tokenize();
+            let index = tokens
+                .iter()
+                .position(|token| token.get_spelling() == "=")?;
+            let mut text = String::new();
+            let mut previous_is_word = false;
+            for token in &tokens[index + 1..] {
+                let spelling = token.get_spelling();
+                let is_word =
+                    matches!(token.get_kind(), TokenKind::Identifier | TokenKind::Keyword);
+                // the format matches type names reported by clang
+                if (is_word && previous_is_word) || spelling == "*" || spelling == "&" {
+                    text.push(' ');
+                }
+                text.push_str(&spelling);
+                previous_is_word = is_word;
+            }
+            Some(text)
+        })
+        .collect()
+}
+
 fn get_context_template_args(entity: Entity<'_>) -> Vec<CppType> {
     let mut current_entity = entity;
     let mut args = Vec::new();
@@ -202,6 +421,75 @@ fn get_path(entity: Entity<'_>) -> Result<CppPath> {
     Ok(CppPath::from_items(parts))
 }
 
+/// Converts accessibility of `entity` to `CppVisibility`.
+fn get_visibility(entity: Entity<'_>) -> CppVisibility {
+    match entity.get_accessibility().unwrap_or(Accessibility::Public) {
+        Accessibility::Public => CppVisibility::Public,
+        Accessibility::Protected => CppVisibility::Protected,
+        Accessibility::Private => CppVisibility::Private,
+    }
+}
+
+/// Parses a `using Base::name;` declaration inside a class.
+fn parse_using_declaration(entity: Entity<'_>) -> Result<CppUsingDeclaration> {
+    let name = entity
+        .get_name()
+        .ok_or_else(|| err_msg("using declaration has no name"))?;
+    let base = entity
+        .get_children()
+        .into_iter()
+        .find(|c| c.get_kind() == EntityKind::TypeRef)
+        .and_then(|c| c.get_reference())
+        .ok_or_else(|| err_msg("base class not found"))?;
+    match base.get_kind() {
+        EntityKind::ClassDecl | EntityKind::StructDecl => {}
+        other => bail!("unsupported base class kind: {:?}", other),
+    }
+    Ok(CppUsingDeclaration {
+        base_class_path: get_path(base)?,
+        name,
+        visibility: get_visibility(entity),
+    })
+}
+
+/// Returns deprecation info of `entity` if it's marked as deprecated.
+///
+/// The message is extracted from the tokens of the attribute, so it's not available
+/// if the attribute is produced by a macro (e.g. `Q_DECL_DEPRECATED`).
+fn get_deprecation(entity: Entity<'_>) -> Option<CppDeprecation> {
+    if entity.get_availability() != Availability::Deprecated {
+        return None;
+    }
+    let message = entity
+        .get_children()
+        .into_iter()
+        .filter(|child| child.get_kind() == EntityKind::UnexposedAttr)
+        .filter_map(|child| child.get_range())
+        .filter_map(|range| {
+            let tokens = range.tokenize();
+            let index = tokens.iter().position(|token| {
+                let spelling = token.get_spelling();
+                spelling == "deprecated" || spelling == "__deprecated__"
+            })?;
+            let parts = tokens[index + 1..]
+                .iter()
+                .skip_while(|token| token.get_spelling() == "(")
+                .take_while(|token| token.get_kind() == TokenKind::Literal)
+                .map(|token| {
+                    let spelling = token.get_spelling();
+                    spelling.trim_matches('"').replace("\\\"", "\"")
+                })
+                .collect_vec();
+            if parts.is_empty() {
+                None
+            } else {
+                Some(parts.join(""))
+            }
+        })
+        .next();
+    Some(CppDeprecation { message })
+}
+
 fn get_full_name_display(entity: Entity<'_>) -> String {
     match get_path(entity) {
         Ok(name) => name.to_cpp_pseudo_code(),
@@ -226,16 +514,68 @@ fn init_clang() -> Result<Clang> {
     Clang::new().map_err(|err| format_err!("clang init failed: {}", err))
 }
 
+/// Returns arguments passed to clang when parsing headers with `config`.
+pub fn clang_arguments(config: &Config, cpp_build_paths: &CppBuildPaths) -> Result<Vec<String>> {
+    let mut args = vec![
+        "-Xclang".to_string(),
+        "-detailed-preprocessing-record".to_string(),
+    ];
+    if current_env() != Env::Msvc {
+        args.push("-std=c++11".to_string());
+    }
+    if let Some(triple) = config.target_triple() {
+        args.push("-target".to_string());
+        args.push(triple.to_string());
+    }
+    if let Some(sysroot) = config.sysroot() {
+        args.push(format!("--sysroot={}", path_to_str(sysroot)?));
+    }
+    args.extend_from_slice(config.cpp_parser_arguments());
+    args.extend_from_slice(config.cpp_parser_only_arguments());
+    let mut cpp_build_paths = cpp_build_paths.clone();
+    cpp_build_paths.apply_env();
+    for dir in cpp_build_paths.include_paths() {
+        let str = path_to_str(dir)?;
+        args.push("-I".to_string());
+        args.push(str.to_string());
+    }
+    if let Ok(path) = ::std::env::var(env_var_names::CLANG_SYSTEM_INCLUDE_PATH) {
+        if !Path::new(&path).exists() {
+            config.progress_handler().warning(format!(
+                "{} environment variable is set to \"{}\" \
+                 but this path does not exist. This may result in parse errors related to system header includes.",
+                env_var_names::CLANG_SYSTEM_INCLUDE_PATH,
+                path
+            ));
+        }
+        args.push("-isystem".to_string());
+        args.push(path);
+    } else {
+        trace!("{} environment variable is not set. This may result in parse errors related to system header includes.", env_var_names::CLANG_SYSTEM_INCLUDE_PATH);
+    }
+    for dir in cpp_build_paths.framework_paths() {
+        let str = path_to_str(dir)?;
+        args.push("-F".to_string());
+        args.push(str.to_string());
+    }
+    Ok(args)
+}
+
 /// Runs `clang` parser with `config`.
 /// If `cpp_code` is specified, it's written to the C++ file before parsing it.
 /// If successful, calls `f` and passes the topmost entity (the translation unit)
 /// as its argument. Returns output value of `f` or an error.
+///
+/// Fatal clang errors abort parsing. Other clang errors are reported as warnings
+/// and returned as rejections attributed to `step`.
 fn run_clang<R, F: FnMut(Entity<'_>) -> Result<R>>(
     config: &Config,
+    cpp_build_paths: &CppBuildPaths,
     tmp_path: &Path,
+    step: &str,
     cpp_code: Option<String>,
     mut f: F,
-) -> Result<R> {
+) -> Result<(R, Vec<Rejection>)> {
     let clang = init_clang()?;
     let index = Index::new(&clang, false, false);
 
@@ -268,49 +608,23 @@ fn run_clang<R, F: FnMut(Entity<'_>) -> Result<R>>(
         }
     }
 
-    let mut args = vec![
-        "-Xclang".to_string(),
-        "-detailed-preprocessing-record".to_string(),
-    ];
-    if current_env() != Env::Msvc {
-        args.push("-std=c++11".to_string());
-    }
-    args.extend_from_slice(config.cpp_parser_arguments());
-    let mut cpp_build_paths = config.cpp_build_paths().clone();
-    cpp_build_paths.apply_env();
-    for dir in cpp_build_paths.include_paths() {
-        let str = path_to_str(dir)?;
-        args.push("-I".to_string());
-        args.push(str.to_string());
-    }
-    if let Ok(path) = ::std::env::var(env_var_names::CLANG_SYSTEM_INCLUDE_PATH) {
-        if !Path::new(&path).exists() {
-            warn!(
-                "{} environment variable is set to \"{}\" \
-                 but this path does not exist. This may result in parse errors related to system header includes.",
-                env_var_names::CLANG_SYSTEM_INCLUDE_PATH,
-                path
-            );
-        }
-        args.push("-isystem".to_string());
-        args.push(path);
-    } else {
-        trace!("{} environment variable is not set. This may result in parse errors related to system header includes.", env_var_names::CLANG_SYSTEM_INCLUDE_PATH);
-    }
-    for dir in config.cpp_build_paths().framework_paths() {
-        let str = path_to_str(dir)?;
-        args.push("-F".to_string());
-        args.push(str.to_string());
-    }
+    let args = clang_arguments(config, cpp_build_paths)?;
     debug!("clang arguments: {:?}", args);
 
     let tu = index
         .parser(&tmp_cpp_path)
         .arguments(&args)
+        .detailed_preprocessing_record(!config.macro_extractors().is_empty())
         .parse()
         .with_context(|_| "clang parse failed")?;
     let translation_unit = tu.get_entity();
-    assert_eq!(translation_unit.get_kind(), EntityKind::TranslationUnit);
+    if translation_unit.get_kind() != EntityKind::TranslationUnit {
+        bail!(
+            "unexpected kind of the root entity: {:?}",
+            translation_unit.get_kind()
+        );
+    }
+    let mut rejections = Vec::new();
     {
         let diagnostics = tu.get_diagnostics();
         if !diagnostics.is_empty() {
@@ -319,20 +633,36 @@ fn run_clang<R, F: FnMut(Entity<'_>) -> Result<R>>(
                 trace!("[DebugParser] {}", diag);
             }
         }
-        let should_print_error = |d: &Diagnostic<'_>| {
-            d.get_severity() == Severity::Error || d.get_severity() == Severity::Fatal
-        };
-        if diagnostics.iter().any(should_print_error) {
+        if diagnostics
+            .iter()
+            .any(|d| d.get_severity() == Severity::Fatal)
+        {
             bail!(
                 "fatal clang error:\n{}",
                 diagnostics.iter().map(ToString::to_string).join("\n")
             );
         }
+        for diag in &diagnostics {
+            if diag.get_severity() == Severity::Error {
+                config
+                    .progress_handler()
+                    .warning(format!("clang error: {}", diag));
+                let (file, line, column) = diag.get_location().get_presumed_location();
+                rejections.push(Rejection {
+                    cpp_name: format!("{}:{}:{}", file, line, column),
+                    step: step.to_string(),
+                    reason: format!("clang error: {}", diag.get_text()),
+                    include_file: canonicalize(&file)
+                        .ok()
+                        .and_then(|path| path.to_str().map(ToString::to_string)),
+                });
+            }
+        }
     }
     let result = f(translation_unit);
     remove_file(&tmp_cpp_path)?;
     remove_file(&global_file_path)?;
-    result
+    Ok((result?, rejections))
 }
 
 /// Runs the parser on specified data.
@@ -344,27 +674,177 @@ pub fn run(data: &mut ProcessorData<'_>) -> Result<()> {
             .config
             .target_include_paths()
             .iter()
-            .map_if_ok(canonicalize)?,
+            .map(|path| normalize_path(path))
+            .collect(),
         source_id: None,
+        unchanged_headers: if data.config.incremental_cpp_parser() {
+            remove_changed_headers(data)?
+        } else {
+            HashSet::new()
+        },
         data,
         output: Default::default(),
+        forward_declarations: Vec::new(),
+        macro_expansions: Vec::new(),
+        normalized_paths: RefCell::default(),
     };
     parser
         .current_target_paths
         .push(canonicalize(parser.data.workspace.tmp_path())?.join("extra"));
-    run_clang(
+    // entities of unchanged headers are not parsed again,
+    // so their rejections are kept
+    let unchanged_headers = &parser.unchanged_headers;
+    parser.data.db.delete_rejections(|r| {
+        (r.step == "cpp_parser" || r.step == USER_CONFIG_STEP)
+            && !r
+                .include_file
+                .as_ref()
+                .map_or(false, |path| unchanged_headers.contains(Path::new(path)))
+    });
+    let cpp_build_paths = parser.data.cpp_build_paths();
+    let ((), rejections) = run_clang(
         &parser.data.config,
+        cpp_build_paths,
         &parser.data.workspace.tmp_path(),
+        parser.step_name(),
         None,
         |translation_unit| parser.parse(translation_unit),
     )?;
+    for rejection in rejections {
+        parser.data.db.add_rejection(rejection);
+    }
+    delete_excluded_items(parser.data);
+    update_header_hashes(parser.data);
 
     Ok(())
 }
 
+/// Returns the hash of the content of file `path`, or `None` if it can't be read.
+fn header_hash(path: &str) -> Option<u64> {
+    let content = fs::read(path).ok()?;
+    Some(stable_hash(&String::from_utf8_lossy(&content)))
+}
+
+/// Deletes the parsed items of the headers that were changed or removed
+/// since the previous run. Returns the paths of the headers that didn't change.
+fn remove_changed_headers(data: &mut ProcessorData<'_>) -> Result<HashSet<PathBuf>> {
+    let mut unchanged_headers = HashSet::new();
+    let mut outdated_headers = HashSet::new();
+    for (path, old_hash) in data.db.header_hashes() {
+        match header_hash(path) {
+            Some(hash) if hash == *old_hash => {
+                unchanged_headers.insert(PathBuf::from(path));
+            }
+            Some(_) => {
+                info!("Header changed: {}", path);
+                outdated_headers.insert(path.clone());
+            }
+            None => {
+                info!("Header removed: {}", path);
+                outdated_headers.insert(path.clone());
+            }
+        }
+    }
+    if !unchanged_headers.is_empty() {
+        info!(
+            "Reusing items of {} unchanged headers",
+            unchanged_headers.len()
+        );
+    }
+    if outdated_headers.is_empty() {
+        return Ok(unchanged_headers);
+    }
+
+    let outdated_items = data
+        .db
+        .cpp_items()
+        .filter(|item| item.source_id.is_none())
+        .filter(|item| {
+            data.db.item_origin(&item.id).map_or(false, |origin| {
+                outdated_headers.contains(&origin.location.include_file_path)
+            })
+        })
+        .map(|item| item.id)
+        .collect::<HashSet<_>>();
+    data.db
+        .delete_items(|item| outdated_items.contains(&item.id));
+    Ok(unchanged_headers)
+}
+
+/// Deletes previously parsed items that are excluded by the current config.
+/// Items of changed headers are checked when they are added, but items
+/// of unchanged headers are reused by the incremental parser.
+fn delete_excluded_items(data: &mut ProcessorData<'_>) {
+    let items = data
+        .db
+        .cpp_items()
+        .filter(|item| item.source_id.is_none())
+        .map(|item| {
+            let include_file = data
+                .db
+                .item_origin(&item.id)
+                .map(|origin| origin.location.include_file_path.clone());
+            (item.id, item.item.clone(), include_file)
+        })
+        .collect_vec();
+    let mut excluded_ids = HashSet::new();
+    for (id, item, include_file) in items {
+        if data.reject_excluded_cpp_item(&item, include_file) {
+            excluded_ids.insert(id);
+        }
+    }
+    if !excluded_ids.is_empty() {
+        data.db.delete_items(|item| excluded_ids.contains(&item.id));
+    }
+}
+
+/// Records content hashes of all headers that the parsed items were taken from.
+fn update_header_hashes(data: &mut ProcessorData<'_>) {
+    let headers = data
+        .db
+        .cpp_items()
+        .filter(|item| item.source_id.is_none())
+        .filter_map(|item| data.db.item_origin(&item.id))
+        .map(|origin| origin.location.include_file_path.clone())
+        .collect::<BTreeSet<_>>();
+    let hashes = headers
+        .into_iter()
+        .filter_map(|path| header_hash(&path).map(|hash| (path, hash)))
+        .collect();
+    data.db.set_header_hashes(hashes);
+}
+
+/// Returns the hash of the parser's inputs that are not stored in the database:
+/// the parser configuration and the content of the previously parsed headers.
+pub fn input_hash(data: &ProcessorData<'_>) -> u64 {
+    let mut text = format!(
+        "{:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?}",
+        data.config.include_directives(),
+        data.config.target_include_paths(),
+        data.config
+            .include_directive_path_filters()
+            .iter()
+            .map(HeaderPathFilter::pattern)
+            .collect_vec(),
+        data.config.cpp_build_paths(),
+        data.config.cpp_lib_versions(),
+        data.config.cpp_parser_arguments(),
+        data.config.cpp_parser_only_arguments(),
+        data.config.cpp_parser_blocked_name_patterns(),
+        data.config.signal_macro_sections(),
+        data.config.method_exclusions(),
+        data.config.class_member_exclusions(),
+    );
+    for path in data.db.header_hashes().keys() {
+        text += &format!(" {}:{:?}", path, header_hash(path));
+    }
+    stable_hash(&text)
+}
+
 pub fn parse_generated_items(data: &mut ProcessorData<'_>) -> Result<()> {
+    data.db.clear_rejections("cpp_parser_stage2");
     let current_target = LibraryTarget {
-        cpp_library_version: data.config.cpp_lib_version().map(ToString::to_string),
+        cpp_library_version: data.cpp_lib_version().map(ToString::to_string),
         target: current_target(),
     };
     for ffi_item_id in data.db.ffi_item_ids().collect_vec() {
@@ -385,37 +865,117 @@ pub fn parse_generated_items(data: &mut ProcessorData<'_>) -> Result<()> {
             source_id: Some(ffi_item_id),
             data,
             output: Default::default(),
+            forward_declarations: Vec::new(),
+            unchanged_headers: HashSet::new(),
+            macro_expansions: Vec::new(),
+            normalized_paths: RefCell::default(),
         };
-        run_clang(
+        let cpp_build_paths = parser.data.cpp_build_paths();
+        let ((), rejections) = run_clang(
             &parser.data.config,
+            cpp_build_paths,
             &parser.data.workspace.tmp_path(),
+            parser.step_name(),
             Some(code),
             |translation_unit| {
                 parser.parse(translation_unit)?;
                 Ok(())
             },
         )?;
+        for rejection in rejections {
+            parser.data.db.add_rejection(rejection);
+        }
     }
     Ok(())
 }
 
 impl CppParser<'_, '_> {
+    /// Returns name of the processing step used in rejection records.
+    fn step_name(&self) -> &'static str {
+        if self.source_id.is_some() {
+            "cpp_parser_stage2"
+        } else {
+            "cpp_parser"
+        }
+    }
+
+    /// Logs and records the reason `entity` was skipped.
+    fn reject_entity(&mut self, entity: Entity<'_>, kind: &str, error: &Error) {
+        let cpp_name = get_full_name_display(entity);
+        debug!("failed to parse {}: {}: {}", kind, cpp_name, error);
+        print_trace(error, Some(log::Level::Trace));
+        trace!("entity: {:?}", entity);
+        self.data.db.add_rejection(Rejection {
+            cpp_name,
+            step: self.step_name().to_string(),
+            reason: error.iter_chain().join(": "),
+            include_file: get_origin_location(entity)
+                .ok()
+                .map(|location| location.include_file_path),
+        });
+    }
+
     fn add_output(
         &mut self,
         include_file: String,
         origin_location: CppOriginLocation,
         item: CppItem,
     ) -> Result<()> {
+        let current_item = item.short_text();
+        let class_include_file = self.class_include_file(&item);
+        let origin_file = Some(origin_location.include_file_path.clone());
+        if self.data.reject_excluded_cpp_item(&item, origin_file) {
+            return Ok(());
+        }
         if let Some(id) = self.data.add_cpp_item(self.source_id.clone(), item)? {
+            self.data.db.set_item_origin(
+                &id,
+                CppItemOrigin {
+                    include_file: include_file.clone(),
+                    location: origin_location.clone(),
+                    class_include_file,
+                },
+            );
             self.output.0.push(CppParserOutputItem {
                 id,
                 include_file,
                 origin_location,
             });
+            // the total number of items is unknown until the parsing is done
+            self.data
+                .progress
+                .items(self.output.0.len() as u64, None, Some(current_item));
         }
         Ok(())
     }
 
+    /// Returns the include file of the class containing `item`
+    /// if the item is a class member and the class was already parsed.
+    fn class_include_file(&self, item: &CppItem) -> Option<String> {
+        let class_path = match item {
+            CppItem::Function(function) if function.member.is_some() => {
+                function.class_path().ok()?
+            }
+            CppItem::ClassField(field) => field.path.parent().ok()?,
+            CppItem::ClassBase(base) => base.derived_class_type.clone(),
+            CppItem::Type(type1) => type1.path.parent().ok()?,
+            _ => return None,
+        };
+        self.data
+            .db
+            .lookup_cpp_path(&class_path)
+            .into_iter()
+            .filter(|view| {
+                view.item()
+                    .item
+                    .as_cpp_item()
+                    .and_then(CppItem::as_type_ref)
+                    .map_or(false, |type1| type1.kind.is_class())
+            })
+            .find_map(|view| self.data.db.item_origin(view.id()))
+            .map(|origin| origin.include_file.clone())
+    }
+
     /// Search for a C++ type information in the types found by the parser
     /// and in types of the dependencies.
     fn find_type(
@@ -586,7 +1146,7 @@ impl CppParser<'_, '_> {
                 CppTypeDeclarationKind::Enum { .. } => {
                     return Ok(CppType::Enum { path });
                 }
-                CppTypeDeclarationKind::Class { .. } => {
+                CppTypeDeclarationKind::Class { .. } | CppTypeDeclarationKind::Opaque => {
                     return Ok(CppType::Class(path));
                 }
             }
@@ -843,6 +1403,24 @@ impl CppParser<'_, '_> {
             TypeKind::Elaborated => {
                 self.parse_type(type1.get_canonical_type(), context_template_args)
             }
+            TypeKind::ConstantArray | TypeKind::IncompleteArray => {
+                let element_type = type1
+                    .get_element_type()
+                    .ok_or_else(|| err_msg("can't get array element type"))?;
+                let parsed_element_type = self
+                    .parse_type(element_type, context_template_args)
+                    .with_context(|_| {
+                        format!(
+                            "Failed to parse array element type: {}",
+                            element_type.get_display_name()
+                        )
+                    })?;
+                Ok(CppType::Array {
+                    element_type: Box::new(parsed_element_type),
+                    is_const: element_type.is_const_qualified(),
+                    size: type1.get_size(),
+                })
+            }
             TypeKind::Unexposed => {
                 trace!("found unexposed type: {:?}", type1);
                 let canonical = type1.get_canonical_type();
@@ -1216,6 +1794,50 @@ impl CppParser<'_, '_> {
             Some(token_strings.join(" "))
         };
 
+        // deleted functions are reported as unavailable by clang
+        let mut is_slot = false;
+        if class_name.is_some() && self.data.config.signal_macro_sections() {
+            match get_qt_section(entity) {
+                QtSection::Signals => is_signal = true,
+                QtSection::Slots => is_slot = true,
+                QtSection::Other => {}
+            }
+        }
+
+        let is_deleted = entity.get_availability() == Availability::Unavailable;
+        if is_deleted && class_name.is_none() {
+            bail!("Deleted free function.");
+        }
+
+        let is_final = has_child_of_kind(entity, EntityKind::FinalAttr);
+        let is_override = has_child_of_kind(entity, EntityKind::OverrideAttr)
+            || entity
+                .get_overridden_methods()
+                .map_or(false, |methods| !methods.is_empty());
+
+        // conditional `noexcept(expr)` is considered potentially throwing
+        let is_noexcept = match entity.get_exception_specification() {
+            Some(ExceptionSpecification::BasicNoexcept)
+            | Some(ExceptionSpecification::DynamicNone)
+            | Some(ExceptionSpecification::NoThrow) => true,
+            _ => false,
+        };
+
+        for type1 in arguments
+            .iter()
+            .map(|arg| &arg.argument_type)
+            .chain(std::iter::once(&return_type_parsed))
+        {
+            if let CppType::Class(path) = type1 {
+                if self.is_forward_declared(path) {
+                    bail!(
+                        "Forward-declared type is used by value: {}",
+                        path.to_cpp_pseudo_code()
+                    );
+                }
+            }
+        }
+
         let function = CppFunction {
             path: name_with_namespace,
             operator: method_operator,
@@ -1235,9 +1857,18 @@ impl CppParser<'_, '_> {
                         Accessibility::Protected => CppVisibility::Protected,
                         Accessibility::Private => CppVisibility::Private,
                     },
-                    // not all signals are detected here! see CppData::detect_signals_and_slots
+                    // without `signal_macro_sections`, only signals with
+                    // a `QPrivateSignal` argument are detected
                     is_signal,
-                    is_slot: false,
+                    is_slot,
+                    is_deleted,
+                    is_defaulted: entity.is_defaulted(),
+                    is_final,
+                    is_override,
+                    is_explicit: has_explicit_specifier(entity),
+                    is_implicit: false,
+                    inherited_from: None,
+                    is_ambiguous: false,
                 })
             } else {
                 None
@@ -1247,6 +1878,10 @@ impl CppParser<'_, '_> {
             return_type: return_type_parsed,
             cast: None,
             declaration_code,
+            is_noexcept,
+            deprecated: get_deprecation(entity),
+            doc_comment: entity.get_comment(),
+            is_extern_c: class_name.is_none() && has_c_linkage(entity),
         };
 
         self.add_output(
@@ -1258,6 +1893,17 @@ impl CppParser<'_, '_> {
         Ok(())
     }
 
+    /// Parses a namespace `entity`.
+    fn parse_namespace(&mut self, entity: Entity<'_>) -> Result<()> {
+        let path = get_path(entity)?;
+        self.add_output(
+            self.entity_include_file(entity)?,
+            get_origin_location(entity)?,
+            CppItem::Namespace(CppNamespace { path }),
+        )?;
+        Ok(())
+    }
+
     /// Parses an enum `entity`.
     fn parse_enum(&mut self, entity: Entity<'_>) -> Result<()> {
         let include_file = self.entity_include_file(entity).with_context(|_| {
@@ -1268,14 +1914,7 @@ impl CppParser<'_, '_> {
             )
         })?;
         let enum_name = get_path(entity)?;
-        self.add_output(
-            include_file.clone(),
-            get_origin_location(entity)?,
-            CppItem::Type(CppTypeDeclaration {
-                kind: CppTypeDeclarationKind::Enum,
-                path: enum_name.clone(),
-            }),
-        )?;
+        let mut values = Vec::new();
         for child in entity.get_children() {
             if child.get_kind() == EntityKind::EnumConstantDecl {
                 let val = child
@@ -1285,16 +1924,47 @@ impl CppParser<'_, '_> {
                 let value_name = child
                     .get_name()
                     .ok_or_else(|| err_msg("failed to get name of enum variant"))?;
-                self.add_output(
-                    include_file.clone(),
-                    get_origin_location(child)?,
-                    CppItem::EnumValue(CppEnumValue {
-                        path: enum_name.join(CppPathItem::from_good_str(&value_name)),
-                        value: val.0,
-                    }),
-                )?;
+                values.push((child, value_name, val.0));
             }
         }
+
+        let clang_underlying_type = entity
+            .get_enum_underlying_type()
+            .ok_or_else(|| err_msg("failed to get underlying type of enum"))?;
+        let mut underlying_type = self
+            .parse_type(clang_underlying_type, &[])
+            .with_context(|_| "failed to parse underlying type of enum")?;
+        if !entity.is_scoped()
+            && underlying_type == CppType::BuiltInNumeric(CppBuiltInNumericType::UInt)
+            && values
+                .iter()
+                .all(|(_, _, value)| i32::try_from(*value).is_ok())
+        {
+            // clang uses `unsigned int` for enums without negative values,
+            // but they are passed in the same way as `int`
+            underlying_type = CppType::BuiltInNumeric(CppBuiltInNumericType::Int);
+        }
+
+        self.add_output(
+            include_file.clone(),
+            get_origin_location(entity)?,
+            CppItem::Type(CppTypeDeclaration {
+                kind: CppTypeDeclarationKind::Enum { underlying_type },
+                path: enum_name.clone(),
+                deprecated: get_deprecation(entity),
+                doc_comment: entity.get_comment(),
+            }),
+        )?;
+        for (child, value_name, value) in values {
+            self.add_output(
+                include_file.clone(),
+                get_origin_location(child)?,
+                CppItem::EnumValue(CppEnumValue {
+                    path: enum_name.join(CppPathItem::from_good_str(&value_name)),
+                    value,
+                }),
+            )?;
+        }
         Ok(())
     }
 
@@ -1324,6 +1994,7 @@ impl CppParser<'_, '_> {
                     Accessibility::Private => CppVisibility::Private,
                 },
                 is_static: entity.get_kind() == EntityKind::VarDecl,
+                is_const: field_clang_type.is_const_qualified(),
             }),
         )?;
 
@@ -1340,22 +2011,20 @@ impl CppParser<'_, '_> {
     ) -> Result<()> {
         let base_type = self
             .parse_type(
-                entity.get_type().unwrap(),
+                entity
+                    .get_type()
+                    .ok_or_else(|| err_msg("failed to get type of base specifier"))?,
                 &get_context_template_args(parent),
             )
             .with_context(|_| "Can't parse base class type")?;
         if let CppType::Class(base_type) = &base_type {
             self.add_output(
                 self.entity_include_file(entity)?,
-                get_origin_location(entity).unwrap(),
+                get_origin_location(entity)?,
                 CppItem::ClassBase(CppBaseSpecifier {
                     base_class_type: base_type.clone(),
                     is_virtual: entity.is_virtual_base(),
-                    visibility: match entity.get_accessibility().unwrap_or(Accessibility::Public) {
-                        Accessibility::Public => CppVisibility::Public,
-                        Accessibility::Protected => CppVisibility::Protected,
-                        Accessibility::Private => CppVisibility::Private,
-                    },
+                    visibility: get_visibility(entity),
                     base_index,
                     derived_class_type: get_path(parent)?,
                 }),
@@ -1366,6 +2035,38 @@ impl CppParser<'_, '_> {
         Ok(())
     }
 
+    /// Parses default arguments of template parameters of class template `entity`.
+    /// Returns an empty vector if there are no default arguments.
+    fn parse_template_argument_defaults(&self, entity: Entity<'_>) -> Vec<Option<CppType>> {
+        let spellings = get_template_default_spellings(entity);
+        if spellings.iter().all(Option::is_none) {
+            return Vec::new();
+        }
+        let context_template_args = get_context_template_args(entity);
+        spellings
+            .into_iter()
+            .map(|spelling| {
+                let spelling = spelling?;
+                match self.parse_unexposed_type(
+                    None,
+                    Some(spelling.clone()),
+                    &context_template_args,
+                ) {
+                    Ok(t) => Some(t),
+                    Err(err) => {
+                        debug!(
+                            "failed to parse default template argument of {}: {}: {}",
+                            get_full_name_display(entity),
+                            spelling,
+                            err
+                        );
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
     /// Parses a class or a struct `entity`.
     fn parse_class(&mut self, entity: Entity<'_>) -> Result<()> {
         let include_file = self.entity_include_file(entity).with_context(|_| {
@@ -1393,44 +2094,115 @@ impl CppParser<'_, '_> {
         } else if template_arguments.is_some() {
             bail!("unexpected template arguments");
         }
+        let template_argument_defaults = if template_arguments.is_some() {
+            self.parse_template_argument_defaults(entity)
+        } else {
+            Vec::new()
+        };
         let mut current_base_index = 0;
+        let mut using_declarations = Vec::new();
         for child in entity.get_children() {
             if child.get_kind() == EntityKind::FieldDecl || child.get_kind() == EntityKind::VarDecl
             {
                 if let Err(err) = self.parse_class_field(child, &full_name) {
-                    debug!(
-                        "failed to parse class field: {}: {}",
-                        get_full_name_display(child),
-                        err
-                    );
-                    trace!("entity: {:?}", entity);
+                    self.reject_entity(child, "class field", &err);
                 }
             }
             if child.get_kind() == EntityKind::BaseSpecifier {
                 if let Err(err) = self.parse_class_base(child, current_base_index, entity) {
-                    debug!(
-                        "failed to parse class base: {}: {}",
-                        get_full_name_display(entity),
-                        err
-                    );
+                    self.reject_entity(entity, "class base", &err);
                 }
                 current_base_index += 1;
             }
+            if child.get_kind() == EntityKind::UsingDeclaration {
+                match parse_using_declaration(child) {
+                    Ok(declaration) => using_declarations.push(declaration),
+                    Err(err) => self.reject_entity(child, "using declaration", &err),
+                }
+            }
             if child.get_kind() == EntityKind::NonTypeTemplateParameter {
                 bail!("Non-type template parameter is not supported");
             }
         }
+        let mut declaration = CppTypeDeclaration {
+            kind: CppTypeDeclarationKind::Class {
+                is_final: has_child_of_kind(entity, EntityKind::FinalAttr),
+                layout: get_class_layout(entity),
+                using_declarations,
+                template_argument_defaults,
+                properties: Vec::new(),
+            },
+            path: full_name,
+            deprecated: get_deprecation(entity),
+            doc_comment: entity.get_comment(),
+        };
+        let config = self.data.config;
+        for instance in self.class_macro_instances(entity) {
+            for extractor in config.macro_extractors() {
+                if let Err(err) = extractor(&instance, &mut declaration) {
+                    self.reject_entity(entity, "class macro", &err);
+                }
+            }
+        }
         self.add_output(
             include_file,
-            get_origin_location(entity).unwrap(),
-            CppItem::Type(CppTypeDeclaration {
-                kind: CppTypeDeclarationKind::Class,
-                path: full_name,
-            }),
+            get_origin_location(entity)?,
+            CppItem::Type(declaration),
         )?;
         Ok(())
     }
 
+    /// Returns macros expanded inside class `entity`, excluding its nested classes.
+    fn class_macro_instances(&self, entity: Entity<'_>) -> Vec<CppMacroInstance> {
+        if self.macro_expansions.is_empty() {
+            return Vec::new();
+        }
+        let range = match entity.get_range() {
+            Some(range) => range,
+            None => return Vec::new(),
+        };
+        let (file_path, start, end) = match (
+            get_file_offset(range.get_start()),
+            get_file_offset(range.get_end()),
+        ) {
+            (Some((file_path, start)), Some((_, end))) => (file_path, start, end),
+            _ => return Vec::new(),
+        };
+        let nested_ranges = entity
+            .get_children()
+            .into_iter()
+            .filter(|child| {
+                matches!(
+                    child.get_kind(),
+                    EntityKind::ClassDecl
+                        | EntityKind::ClassTemplate
+                        | EntityKind::StructDecl
+                        | EntityKind::UnionDecl
+                )
+            })
+            .filter_map(|child| {
+                let range = child.get_range()?;
+                Some((
+                    get_file_offset(range.get_start())?.1,
+                    get_file_offset(range.get_end())?.1,
+                ))
+            })
+            .collect_vec();
+        let first = self
+            .macro_expansions
+            .partition_point(|e| (&e.file_path, e.offset) < (&file_path, start));
+        self.macro_expansions[first..]
+            .iter()
+            .take_while(|e| e.file_path == file_path && e.offset < end)
+            .filter(|e| {
+                !nested_ranges
+                    .iter()
+                    .any(|&(start, end)| e.offset >= start && e.offset < end)
+            })
+            .map(|e| e.instance.clone())
+            .collect()
+    }
+
     /// Determines file path of the include file this `entity` is located in.
     fn entity_include_path(&self, entity: Entity<'_>) -> Result<PathBuf> {
         if let Some(location) = entity.get_location() {
@@ -1438,7 +2210,12 @@ impl CppParser<'_, '_> {
             if file_path.is_empty() {
                 bail!("empty file path")
             } else {
-                Ok(canonicalize(file_path)?)
+                Ok(self
+                    .normalized_paths
+                    .borrow_mut()
+                    .entry(file_path)
+                    .or_insert_with_key(|path| normalize_path(Path::new(path)))
+                    .clone())
             }
         } else {
             bail!("no location for entity")
@@ -1451,7 +2228,32 @@ impl CppParser<'_, '_> {
         let file_name = file_path_buf
             .file_name()
             .ok_or_else(|| err_msg("no file name in file path"))?;
-        Ok(os_str_to_str(file_name)?.to_string())
+        let file_name = os_str_to_str(file_name)?;
+        if cfg!(windows) {
+            // file names are case-insensitive, so the same header can be
+            // included with different spellings
+            Ok(file_name.to_lowercase())
+        } else {
+            Ok(file_name.to_string())
+        }
+    }
+
+    /// Checks if entities declared in the file `file_path` (as returned by
+    /// `entity_include_path`) belong to the target library.
+    fn is_target_path(&self, file_path: &Path) -> bool {
+        if self.current_target_paths.is_empty() {
+            return true;
+        }
+        self.current_target_paths
+            .iter()
+            .any(|path| file_path.starts_with(path))
+            || (self.source_id.is_none()
+                && self
+                    .data
+                    .config
+                    .include_directive_path_filters()
+                    .iter()
+                    .any(|filter| filter.matches(file_path)))
     }
 
     /// Returns false if this `entity` was blacklisted in some way.
@@ -1460,13 +2262,10 @@ impl CppParser<'_, '_> {
             return Ok(true);
         }
         if let Ok(file_path) = self.entity_include_path(entity) {
-            let file_path = canonicalize(Path::new(&file_path))?;
-            if !self.current_target_paths.is_empty()
-                && !self
-                    .current_target_paths
-                    .iter()
-                    .any(|x| file_path.starts_with(x))
-            {
+            if !self.is_target_path(&file_path) {
+                return Ok(false);
+            }
+            if self.unchanged_headers.contains(&file_path) {
                 return Ok(false);
             }
         } else {
@@ -1478,6 +2277,16 @@ impl CppParser<'_, '_> {
                     return Ok(false);
                 }
             }
+            let name = full_name.to_templateless_string();
+            if self
+                .data
+                .config
+                .cpp_parser_blocked_name_patterns()
+                .iter()
+                .any(|pattern| pattern.is_match(&name))
+            {
+                return Ok(false);
+            }
         } else {
             return Ok(false);
         }
@@ -1485,16 +2294,224 @@ impl CppParser<'_, '_> {
     }
 
     fn parse(&mut self, entity: Entity<'_>) -> Result<()> {
+        if !self.data.config.macro_extractors().is_empty() {
+            self.macro_expansions = get_macro_expansions(entity);
+        }
         debug!("Parsing types");
         self.parse_types(entity)?;
         debug!("Parsing functions");
         self.parse_functions(entity)?;
+        self.add_opaque_types()?;
+        if self.source_id.is_none()
+            && !self
+                .data
+                .config
+                .cpp_parser_allowed_name_patterns()
+                .is_empty()
+        {
+            self.remove_not_allowed_items();
+        }
+        let config = self.data.config;
+        user_hooks::run(self.data, config.cpp_data_filters())?;
+        // forget the items deleted by the hooks
+        let db = &self.data.db;
+        self.output.0.retain(|x| db.cpp_item(&x.id).is_ok());
         for hook in self.data.config.after_cpp_parser_hooks() {
             hook(self.data, &self.output)?;
         }
         Ok(())
     }
 
+    /// Remembers a class `entity` that has no definition.
+    fn add_forward_declaration(&mut self, entity: Entity<'_>) -> Result<()> {
+        let path = get_path(entity)?;
+        if self.is_forward_declared(&path) {
+            return Ok(());
+        }
+        self.forward_declarations.push(ForwardDeclaration {
+            path,
+            include_file: self.entity_include_file(entity)?,
+            origin_location: get_origin_location(entity)?,
+        });
+        Ok(())
+    }
+
+    fn is_forward_declared(&self, path: &CppPath) -> bool {
+        self.forward_declarations.iter().any(|x| &x.path == path)
+    }
+
+    /// Adds opaque type declarations for forward-declared classes
+    /// used in parsed functions and class fields.
+    fn add_opaque_types(&mut self) -> Result<()> {
+        if self.forward_declarations.is_empty() {
+            return Ok(());
+        }
+        let mut used_names = HashSet::new();
+        for output_item in &self.output.0 {
+            if let Ok(item) = self.data.db.cpp_item(&output_item.id) {
+                match item.item {
+                    CppItem::Function(function) => {
+                        for type1 in function.all_involved_types() {
+                            add_involved_type_names(&type1, &mut used_names);
+                        }
+                    }
+                    CppItem::ClassField(field) => {
+                        add_involved_type_names(&field.field_type, &mut used_names);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let forward_declarations = mem::replace(&mut self.forward_declarations, Vec::new());
+        for declaration in &forward_declarations {
+            if !used_names.contains(&declaration.path.to_templateless_string()) {
+                continue;
+            }
+            self.add_output(
+                declaration.include_file.clone(),
+                declaration.origin_location.clone(),
+                CppItem::Type(CppTypeDeclaration {
+                    kind: CppTypeDeclarationKind::Opaque,
+                    path: declaration.path.clone(),
+                    deprecated: None,
+                    doc_comment: None,
+                }),
+            )?;
+        }
+        self.forward_declarations = forward_declarations;
+        Ok(())
+    }
+
+    /// Returns true if `path` matches one of the allowed name patterns.
+    fn is_allowed_path(&self, path: &CppPath) -> bool {
+        let name = path.to_templateless_string();
+        self.data
+            .config
+            .cpp_parser_allowed_name_patterns()
+            .iter()
+            .any(|pattern| pattern.is_match(&name))
+    }
+
+    /// Returns false if allowed name patterns are set and function `entity`
+    /// doesn't match any of them. Methods are also allowed if their class matches.
+    fn is_allowed_function(&self, entity: Entity<'_>) -> bool {
+        if self.source_id.is_some()
+            || self
+                .data
+                .config
+                .cpp_parser_allowed_name_patterns()
+                .is_empty()
+        {
+            return true;
+        }
+        let path = match get_path(entity) {
+            Ok(path) => path,
+            Err(_) => return false,
+        };
+        self.is_allowed_path(&path)
+            || path
+                .parent()
+                .map_or(false, |parent| self.is_allowed_path(&parent))
+    }
+
+    /// Removes parsed types that don't match allowed name patterns and are not
+    /// used by any other kept item, along with their fields, bases and enum values.
+    fn remove_not_allowed_items(&mut self) {
+        let output_ids = self.output.0.iter().map(|x| x.id.clone()).collect_vec();
+        let mut kept = HashSet::new();
+        for id in &output_ids {
+            let item = match self.data.db.cpp_item(id) {
+                Ok(item) => item,
+                Err(_) => continue,
+            };
+            match item.item {
+                CppItem::Type(data) => {
+                    if self.is_allowed_path(&data.path) {
+                        kept.insert(data.path.to_templateless_string());
+                    }
+                }
+                CppItem::Function(function) => {
+                    for type1 in function.all_involved_types() {
+                        add_involved_type_names(&type1, &mut kept);
+                    }
+                }
+                _ => {}
+            }
+        }
+        // add types the kept types depend on
+        loop {
+            let mut new_names = HashSet::new();
+            for id in &output_ids {
+                let item = match self.data.db.cpp_item(id) {
+                    Ok(item) => item,
+                    Err(_) => continue,
+                };
+                match item.item {
+                    CppItem::Type(data) => {
+                        if kept.contains(&data.path.to_templateless_string()) {
+                            if let Ok(parent) = data.path.parent() {
+                                new_names.insert(parent.to_templateless_string());
+                            }
+                        }
+                    }
+                    CppItem::ClassField(field) => {
+                        if let Ok(parent) = field.path.parent() {
+                            if kept.contains(&parent.to_templateless_string()) {
+                                add_involved_type_names(&field.field_type, &mut new_names);
+                            }
+                        }
+                    }
+                    CppItem::ClassBase(base) => {
+                        if kept.contains(&base.derived_class_type.to_templateless_string()) {
+                            add_involved_type_names(
+                                &CppType::Class(base.base_class_type.clone()),
+                                &mut new_names,
+                            );
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            let old_len = kept.len();
+            kept.extend(new_names);
+            if kept.len() == old_len {
+                break;
+            }
+        }
+
+        let mut removed_ids = HashSet::new();
+        for id in &output_ids {
+            let item = match self.data.db.cpp_item(id) {
+                Ok(item) => item,
+                Err(_) => continue,
+            };
+            let is_kept = match item.item {
+                CppItem::Type(data) => kept.contains(&data.path.to_templateless_string()),
+                CppItem::EnumValue(value) => value.path.parent().map_or(false, |parent| {
+                    kept.contains(&parent.to_templateless_string())
+                }),
+                CppItem::ClassField(field) => field.path.parent().map_or(false, |parent| {
+                    kept.contains(&parent.to_templateless_string())
+                }),
+                CppItem::ClassBase(base) => {
+                    kept.contains(&base.derived_class_type.to_templateless_string())
+                }
+                _ => true,
+            };
+            if !is_kept {
+                removed_ids.insert(id.clone());
+            }
+        }
+        debug!(
+            "removing {} items not matching allowed name patterns",
+            removed_ids.len()
+        );
+        self.data
+            .db
+            .delete_items(|item| removed_ids.contains(&item.id));
+        self.output.0.retain(|x| !removed_ids.contains(&x.id));
+    }
+
     /// Parses type declarations in translation unit `entity`
     /// and saves them to `self`.
     fn parse_types(&mut self, entity: Entity<'_>) -> Result<()> {
@@ -1508,12 +2525,7 @@ impl CppParser<'_, '_> {
                 }
                 if entity.get_name().is_some() && entity.is_definition() {
                     if let Err(error) = self.parse_enum(entity) {
-                        debug!(
-                            "failed to parse enum: {}: {}",
-                            get_full_name_display(entity),
-                            error
-                        );
-                        trace!("entity: {:?}", entity);
+                        self.reject_entity(entity, "enum", &error);
                     }
                 }
             }
@@ -1526,25 +2538,23 @@ impl CppParser<'_, '_> {
                     entity.get_template().is_none(); // not a template specialization
                 if ok {
                     if let Err(error) = self.parse_class(entity) {
-                        debug!(
-                            "failed to parse class: {}: {}",
-                            get_full_name_display(entity),
-                            error
-                        );
-                        trace!("entity: {:?}", entity);
+                        self.reject_entity(entity, "class", &error);
+                    }
+                } else if entity.get_kind() != EntityKind::ClassTemplate
+                    && entity.get_name().is_some()
+                    && entity.get_definition().is_none()
+                    && entity.get_template().is_none()
+                {
+                    if let Err(error) = self.add_forward_declaration(entity) {
+                        self.reject_entity(entity, "class", &error);
                     }
                 }
             }
-            EntityKind::Namespace => match get_path(entity) {
-                Ok(path) => {
-                    self.add_output(
-                        self.entity_include_file(entity)?,
-                        get_origin_location(entity).unwrap(),
-                        CppItem::Namespace(CppNamespace { path }),
-                    )?;
+            EntityKind::Namespace => {
+                if let Err(error) = self.parse_namespace(entity) {
+                    self.reject_entity(entity, "namespace", &error);
                 }
-                Err(error) => debug!("failed to get namespace name: {}", error),
-            },
+            }
             _ => {}
         }
         match entity.get_kind() {
@@ -1553,6 +2563,7 @@ impl CppParser<'_, '_> {
             | EntityKind::StructDecl
             | EntityKind::ClassDecl
             | EntityKind::UnexposedDecl
+            | EntityKind::LinkageSpec
             | EntityKind::ClassTemplate => {
                 for c in entity.get_children() {
                     self.parse_types(c)?;
@@ -1575,14 +2586,11 @@ impl CppParser<'_, '_> {
             | EntityKind::Destructor
             | EntityKind::ConversionFunction
             | EntityKind::FunctionTemplate => {
+                if !self.is_allowed_function(entity) {
+                    return Ok(());
+                }
                 if let Err(error) = self.parse_function(entity) {
-                    debug!(
-                        "failed to parse function: {}: {}",
-                        get_full_name_display(entity),
-                        error
-                    );
-                    print_trace(&error, Some(log::Level::Trace));
-                    trace!("entity: {:?}", entity);
+                    self.reject_entity(entity, "function", &error);
                 }
             }
             EntityKind::StructDecl
@@ -1621,6 +2629,7 @@ impl CppParser<'_, '_> {
             | EntityKind::StructDecl
             | EntityKind::ClassDecl
             | EntityKind::UnexposedDecl
+            | EntityKind::LinkageSpec
             | EntityKind::ClassTemplate => {
                 for c in entity.get_children() {
                     self.parse_functions(c)?;
@@ -1632,6 +2641,34 @@ impl CppParser<'_, '_> {
     }
 }
 
+/// Adds templateless names of all classes and enums used in `type1` to `output`.
+fn add_involved_type_names(type1: &CppType, output: &mut HashSet<String>) {
+    match type1 {
+        CppType::Class(path) => {
+            output.insert(path.to_templateless_string());
+            for item in path.items() {
+                if let Some(args) = &item.template_arguments {
+                    for arg in args {
+                        add_involved_type_names(arg, output);
+                    }
+                }
+            }
+        }
+        CppType::Enum { path } => {
+            output.insert(path.to_templateless_string());
+        }
+        CppType::PointerLike { target, .. } => add_involved_type_names(target, output),
+        CppType::Array { element_type, .. } => add_involved_type_names(element_type, output),
+        CppType::FunctionPointer(function) => {
+            add_involved_type_names(&function.return_type, output);
+            for arg in &function.arguments {
+                add_involved_type_names(arg, output);
+            }
+        }
+        _ => {}
+    }
+}
+
 fn parse_template_args(str: &str) -> Option<(String, Vec<String>)> {
     let mut level = 0;
     let mut current_str = String::new();
@@ -1,11 +1,15 @@
-use crate::cpp_data::{CppItem, CppPath, CppPathItem, CppTypeDeclaration};
+use crate::config::{TemplateArgument, TemplateArgumentName};
+use crate::cpp_data::{CppItem, CppPath, CppPathItem, CppTypeDeclaration, CppTypeDeclarationKind};
 use crate::cpp_function::{CppFunction, CppFunctionArgument, CppOperator};
-use crate::cpp_type::CppType;
-use crate::database::{DatabaseClient, ItemWithSource};
+use crate::cpp_type::{CppFunctionPointerType, CppPointerLikeTypeKind, CppType};
+use crate::database::{DatabaseClient, DbItem, ItemWithSource, Rejection};
 use crate::processor::ProcessorData;
+use itertools::Itertools;
 use log::{debug, trace};
-use ritual_common::errors::{bail, err_msg, Result};
+use ritual_common::errors::{bail, err_msg, format_err, Result, ResultExt};
 use ritual_common::utils::MapIfOk;
+use std::collections::HashMap;
+use std::iter::once;
 
 /// Returns true if `type1` is a known template instantiation.
 fn check_template_type(data: &ProcessorData<'_>, type1: &CppType) -> Result<()> {
@@ -114,8 +118,12 @@ fn find_suitable_template_arguments<'a>(
     let mut result = Vec::new();
     loop {
         if let Some(template_arguments) = &current_path.last().template_arguments {
-            assert!(!template_arguments.is_empty());
-            if template_arguments.iter().all(|t| t.is_template_parameter()) {
+            if template_arguments.is_empty() {
+                debug!(
+                    "empty template argument list: {}",
+                    current_path.to_cpp_pseudo_code()
+                );
+            } else if template_arguments.iter().all(|t| t.is_template_parameter()) {
                 let items =
                     db.cpp_items()
                         .filter_map(|item| item.item.as_type_ref())
@@ -180,6 +188,8 @@ fn instantiate_types(data: &mut ProcessorData<'_>) -> Result<()> {
                         .item
                         .path
                         .instantiate(substitution.nested_level, substitution.arguments)?,
+                    deprecated: type1.item.deprecated.clone(),
+                    doc_comment: type1.item.doc_comment.clone(),
                 };
 
                 if data
@@ -273,16 +283,371 @@ fn instantiate_functions(data: &mut ProcessorData<'_>) -> Result<()> {
     Ok(())
 }
 
+/// Instantiates member function template `method_path` with `argument`
+/// as requested by `Config::instantiate_method`.
+fn configured_method_instantiations(
+    method_path: &CppPath,
+    argument: &TemplateArgument,
+    data: &ProcessorData<'_>,
+) -> Result<Vec<ItemWithSource<CppFunction>>> {
+    let argument = resolve_template_argument(argument, data)?;
+    let class_path = method_path.parent()?;
+    let methods = data
+        .db
+        .all_cpp_methods_of(&class_path)
+        .filter(|f| f.item.path.last().name == method_path.last().name)
+        .collect_vec();
+    if methods.is_empty() {
+        bail!("method not found");
+    }
+    let templates = methods
+        .iter()
+        .filter(|f| {
+            f.item
+                .path
+                .last()
+                .template_arguments
+                .as_ref()
+                .map_or(false, |args| {
+                    args.iter().all(CppType::is_template_parameter)
+                })
+        })
+        .collect_vec();
+    if templates.is_empty() {
+        bail!("method is not a template");
+    }
+
+    let mut result = Vec::new();
+    for template in templates {
+        let parameters = template
+            .item
+            .path
+            .last()
+            .template_arguments
+            .as_ref()
+            .unwrap();
+        let nested_level = match parameters.as_slice() {
+            [CppType::TemplateParameter(param)] => param.nested_level,
+            _ => {
+                trace!(
+                    "skipping template with multiple parameters: {}",
+                    template.item.short_text()
+                );
+                continue;
+            }
+        };
+        let method = instantiate_function(&template.item, nested_level, &[argument.clone()])
+            .with_context(|_| format!("failed to instantiate {}", template.item.short_text()))?;
+        for type1 in method.all_involved_types() {
+            check_template_type(data, &type1)?;
+        }
+        result.push(ItemWithSource::new(&template.id, method));
+    }
+    if result.is_empty() {
+        bail!("method template must have a single template parameter");
+    }
+    Ok(result)
+}
+
+/// Adds instantiations of member function templates
+/// requested by `Config::instantiate_method`.
+fn instantiate_configured_methods(data: &mut ProcessorData<'_>) -> Result<()> {
+    let config = data.config;
+    let mut new_methods = Vec::new();
+    for (method_path, arguments) in config.method_instantiations() {
+        for argument in arguments {
+            let methods = configured_method_instantiations(method_path, argument, data)
+                .with_context(|_| {
+                    format!(
+                        "invalid method instantiation requested in config: {} with {}",
+                        method_path.to_cpp_pseudo_code(),
+                        argument
+                    )
+                })?;
+            new_methods.extend(methods);
+        }
+    }
+    for new_method in new_methods {
+        data.add_cpp_item(
+            Some(new_method.source_id),
+            CppItem::Function(new_method.item),
+        )?;
+    }
+    Ok(())
+}
+
 /// Generates methods as template instantiations of
 /// methods of existing template classes and existing template methods.
+/// Member function templates are instantiated as requested by
+/// `Config::instantiate_method`.
 pub fn instantiate_templates(data: &mut ProcessorData<'_>) -> Result<()> {
     instantiate_types(data)?;
     instantiate_functions(data)?;
+    instantiate_configured_methods(data)?;
     Ok(())
 }
 
+/// Default template arguments of class templates. Used to complete
+/// instantiations that omit trailing template arguments, so that
+/// `QList<T>` and `QList<T, Alloc<T>>` refer to the same type.
+struct TemplateArgumentDefaults {
+    /// Template parameters and their default arguments, by the template's path
+    /// without template arguments of the last item
+    templates: HashMap<CppPath, (Vec<CppType>, Vec<Option<CppType>>)>,
+}
+
+impl TemplateArgumentDefaults {
+    fn new(data: &ProcessorData<'_>) -> Self {
+        let mut templates = HashMap::new();
+        for type1 in data
+            .db
+            .all_cpp_items()
+            .filter_map(|item| item.item.as_type_ref())
+        {
+            let defaults = type1.kind.template_argument_defaults();
+            if defaults.iter().all(Option::is_none) {
+                continue;
+            }
+            if let Some(parameters) = &type1.path.last().template_arguments {
+                if parameters.iter().all(CppType::is_template_parameter) {
+                    let mut key = type1.path.clone();
+                    key.last_mut().template_arguments = None;
+                    templates.insert(key, (parameters.clone(), defaults.to_vec()));
+                }
+            }
+        }
+        TemplateArgumentDefaults { templates }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+
+    /// Appends default arguments omitted in `arguments` of template `parameters`.
+    /// Returns `arguments` unchanged if any of the omitted arguments has no known default.
+    fn complete_arguments(
+        &self,
+        parameters: &[CppType],
+        defaults: &[Option<CppType>],
+        arguments: Vec<CppType>,
+    ) -> Vec<CppType> {
+        let nested_level = match parameters.get(0) {
+            Some(CppType::TemplateParameter(param)) => param.nested_level,
+            _ => return arguments,
+        };
+        let mut result = arguments.clone();
+        for index in arguments.len()..parameters.len() {
+            let default = match defaults.get(index) {
+                Some(Some(default)) => default,
+                _ => return arguments,
+            };
+            // defaults may refer to the preceding parameters
+            match default.instantiate(nested_level, &result) {
+                Ok(argument) => result.push(self.complete_type(&argument)),
+                Err(_) => return arguments,
+            }
+        }
+        result
+    }
+
+    /// Returns `path` with omitted default template arguments added.
+    fn complete_path(&self, path: &CppPath) -> CppPath {
+        let mut items = Vec::<CppPathItem>::new();
+        for item in path.items() {
+            let template_arguments = item.template_arguments.as_ref().map(|arguments| {
+                let arguments = arguments
+                    .iter()
+                    .map(|t| self.complete_type(t))
+                    .collect_vec();
+                let key = CppPath::from_items(
+                    items
+                        .iter()
+                        .cloned()
+                        .chain(once(CppPathItem {
+                            name: item.name.clone(),
+                            template_arguments: None,
+                        }))
+                        .collect(),
+                );
+                match self.templates.get(&key) {
+                    Some((parameters, defaults)) => {
+                        self.complete_arguments(parameters, defaults, arguments)
+                    }
+                    None => arguments,
+                }
+            });
+            items.push(CppPathItem {
+                name: item.name.clone(),
+                template_arguments,
+            });
+        }
+        CppPath::from_items(items)
+    }
+
+    /// Returns `type1` with omitted default template arguments added.
+    fn complete_type(&self, type1: &CppType) -> CppType {
+        match type1 {
+            CppType::Class(path) => CppType::Class(self.complete_path(path)),
+            CppType::PointerLike {
+                kind,
+                is_const,
+                target,
+            } => CppType::PointerLike {
+                kind: kind.clone(),
+                is_const: *is_const,
+                target: Box::new(self.complete_type(target)),
+            },
+            CppType::Array {
+                element_type,
+                is_const,
+                size,
+            } => CppType::Array {
+                element_type: Box::new(self.complete_type(element_type)),
+                is_const: *is_const,
+                size: *size,
+            },
+            CppType::FunctionPointer(function) => {
+                CppType::FunctionPointer(CppFunctionPointerType {
+                    return_type: Box::new(self.complete_type(&function.return_type)),
+                    arguments: function
+                        .arguments
+                        .iter()
+                        .map(|t| self.complete_type(t))
+                        .collect(),
+                    allows_variadic_arguments: function.allows_variadic_arguments,
+                })
+            }
+            _ => type1.clone(),
+        }
+    }
+
+    /// Adds omitted default template arguments to all types used in `item`.
+    fn complete_item(&self, item: &mut CppItem) {
+        match item {
+            CppItem::Namespace(_) | CppItem::EnumValue(_) => {}
+            CppItem::Type(type1) => type1.path = self.complete_path(&type1.path),
+            CppItem::Function(function) => {
+                function.path = self.complete_path(&function.path);
+                function.return_type = self.complete_type(&function.return_type);
+                for arg in &mut function.arguments {
+                    arg.argument_type = self.complete_type(&arg.argument_type);
+                }
+                if let Some(CppOperator::Conversion(type1)) = &mut function.operator {
+                    *type1 = self.complete_type(type1);
+                }
+            }
+            CppItem::ClassField(field) => {
+                field.path = self.complete_path(&field.path);
+                field.field_type = self.complete_type(&field.field_type);
+            }
+            CppItem::ClassBase(base) => {
+                base.base_class_type = self.complete_path(&base.base_class_type);
+                base.derived_class_type = self.complete_path(&base.derived_class_type);
+            }
+        }
+    }
+}
+
+/// Returns the declaration of the class template `path` is an instantiation of.
+fn find_template_type<'a>(
+    path: &CppPath,
+    data: &'a ProcessorData<'_>,
+) -> Option<DbItem<&'a CppTypeDeclaration>> {
+    data.db
+        .all_cpp_items()
+        .filter_map(|x| x.filter_map(|item| item.as_type_ref()))
+        .find(|t| {
+            let t = &t.item;
+            t.path.parent_parts().ok() == path.parent_parts().ok()
+                && t.path.last().name == path.last().name
+                && t.path
+                    .last()
+                    .template_arguments
+                    .as_ref()
+                    .map_or(false, |args| {
+                        args.iter().all(CppType::is_template_parameter)
+                    })
+        })
+}
+
+/// Resolves a type name specified in the config against the parsed types.
+fn resolve_type_name(name: &TemplateArgumentName, data: &ProcessorData<'_>) -> Result<CppType> {
+    match name {
+        TemplateArgumentName::BuiltInNumeric(t) => Ok(CppType::BuiltInNumeric(t.clone())),
+        TemplateArgumentName::Pointer { is_const, target } => Ok(CppType::PointerLike {
+            kind: CppPointerLikeTypeKind::Pointer,
+            is_const: *is_const,
+            target: Box::new(resolve_type_name(target, data)?),
+        }),
+        TemplateArgumentName::Path(path) => {
+            let type1 = data
+                .db
+                .all_cpp_items_by_path(path)
+                .find_map(|item| item.item.as_type_ref())
+                .ok_or_else(|| format_err!("unknown type: {}", path.to_cpp_pseudo_code()))?;
+            match type1.kind {
+                CppTypeDeclarationKind::Enum { .. } => Ok(CppType::Enum { path: path.clone() }),
+                _ => Ok(CppType::Class(path.clone())),
+            }
+        }
+    }
+}
+
+/// Converts a template argument specified in the config to a type.
+fn resolve_template_argument(
+    argument: &TemplateArgument,
+    data: &ProcessorData<'_>,
+) -> Result<CppType> {
+    match argument {
+        TemplateArgument::Type(t) => {
+            check_template_type(data, t)?;
+            Ok(t.clone())
+        }
+        TemplateArgument::Name(name) => resolve_type_name(&name.parse()?, data),
+    }
+}
+
+/// Returns the path of the instantiation requested by
+/// `Config::add_template_instantiation`, checking that the template exists
+/// and accepts the arguments.
+fn config_instantiation(
+    template_path: &CppPath,
+    arguments: &[TemplateArgument],
+    data: &ProcessorData<'_>,
+) -> Result<CppPath> {
+    let arguments = arguments
+        .iter()
+        .map_if_ok(|argument| resolve_template_argument(argument, data))?;
+    let mut path = template_path.clone();
+    path.last_mut().template_arguments = Some(arguments);
+    let path = TemplateArgumentDefaults::new(data).complete_path(&path);
+    let actual_count = path.last().template_arguments.as_ref().map_or(0, Vec::len);
+
+    let template = find_template_type(&path, data)
+        .filter(|t| t.item.kind.is_class())
+        .ok_or_else(|| err_msg("class template not found"))?;
+    let expected_count = template
+        .item
+        .path
+        .last()
+        .template_arguments
+        .as_ref()
+        .map_or(0, Vec::len);
+    if expected_count != actual_count {
+        bail!(
+            "template argument count mismatch: expected {}, got {}",
+            expected_count,
+            actual_count
+        );
+    }
+    Ok(path)
+}
+
 /// Searches for template instantiations in this library's API,
 /// excluding results that were already processed in dependencies.
+/// Instantiations requested by `Config::add_template_instantiation`
+/// are also added. Omitted default template arguments are added
+/// to all instantiations first.
 pub fn find_template_instantiations(data: &mut ProcessorData<'_>) -> Result<()> {
     fn check_type(type1: &CppType, data: &ProcessorData<'_>, result: &mut Vec<CppPath>) {
         match &type1 {
@@ -313,40 +678,69 @@ pub fn find_template_instantiations(data: &mut ProcessorData<'_>) -> Result<()>
             _ => {}
         }
     }
+    let defaults = TemplateArgumentDefaults::new(data);
+    if !defaults.is_empty() {
+        for item in data.db.cpp_items_mut() {
+            defaults.complete_item(item.item);
+        }
+        data.db.refresh_indexes();
+    }
+
     let mut result = Vec::new();
     for item in data.db.cpp_items() {
         for type1 in item.item.all_involved_types() {
             check_type(&type1, &data, &mut result);
         }
     }
-    for item in result {
-        let original_type = data
+    let config = data.config;
+    for (template_path, arguments) in config.template_instantiations() {
+        let path = config_instantiation(template_path, arguments, data).with_context(|_| {
+            format!(
+                "invalid template instantiation requested in config: {}<{}>",
+                template_path.to_cpp_pseudo_code(),
+                arguments.iter().join(", ")
+            )
+        })?;
+        let is_in_database = data
             .db
-            .all_cpp_items()
-            .filter_map(|x| x.filter_map(|item| item.as_type_ref()))
-            .find(|t| {
-                let t = &t.item;
-                t.path.parent_parts().ok() == item.parent_parts().ok()
-                    && t.path.last().name == item.last().name
-                    && t.path
-                        .last()
-                        .template_arguments
-                        .as_ref()
-                        .map_or(false, |args| {
-                            args.iter().all(CppType::is_template_parameter)
-                        })
-            });
-        if let Some(original_type) = original_type {
-            let mut new_type = original_type.item.clone();
-            new_type.path = item;
-            let source_id = original_type.id.clone();
-            data.add_cpp_item(Some(source_id), CppItem::Type(new_type))?;
-        } else {
-            debug!(
-                "original type not found for instantiation: {}",
-                item.to_cpp_pseudo_code()
-            );
+            .all_cpp_items_by_path(&path)
+            .any(|item| item.item.as_type_ref().is_some());
+        if !is_in_database && !result.contains(&path) {
+            result.push(path);
         }
     }
+    for item in result {
+        let original_type = find_template_type(&item, data);
+        let reason = if let Some(original_type) = original_type {
+            let expected_count = original_type
+                .item
+                .path
+                .last()
+                .template_arguments
+                .as_ref()
+                .map_or(0, Vec::len);
+            let actual_count = item.last().template_arguments.as_ref().map_or(0, Vec::len);
+            if expected_count == actual_count {
+                let mut new_type = original_type.item.clone();
+                new_type.path = item;
+                let source_id = original_type.id.clone();
+                data.add_cpp_item(Some(source_id), CppItem::Type(new_type))?;
+                continue;
+            }
+            format!(
+                "template argument count mismatch: expected {}, got {}",
+                expected_count, actual_count
+            )
+        } else {
+            "original type not found for instantiation".to_string()
+        };
+        debug!("{}: {}", reason, item.to_cpp_pseudo_code());
+        data.db.add_rejection(Rejection {
+            cpp_name: item.to_cpp_pseudo_code(),
+            step: "find_template_instantiations".to_string(),
+            reason,
+            include_file: None,
+        });
+    }
     Ok(())
 }
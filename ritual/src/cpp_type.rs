@@ -2,6 +2,7 @@
 
 use crate::cpp_data::CppPath;
 use ritual_common::errors::{bail, Result};
+use ritual_common::utils::MapIfOk;
 use serde_derive::{Deserialize, Serialize};
 use std::hash::{Hash, Hasher};
 
@@ -120,6 +121,14 @@ pub enum CppType {
         is_const: bool,
         target: Box<CppType>,
     },
+    /// Fixed-size or incomplete array, like `float[16]` or `char[]`
+    Array {
+        element_type: Box<CppType>,
+        /// True if array elements are const
+        is_const: bool,
+        /// Number of elements, or `None` for an array of unknown size
+        size: Option<usize>,
+    },
 }
 
 impl CppBuiltInNumericType {
@@ -205,6 +214,25 @@ impl CppType {
         }
     }
 
+    pub fn new_rvalue_reference(target: CppType) -> Self {
+        CppType::PointerLike {
+            kind: CppPointerLikeTypeKind::RValueReference,
+            is_const: false,
+            target: Box::new(target),
+        }
+    }
+
+    /// Returns true if this is an rvalue reference (`T&&`).
+    pub fn is_rvalue_reference(&self) -> bool {
+        matches!(
+            self,
+            CppType::PointerLike {
+                kind: CppPointerLikeTypeKind::RValueReference,
+                ..
+            }
+        )
+    }
+
     /// Returns true if this is `void` type.
     pub fn is_void(&self) -> bool {
         matches!(self, CppType::Void)
@@ -221,6 +249,10 @@ impl CppType {
     pub fn is_function_pointer(&self) -> bool {
         matches!(self, CppType::FunctionPointer(..))
     }
+    /// Returns true if this is an array type.
+    pub fn is_array(&self) -> bool {
+        matches!(self, CppType::Array { .. })
+    }
 
     pub fn is_pointer(&self) -> bool {
         match self {
@@ -235,6 +267,7 @@ impl CppType {
         match self {
             CppType::TemplateParameter { .. } => true,
             CppType::PointerLike { target, .. } => target.is_or_contains_template_parameter(),
+            CppType::Array { element_type, .. } => element_type.is_or_contains_template_parameter(),
             CppType::FunctionPointer(type1) => {
                 type1.return_type.is_or_contains_template_parameter()
                     || type1
@@ -261,6 +294,7 @@ impl CppType {
                 self_params.nested_level == param.nested_level && self_params.index == param.index
             }
             CppType::PointerLike { target, .. } => target.contains_template_parameter(param),
+            CppType::Array { element_type, .. } => element_type.contains_template_parameter(param),
             CppType::FunctionPointer(type1) => {
                 type1.return_type.contains_template_parameter(param)
                     || type1
@@ -282,8 +316,17 @@ impl CppType {
     }
 
     /// Returns C++ code representing this type.
+    ///
+    /// `function_pointer_inner_text` is placed inside the declarator of a function pointer
+    /// (e.g. `void (*inner_text)(int)`). If it's `None`, an abstract declarator
+    /// is produced (e.g. `void (*)(int)`).
     pub fn to_cpp_code(&self, function_pointer_inner_text: Option<&str>) -> Result<String> {
-        if !self.is_function_pointer() && function_pointer_inner_text.is_some() {
+        let is_function_pointer_like = match self {
+            CppType::FunctionPointer(..) => true,
+            CppType::PointerLike { target, .. } => target.is_function_pointer(),
+            _ => false,
+        };
+        if !is_function_pointer_like && function_pointer_inner_text.is_some() {
             bail!("unexpected function_pointer_inner_text");
         }
         match self {
@@ -296,28 +339,46 @@ impl CppType {
             CppType::TemplateParameter { .. } => {
                 bail!("template parameters are not allowed in C++ code generator");
             }
-            CppType::FunctionPointer(CppFunctionPointerType {
-                return_type,
-                arguments,
-                allows_variadic_arguments,
-            }) => {
-                if *allows_variadic_arguments {
-                    bail!("function pointers with variadic arguments are not supported");
-                }
-                let mut arg_texts = Vec::new();
-                for arg in arguments {
-                    arg_texts.push(arg.to_cpp_code(None)?);
-                }
-                if let Some(function_pointer_inner_text) = function_pointer_inner_text {
-                    Ok(format!(
-                        "{} (*{})({})",
-                        return_type.as_ref().to_cpp_code(None)?,
-                        function_pointer_inner_text,
-                        arg_texts.join(", ")
-                    ))
-                } else {
-                    bail!("function_pointer_inner_text argument is missing");
-                }
+            CppType::FunctionPointer(..) => {
+                self.to_cpp_declaration(function_pointer_inner_text.unwrap_or(""))
+            }
+            CppType::Array {
+                element_type,
+                is_const,
+                size,
+            } => Ok(format!(
+                "{}[{}]",
+                array_element_cpp_code(element_type, *is_const)?,
+                size.map_or(String::new(), |size| size.to_string())
+            )),
+            CppType::PointerLike {
+                kind,
+                is_const,
+                target,
+            } if target.is_array() => {
+                // a pointer or a reference to an array can't be written as a simple suffix
+                let template = match *kind {
+                    CppPointerLikeTypeKind::Pointer => "std::add_pointer",
+                    CppPointerLikeTypeKind::Reference => "std::add_lvalue_reference",
+                    CppPointerLikeTypeKind::RValueReference => "std::add_rvalue_reference",
+                };
+                let target_code = match &**target {
+                    CppType::Array {
+                        element_type,
+                        is_const: false,
+                        size,
+                    } if *is_const => CppType::Array {
+                        element_type: element_type.clone(),
+                        is_const: true,
+                        size: *size,
+                    }
+                    .to_cpp_code(None)?,
+                    _ => target.to_cpp_code(None)?,
+                };
+                Ok(format!("{}<{}>::type", template, target_code))
+            }
+            CppType::PointerLike { target, .. } if target.is_function_pointer() => {
+                self.to_cpp_declaration(function_pointer_inner_text.unwrap_or(""))
             }
             CppType::PointerLike {
                 kind,
@@ -325,7 +386,7 @@ impl CppType {
                 target,
             } => Ok(format!(
                 "{}{} {}",
-                target.to_cpp_code(function_pointer_inner_text)?,
+                target.to_cpp_code(None)?,
                 if *is_const { " const" } else { "" },
                 match *kind {
                     CppPointerLikeTypeKind::Pointer => "*",
@@ -336,6 +397,63 @@ impl CppType {
         }
     }
 
+    /// Returns C++ code declaring a variable or an argument called `name`
+    /// of this type (e.g. `int x`, `float x[16]` or `void (*x)(int)`).
+    /// An abstract declarator is produced if `name` is empty.
+    pub fn to_cpp_declaration(&self, name: &str) -> Result<String> {
+        match self {
+            CppType::FunctionPointer(CppFunctionPointerType {
+                return_type,
+                arguments,
+                allows_variadic_arguments,
+            }) => {
+                if *allows_variadic_arguments {
+                    bail!("function pointers with variadic arguments are not supported");
+                }
+                let arg_texts = arguments.iter().map_if_ok(|arg| arg.to_cpp_code(None))?;
+                // a function pointer returned by the function pointer
+                // wraps the whole declarator
+                return_type.to_cpp_declaration(&format!("(*{})({})", name, arg_texts.join(", ")))
+            }
+            CppType::PointerLike {
+                kind,
+                is_const,
+                target,
+            } if target.is_function_pointer() => {
+                // the pointer goes inside of the function pointer's declarator,
+                // e.g. `void (* const * name)(int)`
+                target.to_cpp_declaration(&format!(
+                    "{}{}{}",
+                    if *is_const { " const " } else { "" },
+                    match *kind {
+                        CppPointerLikeTypeKind::Pointer => "*",
+                        CppPointerLikeTypeKind::Reference => "&",
+                        CppPointerLikeTypeKind::RValueReference => "&&",
+                    },
+                    name
+                ))
+            }
+            CppType::Array {
+                element_type,
+                is_const,
+                size,
+            } if !name.is_empty() => Ok(format!(
+                "{} {}[{}]",
+                array_element_cpp_code(element_type, *is_const)?,
+                name,
+                size.map_or(String::new(), |size| size.to_string())
+            )),
+            _ => {
+                let code = self.to_cpp_code(None)?;
+                if name.is_empty() {
+                    Ok(code)
+                } else {
+                    Ok(format!("{} {}", code, name))
+                }
+            }
+        }
+    }
+
     /// Generates string representation of this type
     /// for debugging output.
     pub fn to_cpp_pseudo_code(&self) -> String {
@@ -354,6 +472,19 @@ impl CppType {
                 is_const,
                 target,
             } => {
+                if target.is_pointer() {
+                    // constness of a nested pointer can only be expressed as a suffix
+                    return format!(
+                        "{}{}{}",
+                        target.to_cpp_pseudo_code(),
+                        if *is_const { " const" } else { "" },
+                        match *kind {
+                            CppPointerLikeTypeKind::Pointer => "*",
+                            CppPointerLikeTypeKind::Reference => "&",
+                            CppPointerLikeTypeKind::RValueReference => "&&",
+                        }
+                    );
+                }
                 return format!(
                     "{}{}{}",
                     if *is_const { "const " } else { "" },
@@ -381,6 +512,16 @@ impl CppType {
             | CppType::Class(path) => path.ascii_caption(),
             CppType::TemplateParameter(param) => param.name.to_string(),
             CppType::FunctionPointer(_) => "fn".into(),
+            CppType::Array {
+                element_type,
+                is_const,
+                size,
+            } => format!(
+                "{}{}_array{}",
+                element_type.ascii_caption(),
+                if *is_const { "_const" } else { "" },
+                size.map_or(String::new(), |size| size.to_string())
+            ),
             CppType::PointerLike {
                 kind,
                 is_const,
@@ -432,14 +573,37 @@ pub enum CppTypeRole {
     NotReturnType,
 }
 
-pub fn is_qflags(path: &CppPath) -> bool {
-    path.last().name == "QFlags"
-        && !path.has_parent()
-        && path
-            .last()
-            .template_arguments
-            .as_ref()
-            .map_or(false, |args| args.len() == 1)
+/// Returns C++ code of the element type of an array, with `const`
+/// placed so that it applies to the elements.
+fn array_element_cpp_code(element_type: &CppType, is_const: bool) -> Result<String> {
+    let code = element_type.to_cpp_code(None)?;
+    Ok(if !is_const {
+        code
+    } else if element_type.is_pointer() {
+        // `const` must follow the pointer (e.g. `char const * const[4]`)
+        format!("{} const", code)
+    } else {
+        format!("const {}", code)
+    })
+}
+
+/// Returns the template of flags type `path`
+/// (e.g. `QFlags` for `QFlags<Qt::AlignmentFlag>`).
+pub fn flags_template_path(path: &CppPath) -> CppPath {
+    let mut path = path.clone();
+    path.last_mut().template_arguments = None;
+    path
+}
+
+/// Checks if `path` is an instantiation of one of `flags_templates`
+/// with a single template argument (e.g. `QFlags<Qt::AlignmentFlag>`).
+/// See `Config::set_flags_template`.
+pub fn is_flags_type(path: &CppPath, flags_templates: &[CppPath]) -> bool {
+    path.last()
+        .template_arguments
+        .as_ref()
+        .map_or(false, |args| args.len() == 1)
+        && flags_templates.contains(&flags_template_path(path))
 }
 
 impl CppType {
@@ -485,6 +649,17 @@ impl CppType {
                 is_const: *is_const,
                 target: Box::new(target.instantiate(nested_level, template_arguments1)?),
             }),
+            CppType::Array {
+                element_type,
+                is_const,
+                size,
+            } => Ok(CppType::Array {
+                element_type: Box::new(
+                    element_type.instantiate(nested_level, template_arguments1)?,
+                ),
+                is_const: *is_const,
+                size: *size,
+            }),
             _ => Ok(self.clone()),
         }
     }
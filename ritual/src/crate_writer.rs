@@ -1,53 +1,172 @@
-use crate::config::{CrateDependencyKind, CrateDependencySource};
+use crate::config::{CrateDependencyKind, CrateDependencySource, VersionSuggestionMode};
+use crate::coverage::CoverageReport;
 use crate::cpp_code_generator;
 use crate::cpp_code_generator::{
     all_include_directives, generate_cpp_type_size_requester, write_include_directives,
 };
-use crate::database::CRATE_DB_FILE_NAME;
+use crate::database::{Database, CRATE_DB_FILE_NAME};
 use crate::processor::ProcessorData;
 use crate::rust_code_generator;
+use crate::rust_info::RustFeatures;
+use crate::smoke_tests;
+use crate::versions::{parse_version, VersionSuggestion};
 use itertools::Itertools;
-use ritual_common::errors::Result;
+use log::info;
+use ritual_common::cpp_build_config::STATIC_LINK_FEATURE;
+use ritual_common::errors::{bail, Result, ResultExt};
 use ritual_common::file_utils::{
     copy_file, copy_recursively, crate_version, create_dir, create_dir_all, create_file,
-    diff_paths, path_to_str, read_dir, remove_dir_all, repo_dir_path, save_json, save_toml_table,
+    diff_paths, path_to_str, read_dir, remove_dir, remove_dir_all, remove_file, rename_file,
+    repo_dir_path, save_json, save_toml_table,
 };
 use ritual_common::toml;
-use ritual_common::utils::run_command;
+use ritual_common::utils::{recursive_merge_toml, run_command};
 use ritual_common::BuildScriptData;
+use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
-/// Merges `a` and `b` recursively. `b` take precedence over `a`.
-fn recursive_merge_toml(a: toml::Value, b: toml::Value) -> toml::Value {
-    if a.same_type(&b) {
-        if let toml::Value::Array(mut a_array) = a {
-            if let toml::Value::Array(mut b_array) = b {
-                a_array.append(&mut b_array);
-                toml::Value::Array(a_array)
-            } else {
-                unreachable!()
+/// Paths (relative to the crate root) that contain hand-written files
+/// and are never deleted when the crate is regenerated.
+const PROTECTED_PATHS: &[&str] = &["src/extra", "README.md"];
+
+fn is_protected(relative_path: &Path) -> bool {
+    PROTECTED_PATHS
+        .iter()
+        .any(|path| relative_path.starts_with(path))
+}
+
+/// Returns paths of all files in `root.join(relative_path)` relative to `root`.
+fn relative_file_paths(root: &Path, relative_path: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for item in read_dir(root.join(relative_path))? {
+        let item = item?;
+        let relative_item_path = relative_path.join(item.file_name());
+        if item.path().is_dir() {
+            paths.extend(relative_file_paths(root, &relative_item_path)?);
+        } else {
+            paths.push(relative_item_path);
+        }
+    }
+    Ok(paths)
+}
+
+/// Copies files of the crate overlay to the generated crate.
+/// Overlay files may only replace protected files. If any other file
+/// already exists, no files are copied and an error listing all conflicting
+/// paths is returned.
+pub fn apply_crate_overlay(overlay_path: &Path, output_path: &Path) -> Result<()> {
+    let mut paths = relative_file_paths(overlay_path, Path::new(""))?;
+    paths.sort();
+    let conflicts = paths
+        .iter()
+        .filter(|path| !is_protected(path) && output_path.join(path).exists())
+        .map(|path| path.display())
+        .join(", ");
+    if !conflicts.is_empty() {
+        bail!(
+            "crate overlay files conflict with generated files: {}",
+            conflicts
+        );
+    }
+    for path in &paths {
+        let target = output_path.join(path);
+        create_dir_all(target.parent().expect("overlay file path must have parent"))?;
+        copy_file(overlay_path.join(path), target)?;
+    }
+    Ok(())
+}
+
+/// Numbers of files affected by `sync_crate_files`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SyncStats {
+    pub written: usize,
+    pub unchanged: usize,
+    pub deleted: usize,
+}
+
+/// Moves files from `staging_path` to `output_path`. Files with unchanged content
+/// are not touched, so their modification times are preserved and `cargo`
+/// doesn't rebuild the crate. Files listed in `previous_files` that were not
+/// generated this time are deleted. Protected paths are never deleted.
+///
+/// Returns paths of the generated files (except protected paths)
+/// relative to `output_path`.
+pub fn sync_crate_files(
+    staging_path: &Path,
+    output_path: &Path,
+    previous_files: &[PathBuf],
+    stats: &mut SyncStats,
+) -> Result<Vec<PathBuf>> {
+    let read =
+        |path: &Path| fs::read(path).with_context(|_| format!("Failed to read file: {:?}", path));
+    let mut files = relative_file_paths(staging_path, Path::new(""))?;
+    files.sort();
+    for path in &files {
+        let source = staging_path.join(path);
+        let target = output_path.join(path);
+        if target.is_file() && read(&source)? == read(&target)? {
+            if !is_protected(path) {
+                stats.unchanged += 1;
             }
-        } else if let toml::Value::Table(mut a_table) = a {
-            if let toml::Value::Table(b_table) = b {
-                for (key, value) in b_table {
-                    if let Some(old_value) = a_table.remove(&key) {
-                        a_table.insert(key, recursive_merge_toml(old_value, value));
-                    } else {
-                        a_table.insert(key, value);
-                    }
-                }
-                toml::Value::Table(a_table)
-            } else {
-                unreachable!()
+            continue;
+        }
+        if target.is_dir() {
+            remove_dir_all(&target)?;
+        }
+        create_dir_all(target.parent().expect("crate file path must have parent"))?;
+        rename_file(&source, &target)?;
+        stats.written += 1;
+    }
+
+    for path in previous_files {
+        if is_protected(path) || files.binary_search(path).is_ok() {
+            continue;
+        }
+        let target = output_path.join(path);
+        if !target.is_file() {
+            continue;
+        }
+        remove_file(&target)?;
+        stats.deleted += 1;
+
+        // remove directories that became empty
+        let mut dir = path.parent();
+        while let Some(relative_dir) = dir {
+            let full_dir = output_path.join(relative_dir);
+            if relative_dir.as_os_str().is_empty() || read_dir(&full_dir)?.next().is_some() {
+                break;
             }
-        } else {
-            b
+            remove_dir(&full_dir)?;
+            dir = relative_dir.parent();
         }
-    } else {
-        b
     }
+
+    files.retain(|path| !is_protected(path));
+    Ok(files)
+}
+
+/// Formats Rust files of the crate at `crate_path`.
+fn format_crate(crate_path: &Path) -> Result<()> {
+    // Modules of the crate are formatted through `src/lib.rs`.
+    let files = relative_file_paths(crate_path, Path::new(""))?
+        .into_iter()
+        .filter(|path| path.extension().map_or(false, |ext| ext == "rs"))
+        .filter(|path| {
+            !path.starts_with("src")
+                || path == Path::new("src/lib.rs")
+                || path == Path::new("src/ffi.in.rs")
+        })
+        .collect_vec();
+    run_command(
+        Command::new("rustfmt")
+            .arg("--edition")
+            .arg("2018")
+            .args(&files)
+            .current_dir(crate_path),
+    )?;
+    Ok(())
 }
 
 fn toml_table_with_single_item(key: &str, value: impl Into<toml::Value>) -> toml::Value {
@@ -58,8 +177,14 @@ fn toml_table_with_single_item(key: &str, value: impl Into<toml::Value>) -> toml
 
 /// Generates `Cargo.toml` file and skeleton of the crate.
 /// If a crate template was supplied, files from it are
-/// copied to the output location.
-fn generate_crate_template(data: &mut ProcessorData<'_>, output_path: &Path) -> Result<()> {
+/// copied to the output location. `crate_path` is the final location
+/// of the crate used for relative paths to dependencies.
+fn generate_crate_template(
+    data: &mut ProcessorData<'_>,
+    rust_features: &RustFeatures,
+    output_path: &Path,
+    crate_path: &Path,
+) -> Result<()> {
     let template_build_rs_path =
         data.config
             .crate_template_path()
@@ -91,11 +216,41 @@ fn generate_crate_template(data: &mut ProcessorData<'_>, output_path: &Path) ->
     );
     package.insert(
         "version".into(),
-        toml::Value::String(data.config.crate_properties().version().into()),
+        toml::Value::String(data.db.crate_version().into()),
     );
     package.insert("build".into(), toml::Value::String("build.rs".into()));
+    let links = match data.config.crate_properties().links() {
+        Some(links) => links.to_string(),
+        None => c_lib_name(data),
+    };
+    package.insert("links".into(), toml::Value::String(links));
     package.insert("edition".into(), toml::Value::String("2018".into()));
 
+    let properties = data.config.crate_properties();
+    for &(key, value) in &[
+        ("description", properties.description()),
+        ("license", properties.license()),
+        ("repository", properties.repository()),
+        ("rust-version", properties.rust_version()),
+    ] {
+        if let Some(value) = value {
+            package.insert(key.into(), toml::Value::String(value.into()));
+        }
+    }
+    for &(key, values) in &[
+        ("authors", properties.authors()),
+        ("keywords", properties.keywords()),
+        ("categories", properties.categories()),
+    ] {
+        if !values.is_empty() {
+            let values = values
+                .iter()
+                .map(|value| toml::Value::String(value.clone()))
+                .collect_vec();
+            package.insert(key.into(), values.into());
+        }
+    }
+
     let docs_rs_metadata = toml_table_with_single_item(
         "features",
         vec![
@@ -123,12 +278,16 @@ fn generate_crate_template(data: &mut ProcessorData<'_>, output_path: &Path) ->
                 let version = data.db.dependency_version(name)?;
                 (version.to_string(), Some(path))
             }
+            CrateDependencySource::Database { .. } => {
+                let version = data.db.dependency_version(name)?;
+                (version.to_string(), None)
+            }
         };
 
         let value = if local_path.is_none() || !data.config.write_dependencies_local_paths() {
             toml::Value::String(version)
         } else {
-            let path = diff_paths(&local_path.expect("checked above"), &output_path)?;
+            let path = diff_paths(&local_path.expect("checked above"), crate_path)?;
             let mut value = toml::value::Table::new();
             value.insert("version".into(), toml::Value::String(version));
             value.insert(
@@ -188,6 +347,27 @@ fn generate_crate_template(data: &mut ProcessorData<'_>, output_path: &Path) ->
 
         features.insert(feature.into(), dep_features.into());
     }
+    if !rust_features.dependencies().is_empty() {
+        for (feature, dependencies) in rust_features.dependencies() {
+            let dependencies = dependencies
+                .iter()
+                .map(|dependency| toml::Value::String(dependency.clone()))
+                .collect_vec();
+            features.insert(feature.clone(), dependencies.into());
+        }
+        let default_features = rust_features
+            .dependencies()
+            .keys()
+            .map(|feature| toml::Value::String(feature.clone()))
+            .collect_vec();
+        features.insert("default".into(), default_features.into());
+    }
+    if data.config.cpp_library_discovery().is_some() {
+        if features.contains_key(STATIC_LINK_FEATURE) {
+            bail!("feature name is reserved: {}", STATIC_LINK_FEATURE);
+        }
+        features.insert(STATIC_LINK_FEATURE.into(), Vec::<toml::Value>::new().into());
+    }
 
     let mut table = toml::value::Table::new();
     table.insert("package".into(), package.into());
@@ -214,6 +394,12 @@ fn generate_crate_template(data: &mut ProcessorData<'_>, output_path: &Path) ->
     Ok(())
 }
 
+/// Returns name of the C++ wrapper library of the crate.
+/// It's also used as `links` value in the crate's manifest.
+fn c_lib_name(data: &ProcessorData<'_>) -> String {
+    format!("{}_c", data.config.crate_properties().name())
+}
+
 /// Generates main files and directories of the library.
 fn generate_c_lib_template(
     lib_name: &str,
@@ -243,23 +429,79 @@ fn generate_c_lib_template(
     Ok(())
 }
 
+/// Compares the API with the previously generated crate, writes
+/// the report to the log directory and returns the version of the generated
+/// crate according to `Config::version_suggestion_mode`.
+fn crate_version_to_write(data: &mut ProcessorData<'_>, crate_path: &Path) -> Result<String> {
+    let crate_name = data.config.crate_properties().name();
+    let configured = data.config.crate_properties().version();
+    let old_db_path = crate_path.join(CRATE_DB_FILE_NAME);
+    if !old_db_path.exists() {
+        return Ok(configured.to_string());
+    }
+    let old = Database::load(&old_db_path)?;
+    let suggestion = VersionSuggestion::new(&old, data.db.data())?;
+    let log_path = data.workspace.log_path();
+    create_dir_all(&log_path)?;
+    let report_path = log_path.join(format!("{}.api_changes.txt", crate_name));
+    write!(create_file(&report_path)?, "{}", suggestion.to_text())?;
+    info!(
+        "Suggested version of {}: {} (previous: {}, see {})",
+        crate_name,
+        suggestion.suggested,
+        suggestion.previous,
+        report_path.display()
+    );
+    match data.config.version_suggestion_mode() {
+        VersionSuggestionMode::Report => Ok(configured.to_string()),
+        VersionSuggestionMode::Apply => {
+            if parse_version(configured)? < suggestion.suggested {
+                Ok(suggestion.suggested.to_string())
+            } else {
+                Ok(configured.to_string())
+            }
+        }
+        VersionSuggestionMode::Check => {
+            suggestion.check(configured)?;
+            Ok(configured.to_string())
+        }
+    }
+}
+
 pub fn run(data: &mut ProcessorData<'_>) -> Result<()> {
     let crate_name = data.config.crate_properties().name();
-    let output_path = data.workspace.crate_path(crate_name);
+    let crate_path = data.workspace.crate_path(crate_name);
 
+    // The crate is generated in a staging directory first, so that only
+    // changed files are written to the crate's directory.
+    let output_path = data
+        .workspace
+        .tmp_path()
+        .join("crate_writer")
+        .join(crate_name);
     if output_path.exists() {
         remove_dir_all(&output_path)?;
     }
+    create_dir_all(&output_path)?;
+    for path in PROTECTED_PATHS {
+        let path = Path::new(path);
+        if crate_path.join(path).exists() {
+            create_dir_all(output_path.join(path.parent().expect("path must have parent")))?;
+            copy_recursively(&crate_path.join(path), &output_path.join(path))?;
+        }
+    }
 
-    create_dir(&output_path)?;
-    generate_crate_template(data, &output_path)?;
-    data.workspace.update_cargo_toml()?;
+    let version = crate_version_to_write(data, &crate_path)?;
+    data.db.set_crate_version(version);
+
+    let rust_features = RustFeatures::new(&data.db, data.config)?;
+    generate_crate_template(data, &rust_features, &output_path, &crate_path)?;
 
     let c_lib_path = output_path.join("c_lib");
     if !c_lib_path.exists() {
         create_dir(&c_lib_path)?;
     }
-    let c_lib_name = format!("{}_c", data.config.crate_properties().name());
+    let c_lib_name = c_lib_name(data);
     let global_header_name = format!("{}_global.h", c_lib_name);
     generate_c_lib_template(
         &c_lib_name,
@@ -268,10 +510,13 @@ pub fn run(data: &mut ProcessorData<'_>) -> Result<()> {
         &all_include_directives(data.config)?,
     )?;
 
-    cpp_code_generator::generate_cpp_file(
+    // CMakeLists.txt compiles all *.cpp files in the directory,
+    // so it doesn't need to know the names of the generated files.
+    cpp_code_generator::generate_cpp_files(
         &data.db,
-        &c_lib_path.join("file1.cpp"),
+        &c_lib_path,
         &global_header_name,
+        data.config,
     )?;
 
     let file = create_file(c_lib_path.join("sized_types.cxx"))?;
@@ -279,23 +524,16 @@ pub fn run(data: &mut ProcessorData<'_>) -> Result<()> {
 
     rust_code_generator::generate(
         &data.db,
+        data.config,
+        &rust_features,
         &output_path.join("src"),
-        data.config.crate_template_path().map(|s| s.join("src")),
-    )?;
-
-    // -p shouldn't be needed, it's a workaround for this bug on Windows:
-    // https://github.com/rust-lang/rustfmt/issues/2694
-    run_command(
-        Command::new("cargo")
-            .arg("fmt")
-            .arg(format!("-p{}", crate_name))
-            .current_dir(&output_path),
-    )?;
-    run_command(
-        Command::new("rustfmt")
-            .arg("src/ffi.in.rs")
-            .current_dir(&output_path),
     )?;
+    smoke_tests::generate(&data.db, data.config, &output_path)?;
+    if data.config.write_coverage_report() {
+        let report = CoverageReport::new(&data.db, data.config)?;
+        let mut file = create_file(output_path.join("COVERAGE.md"))?;
+        write!(file, "{}", report.to_markdown())?;
+    }
 
     save_json(
         output_path.join("build_script_data.json"),
@@ -303,14 +541,45 @@ pub fn run(data: &mut ProcessorData<'_>) -> Result<()> {
             cpp_build_config: data.config.cpp_build_config().clone(),
             cpp_wrapper_lib_name: c_lib_name,
             known_targets: data.db.environments().to_vec(),
+            cpp_library_discovery: data.config.cpp_library_discovery().cloned(),
+            dependency_links: data
+                .config
+                .crate_properties()
+                .dependencies()
+                .iter()
+                .filter(|dep| dep.kind() == CrateDependencyKind::Ritual)
+                .map(|dep| format!("{}_c", dep.name()))
+                .collect(),
         },
         None,
     )?;
 
-    copy_file(
-        data.workspace.database_path(crate_name),
-        output_path.join(CRATE_DB_FILE_NAME),
-    )?;
+    // the database published with the crate is always saved as JSON
+    // because the binary format changes between ritual versions
+    data.db
+        .data()
+        .save(output_path.join(CRATE_DB_FILE_NAME), None)?;
+
+    if let Some(overlay_path) = data.config.crate_overlay_path() {
+        apply_crate_overlay(overlay_path, &output_path)?;
+    }
 
+    format_crate(&output_path)?;
+
+    let previous_files = match data.workspace.load_generated_files(crate_name)? {
+        Some(files) => files,
+        None if crate_path.exists() => relative_file_paths(&crate_path, Path::new(""))?,
+        None => Vec::new(),
+    };
+    let mut stats = SyncStats::default();
+    let files = sync_crate_files(&output_path, &crate_path, &previous_files, &mut stats)?;
+    data.workspace.save_generated_files(crate_name, &files)?;
+    remove_dir_all(&output_path)?;
+    data.workspace.update_cargo_toml()?;
+
+    info!(
+        "Crate files: {} written, {} unchanged, {} deleted",
+        stats.written, stats.unchanged, stats.deleted
+    );
     Ok(())
 }
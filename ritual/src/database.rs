@@ -1,24 +1,74 @@
+use crate::config::TypeAllocationPlace;
 use crate::cpp_checks::{CppChecks, CppChecksItem};
-use crate::cpp_data::{CppItem, CppPath};
+use crate::cpp_data::{
+    CppBaseSpecifier, CppClassLayout, CppItem, CppItemOrigin, CppPath, CppTypeDeclarationKind,
+};
 use crate::cpp_ffi_data::CppFfiItem;
+use crate::cpp_function::CppFunction;
+use crate::cpp_type::CppType;
 use crate::rust_info::RustItem;
-use crate::rust_type::RustPath;
+use crate::rust_type::{unraw_identifier, RustPath};
+use itertools::Itertools;
 use log::{debug, error, info, trace, warn};
 use once_cell::sync::OnceCell;
-use ritual_common::errors::{bail, err_msg, format_err, Result};
-use ritual_common::file_utils::load_json;
+use ritual_common::errors::{bail, err_msg, format_err, Result, ResultExt};
+use ritual_common::file_utils::{open_file, save_atomically};
 use ritual_common::string_utils::ends_with_digit;
 use ritual_common::target::LibraryTarget;
 use ritual_common::ReadOnly;
 use serde_derive::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{BufRead, Read, Write};
 use std::iter::once;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::{fmt, mem};
 
 pub const CRATE_DB_FILE_NAME: &str = "ritual_db_v1.json";
 
+/// Header of a database file in the binary format
+const BINARY_DATABASE_MAGIC: &[u8; 8] = b"RITUALDB";
+
+/// Version of the binary format. Must be increased on any change
+/// of the database types because the binary format doesn't support
+/// missing or unknown fields.
+pub const BINARY_DATABASE_VERSION: u32 = 19;
+
+/// Serialization format of a database file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DatabaseFormat {
+    /// Human-readable JSON format
+    Json,
+    /// Compact `bincode` format with a version header
+    Binary,
+}
+
+impl Default for DatabaseFormat {
+    fn default() -> Self {
+        DatabaseFormat::Json
+    }
+}
+
+impl DatabaseFormat {
+    /// Returns extension of database files in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            DatabaseFormat::Json => "json",
+            DatabaseFormat::Binary => "bin",
+        }
+    }
+
+    /// Returns format of the database file `path` based on its extension.
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        if path.as_ref().extension().map_or(false, |ext| ext == "bin") {
+            DatabaseFormat::Binary
+        } else {
+            DatabaseFormat::Json
+        }
+    }
+}
+
 pub struct DatabaseCache(HashMap<PathBuf, IndexedDatabase>);
 
 impl DatabaseCache {
@@ -41,7 +91,7 @@ impl DatabaseCache {
             }
             if path.exists() {
                 info!("Loading database for {}", crate_name);
-                let db = load_json(&path)?;
+                let db = Database::load(&path)?;
                 return Ok(IndexedDatabase::new(db, path));
             }
         }
@@ -170,6 +220,100 @@ pub struct DocItem {
     pub cross_references: Vec<String>,
 }
 
+/// Information about a C++ entity that was skipped during processing
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
+pub struct Rejection {
+    /// C++ name or location of the rejected entity
+    pub cpp_name: String,
+    /// Name of the processing step that rejected the entity
+    pub step: String,
+    /// Reason of the rejection
+    pub reason: String,
+    /// Header the entity was declared in, if known
+    #[serde(default)]
+    pub include_file: Option<String>,
+}
+
+/// Record of a change made to an item by a user-defined hook
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct UserModification {
+    /// Name of the processing step that ran the hook
+    pub step: String,
+    /// Source location where the hook was registered
+    pub hook_site: String,
+}
+
+/// Rust name of a function wrapper that couldn't be preserved
+/// from a previous run of `rust_generator`
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct RustNameChange {
+    /// Signature of the C++ function
+    pub cpp_signature: String,
+    /// Path assigned in a previous run
+    pub old_path: RustPath,
+    /// Path assigned in the last run
+    pub new_path: RustPath,
+    /// Reason the old path couldn't be used
+    pub reason: String,
+}
+
+/// Rename rule of the config applied to a C++ item by `rust_generator`
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct AppliedRenameRule {
+    /// Path of the renamed C++ item
+    pub cpp_path: CppPath,
+    /// Rust path produced by the rule (before resolving conflicts with other items)
+    pub rust_path: RustPath,
+    /// Text of the rule
+    pub rule: String,
+}
+
+/// Source of an allocation place decision
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
+pub enum TypeAllocationSource {
+    /// Chosen automatically
+    Heuristic,
+    /// Set by `Config::set_type_allocation_place`
+    Override,
+    /// Determined by the type mapping set by `Config::map_type`
+    TypeMapping,
+    /// Forced to heap because the class has different layouts
+    /// or allocation places in the merged databases
+    EnvironmentConflict,
+}
+
+/// Summary of `DatabaseClient::merge`
+#[derive(Debug, Default)]
+pub struct MergeReport {
+    /// Classes that were forced to be heap-allocated because
+    /// of different layouts or allocation places
+    pub conflicting_types: Vec<CppPath>,
+    /// Number of FFI items deleted because they pass conflicting types by value
+    pub deleted_ffi_items: usize,
+    /// Number of FFI items ignored because they are different
+    /// from the items generated for the same C++ items in the current database
+    pub different_ffi_items: usize,
+}
+
+/// Allocation place chosen for a C++ class
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
+pub struct TypeAllocationDecision {
+    pub path: CppPath,
+    pub place: TypeAllocationPlace,
+    pub source: TypeAllocationSource,
+    /// Human-readable explanation of the decision
+    #[serde(default)]
+    pub reason: String,
+    /// Result of comparing the recorded layout with the C++ compiler,
+    /// or `None` if the layout wasn't checked
+    #[serde(default)]
+    pub layout_check: Option<bool>,
+    /// Layouts of the class in each environment if they are different
+    /// (recorded when merging databases)
+    #[serde(default)]
+    pub env_layouts: Vec<(LibraryTarget, Option<CppClassLayout>)>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[allow(clippy::large_enum_variant)]
 pub enum DatabaseItemData {
@@ -228,6 +372,13 @@ impl DatabaseItemData {
     pub fn is_cpp_checks_item(&self) -> bool {
         matches!(self, DatabaseItemData::CppChecksItem(_))
     }
+    pub fn as_rust_item_mut(&mut self) -> Option<&mut RustItem> {
+        if let DatabaseItemData::RustItem(data) = self {
+            Some(data)
+        } else {
+            None
+        }
+    }
     pub fn as_cpp_checks_item(&self) -> Option<&CppChecksItem> {
         if let DatabaseItemData::CppChecksItem(data) = self {
             Some(data)
@@ -269,6 +420,37 @@ impl DatabaseItemData {
             DatabaseItemData::DocItem(_) => "DocItem".into(),
         }
     }
+
+    pub fn kind(&self) -> DatabaseItemKind {
+        match self {
+            DatabaseItemData::CppItem(_) => DatabaseItemKind::CppItem,
+            DatabaseItemData::FfiItem(_) => DatabaseItemKind::FfiItem,
+            DatabaseItemData::CppChecksItem(_) => DatabaseItemKind::CppChecksItem,
+            DatabaseItemData::RustItem(_) => DatabaseItemKind::RustItem,
+            DatabaseItemData::DocItem(_) => DatabaseItemKind::DocItem,
+        }
+    }
+
+    /// Returns the name used to search the item: C++ path for C++ and FFI items,
+    /// Rust path for Rust items.
+    pub fn search_name(&self) -> Option<String> {
+        match self {
+            DatabaseItemData::CppItem(item) => item.path().map(CppPath::to_cpp_pseudo_code),
+            DatabaseItemData::FfiItem(item) => Some(item.path().to_cpp_pseudo_code()),
+            DatabaseItemData::RustItem(item) => item.path().map(|path| path.full_name(None)),
+            DatabaseItemData::CppChecksItem(_) | DatabaseItemData::DocItem(_) => None,
+        }
+    }
+}
+
+/// Kind of a database item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DatabaseItemKind {
+    CppItem,
+    FfiItem,
+    CppChecksItem,
+    RustItem,
+    DocItem,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -277,16 +459,153 @@ pub struct Database {
     crate_version: String,
     items: Vec<DbItem<DatabaseItemData>>,
     targets: Vec<LibraryTarget>,
+    /// Target triple the C++ data was parsed for (`None` for the host target)
+    #[serde(default)]
+    target_triple: Option<String>,
+    /// Entities skipped during processing
+    #[serde(default)]
+    rejections: Vec<Rejection>,
+    /// Allocation places of C++ classes
+    #[serde(default)]
+    type_allocation_places: Vec<TypeAllocationDecision>,
+    /// Declaration places of the items found by the C++ parser
+    /// (indexed by item id)
+    #[serde(default)]
+    item_origins: BTreeMap<u32, CppItemOrigin>,
+    /// Content hashes of the header files the C++ parser took items from
+    /// (indexed by the canonical path of the header)
+    #[serde(default)]
+    header_hashes: BTreeMap<String, u64>,
+    /// Items changed by user-defined hooks (indexed by item id)
+    #[serde(default)]
+    user_modifications: BTreeMap<u32, UserModification>,
+    /// Paths of Rust wrappers of C++ functions assigned in previous runs
+    /// (indexed by the signature of the function). Paths of removed functions
+    /// are kept, so that they are never given to other functions.
+    #[serde(default)]
+    rust_names: BTreeMap<String, RustPath>,
+    /// Paths that couldn't be preserved in the last run of `rust_generator`
+    #[serde(default)]
+    rust_name_changes: Vec<RustNameChange>,
+    /// Rename rules applied in the last run of `rust_generator`
+    #[serde(default)]
+    applied_rename_rules: Vec<AppliedRenameRule>,
     next_id: u32,
 }
 
 impl Database {
+    /// Loads a database file saved by `Workspace`. The format is detected
+    /// by the file's content, so both formats can be loaded from any path.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut file = open_file(path)?.into_inner();
+        let is_binary = file
+            .fill_buf()
+            .with_context(|_| format!("failed to read file: {}", path.display()))?
+            .starts_with(BINARY_DATABASE_MAGIC);
+        if is_binary {
+            let mut header = [0; 12];
+            file.read_exact(&mut header)
+                .with_context(|_| format!("failed to read file: {}", path.display()))?;
+            let mut version = [0; 4];
+            version.copy_from_slice(&header[BINARY_DATABASE_MAGIC.len()..]);
+            let version = u32::from_le_bytes(version);
+            if version != BINARY_DATABASE_VERSION {
+                bail!(
+                    "unsupported version of binary database {}: {} (expected {})",
+                    path.display(),
+                    version,
+                    BINARY_DATABASE_VERSION
+                );
+            }
+            Ok(bincode::deserialize_from(file)
+                .with_context(|_| format!("failed to load database: {}", path.display()))?)
+        } else {
+            Ok(serde_json::from_reader(file)
+                .with_context(|_| format!("failed to load database: {}", path.display()))?)
+        }
+    }
+
+    /// Saves the database to `path` in the format corresponding to its extension.
+    /// If `backup_path` is specified, the old file is preserved there.
+    pub fn save(&self, path: impl AsRef<Path>, backup_path: Option<&Path>) -> Result<()> {
+        let path = path.as_ref();
+        save_atomically(path, backup_path, |file| {
+            match DatabaseFormat::from_path(path) {
+                DatabaseFormat::Json => serde_json::to_writer(file, self)
+                    .with_context(|_| format!("failed to save database: {}", path.display()))?,
+                DatabaseFormat::Binary => {
+                    file.write_all(BINARY_DATABASE_MAGIC)
+                        .and_then(|_| file.write_all(&BINARY_DATABASE_VERSION.to_le_bytes()))
+                        .with_context(|_| format!("failed to write file: {}", path.display()))?;
+                    bincode::serialize_into(file, self)
+                        .with_context(|_| format!("failed to save database: {}", path.display()))?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    pub fn crate_name(&self) -> &str {
+        &self.crate_name
+    }
+
+    pub fn crate_version(&self) -> &str {
+        &self.crate_version
+    }
+
+    pub fn environments(&self) -> &[LibraryTarget] {
+        &self.targets
+    }
+
+    /// Returns environments in which Rust items are available, indexed by
+    /// item id. An item is available if its source FFI item passed `cpp_checker`.
+    /// Items that don't depend on the checks are not included.
+    pub fn rust_item_environments(&self) -> HashMap<ItemId, Vec<LibraryTarget>> {
+        let mut successful = HashMap::<ItemId, Vec<LibraryTarget>>::new();
+        let mut sources = HashMap::new();
+        for item in self.items() {
+            if let (Some(source_id), Some(checks)) =
+                (&item.source_id, item.item.as_cpp_checks_item())
+            {
+                let envs = successful.entry(source_id.clone()).or_default();
+                if checks.is_success {
+                    envs.push(checks.env.clone());
+                }
+            }
+            if let Some(source_id) = item.source_id {
+                sources.insert(item.id, source_id);
+            }
+        }
+        let mut map = HashMap::new();
+        for item in self.rust_items() {
+            let mut current_id = &item.id;
+            while let Some(source_id) = sources.get(current_id) {
+                if let Some(envs) = successful.get(source_id) {
+                    map.insert(item.id.clone(), envs.clone());
+                    break;
+                }
+                current_id = source_id;
+            }
+        }
+        map
+    }
+
     pub fn empty(crate_name: String) -> Self {
         Database {
             crate_name: Arc::new(crate_name),
             crate_version: "0.0.0".into(),
             items: Vec::new(),
             targets: Vec::new(),
+            target_triple: None,
+            rejections: Vec::new(),
+            type_allocation_places: Vec::new(),
+            item_origins: BTreeMap::new(),
+            user_modifications: BTreeMap::new(),
+            header_hashes: BTreeMap::new(),
+            rust_names: BTreeMap::new(),
+            rust_name_changes: Vec::new(),
+            applied_rename_rules: Vec::new(),
             next_id: 1,
         }
     }
@@ -297,7 +616,7 @@ impl Database {
     fn items_mut(&mut self) -> impl Iterator<Item = DbItem<&mut DatabaseItemData>> {
         self.items.iter_mut().map(|item| item.as_mut())
     }
-    fn cpp_items(&self) -> impl Iterator<Item = DbItem<&CppItem>> {
+    pub fn cpp_items(&self) -> impl Iterator<Item = DbItem<&CppItem>> {
         self.items()
             .filter_map(|item| item.filter_map(|v| v.as_cpp_item()))
     }
@@ -313,10 +632,14 @@ impl Database {
         self.items_mut()
             .filter_map(|item| item.filter_map(|v| v.as_ffi_item_mut()))
     }
-    fn rust_items(&self) -> impl Iterator<Item = DbItem<&RustItem>> {
+    pub fn rust_items(&self) -> impl Iterator<Item = DbItem<&RustItem>> {
         self.items()
             .filter_map(|item| item.filter_map(|v| v.as_rust_item()))
     }
+    pub fn doc_items(&self) -> impl Iterator<Item = DbItem<&DocItem>> {
+        self.items()
+            .filter_map(|item| item.filter_map(|v| v.as_doc_item()))
+    }
 }
 
 #[derive(Debug)]
@@ -325,7 +648,13 @@ pub struct IndexedDatabase {
     path: PathBuf,
     source_id_to_index: HashMap<Option<ItemId>, Vec<usize>>,
     cpp_path_to_index: HashMap<CppPath, Vec<usize>>,
+    /// Indexes of C++ items nested in a class or a namespace, by path of the parent
+    cpp_parent_path_to_index: HashMap<CppPath, Vec<usize>>,
+    /// Indexes of base specifiers, by path of the derived class
+    cpp_derived_class_to_index: HashMap<CppPath, Vec<usize>>,
     rust_path_to_index: HashMap<RustPath, usize>,
+    /// Set of `db.rejections`, used to skip duplicates
+    rejection_set: HashSet<Rejection>,
 }
 
 impl IndexedDatabase {
@@ -335,7 +664,10 @@ impl IndexedDatabase {
             path,
             source_id_to_index: HashMap::new(),
             cpp_path_to_index: HashMap::new(),
+            cpp_parent_path_to_index: HashMap::new(),
+            cpp_derived_class_to_index: HashMap::new(),
             rust_path_to_index: HashMap::new(),
+            rejection_set: HashSet::new(),
         };
         value.refresh();
         value
@@ -348,26 +680,19 @@ impl IndexedDatabase {
     fn refresh(&mut self) {
         self.source_id_to_index.clear();
         self.cpp_path_to_index.clear();
+        self.cpp_parent_path_to_index.clear();
+        self.cpp_derived_class_to_index.clear();
         self.rust_path_to_index.clear();
-        for (index, item) in self.db.items.iter().enumerate() {
-            self.source_id_to_index
-                .entry(item.source_id.clone())
-                .or_default()
-                .push(index);
-            if let Some(path) = item.item.as_rust_item().and_then(|item| item.path()) {
-                self.rust_path_to_index.insert(path.clone(), index);
-            }
-            if let Some(path) = item.item.as_cpp_item().and_then(|item| item.path()) {
-                self.cpp_path_to_index
-                    .entry(path.clone())
-                    .or_default()
-                    .push(index);
-            }
+        let items = mem::take(&mut self.db.items);
+        for (index, item) in items.iter().enumerate() {
+            self.add_to_index(index, item);
         }
+        self.db.items = items;
+        self.rejection_set = self.db.rejections.iter().cloned().collect();
     }
 
-    fn push(&mut self, item: DbItem<DatabaseItemData>) {
-        let index = self.db.items.len();
+    /// Adds `item` located at `index` to the lookup tables.
+    fn add_to_index(&mut self, index: usize, item: &DbItem<DatabaseItemData>) {
         self.source_id_to_index
             .entry(item.source_id.clone())
             .or_default()
@@ -375,12 +700,31 @@ impl IndexedDatabase {
         if let Some(path) = item.item.as_rust_item().and_then(|item| item.path()) {
             self.rust_path_to_index.insert(path.clone(), index);
         }
-        if let Some(path) = item.item.as_cpp_item().and_then(|item| item.path()) {
-            self.cpp_path_to_index
-                .entry(path.clone())
-                .or_default()
-                .push(index);
+        if let Some(cpp_item) = item.item.as_cpp_item() {
+            if let Some(path) = cpp_item.path() {
+                self.cpp_path_to_index
+                    .entry(path.clone())
+                    .or_default()
+                    .push(index);
+                if path.has_parent() {
+                    self.cpp_parent_path_to_index
+                        .entry(path.parent().expect("path has parent"))
+                        .or_default()
+                        .push(index);
+                }
+            }
+            if let Some(base) = cpp_item.as_base_ref() {
+                self.cpp_derived_class_to_index
+                    .entry(base.derived_class_type.clone())
+                    .or_default()
+                    .push(index);
+            }
         }
+    }
+
+    fn push(&mut self, item: DbItem<DatabaseItemData>) {
+        let index = self.db.items.len();
+        self.add_to_index(index, &item);
         self.db.items.push(item);
     }
 
@@ -394,17 +738,37 @@ impl IndexedDatabase {
             .flat_map(move |ids| ids.iter().map(move |&id| self.db.items[id].as_ref()))
     }
 
-    fn filter_by_cpp_path(&self, path: &CppPath) -> impl Iterator<Item = DbItem<&CppItem>> {
-        self.cpp_path_to_index
-            .get(path)
-            .into_iter()
-            .flat_map(move |ids| {
-                ids.iter().map(move |&id| {
-                    self.db.items[id]
-                        .as_ref()
-                        .map(|item| item.as_cpp_item().expect("invalid db index"))
-                })
+    /// Returns C++ items at `indexes` of `self.db.items`.
+    fn cpp_items_at<'a>(
+        &'a self,
+        indexes: Option<&'a Vec<usize>>,
+    ) -> impl Iterator<Item = DbItem<&'a CppItem>> {
+        indexes.into_iter().flat_map(move |ids| {
+            ids.iter().map(move |&id| {
+                self.db.items[id]
+                    .as_ref()
+                    .map(|item| item.as_cpp_item().expect("invalid db index"))
             })
+        })
+    }
+
+    fn filter_by_cpp_path(&self, path: &CppPath) -> impl Iterator<Item = DbItem<&CppItem>> {
+        self.cpp_items_at(self.cpp_path_to_index.get(path))
+    }
+
+    fn filter_by_cpp_parent_path(
+        &self,
+        parent_path: &CppPath,
+    ) -> impl Iterator<Item = DbItem<&CppItem>> {
+        self.cpp_items_at(self.cpp_parent_path_to_index.get(parent_path))
+    }
+
+    fn filter_by_cpp_derived_class(
+        &self,
+        class_path: &CppPath,
+    ) -> impl Iterator<Item = DbItem<&CppBaseSpecifier>> {
+        self.cpp_items_at(self.cpp_derived_class_to_index.get(class_path))
+            .map(|item| item.map(|item| item.as_base_ref().expect("invalid db index")))
     }
 
     fn find_rust_item(&self, path: &RustPath) -> Option<DbItem<&RustItem>> {
@@ -483,6 +847,7 @@ impl DatabaseClient {
         self.current_database.db.cpp_items()
     }
     pub fn cpp_items_mut(&mut self) -> impl Iterator<Item = DbItem<&mut CppItem>> {
+        self.is_modified = true;
         self.current_database.db.cpp_items_mut()
     }
 
@@ -625,7 +990,19 @@ impl DatabaseClient {
         source_id: Option<ItemId>,
         data: CppItem,
     ) -> Result<Option<ItemId>> {
-        if self.cpp_items().any(|item| item.item.is_same(&data)) {
+        let is_present = match data.path() {
+            Some(path) => self
+                .current_database
+                .filter_by_cpp_path(path)
+                .any(|item| item.item.is_same(&data)),
+            None => self.cpp_items().any(|item| item.item.is_same(&data)),
+        };
+        if is_present {
+            self.counters.items_ignored += 1;
+            return Ok(None);
+        }
+        if self.is_dependency_cpp_item(&data) {
+            trace!("cpp item is declared in a dependency: {}", data);
             self.counters.items_ignored += 1;
             return Ok(None);
         }
@@ -646,6 +1023,26 @@ impl DatabaseClient {
         Ok(Some(id))
     }
 
+    /// Returns true if the same C++ item is declared in one of the dependencies.
+    pub fn is_dependency_cpp_item(&self, data: &CppItem) -> bool {
+        self.dependencies.iter().any(|db| match data.path() {
+            Some(path) => db
+                .filter_by_cpp_path(path)
+                .any(|item| item.item.is_same(data)),
+            None => db.db.cpp_items().any(|item| item.item.is_same(data)),
+        })
+    }
+
+    /// Returns true if a type with `path` is declared in one of the dependencies
+    /// and not in the current crate.
+    pub fn is_dependency_type(&self, path: &CppPath) -> bool {
+        let is_type = |db: &IndexedDatabase| {
+            db.filter_by_cpp_path(path)
+                .any(|item| item.item.as_type_ref().is_some())
+        };
+        !is_type(&self.current_database) && self.dependencies.iter().any(is_type)
+    }
+
     pub fn add_environment(&mut self, env: LibraryTarget) {
         if !self.current_database.db.targets.iter().any(|e| e == &env) {
             self.is_modified = true;
@@ -657,10 +1054,495 @@ impl DatabaseClient {
         &self.current_database.db.targets
     }
 
+    /// Records that an entity was skipped during processing.
+    pub fn add_rejection(&mut self, rejection: Rejection) {
+        let database = &mut self.current_database;
+        if database.rejection_set.insert(rejection.clone()) {
+            self.is_modified = true;
+            database.db.rejections.push(rejection);
+        }
+    }
+
+    /// Removes all rejections recorded by `step`.
+    pub fn clear_rejections(&mut self, step: &str) {
+        self.delete_rejections(|r| r.step == step);
+    }
+
+    /// Removes rejections of entities in `cpp_names` recorded by `step`.
+    /// Should be called when the step attempts to process the entities again.
+    /// Steps should collect the names and call this function once
+    /// instead of calling it for each entity.
+    pub fn clear_item_rejections(&mut self, step: &str, cpp_names: &HashSet<String>) {
+        if cpp_names.is_empty() {
+            return;
+        }
+        self.delete_rejections(|r| r.step == step && cpp_names.contains(&r.cpp_name));
+    }
+
+    /// Removes all rejections matching `function`.
+    pub fn delete_rejections(&mut self, mut function: impl FnMut(&Rejection) -> bool) {
+        let database = &mut self.current_database;
+        let rejection_set = &mut database.rejection_set;
+        let rejections = &mut database.db.rejections;
+        let old_len = rejections.len();
+        rejections.retain(|r| {
+            if function(r) {
+                rejection_set.remove(r);
+                false
+            } else {
+                true
+            }
+        });
+        if rejections.len() != old_len {
+            self.is_modified = true;
+        }
+    }
+
+    pub fn rejections(&self) -> &[Rejection] {
+        &self.current_database.db.rejections
+    }
+
+    /// Returns rejections of entities with names containing `name`,
+    /// e.g. `rejections_for("QString::arg")`.
+    pub fn rejections_for<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Rejection> {
+        self.current_database
+            .db
+            .rejections
+            .iter()
+            .filter(move |r| r.cpp_name.contains(name))
+    }
+
+    /// Records the declaration place of item `id`.
+    pub fn set_item_origin(&mut self, id: &ItemId, origin: CppItemOrigin) {
+        let item_origins = &mut self.current_database.db.item_origins;
+        if item_origins.get(&id.id) != Some(&origin) {
+            item_origins.insert(id.id, origin);
+            self.is_modified = true;
+        }
+    }
+
+    /// Returns the declaration place of item `id`
+    /// if it was recorded by the C++ parser.
+    pub fn item_origin(&self, id: &ItemId) -> Option<&CppItemOrigin> {
+        self.database(&id.crate_name)
+            .ok()?
+            .db
+            .item_origins
+            .get(&id.id)
+    }
+
+    /// Records that item `id` was changed by a user-defined hook.
+    pub fn set_user_modification(&mut self, id: &ItemId, modification: UserModification) {
+        let user_modifications = &mut self.current_database.db.user_modifications;
+        if user_modifications.get(&id.id) != Some(&modification) {
+            user_modifications.insert(id.id, modification);
+            self.is_modified = true;
+        }
+    }
+
+    /// Returns the record of the last change made to item `id`
+    /// by a user-defined hook, if any.
+    pub fn user_modification(&self, id: &ItemId) -> Option<&UserModification> {
+        self.database(&id.crate_name)
+            .ok()?
+            .db
+            .user_modifications
+            .get(&id.id)
+    }
+
+    /// Returns paths of Rust wrappers of C++ functions assigned in previous runs,
+    /// indexed by the signature of the function.
+    pub fn rust_names(&self) -> &BTreeMap<String, RustPath> {
+        &self.current_database.db.rust_names
+    }
+
+    /// Returns the path assigned to the Rust wrapper of the C++ function
+    /// with `cpp_signature` in a previous run.
+    pub fn rust_name(&self, cpp_signature: &str) -> Option<&RustPath> {
+        self.current_database.db.rust_names.get(cpp_signature)
+    }
+
+    /// Records the path of the Rust wrapper of the C++ function with `cpp_signature`.
+    pub fn set_rust_name(&mut self, cpp_signature: String, path: RustPath) {
+        let rust_names = &mut self.current_database.db.rust_names;
+        if rust_names.get(&cpp_signature) != Some(&path) {
+            rust_names.insert(cpp_signature, path);
+            self.is_modified = true;
+        }
+    }
+
+    /// Forgets all recorded paths of Rust wrappers, so that the paths
+    /// are assigned from scratch.
+    pub fn clear_rust_names(&mut self) {
+        if !self.current_database.db.rust_names.is_empty() {
+            self.current_database.db.rust_names.clear();
+            self.is_modified = true;
+        }
+    }
+
+    /// Returns paths that couldn't be preserved in the last run of `rust_generator`.
+    pub fn rust_name_changes(&self) -> &[RustNameChange] {
+        &self.current_database.db.rust_name_changes
+    }
+
+    pub fn add_rust_name_change(&mut self, change: RustNameChange) {
+        self.current_database.db.rust_name_changes.push(change);
+        self.is_modified = true;
+    }
+
+    pub fn clear_rust_name_changes(&mut self) {
+        if !self.current_database.db.rust_name_changes.is_empty() {
+            self.current_database.db.rust_name_changes.clear();
+            self.is_modified = true;
+        }
+    }
+
+    /// Returns rename rules applied in the last run of `rust_generator`.
+    pub fn applied_rename_rules(&self) -> &[AppliedRenameRule] {
+        &self.current_database.db.applied_rename_rules
+    }
+
+    pub fn add_applied_rename_rule(&mut self, rule: AppliedRenameRule) {
+        let rules = &mut self.current_database.db.applied_rename_rules;
+        if !rules.contains(&rule) {
+            rules.push(rule);
+            self.is_modified = true;
+        }
+    }
+
+    pub fn clear_applied_rename_rules(&mut self) {
+        if !self.current_database.db.applied_rename_rules.is_empty() {
+            self.current_database.db.applied_rename_rules.clear();
+            self.is_modified = true;
+        }
+    }
+
+    /// Updates the indexes after paths of items were changed in place.
+    pub fn refresh_indexes(&mut self) {
+        self.current_database.refresh();
+    }
+
+    /// Returns content hashes of the header files recorded by the C++ parser.
+    pub fn header_hashes(&self) -> &BTreeMap<String, u64> {
+        &self.current_database.db.header_hashes
+    }
+
+    /// Replaces the recorded content hashes of the header files.
+    pub fn set_header_hashes(&mut self, hashes: BTreeMap<String, u64>) {
+        if self.current_database.db.header_hashes != hashes {
+            self.current_database.db.header_hashes = hashes;
+            self.is_modified = true;
+        }
+    }
+
+    /// Returns the declaration place of the C++ item that item `id`
+    /// originates from, i.e. the first item in the chain of its sources.
+    /// Items derived from the same C++ item (e.g. a slot wrapper and its FFI functions)
+    /// always get the same result.
+    pub fn root_item_origin(&self, id: &ItemId) -> Result<Option<&CppItemOrigin>> {
+        let mut current_item = self.item(id)?;
+        while let Some(source_id) = &current_item.source_id {
+            current_item = self.item(source_id)?;
+        }
+        Ok(self.item_origin(&current_item.id))
+    }
+
+    /// Records the allocation place of a class, replacing the previous decision.
+    pub fn set_type_allocation_place(&mut self, decision: TypeAllocationDecision) {
+        let decisions = &mut self.current_database.db.type_allocation_places;
+        if let Some(old) = decisions.iter_mut().find(|d| d.path == decision.path) {
+            if *old != decision {
+                *old = decision;
+                self.is_modified = true;
+            }
+        } else {
+            decisions.push(decision);
+            self.is_modified = true;
+        }
+    }
+
+    /// Adds items of `other`, generated for other environments, to the current
+    /// database. Equal items are merged, so that checks of each FFI item record
+    /// all environments where it's available. Rust items of `other` are ignored
+    /// because they should be generated again from the merged data.
+    ///
+    /// Classes that have different layouts or allocation places in the databases
+    /// are made heap-allocated. FFI items that pass such classes by value
+    /// are deleted because they need to be generated again.
+    pub fn merge(&mut self, other: &Database) -> Result<MergeReport> {
+        if other.crate_name != self.current_database.db.crate_name {
+            bail!(
+                "can't merge database of {} into database of {}",
+                other.crate_name,
+                self.current_database.db.crate_name
+            );
+        }
+        let old_environments = self.environments().to_vec();
+        for env in &other.targets {
+            self.add_environment(env.clone());
+        }
+
+        let mut report = MergeReport::default();
+        let mut id_map = HashMap::<ItemId, ItemId>::new();
+        for item in &other.items {
+            let source_id = match &item.source_id {
+                Some(id) if id.crate_name == other.crate_name => match id_map.get(id) {
+                    Some(id) => Some(id.clone()),
+                    // parent item was not merged
+                    None => continue,
+                },
+                source_id => source_id.clone(),
+            };
+            let new_id = match &item.item {
+                DatabaseItemData::CppItem(cpp_item) => {
+                    let existing = self
+                        .cpp_items()
+                        .find(|other| other.item.is_same(cpp_item))
+                        .map(|other| other.id);
+                    match existing {
+                        Some(id) => Some(id),
+                        None => {
+                            let id = self.add_cpp_item_without_hook(source_id, cpp_item.clone())?;
+                            if let (Some(id), Some(origin)) =
+                                (&id, other.item_origins.get(&item.id.id))
+                            {
+                                self.set_item_origin(id, origin.clone());
+                            }
+                            if let (Some(id), Some(modification)) =
+                                (&id, other.user_modifications.get(&item.id.id))
+                            {
+                                self.set_user_modification(id, modification.clone());
+                            }
+                            id
+                        }
+                    }
+                }
+                DatabaseItemData::FfiItem(ffi_item) => {
+                    let existing = self
+                        .current_database
+                        .filter_by_source(&source_id)
+                        .filter_map(|other| other.filter_map(|other| other.as_ffi_item()))
+                        .find(|other| other.item.has_same_kind(ffi_item))
+                        .map(|other| (other.id, other.item == ffi_item));
+                    match existing {
+                        Some((id, true)) => Some(id),
+                        Some((_, false)) => {
+                            warn!(
+                                "FFI item differs between environments: {}",
+                                ffi_item.short_text()
+                            );
+                            report.different_ffi_items += 1;
+                            None
+                        }
+                        None => self.add_ffi_item(source_id, ffi_item.clone())?,
+                    }
+                }
+                DatabaseItemData::CppChecksItem(checks_item) => {
+                    if let Some(source_id) = source_id {
+                        self.add_cpp_checks_item(source_id, checks_item.clone());
+                    }
+                    None
+                }
+                DatabaseItemData::DocItem(doc_item) => {
+                    if let Some(source_id) = source_id {
+                        self.add_doc_item(source_id, doc_item.clone());
+                    }
+                    None
+                }
+                DatabaseItemData::RustItem(_) => None,
+            };
+            if let Some(new_id) = new_id {
+                id_map.insert(item.id.clone(), new_id);
+            }
+        }
+
+        for rejection in &other.rejections {
+            self.add_rejection(rejection.clone());
+        }
+        for (cpp_signature, path) in &other.rust_names {
+            if self.rust_name(cpp_signature).is_none() {
+                self.set_rust_name(cpp_signature.clone(), path.clone());
+            }
+        }
+        self.merge_type_allocation_places(other, &old_environments, &mut report);
+
+        let conflicting_types = report
+            .conflicting_types
+            .iter()
+            .map(|path| CppType::Class(path.clone()))
+            .collect::<Vec<_>>();
+        let mut deleted_ffi_items = Vec::new();
+        self.delete_items(|item| {
+            let function = match item.item.as_ffi_item() {
+                Some(CppFfiItem::Function(function)) => function,
+                _ => return false,
+            };
+            let uses_conflicting_type = once(&function.return_type)
+                .chain(function.arguments.iter().map(|arg| &arg.argument_type))
+                .any(|t| conflicting_types.contains(t.original_type()));
+            if uses_conflicting_type {
+                deleted_ffi_items.push(function.path.to_cpp_pseudo_code());
+            }
+            uses_conflicting_type
+        });
+        for cpp_name in deleted_ffi_items {
+            self.add_rejection(Rejection {
+                cpp_name,
+                step: "merge".to_string(),
+                reason: "allocation place of a class differs between environments".to_string(),
+                include_file: None,
+            });
+            report.deleted_ffi_items += 1;
+        }
+        Ok(report)
+    }
+
+    /// Merges allocation places of classes of `other` into the current database.
+    /// `environments` are the environments of the current database before merging.
+    fn merge_type_allocation_places(
+        &mut self,
+        other: &Database,
+        environments: &[LibraryTarget],
+        report: &mut MergeReport,
+    ) {
+        fn class_layout(db: &Database, path: &CppPath) -> Option<CppClassLayout> {
+            db.cpp_items().find_map(|item| match item.item {
+                CppItem::Type(type1) if &type1.path == path => match &type1.kind {
+                    CppTypeDeclarationKind::Class { layout, .. } => layout.clone(),
+                    _ => None,
+                },
+                _ => None,
+            })
+        }
+
+        for decision in &other.type_allocation_places {
+            let existing = self
+                .current_database
+                .db
+                .type_allocation_places
+                .iter()
+                .find(|d| d.path == decision.path)
+                .cloned();
+            let existing = match existing {
+                Some(existing) => existing,
+                None => {
+                    self.set_type_allocation_place(decision.clone());
+                    continue;
+                }
+            };
+            let layout = class_layout(&self.current_database.db, &decision.path);
+            let other_layout = class_layout(other, &decision.path);
+            let is_conflict = existing.source == TypeAllocationSource::EnvironmentConflict
+                || existing.place != decision.place
+                || (existing.place == TypeAllocationPlace::Stack && layout != other_layout);
+            if !is_conflict {
+                continue;
+            }
+            let mut env_layouts = existing.env_layouts;
+            if env_layouts.is_empty() {
+                env_layouts.extend(environments.iter().map(|env| (env.clone(), layout.clone())));
+            }
+            env_layouts.extend(
+                other
+                    .targets
+                    .iter()
+                    .map(|env| (env.clone(), other_layout.clone())),
+            );
+            if existing.place == TypeAllocationPlace::Stack
+                || decision.place == TypeAllocationPlace::Stack
+            {
+                report.conflicting_types.push(decision.path.clone());
+            }
+            self.set_type_allocation_place(TypeAllocationDecision {
+                path: decision.path.clone(),
+                place: TypeAllocationPlace::Heap,
+                source: TypeAllocationSource::EnvironmentConflict,
+                reason: "layouts or allocation places differ between environments".into(),
+                layout_check: None,
+                env_layouts,
+            });
+        }
+    }
+
+    /// Returns allocation places recorded for the current crate.
+    pub fn type_allocation_places(&self) -> &[TypeAllocationDecision] {
+        &self.current_database.db.type_allocation_places
+    }
+
+    /// Returns the allocation place of class `path` recorded in the current
+    /// crate or its dependencies. Classes without a recorded decision
+    /// are heap-allocated.
+    pub fn type_allocation_place(&self, path: &CppPath) -> TypeAllocationPlace {
+        self.all_databases()
+            .flat_map(|d| d.db.type_allocation_places.iter())
+            .find(|d| &d.path == path)
+            .map_or(TypeAllocationPlace::Heap, |d| d.place)
+    }
+
+    /// Returns all stack-allocated classes of the current crate and its dependencies.
+    pub fn stack_allocated_types(&self) -> Vec<CppPath> {
+        self.all_databases()
+            .flat_map(|d| d.db.type_allocation_places.iter())
+            .filter(|d| d.place == TypeAllocationPlace::Stack)
+            .map(|d| d.path.clone())
+            .collect()
+    }
+
+    pub fn target_triple(&self) -> Option<&str> {
+        self.current_database.db.target_triple.as_deref()
+    }
+
+    pub fn set_target_triple(&mut self, triple: Option<String>) {
+        if self.current_database.db.target_triple != triple {
+            self.is_modified = true;
+            self.current_database.db.target_triple = triple;
+        }
+    }
+
     pub fn find_rust_item(&self, path: &RustPath) -> Option<DbItem<&RustItem>> {
         self.current_database.find_rust_item(path)
     }
 
+    /// Returns the Rust item with `path` from the current crate or its dependencies.
+    pub fn find_rust_item_in_all_crates(&self, path: &RustPath) -> Option<DbItem<&RustItem>> {
+        self.all_databases()
+            .find(|db| db.db.crate_name() == path.crate_name())
+            .and_then(|db| db.find_rust_item(path))
+    }
+
+    /// Returns true if the item is generated unconditionally, i.e. its
+    /// FFI function (if any) passed `cpp_checker` in all known environments.
+    pub fn is_always_available(&self, id: &ItemId) -> Result<bool> {
+        if let Some(ffi_item) = self.source_ffi_item(id)? {
+            let checks = self.cpp_checks(&ffi_item.id)?;
+            return Ok(checks.all_success(self.environments()));
+        }
+        Ok(true)
+    }
+
+    /// Returns true if the Rust item at `path` can be used outside of its crate.
+    pub fn is_reachable(&self, path: &RustPath) -> bool {
+        let mut current = path.clone();
+        while let Ok(parent) = current.parent() {
+            if parent.parts.len() < 2 {
+                return true;
+            }
+            let is_public = match self
+                .find_rust_item_in_all_crates(&parent)
+                .map(|item| item.item)
+            {
+                Some(RustItem::Module(module)) => module.is_public,
+                Some(RustItem::Struct(rust_struct)) => rust_struct.is_public,
+                _ => false,
+            };
+            if !is_public {
+                return false;
+            }
+            current = parent;
+        }
+        true
+    }
+
     pub fn rust_children<'a>(
         &'a self,
         path: &'a RustPath,
@@ -724,22 +1606,29 @@ impl DatabaseClient {
     }
 
     pub fn make_unique_rust_path(&self, path: &RustPath) -> RustPath {
+        self.make_unique_rust_path_with(path, |_| false)
+    }
+
+    /// Same as `make_unique_rust_path`, but also skips paths
+    /// for which `is_taken` returns true.
+    pub fn make_unique_rust_path_with(
+        &self,
+        path: &RustPath,
+        is_taken: impl Fn(&RustPath) -> bool,
+    ) -> RustPath {
         let mut number = None;
         let mut path_try = path.clone();
+        let name = unraw_identifier(path.last());
         loop {
             if let Some(number) = number {
                 *path_try.last_mut() = format!(
                     "{}{}{}",
-                    path.last(),
-                    if ends_with_digit(path.last()) {
-                        "_"
-                    } else {
-                        ""
-                    },
+                    name,
+                    if ends_with_digit(name) { "_" } else { "" },
                     number
                 );
             }
-            if self.find_rust_item(&path_try).is_none() {
+            if self.find_rust_item(&path_try).is_none() && !is_taken(&path_try) {
                 return path_try;
             }
 
@@ -842,6 +1731,15 @@ impl DatabaseClient {
     fn delete_children(&mut self, mut ids: HashSet<ItemId>) {
         let mut items_deleted = 0;
         loop {
+            let db = &mut self.current_database.db;
+            let local_ids = ids
+                .iter()
+                .filter(|id| id.crate_name == db.crate_name)
+                .map(|id| id.id)
+                .collect::<HashSet<_>>();
+            db.item_origins.retain(|id, _| !local_ids.contains(id));
+            db.user_modifications
+                .retain(|id, _| !local_ids.contains(id));
             let mut new_ids = HashSet::new();
             self.current_database.db.items.retain(|i| {
                 let result = i
@@ -880,6 +1778,22 @@ impl DatabaseClient {
         }
     }
 
+    /// Returns the layout of the C++ class item `id` was generated from,
+    /// if the layout is known.
+    pub fn source_class_layout(&self, id: &ItemId) -> Result<Option<&CppClassLayout>> {
+        let cpp_item = match self.source_cpp_item(id)? {
+            Some(item) => item,
+            None => return Ok(None),
+        };
+        Ok(match cpp_item.item {
+            CppItem::Type(type1) => match &type1.kind {
+                CppTypeDeclarationKind::Class { layout, .. } => layout.as_ref(),
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+
     pub fn original_cpp_item(&self, id: &ItemId) -> Result<Option<DbItem<&CppItem>>> {
         let mut current_item = self.item(id)?;
         let mut last_cpp_item = None;
@@ -940,10 +1854,59 @@ impl DatabaseClient {
         self.all_databases().flat_map(|d| d.db.cpp_items())
     }
 
+    /// Returns C++ items of the current crate with `path`.
+    pub fn cpp_items_by_path(&self, path: &CppPath) -> impl Iterator<Item = DbItem<&CppItem>> {
+        self.current_database.filter_by_cpp_path(path)
+    }
+
+    /// Returns C++ items of the current crate and its dependencies with `path`.
+    pub fn all_cpp_items_by_path<'a: 'p, 'p>(
+        &'a self,
+        path: &'p CppPath,
+    ) -> impl Iterator<Item = DbItem<&'a CppItem>> + 'p {
+        self.all_databases()
+            .flat_map(move |db| db.filter_by_cpp_path(path))
+    }
+
+    /// Returns C++ items of the current crate and its dependencies nested
+    /// in `parent_path`, e.g. members of a class.
+    pub fn all_cpp_items_by_parent_path<'a: 'p, 'p>(
+        &'a self,
+        parent_path: &'p CppPath,
+    ) -> impl Iterator<Item = DbItem<&'a CppItem>> + 'p {
+        self.all_databases()
+            .flat_map(move |db| db.filter_by_cpp_parent_path(parent_path))
+    }
+
+    /// Returns member functions of `class_path` declared in the current crate
+    /// and its dependencies.
+    pub fn all_cpp_methods_of<'a: 'p, 'p>(
+        &'a self,
+        class_path: &'p CppPath,
+    ) -> impl Iterator<Item = DbItem<&'a CppFunction>> + 'p {
+        self.all_cpp_items_by_parent_path(class_path)
+            .filter_map(|item| item.filter_map(|item| item.as_function_ref()))
+            .filter(|function| function.item.member.is_some())
+    }
+
+    /// Returns direct bases of `class_path` declared in the current crate
+    /// and its dependencies.
+    pub fn all_cpp_bases_of<'a: 'p, 'p>(
+        &'a self,
+        class_path: &'p CppPath,
+    ) -> impl Iterator<Item = DbItem<&'a CppBaseSpecifier>> + 'p {
+        self.all_databases()
+            .flat_map(move |db| db.filter_by_cpp_derived_class(class_path))
+    }
+
     pub fn all_ffi_items(&self) -> impl Iterator<Item = DbItem<&CppFfiItem>> {
         self.all_databases().flat_map(|d| d.db.ffi_items())
     }
 
+    pub fn all_rust_items(&self) -> impl Iterator<Item = DbItem<&RustItem>> {
+        self.all_databases().flat_map(|d| d.db.rust_items())
+    }
+
     pub fn find_rust_items_for_cpp_path(
         &self,
         cpp_path: &CppPath,
@@ -995,6 +1958,12 @@ impl DatabaseClient {
         }
         for source in sources.iter().rev() {
             info!("{:?}", source);
+            if let Some(modification) = self.user_modification(&source.id) {
+                info!(
+                    "    modified by hook registered at {} in step {}",
+                    modification.hook_site, modification.step
+                );
+            }
         }
         info!("Children:");
         self.print_item_children(item_id);
@@ -1012,4 +1981,201 @@ impl DatabaseClient {
             self.print_item_children(&child.id);
         }
     }
+
+    /// Returns a view of the item with `id`.
+    pub fn view(&self, id: &ItemId) -> Result<ItemView<'_>> {
+        Ok(ItemView {
+            client: self,
+            item: self.item(id)?,
+        })
+    }
+
+    /// Finds C++ items of the crate and its dependencies by qualified name,
+    /// e.g. `QIODevice::read`. If argument types are specified
+    /// (`QIODevice::read(char*, qint64)`), only functions with these exact
+    /// argument types are returned. Whitespace in the argument list is ignored.
+    pub fn lookup_cpp_name(&self, name: &str) -> Result<Vec<ItemView<'_>>> {
+        let (path, arguments) = match name.find('(') {
+            Some(index) => {
+                let arguments = name[index..].trim_end();
+                if !arguments.ends_with(')') {
+                    bail!("invalid C++ name: {}", name);
+                }
+                let arguments = remove_whitespace(&arguments[1..arguments.len() - 1]);
+                (&name[..index], Some(arguments))
+            }
+            None => (name, None),
+        };
+        let path = CppPath::from_str(path.trim())?;
+        let arguments_match = |item: &CppItem| {
+            let arguments = if let Some(arguments) = &arguments {
+                arguments
+            } else {
+                return true;
+            };
+            item.as_function_ref().map_or(false, |function| {
+                let function_arguments = function
+                    .arguments
+                    .iter()
+                    .map(|arg| arg.argument_type.to_cpp_pseudo_code())
+                    .join(",");
+                remove_whitespace(&function_arguments) == *arguments
+            })
+        };
+
+        Ok(self
+            .lookup_cpp_path(&path)
+            .into_iter()
+            .filter(|view| view.item.item.as_cpp_item().map_or(false, &arguments_match))
+            .collect())
+    }
+
+    /// Returns C++ items of the crate and its dependencies with `path`
+    /// (e.g. all overloads of a function).
+    pub fn lookup_cpp_path(&self, path: &CppPath) -> Vec<ItemView<'_>> {
+        self.all_databases()
+            .flat_map(|db| {
+                db.cpp_path_to_index
+                    .get(path)
+                    .into_iter()
+                    .flat_map(move |ids| ids.iter().map(move |&id| db.db.items[id].as_ref()))
+            })
+            .map(|item| ItemView { client: self, item })
+            .collect()
+    }
+
+    /// Finds the Rust item of the crate or its dependencies by full path,
+    /// e.g. `qt_core::QIODevice`.
+    pub fn lookup_rust_path(&self, path: &str) -> Result<Option<ItemView<'_>>> {
+        let path = RustPath::from_str(path)?;
+        Ok(self
+            .all_databases()
+            .find_map(|db| {
+                db.rust_path_to_index
+                    .get(&path)
+                    .map(|&index| db.db.items[index].as_ref())
+            })
+            .map(|item| ItemView { client: self, item }))
+    }
+
+    /// Returns items of the current crate whose search name contains `pattern`.
+    /// The search is case-insensitive.
+    pub fn search<'a>(&'a self, pattern: &str) -> impl Iterator<Item = ItemView<'a>> {
+        let pattern = pattern.to_lowercase();
+        self.items()
+            .filter(move |item| {
+                item.item
+                    .search_name()
+                    .map_or(false, |name| name.to_lowercase().contains(&pattern))
+            })
+            .map(move |item| ItemView { client: self, item })
+    }
+
+    /// Returns all items of the current crate of the specified kind.
+    pub fn items_of_kind(&self, kind: DatabaseItemKind) -> impl Iterator<Item = ItemView<'_>> {
+        self.items()
+            .filter(move |item| item.item.kind() == kind)
+            .map(move |item| ItemView { client: self, item })
+    }
+}
+
+fn remove_whitespace(text: &str) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// Borrowed view of a database item that allows to navigate to
+/// the items it was generated from and the items generated from it.
+#[derive(Debug, Clone)]
+pub struct ItemView<'a> {
+    client: &'a DatabaseClient,
+    item: DbItem<&'a DatabaseItemData>,
+}
+
+impl<'a> ItemView<'a> {
+    pub fn item(&self) -> &DbItem<&'a DatabaseItemData> {
+        &self.item
+    }
+
+    pub fn id(&self) -> &ItemId {
+        &self.item.id
+    }
+
+    pub fn kind(&self) -> DatabaseItemKind {
+        self.item.item.kind()
+    }
+
+    /// Returns the record of the change made to this item by a user-defined hook.
+    pub fn user_modification(&self) -> Option<&'a UserModification> {
+        self.client.user_modification(&self.item.id)
+    }
+
+    /// Returns the items this item was generated from,
+    /// starting with its direct source.
+    pub fn sources(&self) -> Result<Vec<ItemView<'a>>> {
+        let mut sources = Vec::new();
+        let mut source_id = self.item.source_id.clone();
+        while let Some(id) = source_id {
+            let item = self.client.item(&id)?;
+            source_id = item.source_id.clone();
+            sources.push(ItemView {
+                client: self.client,
+                item,
+            });
+        }
+        Ok(sources)
+    }
+
+    /// Returns the items directly generated from this item.
+    pub fn derived(&self) -> Vec<ItemView<'a>> {
+        let client = self.client;
+        let item_id = Some(self.item.id.clone());
+        client
+            .all_databases()
+            .flat_map(|db| db.filter_by_source(&item_id))
+            .map(|item| ItemView { client, item })
+            .collect()
+    }
+
+    /// Returns all items directly or indirectly generated from this item.
+    pub fn descendants(&self) -> Vec<ItemView<'a>> {
+        let mut descendants = self.derived();
+        let mut index = 0;
+        while index < descendants.len() {
+            let derived = descendants[index].derived();
+            descendants.extend(derived);
+            index += 1;
+        }
+        descendants
+    }
+
+    /// Returns the C++ item produced by the C++ parser this item originates from
+    /// (the item itself if it's such an item).
+    pub fn original_cpp_item(&self) -> Result<Option<DbItem<&'a CppItem>>> {
+        self.client.original_cpp_item(&self.item.id)
+    }
+
+    /// Returns the FFI item this item was generated from
+    /// (the item itself if it's an FFI item).
+    pub fn ffi_item(&self) -> Result<Option<DbItem<&'a CppFfiItem>>> {
+        if let Some(item) = self.item.clone().filter_map(|i| i.as_ffi_item()) {
+            return Ok(Some(item));
+        }
+        self.client.source_ffi_item(&self.item.id)
+    }
+
+    /// Returns the FFI items generated from this item.
+    pub fn ffi_items(&self) -> Vec<DbItem<&'a CppFfiItem>> {
+        self.descendants()
+            .into_iter()
+            .filter_map(|view| view.item.filter_map(|i| i.as_ffi_item()))
+            .collect()
+    }
+
+    /// Returns the Rust items generated from this item.
+    pub fn rust_items(&self) -> Vec<DbItem<&'a RustItem>> {
+        self.descendants()
+            .into_iter()
+            .filter_map(|view| view.item.filter_map(|i| i.as_rust_item()))
+            .collect()
+    }
 }
@@ -0,0 +1,283 @@
+//! Comparison of databases produced by two generation runs.
+
+use crate::cpp_data::CppItem;
+use crate::cpp_function::CppFunction;
+use crate::database::Database;
+use itertools::Itertools;
+use ritual_common::target::LibraryTarget;
+use serde_derive::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// Category of a compared item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum DiffItemKind {
+    CppType,
+    CppFunction,
+    CppEnumValue,
+    RustItem,
+}
+
+/// Kind of a change of an item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum DiffChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// Semver bump required by a set of changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum VersionBump {
+    /// No changes of the API
+    Patch,
+    /// Only additions
+    Minor,
+    /// Some items were removed or changed
+    Major,
+}
+
+/// A change of a single item
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiffEntry {
+    pub item_kind: DiffItemKind,
+    pub change: DiffChangeKind,
+    /// Identifier of the item (e.g. the path and argument types of a function)
+    pub key: String,
+    /// Description of the item in the old database
+    pub old: Option<String>,
+    /// Description of the item in the new database
+    pub new: Option<String>,
+}
+
+impl DiffEntry {
+    /// Returns true if the change can break code using the generated crate.
+    pub fn is_breaking(&self) -> bool {
+        self.change != DiffChangeKind::Added
+    }
+}
+
+/// Differences between two databases
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DatabaseDiff {
+    pub entries: Vec<DiffEntry>,
+}
+
+impl DatabaseDiff {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the semver bump required by the changes.
+    pub fn version_bump(&self) -> VersionBump {
+        if self.entries.iter().any(DiffEntry::is_breaking) {
+            VersionBump::Major
+        } else if self.entries.is_empty() {
+            VersionBump::Patch
+        } else {
+            VersionBump::Minor
+        }
+    }
+
+    /// Returns a human-readable report.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let groups = [("Breaking changes", true), ("Additions", false)];
+        for &(title, is_breaking) in &groups {
+            let entries = self
+                .entries
+                .iter()
+                .filter(|entry| entry.is_breaking() == is_breaking)
+                .collect_vec();
+            if entries.is_empty() {
+                continue;
+            }
+            writeln!(text, "{} ({}):", title, entries.len()).unwrap();
+            for entry in entries {
+                match entry.change {
+                    DiffChangeKind::Added => {
+                        writeln!(text, "    + {}", entry.new.as_ref().unwrap()).unwrap();
+                    }
+                    DiffChangeKind::Removed => {
+                        writeln!(text, "    - {}", entry.old.as_ref().unwrap()).unwrap();
+                    }
+                    DiffChangeKind::Changed => {
+                        writeln!(text, "    - {}", entry.old.as_ref().unwrap()).unwrap();
+                        writeln!(text, "    + {}", entry.new.as_ref().unwrap()).unwrap();
+                    }
+                }
+            }
+        }
+        writeln!(text, "Suggested version bump: {:?}", self.version_bump()).unwrap();
+        text
+    }
+}
+
+/// Returns the identifier of a function overload that doesn't depend
+/// on its return type.
+fn function_key(function: &CppFunction) -> String {
+    let arguments = function
+        .arguments
+        .iter()
+        .map(|arg| arg.argument_type.to_cpp_pseudo_code())
+        .join(", ");
+    let is_const = function.member.as_ref().map_or(false, |m| m.is_const);
+    format!(
+        "{}({}){}",
+        function.path.to_cpp_pseudo_code(),
+        arguments,
+        if is_const { " const" } else { "" }
+    )
+}
+
+/// An item prepared for comparison
+struct ApiItem {
+    /// Data that affects the generated API
+    comparable: String,
+    /// Description of the item for the report
+    text: String,
+    /// Environments in which the item is available
+    /// (`None` if it doesn't depend on the environment)
+    environments: Option<BTreeSet<String>>,
+}
+
+impl ApiItem {
+    fn new(text: String) -> Self {
+        ApiItem {
+            comparable: text.clone(),
+            text,
+            environments: None,
+        }
+    }
+}
+
+/// Returns comparable items of `database` grouped by kind and indexed by key.
+/// Only the data that affects the generated API is compared,
+/// so that item ids, declaration places and argument names don't cause changes.
+/// Availability of Rust items is only recorded for `environments`.
+fn api_items(
+    database: &Database,
+    environments: &[LibraryTarget],
+) -> BTreeMap<(DiffItemKind, String), ApiItem> {
+    let mut items = BTreeMap::new();
+    for item in database.cpp_items() {
+        let (kind, key, api_item) = match &item.item {
+            CppItem::Type(data) => (
+                DiffItemKind::CppType,
+                data.path.to_cpp_pseudo_code(),
+                ApiItem::new(item.item.to_string()),
+            ),
+            CppItem::Function(data) => {
+                let mut unnamed = data.clone();
+                for arg in &mut unnamed.arguments {
+                    arg.name.clear();
+                }
+                let api_item = ApiItem {
+                    comparable: unnamed.short_text(),
+                    text: data.short_text(),
+                };
+                (DiffItemKind::CppFunction, function_key(data), api_item)
+            }
+            CppItem::EnumValue(data) => (
+                DiffItemKind::CppEnumValue,
+                data.path.to_cpp_pseudo_code(),
+                ApiItem::new(format!(
+                    "{} = {}",
+                    data.path.to_cpp_pseudo_code(),
+                    data.value
+                )),
+            ),
+            _ => continue,
+        };
+        items.insert((kind, key), api_item);
+    }
+    let rust_item_environments = database.rust_item_environments();
+    for item in database.rust_items() {
+        if let Some(path) = item.item.path() {
+            let mut api_item = ApiItem::new(item.item.short_text());
+            api_item.environments = rust_item_environments.get(&item.id).map(|envs| {
+                envs.iter()
+                    .filter(|env| environments.contains(env))
+                    .map(LibraryTarget::short_text)
+                    .collect()
+            });
+            items.insert((DiffItemKind::RustItem, path.full_name(None)), api_item);
+        }
+    }
+    items
+}
+
+/// Compares C++ types, functions, enum values and names of Rust items
+/// of two databases. Availability of Rust items is compared separately
+/// for each environment present in both databases, so an item that
+/// became unavailable in one of them is reported as removed in that environment.
+pub fn diff(old: &Database, new: &Database) -> DatabaseDiff {
+    let environments = old
+        .environments()
+        .iter()
+        .filter(|env| new.environments().contains(env))
+        .cloned()
+        .collect_vec();
+    let all_environments = environments
+        .iter()
+        .map(LibraryTarget::short_text)
+        .collect::<BTreeSet<_>>();
+    let old_items = api_items(old, &environments);
+    let new_items = api_items(new, &environments);
+    let mut entries = Vec::new();
+    for ((item_kind, key), old_item) in &old_items {
+        match new_items.get(&(*item_kind, key.clone())) {
+            None => entries.push(DiffEntry {
+                item_kind: *item_kind,
+                change: DiffChangeKind::Removed,
+                key: key.clone(),
+                old: Some(old_item.text.clone()),
+                new: None,
+            }),
+            Some(new_item) if new_item.comparable != old_item.comparable => {
+                entries.push(DiffEntry {
+                    item_kind: *item_kind,
+                    change: DiffChangeKind::Changed,
+                    key: key.clone(),
+                    old: Some(old_item.text.clone()),
+                    new: Some(new_item.text.clone()),
+                })
+            }
+            Some(new_item) => {
+                let old_envs = old_item.environments.as_ref().unwrap_or(&all_environments);
+                let new_envs = new_item.environments.as_ref().unwrap_or(&all_environments);
+                for env in old_envs.difference(new_envs) {
+                    entries.push(DiffEntry {
+                        item_kind: *item_kind,
+                        change: DiffChangeKind::Removed,
+                        key: format!("{} ({})", key, env),
+                        old: Some(format!("{} ({})", old_item.text, env)),
+                        new: None,
+                    });
+                }
+                for env in new_envs.difference(old_envs) {
+                    entries.push(DiffEntry {
+                        item_kind: *item_kind,
+                        change: DiffChangeKind::Added,
+                        key: format!("{} ({})", key, env),
+                        old: None,
+                        new: Some(format!("{} ({})", new_item.text, env)),
+                    });
+                }
+            }
+        }
+    }
+    for ((item_kind, key), new_item) in &new_items {
+        if !old_items.contains_key(&(*item_kind, key.clone())) {
+            entries.push(DiffEntry {
+                item_kind: *item_kind,
+                change: DiffChangeKind::Added,
+                key: key.clone(),
+                old: None,
+                new: Some(new_item.text.clone()),
+            });
+        }
+    }
+    entries.sort_by(|a, b| (a.item_kind, &a.key).cmp(&(b.item_kind, &b.key)));
+    DatabaseDiff { entries }
+}
@@ -3,17 +3,28 @@
 
 #![allow(dead_code)]
 
-use crate::cpp_ffi_data::{CppFfiFunctionKind, CppFfiItem, CppFieldAccessorType};
-use crate::cpp_type::CppType;
-use crate::database::{DatabaseClient, DbItem, DocItem};
+use crate::config::Config;
+use crate::cpp_data::{CppItem, CppItemOrigin};
+use crate::cpp_ffi_data::{
+    CppFfiArgumentMeaning, CppFfiFunctionKind, CppFfiItem, CppFieldAccessorType,
+};
+use crate::cpp_function::CppFunction;
+use crate::cpp_operator::CppOperator;
+use crate::cpp_type::{CppPointerLikeTypeKind, CppType};
+use crate::database::{DatabaseClient, DbItem, DocItem, ItemId};
 use crate::rust_code_generator::rust_type_to_code;
 use crate::rust_info::{
-    RustEnumValue, RustFunction, RustFunctionKind, RustModule, RustModuleKind, RustQtReceiverType,
-    RustSpecialModuleKind, RustStruct, RustStructKind, RustWrapperTypeKind,
+    RustEnumValue, RustExtraImplKind, RustFunction, RustFunctionKind, RustItem, RustModule,
+    RustModuleKind, RustOverloadedFunction, RustQtReceiverType, RustSpecialModuleKind, RustStruct,
+    RustStructKind, RustWrapperTypeKind,
 };
+use crate::rust_type::{RustPath, RustPointerLikeTypeKind, RustType};
 use itertools::Itertools;
 use ritual_common::errors::{bail, err_msg, Result};
+use std::collections::HashSet;
 use std::fmt::Write;
+use std::iter::once;
+use std::mem;
 
 pub fn wrap_inline_cpp_code(code: &str) -> String {
     format!("<span style='color: green;'>```{}```</span>", code)
@@ -78,6 +89,24 @@ pub fn module_doc(module: DbItem<&RustModule>, database: &DatabaseClient) -> Res
             let cpp_path_text = wrap_inline_cpp_code(&cpp_item.path.to_cpp_pseudo_code());
             write!(output, "C++ type: {}", cpp_path_text)?;
         }
+        RustModuleKind::CppHeader => {
+            // header modules don't have a source item,
+            // so the header is taken from any item of the module
+            let mut include_file = None;
+            for item in database.rust_children(&module.item.path) {
+                if let Some(origin) = database.root_item_origin(&item.id)? {
+                    include_file = Some(origin.include_file.clone());
+                    break;
+                }
+            }
+            if let Some(include_file) = include_file {
+                write!(
+                    output,
+                    "C++ header: {}",
+                    wrap_inline_cpp_code(&include_file)
+                )?;
+            }
+        }
     };
     Ok(output)
 }
@@ -92,14 +121,312 @@ fn first_phrase(html: &str) -> &str {
     html
 }
 
-pub fn struct_doc(type1: DbItem<&RustStruct>, database: &DatabaseClient) -> Result<String> {
+/// Removes comment markers from a raw C++ documentation comment.
+/// Doxygen commands and HTML are kept as is.
+fn clean_cpp_comment(raw: &str) -> String {
+    let lines = raw
+        .lines()
+        .map(|line| {
+            let mut line = line.trim();
+            for prefix in &["/**", "/*!", "///", "//!", "*/", "*"] {
+                if let Some(rest) = line.strip_prefix(prefix) {
+                    line = rest;
+                    break;
+                }
+            }
+            if let Some(rest) = line.strip_suffix("*/") {
+                line = rest;
+            }
+            line.trim()
+        })
+        .collect::<Vec<_>>();
+    lines.join("\n").trim().to_string()
+}
+
+/// Section of a Doxygen comment that receives the following text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DoxygenSection {
+    Description,
+    Parameter,
+    Returns,
+    Note,
+    Deprecated,
+}
+
+/// C++ documentation comment with Doxygen commands converted to markdown.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DoxygenComment {
+    /// Paragraphs of the description, starting with the brief description.
+    pub description: Vec<String>,
+    /// C++ names and descriptions of the documented parameters.
+    pub parameters: Vec<(String, String)>,
+    /// Description of the return value.
+    pub returns: Option<String>,
+    pub notes: Vec<String>,
+    /// Text of `\deprecated` command (may be empty).
+    pub deprecated: Option<String>,
+}
+
+/// Splits `line` into a Doxygen command name and the rest of the line
+/// if `line` starts with a command.
+fn split_doxygen_command(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix('\\').or_else(|| line.strip_prefix('@'))?;
+    let name_end = rest
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .unwrap_or(rest.len());
+    if name_end == 0 {
+        return None;
+    }
+    Some((&rest[..name_end], &rest[name_end..]))
+}
+
+/// Escapes characters that rustdoc would interpret as links or HTML tags.
+/// Doxygen escape sequences (e.g. `\<`) are replaced with the escaped characters.
+fn escape_doc_text(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(mut c) = chars.next() {
+        if c == '\\' {
+            if let Some(&next) = chars.peek() {
+                if "\\@&$#<>%\".".contains(next) {
+                    c = next;
+                    chars.next();
+                }
+            }
+        }
+        match c {
+            '[' => output.push_str("\\["),
+            ']' => output.push_str("\\]"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
+/// Converts a line of text to markdown. Inline commands (`\a`, `\p`, `\c`, `\b`, `\e`)
+/// are replaced with markdown formatting. Other commands are kept as plain text.
+fn format_doxygen_text(line: &str) -> String {
+    let mut words = Vec::new();
+    let mut in_code_span = false;
+    let mut iter = line.split_whitespace();
+    while let Some(word) = iter.next() {
+        if word.contains('`') || in_code_span {
+            in_code_span ^= word.matches('`').count() % 2 == 1;
+            words.push(word.to_string());
+            continue;
+        }
+        let inline_command = split_doxygen_command(word)
+            .filter(|(name, rest)| {
+                rest.is_empty() && ["a", "p", "c", "b", "e", "em"].contains(name)
+            })
+            .map(|(name, _)| name);
+        match (inline_command, iter.clone().next()) {
+            (Some(name), Some(argument)) => {
+                iter.next();
+                // trailing punctuation is not a part of the argument
+                let argument_len = argument.trim_end_matches(|c| ".,;:!?".contains(c)).len();
+                let (argument, suffix) = argument.split_at(argument_len);
+                let formatted = match name {
+                    "b" => format!("**{}**", escape_doc_text(argument)),
+                    "e" | "em" => format!("*{}*", escape_doc_text(argument)),
+                    _ => format!("`{}`", argument),
+                };
+                words.push(formatted + &escape_doc_text(suffix));
+            }
+            _ => words.push(escape_doc_text(word)),
+        }
+    }
+    words.join(" ")
+}
+
+impl DoxygenComment {
+    /// Parses a C++ documentation comment with comment markers already removed.
+    /// Unknown commands are treated as plain text.
+    pub fn parse(text: &str) -> Self {
+        let mut comment = DoxygenComment::default();
+        let mut section = DoxygenSection::Description;
+        let mut paragraph = String::new();
+        let mut lines = text.lines();
+        while let Some(line) = lines.next() {
+            let line = line.trim();
+            if line.is_empty() {
+                comment.end_paragraph(section, &mut paragraph);
+                section = DoxygenSection::Description;
+                continue;
+            }
+            let (name, rest) = split_doxygen_command(line).unwrap_or(("", line));
+            let rest = rest.trim();
+            let text = match name {
+                "code" => {
+                    let code = lines
+                        .by_ref()
+                        .take_while(|line| {
+                            let line = line.trim();
+                            line != "\\endcode" && line != "@endcode"
+                        })
+                        .join("\n");
+                    if !paragraph.is_empty() && !paragraph.ends_with('\n') {
+                        paragraph.push('\n');
+                    }
+                    writeln!(paragraph, "```cpp\n{}\n```", code).unwrap();
+                    continue;
+                }
+                "brief" | "short" | "details" => {
+                    comment.end_paragraph(section, &mut paragraph);
+                    section = DoxygenSection::Description;
+                    rest
+                }
+                "param" => {
+                    let rest = if rest.starts_with('[') {
+                        rest.find(']')
+                            .map_or(rest, |index| rest[index + 1..].trim())
+                    } else {
+                        rest
+                    };
+                    let mut parts = rest.splitn(2, char::is_whitespace);
+                    match parts.next().filter(|name| !name.is_empty()) {
+                        Some(parameter) => {
+                            comment.end_paragraph(section, &mut paragraph);
+                            section = DoxygenSection::Parameter;
+                            comment
+                                .parameters
+                                .push((parameter.to_string(), String::new()));
+                            parts.next().unwrap_or("").trim()
+                        }
+                        None => line,
+                    }
+                }
+                "return" | "returns" | "result" => {
+                    comment.end_paragraph(section, &mut paragraph);
+                    section = DoxygenSection::Returns;
+                    rest
+                }
+                "note" => {
+                    comment.end_paragraph(section, &mut paragraph);
+                    section = DoxygenSection::Note;
+                    rest
+                }
+                "deprecated" => {
+                    comment.end_paragraph(section, &mut paragraph);
+                    section = DoxygenSection::Deprecated;
+                    comment.deprecated = Some(String::new());
+                    rest
+                }
+                _ => line,
+            };
+            if !text.is_empty() {
+                if !paragraph.is_empty() && !paragraph.ends_with('\n') {
+                    paragraph.push(' ');
+                }
+                paragraph.push_str(&format_doxygen_text(text));
+            }
+        }
+        comment.end_paragraph(section, &mut paragraph);
+        comment
+    }
+
+    fn end_paragraph(&mut self, section: DoxygenSection, paragraph: &mut String) {
+        let text = paragraph.trim_end().to_string();
+        paragraph.clear();
+        if text.is_empty() {
+            return;
+        }
+        match section {
+            DoxygenSection::Description => self.description.push(text),
+            DoxygenSection::Parameter => {
+                if let Some((_, description)) = self.parameters.last_mut() {
+                    *description = text;
+                }
+            }
+            DoxygenSection::Returns => self.returns = Some(text),
+            DoxygenSection::Note => self.notes.push(text),
+            DoxygenSection::Deprecated => self.deprecated = Some(text),
+        }
+    }
+
+    /// Returns the brief description, if any.
+    pub fn brief(&self) -> Option<&str> {
+        self.description.first().map(String::as_str)
+    }
+
+    /// Generates markdown for the comment. `argument_names` contains C++ names
+    /// of arguments along with the corresponding Rust names.
+    pub fn to_markdown(&self, argument_names: &[(String, String)]) -> String {
+        let mut sections = self.description.clone();
+        if !self.parameters.is_empty() {
+            let mut list = "Arguments:\n".to_string();
+            for (name, description) in &self.parameters {
+                let name = argument_names
+                    .iter()
+                    .find(|(cpp_name, _)| cpp_name == name)
+                    .map_or(name, |(_, rust_name)| rust_name);
+                write!(list, "\n- `{}`", name).unwrap();
+                if !description.is_empty() {
+                    write!(list, ": {}", description.replace('\n', "\n  ")).unwrap();
+                }
+            }
+            sections.push(list);
+        }
+        if let Some(returns) = &self.returns {
+            sections.push(format!("Returns: {}", returns));
+        }
+        for note in &self.notes {
+            sections.push(format!("> Note: {}", note.replace('\n', "\n> ")));
+        }
+        if let Some(deprecated) = &self.deprecated {
+            if deprecated.is_empty() {
+                sections.push("Deprecated.".to_string());
+            } else {
+                sections.push(format!("Deprecated: {}", deprecated));
+            }
+        }
+        sections.join("\n\n")
+    }
+}
+
+/// Parses a raw C++ documentation comment. Returns `None` if the comment is empty.
+pub fn parse_doc_comment(raw: &str) -> Option<DoxygenComment> {
+    let text = clean_cpp_comment(raw);
+    if text.is_empty() {
+        None
+    } else {
+        Some(DoxygenComment::parse(&text))
+    }
+}
+
+/// Returns documentation comment of `cpp_item` extracted by the C++ parser, if any.
+pub fn cpp_doc_comment(cpp_item: &CppItem) -> Option<DoxygenComment> {
+    let raw = match cpp_item {
+        CppItem::Function(function) => function.doc_comment.as_ref(),
+        CppItem::Type(type1) => type1.doc_comment.as_ref(),
+        _ => None,
+    }?;
+    parse_doc_comment(raw)
+}
+
+pub fn struct_doc(
+    type1: DbItem<&RustStruct>,
+    database: &DatabaseClient,
+    config: &Config,
+) -> Result<String> {
     let mut output = String::new();
 
     let doc_item = database.find_doc_for(&type1.id)?;
+    let doc_comment = if doc_item.is_none() {
+        database
+            .source_cpp_item(&type1.id)?
+            .and_then(|cpp_item| cpp_doc_comment(cpp_item.item))
+    } else {
+        None
+    };
     if let Some(doc_item) = &doc_item {
         if !doc_item.item.html.is_empty() {
             writeln!(output, "{}\n", first_phrase(&doc_item.item.html))?;
         }
+    } else if let Some(brief) = doc_comment.as_ref().and_then(DoxygenComment::brief) {
+        writeln!(output, "{}\n", brief)?;
     }
 
     match &type1.item.kind {
@@ -115,7 +442,7 @@ pub fn struct_doc(type1: DbItem<&RustStruct>, database: &DatabaseClient) -> Resu
                 .to_cpp_pseudo_code();
 
             match kind {
-                RustWrapperTypeKind::EnumWrapper => {
+                RustWrapperTypeKind::EnumWrapper { .. } => {
                     writeln!(
                         output,
                         "C++ enum: {}.\n",
@@ -129,6 +456,21 @@ pub fn struct_doc(type1: DbItem<&RustStruct>, database: &DatabaseClient) -> Resu
                         wrap_inline_cpp_code(&cpp_type_code)
                     )?;
                 }
+                RustWrapperTypeKind::OpaqueClassWrapper => {
+                    let reason = match cpp_item.item {
+                        CppItem::Type(type1) if type1.kind.is_class() => {
+                            "The class is opaque in the Rust API"
+                        }
+                        _ => "The class is only forward-declared in C++ headers",
+                    };
+                    writeln!(
+                        output,
+                        "C++ class: {}.\n\n{}, so it can only be used \
+                         through pointers and references.\n",
+                        wrap_inline_cpp_code(&cpp_type_code),
+                        reason
+                    )?;
+                }
                 RustWrapperTypeKind::MovableClassWrapper { .. } => {
                     // not supported now
                 }
@@ -168,7 +510,9 @@ pub fn struct_doc(type1: DbItem<&RustStruct>, database: &DatabaseClient) -> Resu
                     .item;
 
                 let cpp_signal_arguments = match ffi_item {
-                    CppFfiItem::Function(_) => bail!("invalid source ffi item type"),
+                    CppFfiItem::Function(_) | CppFfiItem::SubclassShim(_) => {
+                        bail!("invalid source ffi item type")
+                    }
                     CppFfiItem::QtSlotWrapper(w) => &w.signal_arguments,
                     CppFfiItem::QtSignalWrapper(w) => &w.signal_arguments,
                 };
@@ -220,15 +564,32 @@ pub fn struct_doc(type1: DbItem<&RustStruct>, database: &DatabaseClient) -> Resu
         }
         // private struct, no doc needed
         RustStructKind::SizedType(_) => {}
+        RustStructKind::FlagsType(flags_type) => {
+            writeln!(
+                output,
+                "An OR-combination of integer values of the enum type `E`.\n\n\
+                 C++ class template: {}.\n",
+                wrap_inline_cpp_code(&flags_type.cpp_path.to_cpp_pseudo_code())
+            )?;
+        }
     };
 
+    if let RustStructKind::WrapperType(_) = &type1.item.kind {
+        write!(output, "{}", cpp_doc_url_text(&type1.id, database, config)?)?;
+    }
     if let Some(doc_item) = doc_item {
         write!(output, "{}", format_doc_item(doc_item.item))?;
+    } else if let Some(doc_comment) = doc_comment {
+        write!(output, "{}", format_doc_comment(&doc_comment, &[]))?;
     }
     Ok(output)
 }
 
-pub fn enum_value_doc(value: DbItem<&RustEnumValue>, database: &DatabaseClient) -> Result<String> {
+pub fn enum_value_doc(
+    value: DbItem<&RustEnumValue>,
+    database: &DatabaseClient,
+    config: &Config,
+) -> Result<String> {
     let cpp_item = database
         .source_cpp_item(&value.id)?
         .ok_or_else(|| err_msg("source cpp item not found"))?
@@ -247,9 +608,147 @@ pub fn enum_value_doc(value: DbItem<&RustEnumValue>, database: &DatabaseClient)
     if let Some(doc_item) = database.find_doc_for(&value.id)? {
         doc = format!("{} ({})", doc_item.item.html, doc);
     }
+    if let Some(url) = cpp_doc_url(&value.id, database, config)? {
+        write!(doc, "\n\nSee the [C++ documentation]({}).", url)?;
+    }
     Ok(doc)
 }
 
+/// Percent-encodes `text` for use in a URL.
+fn percent_encode(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            output.push(char::from(byte));
+        } else {
+            write!(output, "%{:02X}", byte).unwrap();
+        }
+    }
+    output
+}
+
+/// Substitutes placeholders (e.g. `{class}`) in `template` with percent-encoded `values`.
+/// If a placeholder in the fragment of the URL (after `#`) can't be filled,
+/// the fragment is omitted. Returns `None` if other placeholders can't be filled.
+pub fn fill_doc_url_template(template: &str, values: &[(&str, &str)]) -> Option<String> {
+    let fill = |template: &str| -> Option<String> {
+        let mut output = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let end = start + rest[start..].find('}')?;
+            let name = &rest[start + 1..end];
+            let (_, value) = values.iter().find(|(key, _)| *key == name)?;
+            output.push_str(&rest[..start]);
+            output.push_str(&percent_encode(value));
+            rest = &rest[end + 1..];
+        }
+        output.push_str(rest);
+        Some(output)
+    };
+    fill(template).or_else(|| fill(&template[..template.rfind('#')?]))
+}
+
+/// Returns the C++ item `id` was generated from, along with its declaration place.
+/// Items added by ritual (e.g. implicit destructors) don't have a declaration place,
+/// but template instantiations use the declaration place of the template.
+fn declared_cpp_item<'a>(
+    id: &ItemId,
+    database: &'a DatabaseClient,
+) -> Result<Option<(DbItem<&'a CppItem>, &'a CppItemOrigin)>> {
+    let mut current = match database.source_cpp_item(id)? {
+        Some(item) => item,
+        None => return Ok(None),
+    };
+    loop {
+        if let Some(origin) = database.item_origin(&current.id) {
+            return Ok(Some((current, origin)));
+        }
+        let source = match &current.source_id {
+            Some(source_id) => database
+                .item(source_id)?
+                .filter_map(|item| item.as_cpp_item()),
+            None => None,
+        };
+        match source {
+            Some(source) if mem::discriminant(source.item) == mem::discriminant(current.item) => {
+                current = source;
+            }
+            _ => return Ok(None),
+        }
+    }
+}
+
+/// Returns the URL of the online C++ documentation of the item
+/// if it can be generated from the templates set in `config`.
+pub fn cpp_doc_url(
+    id: &ItemId,
+    database: &DatabaseClient,
+    config: &Config,
+) -> Result<Option<String>> {
+    let (cpp_item, origin) = match declared_cpp_item(id, database)? {
+        Some(item) => item,
+        None => return Ok(None),
+    };
+    let header = origin.include_file.as_str();
+    let url = match cpp_item.item {
+        CppItem::Type(type1) => {
+            let path = type1.path.to_cpp_pseudo_code();
+            if type1.kind.is_enum() {
+                config.enum_doc_url_template().and_then(|template| {
+                    fill_doc_url_template(template, &[("enum", &path), ("header", header)])
+                })
+            } else {
+                config.doc_url_template().and_then(|template| {
+                    fill_doc_url_template(template, &[("class", &path), ("header", header)])
+                })
+            }
+        }
+        CppItem::Function(function) => {
+            if function.member.is_some() {
+                let class_path = function.class_path()?.to_cpp_pseudo_code();
+                config.doc_url_template().and_then(|template| {
+                    fill_doc_url_template(
+                        template,
+                        &[
+                            ("class", &class_path),
+                            ("method", &function.path.last().name),
+                            ("header", header),
+                        ],
+                    )
+                })
+            } else {
+                let path = function.path.to_cpp_pseudo_code();
+                config.function_doc_url_template().and_then(|template| {
+                    fill_doc_url_template(template, &[("function", &path), ("header", header)])
+                })
+            }
+        }
+        CppItem::EnumValue(value) => {
+            let enum_path = value.path.parent()?.to_cpp_pseudo_code();
+            config.enum_doc_url_template().and_then(|template| {
+                fill_doc_url_template(
+                    template,
+                    &[
+                        ("enum", &enum_path),
+                        ("value", &value.path.last().name),
+                        ("header", header),
+                    ],
+                )
+            })
+        }
+        _ => None,
+    };
+    Ok(url)
+}
+
+/// Returns a link to the online C++ documentation of the item, if available.
+fn cpp_doc_url_text(id: &ItemId, database: &DatabaseClient, config: &Config) -> Result<String> {
+    Ok(match cpp_doc_url(id, database, config)? {
+        Some(url) => format!("See the [C++ documentation]({}).\n\n", url),
+        None => String::new(),
+    })
+}
+
 fn format_maybe_link(url: &Option<String>, text: &str) -> String {
     if let Some(url) = url {
         format!("<a href=\"{}\">{}</a>", url, text)
@@ -273,7 +772,199 @@ fn format_doc_item(cpp_doc: &DocItem) -> String {
     output
 }
 
-pub fn function_doc(function: DbItem<&RustFunction>, database: &DatabaseClient) -> Result<String> {
+fn format_doc_comment(doc_comment: &DoxygenComment, argument_names: &[(String, String)]) -> String {
+    format!(
+        "C++ documentation:\n\n{}",
+        doc_comment.to_markdown(argument_names)
+    )
+}
+
+/// Returns names of arguments of the source C++ function of `function`
+/// along with the names of the corresponding Rust arguments.
+pub fn argument_names(
+    function: &DbItem<&RustFunction>,
+    database: &DatabaseClient,
+) -> Result<Vec<(String, String)>> {
+    let cpp_function = database
+        .source_cpp_item(&function.id)?
+        .and_then(|item| item.item.as_function_ref());
+    let ffi_function = database
+        .source_ffi_item(&function.id)?
+        .and_then(|item| item.item.as_function_ref());
+    let (cpp_function, ffi_function) = match (cpp_function, ffi_function) {
+        (Some(cpp_function), Some(ffi_function)) => (cpp_function, ffi_function),
+        _ => return Ok(Vec::new()),
+    };
+    let mut names = Vec::new();
+    for argument in &function.item.arguments {
+        let meaning = ffi_function
+            .arguments
+            .get(argument.ffi_index)
+            .map(|ffi_argument| &ffi_argument.meaning);
+        if let Some(CppFfiArgumentMeaning::Argument(index)) = meaning {
+            if let Some(cpp_argument) = cpp_function.arguments.get(*index) {
+                names.push((cpp_argument.name.clone(), argument.name.clone()));
+            }
+        }
+    }
+    Ok(names)
+}
+
+/// Returns markdown code of `text` linking to the Rust item at `path`.
+/// Items that are not public or not available on all platforms
+/// are not linked to avoid broken links.
+fn rust_item_link(path: &RustPath, text: &str, database: &DatabaseClient) -> Result<String> {
+    let item = match database.find_rust_item_in_all_crates(path) {
+        Some(item) => item,
+        None => return Ok(format!("`{}`", text)),
+    };
+    let is_public = match item.item {
+        RustItem::Struct(data) => data.is_public,
+        RustItem::Function(data) => data.is_public,
+        _ => false,
+    };
+    if !is_public || !database.is_reachable(path) || !database.is_always_available(&item.id)? {
+        return Ok(format!("`{}`", text));
+    }
+    let target = if path.crate_name() == database.crate_name() {
+        once("crate")
+            .chain(path.parts[1..].iter().map(String::as_str))
+            .join("::")
+    } else {
+        path.parts.join("::")
+    };
+    Ok(format!("[`{}`]({})", text, target))
+}
+
+/// Generates links to the type containing `function`, other types used in
+/// its signature and other Rust functions generated from overloads
+/// of the same C++ function.
+pub fn related_items_doc(
+    function: &DbItem<&RustFunction>,
+    cpp_function: &CppFunction,
+    database: &DatabaseClient,
+) -> Result<String> {
+    let mut output = String::new();
+    let parent = function.item.path.parent()?;
+    let is_method = database
+        .find_rust_item(&parent)
+        .map_or(false, |item| item.item.as_struct_ref().is_some());
+    if is_method {
+        writeln!(
+            output,
+            "Member of {}.\n",
+            rust_item_link(&parent, parent.last(), database)?
+        )?;
+    }
+
+    let mut paths = Vec::new();
+    for argument in &function.item.arguments {
+        argument.argument_type.api_type().collect_paths(&mut paths);
+    }
+    function
+        .item
+        .return_type
+        .api_type()
+        .collect_paths(&mut paths);
+    let mut types = Vec::new();
+    for path in paths.into_iter().unique() {
+        let is_type = database
+            .find_rust_item_in_all_crates(&path)
+            .map_or(false, |item| item.item.as_struct_ref().is_some());
+        if is_type && path != parent {
+            types.push(rust_item_link(&path, path.last(), database)?);
+        }
+    }
+    if !types.is_empty() {
+        writeln!(output, "Related types: {}.\n", types.join(", "))?;
+    }
+
+    let mut overloads = Vec::new();
+    let mut visited = HashSet::new();
+    visited.insert(function.id.clone());
+    for cpp_item in database.lookup_cpp_path(&cpp_function.path) {
+        for item in cpp_item.rust_items() {
+            if !visited.insert(item.id.clone()) {
+                continue;
+            }
+            let other = match item.item.as_function_ref() {
+                Some(other) => other,
+                None => continue,
+            };
+            if !other.is_public
+                || !matches!(other.kind, RustFunctionKind::FfiWrapper(_))
+                || !other.path.is_child_of(&parent)
+            {
+                continue;
+            }
+            let other_cpp_function = database
+                .source_cpp_item(&item.id)?
+                .and_then(|item| item.item.as_function_ref());
+            let link = rust_item_link(&other.path, other.path.last(), database)?;
+            match other_cpp_function {
+                Some(other_cpp_function) => overloads.push(format!(
+                    "- {}: {}",
+                    link,
+                    wrap_inline_cpp_code(&other_cpp_function.short_text())
+                )),
+                None => overloads.push(format!("- {}", link)),
+            }
+        }
+    }
+    if !overloads.is_empty() {
+        writeln!(output, "Overloads:\n\n{}\n", overloads.join("\n"))?;
+    }
+
+    if let Some(source_id) = &function.source_id {
+        let trait_path =
+            database
+                .view(source_id)?
+                .derived()
+                .into_iter()
+                .find_map(|view| match view.item().item.as_rust_item() {
+                    Some(RustItem::ExtraImpl(data)) => match &data.kind {
+                        RustExtraImplKind::Overload(overload)
+                            if overload.function_path == function.item.path =>
+                        {
+                            Some(overload.trait_path.clone())
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                });
+        if let Some(trait_path) = trait_path {
+            let generic_function = database
+                .rust_children(&trait_path.parent()?)
+                .find_map(
+                    |item| match item.item.as_extra_impl_ref().map(|item| &item.kind) {
+                        Some(RustExtraImplKind::OverloadedFunction(data))
+                            if data.trait_path == trait_path =>
+                        {
+                            Some(data.path.clone())
+                        }
+                        _ => None,
+                    },
+                );
+            if let Some(path) = generic_function {
+                writeln!(
+                    output,
+                    "This overload can also be called through the generic function {}.\n",
+                    rust_item_link(&path, path.last(), database)?
+                )?;
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// Generates documentation of a Rust function. If source annotations are enabled
+/// in `config`, the documentation of wrappers mentions the declaration place
+/// of the C++ item.
+pub fn function_doc(
+    function: DbItem<&RustFunction>,
+    database: &DatabaseClient,
+    config: &Config,
+) -> Result<String> {
     let cpp_item = database
         .source_cpp_item(&function.id)?
         .ok_or_else(|| err_msg("source cpp item not found"))?;
@@ -352,14 +1043,21 @@ pub fn function_doc(function: DbItem<&RustFunction>, database: &DatabaseClient)
     }
 
     let doc_item = database.find_doc_for(&function.id)?;
+    let doc_comment = if doc_item.is_none() {
+        cpp_doc_comment(cpp_item.item)
+    } else {
+        None
+    };
     if let Some(doc_item) = &doc_item {
         if !doc_item.item.html.is_empty() {
             writeln!(output, "{}\n", first_phrase(&doc_item.item.html))?;
         }
+    } else if let Some(brief) = doc_comment.as_ref().and_then(DoxygenComment::brief) {
+        writeln!(output, "{}\n", brief)?;
     }
 
     match &function.item.kind {
-        RustFunctionKind::FfiWrapper(_) => {
+        RustFunctionKind::FfiWrapper(wrapper_data) => {
             let cpp_ffi_function = database
                 .source_ffi_item(&function.id)?
                 .ok_or_else(|| err_msg("source cpp item not found"))?
@@ -368,7 +1066,7 @@ pub fn function_doc(function: DbItem<&RustFunction>, database: &DatabaseClient)
                 .ok_or_else(|| err_msg("invalid source ffi item type"))?;
 
             match &cpp_ffi_function.kind {
-                CppFfiFunctionKind::Function => {
+                CppFfiFunctionKind::Function | CppFfiFunctionKind::ClosureCallback { .. } => {
                     let cpp_item = cpp_item
                         .item
                         .as_function_ref()
@@ -378,6 +1076,108 @@ pub fn function_doc(function: DbItem<&RustFunction>, database: &DatabaseClient)
                         "Calls C++ function: {}.\n\n",
                         wrap_inline_cpp_code(&cpp_item.short_text())
                     )?;
+                    if let Some(declaration) = &cpp_item.declaration_code {
+                        write!(
+                            output,
+                            "C++ declaration: `{}`\n\n",
+                            declaration.split_whitespace().join(" ")
+                        )?;
+                    }
+                    if let Some(base) = cpp_item.inherited_from() {
+                        write!(
+                            output,
+                            "Inherited from {}.\n\n",
+                            wrap_inline_cpp_code(&base.to_cpp_pseudo_code())
+                        )?;
+                    }
+                    if cpp_item.operator == Some(CppOperator::Subscript) {
+                        write!(
+                            output,
+                            "The index is not checked. Passing an out-of-range index \
+                             has the same effect as in C++.\n\n"
+                        )?;
+                    }
+                    if cpp_item.return_type.is_pointer() {
+                        let conversion = function.item.return_type.conversion();
+                        if conversion.is_option_utils_ref_to_ptr() {
+                            write!(
+                                output,
+                                "Returns `None` if the C++ function returns a null pointer.\n\n"
+                            )?;
+                        } else if conversion.is_utils_ref_to_ptr() {
+                            write!(
+                                output,
+                                "Panics if the C++ function returns a null pointer.\n\n"
+                            )?;
+                        }
+                    }
+                    let returns_reference = matches!(
+                        &cpp_item.return_type,
+                        CppType::PointerLike {
+                            kind: CppPointerLikeTypeKind::Reference,
+                            ..
+                        }
+                    );
+                    let returns_raw_pointer = matches!(
+                        function.item.return_type.api_type(),
+                        RustType::PointerLike {
+                            kind: RustPointerLikeTypeKind::Pointer,
+                            ..
+                        }
+                    );
+                    if returns_reference && returns_raw_pointer {
+                        write!(
+                            output,
+                            "The C++ function returns a reference with unknown lifetime, \
+                             so it's returned as a raw pointer. The caller must make sure \
+                             that the referenced object is still alive when \
+                             the pointer is dereferenced.\n\n"
+                        )?;
+                    }
+                    if !wrapper_data.out_arguments.is_empty() {
+                        let names = wrapper_data
+                            .out_arguments
+                            .iter()
+                            .map(|arg| format!("`{}`", arg.name))
+                            .join(", ");
+                        if wrapper_data.returns_success_flag {
+                            write!(
+                                output,
+                                "Returns values of out-parameters {} if the C++ function \
+                                 returns `true`, and `None` otherwise.\n\n",
+                                names
+                            )?;
+                        } else {
+                            write!(
+                                output,
+                                "Values of out-parameters {} are returned \
+                                 instead of being passed as arguments.\n\n",
+                                names
+                            )?;
+                        }
+                    }
+                    if let CppFfiFunctionKind::ClosureCallback { callback } = &cpp_ffi_function.kind
+                    {
+                        if callback.unregister.is_some() {
+                            write!(
+                                output,
+                                "The closure is passed to C++ as a callback. Dropping the \
+                                 returned guard unregisters the callback and frees the closure.\n\n"
+                            )?;
+                        } else {
+                            write!(
+                                output,
+                                "The closure is passed to C++ as a callback and is never freed.\n\n"
+                            )?;
+                        }
+                    }
+                    if !is_trait_impl {
+                        write!(
+                            output,
+                            "{}",
+                            related_items_doc(&function, cpp_item, database)?
+                        )?;
+                    }
 
                     // TODO: detect omitted arguments using source_id
                     /*if let Some(arguments_before_omitting) =
@@ -416,6 +1216,25 @@ pub fn function_doc(function: DbItem<&RustFunction>, database: &DatabaseClient)
                         }
                     };
                 }
+                CppFfiFunctionKind::StreamOutput => {
+                    bail!("stream output functions don't have wrappers");
+                }
+                CppFfiFunctionKind::StringFromUtf8 { .. }
+                | CppFfiFunctionKind::StringToUtf8 { .. } => {
+                    bail!("string conversion functions don't have wrappers");
+                }
+            }
+            if config.write_source_annotations() {
+                if let Some(origin) = database.root_item_origin(&function.id)? {
+                    if !output.ends_with('\n') {
+                        write!(output, "\n\n")?;
+                    }
+                    write!(
+                        output,
+                        "C++ source: `{}`, line {}.\n\n",
+                        origin.include_file, origin.location.line
+                    )?;
+                }
             }
         }
         RustFunctionKind::SignalOrSlotGetter(_) => {
@@ -441,10 +1260,33 @@ pub fn function_doc(function: DbItem<&RustFunction>, database: &DatabaseClient)
             )?;
         }
         // FFI functions are private
-        RustFunctionKind::FfiFunction => {}
+        RustFunctionKind::FfiFunction => {
+            let ffi_function = database
+                .source_ffi_item(&function.id)?
+                .and_then(|item| item.item.as_function_ref());
+            if let Some(ffi_function) = ffi_function {
+                if !ffi_function.is_direct_binding {
+                    writeln!(output, "FFI name: `{}`\n", ffi_function.descriptive_name)?;
+                }
+            }
+        }
+    }
+    if function.item.kind != RustFunctionKind::FfiFunction {
+        write!(
+            output,
+            "{}",
+            cpp_doc_url_text(&function.id, database, config)?
+        )?;
     }
-    if let Some(doc_item) = database.find_doc_for(&function.id)? {
+    if let Some(doc_item) = doc_item {
         write!(output, "{}", format_doc_item(doc_item.item))?;
+    } else if let Some(doc_comment) = doc_comment {
+        let argument_names = argument_names(&function, database)?;
+        write!(
+            output,
+            "{}",
+            format_doc_comment(&doc_comment, &argument_names)
+        )?;
     }
     Ok(output)
 }
@@ -485,3 +1327,52 @@ pub fn function_doc(function: DbItem<&RustFunction>, database: &DatabaseClient)
         }
     }
 */
+
+/// Generates documentation of a generic function accepting arguments
+/// of all overloads of a C++ function.
+pub fn overloaded_function_doc(
+    function: &RustOverloadedFunction,
+    database: &DatabaseClient,
+) -> Result<String> {
+    let mut output = String::new();
+    writeln!(
+        output,
+        "Calls the overload of the C++ function that accepts `args`.\n"
+    )?;
+    writeln!(output, "Accepted argument types:\n")?;
+    let module_path = function.trait_path.parent()?;
+    for item in database.rust_children(&module_path) {
+        let overload = match item.item.as_extra_impl_ref().map(|item| &item.kind) {
+            Some(RustExtraImplKind::Overload(overload))
+                if overload.trait_path == function.trait_path =>
+            {
+                overload
+            }
+            _ => continue,
+        };
+        let cpp_function = database
+            .source_cpp_item(&item.id)?
+            .ok_or_else(|| err_msg("source cpp item not found"))?
+            .item
+            .as_function_ref()
+            .ok_or_else(|| err_msg("invalid source cpp item type"))?;
+        writeln!(
+            output,
+            "- `{}`: calls {} ({}).",
+            rust_type_to_code(&overload.arguments_type(), Some(database.crate_name())),
+            rust_item_link(
+                &overload.function_path,
+                overload.function_path.last(),
+                database
+            )?,
+            wrap_inline_cpp_code(&cpp_function.short_text())
+        )?;
+    }
+    writeln!(
+        output,
+        "\nEach overload is also available as a separate function. \
+         If argument types of two overloads can be the same type on some platform \
+         (e.g. `c_int` and `c_long`), only the first of them is accepted here."
+    )?;
+    Ok(output)
+}
@@ -11,6 +11,7 @@ pub use ritual_common as common;
 pub mod cli;
 pub mod cluster_api;
 pub mod config;
+pub mod coverage;
 mod cpp_casts;
 pub mod cpp_checker;
 mod cpp_checks;
@@ -20,7 +21,8 @@ pub mod cpp_ffi_data;
 pub mod cpp_ffi_generator;
 pub mod cpp_function;
 mod cpp_implicit_methods;
-mod cpp_inheritance; // TODO: deal with inheritance for subclassing support
+mod cpp_inheritance;
+pub mod cpp_macros;
 mod cpp_omitting_arguments;
 mod cpp_operator;
 pub mod cpp_parser;
@@ -28,14 +30,20 @@ pub mod cpp_template_instantiator;
 pub mod cpp_type;
 mod crate_writer;
 pub mod database;
+pub mod database_diff;
 mod doc_formatter;
 mod download_db;
+pub mod logging;
 pub mod processor;
+pub mod progress;
 mod rust_code_generator;
 mod rust_generator;
 pub mod rust_info;
 pub mod rust_type;
+mod smoke_tests;
 mod type_allocation_places;
+pub mod user_hooks;
+pub mod versions;
 pub mod workspace;
 
 #[cfg(test)]
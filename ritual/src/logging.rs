@@ -0,0 +1,356 @@
+//! Logger with per-category verbosity control.
+//!
+//! Messages are assigned to categories based on their log target
+//! (the module path by default), so the existing `log` macros don't need
+//! any changes. Levels of categories can be limited by `init`,
+//! `Config::set_log_level` or the `--log-level` command line option.
+
+use log::{info, Level, LevelFilter, Log, Metadata, Record};
+use once_cell::sync::OnceCell;
+use ritual_common::errors::{bail, format_err, Error, Result, ResultExt};
+use ritual_common::file_utils::create_dir_all;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Mutex, RwLock};
+
+/// Group of log messages with a common verbosity level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogCategory {
+    /// C++ parser and macro extractors
+    Parser,
+    /// Template instantiation
+    Templates,
+    /// Inherited methods and casts
+    Inheritance,
+    /// C++ checker
+    Checker,
+    /// Rust names and Rust API generation
+    RustNames,
+    /// Consistency of the database
+    Integrity,
+    /// All other messages
+    General,
+}
+
+impl LogCategory {
+    pub const ALL: &'static [LogCategory] = &[
+        LogCategory::Parser,
+        LogCategory::Templates,
+        LogCategory::Inheritance,
+        LogCategory::Checker,
+        LogCategory::RustNames,
+        LogCategory::Integrity,
+        LogCategory::General,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LogCategory::Parser => "parser",
+            LogCategory::Templates => "templates",
+            LogCategory::Inheritance => "inheritance",
+            LogCategory::Checker => "checker",
+            LogCategory::RustNames => "rust_names",
+            LogCategory::Integrity => "integrity",
+            LogCategory::General => "general",
+        }
+    }
+
+    /// Returns the category of messages logged with `target`.
+    pub fn of_target(target: &str) -> Self {
+        let module = match target.strip_prefix("ritual::") {
+            Some(path) => path.split("::").next().unwrap_or(path),
+            None => return LogCategory::General,
+        };
+        match module {
+            "cpp_parser" | "cpp_macros" => LogCategory::Parser,
+            "cpp_template_instantiator" => LogCategory::Templates,
+            "cpp_inheritance" | "cpp_casts" => LogCategory::Inheritance,
+            "cpp_checker" | "cpp_checks" => LogCategory::Checker,
+            "rust_generator" | "rust_info" | "rust_type" => LogCategory::RustNames,
+            "database" | "database_diff" => LogCategory::Integrity,
+            _ => LogCategory::General,
+        }
+    }
+}
+
+impl fmt::Display for LogCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for LogCategory {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        LogCategory::ALL
+            .iter()
+            .cloned()
+            .find(|category| category.name() == s)
+            .ok_or_else(|| {
+                let names = LogCategory::ALL
+                    .iter()
+                    .map(|c| c.name())
+                    .collect::<Vec<_>>();
+                format_err!(
+                    "unknown log category: {:?} (expected one of: {})",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Parses a level limit of a category, e.g. `templates=error`.
+pub fn parse_category_level(text: &str) -> Result<(LogCategory, LevelFilter)> {
+    let mut parts = text.splitn(2, '=');
+    let category = parts.next().unwrap_or_default().trim().parse()?;
+    let level = match parts.next() {
+        Some(level) => LevelFilter::from_str(level.trim())
+            .map_err(|_| format_err!("invalid log level: {:?}", level))?,
+        None => bail!("expected category=level, got {:?}", text),
+    };
+    Ok((category, level))
+}
+
+/// Output format of log messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `LEVEL [category] message`
+    Text,
+    /// A JSON object per line with `level`, `category`, `target`
+    /// and `message` fields
+    JsonLines,
+}
+
+impl FromStr for LogFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::JsonLines),
+            _ => bail!("unknown log format: {:?} (expected text or json)", s),
+        }
+    }
+}
+
+/// Settings of the logger created by `init`.
+#[derive(Debug, Clone)]
+pub struct LogSettings {
+    /// Messages up to this level are printed to stderr
+    pub stderr_level: LevelFilter,
+    /// File that receives messages of all levels
+    pub file: Option<PathBuf>,
+    pub format: LogFormat,
+    /// Maximal levels of categories. Other categories are not limited.
+    pub category_levels: Vec<(LogCategory, LevelFilter)>,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        LogSettings {
+            stderr_level: LevelFilter::Info,
+            file: None,
+            format: LogFormat::Text,
+            category_levels: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    level: &'a str,
+    category: LogCategory,
+    target: &'a str,
+    message: String,
+}
+
+/// Logger that filters messages by category and counts warnings.
+pub struct Logger {
+    stderr_level: LevelFilter,
+    format: LogFormat,
+    file: Option<Mutex<BufWriter<fs::File>>>,
+    category_levels: RwLock<HashMap<LogCategory, LevelFilter>>,
+    warning_counts: Mutex<BTreeMap<LogCategory, usize>>,
+}
+
+impl fmt::Debug for Logger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Logger")
+            .field("stderr_level", &self.stderr_level)
+            .field("format", &self.format)
+            .finish()
+    }
+}
+
+impl Logger {
+    pub fn new(settings: LogSettings) -> Result<Self> {
+        let file = match &settings.file {
+            Some(path) => {
+                if let Some(parent) = path.parent() {
+                    create_dir_all(parent)?;
+                }
+                let file = fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|_| format!("failed to open log file: {:?}", path))?;
+                Some(Mutex::new(BufWriter::new(file)))
+            }
+            None => None,
+        };
+        Ok(Logger {
+            stderr_level: settings.stderr_level,
+            format: settings.format,
+            file,
+            category_levels: RwLock::new(settings.category_levels.into_iter().collect()),
+            warning_counts: Mutex::default(),
+        })
+    }
+
+    /// Returns the maximal level of messages of `category`.
+    pub fn category_level(&self, category: LogCategory) -> LevelFilter {
+        self.category_levels
+            .read()
+            .unwrap()
+            .get(&category)
+            .cloned()
+            .unwrap_or(LevelFilter::Trace)
+    }
+
+    /// Sets maximal levels of categories. Returns the previous levels.
+    pub fn set_category_levels(
+        &self,
+        levels: HashMap<LogCategory, LevelFilter>,
+    ) -> HashMap<LogCategory, LevelFilter> {
+        std::mem::replace(&mut *self.category_levels.write().unwrap(), levels)
+    }
+
+    /// Returns the number of warnings logged in each category,
+    /// including warnings hidden by the category level.
+    pub fn warning_counts(&self) -> BTreeMap<LogCategory, usize> {
+        self.warning_counts.lock().unwrap().clone()
+    }
+
+    fn format_record(&self, record: &Record<'_>, category: LogCategory) -> String {
+        match self.format {
+            LogFormat::Text => format!("{} [{}] {}", record.level(), category, record.args()),
+            LogFormat::JsonLines => {
+                let json_record = JsonRecord {
+                    level: record.level().as_str(),
+                    category,
+                    target: record.target(),
+                    message: record.args().to_string(),
+                };
+                serde_json::to_string(&json_record)
+                    .unwrap_or_else(|err| format!("failed to serialize log record: {}", err))
+            }
+        }
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        // warnings are always processed to be counted
+        metadata.level() <= Level::Warn
+            || metadata.level() <= self.category_level(LogCategory::of_target(metadata.target()))
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        let category = LogCategory::of_target(record.target());
+        if record.level() == Level::Warn {
+            *self
+                .warning_counts
+                .lock()
+                .unwrap()
+                .entry(category)
+                .or_default() += 1;
+        }
+        if record.level() > self.category_level(category) {
+            return;
+        }
+        let line = self.format_record(record, category);
+        if let Some(file) = &self.file {
+            let mut file = file.lock().unwrap();
+            // errors of the log output shouldn't interrupt processing
+            let _ = writeln!(file, "{}", line).and_then(|_| file.flush());
+        }
+        if record.level() <= self.stderr_level {
+            eprintln!("{}", line);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &self.file {
+            let _ = file.lock().unwrap().flush();
+        }
+    }
+}
+
+static LOGGER: OnceCell<Logger> = OnceCell::new();
+
+/// Installs the global logger.
+pub fn init(settings: LogSettings) -> Result<()> {
+    if LOGGER.set(Logger::new(settings)?).is_err() {
+        bail!("logger is already initialized");
+    }
+    let logger = LOGGER.get().expect("logger was just initialized");
+    log::set_logger(logger).map_err(|err| format_err!("failed to set logger: {}", err))?;
+    log::set_max_level(LevelFilter::Trace);
+    Ok(())
+}
+
+/// Returns the logger installed by `init`.
+pub fn global() -> Option<&'static Logger> {
+    LOGGER.get()
+}
+
+/// Restores previous category levels when dropped.
+#[derive(Debug)]
+pub struct CategoryLevelsGuard {
+    previous: Option<HashMap<LogCategory, LevelFilter>>,
+}
+
+impl Drop for CategoryLevelsGuard {
+    fn drop(&mut self) {
+        if let (Some(logger), Some(previous)) = (global(), self.previous.take()) {
+            logger.set_category_levels(previous);
+        }
+    }
+}
+
+/// Sets levels of `levels` categories of the global logger
+/// until the returned guard is dropped. Levels of other categories are kept.
+pub fn override_category_levels(levels: &[(LogCategory, LevelFilter)]) -> CategoryLevelsGuard {
+    let logger = match global() {
+        Some(logger) if !levels.is_empty() => logger,
+        _ => return CategoryLevelsGuard { previous: None },
+    };
+    let mut new_levels = logger.category_levels.read().unwrap().clone();
+    new_levels.extend(levels.iter().cloned());
+    CategoryLevelsGuard {
+        previous: Some(logger.set_category_levels(new_levels)),
+    }
+}
+
+/// Prints the number of warnings in each category.
+pub fn report_warning_summary() {
+    let counts = match global() {
+        Some(logger) => logger.warning_counts(),
+        None => return,
+    };
+    if counts.is_empty() {
+        return;
+    }
+    info!("Warnings:");
+    for (category, count) in counts {
+        info!("    {}: {}", category, count);
+    }
+}
@@ -1,26 +1,37 @@
-use crate::config::Config;
+use crate::config::{
+    class_members_exclusion_reason, method_exclusion_reason, Config, CppLibVersion,
+    CrateDependencyKind,
+};
+use crate::coverage::CoverageReport;
 use crate::cpp_checker::{check_cpp_parser_hook, delete_blacklisted_items};
-use crate::cpp_data::CppItem;
-use crate::database::{DatabaseClient, ItemId};
-use crate::workspace::Workspace;
+use crate::cpp_checks::CppCheckFailure;
+use crate::cpp_data::{CppData, CppItem};
+use crate::database::{DatabaseClient, ItemId, Rejection, TypeAllocationSource};
+use crate::logging;
+use crate::progress::{ProgressEvent, ProgressReporter};
+use crate::versions::{is_breaking_change, library_target_sort_key};
+use crate::workspace::{CompletedStep, PlannedStep, RunProgress, StepStates, Workspace};
 use crate::{
-    cpp_casts, cpp_checker, cpp_ffi_generator, cpp_implicit_methods, cpp_omitting_arguments,
-    cpp_parser, cpp_template_instantiator, crate_writer, rust_generator,
+    cpp_casts, cpp_checker, cpp_ffi_generator, cpp_implicit_methods, cpp_inheritance,
+    cpp_omitting_arguments, cpp_parser, cpp_template_instantiator, crate_writer, rust_generator,
+    type_allocation_places,
 };
 use itertools::Itertools;
 use log::debug;
-use log::{error, info, trace};
+use log::{error, info};
 use regex::Regex;
+use ritual_common::cpp_build_config::CppBuildPaths;
 use ritual_common::env_var_names::WORKSPACE_TARGET_DIR;
 use ritual_common::errors::{bail, err_msg, format_err, Result, ResultExt};
-use ritual_common::target::LibraryTarget;
 use ritual_common::utils::{run_command, MapIfOk};
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::ops::Bound;
 use std::path::PathBuf;
 use std::process::Command;
-use std::time::{Duration, Instant};
+use std::rc::Rc;
+use std::sync::atomic;
+use std::time::Instant;
 use std::{env, fmt};
 
 /// Creates output and cache directories if they don't exist.
@@ -45,14 +56,19 @@ fn check_all_paths(config: &Config) -> Result<()> {
     if let Some(path) = config.crate_template_path() {
         check_path(path, true)?;
     }
-    for path in config.cpp_build_paths().include_paths() {
-        check_path(path, true)?;
-    }
-    for path in config.cpp_build_paths().lib_paths() {
-        check_path(path, true)?;
-    }
-    for path in config.cpp_build_paths().framework_paths() {
-        check_path(path, true)?;
+    let all_build_paths = Some(config.cpp_build_paths())
+        .into_iter()
+        .chain(config.cpp_lib_versions().iter().map(|v| v.build_paths()));
+    for build_paths in all_build_paths {
+        for path in build_paths.include_paths() {
+            check_path(path, true)?;
+        }
+        for path in build_paths.lib_paths() {
+            check_path(path, true)?;
+        }
+        for path in build_paths.framework_paths() {
+            check_path(path, true)?;
+        }
     }
     for path in config.target_include_paths() {
         check_path(path, false)?;
@@ -64,17 +80,47 @@ pub struct ProcessorData<'a> {
     pub workspace: &'a mut Workspace,
     pub config: &'a Config,
     pub db: &'a mut DatabaseClient,
+    /// Reports progress of the current step
+    pub progress: ProgressReporter,
+    /// Version of the C++ library the current step is run for
+    /// (see `ProcessingSteps::set_per_cpp_lib_version`)
+    current_cpp_lib_version: Option<&'a CppLibVersion>,
+}
+
+impl<'a> ProcessorData<'a> {
+    /// Returns the version of the C++ library the current step is run for.
+    pub fn cpp_lib_version(&self) -> Option<&'a str> {
+        match self.current_cpp_lib_version {
+            Some(version) => Some(version.version()),
+            None => self.config.cpp_lib_version(),
+        }
+    }
+
+    /// Returns paths to the C++ library the current step is run for.
+    pub fn cpp_build_paths(&self) -> &'a CppBuildPaths {
+        match self.current_cpp_lib_version {
+            Some(version) => version.build_paths(),
+            None => self.config.cpp_build_paths(),
+        }
+    }
 }
 
 struct ProcessingStep {
     name: String,
     function: Box<dyn Fn(&mut ProcessorData<'_>) -> Result<()>>,
+    /// Steps that must be completed before this step can run
+    prerequisites: Vec<String>,
+    /// Computes the hash of the step's inputs that are not stored in the database
+    input_hash: Option<Rc<dyn Fn(&ProcessorData<'_>) -> u64>>,
+    /// The step is run for each of `Config::cpp_lib_versions`
+    is_per_cpp_lib_version: bool,
 }
 
 impl fmt::Debug for ProcessingStep {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("ProcessingStep")
             .field("name", &self.name)
+            .field("prerequisites", &self.prerequisites)
             .finish()
     }
 }
@@ -92,40 +138,70 @@ impl Default for ProcessingSteps {
             main_procedure: Vec::new(),
         };
 
-        let push_cpp_post_processing = |s: &mut Self, suffix: &str| {
-            s.push(
-                &format!("add_implicit_methods{}", suffix),
+        let push_cpp_post_processing = |s: &mut Self, parser: &str, suffix: &str| {
+            let name = |step: &str| format!("{}{}", step, suffix);
+            s.push_requiring(
+                &name("add_implicit_methods"),
+                &[parser],
                 cpp_implicit_methods::run,
             );
-            s.push(
-                &format!("find_template_instantiations{}", suffix),
+            s.push_requiring(
+                &name("add_inherited_methods"),
+                &[parser],
+                cpp_inheritance::add_inherited_methods,
+            );
+            s.push_requiring(
+                &name("find_template_instantiations"),
+                &[parser],
                 cpp_template_instantiator::find_template_instantiations,
             );
-            s.push(
-                &format!("instantiate_templates{}", suffix),
+            s.push_requiring(
+                &name("instantiate_templates"),
+                &[&name("find_template_instantiations")],
                 cpp_template_instantiator::instantiate_templates,
             );
-            s.push(
-                &format!("omitting_arguments{}", suffix),
+            s.push_requiring(
+                &name("omitting_arguments"),
+                &[parser],
                 cpp_omitting_arguments::run,
             );
-            s.push(&format!("cpp_casts{}", suffix), cpp_casts::run);
-            s.push(
-                &format!("cpp_ffi_generator{}", suffix),
+            s.push_requiring(&name("cpp_casts"), &[parser], cpp_casts::run);
+            s.push_requiring(
+                &name("type_allocation_places"),
+                &[parser],
+                type_allocation_places::run,
+            );
+            s.push_requiring(
+                &name("cpp_ffi_generator"),
+                &[parser],
                 cpp_ffi_generator::run,
             );
-            s.push(&format!("cpp_checker{}", suffix), |data| {
-                cpp_checker::run(data, false)
-            });
+            s.push_requiring(
+                &name("cpp_checker"),
+                &[&name("cpp_ffi_generator")],
+                |data| cpp_checker::run(data, false),
+            );
         };
 
         s.push("cpp_parser", cpp_parser::run);
-        push_cpp_post_processing(&mut s, "");
-        s.push("cpp_parser_stage2", cpp_parser::parse_generated_items);
-        push_cpp_post_processing(&mut s, "_stage2");
-        s.push("rust_generator", rust_generator::run);
-        s.push("crate_writer", crate_writer::run);
-        s.push("build_crate", build_crate);
+        let parser_input_hash: Rc<dyn Fn(&ProcessorData<'_>) -> u64> =
+            Rc::new(cpp_parser::input_hash);
+        s.all_steps.last_mut().unwrap().input_hash = Some(parser_input_hash);
+        s.push_requiring(
+            "add_subclass_shims",
+            &["cpp_parser"],
+            cpp_inheritance::add_subclass_shims,
+        );
+        push_cpp_post_processing(&mut s, "cpp_parser", "");
+        s.push_requiring(
+            "cpp_parser_stage2",
+            &["cpp_checker"],
+            cpp_parser::parse_generated_items,
+        );
+        push_cpp_post_processing(&mut s, "cpp_parser_stage2", "_stage2");
+        s.push_requiring("rust_generator", &["cpp_checker"], rust_generator::run);
+        s.push_requiring("crate_writer", &["rust_generator"], crate_writer::run);
+        s.push_requiring("build_crate", &["crate_writer"], build_crate);
 
         s.add_custom("clear_ffi", |data| {
             data.db.delete_items(|i| i.item.is_ffi_item());
@@ -144,6 +220,16 @@ impl Default for ProcessingSteps {
         s.add_custom("delete_orphans", delete_orphans);
         s.add_custom("delete_blacklisted_items", delete_blacklisted_items);
         s.add_custom("force_cpp_checker", |data| cpp_checker::run(data, true));
+        for name in &[
+            "cpp_parser",
+            "cpp_checker",
+            "cpp_parser_stage2",
+            "cpp_checker_stage2",
+            "force_cpp_checker",
+        ] {
+            s.set_per_cpp_lib_version(name)
+                .expect("step name must be valid");
+        }
         s
     }
 }
@@ -167,7 +253,9 @@ impl ProcessingSteps {
             .max()
             .ok_or_else(|| err_msg("no steps provided"))?;
         self.main_procedure.insert(max_index + 1, name.to_string());
-        self.all_steps.push(ProcessingStep::new(name, func));
+        let mut step = ProcessingStep::new(name, func);
+        step.prerequisites = after.iter().map(|s| s.to_string()).collect();
+        self.all_steps.push(step);
         Ok(())
     }
 
@@ -180,6 +268,17 @@ impl ProcessingSteps {
         self.all_steps.push(ProcessingStep::new(name, func));
     }
 
+    fn push_requiring(
+        &mut self,
+        name: &str,
+        prerequisites: &[&str],
+        func: impl Fn(&mut ProcessorData<'_>) -> Result<()> + 'static,
+    ) {
+        self.push(name, func);
+        self.all_steps.last_mut().unwrap().prerequisites =
+            prerequisites.iter().map(|s| s.to_string()).collect();
+    }
+
     pub fn add_custom(
         &mut self,
         name: &str,
@@ -187,6 +286,84 @@ impl ProcessingSteps {
     ) {
         self.all_steps.push(ProcessingStep::new(name, func));
     }
+
+    /// Sets steps that must be completed before step `name` can run.
+    pub fn set_prerequisites(&mut self, name: &str, prerequisites: &[&str]) -> Result<()> {
+        for prerequisite in prerequisites {
+            if !self.all_steps.iter().any(|step| &step.name == prerequisite) {
+                bail!("requested step not found: {}", prerequisite);
+            }
+        }
+        let mut found = false;
+        for step in self.all_steps.iter_mut().filter(|step| step.name == name) {
+            step.prerequisites = prerequisites.iter().map(|s| s.to_string()).collect();
+            found = true;
+        }
+        if !found {
+            bail!("requested step not found: {}", name);
+        }
+        Ok(())
+    }
+
+    /// Sets the function that computes the hash of inputs of step `name`
+    /// that are not stored in the database (e.g. files read by the step).
+    /// When running only changed steps, the step is run again if the hash differs
+    /// from the value recorded when it was completed.
+    pub fn set_input_hash(
+        &mut self,
+        name: &str,
+        func: impl Fn(&ProcessorData<'_>) -> u64 + 'static,
+    ) -> Result<()> {
+        let func = Rc::new(func) as Rc<dyn Fn(&ProcessorData<'_>) -> u64>;
+        let mut found = false;
+        for step in self.all_steps.iter_mut().filter(|step| step.name == name) {
+            step.input_hash = Some(func.clone());
+            found = true;
+        }
+        if !found {
+            bail!("requested step not found: {}", name);
+        }
+        Ok(())
+    }
+
+    /// Makes step `name` run once for each version of the C++ library
+    /// added with `Config::add_cpp_lib_version`. The step should use
+    /// `ProcessorData::cpp_lib_version` and `ProcessorData::cpp_build_paths`
+    /// instead of the corresponding `Config` values.
+    pub fn set_per_cpp_lib_version(&mut self, name: &str) -> Result<()> {
+        let mut found = false;
+        for step in self.all_steps.iter_mut().filter(|step| step.name == name) {
+            step.is_per_cpp_lib_version = true;
+            found = true;
+        }
+        if !found {
+            bail!("requested step not found: {}", name);
+        }
+        Ok(())
+    }
+
+    fn step(&self, name: &str) -> &ProcessingStep {
+        self.all_steps
+            .iter()
+            .find(|item| item.name == name)
+            .expect("step name must be valid (checked above)")
+    }
+
+    /// Returns the key identifying the step at `index` of the main procedure
+    /// in the completion markers. Steps added to the main procedure
+    /// several times get a numbered suffix.
+    fn step_key(&self, index: usize) -> String {
+        let name = &self.main_procedure[index];
+        let occurrence = self.main_procedure[..index]
+            .iter()
+            .filter(|s| *s == name)
+            .count();
+        if occurrence == 0 {
+            name.clone()
+        } else {
+            format!("{}#{}", name, occurrence + 1)
+        }
+    }
 }
 
 impl ProcessingStep {
@@ -197,6 +374,9 @@ impl ProcessingStep {
         ProcessingStep {
             name: name.into(),
             function: Box::new(function),
+            prerequisites: Vec::new(),
+            input_hash: None,
+            is_per_cpp_lib_version: false,
         }
     }
 }
@@ -232,35 +412,6 @@ fn build_crate(data: &mut ProcessorData<'_>) -> Result<()> {
     Ok(())
 }
 
-fn library_target_sort_key(item: &LibraryTarget) -> impl Ord {
-    #[derive(PartialEq, Eq, PartialOrd, Ord)]
-    enum Version {
-        Semver(semver::Version),
-        String(String),
-    }
-
-    item.cpp_library_version.as_ref().map(|version| {
-        if let Ok(x) = semver::Version::parse(version) {
-            Version::Semver(x)
-        } else {
-            Version::String(version.clone())
-        }
-    })
-}
-
-fn is_breaking_change(current: &[LibraryTarget], all: &[LibraryTarget]) -> bool {
-    for x in current {
-        let index = all
-            .iter()
-            .position(|i| x.cpp_library_version == i.cpp_library_version)
-            .unwrap();
-        if all[index + 1..].iter().any(|i| !current.contains(i)) {
-            return true;
-        }
-    }
-    false
-}
-
 fn show_non_portable(data: &mut ProcessorData<'_>) -> Result<()> {
     let mut all_envs = data.db.environments().to_vec();
     all_envs.sort_by_cached_key(library_target_sort_key);
@@ -354,11 +505,24 @@ pub fn process(
     mut step_names: &[String],
     trace_item_id: Option<&ItemId>,
 ) -> Result<()> {
+    let _log_levels = logging::override_category_levels(config.log_levels());
     info!("Processing crate: {}", config.crate_properties().name());
     check_all_paths(&config)?;
 
     if let Some(version) = config.cpp_lib_version() {
+        if !config.cpp_lib_versions().is_empty() {
+            bail!("set_cpp_lib_version can't be used together with add_cpp_lib_version");
+        }
         info!("Current C++ library version: {}", version);
+    } else if !config.cpp_lib_versions().is_empty() {
+        info!(
+            "C++ library versions: {}",
+            config
+                .cpp_lib_versions()
+                .iter()
+                .map(CppLibVersion::version)
+                .join(", ")
+        );
     }
 
     let allow_load;
@@ -380,30 +544,323 @@ pub fn process(
 
     db_client.set_crate_version(config.crate_properties().version().to_string());
 
+    if db_client.cpp_items().next().is_some() && db_client.target_triple() != config.target_triple()
+    {
+        bail!(
+            "database contains C++ data parsed for target {:?}, but current target is {:?}. \
+             Use \"discard\" step to reset the database.",
+            db_client.target_triple().unwrap_or("host"),
+            config.target_triple().unwrap_or("host"),
+        );
+    }
+    db_client.set_target_triple(config.target_triple().map(ToString::to_string));
+
     if let Some(trace_item_id) = trace_item_id {
         db_client.print_item_trace(trace_item_id)?;
         return Ok(());
     }
 
+    let crate_name = config.crate_properties().name();
+    let step_states = if allow_load {
+        workspace.load_step_states(crate_name)?
+    } else {
+        None
+    };
+    let check_prerequisites = step_states.is_some() || db_client.cpp_items().next().is_none();
+    if !check_prerequisites {
+        config.progress_handler().warning(
+            "Completion state of processing steps is unknown. Prerequisites are not checked.",
+        );
+    }
+    let mut step_states = step_states.unwrap_or_default();
+
+    let steps = config.processing_steps();
+    let run_progress = if allow_load {
+        workspace.load_run_progress(crate_name)?
+    } else {
+        workspace.remove_run_progress(crate_name)?;
+        None
+    };
+    let planned_steps = if step_names == ["resume"] {
+        let run_progress =
+            run_progress.ok_or_else(|| err_msg("there is no interrupted run to resume"))?;
+        let current_step = run_progress
+            .remaining_steps
+            .get(0)
+            .ok_or_else(|| err_msg("invalid run progress: no remaining steps"))?;
+        info!(
+            "Resuming interrupted run from step {} ({} items were processed)",
+            current_step.name, run_progress.processed_items
+        );
+        for step in &run_progress.remaining_steps {
+            let is_valid = match step.main_index {
+                Some(index) => steps.main_procedure.get(index) == Some(&step.name),
+                None => steps.all_steps.iter().any(|s| s.name == step.name),
+            };
+            if !is_valid {
+                bail!("can't resume: processing steps were changed since the run was interrupted");
+            }
+        }
+        run_progress.remaining_steps
+    } else {
+        if let Some(run_progress) = &run_progress {
+            if let Some(step) = run_progress.remaining_steps.get(0) {
+                config.progress_handler().warning(format!(
+                    "Previous run was interrupted in step {}. \
+                     Use \"resume\" operation to continue it.",
+                    step.name
+                ));
+            }
+        }
+        plan_steps(steps, step_names)?
+    };
+
+    if check_prerequisites {
+        check_step_prerequisites(steps, &planned_steps, &step_states)?;
+    }
+
     let mut steps_result = Ok(());
+    for (step_index, planned_step) in planned_steps.iter().enumerate() {
+        let step = steps.step(&planned_step.name);
+        let key = planned_step.main_index.map(|index| steps.step_key(index));
+
+        if step.name == "crate_writer" || key.is_none() {
+            save_state(workspace, &mut db_client, &step_states)?;
+        }
+
+        let mut data = ProcessorData {
+            workspace,
+            db: &mut db_client,
+            config,
+            progress: ProgressReporter::new(config.progress_handler().clone(), &step.name),
+            current_cpp_lib_version: None,
+        };
+        let input_hash = |data: &ProcessorData<'_>| step.input_hash.as_ref().map(|func| func(data));
+
+        if let Some(key) = &key {
+            if planned_step.only_if_changed
+                && step_states
+                    .completed
+                    .get(key)
+                    .map_or(false, |state| state.input_hash == input_hash(&data))
+            {
+                info!("Skipping up to date processing step: {}", &step.name);
+                continue;
+            }
+        }
+        if let Some(index) = planned_step.main_index {
+            // this and all following steps have to be run again
+            for index in index..steps.main_procedure.len() {
+                step_states.completed.remove(&steps.step_key(index));
+            }
+        }
+        data.workspace.save_step_states(crate_name, &step_states)?;
+        data.workspace.save_run_progress(
+            crate_name,
+            &RunProgress {
+                remaining_steps: planned_steps[step_index..].to_vec(),
+                processed_items: 0,
+            },
+        )?;
+
+        if config
+            .interruption_flag()
+            .map_or(false, |flag| flag.load(atomic::Ordering::SeqCst))
+        {
+            steps_result = Err(err_msg("processing was interrupted"));
+            break;
+        }
 
-    let step_index = |name| {
         config
-            .processing_steps()
+            .progress_handler()
+            .handle(&ProgressEvent::StepStarted {
+                crate_name: crate_name.to_string(),
+                step: step.name.clone(),
+                index: step_index,
+                count: planned_steps.len(),
+            });
+
+        let started_time = Instant::now();
+        let result = if step.is_per_cpp_lib_version && !config.cpp_lib_versions().is_empty() {
+            config.cpp_lib_versions().iter().try_for_each(|version| {
+                info!("C++ library version: {}", version.version());
+                data.current_cpp_lib_version = Some(version);
+                (step.function)(&mut data)
+            })
+        } else {
+            (step.function)(&mut data)
+        };
+
+        config
+            .progress_handler()
+            .handle(&ProgressEvent::StepFinished {
+                crate_name: crate_name.to_string(),
+                step: step.name.clone(),
+                success: result.is_ok(),
+                duration_ms: started_time.elapsed().as_millis() as u64,
+            });
+
+        if let Err(err) = result {
+            steps_result = Err(err);
+            error!("Step failed! Aborting...");
+            break;
+        }
+
+        if let Some(key) = key {
+            // the step may have recorded its inputs in the database,
+            // so the hash is computed again
+            step_states.completed.insert(
+                key,
+                CompletedStep {
+                    input_hash: input_hash(&data),
+                    completed_at: chrono::Local::now().to_rfc3339(),
+                },
+            );
+        } else if db_client.is_modified() {
+            // a custom step changed the database, so nothing can be considered complete
+            step_states.completed.clear();
+        }
+
+        db_client.report_counters();
+
+        // checkpoint that allows to resume the run if it's interrupted
+        save_state(workspace, &mut db_client, &step_states)?;
+    }
+
+    save_state(workspace, &mut db_client, &step_states)?;
+    if steps_result.is_ok() {
+        workspace.remove_run_progress(crate_name)?;
+    } else {
+        info!("Use \"resume\" operation to continue the run after fixing the problem.");
+    }
+
+    report_unmatched_allocation_overrides(config, &db_client);
+    report_rust_name_changes(config, &db_client);
+    report_unmatched_rename_rules(config, &db_client);
+    report_unmatched_exclusions(config, &db_client);
+    report_abnormal_check_failures(config, &db_client)?;
+    report_rejections(&db_client);
+    if steps_result.is_ok() {
+        report_coverage(workspace, config, &db_client)?;
+    }
+
+    steps_result
+}
+
+/// Runs only the C++ parser step for the crate described by `config`
+/// and returns the parsed items.
+///
+/// The step is executed through `process`, exactly like the first step of
+/// the full pipeline, so the parsed data is also saved in the workspace
+/// and can be used by subsequent runs.
+pub fn parse_only(workspace: &mut Workspace, config: &Config) -> Result<CppData> {
+    process(workspace, config, &["cpp_parser".to_string()], None)?;
+    let db = workspace.get_database_client(
+        config.crate_properties().name(),
+        config.crate_properties().dependencies(),
+        true,
+        false,
+    )?;
+    Ok(CppData::from_database(&db))
+}
+
+/// Returns indexes of `configs` in the order of processing, so that each crate
+/// is processed after the crates it depends on.
+pub fn processing_order(configs: &[Config]) -> Result<Vec<usize>> {
+    let names = configs
+        .iter()
+        .map(|config| config.crate_properties().name())
+        .collect_vec();
+    for (index, name) in names.iter().enumerate() {
+        if names[..index].contains(name) {
+            bail!("crate {} is specified multiple times", name);
+        }
+    }
+    let dependencies = configs
+        .iter()
+        .map(|config| {
+            config
+                .crate_properties()
+                .dependencies()
+                .iter()
+                .filter(|dep| dep.kind() == CrateDependencyKind::Ritual)
+                .filter_map(|dep| names.iter().position(|name| *name == dep.name()))
+                .collect_vec()
+        })
+        .collect_vec();
+
+    let mut order = Vec::new();
+    let mut is_added = vec![false; configs.len()];
+    while order.len() < configs.len() {
+        let next = (0..configs.len()).find(|&index| {
+            !is_added[index] && dependencies[index].iter().all(|&dep| is_added[dep])
+        });
+        let next = match next {
+            Some(next) => next,
+            None => {
+                let remaining = (0..configs.len())
+                    .filter(|&index| !is_added[index])
+                    .map(|index| names[index])
+                    .join(", ");
+                bail!("circular dependency between crates: {}", remaining);
+            }
+        };
+        is_added[next] = true;
+        order.push(next);
+    }
+    Ok(order)
+}
+
+/// Runs `step_names` for multiple crates of the workspace.
+/// Crates are processed in order of their dependencies, so databases
+/// of dependencies are up to date when a crate is processed.
+///
+/// If a crate fails, processing stops and the error names the failed crate.
+/// Crates processed before it are complete and usable.
+pub fn process_all(
+    workspace: &mut Workspace,
+    configs: &[Config],
+    step_names: &[String],
+    trace_item_id: Option<&ItemId>,
+) -> Result<()> {
+    let mut completed = Vec::new();
+    for index in processing_order(configs)? {
+        let config = &configs[index];
+        let crate_name = config.crate_properties().name();
+        let result = process(workspace, config, step_names, trace_item_id)
+            .with_context(|_| format!("failed to process crate {}", crate_name));
+        if result.is_err() && !completed.is_empty() {
+            info!("Completed crates: {}", completed.join(", "));
+        }
+        result?;
+        completed.push(crate_name);
+    }
+    Ok(())
+}
+
+/// Converts requested operations (step names, ranges of steps,
+/// "main" or "changed") to the sequence of steps to run.
+fn plan_steps(steps: &ProcessingSteps, step_names: &[String]) -> Result<Vec<PlannedStep>> {
+    let step_index = |name| {
+        steps
             .main_procedure
             .iter()
             .position(|s| s == &name)
             .ok_or_else(|| format_err!("requested step not found: {}", name))
     };
 
-    let step_ranges = step_names.iter().map_if_ok(|step_name| {
-        if config
-            .processing_steps()
-            .all_steps
-            .iter()
-            .any(|step| &step.name == step_name)
-        {
-            return Ok(vec![step_name.clone()]);
+    let planned_steps = step_names.iter().map_if_ok(|step_name| {
+        if steps.all_steps.iter().any(|step| &step.name == step_name) {
+            return Ok(vec![PlannedStep {
+                name: step_name.clone(),
+                main_index: steps.main_procedure.iter().position(|s| s == step_name),
+                only_if_changed: false,
+            }]);
+        }
+        if step_name == "changed" {
+            return Ok((0..steps.main_procedure.len())
+                .map(|index| main_step(steps, index, true))
+                .collect());
         }
 
         let range = parse_steps_spec(step_name)?;
@@ -415,67 +872,261 @@ pub fn process(
         let end_index = match range.1 {
             Bound::Included(name) => step_index(name)? + 1,
             Bound::Excluded(name) => step_index(name)?,
-            Bound::Unbounded => config.processing_steps().main_procedure.len(),
+            Bound::Unbounded => steps.main_procedure.len(),
         };
-        let range = config
-            .processing_steps()
-            .main_procedure
-            .get(start_index..end_index)
-            .ok_or_else(|| err_msg("invalid steps range"))?
-            .to_vec();
-        if range.is_empty() {
+        if start_index >= end_index {
             bail!("empty steps range");
         }
-        Ok(range)
+        Ok((start_index..end_index)
+            .map(|index| main_step(steps, index, false))
+            .collect())
     })?;
+    Ok(planned_steps.into_iter().flatten().collect())
+}
 
-    for step_range in step_ranges {
-        if steps_result.is_err() {
-            break;
-        }
-
-        for step_name in step_range {
-            let step = config
-                .processing_steps()
-                .all_steps
-                .iter()
-                .find(|item| item.name == step_name)
-                .expect("step name must be valid (checked above)");
+fn main_step(steps: &ProcessingSteps, index: usize, only_if_changed: bool) -> PlannedStep {
+    PlannedStep {
+        name: steps.main_procedure[index].clone(),
+        main_index: Some(index),
+        only_if_changed,
+    }
+}
 
-            if step.name == "crate_writer" {
-                workspace.save_database(&mut db_client)?;
+/// Checks that all steps required by the planned steps were completed before
+/// or will be run earlier in the same sequence.
+fn check_step_prerequisites(
+    steps: &ProcessingSteps,
+    planned_steps: &[PlannedStep],
+    step_states: &StepStates,
+) -> Result<()> {
+    let mut available = step_states
+        .completed
+        .keys()
+        .cloned()
+        .collect::<HashSet<_>>();
+    let mut missing = Vec::new();
+    for planned_step in planned_steps {
+        if !planned_step.only_if_changed {
+            for prerequisite in &steps.step(&planned_step.name).prerequisites {
+                if !available.contains(prerequisite) {
+                    missing.push(format!(
+                        "{} (required by {})",
+                        prerequisite, planned_step.name
+                    ));
+                }
             }
+        }
+        available.insert(planned_step.name.clone());
+    }
+    if !missing.is_empty() {
+        bail!(
+            "prerequisite steps were not completed: {}. \
+             Run these steps first or request a range of steps starting earlier.",
+            missing.join(", ")
+        );
+    }
+    Ok(())
+}
 
-            info!("Running processing step: {}", &step.name);
-
-            let mut data = ProcessorData {
-                workspace,
-                db: &mut db_client,
-                config,
-            };
+/// Saves the database and completion markers of the steps.
+fn save_state(
+    workspace: &Workspace,
+    db_client: &mut DatabaseClient,
+    step_states: &StepStates,
+) -> Result<()> {
+    workspace.save_database(db_client)?;
+    workspace.save_step_states(db_client.crate_name(), step_states)
+}
 
-            let started_time = Instant::now();
+/// Prints the number of rejected entities for each processing step.
+fn report_rejections(db: &DatabaseClient) {
+    let mut counts = BTreeMap::<&str, usize>::new();
+    for rejection in db.rejections() {
+        *counts.entry(&rejection.step).or_default() += 1;
+    }
+    if counts.is_empty() {
+        return;
+    }
+    info!("Rejected entities:");
+    for (step, count) in counts {
+        info!("    {}: {}", step, count);
+    }
+}
 
-            if let Err(err) = (step.function)(&mut data) {
-                steps_result = Err(err);
-                error!("Step failed! Aborting...");
-                break;
-            }
+/// Lists FFI items whose checks timed out or crashed the compiler,
+/// so that they can be blacklisted.
+fn report_abnormal_check_failures(config: &Config, db: &DatabaseClient) -> Result<()> {
+    let mut timed_out = Vec::new();
+    let mut crashed = Vec::new();
+    for ffi_item in db.ffi_items() {
+        let checks = db.cpp_checks(&ffi_item.id)?;
+        for failure in checks.failures().unique() {
+            let list = match failure {
+                CppCheckFailure::Timeout => &mut timed_out,
+                CppCheckFailure::CompilerCrash => &mut crashed,
+            };
+            list.push(ffi_item.item.short_text());
+        }
+    }
+    for (title, list) in &[
+        ("items that timed out in cpp_checker:", timed_out),
+        ("items that crashed the compiler in cpp_checker:", crashed),
+    ] {
+        if !list.is_empty() {
+            let items = list.iter().map(|item| format!("\n    {}", item)).join("");
+            config
+                .progress_handler()
+                .warning(format!("{}{}", title, items));
+        }
+    }
+    Ok(())
+}
 
-            let elapsed = started_time.elapsed();
-            trace!("Step '{}' completed in {:?}", step.name, elapsed);
+/// Saves the coverage report in the workspace and fails if the coverage
+/// is lower than `Config::min_coverage`.
+fn report_coverage(workspace: &Workspace, config: &Config, db: &DatabaseClient) -> Result<()> {
+    if db.rust_items().next().is_none() {
+        // Rust items were not generated yet
+        return Ok(());
+    }
+    let report = CoverageReport::new(db, config)?;
+    workspace.save_coverage_report(&report)?;
+    info!(
+        "API coverage: {:.1}% (see {})",
+        report.percentage(),
+        workspace
+            .coverage_report_path(db.crate_name(), "md")
+            .display()
+    );
+    if let Some(min_coverage) = config.min_coverage() {
+        if report.percentage() < min_coverage {
+            bail!(
+                "API coverage of {} is {:.1}%, which is lower than the required {}%",
+                db.crate_name(),
+                report.percentage(),
+                min_coverage
+            );
+        }
+    }
+    Ok(())
+}
 
-            db_client.report_counters();
+/// Warns about allocation place overrides that don't match any parsed class,
+/// e.g. because of a typo in the class name.
+fn report_unmatched_allocation_overrides(config: &Config, db: &DatabaseClient) {
+    if db.type_allocation_places().is_empty() {
+        // allocation places were not processed yet
+        return;
+    }
+    let unmatched = config
+        .type_allocation_places()
+        .iter()
+        .filter(|(path, _)| {
+            !db.type_allocation_places().iter().any(|decision| {
+                decision.source == TypeAllocationSource::Override
+                    && type_allocation_places::override_matches(path, &decision.path)
+            })
+        })
+        .collect_vec();
+    if !unmatched.is_empty() {
+        let items = unmatched
+            .iter()
+            .map(|(path, place)| format!("\n    {} ({:?})", path.to_cpp_pseudo_code(), place))
+            .join("");
+        config.progress_handler().warning(format!(
+            "allocation place overrides that don't match any class:{}",
+            items
+        ));
+    }
+}
 
-            if elapsed > Duration::from_secs(15) {
-                workspace.save_database(&mut db_client)?;
-            }
-        }
+/// Warns about Rust names of functions that were changed since the previous run.
+fn report_rust_name_changes(config: &Config, db: &DatabaseClient) {
+    if db.rust_name_changes().is_empty() {
+        return;
     }
+    let items = db
+        .rust_name_changes()
+        .iter()
+        .map(|change| {
+            format!(
+                "\n    {}: {} -> {} ({})",
+                change.cpp_signature,
+                change.old_path.full_name(None),
+                change.new_path.full_name(None),
+                change.reason
+            )
+        })
+        .join("");
+    config
+        .progress_handler()
+        .warning(format!("Rust names that couldn't be preserved:{}", items));
+}
 
-    workspace.save_database(&mut db_client)?;
+/// Warns about rename rules that don't match any item,
+/// e.g. because the item was removed from the C++ library.
+fn report_unmatched_rename_rules(config: &Config, db: &DatabaseClient) {
+    if config.rename_rules().is_empty() || db.rust_items().next().is_none() {
+        // Rust items were not generated yet
+        return;
+    }
+    let unmatched = config
+        .rename_rules()
+        .iter()
+        .map(|rule| rule.to_string())
+        .filter(|rule| {
+            !db.applied_rename_rules()
+                .iter()
+                .any(|applied| &applied.rule == rule)
+        })
+        .collect_vec();
+    if !unmatched.is_empty() {
+        let items = unmatched
+            .iter()
+            .map(|rule| format!("\n    {}", rule))
+            .join("");
+        config
+            .progress_handler()
+            .warning(format!("rename rules that don't match any item:{}", items));
+    }
+}
 
-    steps_result
+/// Warns about method and class member exclusions that don't match any item,
+/// e.g. because of a typo in the name or the signature.
+fn report_unmatched_exclusions(config: &Config, db: &DatabaseClient) {
+    if db.cpp_items().next().is_none() {
+        // the C++ parser didn't run yet
+        return;
+    }
+    let is_matched = |reason: &str| {
+        db.rejections()
+            .iter()
+            .any(|r| r.step == USER_CONFIG_STEP && r.reason == reason)
+    };
+    let unmatched = config
+        .method_exclusions()
+        .iter()
+        .filter(|exclusion| !is_matched(&method_exclusion_reason(exclusion)))
+        .map(|exclusion| format!("\n    blacklist_method({})", exclusion))
+        .chain(
+            config
+                .class_member_exclusions()
+                .iter()
+                .filter(|path| !is_matched(&class_members_exclusion_reason(path)))
+                .map(|path| {
+                    format!(
+                        "\n    blacklist_class_members({})",
+                        path.to_cpp_pseudo_code()
+                    )
+                }),
+        )
+        .join("");
+    if !unmatched.is_empty() {
+        config.progress_handler().warning(format!(
+            "exclusions that don't match any item:{}",
+            unmatched
+        ));
+    }
 }
 
 fn parse_steps_spec(text: &str) -> Result<(Bound<String>, Bound<String>)> {
@@ -591,12 +1242,26 @@ fn test_parse_steps_spec() {
     assert!(parse_steps_spec("[t1..t2[").is_err());
 }
 
+/// Name of the step in rejections of items excluded by `Config::blacklist_method`,
+/// `Config::blacklist_class_members` and `Config::add_method_filter`.
+pub const USER_CONFIG_STEP: &str = "user config";
+
 impl ProcessorData<'_> {
     pub fn add_cpp_item(
         &mut self,
         source_id: Option<ItemId>,
         data: CppItem,
     ) -> Result<Option<ItemId>> {
+        let include_file = match &source_id {
+            Some(source_id) => self
+                .db
+                .root_item_origin(source_id)?
+                .map(|origin| origin.location.include_file_path.clone()),
+            None => None,
+        };
+        if self.reject_excluded_cpp_item(&data, include_file) {
+            return Ok(None);
+        }
         if let Some(hook) = self.config.cpp_item_filter_hook() {
             if !hook(&data)? {
                 debug!("blacklisted cpp item: {}", data.short_text());
@@ -612,4 +1277,39 @@ impl ProcessorData<'_> {
 
         self.db.add_cpp_item_without_hook(source_id, data)
     }
+
+    /// Checks if `item` is excluded by the user config. Exclusions are recorded
+    /// as rejections of `USER_CONFIG_STEP`. Returns true if the item is excluded.
+    pub fn reject_excluded_cpp_item(
+        &mut self,
+        item: &CppItem,
+        include_file: Option<String>,
+    ) -> bool {
+        let reason = match self.config.cpp_item_exclusion_reason(item) {
+            Some(reason) => reason,
+            None => return false,
+        };
+        debug!("excluded cpp item: {}: {}", item.short_text(), reason);
+        self.db.add_rejection(Rejection {
+            cpp_name: item.short_text(),
+            step: USER_CONFIG_STEP.to_string(),
+            reason,
+            include_file,
+        });
+        true
+    }
+
+    /// Saves the database and records that the current step has processed
+    /// `processed_items` items. Long steps should call it periodically, so that
+    /// the work is not lost if the run is interrupted. The step must be able
+    /// to continue from the saved state when the run is resumed.
+    pub fn checkpoint(&mut self, processed_items: usize) -> Result<()> {
+        let crate_name = self.db.crate_name().to_string();
+        self.workspace.save_database(self.db)?;
+        if let Some(mut progress) = self.workspace.load_run_progress(&crate_name)? {
+            progress.processed_items = processed_items;
+            self.workspace.save_run_progress(&crate_name, &progress)?;
+        }
+        Ok(())
+    }
 }
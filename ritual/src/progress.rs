@@ -0,0 +1,203 @@
+//! Reporting of processing progress to external tools.
+//!
+//! Progress handlers only observe the processing: they receive copies of
+//! the event data and can't affect the database or the generated output.
+
+use log::{info, trace, warn};
+use ritual_common::errors::{Result, ResultExt};
+use ritual_common::file_utils::create_file;
+use serde_derive::Serialize;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// An event emitted during processing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    StepStarted {
+        crate_name: String,
+        step: String,
+        /// Index of the step in the requested sequence
+        index: usize,
+        /// Number of steps in the requested sequence
+        count: usize,
+    },
+    StepFinished {
+        crate_name: String,
+        step: String,
+        success: bool,
+        duration_ms: u64,
+    },
+    /// Number of items processed by the current step
+    Items {
+        step: String,
+        processed: u64,
+        /// Total number of items, if known in advance
+        total: Option<u64>,
+        /// Name of the last processed item
+        current_item: Option<String>,
+    },
+    Warning {
+        message: String,
+    },
+}
+
+/// Receives progress events.
+pub trait ProgressHandler: Send + Sync {
+    fn handle(&self, event: &ProgressEvent);
+}
+
+impl dyn ProgressHandler {
+    pub fn warning(&self, message: impl Into<String>) {
+        self.handle(&ProgressEvent::Warning {
+            message: message.into(),
+        });
+    }
+}
+
+/// Reports events to the log. This is the default handler.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LogProgressHandler;
+
+impl ProgressHandler for LogProgressHandler {
+    fn handle(&self, event: &ProgressEvent) {
+        match event {
+            ProgressEvent::StepStarted { step, .. } => {
+                info!("Running processing step: {}", step);
+            }
+            ProgressEvent::StepFinished {
+                step,
+                success,
+                duration_ms,
+                ..
+            } => {
+                let duration = Duration::from_millis(*duration_ms);
+                if *success {
+                    trace!("Step '{}' completed in {:?}", step, duration);
+                } else {
+                    trace!("Step '{}' failed after {:?}", step, duration);
+                }
+            }
+            ProgressEvent::Items {
+                step,
+                processed,
+                total,
+                current_item,
+            } => {
+                let total = total.map_or_else(String::new, |total| format!(" / {}", total));
+                let current_item = current_item
+                    .as_ref()
+                    .map_or_else(String::new, |item| format!(" ({})", item));
+                trace!("[{}] {}{} items{}", step, processed, total, current_item);
+            }
+            ProgressEvent::Warning { message } => {
+                warn!("{}", message);
+            }
+        }
+    }
+}
+
+/// Writes each event as a JSON object on a separate line.
+pub struct JsonLinesProgressHandler {
+    output: Mutex<Box<dyn Write + Send>>,
+}
+
+impl JsonLinesProgressHandler {
+    pub fn new(output: impl Write + Send + 'static) -> Self {
+        JsonLinesProgressHandler {
+            output: Mutex::new(Box::new(output)),
+        }
+    }
+
+    pub fn to_stdout() -> Self {
+        Self::new(io::stdout())
+    }
+
+    pub fn to_file(path: impl AsRef<Path>) -> Result<Self> {
+        let file =
+            create_file(path.as_ref()).with_context(|_| "failed to create progress output file")?;
+        Ok(Self::new(file.into_inner()))
+    }
+}
+
+impl fmt::Debug for JsonLinesProgressHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonLinesProgressHandler").finish()
+    }
+}
+
+impl ProgressHandler for JsonLinesProgressHandler {
+    fn handle(&self, event: &ProgressEvent) {
+        let mut output = self.output.lock().unwrap();
+        // errors of the progress output shouldn't interrupt processing
+        let result = serde_json::to_writer(&mut *output, event)
+            .map_err(io::Error::from)
+            .and_then(|_| writeln!(output))
+            .and_then(|_| output.flush());
+        if let Err(err) = result {
+            trace!("failed to write progress event: {}", err);
+        }
+    }
+}
+
+impl<T: ProgressHandler + ?Sized> ProgressHandler for Arc<T> {
+    fn handle(&self, event: &ProgressEvent) {
+        (**self).handle(event);
+    }
+}
+
+/// Sends events to both handlers.
+impl<A: ProgressHandler, B: ProgressHandler> ProgressHandler for (A, B) {
+    fn handle(&self, event: &ProgressEvent) {
+        self.0.handle(event);
+        self.1.handle(event);
+    }
+}
+
+/// Reports progress of the current processing step.
+/// It can be cloned and sent to other threads.
+#[derive(Clone)]
+pub struct ProgressReporter {
+    handler: Arc<dyn ProgressHandler>,
+    step: Arc<String>,
+}
+
+impl ProgressReporter {
+    pub fn new(handler: Arc<dyn ProgressHandler>, step: &str) -> Self {
+        ProgressReporter {
+            handler,
+            step: Arc::new(step.to_string()),
+        }
+    }
+
+    /// Returns name of the current step.
+    pub fn step(&self) -> &str {
+        &self.step
+    }
+
+    /// Reports that `processed` items (out of `total`, if known) were processed
+    /// by the current step.
+    pub fn items(&self, processed: u64, total: Option<u64>, current_item: Option<String>) {
+        self.handler.handle(&ProgressEvent::Items {
+            step: self.step.to_string(),
+            processed,
+            total,
+            current_item,
+        });
+    }
+
+    pub fn warning(&self, message: impl Into<String>) {
+        self.handler.warning(message);
+    }
+}
+
+impl fmt::Debug for ProgressReporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressReporter")
+            .field("step", &self.step)
+            .finish()
+    }
+}
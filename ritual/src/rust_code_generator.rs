@@ -1,26 +1,29 @@
 //! Types and functions used for Rust code generation.
 
+use crate::config::{Config, ThreadSafety};
 use crate::cpp_checks::Condition;
+use crate::cpp_data::CppItem;
 use crate::cpp_ffi_data::{CppFfiArgumentMeaning, CppFfiItem};
 use crate::cpp_function::CppFunction;
 use crate::database::{DatabaseClient, DbItem, ItemId};
 use crate::doc_formatter;
 use crate::rust_generator::qt_core_path;
 use crate::rust_info::{
-    RustEnumValue, RustExtraImpl, RustExtraImplKind, RustFfiWrapperData, RustFunction,
-    RustFunctionArgument, RustFunctionKind, RustItem, RustModule, RustModuleKind,
-    RustQtReceiverType, RustSpecialModuleKind, RustStruct, RustStructKind, RustTraitImpl,
-    RustWrapperTypeKind,
+    RustEnumValue, RustExtraImpl, RustExtraImplKind, RustFeatures, RustFfiWrapperData,
+    RustFunction, RustFunctionArgument, RustFunctionKind, RustItem, RustModule, RustModuleKind,
+    RustQtReceiverType, RustReexportSource, RustSpecialModuleKind, RustStruct, RustStructKind,
+    RustTraitImpl, RustWrapperTypeKind,
 };
 use crate::rust_type::{
-    RustClosureToCallbackConversion, RustCommonType, RustFinalType, RustPath,
-    RustPointerLikeTypeKind, RustToFfiTypeConversion, RustType,
+    string_arg, unraw_identifier, RustClosureToCallbackConversion, RustCommonType, RustFinalType,
+    RustPath, RustPointerLikeTypeKind, RustToFfiTypeConversion, RustType,
 };
 use itertools::Itertools;
 use ritual_common::errors::{bail, err_msg, format_err, Result};
 use ritual_common::file_utils::{create_dir_all, create_file, file_to_string, File};
 use ritual_common::string_utils::trim_slice;
 use ritual_common::utils::MapIfOk;
+use std::collections::BTreeSet;
 use std::fmt::Write as _;
 use std::fs;
 use std::io::{self, BufWriter, Write};
@@ -35,6 +38,64 @@ fn wrap_unsafe(in_unsafe_context: bool, content: &str) -> String {
     format!("{}{}{}", unsafe_start, content, unsafe_end)
 }
 
+/// Type of a field that makes class wrappers neither `Send` nor `Sync`
+/// unless the config says otherwise.
+const NOT_THREAD_SAFE_MARKER: &str = "::std::marker::PhantomData<*mut u8>";
+
+/// Returns code of enum value `value` as a literal of integer type `underlying_type`.
+fn enum_value_literal(value: i64, underlying_type: &RustType) -> String {
+    let is_unsigned = match underlying_type {
+        RustType::Primitive(name) => name.starts_with('u'),
+        RustType::Common(RustCommonType { path, .. }) => path.last().starts_with("c_u"),
+        _ => false,
+    };
+    if is_unsigned {
+        // values of unsigned 64-bit enums may not fit in `i64`
+        (value as u64).to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Returns the primitive type that can be used in `#[repr]` of a Rust enum
+/// with `underlying_type`.
+fn enum_repr_type(underlying_type: &RustType) -> Result<String> {
+    let name = match underlying_type {
+        RustType::Primitive(name) => Some(name.as_str()),
+        RustType::Common(RustCommonType { path, .. })
+            if path.parent().ok() == Some(RustPath::from_good_str("std::os::raw")) =>
+        {
+            match path.last() {
+                "c_schar" => Some("i8"),
+                "c_uchar" => Some("u8"),
+                "c_short" => Some("i16"),
+                "c_ushort" => Some("u16"),
+                "c_int" => Some("i32"),
+                "c_uint" => Some("u32"),
+                "c_longlong" => Some("i64"),
+                "c_ulonglong" => Some("u64"),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    name.map(String::from).ok_or_else(|| {
+        format_err!(
+            "unsupported enum type for a Rust enum: {:?}",
+            underlying_type
+        )
+    })
+}
+
+/// Returns code of a tuple of `values`, or the value itself if there is only one.
+fn tuple_or_value(values: &[String]) -> String {
+    if values.len() == 1 {
+        values[0].clone()
+    } else {
+        format!("({})", values.join(", "))
+    }
+}
+
 pub fn rust_common_type_to_code(rust_type: &RustCommonType, current_crate: Option<&str>) -> String {
     let mut code = rust_type.path.full_name(current_crate);
     if let Some(args) = &rust_type.generic_arguments {
@@ -110,6 +171,11 @@ pub fn rust_type_to_code(rust_type: &RustType, current_crate: Option<&str>) -> S
             "impl {}",
             rust_common_type_to_code(trait_type, current_crate)
         ),
+        RustType::Array { element_type, size } => format!(
+            "[{}; {}]",
+            rust_type_to_code(element_type, current_crate),
+            size
+        ),
     }
 }
 
@@ -118,6 +184,8 @@ struct Generator<'a> {
     crate_template_src_path: Option<PathBuf>,
     destination: Vec<File<BufWriter<fs::File>>>,
     current_database: &'a DatabaseClient,
+    config: &'a Config,
+    features: &'a RustFeatures,
 }
 
 impl Write for Generator<'_> {
@@ -138,6 +206,40 @@ impl Write for Generator<'_> {
     }
 }
 
+/// Returns names of the source C++ item of `id` that can be used as `#[doc(alias)]`
+/// of the Rust item named `rust_name`. Names equal to `rust_name`
+/// and names rustdoc doesn't accept as aliases are skipped.
+pub fn doc_aliases(database: &DatabaseClient, id: &ItemId, rust_name: &str) -> Result<Vec<String>> {
+    let cpp_item = match database.source_cpp_item(id)? {
+        Some(item) => item,
+        None => return Ok(Vec::new()),
+    };
+    let names = match &cpp_item.item {
+        CppItem::Function(function) => vec![function.path.last().name.clone()],
+        CppItem::Type(type1) => vec![
+            type1.path.last().name.clone(),
+            type1.path.last().to_cpp_pseudo_code(),
+            type1.path.to_cpp_pseudo_code(),
+        ],
+        CppItem::EnumValue(value) => vec![value.path.last().name.clone()],
+        _ => return Ok(Vec::new()),
+    };
+    let rust_name = unraw_identifier(rust_name);
+    let aliases = names
+        .iter()
+        .map(|name| name.trim())
+        .unique()
+        .filter(|name| {
+            let is_valid = !name.is_empty()
+                && !name
+                    .contains(|c: char| c == '"' || c == '\'' || (c.is_whitespace() && c != ' '));
+            is_valid && *name != rust_name
+        })
+        .map(str::to_string)
+        .collect();
+    Ok(aliases)
+}
+
 /// Generates documentation comments containing
 /// markdown code `doc`.
 fn format_doc(doc: &str) -> String {
@@ -146,7 +248,10 @@ fn format_doc(doc: &str) -> String {
 
 #[derive(Debug, Default)]
 struct ConditionTexts {
+    /// Attributes for the platform condition and the Cargo features of the item.
     attribute: String,
+    /// Attribute for the Cargo features of the item only.
+    feature_attribute: String,
     doc_text: String,
 }
 
@@ -176,24 +281,53 @@ fn format_doc_extended(doc: &str, is_outer: bool) -> String {
         + extra_line_breaks
 }
 
-fn condition_expression(condition: &Condition) -> String {
+/// Returns attributes that enable an item only if all `features` are enabled.
+fn feature_attribute(features: &BTreeSet<String>) -> String {
+    let expression = if features.len() == 1 {
+        format!("feature = {:?}", features.iter().next().unwrap())
+    } else {
+        format!(
+            "all({})",
+            features
+                .iter()
+                .map(|feature| format!("feature = {:?}", feature))
+                .join(", ")
+        )
+    };
+    format!(
+        "#[cfg_attr(feature = \"ritual_rustdoc_nightly\", doc(cfg({})))]\n\
+         #[cfg({})]\n",
+        expression, expression
+    )
+}
+
+fn condition_expression(condition: &Condition, config: &Config) -> String {
     match condition {
         Condition::CppLibraryVersion(version) => format!("cpp_lib_version={:?}", version),
-        Condition::Arch(_) => unimplemented!(),
-        Condition::OS(_) => unimplemented!(),
-        Condition::Family(_) => unimplemented!(),
-        Condition::Env(_) => unimplemented!(),
-        Condition::PointerWidth(_) => unimplemented!(),
-        Condition::Endian(_) => unimplemented!(),
+        Condition::Target(target) => config.target_cfg_expression(target),
+        Condition::Arch(arch) => format!("target_arch = {:?}", arch.cfg_value()),
+        Condition::OS(os) => format!("target_os = {:?}", os.cfg_value()),
+        Condition::Family(family) => format!("target_family = {:?}", family.cfg_value()),
+        Condition::Env(env) => format!("target_env = {:?}", env.cfg_value()),
+        Condition::PointerWidth(width) => {
+            format!("target_pointer_width = {:?}", width.cfg_value())
+        }
+        Condition::Endian(endian) => format!("target_endian = {:?}", endian.cfg_value()),
         Condition::And(conditions) => {
-            let list = conditions.iter().map(condition_expression).join(", ");
+            let list = conditions
+                .iter()
+                .map(|c| condition_expression(c, config))
+                .join(", ");
             format!("all({})", list)
         }
         Condition::Or(conditions) => {
-            let list = conditions.iter().map(condition_expression).join(", ");
+            let list = conditions
+                .iter()
+                .map(|c| condition_expression(c, config))
+                .join(", ");
             format!("any({})", list)
         }
-        Condition::Not(condition) => format!("not({})", condition_expression(condition)),
+        Condition::Not(condition) => format!("not({})", condition_expression(condition, config)),
         Condition::True => "not(false)".to_string(),
         Condition::False => "false".to_string(),
     }
@@ -293,7 +427,7 @@ impl Generator<'_> {
                 .cpp_checks(&ffi_item.id)?
                 .condition(self.current_database.environments());
             if condition != Condition::True {
-                let expression = condition_expression(&condition);
+                let expression = condition_expression(&condition, self.config);
                 condition_texts.attribute = format!(
                     "#[cfg_attr(feature = \"ritual_rustdoc_nightly\", doc(cfg({})))]\n\
                     #[cfg(any({}, feature = \"ritual_rustdoc\"))]\n",
@@ -303,9 +437,17 @@ impl Generator<'_> {
                 // format!("\n\nThis item is available if `{}`.", expression);
             }
         }
+        if let Some(features) = self.features.item_features(&item.id) {
+            condition_texts.feature_attribute = feature_attribute(features);
+            condition_texts
+                .attribute
+                .insert_str(0, &condition_texts.feature_attribute);
+        }
 
         match &item.item {
-            RustItem::Module(_) => self.generate_module(item.map(|i| i.as_module_ref().unwrap())),
+            RustItem::Module(_) => {
+                self.generate_module(item.map(|i| i.as_module_ref().unwrap()), &condition_texts)
+            }
             RustItem::Struct(_) => {
                 self.generate_struct(item.map(|i| i.as_struct_ref().unwrap()), &condition_texts)
             }
@@ -327,12 +469,20 @@ impl Generator<'_> {
                 &condition_texts,
             ),
             RustItem::Reexport(reexport) => {
-                writeln!(
-                    self,
-                    "pub use {} as {};",
-                    self.rust_path_to_string(&reexport.target),
-                    reexport.path.last()
-                )?;
+                write!(self, "{}", condition_texts.attribute)?;
+                match &reexport.source {
+                    RustReexportSource::DependencyCrate { .. } => writeln!(
+                        self,
+                        "pub use {} as {};",
+                        self.rust_path_to_string(&reexport.target),
+                        reexport.path.last()
+                    )?,
+                    RustReexportSource::CppNamespace { .. } => writeln!(
+                        self,
+                        "pub use {}::*;",
+                        self.rust_path_to_string(&reexport.target)
+                    )?,
+                }
                 Ok(())
             }
         }
@@ -347,7 +497,11 @@ impl Generator<'_> {
     }
 
     #[allow(clippy::collapsible_if)]
-    fn generate_module(&mut self, module: DbItem<&RustModule>) -> Result<()> {
+    fn generate_module(
+        &mut self,
+        module: DbItem<&RustModule>,
+        condition_texts: &ConditionTexts,
+    ) -> Result<()> {
         if self
             .current_database
             .rust_children(&module.item.path)
@@ -363,7 +517,13 @@ impl Generator<'_> {
         let mut content_from_template = None;
         if module.item.kind.is_in_separate_file() {
             if module.item.kind != RustModuleKind::Special(RustSpecialModuleKind::CrateRoot) {
-                writeln!(self, "{}mod {};", vis, module.item.path.last())?;
+                writeln!(
+                    self,
+                    "{}{}mod {};",
+                    condition_texts.attribute,
+                    vis,
+                    module.item.path.last()
+                )?;
             }
             let path = self.module_path(&module.item.path, &self.output_src_path)?;
             self.push_file(&path)?;
@@ -372,6 +532,10 @@ impl Generator<'_> {
                 self,
                 "#![cfg_attr(feature = \"ritual_rustdoc_nightly\", feature(doc_cfg))]"
             )?;
+            if module.item.kind == RustModuleKind::Special(RustSpecialModuleKind::CrateRoot) {
+                // generated code itself uses deprecated C++ APIs
+                writeln!(self, "#![allow(deprecated)]")?;
+            }
 
             if let Some(crate_template_src_path) = &self.crate_template_src_path {
                 let template_path = self.module_path(&module.item.path, crate_template_src_path)?;
@@ -385,7 +549,13 @@ impl Generator<'_> {
                 module.item.kind,
                 RustModuleKind::Special(RustSpecialModuleKind::CrateRoot)
             );
-            writeln!(self, "{}mod {} {{", vis, module.item.path.last())?;
+            writeln!(
+                self,
+                "{}{}mod {} {{",
+                condition_texts.attribute,
+                vis,
+                module.item.path.last()
+            )?;
         }
 
         write!(
@@ -401,6 +571,24 @@ impl Generator<'_> {
             writeln!(self, "{}", content)?;
         }
 
+        if module.item.kind == RustModuleKind::Special(RustSpecialModuleKind::CrateRoot)
+            && self.has_extra_module()
+        {
+            let has_conflict =
+                self.current_database
+                    .rust_children(&module.item.path)
+                    .any(|child| {
+                        child
+                            .item
+                            .path()
+                            .map_or(false, |path| path.last() == "extra")
+                    });
+            if has_conflict {
+                bail!("module name `extra` is reserved for hand-written code of the crate");
+            }
+            writeln!(self, "pub mod extra;")?;
+        }
+
         match module.item.kind {
             RustModuleKind::Special(RustSpecialModuleKind::Ffi) => {
                 writeln!(self, "include!(concat!(env!(\"OUT_DIR\"), \"/ffi.rs\"));")?;
@@ -414,7 +602,8 @@ impl Generator<'_> {
             RustModuleKind::Special(RustSpecialModuleKind::CrateRoot)
             | RustModuleKind::Special(RustSpecialModuleKind::Ops)
             | RustModuleKind::CppNamespace { .. }
-            | RustModuleKind::CppNestedTypes { .. } => {
+            | RustModuleKind::CppNestedTypes { .. }
+            | RustModuleKind::CppHeader => {
                 self.generate_children(&module.item.path, None)?;
             }
         }
@@ -438,6 +627,19 @@ impl Generator<'_> {
         Ok(())
     }
 
+    /// Returns true if `src/extra/mod.rs` is provided by the crate overlay
+    /// or preserved in the output directory from the previous generation.
+    fn has_extra_module(&self) -> bool {
+        let relative_path = Path::new("extra").join("mod.rs");
+        self.output_src_path.join(&relative_path).exists()
+            || self
+                .config
+                .crate_overlay_path()
+                .map_or(false, |overlay_path| {
+                    overlay_path.join("src").join(&relative_path).exists()
+                })
+    }
+
     fn qt_core_path(&self) -> RustPath {
         qt_core_path(&self.current_database.crate_name())
     }
@@ -451,14 +653,83 @@ impl Generator<'_> {
         }
     }
 
+    /// Returns `#[deprecated]` attribute if the source C++ item of `id` is deprecated.
+    fn deprecated_attribute(&self, id: &ItemId) -> Result<String> {
+        let cpp_item = match self.current_database.source_cpp_item(id)? {
+            Some(item) => item,
+            None => return Ok(String::new()),
+        };
+        let (deprecated, path) = match &cpp_item.item {
+            CppItem::Function(function) => (&function.deprecated, &function.path),
+            CppItem::Type(type1) => (&type1.deprecated, &type1.path),
+            _ => return Ok(String::new()),
+        };
+        let deprecated = match deprecated {
+            Some(deprecated) => deprecated,
+            None => return Ok(String::new()),
+        };
+        let note = match &deprecated.message {
+            Some(message) => message.clone(),
+            None => format!("`{}` is deprecated in C++", path.to_cpp_pseudo_code()),
+        };
+        Ok(format!("#[deprecated(note = {:?})]\n", note))
+    }
+
+    /// Returns `#[doc(alias)]` attributes with the names of the source C++ item of `id`
+    /// that differ from `rust_name`, so that the item can be found by its C++ name.
+    fn doc_alias_attribute(&self, id: &ItemId, rust_name: &str) -> Result<String> {
+        let mut output = String::new();
+        for name in doc_aliases(self.current_database, id, rust_name)? {
+            writeln!(output, "#[doc(alias = {:?})]", name)?;
+        }
+        Ok(output)
+    }
+
+    /// Generates constants that fail to compile if the layout of the movable
+    /// wrapper type differs from the C++ class layout recorded by the C++ parser.
+    fn generate_layout_assertions(
+        &mut self,
+        rust_struct: DbItem<&RustStruct>,
+        condition_texts: &ConditionTexts,
+    ) -> Result<()> {
+        let layout = match self.current_database.source_class_layout(&rust_struct.id)? {
+            Some(layout) => layout.clone(),
+            None => return Ok(()),
+        };
+        let name = rust_struct.item.path.last();
+        writeln!(
+            self,
+            "{}const _: [(); {}] = [(); ::std::mem::size_of::<{}>()];",
+            condition_texts.feature_attribute, layout.size, name
+        )?;
+        if let Some(align) = layout.align {
+            writeln!(
+                self,
+                "{}const _: [(); {}] = [(); ::std::mem::align_of::<{}>()];",
+                condition_texts.feature_attribute, align, name
+            )?;
+        }
+        writeln!(self)?;
+        Ok(())
+    }
+
     fn generate_struct(
         &mut self,
         rust_struct: DbItem<&RustStruct>,
         condition_texts: &ConditionTexts,
     ) -> Result<()> {
-        let doc = doc_formatter::struct_doc(rust_struct.clone(), self.current_database)?
-            + &condition_texts.doc_text;
+        let doc =
+            doc_formatter::struct_doc(rust_struct.clone(), self.current_database, self.config)?
+                + &condition_texts.doc_text;
         write!(self, "{}", format_doc(&doc))?;
+        if let RustStructKind::WrapperType(_) = &rust_struct.item.kind {
+            let deprecated = self.deprecated_attribute(&rust_struct.id)?;
+            write!(self, "{}", deprecated)?;
+            let doc_alias =
+                self.doc_alias_attribute(&rust_struct.id, rust_struct.item.path.last())?;
+            write!(self, "{}", doc_alias)?;
+        }
+        write!(self, "{}", condition_texts.feature_attribute)?;
 
         let visibility = if rust_struct.item.is_public {
             "pub "
@@ -467,33 +738,87 @@ impl Generator<'_> {
         };
         match &rust_struct.item.kind {
             RustStructKind::WrapperType(kind) => match kind {
-                RustWrapperTypeKind::EnumWrapper => {
+                RustWrapperTypeKind::EnumWrapper { underlying_type } => {
+                    let name = rust_struct.item.path.last();
+                    let int = self.rust_type_to_code(underlying_type);
+                    if self.is_closed_enum(&rust_struct.id, &rust_struct.item.path)? {
+                        let mut variants = String::new();
+                        let mut try_from_arms = String::new();
+                        for (value_name, value) in self.enum_variants(&rust_struct.item.path) {
+                            let value = enum_value_literal(value, underlying_type);
+                            writeln!(variants, "    {} = {},", value_name, value)?;
+                            writeln!(
+                                try_from_arms,
+                                "            {} => Ok({}::{}),",
+                                value, name, value_name
+                            )?;
+                        }
+                        writeln!(
+                            self,
+                            include_str!("../templates/crate/closed_enum.rs.in"),
+                            vis = visibility,
+                            name = name,
+                            int = int,
+                            repr = enum_repr_type(underlying_type)?,
+                            variants = variants,
+                            try_from_arms = try_from_arms,
+                            condition_attribute = condition_texts.feature_attribute,
+                        )?;
+                    } else {
+                        let mut debug_arms = String::new();
+                        for (value_name, value) in self.enum_variants(&rust_struct.item.path) {
+                            writeln!(
+                                debug_arms,
+                                "            {} => f.write_str({:?}),",
+                                enum_value_literal(value, underlying_type),
+                                value_name
+                            )?;
+                        }
+                        writeln!(
+                            self,
+                            include_str!("../templates/crate/enum_wrapper.rs.in"),
+                            vis = visibility,
+                            name = name,
+                            int = int,
+                            debug_arms = debug_arms,
+                            condition_attribute = condition_texts.feature_attribute,
+                        )?;
+                    }
+                }
+                RustWrapperTypeKind::ImmovableClassWrapper => {
+                    writeln!(self, "#[repr(C)]")?;
                     writeln!(
                         self,
-                        include_str!("../templates/crate/enum_wrapper.rs.in"),
-                        vis = visibility,
-                        name = rust_struct.item.path.last()
+                        "{}struct {} {{ _unused: u8, _marker: {}, }}",
+                        visibility,
+                        rust_struct.item.path.last(),
+                        NOT_THREAD_SAFE_MARKER
                     )?;
                 }
-                RustWrapperTypeKind::ImmovableClassWrapper => {
+                RustWrapperTypeKind::OpaqueClassWrapper => {
                     writeln!(self, "#[repr(C)]")?;
                     writeln!(
                         self,
-                        "{}struct {} {{ _unused: u8, }}",
+                        "{}struct {} {{ _private: [u8; 0], _marker: {}, }}",
                         visibility,
-                        rust_struct.item.path.last()
+                        rust_struct.item.path.last(),
+                        NOT_THREAD_SAFE_MARKER
                     )?;
                 }
                 RustWrapperTypeKind::MovableClassWrapper { sized_type_path } => {
                     writeln!(self, "#[repr(transparent)]")?;
                     writeln!(
                         self,
-                        "{}struct {}({});",
+                        "{}struct {}({}, {});",
                         visibility,
                         rust_struct.item.path.last(),
                         self.rust_path_to_string(sized_type_path),
+                        NOT_THREAD_SAFE_MARKER
                     )?;
                     writeln!(self)?;
+                    if self.config.write_layout_assertions() {
+                        self.generate_layout_assertions(rust_struct.clone(), condition_texts)?;
+                    }
                 }
             },
             RustStructKind::QtSlotWrapper(_) => {
@@ -502,6 +827,37 @@ impl Generator<'_> {
             RustStructKind::SizedType(_) => {
                 bail!("sized struct can't be generated with rust code generator")
             }
+            RustStructKind::FlagsType(_) => {
+                writeln!(
+                    self,
+                    include_str!("../templates/crate/flags_type.rs.in"),
+                    vis = visibility,
+                    name = rust_struct.item.path.last(),
+                    condition_attribute = condition_texts.feature_attribute,
+                )?;
+            }
+        }
+
+        let name = rust_struct.item.path.last();
+        if rust_struct.item.thread_safety != ThreadSafety::None {
+            writeln!(
+                self,
+                "/// The C++ type is declared to be safe to move to another thread \
+                 in the configuration of this crate.\n\
+                 /// This claim is not verified by the generator.\n\
+                 {}unsafe impl ::std::marker::Send for {} {{}}\n",
+                condition_texts.feature_attribute, name
+            )?;
+        }
+        if rust_struct.item.thread_safety == ThreadSafety::SendSync {
+            writeln!(
+                self,
+                "/// The C++ type is declared to be safe to use from multiple threads \
+                 in the configuration of this crate.\n\
+                 /// This claim is not verified by the generator.\n\
+                 {}unsafe impl ::std::marker::Sync for {} {{}}\n",
+                condition_texts.feature_attribute, name
+            )?;
         }
 
         if self
@@ -515,7 +871,12 @@ impl Generator<'_> {
                 generic_arguments: None,
             });
 
-            writeln!(self, "impl {} {{", rust_struct.item.path.last())?;
+            writeln!(
+                self,
+                "{}impl {} {{",
+                condition_texts.feature_attribute,
+                rust_struct.item.path.last()
+            )?;
             self.generate_children(&rust_struct.item.path, Some(&struct_type))?;
             writeln!(self, "}}")?;
             writeln!(self)?;
@@ -524,29 +885,89 @@ impl Generator<'_> {
         Ok(())
     }
 
+    /// Returns true if the enum should be generated as a Rust enum.
+    fn is_closed_enum(&self, id: &ItemId, path: &RustPath) -> Result<bool> {
+        let cpp_item = self.current_database.source_cpp_item(id)?;
+        let is_closed = cpp_item
+            .and_then(|item| item.item.path())
+            .map_or(false, |cpp_path| {
+                self.config.closed_enums().contains(cpp_path)
+            });
+        // Rust enums must have at least one variant to have a `repr`
+        Ok(is_closed && !self.enum_variants(path).is_empty())
+    }
+
+    /// Returns names and values of the enum at `path`. Each value is only returned once,
+    /// along with the name of its first enumerator.
+    fn enum_variants(&self, path: &RustPath) -> Vec<(String, i64)> {
+        let mut variants = Vec::<(String, i64)>::new();
+        for item in self.current_database.rust_children(path) {
+            if let Some(value) = item.item.as_enum_value_ref() {
+                if variants.iter().all(|(_, other)| *other != value.value) {
+                    variants.push((value.path.last().to_string(), value.value));
+                }
+            }
+        }
+        variants
+    }
+
     fn generate_enum_value(&mut self, value: DbItem<&RustEnumValue>) -> Result<()> {
+        let enum_path = value
+            .item
+            .path
+            .parent()
+            .expect("enum value must have parent");
+        let rust_enum = self
+            .current_database
+            .find_rust_item(&enum_path)
+            .ok_or_else(|| err_msg("enum not found"))?;
+        let underlying_type = match rust_enum.item {
+            RustItem::Struct(RustStruct {
+                kind:
+                    RustStructKind::WrapperType(RustWrapperTypeKind::EnumWrapper { underlying_type }),
+                ..
+            }) => underlying_type,
+            _ => bail!("parent of enum value is not an enum: {:?}", rust_enum.item),
+        };
+        let struct_path = self.rust_path_to_string(&enum_path);
+
+        let value_literal = if self.is_closed_enum(&rust_enum.id, &enum_path)? {
+            let (first_name, _) = self
+                .enum_variants(&enum_path)
+                .into_iter()
+                .find(|(_, other)| *other == value.item.value)
+                .ok_or_else(|| err_msg("enum variant not found"))?;
+            if first_name == value.item.path.last() {
+                // the value is a variant of the Rust enum
+                return Ok(());
+            }
+            format!("{}::{}", struct_path, first_name)
+        } else {
+            format!(
+                "{}({})",
+                struct_path,
+                enum_value_literal(value.item.value, underlying_type)
+            )
+        };
+
         write!(
             self,
             "{}",
             format_doc(&doc_formatter::enum_value_doc(
                 value.clone(),
-                self.current_database
+                self.current_database,
+                self.config
             )?)
         )?;
-        let struct_path = self.rust_path_to_string(
-            &value
-                .item
-                .path
-                .parent()
-                .expect("enum value must have parent"),
-        );
+        let doc_alias = self.doc_alias_attribute(&value.id, value.item.path.last())?;
+        write!(self, "{}", doc_alias)?;
         writeln!(self, "#[allow(non_upper_case_globals)]")?;
         writeln!(
             self,
-            "pub const {value_name}: {struct_path} = {struct_path}({value});",
-            value_name = value.item.path.last(),
-            struct_path = struct_path,
-            value = value.item.value
+            "pub const {}: {} = {};",
+            value.item.path.last(),
+            struct_path,
+            value_literal
         )?;
         Ok(())
     }
@@ -583,7 +1004,8 @@ impl Generator<'_> {
         };
         let code2 = match type1.conversion() {
             RustToFfiTypeConversion::None => unreachable!(),
-            RustToFfiTypeConversion::RefToPtr { .. } => {
+            RustToFfiTypeConversion::RefToPtr { .. }
+            | RustToFfiTypeConversion::BorrowToPtr { .. } => {
                 let api_is_const = type1.api_type().is_const_pointer_like()?;
                 let code = format!(
                     "{}.{}()",
@@ -608,6 +1030,10 @@ impl Generator<'_> {
                 );
                 wrap_unsafe(in_unsafe_context, &code)
             }
+            RustToFfiTypeConversion::OptionCppBoxToPtr => {
+                let code = format!("::cpp_core::CppBox::from_raw({})", source_expr);
+                wrap_unsafe(in_unsafe_context, &code)
+            }
             RustToFfiTypeConversion::QBoxToPtr => {
                 let code = format!("{}::QBox::from_raw({})", self.qt_core_prefix(), source_expr);
                 wrap_unsafe(in_unsafe_context, &code)
@@ -690,9 +1116,16 @@ impl Generator<'_> {
             RustToFfiTypeConversion::ImplCastInto(_) => {
                 bail!("ImplCastInto is not convertable from FFI type");
             }
+            RustToFfiTypeConversion::StringArgToPtr => {
+                bail!("StringArgToPtr is not convertable from FFI type");
+            }
             RustToFfiTypeConversion::ClosureToCallback { .. } => {
                 bail!("ClosureToCallback is not convertable from FFI type");
             }
+            RustToFfiTypeConversion::CallbackGuardToPtr => {
+                let code = format!("::cpp_core::CallbackGuard::from_raw({})", source_expr);
+                wrap_unsafe(in_unsafe_context, &code)
+            }
         };
         Ok(code1 + &code2)
     }
@@ -700,7 +1133,8 @@ impl Generator<'_> {
     fn convert_type_to_ffi(&self, expr: &str, type1: &RustFinalType) -> Result<String> {
         let code = match type1.conversion() {
             RustToFfiTypeConversion::None => expr.to_string(),
-            RustToFfiTypeConversion::RefToPtr { .. } => {
+            RustToFfiTypeConversion::RefToPtr { .. }
+            | RustToFfiTypeConversion::BorrowToPtr { .. } => {
                 if type1.api_type().is_const_pointer_like()?
                     && !type1.ffi_type().is_const_pointer_like()?
                 {
@@ -728,6 +1162,12 @@ impl Generator<'_> {
             RustToFfiTypeConversion::CppBoxToPtr | RustToFfiTypeConversion::QBoxToPtr => {
                 format!("{}.into_raw_ptr()", expr)
             }
+            RustToFfiTypeConversion::OptionCppBoxToPtr => {
+                format!(
+                    "{}.map_or(::std::ptr::null_mut(), |b| b.into_raw_ptr())",
+                    expr
+                )
+            }
             RustToFfiTypeConversion::UtilsPtrToPtr { .. }
             | RustToFfiTypeConversion::UtilsRefToPtr { .. }
             | RustToFfiTypeConversion::QPtrToPtr { .. } => {
@@ -750,9 +1190,18 @@ impl Generator<'_> {
                 }
             }
             RustToFfiTypeConversion::OptionUtilsRefToPtr { .. } => {
-                bail!("OptionUtilsRefToPtr is not supported in argument position");
+                if type1.ffi_type().is_const_pointer_like()? {
+                    format!("{}.map_or(::std::ptr::null(), |r| r.as_raw_ptr())", expr)
+                } else {
+                    format!(
+                        "{}.map_or(::std::ptr::null_mut(), |r| r.as_mut_raw_ptr())",
+                        expr
+                    )
+                }
+            }
+            RustToFfiTypeConversion::QFlagsToUInt { .. } => {
+                format!("::std::os::raw::c_int::from({})", expr)
             }
-            RustToFfiTypeConversion::QFlagsToUInt { .. } => format!("{}.to_int()", expr),
             RustToFfiTypeConversion::UnitToAnything => {
                 bail!("UnitToAnything is not possible to use in argument position");
             }
@@ -780,9 +1229,25 @@ impl Generator<'_> {
                 );
                 self.convert_type_to_ffi(&intermediate_expr, &intermediate)?
             }
+            RustToFfiTypeConversion::StringArgToPtr => {
+                // the temporary string object lives until the end of the FFI call
+                let call = format!(
+                    "::std::convert::Into::<{}>::into({}).as_raw_ptr()",
+                    self.rust_type_to_code(&string_arg(type1.ffi_type())?),
+                    expr
+                );
+                if type1.ffi_type().is_const_pointer_like()? {
+                    call
+                } else {
+                    format!("{} as {}", call, self.rust_type_to_code(type1.ffi_type()))
+                }
+            }
             RustToFfiTypeConversion::ClosureToCallback { .. } => {
                 "Some(ffi_callback::<T>), Some(deleter::<T>), data".to_string()
             }
+            RustToFfiTypeConversion::CallbackGuardToPtr => {
+                bail!("CallbackGuardToPtr is not possible to use in argument position");
+            }
         };
         Ok(code)
     }
@@ -816,16 +1281,23 @@ impl Generator<'_> {
             }}"
         )?;
 
+        let return_type = &conversion.closure_return_type;
+        let return_type_text = if return_type.api_type().is_unit() {
+            String::new()
+        } else {
+            format!(" -> {}", self.rust_type_to_code(return_type.ffi_type()))
+        };
         writeln!(
             code,
-            "extern \"C\" fn ffi_callback<{}>(data: *mut ::std::ffi::c_void, {}) {{",
+            "extern \"C\" fn ffi_callback<{}>(data: *mut ::std::ffi::c_void, {}){} {{",
             self.callback_bound_code(conversion),
             conversion
                 .closure_arguments
                 .iter()
                 .enumerate()
                 .map(|(i, arg)| format!("arg{}: {}", i, self.rust_type_to_code(arg.ffi_type())))
-                .join(", ")
+                .join(", "),
+            return_type_text
         )?;
 
         let func_args = conversion
@@ -837,10 +1309,13 @@ impl Generator<'_> {
             })?
             .join(", ");
 
-        code.push_str(&wrap_unsafe(
-            false,
-            &format!("(*(data as *mut T))({})", func_args),
-        ));
+        let call = format!("(*(data as *mut T))({})", func_args);
+        let call = if return_type.api_type().is_unit() {
+            call
+        } else {
+            self.convert_type_to_ffi(&call, return_type)?
+        };
+        code.push_str(&wrap_unsafe(false, &call));
 
         writeln!(code, "}}")?;
         writeln!(
@@ -868,6 +1343,10 @@ impl Generator<'_> {
             final_args.resize(arg.ffi_index + 1, None);
             final_args[arg.ffi_index] = Some(code);
         }
+        for arg in &wrapper_data.out_arguments {
+            final_args.resize(final_args.len().max(arg.ffi_index + 1), None);
+            final_args[arg.ffi_index] = Some(format!("&mut {}", arg.name));
+        }
 
         let mut result = Vec::new();
 
@@ -899,7 +1378,12 @@ impl Generator<'_> {
         if let Some(i) = return_type_ffi_index {
             let mut return_var_name = "object".to_string();
             let mut ii = 1;
-            while arguments.iter().any(|x| x.name == return_var_name) {
+            while arguments.iter().any(|x| x.name == return_var_name)
+                || wrapper_data
+                    .out_arguments
+                    .iter()
+                    .any(|x| x.name == return_var_name)
+            {
                 ii += 1;
                 return_var_name = format!("object{}", ii);
             }
@@ -954,11 +1438,43 @@ impl Generator<'_> {
             result.push(format!("{}\n}}", name));
         }
         let code = result.join("");
-        if maybe_result_var_name.is_none() {
-            self.convert_type_from_ffi(&return_type, code, in_unsafe_context, true)
+        let code = if maybe_result_var_name.is_none() {
+            self.convert_type_from_ffi(&return_type, code, in_unsafe_context, true)?
         } else {
-            Ok(code)
+            code
+        };
+        if wrapper_data.out_arguments.is_empty() {
+            return Ok(code);
+        }
+
+        let mut out_code = String::new();
+        for arg in &wrapper_data.out_arguments {
+            out_code.push_str(&format!(
+                "let mut {}: {} = {};\n",
+                arg.name,
+                self.rust_type_to_code(&arg.value_type),
+                wrap_unsafe(in_unsafe_context, "::std::mem::zeroed()")
+            ));
         }
+        let mut values = wrapper_data
+            .out_arguments
+            .iter()
+            .map(|arg| arg.name.clone())
+            .collect_vec();
+        if wrapper_data.returns_success_flag {
+            out_code.push_str(&format!(
+                "if {} {{ Some({}) }} else {{ None }}",
+                code,
+                tuple_or_value(&values)
+            ));
+        } else if return_type.api_type().is_unit() {
+            out_code.push_str(&format!("{};\n{}", code, tuple_or_value(&values)));
+        } else {
+            // tuple fields are evaluated in order, so the out values are read after the call
+            values.insert(0, code);
+            out_code.push_str(&tuple_or_value(&values));
+        }
+        Ok(format!("{{\n{}\n}}", out_code))
     }
 
     /// Generates Rust code for declaring a function's arguments.
@@ -1070,13 +1586,11 @@ impl Generator<'_> {
             Some(text) => format!("{{\n{}\n}}", text),
         };
 
-        let return_type_for_signature = if func.item.return_type.api_type().is_unit() {
+        let api_return_type = func.item.api_return_type();
+        let return_type_for_signature = if api_return_type.is_unit() {
             String::new()
         } else {
-            format!(
-                " -> {}",
-                self.rust_type_to_code(func.item.return_type.api_type())
-            )
+            format!(" -> {}", self.rust_type_to_code(&api_return_type))
         };
         let generic_args = func
             .item
@@ -1101,15 +1615,25 @@ impl Generator<'_> {
             format!("<{}>", generic_args.join(", "))
         };
 
+        // `#[deprecated]` has no effect on trait impl items,
+        // and `#[doc(alias)]` is not allowed there
+        let deprecated = if is_in_trait_context || func.item.kind == RustFunctionKind::FfiFunction {
+            String::new()
+        } else {
+            self.deprecated_attribute(&func.id)?
+                + &self.doc_alias_attribute(&func.id, func.item.path.last())?
+        };
+
         // TODO: move condition texts to doc parser
-        let doc = doc_formatter::function_doc(func.clone(), self.current_database)?
+        let doc = doc_formatter::function_doc(func.clone(), self.current_database, self.config)?
             + &condition_texts.doc_text;
         writeln!(
             self,
-            "{doc}{maybe_inline}{condition}{maybe_pub}{maybe_unsafe} \
+            "{doc}{deprecated}{maybe_inline}{condition}{maybe_pub}{maybe_unsafe} \
              fn {name}{generic_args_text}({args}){return_type} \
              {maybe_body}\n\n",
             doc = format_doc(&doc),
+            deprecated = deprecated,
             maybe_inline = if body.is_some() {
                 "#[inline(always)]\n"
             } else {
@@ -1179,13 +1703,14 @@ impl Generator<'_> {
         match &item.item.kind {
             RustExtraImplKind::FlagEnum(data) => {
                 let enum_path = self.rust_path_to_string(&data.enum_path);
-                let qflags = self.rust_path_to_string(&self.qt_core_path().join("QFlags"));
+                let flags = self.rust_path_to_string(&data.flags_path);
 
                 writeln!(
                     self,
                     include_str!("../templates/crate/flag_enum_impl.rs.in"),
                     e = enum_path,
-                    qflags = qflags
+                    flags = flags,
+                    condition_attribute = condition_texts.attribute,
                 )?;
             }
             RustExtraImplKind::QtReceiverImpl(data) => {
@@ -1196,7 +1721,9 @@ impl Generator<'_> {
                     .item;
 
                 let receiver_id = match wrapper {
-                    CppFfiItem::Function(_) => bail!("invalid source ffi item type"),
+                    CppFfiItem::Function(_) | CppFfiItem::SubclassShim(_) => {
+                        bail!("invalid source ffi item type")
+                    }
                     CppFfiItem::QtSlotWrapper(w) => CppFunction::receiver_id_from_data(
                         RustQtReceiverType::Slot,
                         "slot_",
@@ -1231,6 +1758,193 @@ impl Generator<'_> {
                     )?;
                 }
             }
+            RustExtraImplKind::Debug(data) => {
+                writeln!(
+                    self,
+                    include_str!("../templates/crate/debug_impl.rs.in"),
+                    type_path = self.rust_path_to_string(&data.target_path),
+                    ffi_function = self.rust_path_to_string(&data.ffi_function_path),
+                    condition_attribute = condition_texts.attribute,
+                )?;
+            }
+            RustExtraImplKind::FromUtf8(data) => {
+                writeln!(
+                    self,
+                    include_str!("../templates/crate/from_utf8_impl.rs.in"),
+                    type_path = self.rust_path_to_string(&data.target_path),
+                    ffi_function = self.rust_path_to_string(&data.ffi_function_path),
+                    condition_attribute = condition_texts.attribute,
+                )?;
+            }
+            RustExtraImplKind::ToStdString(data) => {
+                writeln!(
+                    self,
+                    include_str!("../templates/crate/to_std_string_impl.rs.in"),
+                    type_path = self.rust_path_to_string(&data.target_path),
+                    ffi_function = self.rust_path_to_string(&data.ffi_function_path),
+                    condition_attribute = condition_texts.attribute,
+                )?;
+            }
+            RustExtraImplKind::Iterator(data) => {
+                // `iter_mut()` takes `&mut self`, so that it can't be called
+                // again while the returned mutable references are alive
+                let (name, self_arg, iter_type, item_kind, begin, end) = if data.is_mut {
+                    (
+                        "iter_mut",
+                        "&mut self",
+                        "RefIterMut",
+                        "mutable references",
+                        ("BeginMut", "begin_mut"),
+                        ("EndMut", "end_mut"),
+                    )
+                } else {
+                    (
+                        "iter",
+                        "&self",
+                        "RefIter",
+                        "references",
+                        ("Begin", "begin"),
+                        ("End", "end"),
+                    )
+                };
+                writeln!(
+                    self,
+                    include_str!("../templates/crate/iter_impl.rs.in"),
+                    type_path = self.rust_path_to_string(&data.target_path),
+                    name = name,
+                    self_arg = self_arg,
+                    iter_type = iter_type,
+                    item_kind = item_kind,
+                    begin_type = self.rust_type_to_code(&data.begin_type),
+                    end_type = self.rust_type_to_code(&data.end_type),
+                    begin_trait = begin.0,
+                    begin_function = begin.1,
+                    end_trait = end.0,
+                    end_function = end.1,
+                    condition_attribute = condition_texts.attribute,
+                )?;
+            }
+            RustExtraImplKind::TotalOrder(data) => {
+                // `other_type` is either `&T` or `cpp_core::Ref<T>`
+                let other = if data.other_type.is_ref() {
+                    "other"
+                } else {
+                    "unsafe { ::cpp_core::Ref::from_raw_non_null(::std::ptr::NonNull::from(other)) }"
+                };
+                writeln!(
+                    self,
+                    include_str!("../templates/crate/total_order_impl.rs.in"),
+                    type_path = self.rust_path_to_string(&data.target_path),
+                    other_type = self.rust_type_to_code(&data.other_type),
+                    other = other,
+                    condition_attribute = condition_texts.attribute,
+                )?;
+            }
+            RustExtraImplKind::BaseCast(data) => {
+                writeln!(
+                    self,
+                    include_str!("../templates/crate/base_cast_impl.rs.in"),
+                    type_path = self.rust_path_to_string(&data.target_path),
+                    base_path = self.rust_path_to_string(&data.base_path),
+                    base_name = data.base_path.last(),
+                    ffi_function = self.rust_path_to_string(&data.ffi_function_path),
+                    name = data.name,
+                    condition_attribute = condition_texts.attribute,
+                )?;
+            }
+            RustExtraImplKind::OverloadedFunction(data) => {
+                let maybe_unsafe = if data.is_unsafe { "unsafe " } else { "" };
+                let trait_path = self.rust_path_to_string(&data.trait_path);
+                let this_arg = match &data.self_type {
+                    Some(self_type) => format!(", this: {}", self.rust_type_to_code(self_type)),
+                    None => String::new(),
+                };
+                writeln!(
+                    self,
+                    "{condition}/// Argument types accepted by `{function}`.\n\
+                     pub trait {name} {{\n\
+                     /// Return type of the overload.\n\
+                     type Output;\n\
+                     /// Calls the overload accepting these arguments.\n\
+                     {maybe_unsafe}fn call(self{this_arg}) -> Self::Output;\n\
+                     }}\n",
+                    condition = condition_texts.attribute,
+                    function = self.rust_path_to_string(&data.path),
+                    name = data.trait_path.last(),
+                    maybe_unsafe = maybe_unsafe,
+                    this_arg = this_arg,
+                )?;
+
+                let self_arg = match &data.self_type {
+                    Some(self_type) => {
+                        if self_type.is_const_pointer_like()? {
+                            "&self, "
+                        } else {
+                            "&mut self, "
+                        }
+                    }
+                    None => "",
+                };
+                let function = format!(
+                    "{doc}#[inline(always)]\n\
+                     pub {maybe_unsafe}fn {name}<Args: {trait_path}>({self_arg}args: Args) \
+                     -> Args::Output {{\n\
+                     args.call({self_value})\n\
+                     }}\n",
+                    doc = format_doc(&doc_formatter::overloaded_function_doc(
+                        data,
+                        self.current_database
+                    )?),
+                    maybe_unsafe = maybe_unsafe,
+                    name = data.path.last(),
+                    trait_path = trait_path,
+                    self_arg = self_arg,
+                    self_value = if data.self_type.is_some() { "self" } else { "" },
+                );
+                match &data.impl_type {
+                    Some(impl_type) => writeln!(
+                        self,
+                        "{}impl {} {{\n{}}}\n",
+                        condition_texts.attribute,
+                        self.rust_path_to_string(impl_type),
+                        function
+                    )?,
+                    None => writeln!(self, "{}{}", condition_texts.attribute, function)?,
+                }
+            }
+            RustExtraImplKind::Overload(data) => {
+                let this_arg = match &data.self_type {
+                    Some(self_type) => format!(", this: {}", self.rust_type_to_code(self_type)),
+                    None => String::new(),
+                };
+                let mut args = Vec::new();
+                if data.self_type.is_some() {
+                    args.push("this".to_string());
+                }
+                if data.argument_types.len() == 1 {
+                    args.push("self".to_string());
+                } else {
+                    args.extend((0..data.argument_types.len()).map(|i| format!("self.{}", i)));
+                }
+                writeln!(
+                    self,
+                    "{condition}impl {trait_path} for {args_type} {{\n\
+                     type Output = {return_type};\n\
+                     #[inline(always)]\n\
+                     {maybe_unsafe}fn call(self{this_arg}) -> {return_type} {{\n\
+                     {function}({args})\n\
+                     }}\n\
+                     }}\n",
+                    condition = condition_texts.attribute,
+                    trait_path = self.rust_path_to_string(&data.trait_path),
+                    args_type = self.rust_type_to_code(&data.arguments_type()),
+                    return_type = self.rust_type_to_code(&data.return_type),
+                    maybe_unsafe = if data.is_unsafe { "unsafe " } else { "" },
+                    this_arg = this_arg,
+                    function = self.rust_path_to_string(&data.function_path),
+                    args = args.join(", "),
+                )?;
+            }
         }
         Ok(())
     }
@@ -1238,14 +1952,17 @@ impl Generator<'_> {
 
 pub fn generate(
     current_database: &DatabaseClient,
+    config: &Config,
+    features: &RustFeatures,
     output_src_path: impl Into<PathBuf>,
-    crate_template_src_path: Option<impl Into<PathBuf>>,
 ) -> Result<()> {
     let mut generator = Generator {
         destination: Vec::new(),
         output_src_path: output_src_path.into(),
-        crate_template_src_path: crate_template_src_path.map(Into::into),
+        crate_template_src_path: config.crate_template_path().map(|s| s.join("src")),
         current_database,
+        config,
+        features,
     };
 
     let crate_root = generator
@@ -1257,6 +1974,6 @@ pub fn generate(
         })
         .ok_or_else(|| err_msg("crate root not found"))?;
 
-    generator.generate_module(crate_root)?;
+    generator.generate_module(crate_root, &ConditionTexts::default())?;
     Ok(())
 }
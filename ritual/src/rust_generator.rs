@@ -1,4 +1,7 @@
-use crate::config::CrateDependencyKind;
+use crate::config::{
+    CrateDependencyKind, ModuleStrategy, NestedTypesStrategy, OutParams, OverloadingStrategy,
+    PassBy, RenameRule, ReturnOwnership, ThreadSafety, TypeAllocationPlace,
+};
 use crate::cpp_checks::CppChecks;
 use crate::cpp_data::{
     inherits, CppItem, CppPath, CppPathItem, CppTypeDeclaration, CppTypeDeclarationKind,
@@ -10,32 +13,39 @@ use crate::cpp_ffi_data::{
 use crate::cpp_ffi_generator::ffi_type;
 use crate::cpp_function::{CppFunction, CppOperator, ReturnValueAllocationPlace};
 use crate::cpp_type::{
-    is_qflags, CppBuiltInNumericType, CppFunctionPointerType, CppPointerLikeTypeKind,
+    flags_template_path, CppBuiltInNumericType, CppFunctionPointerType, CppPointerLikeTypeKind,
     CppSpecificNumericType, CppSpecificNumericTypeKind, CppType, CppTypeRole,
 };
-use crate::database::{DbItem, ItemId, ItemWithSource};
+use crate::database::{
+    AppliedRenameRule, DbItem, ItemId, ItemWithSource, Rejection, RustNameChange,
+};
 use crate::processor::ProcessorData;
 use crate::rust_info::{
-    NameType, RustEnumValue, RustExtraImpl, RustExtraImplKind, RustFfiWrapperData,
-    RustFlagEnumImpl, RustFunction, RustFunctionArgument, RustFunctionCaptionStrategy,
-    RustFunctionKind, RustFunctionSelfArgKind, RustItem, RustModule, RustModuleKind, RustPathScope,
+    detect_iterator_protocol, detect_total_order, NameType, RustBaseCastImpl, RustDebugImpl,
+    RustEnumValue, RustExtraImpl, RustExtraImplKind, RustFfiWrapperData, RustFlagEnumImpl,
+    RustFlagsType, RustFunction, RustFunctionArgument, RustFunctionCaptionStrategy,
+    RustFunctionKind, RustFunctionSelfArgKind, RustItem, RustIteratorImpl, RustModule,
+    RustModuleKind, RustOutArgument, RustOverload, RustOverloadedFunction, RustPathScope,
     RustQtReceiverData, RustQtReceiverImpl, RustQtReceiverType, RustReexport, RustReexportSource,
-    RustSignalOrSlotGetter, RustSizedType, RustSpecialModuleKind, RustStruct, RustStructKind,
-    RustTraitAssociatedType, RustTraitImpl, RustTraitImplExtraKind, RustTypeCaptionStrategy,
-    RustWrapperTypeKind, UnnamedRustFunction,
+    RustSignalOrSlotGetter, RustSizedType, RustSpecialModuleKind, RustStringConversionImpl,
+    RustStruct, RustStructKind, RustTotalOrderImpl, RustTraitAssociatedType, RustTraitImpl,
+    RustTraitImplExtraKind, RustTypeCaptionStrategy, RustWrapperTypeKind, UnnamedRustFunction,
 };
 use crate::rust_type::{
-    RustClosureToCallbackConversion, RustCommonType, RustFinalType, RustFunctionPointerType,
-    RustPath, RustPointerLikeTypeKind, RustToFfiTypeConversion, RustType,
+    string_arg, unraw_identifier, RustClosureToCallbackConversion, RustCommonType, RustFinalType,
+    RustFunctionPointerType, RustPath, RustPointerLikeTypeKind, RustToFfiTypeConversion, RustType,
 };
+use crate::user_hooks;
 use itertools::Itertools;
-use log::{debug, trace};
+use log::{debug, trace, warn};
 use ritual_common::errors::{bail, err_msg, format_err, print_trace, Result};
 use ritual_common::string_utils::CaseOperations;
 use ritual_common::utils::MapIfOk;
+use std::cell::RefCell;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::iter::Iterator;
 use std::ops::Deref;
+use std::path::Path;
 
 pub fn qt_core_path(crate_name: &str) -> RustPath {
     if crate_name.starts_with("moqt_") {
@@ -45,18 +55,74 @@ pub fn qt_core_path(crate_name: &str) -> RustPath {
     }
 }
 
-/// Adds "_" to a string if it is a reserved word in Rust
+/// Rust keywords, including the ones reserved for future use
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+/// Keywords that can't be used as raw identifiers
+const NON_RAW_KEYWORDS: &[&str] = &["crate", "self", "Self", "super"];
+
+/// Names of the Rust prelude items that shouldn't be shadowed by generated types
+const RUST_PRELUDE_NAMES: &[&str] = &[
+    "AsMut",
+    "AsRef",
+    "Box",
+    "Clone",
+    "Copy",
+    "Default",
+    "DoubleEndedIterator",
+    "Drop",
+    "Eq",
+    "Err",
+    "ExactSizeIterator",
+    "Extend",
+    "Fn",
+    "FnMut",
+    "FnOnce",
+    "From",
+    "FromIterator",
+    "Into",
+    "IntoIterator",
+    "Iterator",
+    "None",
+    "Ok",
+    "Option",
+    "Ord",
+    "PartialEq",
+    "PartialOrd",
+    "Result",
+    "Send",
+    "Sized",
+    "Some",
+    "String",
+    "Sync",
+    "ToOwned",
+    "ToString",
+    "TryFrom",
+    "TryInto",
+    "Unpin",
+    "Vec",
+];
+
+/// Makes `name` usable as a Rust identifier. Keywords are turned into
+/// raw identifiers (`r#type`) if possible. Module names are also used as
+/// file names, so "_" is added to them instead (`type_`).
 fn sanitize_rust_identifier(name: &str, is_module: bool) -> String {
-    match name {
-        "abstract" | "alignof" | "as" | "become" | "box" | "break" | "const" | "continue"
-        | "crate" | "do" | "else" | "enum" | "extern" | "false" | "final" | "fn" | "for" | "if"
-        | "impl" | "in" | "let" | "loop" | "macro" | "match" | "mod" | "move" | "mut"
-        | "offsetof" | "override" | "priv" | "proc" | "pub" | "pure" | "ref" | "return"
-        | "Self" | "self" | "sizeof" | "static" | "struct" | "super" | "trait" | "true"
-        | "type" | "typeof" | "unsafe" | "unsized" | "use" | "virtual" | "where" | "while"
-        | "yield" => format!("{}_", name),
-        "lib" | "main" if is_module => format!("{}_", name),
-        _ => name.to_string(),
+    if RUST_KEYWORDS.contains(&name) {
+        if is_module || NON_RAW_KEYWORDS.contains(&name) {
+            format!("{}_", name)
+        } else {
+            format!("r#{}", name)
+        }
+    } else if is_module && (name == "lib" || name == "main") {
+        format!("{}_", name)
+    } else {
+        name.to_string()
     }
 }
 
@@ -64,8 +130,12 @@ fn sanitize_rust_identifier(name: &str, is_module: bool) -> String {
 fn sanitize_rust_identifier_test() {
     assert_eq!(&sanitize_rust_identifier("good", false), "good");
     assert_eq!(&sanitize_rust_identifier("Self", false), "Self_");
-    assert_eq!(&sanitize_rust_identifier("mod", false), "mod_");
+    assert_eq!(&sanitize_rust_identifier("self", true), "self_");
+    assert_eq!(&sanitize_rust_identifier("mod", false), "r#mod");
     assert_eq!(&sanitize_rust_identifier("mod", true), "mod_");
+    assert_eq!(&sanitize_rust_identifier("try", false), "r#try");
+    assert_eq!(&sanitize_rust_identifier("async", true), "async_");
+    assert_eq!(&sanitize_rust_identifier("sizeof", false), "sizeof");
     assert_eq!(&sanitize_rust_identifier("main", false), "main");
     assert_eq!(&sanitize_rust_identifier("main", true), "main_");
     assert_eq!(&sanitize_rust_identifier("lib", false), "lib");
@@ -89,6 +159,10 @@ enum ReturnTypeConstraint {
     Usize,
     #[allow(dead_code)]
     Unit,
+    /// C++ reference returned as a Rust reference bound to `self`.
+    Reference {
+        is_const: bool,
+    },
     Any,
 }
 
@@ -381,7 +455,56 @@ impl TraitImplInfo {
         })
     }
 
+    /// `T& operator[](...)` and `const T& operator[](...) const` are mapped
+    /// to `IndexMut` and `Index`. Subscript operators returning values
+    /// (e.g. proxy objects) are kept as regular methods.
+    fn subscript(function: &CppFunction) -> Option<TraitImplInfo> {
+        let member = function.member.as_ref()?;
+        let returns_reference = matches!(
+            function.return_type,
+            CppType::PointerLike {
+                kind: CppPointerLikeTypeKind::Reference,
+                ..
+            }
+        );
+        if member.is_static || function.arguments.len() != 1 || !returns_reference {
+            return None;
+        }
+        let info = if member.is_const {
+            TraitImplInfo {
+                trait_path: "std::ops::Index",
+                function_name: "index",
+                is_unsafe: false,
+                is_inherent: false,
+                self_arg_kind: RustFunctionSelfArgKind::ConstRef,
+                has_output_associated_type: true,
+                trait_arg_is_second_arg_type: true,
+                second_arg_is_reference: false,
+                return_type_constraint: ReturnTypeConstraint::Reference { is_const: true },
+                target_is_reference: false,
+            }
+        } else {
+            TraitImplInfo {
+                trait_path: "std::ops::IndexMut",
+                function_name: "index_mut",
+                is_unsafe: false,
+                is_inherent: false,
+                self_arg_kind: RustFunctionSelfArgKind::MutRef,
+                // `Output` is taken from the `Index` implementation
+                has_output_associated_type: false,
+                trait_arg_is_second_arg_type: true,
+                second_arg_is_reference: false,
+                return_type_constraint: ReturnTypeConstraint::Reference { is_const: false },
+                target_is_reference: false,
+            }
+        };
+        Some(info)
+    }
+
     fn new(function: &CppFunction) -> Option<TraitImplInfo> {
+        if function.operator == Some(CppOperator::Subscript) {
+            return Self::subscript(function);
+        }
         if let Some(operator) = &function.operator {
             return Self::from_operator(operator);
         }
@@ -503,6 +626,31 @@ impl TraitImplInfo {
         }
         None
     }
+
+    /// Checks if this is a binary operator trait (e.g. `Mul`) that can also be
+    /// implemented for the type of the first argument of a free operator
+    /// when only its second argument has a type of the current crate.
+    fn is_reflectable(&self) -> bool {
+        self.self_arg_kind == RustFunctionSelfArgKind::Value
+            && self.trait_arg_is_second_arg_type
+            && self.has_output_associated_type
+            && !self.second_arg_is_reference
+            && matches!(self.return_type_constraint, ReturnTypeConstraint::Any)
+    }
+}
+
+/// Checks if `rust_type` is a type declared in `crate_name` or a reference to it.
+/// References are fundamental types, so they don't affect the orphan rule.
+fn is_local_type(rust_type: &RustType, crate_name: &str) -> bool {
+    match rust_type {
+        RustType::Common(RustCommonType { path, .. }) => path.crate_name() == crate_name,
+        RustType::PointerLike {
+            kind: RustPointerLikeTypeKind::Reference { .. },
+            target,
+            ..
+        } => is_local_type(target, crate_name),
+        _ => false,
+    }
 }
 
 #[derive(Debug)]
@@ -547,6 +695,12 @@ fn check_trait_impl_uniqueness(
 struct State<'b, 'a> {
     data: &'b mut ProcessorData<'a>,
     special_module_paths: HashMap<RustSpecialModuleKind, RustPath>,
+    /// Rename rules applied by `generate_rust_path`
+    applied_rename_rules: RefCell<Vec<AppliedRenameRule>>,
+    /// Namespaces that don't have their own Rust modules
+    namespaces_without_module: HashSet<CppPath>,
+    /// Modules of top level items (only for `ModuleStrategy::Headers`)
+    header_module_paths: HashMap<CppPath, RustPath>,
 }
 
 impl State<'_, '_> {
@@ -644,6 +798,13 @@ impl State<'_, '_> {
                 let name = if *is_signed { "isize" } else { "usize" };
                 RustType::Primitive(name.into())
             }
+            CppType::Class(path) if self.data.config.type_mapping(path).is_some() => {
+                let mapping = self.data.config.type_mapping(path).unwrap();
+                RustType::Common(RustCommonType {
+                    path: mapping.rust_path.clone(),
+                    generic_arguments: None,
+                })
+            }
             CppType::Enum { path } | CppType::Class(path) => {
                 let rust_item = self.find_wrapper_type(path)?;
                 let path = rust_item
@@ -678,6 +839,16 @@ impl State<'_, '_> {
                     generic_arguments: Some(vec![pointer]),
                 })
             }
+            CppType::Array {
+                element_type, size, ..
+            } => {
+                let size =
+                    size.ok_or_else(|| err_msg("arrays of unknown size are not supported"))?;
+                RustType::Array {
+                    element_type: Box::new(self.ffi_type_to_rust_ffi_type(element_type)?),
+                    size,
+                }
+            }
             CppType::TemplateParameter { .. } => bail!("invalid cpp type"),
         };
 
@@ -688,16 +859,38 @@ impl State<'_, '_> {
         qt_core_path(self.data.config.crate_properties().name())
     }
 
-    fn create_qflags(&self, arg: &RustPath) -> RustType {
-        let path = self.qt_core_path().join("QFlags");
+    /// Returns the path of the Rust type of flags template `template`.
+    /// It's either provided by `Config::map_flags_template` or generated
+    /// in the crate declaring the template.
+    fn flags_type_path(&self, template: &CppPath) -> Result<RustPath> {
+        if let Some(path) = self.data.config.flags_type_mapping(template) {
+            return Ok(path.clone());
+        }
+        self.data
+            .db
+            .all_rust_items()
+            .filter_map(|item| item.item.as_struct_ref())
+            .find(|item| match &item.kind {
+                RustStructKind::FlagsType(flags_type) => &flags_type.cpp_path == template,
+                _ => false,
+            })
+            .map(|item| item.path.clone())
+            .ok_or_else(|| {
+                format_err!(
+                    "no Rust type for flags template {}",
+                    template.to_cpp_pseudo_code()
+                )
+            })
+    }
 
-        RustType::Common(RustCommonType {
-            path,
+    fn create_flags_type(&self, template: &CppPath, arg: &RustPath) -> Result<RustType> {
+        Ok(RustType::Common(RustCommonType {
+            path: self.flags_type_path(template)?,
             generic_arguments: Some(vec![RustType::Common(RustCommonType {
                 path: arg.clone(),
                 generic_arguments: None,
             })]),
-        })
+        }))
     }
 
     fn is_type_deletable(&self, ffi_type: &CppType, checks: &CppChecks) -> Result<bool> {
@@ -767,8 +960,39 @@ impl State<'_, '_> {
             } else {
                 false
             };
+            let mapping = if let CppType::Class(path) = &target {
+                self.data.config.type_mapping(path)
+            } else {
+                None
+            };
+            let is_string_type = matches!(
+                &target,
+                CppType::Class(path) if self.data.config.string_type(path).is_some()
+            );
+            // strings passed by value or by const reference
+            let is_string_argument = is_string_type
+                && matches!(argument_meaning, CppFfiArgumentMeaning::Argument(_))
+                && match cpp_ffi_type.conversion() {
+                    CppToFfiTypeConversion::ValueToPointer { .. } => true,
+                    CppToFfiTypeConversion::ReferenceToPointer => {
+                        rust_ffi_type.is_const_pointer_like()?
+                    }
+                    _ => false,
+                };
 
-            if let CppToFfiTypeConversion::ValueToPointer { .. } = cpp_ffi_type.conversion() {
+            if is_string_argument {
+                api_to_ffi_conversion = RustToFfiTypeConversion::StringArgToPtr;
+            } else if let CppToFfiTypeConversion::ValueToPointer { .. } = cpp_ffi_type.conversion()
+            {
+                if let Some(mapping) = mapping.filter(|m| m.pass_by == PassBy::ByPointer) {
+                    if argument_meaning == &CppFfiArgumentMeaning::ReturnValue {
+                        // there is no wrapper that could own the returned object
+                        bail!(
+                            "type mapped to {} can't be returned by value",
+                            mapping.rust_path.full_name(None)
+                        );
+                    }
+                }
                 if argument_meaning == &CppFfiArgumentMeaning::ReturnValue {
                     match allocation_place {
                         ReturnValueAllocationPlace::Stack => {
@@ -795,6 +1019,9 @@ impl State<'_, '_> {
                             bail!("NotApplicable conflicts with ValueToPointer");
                         }
                     }
+                } else if mapping.map_or(false, |m| m.pass_by == PassBy::ByValue) {
+                    // hand-written types are passed by value on Rust side as well
+                    api_to_ffi_conversion = RustToFfiTypeConversion::ValueToPtr;
                 } else {
                     // argument passed by value is represented as a reference on Rust side
                     api_to_ffi_conversion = RustToFfiTypeConversion::ImplCastInto(Box::new(
@@ -802,7 +1029,11 @@ impl State<'_, '_> {
                     ));
                 }
             } else if argument_meaning == &CppFfiArgumentMeaning::This {
-                api_to_ffi_conversion = RustToFfiTypeConversion::RefToPtr { lifetime: None };
+                api_to_ffi_conversion = if self.data.config.borrow_checking() {
+                    RustToFfiTypeConversion::BorrowToPtr { lifetime: None }
+                } else {
+                    RustToFfiTypeConversion::RefToPtr { lifetime: None }
+                };
             } else if argument_meaning == &CppFfiArgumentMeaning::ReturnValue {
                 if target.is_class() {
                     api_to_ffi_conversion =
@@ -813,11 +1044,16 @@ impl State<'_, '_> {
                         } else if inherits_qobject {
                             RustToFfiTypeConversion::QPtrToPtr
                         } else {
-                            RustToFfiTypeConversion::UtilsPtrToPtr {}
+                            // C++ pointers may be null
+                            RustToFfiTypeConversion::OptionUtilsRefToPtr {}
                         };
                 } else {
                     api_to_ffi_conversion = RustToFfiTypeConversion::None;
                 }
+            } else if self.data.config.borrow_checking()
+                && cpp_ffi_type.conversion() == &CppToFfiTypeConversion::ReferenceToPointer
+            {
+                api_to_ffi_conversion = RustToFfiTypeConversion::BorrowToPtr { lifetime: None };
             } else {
                 // argument
                 if target.is_class() {
@@ -829,6 +1065,8 @@ impl State<'_, '_> {
                                 RustToFfiTypeConversion::UtilsRefToPtr {},
                             ))
                         } else {
+                            // pointers and rvalue references (the object is moved from,
+                            // so it must be mutable)
                             RustToFfiTypeConversion::ImplCastInto(Box::new(
                                 RustToFfiTypeConversion::UtilsPtrToPtr {},
                             ))
@@ -839,7 +1077,7 @@ impl State<'_, '_> {
             }
         }
         if cpp_ffi_type.conversion() == &CppToFfiTypeConversion::QFlagsToInt {
-            let qflags_type = match cpp_ffi_type.original_type() {
+            let flags_type = match cpp_ffi_type.original_type() {
                 CppType::PointerLike {
                     kind,
                     is_const,
@@ -858,16 +1096,16 @@ impl State<'_, '_> {
                 }
                 a => a,
             };
-            let enum_type = if let CppType::Class(path) = qflags_type {
+            let (flags_path, enum_type) = if let CppType::Class(path) = flags_type {
                 let template_arguments = path
                     .last()
                     .template_arguments
                     .as_ref()
-                    .ok_or_else(|| err_msg("expected template arguments for QFlags"))?;
+                    .ok_or_else(|| err_msg("expected template arguments for flags type"))?;
                 if template_arguments.len() != 1 {
-                    bail!("QFlags type must have exactly 1 template argument");
+                    bail!("flags type must have exactly 1 template argument");
                 }
-                &template_arguments[0]
+                (path, &template_arguments[0])
             } else {
                 bail!("invalid original type for QFlagsToUInt: {:?}", cpp_ffi_type);
             };
@@ -875,7 +1113,7 @@ impl State<'_, '_> {
             let enum_path = if let CppType::Enum { path } = &enum_type {
                 path
             } else {
-                bail!("invalid QFlags argument type: {:?}", enum_type);
+                bail!("invalid flags type argument: {:?}", enum_type);
             };
 
             let rust_enum_type = self.find_wrapper_type(enum_path)?;
@@ -887,7 +1125,8 @@ impl State<'_, '_> {
             })?;
 
             api_to_ffi_conversion = RustToFfiTypeConversion::QFlagsToUInt {
-                api_type: self.create_qflags(rust_enum_path),
+                api_type: self
+                    .create_flags_type(&flags_template_path(flags_path), rust_enum_path)?,
             };
         };
 
@@ -975,6 +1214,12 @@ impl State<'_, '_> {
             if let RustToFfiTypeConversion::ImplCastInto(conversion) = other_type1.conversion() {
                 other_type1 =
                     RustFinalType::new(other_type1.ffi_type().clone(), (**conversion).clone())?;
+            } else if other_type1.conversion() == &RustToFfiTypeConversion::StringArgToPtr {
+                // `impl Trait` can't be used as a trait argument
+                other_type1 = RustFinalType::new(
+                    other_type1.ffi_type().clone(),
+                    RustToFfiTypeConversion::UtilsRefToPtr {},
+                )?;
             }
             trait_args = Some(vec![other_type1.api_type().clone()]);
             other_type = Some(other_type1);
@@ -1001,9 +1246,20 @@ impl State<'_, '_> {
         };
 
         let associated_types = if operator_info.has_output_associated_type {
+            let value = if let ReturnTypeConstraint::Reference { .. } =
+                operator_info.return_type_constraint
+            {
+                // the trait function returns `&Self::Output`
+                unnamed_function
+                    .return_type
+                    .ffi_type()
+                    .pointer_like_to_target()?
+            } else {
+                unnamed_function.return_type.api_type().clone()
+            };
             let output = RustTraitAssociatedType {
                 name: "Output".into(),
-                value: unnamed_function.return_type.api_type().clone(),
+                value,
             };
             vec![output]
         } else {
@@ -1054,6 +1310,16 @@ impl State<'_, '_> {
                     bail!("return type is not bool");
                 }
             }
+            ReturnTypeConstraint::Reference { is_const } => {
+                let ffi_return_type = function.return_type.ffi_type().clone();
+                if ffi_return_type.is_const_pointer_like()? != is_const {
+                    bail!("constness of the returned reference doesn't match");
+                }
+                function.return_type = RustFinalType::new(
+                    ffi_return_type,
+                    RustToFfiTypeConversion::RefToPtr { lifetime: None },
+                )?;
+            }
         }
 
         Ok(RustTraitImpl {
@@ -1066,6 +1332,163 @@ impl State<'_, '_> {
         })
     }
 
+    /// Generates a trait impl for a free binary operator whose first argument
+    /// doesn't have a type of the current crate, e.g. `impl Mul<&QPoint> for f64`
+    /// for `QPoint operator*(double, const QPoint&)`. The impl is skipped with
+    /// a warning if it would violate the orphan rule.
+    fn process_operator_as_reflected_trait_impl(
+        unnamed_function: UnnamedRustFunction,
+        operator_info: TraitImplInfo,
+        crate_name: &str,
+        trait_types: &[TraitTypes],
+    ) -> Result<RustTraitImpl> {
+        if unnamed_function.arguments.len() != 2 {
+            bail!("reflected operator must have two arguments");
+        }
+        let other_ffi_type = unnamed_function.arguments[1]
+            .argument_type
+            .ffi_type()
+            .clone();
+        if !other_ffi_type.is_const_pointer_like()? {
+            bail!("second argument is not a const reference");
+        }
+        let other_type = RustFinalType::new(
+            other_ffi_type,
+            RustToFfiTypeConversion::RefToPtr { lifetime: None },
+        )?;
+
+        let self_arg_type = &unnamed_function.arguments[0].argument_type;
+        let self_type = match self_arg_type.ffi_type().pointer_like_to_target() {
+            Ok(RustType::Common(_)) => {
+                if !self_arg_type.ffi_type().is_const_pointer_like()? {
+                    bail!("first argument is not a const reference");
+                }
+                RustFinalType::new(
+                    self_arg_type.ffi_type().clone(),
+                    RustToFfiTypeConversion::RefToPtr { lifetime: None },
+                )?
+            }
+            Ok(_) => bail!("unsupported first argument type: {:?}", self_arg_type),
+            Err(_) => {
+                if self_arg_type.conversion() != &RustToFfiTypeConversion::None {
+                    bail!("unsupported first argument type: {:?}", self_arg_type);
+                }
+                self_arg_type.clone()
+            }
+        };
+        let target_type = self_type.api_type().clone();
+
+        let parent_path = if let RustType::Common(RustCommonType { path, .. }) =
+            other_type.ffi_type().pointer_like_to_target()?
+        {
+            if path.crate_name() != crate_name {
+                // implementing a foreign trait for foreign types violates the orphan rule
+                warn!(
+                    "skipping {} impl: neither {:?} nor {:?} is declared in the current crate",
+                    operator_info.trait_path,
+                    target_type,
+                    other_type.api_type()
+                );
+                bail!("second argument type is outside current crate");
+            }
+            path.parent()?
+        } else {
+            bail!("second argument type is not Common");
+        };
+
+        let trait_path = RustPath::from_good_str(operator_info.trait_path);
+        let trait_type = RustCommonType {
+            path: trait_path.clone(),
+            generic_arguments: Some(vec![other_type.api_type().clone()]),
+        };
+        check_trait_impl_uniqueness(trait_types, &target_type, &trait_type)?;
+
+        let output = RustTraitAssociatedType {
+            name: "Output".into(),
+            value: unnamed_function.return_type.api_type().clone(),
+        };
+
+        let mut function = unnamed_function.with_path(trait_path.join(operator_info.function_name));
+        function.is_unsafe = operator_info.is_unsafe;
+        function.arguments[0].argument_type = self_type;
+        function.arguments[0].name = "self".to_string();
+        function.arguments[1].argument_type = other_type;
+
+        Ok(RustTraitImpl {
+            target_type,
+            parent_path,
+            trait_type,
+            associated_types: vec![output],
+            functions: vec![function],
+            extra_kind: RustTraitImplExtraKind::Normal,
+        })
+    }
+
+    /// Generates `impl From<&A> for B` for an implicit conversion operator
+    /// `A::operator B() const`. The named `to_b()` method is still generated.
+    fn process_conversion_operator_as_from_impl(
+        unnamed_function: UnnamedRustFunction,
+        crate_name: &str,
+    ) -> Result<RustTraitImpl> {
+        let self_ffi_type = unnamed_function
+            .arguments
+            .get(0)
+            .ok_or_else(|| err_msg("no arguments"))?
+            .argument_type
+            .ffi_type()
+            .clone();
+        if !self_ffi_type.is_const_pointer_like()? {
+            bail!("conversion operator is not const");
+        }
+        let self_value_type = self_ffi_type.pointer_like_to_target()?;
+
+        let return_type = &unnamed_function.return_type;
+        match return_type.conversion() {
+            RustToFfiTypeConversion::None => {
+                if let RustType::PointerLike { .. } = return_type.api_type() {
+                    bail!("conversion to a pointer type");
+                }
+            }
+            RustToFfiTypeConversion::CppBoxToPtr
+            | RustToFfiTypeConversion::QFlagsToUInt { .. }
+            | RustToFfiTypeConversion::AsCast { .. } => {}
+            _ => bail!("unsupported return type conversion: {:?}", return_type),
+        }
+
+        let parent_path = if let RustType::Common(RustCommonType { path, .. }) = &self_value_type {
+            if path.crate_name() != crate_name {
+                bail!("self type is outside current crate");
+            }
+            path.parent()?
+        } else {
+            bail!("self type is not Common");
+        };
+
+        let trait_path = RustPath::from_good_str("std::convert::From");
+        let trait_type = RustCommonType {
+            path: trait_path.clone(),
+            generic_arguments: Some(vec![RustType::new_reference(true, self_value_type)]),
+        };
+        let target_type = return_type.api_type().clone();
+
+        let mut function = unnamed_function.with_path(trait_path.join("from"));
+        function.is_unsafe = false;
+        function.arguments[0].argument_type = RustFinalType::new(
+            self_ffi_type,
+            RustToFfiTypeConversion::RefToPtr { lifetime: None },
+        )?;
+        function.arguments[0].name = "value".to_string();
+
+        Ok(RustTraitImpl {
+            target_type,
+            parent_path,
+            trait_type,
+            associated_types: Vec::new(),
+            functions: vec![function],
+            extra_kind: RustTraitImplExtraKind::Normal,
+        })
+    }
+
     fn process_destructor(
         unnamed_function: UnnamedRustFunction,
         allocation_place: ReturnValueAllocationPlace,
@@ -1119,6 +1542,70 @@ impl State<'_, '_> {
         })
     }
 
+    /// Generates a `Clone` implementation based on a copy constructor.
+    /// Heap-allocated types implement `cpp_core::CppClone`, making `CppBox<T>` cloneable,
+    /// and stack-allocated types implement `Clone` directly.
+    /// The `new_copy` function is still generated.
+    fn process_copy_constructor(
+        mut unnamed_function: UnnamedRustFunction,
+        allocation_place: ReturnValueAllocationPlace,
+        crate_name: &str,
+        trait_types: &[TraitTypes],
+    ) -> Result<RustTraitImpl> {
+        if unnamed_function.arguments.len() != 1 {
+            bail!("copy constructor must have one argument");
+        }
+        let arg = &mut unnamed_function.arguments[0];
+        arg.argument_type = RustFinalType::new(
+            arg.argument_type.ffi_type().clone(),
+            RustToFfiTypeConversion::RefToPtr { lifetime: None },
+        )?;
+        arg.name = "self".to_string();
+        let target_type = arg.argument_type.api_type().pointer_like_to_target()?;
+
+        let parent_path = if let RustType::Common(RustCommonType { path, .. }) = &target_type {
+            if path.crate_name() != crate_name {
+                bail!("self type is outside current crate");
+            }
+            path.parent()?
+        } else {
+            bail!("can't get parent for target type: {:?}", target_type);
+        };
+
+        let trait_path;
+        let is_unsafe;
+        match allocation_place {
+            ReturnValueAllocationPlace::Stack => {
+                trait_path = RustPath::from_good_str("std::clone::Clone");
+                is_unsafe = false;
+            }
+            ReturnValueAllocationPlace::Heap => {
+                trait_path = RustPath::from_good_str("cpp_core::CppClone");
+                is_unsafe = true;
+            }
+            ReturnValueAllocationPlace::NotApplicable => {
+                bail!("invalid allocation_place for copy constructor");
+            }
+        }
+        let trait_type = RustCommonType {
+            path: trait_path.clone(),
+            generic_arguments: None,
+        };
+        check_trait_impl_uniqueness(trait_types, &target_type, &trait_type)?;
+
+        let mut function = unnamed_function.with_path(trait_path.join("clone"));
+        function.is_unsafe = is_unsafe;
+
+        Ok(RustTraitImpl {
+            target_type,
+            parent_path,
+            trait_type,
+            associated_types: Vec::new(),
+            functions: vec![function],
+            extra_kind: RustTraitImplExtraKind::Normal,
+        })
+    }
+
     fn process_cast(
         mut unnamed_function: UnnamedRustFunction,
         cast: &CppCast,
@@ -1197,6 +1684,15 @@ impl State<'_, '_> {
                 Ok(())
             };
 
+            // `&mut T` to `*mut T`
+            let make_type_mut_ref = |type1: &mut RustFinalType| -> Result<()> {
+                *type1 = RustFinalType::new(
+                    type1.ffi_type().clone(),
+                    RustToFfiTypeConversion::BorrowToPtr { lifetime: None },
+                )?;
+                Ok(())
+            };
+
             let deref_trait_path = RustPath::from_good_str("std::ops::Deref");
             let deref_trait_type = RustCommonType {
                 path: deref_trait_path.clone(),
@@ -1204,15 +1700,16 @@ impl State<'_, '_> {
             };
             match check_trait_impl_uniqueness(trait_types, &target_type, &deref_trait_type) {
                 Ok(_) => {
-                    let mut deref_function =
-                        fixed_function_mut.with_path(deref_trait_path.join("deref"));
+                    let mut deref_function = fixed_function_mut
+                        .clone()
+                        .with_path(deref_trait_path.join("deref"));
                     deref_function.is_unsafe = false;
                     make_type_ref(&mut deref_function.return_type)?;
                     make_type_ref(&mut deref_function.arguments[0].argument_type)?;
                     deref_function.arguments[0].name = "self".into();
                     results.push(RustTraitImpl {
                         target_type: target_type.clone(),
-                        parent_path,
+                        parent_path: parent_path.clone(),
                         trait_type: deref_trait_type,
                         associated_types: vec![RustTraitAssociatedType {
                             name: "Target".to_string(),
@@ -1221,6 +1718,37 @@ impl State<'_, '_> {
                         functions: vec![deref_function],
                         extra_kind: RustTraitImplExtraKind::Deref,
                     });
+
+                    let deref_mut_trait_path = RustPath::from_good_str("std::ops::DerefMut");
+                    let deref_mut_trait_type = RustCommonType {
+                        path: deref_mut_trait_path.clone(),
+                        generic_arguments: None,
+                    };
+                    match check_trait_impl_uniqueness(
+                        trait_types,
+                        &target_type,
+                        &deref_mut_trait_type,
+                    ) {
+                        Ok(_) => {
+                            let mut deref_mut_function = fixed_function_mut
+                                .with_path(deref_mut_trait_path.join("deref_mut"));
+                            deref_mut_function.is_unsafe = false;
+                            make_type_mut_ref(&mut deref_mut_function.return_type)?;
+                            make_type_mut_ref(&mut deref_mut_function.arguments[0].argument_type)?;
+                            deref_mut_function.arguments[0].name = "self".into();
+                            results.push(RustTraitImpl {
+                                target_type: target_type.clone(),
+                                parent_path,
+                                trait_type: deref_mut_trait_type,
+                                associated_types: Vec::new(),
+                                functions: vec![deref_mut_function],
+                                extra_kind: RustTraitImplExtraKind::DerefMut,
+                            });
+                        }
+                        Err(err) => {
+                            debug!("not implementing DerefMut: {}", err);
+                        }
+                    }
                 }
                 Err(err) => {
                     debug!("not implementing Deref: {}", err);
@@ -1234,6 +1762,7 @@ impl State<'_, '_> {
     fn convert_callbacks_to_closure(
         &self,
         id: &ItemId,
+        ffi_function: &CppFfiFunction,
         function: &mut UnnamedRustFunction,
         checks: &CppChecks,
     ) -> Result<()> {
@@ -1241,16 +1770,61 @@ impl State<'_, '_> {
             return Ok(());
         };
 
-        let wrapper = self
-            .data
-            .db
-            .source_ffi_item(id)?
-            .ok_or_else(|| err_msg("source ffi item not found"))?
-            .item
-            .as_slot_wrapper_ref()
-            .ok_or_else(|| err_msg("invalid source ffi item type"))?;
+        let mut guard_returned = false;
+        let (arguments, return_type) = if let CppFfiFunctionKind::ClosureCallback { callback } =
+            &ffi_function.kind
+        {
+            guard_returned = callback.unregister.is_some();
+            let arguments = callback
+                .closure_arguments()
+                .into_iter()
+                .map_if_ok(|arg| CppFfiType::new(arg, CppToFfiTypeConversion::NoChange))?;
+            let return_type = &*callback.function_type.return_type;
+            let return_type = if return_type.is_void() {
+                None
+            } else {
+                Some(CppFfiType::new(
+                    return_type.clone(),
+                    CppToFfiTypeConversion::NoChange,
+                )?)
+            };
+            (arguments, return_type)
+        } else {
+            let source_ffi_item = self
+                .data
+                .db
+                .source_ffi_item(id)?
+                .ok_or_else(|| err_msg("source ffi item not found"))?
+                .item;
+
+            match source_ffi_item {
+                CppFfiItem::QtSlotWrapper(wrapper) => (wrapper.arguments.clone(), None),
+                CppFfiItem::SubclassShim(shim) => {
+                    let setter = self
+                        .data
+                        .db
+                        .source_cpp_item(id)?
+                        .ok_or_else(|| err_msg("source cpp item not found"))?;
+                    let setter_name = &setter
+                        .item
+                        .as_function_ref()
+                        .ok_or_else(|| err_msg("invalid source cpp item type"))?
+                        .path
+                        .last()
+                        .name;
+                    let function = shim.function_by_setter_name(setter_name).ok_or_else(|| {
+                        format_err!("unknown subclass shim setter: {}", setter_name)
+                    })?;
+                    (
+                        function.arguments.clone(),
+                        Some(function.return_type.clone()),
+                    )
+                }
+                _ => bail!("invalid source ffi item type"),
+            }
+        };
 
-        let closure_arguments = wrapper.arguments.iter().map_if_ok(|arg| {
+        let closure_arguments = arguments.iter().map_if_ok(|arg| {
             self.rust_final_type(
                 arg,
                 // closure argument should be handled in the same way
@@ -1260,13 +1834,24 @@ impl State<'_, '_> {
                 Some(&checks),
             )
         })?;
-        let closure_return_type = self.rust_final_type(
-            &CppFfiType::void(),
-            // TODO: not sure about the meaning.
-            &CppFfiArgumentMeaning::Argument(0),
-            ReturnValueAllocationPlace::NotApplicable,
-            Some(&checks),
-        )?;
+        let closure_return_type = match return_type {
+            // the value returned by the closure is passed to C++,
+            // so it's handled in the same way as a returned value
+            // of a heap-allocating function
+            Some(return_type) => self.rust_final_type(
+                &return_type,
+                &CppFfiArgumentMeaning::ReturnValue,
+                ReturnValueAllocationPlace::Heap,
+                Some(&checks),
+            )?,
+            None => self.rust_final_type(
+                &CppFfiType::void(),
+                // TODO: not sure about the meaning.
+                &CppFfiArgumentMeaning::Argument(0),
+                ReturnValueAllocationPlace::NotApplicable,
+                Some(&checks),
+            )?,
+        };
 
         function.arguments.drain(function.arguments.len() - 2..);
         let arg = function
@@ -1280,13 +1865,77 @@ impl State<'_, '_> {
                 closure_return_type,
             })),
         )?;
+        if guard_returned {
+            function.return_type = RustFinalType::new(
+                function.return_type.ffi_type().clone(),
+                RustToFfiTypeConversion::CallbackGuardToPtr,
+            )?;
+        }
 
         Ok(())
     }
 
-    /// Converts one function to a `RustSingleMethod`.
-    fn process_rust_function(
-        &self,
+    /// Checks if the source C++ function of FFI function `id`
+    /// was marked with `Config::set_non_null`.
+    fn is_non_null_function(&self, id: &ItemId) -> Result<bool> {
+        let cpp_item = self.data.db.source_cpp_item(id)?;
+        let cpp_function = match cpp_item
+            .as_ref()
+            .and_then(|item| item.item.as_function_ref())
+        {
+            Some(function) => function,
+            None => return Ok(false),
+        };
+        Ok(self
+            .data
+            .config
+            .non_null_functions()
+            .contains(&cpp_function.path))
+    }
+
+    /// Converts the type of a pointer returned by a function that transfers
+    /// ownership of the object to the caller. The returned box deletes
+    /// the object when it's dropped.
+    fn owned_return_type(
+        &self,
+        return_type: &RustFinalType,
+        cpp_return_type: &CppFfiType,
+        is_non_null: bool,
+        checks: &CppChecks,
+    ) -> Result<RustFinalType> {
+        let conversion = match return_type.conversion() {
+            RustToFfiTypeConversion::QPtrToPtr => RustToFfiTypeConversion::QBoxToPtr,
+            RustToFfiTypeConversion::OptionUtilsRefToPtr {} => {
+                if !self.is_type_deletable(cpp_return_type.ffi_type(), checks)? {
+                    bail!("returned type is not deletable");
+                }
+                if is_non_null {
+                    RustToFfiTypeConversion::CppBoxToPtr
+                } else {
+                    RustToFfiTypeConversion::OptionCppBoxToPtr
+                }
+            }
+            _ => bail!("function doesn't return a pointer to a class"),
+        };
+        RustFinalType::new(return_type.ffi_type().clone(), conversion)
+    }
+
+    fn is_unbound_return_lifetime_function(&self, id: &ItemId) -> Result<bool> {
+        let cpp_item = self.data.db.source_cpp_item(id)?;
+        Ok(cpp_item
+            .as_ref()
+            .and_then(|item| item.item.as_function_ref())
+            .map_or(false, |function| {
+                self.data
+                    .config
+                    .unbound_return_lifetime_functions()
+                    .contains(&function.path)
+            }))
+    }
+
+    /// Converts one function to a `RustSingleMethod`.
+    fn process_rust_function(
+        &self,
         item: DbItem<&CppFfiFunction>,
         checks: &CppChecks,
         trait_types: &[TraitTypes],
@@ -1298,6 +1947,20 @@ impl State<'_, '_> {
             rust_ffi_function,
         ))];
 
+        match &function.kind {
+            CppFfiFunctionKind::StreamOutput => {
+                let item = self.process_stream_output(function, ffi_function_path)?;
+                results.push(ProcessedFfiItem::Item(item));
+                return Ok(results);
+            }
+            CppFfiFunctionKind::StringFromUtf8 { .. } | CppFfiFunctionKind::StringToUtf8 { .. } => {
+                let item = self.process_string_conversion(function, ffi_function_path)?;
+                results.push(ProcessedFfiItem::Item(item));
+                return Ok(results);
+            }
+            _ => {}
+        }
+
         let mut arguments = Vec::new();
         for (arg_index, arg) in function.arguments.iter().enumerate() {
             if arg.meaning != CppFfiArgumentMeaning::ReturnValue {
@@ -1313,7 +1976,7 @@ impl State<'_, '_> {
                     name: if arg.meaning == CppFfiArgumentMeaning::This {
                         "self".to_string()
                     } else {
-                        sanitize_rust_identifier(&arg.name.to_snake_case(), false)
+                        sanitize_rust_identifier(&self.snake_case_name(&arg.name), false)
                     },
                 });
             }
@@ -1343,6 +2006,57 @@ impl State<'_, '_> {
                 Some(checks),
             )?
         };
+        let is_non_null = self.is_non_null_function(&item.id)?;
+        let owned_function_path = self
+            .data
+            .db
+            .source_cpp_item(&item.id)?
+            .and_then(|item| item.item.as_function_ref())
+            .map(|function| &function.path)
+            .filter(|path| self.data.config.return_ownership(path) == ReturnOwnership::Owned);
+        if let Some(path) = owned_function_path {
+            match self.owned_return_type(&return_type, &function.return_type, is_non_null, checks) {
+                Ok(owned_type) => return_type = owned_type,
+                Err(err) => warn!(
+                    "ignoring return ownership of {}: {}",
+                    path.to_cpp_pseudo_code(),
+                    err
+                ),
+            }
+        }
+        if return_type.conversion().is_option_utils_ref_to_ptr() && is_non_null {
+            return_type = RustFinalType::new(
+                return_type.ffi_type().clone(),
+                RustToFfiTypeConversion::UtilsRefToPtr {},
+            )?;
+        }
+        if self.data.config.borrow_checking()
+            && function.return_type.conversion() == &CppToFfiTypeConversion::ReferenceToPointer
+            && !self.is_unbound_return_lifetime_function(&item.id)?
+        {
+            if let Some(self_arg) = arguments.iter_mut().find(|arg| arg.name == "self") {
+                // the returned reference borrows the object
+                let lifetime = self_arg
+                    .argument_type
+                    .api_type()
+                    .lifetime()
+                    .unwrap_or("l0")
+                    .to_string();
+                self_arg.argument_type = self_arg.argument_type.with_lifetime(lifetime.clone())?;
+                return_type = RustFinalType::new(
+                    return_type.ffi_type().clone(),
+                    RustToFfiTypeConversion::BorrowToPtr {
+                        lifetime: Some(lifetime),
+                    },
+                )?;
+            } else {
+                // lifetime of the referenced object is unknown
+                return_type = RustFinalType::new(
+                    return_type.ffi_type().clone(),
+                    RustToFfiTypeConversion::None,
+                )?;
+            }
+        }
         if return_type.api_type().is_ref() && return_type.api_type().lifetime().is_none() {
             let mut found = false;
             for arg in &arguments {
@@ -1382,10 +2096,10 @@ impl State<'_, '_> {
             is_public: true,
             arguments,
             return_type,
-            kind: RustFunctionKind::FfiWrapper(RustFfiWrapperData { ffi_function_path }),
+            kind: RustFunctionKind::FfiWrapper(RustFfiWrapperData::new(ffi_function_path)),
             is_unsafe: true,
         };
-        self.convert_callbacks_to_closure(&item.id, &mut unnamed_function, checks)?;
+        self.convert_callbacks_to_closure(&item.id, function, &mut unnamed_function, checks)?;
 
         let cpp_item = self
             .data
@@ -1404,6 +2118,21 @@ impl State<'_, '_> {
                 results.push(ProcessedFfiItem::Item(RustItem::TraitImpl(item)));
                 return Ok(results);
             }
+            if cpp_function.is_copy_constructor() {
+                match State::process_copy_constructor(
+                    unnamed_function.clone(),
+                    function.allocation_place,
+                    self.data.db.crate_name(),
+                    trait_types,
+                ) {
+                    Ok(item) => {
+                        results.push(ProcessedFfiItem::Item(RustItem::TraitImpl(item)));
+                    }
+                    Err(err) => {
+                        debug!("failed to convert copy constructor to Clone: {}", err);
+                    }
+                }
+            }
             if let Some(cast) = &cpp_function.cast {
                 let impls = State::process_cast(unnamed_function, cast, trait_types)?;
                 results.extend(
@@ -1416,13 +2145,68 @@ impl State<'_, '_> {
             if cpp_function.operator.as_ref() == Some(&CppOperator::NotEqualTo) {
                 bail!("NotEqualTo is not needed in public API because PartialEq is used");
             }
-            if let Some(operator_info) = TraitImplInfo::new(cpp_function) {
-                match State::process_operator_as_trait_impl(
-                    unnamed_function.clone(),
-                    operator_info,
-                    self.data.db.crate_name(),
-                    trait_types,
-                ) {
+            if let Some(CppOperator::Conversion(_)) = &cpp_function.operator {
+                if !cpp_function.is_explicit() {
+                    match State::process_conversion_operator_as_from_impl(
+                        unnamed_function.clone(),
+                        self.data.db.crate_name(),
+                    ) {
+                        Ok(item) => {
+                            // e.g. `operator int()` and `operator qint32()` produce the same impl
+                            match check_trait_impl_uniqueness(
+                                trait_types,
+                                &item.target_type,
+                                &item.trait_type,
+                            ) {
+                                Ok(()) => {
+                                    results.push(ProcessedFfiItem::Item(RustItem::TraitImpl(item)));
+                                }
+                                Err(err) => {
+                                    warn!(
+                                        "skipping From impl for {}: {}",
+                                        cpp_function.short_text(),
+                                        err
+                                    );
+                                }
+                            }
+                        }
+                        Err(err) => {
+                            debug!("failed to convert conversion operator to From: {}", err);
+                        }
+                    }
+                }
+            }
+            let operator_info = TraitImplInfo::new(cpp_function).filter(|info| {
+                // `IndexMut` can only be implemented together with `Index`
+                info.trait_path != "std::ops::IndexMut" || self.has_const_overload(cpp_function)
+            });
+            if let Some(operator_info) = operator_info {
+                let crate_name = self.data.db.crate_name();
+                // free operators are attached to the type of their first argument if possible
+                let is_reflected = cpp_function.member.is_none()
+                    && operator_info.is_reflectable()
+                    && !unnamed_function.arguments.get(0).map_or(false, |arg| {
+                        arg.argument_type
+                            .ffi_type()
+                            .pointer_like_to_target()
+                            .map_or(false, |t| is_local_type(&t, crate_name))
+                    });
+                let trait_impl = if is_reflected {
+                    State::process_operator_as_reflected_trait_impl(
+                        unnamed_function.clone(),
+                        operator_info,
+                        crate_name,
+                        trait_types,
+                    )
+                } else {
+                    State::process_operator_as_trait_impl(
+                        unnamed_function.clone(),
+                        operator_info,
+                        crate_name,
+                        trait_types,
+                    )
+                };
+                match trait_impl {
                     Ok(item) => {
                         results.push(ProcessedFfiItem::Item(RustItem::TraitImpl(item)));
                         if !operator_info.is_inherent {
@@ -1478,6 +2262,12 @@ impl State<'_, '_> {
             }
         }
 
+        if let CppItem::Function(cpp_function) = cpp_item {
+            if let Some(mode) = self.data.config.out_params(&cpp_function.path) {
+                self.convert_out_arguments(function, &mut unnamed_function, mode)?;
+            }
+        }
+
         let desired_path = self.generate_rust_path(cpp_path, NameType::ApiFunction(item))?;
         results.push(ProcessedFfiItem::Function(FunctionWithDesiredPath {
             function: unnamed_function,
@@ -1486,6 +2276,435 @@ impl State<'_, '_> {
         Ok(results)
     }
 
+    /// Returns true if `ffi_type` is a non-const pointer to a value that can be
+    /// allocated by the Rust wrapper: a number, an enum or
+    /// a stack-allocated class. Character types are excluded because
+    /// pointers to them are usually strings or buffers.
+    fn is_out_argument_type(&self, ffi_type: &CppFfiType) -> bool {
+        if ffi_type.conversion() != &CppToFfiTypeConversion::NoChange {
+            return false;
+        }
+        let target = match ffi_type.original_type() {
+            CppType::PointerLike {
+                kind: CppPointerLikeTypeKind::Pointer,
+                is_const: false,
+                target,
+            } => target,
+            _ => return false,
+        };
+        match &**target {
+            CppType::BuiltInNumeric(numeric) => !matches!(
+                numeric,
+                CppBuiltInNumericType::Char
+                    | CppBuiltInNumericType::SChar
+                    | CppBuiltInNumericType::UChar
+            ),
+            CppType::SpecificNumeric(numeric) => numeric.bits > 8,
+            CppType::PointerSizedInteger { .. } | CppType::Enum { .. } => true,
+            CppType::Class(path) => match self.find_wrapper_type(path) {
+                Ok(wrapper) => matches!(
+                    wrapper.item.as_struct_ref().map(|s| &s.kind),
+                    Some(RustStructKind::WrapperType(
+                        RustWrapperTypeKind::MovableClassWrapper { .. }
+                    ))
+                ),
+                Err(_) => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Removes trailing out-parameters from the arguments of `rust_function`
+    /// and makes the wrapper return their values instead.
+    fn convert_out_arguments(
+        &self,
+        ffi_function: &CppFfiFunction,
+        rust_function: &mut UnnamedRustFunction,
+        mode: OutParams,
+    ) -> Result<()> {
+        let mut out_arguments = Vec::new();
+        while let Some(arg) = rust_function.arguments.last() {
+            let ffi_arg = ffi_function
+                .arguments
+                .get(arg.ffi_index)
+                .ok_or_else(|| err_msg("invalid ffi index"))?;
+            if ffi_arg.meaning == CppFfiArgumentMeaning::This
+                || !self.is_out_argument_type(&ffi_arg.argument_type)
+            {
+                break;
+            }
+            let arg = rust_function.arguments.pop().unwrap();
+            out_arguments.push(RustOutArgument {
+                value_type: arg.argument_type.ffi_type().pointer_like_to_target()?,
+                name: arg.name,
+                ffi_index: arg.ffi_index,
+            });
+        }
+        if out_arguments.is_empty() {
+            trace!(
+                "no out-parameters found in {}",
+                ffi_function.path.to_cpp_pseudo_code()
+            );
+            return Ok(());
+        }
+        out_arguments.reverse();
+
+        let returns_success_flag = mode == OutParams::SuccessFlag;
+        if returns_success_flag && rust_function.return_type.api_type() != &RustType::bool() {
+            bail!("success flag must be bool");
+        }
+        if let RustFunctionKind::FfiWrapper(data) = &mut rust_function.kind {
+            data.out_arguments = out_arguments;
+            data.returns_success_flag = returns_success_flag;
+        } else {
+            bail!("out-parameters are only supported for FFI wrappers");
+        }
+        Ok(())
+    }
+
+    /// Returns true if the class has a const member function with the same name
+    /// and argument types as `function`.
+    fn has_const_overload(&self, function: &CppFunction) -> bool {
+        self.data
+            .db
+            .cpp_items()
+            .filter_map(|item| item.item.as_function_ref())
+            .any(|other| {
+                other.path == function.path
+                    && other.member.as_ref().map_or(false, |m| m.is_const)
+                    && other.argument_types_equal(function)
+            })
+    }
+
+    /// Generates a `Debug` implementation based on a stream output function.
+    /// Only the first `operator<<` found for a type is used.
+    fn process_stream_output(
+        &self,
+        function: &CppFfiFunction,
+        ffi_function_path: RustPath,
+    ) -> Result<RustItem> {
+        let value_type = function
+            .arguments
+            .get(0)
+            .ok_or_else(|| err_msg("no arguments"))?
+            .argument_type
+            .ffi_type()
+            .pointer_like_to_target()?;
+        let class_path = if let CppType::Class(path) = value_type {
+            path
+        } else {
+            bail!("stream output value is not a class");
+        };
+        let target_path = self
+            .find_wrapper_type(class_path)?
+            .item
+            .path()
+            .ok_or_else(|| err_msg("wrapper type must have a path"))?
+            .clone();
+        if target_path.crate_name() != self.data.db.crate_name() {
+            bail!("Debug can't be implemented for a type outside current crate");
+        }
+        let has_debug_impl = self
+            .data
+            .db
+            .rust_items()
+            .filter_map(|item| item.item.as_extra_impl_ref())
+            .any(|item| match &item.kind {
+                RustExtraImplKind::Debug(data) => data.target_path == target_path,
+                _ => false,
+            });
+        if has_debug_impl {
+            bail!(
+                "Debug is already implemented for {}",
+                target_path.full_name(None)
+            );
+        }
+        Ok(RustItem::ExtraImpl(RustExtraImpl {
+            parent_path: target_path.parent()?,
+            kind: RustExtraImplKind::Debug(RustDebugImpl {
+                target_path,
+                ffi_function_path,
+            }),
+        }))
+    }
+
+    /// Generates a `FromUtf8` implementation or a `to_std_string()` method
+    /// of a string type based on a string conversion function.
+    fn process_string_conversion(
+        &self,
+        function: &CppFfiFunction,
+        ffi_function_path: RustPath,
+    ) -> Result<RustItem> {
+        let string_type = if let CppFfiFunctionKind::StringFromUtf8 { .. } = &function.kind {
+            function.return_type.ffi_type().pointer_like_to_target()?
+        } else {
+            function
+                .arguments
+                .get(0)
+                .ok_or_else(|| err_msg("no arguments"))?
+                .argument_type
+                .ffi_type()
+                .pointer_like_to_target()?
+        };
+        let class_path = if let CppType::Class(path) = string_type {
+            path
+        } else {
+            bail!("string type is not a class");
+        };
+        let target_path = self
+            .find_wrapper_type(&class_path)?
+            .item
+            .path()
+            .ok_or_else(|| err_msg("wrapper type must have a path"))?
+            .clone();
+        if target_path.crate_name() != self.data.db.crate_name() {
+            bail!("string conversions can't be implemented for a type outside current crate");
+        }
+        let parent_path = target_path.parent()?;
+        let data = RustStringConversionImpl {
+            target_path,
+            ffi_function_path,
+        };
+        let kind = if let CppFfiFunctionKind::StringFromUtf8 { .. } = &function.kind {
+            RustExtraImplKind::FromUtf8(data)
+        } else {
+            RustExtraImplKind::ToStdString(data)
+        };
+        Ok(RustItem::ExtraImpl(RustExtraImpl { parent_path, kind }))
+    }
+
+    /// Generates `iter()` and `iter_mut()` methods for containers with
+    /// `begin()` and `end()` functions returning C++ iterators.
+    /// Containers whose iterators don't fit `cpp_core::CppIterator` are skipped.
+    fn generate_container_iterators(&mut self) -> Result<()> {
+        let crate_name = self.data.db.crate_name().to_string();
+        let mut new_items = Vec::new();
+        {
+            // iterator types may come from dependencies
+            let trait_impls = self
+                .data
+                .db
+                .all_rust_items()
+                .filter_map(|item| item.item.as_trait_impl_ref())
+                .collect_vec();
+            let begin_impls = self.data.db.rust_items().filter_map(|item| {
+                item.item
+                    .as_trait_impl_ref()
+                    .map(|trait_impl| (item.id.clone(), trait_impl))
+            });
+            for (begin_id, begin_impl) in begin_impls {
+                let (end_trait, name, is_mut) = if begin_impl.is_impl_of("cpp_core::ops::Begin") {
+                    ("cpp_core::ops::End", "iter", false)
+                } else if begin_impl.is_impl_of("cpp_core::ops::BeginMut") {
+                    ("cpp_core::ops::EndMut", "iter_mut", true)
+                } else {
+                    continue;
+                };
+                let target_path = if let RustType::Common(common) = &begin_impl.target_type {
+                    common.path.clone()
+                } else {
+                    continue;
+                };
+                if target_path.crate_name() != crate_name {
+                    continue;
+                }
+                let end_impl = trait_impls
+                    .iter()
+                    .find(|t| t.target_type == begin_impl.target_type && t.is_impl_of(end_trait));
+                let result = end_impl
+                    .ok_or_else(|| err_msg("end() is not available"))
+                    .and_then(|end_impl| {
+                        let begin_type = begin_impl
+                            .associated_type("Output")
+                            .ok_or_else(|| err_msg("begin() has no output type"))?;
+                        let end_type = end_impl
+                            .associated_type("Output")
+                            .ok_or_else(|| err_msg("end() has no output type"))?;
+                        let (begin_type, end_type) =
+                            detect_iterator_protocol(&trait_impls, begin_type, end_type)?;
+                        if self
+                            .data
+                            .db
+                            .find_rust_item(&target_path.join(name))
+                            .is_some()
+                        {
+                            bail!("{} is already defined", name);
+                        }
+                        Ok(RustIteratorImpl {
+                            target_path: target_path.clone(),
+                            begin_type,
+                            end_type,
+                            is_mut,
+                        })
+                    });
+                match result {
+                    Ok(data) => new_items.push((begin_id, data)),
+                    Err(err) => trace!(
+                        "not generating {}() for {}: {}",
+                        name,
+                        target_path.full_name(None),
+                        err
+                    ),
+                }
+            }
+        }
+        for (begin_id, data) in new_items {
+            let item = RustItem::ExtraImpl(RustExtraImpl {
+                parent_path: data.target_path.parent()?,
+                kind: RustExtraImplKind::Iterator(data),
+            });
+            self.add_rust_item(Some(begin_id), item)?;
+        }
+        Ok(())
+    }
+
+    /// Generates `as_<base>()` and `as_<base>_mut()` methods for public bases
+    /// other than the first one, which is available through `Deref` instead.
+    fn generate_base_casts(&mut self) -> Result<()> {
+        let mut new_items = Vec::new();
+        for item in self.data.db.rust_items() {
+            let trait_impl = match item.item.as_trait_impl_ref() {
+                Some(trait_impl) if trait_impl.is_impl_of("cpp_core::StaticUpcast") => trait_impl,
+                _ => continue,
+            };
+            let cast = self
+                .data
+                .db
+                .source_cpp_item(&item.id)?
+                .and_then(|cpp_item| cpp_item.item.as_function_ref())
+                .and_then(|function| function.cast.as_ref());
+            match cast {
+                Some(CppCast::Static {
+                    is_unsafe: false,
+                    base_index: Some(index),
+                }) if *index > 0 => {}
+                _ => continue,
+            }
+            let ffi_function_path = match trait_impl.functions.get(0).map(|f| &f.kind) {
+                Some(RustFunctionKind::FfiWrapper(data)) => data.ffi_function_path.clone(),
+                _ => bail!("StaticUpcast implementation must call an FFI function"),
+            };
+            let target_path = match &trait_impl.target_type {
+                RustType::Common(common) => common.path.clone(),
+                _ => bail!("unexpected StaticUpcast target type"),
+            };
+            let base_path = match trait_impl
+                .trait_type
+                .generic_arguments
+                .as_ref()
+                .and_then(|args| args.get(0))
+            {
+                Some(RustType::Common(common)) => common.path.clone(),
+                _ => bail!("unexpected StaticUpcast argument"),
+            };
+            let name = format!("as_{}", base_path.last().to_snake_case());
+            let mut_name = format!("{}_mut", name);
+            if let Some(taken) = [&name, &mut_name].iter().find(|name| {
+                self.data
+                    .db
+                    .find_rust_item(&target_path.join(*name))
+                    .is_some()
+            }) {
+                trace!(
+                    "not generating {}() for {}: name is already taken",
+                    taken,
+                    target_path.full_name(None)
+                );
+                continue;
+            }
+            new_items.push((
+                item.id.clone(),
+                RustBaseCastImpl {
+                    target_path,
+                    base_path,
+                    ffi_function_path,
+                    name,
+                },
+            ));
+        }
+        for (id, data) in new_items {
+            let item = RustItem::ExtraImpl(RustExtraImpl {
+                parent_path: data.target_path.parent()?,
+                kind: RustExtraImplKind::BaseCast(data),
+            });
+            self.add_rust_item(Some(id), item)?;
+        }
+        Ok(())
+    }
+
+    /// Generates `Eq`, `PartialOrd` and `Ord` implementations for classes
+    /// declaring all six comparison operators (see `Config::set_total_order_impls`).
+    fn generate_total_order_impls(&mut self) -> Result<()> {
+        let mut class_operators = HashMap::<CppPath, HashSet<CppOperator>>::new();
+        for item in self.data.db.cpp_items() {
+            let function = if let Some(function) = item.item.as_function_ref() {
+                function
+            } else {
+                continue;
+            };
+            let operator = match &function.operator {
+                Some(operator) if COMPARISON_OPERATORS.contains(operator) => operator,
+                _ => continue,
+            };
+            if function.is_deleted() || function.is_private() || function.is_protected() {
+                continue;
+            }
+            if let Some(class_path) = comparison_operand_class(function) {
+                class_operators
+                    .entry(class_path)
+                    .or_default()
+                    .insert(operator.clone());
+            }
+        }
+
+        let mut new_items = Vec::new();
+        {
+            let trait_impls = self
+                .data
+                .db
+                .rust_items()
+                .filter_map(|item| item.item.as_trait_impl_ref())
+                .collect_vec();
+            for (class_path, operators) in class_operators {
+                if operators.len() < COMPARISON_OPERATORS.len() {
+                    continue;
+                }
+                let result = self.find_wrapper_type(&class_path).and_then(|wrapper| {
+                    let target_path = wrapper
+                        .item
+                        .path()
+                        .ok_or_else(|| err_msg("wrapper type has no path"))?
+                        .clone();
+                    let target_type = RustType::Common(RustCommonType {
+                        path: target_path.clone(),
+                        generic_arguments: None,
+                    });
+                    let other_type = detect_total_order(&trait_impls, &target_type)?;
+                    Ok((wrapper.id.clone(), target_path, other_type))
+                });
+                match result {
+                    Ok(data) => new_items.push(data),
+                    Err(err) => trace!(
+                        "not generating total order for {}: {}",
+                        class_path.to_cpp_pseudo_code(),
+                        err
+                    ),
+                }
+            }
+        }
+        for (wrapper_id, target_path, other_type) in new_items {
+            let item = RustItem::ExtraImpl(RustExtraImpl {
+                parent_path: target_path.parent()?,
+                kind: RustExtraImplKind::TotalOrder(RustTotalOrderImpl {
+                    target_path,
+                    other_type,
+                }),
+            });
+            self.add_rust_item(Some(wrapper_id), item)?;
+        }
+        Ok(())
+    }
+
     fn find_wrapper_type(&self, cpp_path: &CppPath) -> Result<DbItem<&RustItem>> {
         self.data
             .db
@@ -1508,9 +2727,15 @@ impl State<'_, '_> {
             }
         }
 
+        let prefix_nested_types =
+            self.data.config.nested_types_strategy() == NestedTypesStrategy::Prefixed;
         let allow_module_for_nested;
         let allow_wrapper_type;
         match name_type {
+            NameType::Type { .. } if prefix_nested_types => {
+                allow_module_for_nested = false;
+                allow_wrapper_type = true;
+            }
             NameType::Type { .. } | NameType::Module { .. } => {
                 allow_module_for_nested = true;
                 allow_wrapper_type = false;
@@ -1560,12 +2785,46 @@ impl State<'_, '_> {
             )
         })?;
 
+        if prefix_nested_types
+            && rust_item.item.is_wrapper_type()
+            && matches!(name_type, NameType::Type { .. })
+        {
+            // e.g. `OuterInner` for `Outer::Inner`
+            return Ok(RustPathScope {
+                path: rust_path.parent()?,
+                prefix: Some(rust_path.last().to_string()),
+            });
+        }
+
         Ok(RustPathScope {
             path: rust_path.clone(),
             prefix: None,
         })
     }
 
+    /// Returns the closest parent of `cpp_path` that has its own Rust scope,
+    /// i.e. skips namespaces that don't have Rust modules.
+    /// Returns `None` for top level items.
+    fn scope_parent(&self, cpp_path: &CppPath) -> Option<CppPath> {
+        let mut parent = cpp_path.parent().ok()?;
+        while self.namespaces_without_module.contains(&parent) {
+            parent = parent.parent().ok()?;
+        }
+        Some(parent)
+    }
+
+    /// Returns the scope of a top level item.
+    fn top_level_scope(&self, cpp_path: &CppPath) -> RustPathScope {
+        if let Some(path) = self.header_module_paths.get(cpp_path) {
+            RustPathScope {
+                path: path.clone(),
+                prefix: None,
+            }
+        } else {
+            self.default_path_scope()
+        }
+    }
+
     fn default_path_scope(&self) -> RustPathScope {
         RustPathScope {
             path: RustPath {
@@ -1579,7 +2838,11 @@ impl State<'_, '_> {
         let mut captions = Vec::new();
         for arg in types {
             let rust_type = self.rust_final_type(
-                &ffi_type(arg, CppTypeRole::NotReturnType)?,
+                &ffi_type(
+                    arg,
+                    CppTypeRole::NotReturnType,
+                    self.data.config.flags_templates(),
+                )?,
                 &CppFfiArgumentMeaning::Argument(0),
                 ReturnValueAllocationPlace::NotApplicable,
                 None,
@@ -1610,7 +2873,7 @@ impl State<'_, '_> {
             .item;
 
         let r = match &function.kind {
-            CppFfiFunctionKind::Function => {
+            CppFfiFunctionKind::Function | CppFfiFunctionKind::ClosureCallback { .. } => {
                 let cpp_function = cpp_item
                     .as_function_ref()
                     .ok_or_else(|| err_msg("invalid source cpp item type"))?;
@@ -1618,6 +2881,8 @@ impl State<'_, '_> {
                 if cpp_function.is_constructor() {
                     if cpp_function.is_copy_constructor() {
                         Some("new_copy".to_string())
+                    } else if cpp_function.is_move_constructor() {
+                        Some("new_move".to_string())
                     } else {
                         Some("new".to_string())
                     }
@@ -1625,7 +2890,11 @@ impl State<'_, '_> {
                     match operator {
                         CppOperator::Conversion(type1) => {
                             let rust_type = self.rust_final_type(
-                                &ffi_type(type1, CppTypeRole::ReturnType)?,
+                                &ffi_type(
+                                    type1,
+                                    CppTypeRole::ReturnType,
+                                    self.data.config.flags_templates(),
+                                )?,
                                 &CppFfiArgumentMeaning::ReturnValue,
                                 function.allocation_place,
                                 None,
@@ -1637,9 +2906,34 @@ impl State<'_, '_> {
                                     .caption(context, RustTypeCaptionStrategy::LastName)?
                             ))
                         }
-                        CppOperator::Assignment => Some("copy_from".to_string()),
+                        CppOperator::Assignment => {
+                            if cpp_function.has_rvalue_reference_arguments() {
+                                Some("move_from".to_string())
+                            } else {
+                                Some("copy_from".to_string())
+                            }
+                        }
                         _ => Some(operator_function_name(operator)?.to_string()),
                     }
+                } else if let Some(base) = cpp_function
+                    .inherited_from()
+                    .filter(|_| cpp_function.is_ambiguous())
+                {
+                    // the same method is inherited from multiple bases
+                    let name = self.cpp_path_item_to_name(
+                        cpp_function.path.last(),
+                        context,
+                        &NameType::ApiFunction(item.clone()),
+                    )?;
+                    Some(format!("{}_from_{}", name, base.last().name))
+                } else if cpp_function.has_rvalue_reference_arguments() {
+                    // keep rvalue overloads apart from the usual `const T&` ones
+                    let name = self.cpp_path_item_to_name(
+                        cpp_function.path.last(),
+                        context,
+                        &NameType::ApiFunction(item.clone()),
+                    )?;
+                    Some(format!("{}_move", name))
                 } else {
                     None
                 }
@@ -1659,27 +2953,195 @@ impl State<'_, '_> {
                 };
                 Some(function_name)
             }
+            CppFfiFunctionKind::StreamOutput => {
+                bail!("stream output functions don't have wrappers");
+            }
+            CppFfiFunctionKind::StringFromUtf8 { .. } | CppFfiFunctionKind::StringToUtf8 { .. } => {
+                bail!("string conversion functions don't have wrappers");
+            }
         };
 
-        Ok(r)
+        Ok(r)
+    }
+
+    /// Converts `name` to the snake case, keeping acronyms set in the config together.
+    fn snake_case_name(&self, name: &str) -> String {
+        name.to_snake_case_with_acronyms(self.data.config.acronyms())
+    }
+
+    fn cpp_path_item_to_name(
+        &self,
+        item: &CppPathItem,
+        context: &RustPath,
+        name_type: &NameType<'_>,
+    ) -> Result<String> {
+        if let Some(template_arguments) = &item.template_arguments {
+            let captions = self.type_list_caption(template_arguments, context)?;
+            if name_type.is_api_function() {
+                Ok(format!("{}_{}", item.name, captions))
+            } else {
+                Ok(format!("{}_of_{}", item.name, captions))
+            }
+        } else {
+            Ok(item.name.clone())
+        }
+    }
+
+    /// Saves rename rules applied by `generate_rust_path` to the database.
+    /// Fails if the rules give the same path to different C++ items.
+    fn record_applied_rename_rules(&mut self) -> Result<()> {
+        let rules = self.applied_rename_rules.replace(Vec::new());
+        for (index, rule) in rules.iter().enumerate() {
+            if let Some(other) = rules[..index]
+                .iter()
+                .find(|other| other.rust_path == rule.rust_path && other.cpp_path != rule.cpp_path)
+            {
+                bail!(
+                    "rename rules map different items to {}: {} ({}) and {} ({})",
+                    rule.rust_path.full_name(None),
+                    other.cpp_path.to_cpp_pseudo_code(),
+                    other.rule,
+                    rule.cpp_path.to_cpp_pseudo_code(),
+                    rule.rule
+                );
+            }
+        }
+        self.data.db.clear_applied_rename_rules();
+        for rule in rules {
+            self.data.db.add_applied_rename_rule(rule);
+        }
+        Ok(())
+    }
+
+    /// Sets thread safety of class wrappers according to the config.
+    /// Existing items are also updated, so changes of the config are applied
+    /// on regeneration.
+    fn update_thread_safety(&mut self) -> Result<()> {
+        let mut changes = Vec::new();
+        for item in self.data.db.rust_items() {
+            let rust_struct = match item.item.as_struct_ref() {
+                Some(rust_struct) => rust_struct,
+                None => continue,
+            };
+            match &rust_struct.kind {
+                RustStructKind::WrapperType(RustWrapperTypeKind::EnumWrapper { .. }) => continue,
+                RustStructKind::WrapperType(_) => {}
+                _ => continue,
+            }
+            let cpp_path = match self.data.db.source_cpp_item(&item.id)? {
+                Some(cpp_item) => match cpp_item.item.path() {
+                    Some(path) => path.clone(),
+                    None => continue,
+                },
+                None => continue,
+            };
+            let thread_safety = self.data.config.thread_safety(&cpp_path);
+            if thread_safety != ThreadSafety::None {
+                self.check_base_thread_safety(&cpp_path, thread_safety);
+            }
+            if thread_safety != rust_struct.thread_safety {
+                changes.push((item.id.clone(), thread_safety));
+            }
+        }
+        for (id, thread_safety) in changes {
+            if let Some(RustItem::Struct(rust_struct)) =
+                self.data.db.item_mut(&id)?.item.as_rust_item_mut()
+            {
+                rust_struct.thread_safety = thread_safety;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reports a warning if a thread-safe class inherits a class
+    /// that is explicitly marked as not thread-safe.
+    fn check_base_thread_safety(&self, cpp_path: &CppPath, thread_safety: ThreadSafety) {
+        let mut classes = vec![cpp_path.clone()];
+        let mut index = 0;
+        while index < classes.len() {
+            let bases = self
+                .data
+                .db
+                .all_cpp_items()
+                .filter_map(|item| item.item.as_base_ref())
+                .filter(|base| base.derived_class_type == classes[index])
+                .map(|base| base.base_class_type.clone())
+                .collect_vec();
+            for base in bases {
+                if classes.contains(&base) {
+                    continue;
+                }
+                if self.data.config.thread_safety_rule(&base) == Some(ThreadSafety::None) {
+                    self.data.progress.warning(format!(
+                        "{} is marked as {:?}, but it inherits {} that is marked as not thread-safe",
+                        cpp_path.to_cpp_pseudo_code(),
+                        thread_safety,
+                        base.to_cpp_pseudo_code()
+                    ));
+                }
+                classes.push(base);
+            }
+            index += 1;
+        }
     }
 
-    fn cpp_path_item_to_name(
+    /// Applies rename rules of the config to the last part of `cpp_path`.
+    /// Returns the renamed path item, the applied rules and
+    /// whether the name was set by an exact rule.
+    fn apply_rename_rules(
         &self,
-        item: &CppPathItem,
-        context: &RustPath,
+        cpp_path: &CppPath,
         name_type: &NameType<'_>,
-    ) -> Result<String> {
-        if let Some(template_arguments) = &item.template_arguments {
-            let captions = self.type_list_caption(template_arguments, context)?;
-            if name_type.is_api_function() {
-                Ok(format!("{}_{}", item.name, captions))
-            } else {
-                Ok(format!("{}_of_{}", item.name, captions))
+    ) -> (CppPathItem, Vec<&RenameRule>, bool) {
+        let mut item = cpp_path.last().clone();
+        let mut applied_rules = Vec::new();
+        let (allow_exact, allow_module) = match name_type {
+            NameType::Type {
+                is_from_other_crate: false,
+            }
+            | NameType::EnumValue
+            | NameType::ApiFunction(_) => (true, false),
+            NameType::Module {
+                is_from_other_crate: false,
+            } => (false, true),
+            NameType::ReceiverFunction { .. } => (false, false),
+            // FFI functions, generated types and items of other crates
+            _ => return (item, applied_rules, false),
+        };
+        let templateless_path = cpp_path.to_templateless_string();
+        for rule in self.data.config.rename_rules() {
+            match rule {
+                RenameRule::Exact {
+                    cpp_path,
+                    rust_name,
+                } if allow_exact => {
+                    if cpp_path.to_templateless_string() == templateless_path {
+                        item.name = rust_name.clone();
+                        return (item, vec![rule], true);
+                    }
+                }
+                RenameRule::Module {
+                    cpp_path,
+                    rust_name,
+                } if allow_module => {
+                    if cpp_path.to_templateless_string() == templateless_path {
+                        item.name = rust_name.clone();
+                        return (item, vec![rule], true);
+                    }
+                }
+                _ => {}
             }
-        } else {
-            Ok(item.name.clone())
         }
+        for rule in self.data.config.rename_rules() {
+            if let RenameRule::Pattern { regex, replacement } = rule {
+                let name = regex.replace(&item.name, replacement.as_str());
+                if name != item.name && !name.is_empty() {
+                    item.name = name.into_owned();
+                    applied_rules.push(rule);
+                }
+            }
+        }
+        (item, applied_rules, false)
     }
 
     fn generate_rust_path(&self, cpp_path: &CppPath, name_type: NameType<'_>) -> Result<RustPath> {
@@ -1706,7 +3168,7 @@ impl State<'_, '_> {
             | NameType::EnumValue
             | NameType::ApiFunction { .. }
             | NameType::ReceiverFunction { .. } => {
-                if let Ok(parent) = cpp_path.parent() {
+                if let Some(parent) = self.scope_parent(cpp_path) {
                     self.get_path_scope(&parent, name_type.clone())?
                 } else if let NameType::ApiFunction(item) = &name_type {
                     let cpp_item = self
@@ -1726,14 +3188,17 @@ impl State<'_, '_> {
                             prefix: None,
                         }
                     } else {
-                        self.default_path_scope()
+                        self.top_level_scope(cpp_path)
                     }
                 } else {
-                    self.default_path_scope()
+                    self.top_level_scope(cpp_path)
                 }
             }
         };
 
+        let (renamed_item, mut applied_rules, is_exact_rename) =
+            self.apply_rename_rules(cpp_path, &name_type);
+
         let full_last_name = match &name_type {
             NameType::SizedItem => cpp_path
                 .items()
@@ -1741,14 +3206,19 @@ impl State<'_, '_> {
                 .map_if_ok(|item| self.cpp_path_item_to_name(item, &scope.path, &name_type))?
                 .join("_"),
             NameType::ApiFunction(function) => {
-                let s = if let Some(last_name_override) =
+                let special_name = if is_exact_rename {
+                    None
+                } else {
                     self.special_function_rust_name(function.clone(), &scope.path)?
-                {
+                };
+                let s = if let Some(last_name_override) = special_name {
+                    // e.g. `new` for constructors
+                    applied_rules.clear();
                     last_name_override
                 } else {
-                    self.cpp_path_item_to_name(cpp_path.last(), &scope.path, &name_type)?
+                    self.cpp_path_item_to_name(&renamed_item, &scope.path, &name_type)?
                 };
-                s.to_snake_case()
+                self.snake_case_name(&s)
             }
             NameType::ReceiverFunction {
                 receiver_type,
@@ -1757,9 +3227,11 @@ impl State<'_, '_> {
                 if *is_wrapped_signal {
                     "signal".to_string()
                 } else {
-                    let name = self
-                        .cpp_path_item_to_name(cpp_path.last(), &scope.path, &name_type)?
-                        .to_snake_case();
+                    let name = self.snake_case_name(&self.cpp_path_item_to_name(
+                        &renamed_item,
+                        &scope.path,
+                        &name_type,
+                    )?);
                     match receiver_type {
                         RustQtReceiverType::Signal => name,
                         RustQtReceiverType::Slot => format!("slot_{}", name),
@@ -1769,20 +3241,22 @@ impl State<'_, '_> {
             NameType::Type { .. } | NameType::EnumValue => {
                 if cpp_path.to_templateless_string() == "std::vector" {
                     // remove allocator template argument
-                    let mut path_item = cpp_path.last().clone();
+                    let mut path_item = renamed_item.clone();
                     if let Some(args) = &mut path_item.template_arguments {
                         args.pop();
                     }
                     self.cpp_path_item_to_name(&path_item, &scope.path, &name_type)?
                         .to_class_case()
                 } else {
-                    self.cpp_path_item_to_name(&cpp_path.last(), &scope.path, &name_type)?
+                    self.cpp_path_item_to_name(&renamed_item, &scope.path, &name_type)?
                         .to_class_case()
                 }
             }
-            NameType::Module { .. } => self
-                .cpp_path_item_to_name(&cpp_path.last(), &scope.path, &name_type)?
-                .to_snake_case(),
+            NameType::Module { .. } => self.snake_case_name(&self.cpp_path_item_to_name(
+                &renamed_item,
+                &scope.path,
+                &name_type,
+            )?),
             NameType::FfiFunction => cpp_path.last().name.clone(),
             NameType::QtSlotWrapper { signal_arguments } => {
                 if signal_arguments.is_empty() {
@@ -1810,9 +3284,38 @@ impl State<'_, '_> {
             return Ok(rust_path);
         }
 
-        let sanitized_name = sanitize_rust_identifier(&full_last_name, name_type.is_module());
+        let mut sanitized_name = sanitize_rust_identifier(&full_last_name, name_type.is_module());
+        if matches!(
+            name_type,
+            NameType::Type {
+                is_from_other_crate: false
+            }
+        ) && RUST_PRELUDE_NAMES.contains(&sanitized_name.as_str())
+        {
+            let suffix = self.data.config.prelude_collision_suffix();
+            self.data.progress.warning(format!(
+                "type name {} of {} is the same as a Rust prelude item, renamed to {}{}",
+                sanitized_name,
+                cpp_path.to_cpp_pseudo_code(),
+                sanitized_name,
+                suffix
+            ));
+            sanitized_name.push_str(suffix);
+        }
         let rust_path = scope.apply(&sanitized_name);
 
+        let mut renames = self.applied_rename_rules.borrow_mut();
+        for rule in applied_rules {
+            let item = AppliedRenameRule {
+                cpp_path: cpp_path.clone(),
+                rust_path: rust_path.clone(),
+                rule: rule.to_string(),
+            };
+            if !renames.contains(&item) {
+                renames.push(item);
+            }
+        }
+
         if name_type.is_api_function() {
             Ok(rust_path)
         } else {
@@ -1838,6 +3341,9 @@ impl State<'_, '_> {
             CppFfiItem::QtSignalWrapper(_) => {
                 bail!("signal wrappers do not need to be processed here");
             }
+            CppFfiItem::SubclassShim(_) => {
+                bail!("subclass shims do not need to be processed here");
+            }
         }
     }
 
@@ -1847,23 +3353,40 @@ impl State<'_, '_> {
         let data = item.item;
 
         // TODO: do something about `QUrlTwoFlags<T1, T2>`
-        if is_qflags(&data.path) {
+        if self.data.config.is_flags_type(&data.path) {
+            let template = flags_template_path(&data.path);
             let argument = &data.path.last().template_arguments.as_ref().unwrap()[0];
-            if !argument.is_template_parameter() {
-                if let CppType::Enum { path } = &argument {
-                    let rust_type = self.find_wrapper_type(path)?;
-                    let rust_type_path = rust_type
-                        .item
-                        .path()
-                        .expect("enum rust item must have path");
-                    let rust_item = RustItem::ExtraImpl(RustExtraImpl {
-                        parent_path: rust_type_path.parent()?,
-                        kind: RustExtraImplKind::FlagEnum(RustFlagEnumImpl {
-                            enum_path: rust_type_path.clone(),
-                        }),
+            if argument.is_template_parameter() {
+                if self.data.config.flags_type_mapping(&template).is_none() {
+                    let rust_path = self.generate_rust_path(
+                        &template,
+                        NameType::Type {
+                            is_from_other_crate: false,
+                        },
+                    )?;
+                    let rust_item = RustItem::Struct(RustStruct {
+                        path: rust_path,
+                        kind: RustStructKind::FlagsType(RustFlagsType { cpp_path: template }),
+                        is_public: true,
+                        qt_receiver_data: None,
+                        thread_safety: ThreadSafety::None,
                     });
                     return Ok(vec![rust_item]);
                 }
+            } else if let CppType::Enum { path } = &argument {
+                let rust_type = self.find_wrapper_type(path)?;
+                let rust_type_path = rust_type
+                    .item
+                    .path()
+                    .expect("enum rust item must have path");
+                let rust_item = RustItem::ExtraImpl(RustExtraImpl {
+                    parent_path: rust_type_path.parent()?,
+                    kind: RustExtraImplKind::FlagEnum(RustFlagEnumImpl {
+                        enum_path: rust_type_path.clone(),
+                        flags_path: self.flags_type_path(&template)?,
+                    }),
+                });
+                return Ok(vec![rust_item]);
             }
         }
 
@@ -1902,7 +3425,8 @@ impl State<'_, '_> {
 
         let mut rust_items = Vec::new();
 
-        let is_movable = false;
+        let is_movable =
+            self.data.db.type_allocation_place(&data.path) == TypeAllocationPlace::Stack;
 
         let wrapper_kind;
         if is_movable {
@@ -1922,6 +3446,7 @@ impl State<'_, '_> {
                 }),
                 is_public: true,
                 qt_receiver_data: None,
+                thread_safety: ThreadSafety::None,
             });
 
             rust_items.push(internal_rust_item);
@@ -1933,19 +3458,21 @@ impl State<'_, '_> {
             wrapper_kind = RustWrapperTypeKind::ImmovableClassWrapper;
         }
 
-        let nested_types_path = self.generate_rust_path(
-            &data.path,
-            NameType::Module {
-                is_from_other_crate,
-            },
-        )?;
+        if self.data.config.nested_types_strategy() == NestedTypesStrategy::Modules {
+            let nested_types_path = self.generate_rust_path(
+                &data.path,
+                NameType::Module {
+                    is_from_other_crate,
+                },
+            )?;
 
-        let nested_types_rust_item = RustItem::Module(RustModule {
-            is_public: true,
-            path: nested_types_path,
-            kind: RustModuleKind::CppNestedTypes,
-        });
-        rust_items.push(nested_types_rust_item);
+            let nested_types_rust_item = RustItem::Module(RustModule {
+                is_public: true,
+                path: nested_types_path,
+                kind: RustModuleKind::CppNestedTypes,
+            });
+            rust_items.push(nested_types_rust_item);
+        }
 
         let qt_receiver_data;
         if let Some(wrapper) = qt_slot_wrapper {
@@ -1974,7 +3501,13 @@ impl State<'_, '_> {
                 .item
                 .signal_arguments
                 .iter()
-                .map_if_ok(|t| ffi_type(&t, CppTypeRole::NotReturnType))?
+                .map_if_ok(|t| {
+                    ffi_type(
+                        &t,
+                        CppTypeRole::NotReturnType,
+                        self.data.config.flags_templates(),
+                    )
+                })?
                 .map_if_ok(|t| self.ffi_type_to_rust_ffi_type(t.ffi_type()))?;
 
             let impl_item = RustItem::ExtraImpl(RustExtraImpl {
@@ -2000,6 +3533,7 @@ impl State<'_, '_> {
             kind: RustStructKind::WrapperType(wrapper_kind),
             is_public: true,
             qt_receiver_data,
+            thread_safety: ThreadSafety::None,
         });
         rust_items.push(public_rust_item);
         Ok(rust_items)
@@ -2014,6 +3548,9 @@ impl State<'_, '_> {
 
         match &cpp_item.item {
             CppItem::Namespace(namespace) => {
+                if self.namespaces_without_module.contains(&namespace.path) {
+                    return Ok(Vec::new());
+                }
                 let rust_path = self.generate_rust_path(
                     &namespace.path,
                     NameType::Module {
@@ -2027,22 +3564,39 @@ impl State<'_, '_> {
                 });
                 Ok(vec![rust_item])
             }
+            CppItem::Type(data) if self.data.config.type_mapping(&data.path).is_some() => {
+                // the Rust type is provided by the user
+                Ok(Vec::new())
+            }
             CppItem::Type(data) => match data.kind {
-                CppTypeDeclarationKind::Class { .. } => {
+                CppTypeDeclarationKind::Class { .. } if !self.data.config.is_opaque(&data.path) => {
                     self.process_cpp_class(cpp_item.map(|v| v.as_type_ref().unwrap()))
                 }
-                CppTypeDeclarationKind::Enum => {
+                // classes made opaque in the config are wrapped
+                // in the same way as forward-declared classes
+                CppTypeDeclarationKind::Enum { .. }
+                | CppTypeDeclarationKind::Class { .. }
+                | CppTypeDeclarationKind::Opaque => {
                     let rust_path = self.generate_rust_path(
                         &data.path,
                         NameType::Type {
                             is_from_other_crate: false,
                         },
                     )?;
+                    let wrapper_kind = match &data.kind {
+                        CppTypeDeclarationKind::Enum { underlying_type } => {
+                            RustWrapperTypeKind::EnumWrapper {
+                                underlying_type: self.ffi_type_to_rust_ffi_type(underlying_type)?,
+                            }
+                        }
+                        _ => RustWrapperTypeKind::OpaqueClassWrapper,
+                    };
                     let rust_item = RustItem::Struct(RustStruct {
                         path: rust_path,
-                        kind: RustStructKind::WrapperType(RustWrapperTypeKind::EnumWrapper),
+                        kind: RustStructKind::WrapperType(wrapper_kind),
                         is_public: true,
                         qt_receiver_data: None,
+                        thread_safety: ThreadSafety::None,
                     });
 
                     Ok(vec![rust_item])
@@ -2127,7 +3681,11 @@ impl State<'_, '_> {
                     .iter()
                     .map_if_ok(|arg| -> Result<_> {
                         // TODO: rust generator shouldn't know about cpp ffi types
-                        let ffi_type = ffi_type(&arg.argument_type, CppTypeRole::NotReturnType)?;
+                        let ffi_type = ffi_type(
+                            &arg.argument_type,
+                            CppTypeRole::NotReturnType,
+                            self.data.config.flags_templates(),
+                        )?;
                         self.ffi_type_to_rust_ffi_type(ffi_type.ffi_type())
                     })?;
 
@@ -2235,6 +3793,115 @@ impl State<'_, '_> {
         Ok(())
     }
 
+    /// Collects namespaces that don't have their own Rust modules and
+    /// generates modules of headers for `ModuleStrategy::Headers`.
+    fn generate_module_layout(&mut self) -> Result<()> {
+        let use_headers = self.data.config.module_strategy() == ModuleStrategy::Headers;
+        for item in self.data.db.cpp_items() {
+            if let CppItem::Namespace(namespace) = &item.item {
+                if use_headers
+                    || self
+                        .data
+                        .config
+                        .flattened_namespaces()
+                        .contains(&namespace.path)
+                {
+                    self.namespaces_without_module
+                        .insert(namespace.path.clone());
+                }
+            }
+        }
+        if !use_headers {
+            return Ok(());
+        }
+
+        let mut top_level_items = Vec::new();
+        for item in self.data.db.cpp_items() {
+            let path = match &item.item {
+                CppItem::Type(data) => &data.path,
+                CppItem::Function(function) => &function.path,
+                _ => continue,
+            };
+            if self.scope_parent(path).is_some() {
+                // e.g. class members
+                continue;
+            }
+            if let Some(origin) = self.data.db.root_item_origin(&item.id)? {
+                top_level_items.push((path.clone(), origin.include_file.clone()));
+            }
+        }
+
+        let crate_root = self.special_module_paths[&RustSpecialModuleKind::CrateRoot].clone();
+        let mut modules = HashMap::<String, RustPath>::new();
+        for (cpp_path, include_file) in top_level_items {
+            if !modules.contains_key(&include_file) {
+                let stem = Path::new(&include_file)
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .ok_or_else(|| format_err!("invalid include file name: {}", include_file))?;
+                let name = sanitize_rust_identifier(&self.snake_case_name(stem), true);
+                let path = crate_root.join(name);
+                let is_existing_module = self
+                    .data
+                    .db
+                    .find_rust_item(&path)
+                    .and_then(|item| item.item.as_module_ref())
+                    .map_or(false, |module| module.kind == RustModuleKind::CppHeader);
+                let path = if is_existing_module {
+                    path
+                } else {
+                    self.data.db.make_unique_rust_path(&path)
+                };
+                let rust_item = RustItem::Module(RustModule {
+                    is_public: true,
+                    path: path.clone(),
+                    kind: RustModuleKind::CppHeader,
+                });
+                self.add_rust_item(None, rust_item)?;
+                modules.insert(include_file.clone(), path);
+            }
+            self.header_module_paths
+                .insert(cpp_path, modules[&include_file].clone());
+        }
+        Ok(())
+    }
+
+    /// Re-exports items of the namespace set by `Config::set_crate_root_namespace`
+    /// from the crate root.
+    fn generate_crate_root_namespace_reexport(&mut self) -> Result<()> {
+        let cpp_path = if let Some(path) = self.data.config.crate_root_namespace() {
+            path.clone()
+        } else {
+            return Ok(());
+        };
+        if self.namespaces_without_module.contains(&cpp_path) {
+            bail!(
+                "crate root namespace {} doesn't have a Rust module",
+                cpp_path.to_cpp_pseudo_code()
+            );
+        }
+        let module_path = self
+            .data
+            .db
+            .find_rust_items_for_cpp_path(&cpp_path, false)?
+            .filter(|item| item.item.is_module())
+            .find_map(|item| item.item.path().cloned())
+            .ok_or_else(|| {
+                format_err!(
+                    "no Rust module for crate root namespace {}",
+                    cpp_path.to_cpp_pseudo_code()
+                )
+            })?;
+        let crate_root = self.special_module_paths[&RustSpecialModuleKind::CrateRoot].clone();
+        let rust_item = RustItem::Reexport(RustReexport {
+            path: crate_root.join("*"),
+            target: module_path,
+            source: RustReexportSource::CppNamespace { cpp_path },
+        });
+        self.add_rust_item(None, rust_item)?;
+        Ok(())
+    }
+
     fn process_cpp_items(&mut self) -> Result<()> {
         let mut processed_ids = HashSet::new();
         let all_cpp_item_ids = self.data.db.cpp_item_ids().collect_vec();
@@ -2261,6 +3928,9 @@ impl State<'_, '_> {
         }
 
         for cpp_item_id in all_cpp_item_ids {
+            if processed_ids.contains(&cpp_item_id) {
+                continue;
+            }
             let cpp_item = self.data.db.cpp_item(&cpp_item_id)?;
             if let Err(err) = self.process_cpp_item(cpp_item.clone()) {
                 debug!(
@@ -2268,6 +3938,12 @@ impl State<'_, '_> {
                     cpp_item.id, &cpp_item.item, err
                 );
                 print_trace(&err, Some(log::Level::Trace));
+                self.data.db.add_rejection(Rejection {
+                    cpp_name: cpp_item.item.short_text(),
+                    step: "rust_generator".to_string(),
+                    reason: err.iter_chain().join(": "),
+                    include_file: None,
+                });
             }
         }
         Ok(())
@@ -2324,25 +4000,117 @@ impl State<'_, '_> {
                         err
                     );
                     print_trace(&err, Some(log::Level::Trace));
+                    self.data.db.add_rejection(Rejection {
+                        cpp_name: ffi_item.item.short_text(),
+                        step: "rust_generator".to_string(),
+                        reason: err.iter_chain().join(": "),
+                        include_file: None,
+                    });
                 }
             }
         }
         Ok(grouped_functions)
     }
 
+    /// Returns the key of the Rust name of the FFI item in the database.
+    /// The key is based on the full signature of the source C++ item,
+    /// so it doesn't depend on the other overloads of the function.
+    fn rust_name_key(&self, ffi_item_id: &ItemId) -> Result<String> {
+        let ffi_item = self.data.db.ffi_item(ffi_item_id)?;
+        let cpp_item = self
+            .data
+            .db
+            .source_cpp_item(ffi_item_id)?
+            .ok_or_else(|| err_msg("source cpp item not found"))?;
+        let mut key = if let Some(function) = cpp_item.item.as_function_ref() {
+            function.signature_text()
+        } else {
+            cpp_item.item.short_text()
+        };
+        if let Some(function) = ffi_item.item.as_function_ref() {
+            match &function.kind {
+                CppFfiFunctionKind::FieldAccessor { accessor_type } => {
+                    key.push_str(&format!(" [{:?}]", accessor_type));
+                }
+                CppFfiFunctionKind::ClosureCallback { .. } => {
+                    key.push_str(" [closure]");
+                }
+                _ => {}
+            }
+        }
+        Ok(key)
+    }
+
+    /// Returns the stored Rust name of `function` if it can be used again.
+    /// Otherwise, returns the reason why it can't be used.
+    fn check_stored_rust_name(
+        &self,
+        function: &ItemWithSource<FunctionWithDesiredPath>,
+        stored_path: &RustPath,
+        reserved_path: Option<&RustPath>,
+    ) -> std::result::Result<(), String> {
+        let desired_path = &function.item.desired_path;
+        // the stored name must still be derived from the desired name,
+        // e.g. `value_int` or `value2` for `value`
+        let is_derived = unraw_identifier(stored_path.last())
+            .strip_prefix(unraw_identifier(desired_path.last()))
+            .map_or(false, |rest| {
+                rest.is_empty() || rest.starts_with('_') || rest.chars().all(|c| c.is_ascii_digit())
+            });
+        if stored_path.parent().ok() != desired_path.parent().ok() || !is_derived {
+            return Err(format!("desired name changed to {:?}", desired_path));
+        }
+        if Some(stored_path) == reserved_path {
+            return Err("name is reserved for the generic function".to_string());
+        }
+        if let Some(item) = self.data.db.find_rust_item(stored_path) {
+            if item.source_id.as_ref() != Some(&function.source_id) {
+                return Err("name is taken by another item".to_string());
+            }
+        }
+        Ok(())
+    }
+
     fn try_caption_strategy(
         &self,
         functions: &[ItemWithSource<FunctionWithDesiredPath>],
+        preserved_paths: &[Option<RustPath>],
         strategy: &RustFunctionCaptionStrategy,
+        desired_paths: &BTreeSet<RustPath>,
+        stored_paths: &BTreeSet<RustPath>,
+        reserved_path: Option<&RustPath>,
     ) -> Result<()> {
+        let mut strategy_paths = BTreeSet::new();
         let mut paths = BTreeSet::new();
-        for function in functions {
-            let path = function.item.apply_strategy(strategy)?;
+        for (function, preserved_path) in functions.iter().zip(preserved_paths) {
+            // the strategy must tell apart all functions of the group,
+            // even if only new functions are named with it
+            let strategy_path = function.item.apply_strategy(strategy)?;
+            if strategy_paths.contains(&strategy_path) {
+                bail!("conflicting path: {:?}", strategy_path);
+            }
+            strategy_paths.insert(strategy_path.clone());
+            let path = preserved_path.clone().unwrap_or(strategy_path);
             if paths.contains(&path) {
                 bail!("conflicting path: {:?}", path);
             }
-            if self.data.db.find_rust_item(&path).is_some() {
-                bail!("path already taken by an existing item: {:?}", path);
+            if preserved_path.is_none() {
+                if Some(&path) == reserved_path {
+                    bail!("path is reserved for the generic function: {:?}", path);
+                }
+                // names of other functions (including removed ones)
+                // are never given to new functions
+                if stored_paths.contains(&path) {
+                    bail!("path is stored for another function: {:?}", path);
+                }
+                // e.g. `value_mut` generated for a non-const `value()` overload
+                // must not take the name of a C++ function `valueMut()`
+                if path != function.item.desired_path && desired_paths.contains(&path) {
+                    bail!("path is desired by another function: {:?}", path);
+                }
+                if self.data.db.find_rust_item(&path).is_some() {
+                    bail!("path already taken by an existing item: {:?}", path);
+                }
             }
             paths.insert(path);
         }
@@ -2355,16 +4123,83 @@ impl State<'_, '_> {
         grouped_functions: BTreeMap<RustPath, Vec<ItemWithSource<FunctionWithDesiredPath>>>,
     ) -> Result<()> {
         let all_strategies = RustFunctionCaptionStrategy::all();
+        let desired_paths = grouped_functions.keys().cloned().collect::<BTreeSet<_>>();
+        let use_traits = self.data.config.overloading_strategy() == OverloadingStrategy::Traits;
+        let mut trait_paths = BTreeSet::new();
+        let mut stored_paths = self
+            .data
+            .db
+            .rust_names()
+            .values()
+            .cloned()
+            .collect::<BTreeSet<_>>();
 
-        for (_group_path, functions) in grouped_functions {
+        for (group_path, functions) in grouped_functions {
             let mut chosen_strategy = None;
-            if functions.len() > 1 {
+            let mut overloaded_function = None;
+            if functions.len() > 1 && use_traits {
+                match self.overloaded_function(&group_path, &functions, &trait_paths) {
+                    Ok(data) => {
+                        trait_paths.insert(data.trait_path.clone());
+                        overloaded_function = Some(data);
+                    }
+                    Err(err) => {
+                        trace!(
+                            "can't use trait-based overloading for {:?}: {}",
+                            group_path,
+                            err
+                        );
+                    }
+                }
+            }
+            let reserved_path = overloaded_function.as_ref().map(|_| &group_path);
+
+            let keys = functions
+                .iter()
+                .map_if_ok(|function| self.rust_name_key(&function.source_id))?;
+            let mut preserved_paths = Vec::new();
+            let mut lost_paths = Vec::new();
+            for (function, key) in functions.iter().zip(&keys) {
+                let stored_path = self.data.db.rust_name(key).cloned();
+                let mut preserved_path = None;
+                let mut lost_path = None;
+                if let Some(stored_path) = stored_path {
+                    let result = self
+                        .check_stored_rust_name(function, &stored_path, reserved_path)
+                        .and_then(|()| {
+                            if preserved_paths.contains(&Some(stored_path.clone())) {
+                                Err("name is used by another overload".to_string())
+                            } else {
+                                Ok(())
+                            }
+                        });
+                    match result {
+                        Ok(()) => preserved_path = Some(stored_path),
+                        Err(reason) => lost_path = Some((stored_path, reason)),
+                    }
+                }
+                preserved_paths.push(preserved_path);
+                lost_paths.push(lost_path);
+            }
+
+            let has_new_functions = preserved_paths.iter().any(Option::is_none);
+            let need_strategy = functions.len() > 1
+                || reserved_path.is_some()
+                || stored_paths.contains(&group_path);
+            if has_new_functions && need_strategy {
                 trace!("choosing caption strategy for:");
                 for function in &functions {
                     trace!("* {}", function.item.function.kind.short_text());
                 }
                 for strategy in &all_strategies {
-                    match self.try_caption_strategy(&functions, strategy) {
+                    match self.try_caption_strategy(
+                        &functions,
+                        &preserved_paths,
+                        strategy,
+                        &desired_paths,
+                        &stored_paths,
+                        reserved_path,
+                    ) {
                         Ok(_) => {
                             trace!("  chosen strategy: {:?}", strategy);
                             chosen_strategy = Some(strategy.clone());
@@ -2379,7 +4214,9 @@ impl State<'_, '_> {
                     trace!("  all strategies failed, using default strategy");
                     chosen_strategy = Some(RustFunctionCaptionStrategy {
                         mut_: false,
-                        args_count: false,
+                        // the number of arguments makes sure that
+                        // no function takes the reserved path
+                        args_count: reserved_path.is_some(),
                         arg_names: false,
                         arg_types: Some(RustTypeCaptionStrategy::LastName),
                         static_: false,
@@ -2387,25 +4224,229 @@ impl State<'_, '_> {
                 }
             }
 
-            for function in functions {
-                let path = if let Some(strategy) = &chosen_strategy {
-                    function.item.apply_strategy(strategy).unwrap()
+            let mut overloads = Vec::<RustOverload>::new();
+            let items = functions
+                .into_iter()
+                .zip(keys)
+                .zip(preserved_paths.into_iter().zip(lost_paths));
+            for ((function, key), (preserved_path, lost_path)) in items {
+                let final_path = if let Some(path) = preserved_path {
+                    path
                 } else {
-                    function.item.desired_path
+                    let path = if let Some(strategy) = &chosen_strategy {
+                        function.item.apply_strategy(strategy).unwrap()
+                    } else {
+                        function.item.desired_path.clone()
+                    };
+                    self.data.db.make_unique_rust_path_with(&path, |path| {
+                        stored_paths.contains(path) || Some(path) == reserved_path
+                    })
                 };
-                let final_path = self.data.db.make_unique_rust_path(&path);
-                let item = RustItem::Function(function.item.function.with_path(final_path));
+                if let Some((old_path, reason)) = lost_path {
+                    self.data.db.add_rust_name_change(RustNameChange {
+                        cpp_signature: key.clone(),
+                        old_path,
+                        new_path: final_path.clone(),
+                        reason,
+                    });
+                }
+                stored_paths.insert(final_path.clone());
+                self.data.db.set_rust_name(key, final_path.clone());
+
+                let rust_function = function.item.function.with_path(final_path);
+                if let Some(overloaded_function) = &overloaded_function {
+                    let overload = RustOverload {
+                        trait_path: overloaded_function.trait_path.clone(),
+                        self_type: overloaded_function.self_type.clone(),
+                        argument_types: overload_argument_types(&rust_function.arguments)?,
+                        return_type: rust_function.api_return_type(),
+                        function_path: rust_function.path.clone(),
+                        is_unsafe: overloaded_function.is_unsafe,
+                    };
+                    // e.g. `c_int` and `c_long` are the same type on some platforms,
+                    // so only the first of these overloads can implement the trait
+                    if let Some(other) = overloads.iter().find(|other| {
+                        other
+                            .arguments_type()
+                            .can_be_same_as(&overload.arguments_type())
+                    }) {
+                        trace!(
+                            "overload {:?} conflicts with {:?}, skipped in the generic function",
+                            overload.function_path,
+                            other.function_path
+                        );
+                    } else {
+                        let item = RustItem::ExtraImpl(RustExtraImpl {
+                            parent_path: overload.trait_path.parent()?,
+                            kind: RustExtraImplKind::Overload(overload.clone()),
+                        });
+                        self.add_rust_item(Some(function.source_id.clone()), item)?;
+                        overloads.push(overload);
+                    }
+                }
+                let item = RustItem::Function(rust_function);
                 self.add_rust_item(Some(function.source_id), item)?;
             }
+            if let Some(overloaded_function) = overloaded_function {
+                let item = RustItem::ExtraImpl(RustExtraImpl {
+                    parent_path: overloaded_function.trait_path.parent()?,
+                    kind: RustExtraImplKind::OverloadedFunction(overloaded_function),
+                });
+                self.add_rust_item(None, item)?;
+            }
         }
         Ok(())
     }
+
+    /// Checks whether `functions` can be called through a single generic function
+    /// and returns information about that function.
+    fn overloaded_function(
+        &self,
+        group_path: &RustPath,
+        functions: &[ItemWithSource<FunctionWithDesiredPath>],
+        trait_paths: &BTreeSet<RustPath>,
+    ) -> Result<RustOverloadedFunction> {
+        if self.data.db.find_rust_item(group_path).is_some() {
+            bail!("path already taken by an existing item: {:?}", group_path);
+        }
+        let mut self_arg_kinds = HashSet::new();
+        for function in functions {
+            let function = &function.item.function;
+            if !function.is_public {
+                bail!("function is not public");
+            }
+            if !matches!(function.kind, RustFunctionKind::FfiWrapper(_)) {
+                bail!("unsupported function kind: {}", function.kind.short_text());
+            }
+            let self_arg_kind = function.self_arg_kind()?;
+            if self_arg_kind == RustFunctionSelfArgKind::Value {
+                bail!("self argument by value is not supported");
+            }
+            self_arg_kinds.insert(self_arg_kind);
+            overload_argument_types(&function.arguments)?;
+            let return_type = function
+                .clone()
+                .with_path(group_path.clone())
+                .api_return_type();
+            check_overload_type(&return_type)?;
+        }
+        if self_arg_kinds.len() > 1 {
+            bail!("overloads have different self argument kinds");
+        }
+
+        let first_function = &functions[0].item.function;
+        let self_type = if first_function.self_arg_kind()? == RustFunctionSelfArgKind::None {
+            None
+        } else {
+            let self_arg = first_function.arguments[0].argument_type.ffi_type();
+            Some(RustType::new_reference(
+                self_arg.is_const_pointer_like()?,
+                self_arg.pointer_like_to_target()?,
+            ))
+        };
+
+        let parent = group_path.parent()?;
+        let impl_type = if self
+            .data
+            .db
+            .find_rust_item(&parent)
+            .map_or(false, |item| item.item.as_struct_ref().is_some())
+        {
+            Some(parent)
+        } else {
+            None
+        };
+        let trait_name = match &impl_type {
+            Some(impl_type) => format!(
+                "ArgsFor{}{}",
+                impl_type.last(),
+                unraw_identifier(group_path.last()).to_class_case()
+            ),
+            None => format!(
+                "ArgsFor{}",
+                unraw_identifier(group_path.last()).to_class_case()
+            ),
+        };
+        let module_path = match &impl_type {
+            Some(impl_type) => impl_type.parent()?,
+            None => group_path.parent()?,
+        };
+        let trait_path = self
+            .data
+            .db
+            .make_unique_rust_path(&module_path.join(trait_name));
+        // traits are not found by `find_rust_item`
+        if trait_paths.contains(&trait_path) {
+            bail!("trait path is already taken: {:?}", trait_path);
+        }
+
+        Ok(RustOverloadedFunction {
+            path: group_path.clone(),
+            trait_path,
+            impl_type,
+            self_type,
+            is_unsafe: functions.iter().any(|f| f.item.function.is_unsafe),
+        })
+    }
+}
+
+/// Returns types of the arguments of an overload accepted by its generic function.
+/// `impl Trait` arguments are replaced by concrete types because
+/// they can't be used in a trait implementation.
+fn overload_argument_types(arguments: &[RustFunctionArgument]) -> Result<Vec<RustType>> {
+    arguments
+        .iter()
+        .filter(|arg| arg.name != "self")
+        .map_if_ok(|arg| -> Result<_> {
+            let argument_type = match arg.argument_type.conversion() {
+                RustToFfiTypeConversion::ImplCastInto(conversion) => RustFinalType::new(
+                    arg.argument_type.ffi_type().clone(),
+                    (**conversion).clone(),
+                )?
+                .api_type()
+                .clone(),
+                RustToFfiTypeConversion::StringArgToPtr => {
+                    string_arg(arg.argument_type.ffi_type())?
+                }
+                _ => arg.argument_type.api_type().clone(),
+            };
+            check_overload_type(&argument_type)?;
+            Ok(argument_type)
+        })
+}
+
+/// Checks that `rust_type` can be used in an implementation
+/// of the trait of a generic function.
+fn check_overload_type(rust_type: &RustType) -> Result<()> {
+    match rust_type {
+        RustType::PointerLike { kind, target, .. } => {
+            if kind.is_ref() {
+                bail!("references are not supported: {:?}", rust_type);
+            }
+            check_overload_type(target)
+        }
+        RustType::Tuple(types) => types.iter().map_if_ok(check_overload_type).map(|_| ()),
+        RustType::Common(common) => common
+            .generic_arguments
+            .iter()
+            .flatten()
+            .map_if_ok(check_overload_type)
+            .map(|_| ()),
+        RustType::Array { element_type, .. } => check_overload_type(element_type),
+        RustType::Primitive(_) | RustType::FunctionPointer(_) => Ok(()),
+        RustType::ImplTrait(_) | RustType::GenericParameter(_) => {
+            bail!("generic types are not supported: {:?}", rust_type)
+        }
+    }
 }
 
 pub fn run(data: &mut ProcessorData<'_>) -> Result<()> {
     let mut state = State {
         data,
         special_module_paths: HashMap::new(),
+        applied_rename_rules: RefCell::new(Vec::new()),
+        namespaces_without_module: HashSet::new(),
+        header_module_paths: HashMap::new(),
     };
     for &module in &[
         RustSpecialModuleKind::CrateRoot,
@@ -2428,11 +4469,76 @@ pub fn run(data: &mut ProcessorData<'_>) -> Result<()> {
         state.generate_crate_reexport(dependency.name())?;
     }
 
+    // all items are processed again, so old rejections are no longer relevant
+    state.data.db.clear_rejections("rust_generator");
+    state.generate_module_layout()?;
     state.process_cpp_items()?;
+    state.generate_crate_root_namespace_reexport()?;
+    state.update_thread_safety()?;
     let grouped_functions = state.process_ffi_items()?;
+    state.data.db.clear_rust_name_changes();
+    if state.data.config.reset_rust_names() {
+        state.data.db.clear_rust_names();
+    }
     state.finalize_functions(grouped_functions)?;
+    state.generate_container_iterators()?;
+    state.generate_base_casts()?;
+    if state.data.config.total_order_impls() {
+        state.generate_total_order_impls()?;
+    }
+    state.record_applied_rename_rules()?;
 
-    Ok(())
+    let config = state.data.config;
+    user_hooks::run(state.data, config.rust_info_hooks())
+}
+
+const COMPARISON_OPERATORS: &[CppOperator] = &[
+    CppOperator::EqualTo,
+    CppOperator::NotEqualTo,
+    CppOperator::LessThan,
+    CppOperator::LessThanOrEqualTo,
+    CppOperator::GreaterThan,
+    CppOperator::GreaterThanOrEqualTo,
+];
+
+/// Returns the class of both operands of a binary operator
+/// if they are the same class passed by value or by const reference.
+fn comparison_operand_class(function: &CppFunction) -> Option<CppPath> {
+    fn class_type(argument_type: &CppType) -> Option<&CppPath> {
+        match argument_type {
+            CppType::Class(path) => Some(path),
+            CppType::PointerLike {
+                kind: CppPointerLikeTypeKind::Reference,
+                is_const: true,
+                target,
+            } => {
+                if let CppType::Class(path) = &**target {
+                    Some(path)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    let (first, second) = if function.member.is_some() {
+        if function.arguments.len() != 1 {
+            return None;
+        }
+        (function.class_path().ok()?, &function.arguments[0])
+    } else {
+        if function.arguments.len() != 2 {
+            return None;
+        }
+        let first = class_type(&function.arguments[0].argument_type)?.clone();
+        (first, &function.arguments[1])
+    };
+    if class_type(&second.argument_type)? == &first {
+        Some(first)
+    } else {
+        None
+    }
 }
 
 fn detect_callback_function(function: &UnnamedRustFunction) -> Option<&RustFunctionPointerType> {
@@ -2503,7 +4609,10 @@ impl FunctionWithDesiredPath {
             suffix.push_str(&format!("_{}a", normal_args.len()));
         }
         if strategy.arg_names && !normal_args.is_empty() {
-            let names = normal_args.iter().map(|arg| &arg.name).join("_");
+            let names = normal_args
+                .iter()
+                .map(|arg| unraw_identifier(&arg.name))
+                .join("_");
             suffix.push_str(&format!("_{}", names));
         }
         if let Some(type_strategy) = strategy.arg_types {
@@ -2549,17 +4658,14 @@ impl FunctionWithDesiredPath {
         }
 
         let suffix = suffix.to_snake_case();
+        let desired_name = unraw_identifier(self.desired_path.last());
         let name = if suffix.is_empty() {
-            self.desired_path.last().to_string()
-        } else if strategy.arg_types.is_some() && self.desired_path.last() == "new" {
+            desired_name.to_string()
+        } else if strategy.arg_types.is_some() && desired_name == "new" {
             format!("from_{}", suffix)
         } else {
-            let delimiter = if self.desired_path.last().ends_with('_') {
-                ""
-            } else {
-                "_"
-            };
-            format!("{}{}{}", self.desired_path.last(), delimiter, suffix)
+            let delimiter = if desired_name.ends_with('_') { "" } else { "_" };
+            format!("{}{}{}", desired_name, delimiter, suffix)
         };
         let name = sanitize_rust_identifier(&name, false);
         Ok(self.desired_path.parent()?.join(name))
@@ -1,15 +1,18 @@
 //! Types holding information about generates Rust API.
 
+use crate::config::{Config, ThreadSafety};
 use crate::cpp_data::CppPath;
 use crate::cpp_ffi_data::CppFfiFunction;
 use crate::cpp_type::CppType;
-use crate::database::DbItem;
+use crate::database::{DatabaseClient, DbItem, ItemId};
 use crate::rust_code_generator::{rust_common_type_to_code, rust_type_to_code};
 use crate::rust_type::{
     RustCommonType, RustFinalType, RustPath, RustPointerLikeTypeKind, RustType,
 };
-use ritual_common::errors::{bail, Result};
+use itertools::Itertools;
+use ritual_common::errors::{bail, err_msg, Result};
 use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 /// One variant of a Rust enum
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
@@ -29,9 +32,16 @@ pub struct RustQtSlotWrapper {
 
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum RustWrapperTypeKind {
-    EnumWrapper,
+    EnumWrapper {
+        /// Integer type of the enum values
+        underlying_type: RustType,
+    },
     ImmovableClassWrapper,
-    MovableClassWrapper { sized_type_path: RustPath },
+    /// Wrapper for a C++ class that is only forward-declared
+    OpaqueClassWrapper,
+    MovableClassWrapper {
+        sized_type_path: RustPath,
+    },
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
@@ -39,12 +49,20 @@ pub struct RustSizedType {
     pub cpp_path: CppPath,
 }
 
+/// Generic type generated for a flags template (see `Config::set_flags_template`)
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct RustFlagsType {
+    /// Path of the C++ template without template arguments
+    pub cpp_path: CppPath,
+}
+
 /// Information about a Rust type wrapper
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum RustStructKind {
     WrapperType(RustWrapperTypeKind),
     QtSlotWrapper(RustQtSlotWrapper), // TODO: remove
     SizedType(RustSizedType),
+    FlagsType(RustFlagsType),
 }
 
 impl RustStructKind {
@@ -67,6 +85,9 @@ impl RustStructKind {
             RustStructKind::SizedType(_) => {
                 matches!(other, RustStructKind::SizedType(_))
             }
+            RustStructKind::FlagsType(_) => {
+                matches!(other, RustStructKind::FlagsType(_))
+            }
         }
     }
 }
@@ -85,6 +106,9 @@ pub struct RustStruct {
     pub kind: RustStructKind,
     /// Indicates whether this type is public
     pub is_public: bool,
+    /// `Send` and `Sync` implementations of a class wrapper
+    /// (see `Config::set_thread_safety`)
+    pub thread_safety: ThreadSafety,
 
     pub qt_receiver_data: Option<RustQtReceiverData>,
 }
@@ -118,9 +142,62 @@ pub enum RustQtReceiverType {
     Slot,
 }
 
+/// An out-parameter of a C++ function. The Rust wrapper allocates the value,
+/// passes its address to the FFI function and returns the value.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct RustOutArgument {
+    /// Name of the local variable holding the value.
+    pub name: String,
+    /// Type of the value.
+    pub value_type: RustType,
+    /// Index of the corresponding argument of the FFI function.
+    pub ffi_index: usize,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct RustFfiWrapperData {
     pub ffi_function_path: RustPath,
+    /// Out-parameters returned by the wrapper instead of being taken as arguments.
+    pub out_arguments: Vec<RustOutArgument>,
+    /// If true, the C++ function returns `bool` indicating success,
+    /// and the wrapper returns `Option` of the out values.
+    pub returns_success_flag: bool,
+}
+
+impl RustFfiWrapperData {
+    pub fn new(ffi_function_path: RustPath) -> Self {
+        RustFfiWrapperData {
+            ffi_function_path,
+            out_arguments: Vec::new(),
+            returns_success_flag: false,
+        }
+    }
+
+    /// Returns the return type of the wrapper, given the API type
+    /// corresponding to the return type of the FFI function.
+    pub fn api_return_type(&self, return_type: &RustType) -> RustType {
+        if self.out_arguments.is_empty() {
+            return return_type.clone();
+        }
+        let mut types = self
+            .out_arguments
+            .iter()
+            .map(|arg| arg.value_type.clone())
+            .collect::<Vec<_>>();
+        if !self.returns_success_flag && !return_type.is_unit() {
+            types.insert(0, return_type.clone());
+        }
+        let values = if types.len() == 1 {
+            types.pop().unwrap()
+        } else {
+            RustType::Tuple(types)
+        };
+        if self.returns_success_flag {
+            RustType::new_option(values)
+        } else {
+            values
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
@@ -192,7 +269,7 @@ impl UnnamedRustFunction {
                             }
                         }
                     },
-                    RustType::Common { .. } => {
+                    RustType::Common { .. } | RustType::Primitive(_) => {
                         return Ok(RustFunctionSelfArgKind::Value);
                     }
                     _ => {
@@ -302,6 +379,32 @@ pub struct RustFunction {
     pub return_type: RustFinalType,
 }
 
+impl RustFunction {
+    /// Returns the return type of the function in the Rust API,
+    /// including values of out-parameters.
+    pub fn api_return_type(&self) -> RustType {
+        if let RustFunctionKind::FfiWrapper(data) = &self.kind {
+            data.api_return_type(self.return_type.api_type())
+        } else {
+            self.return_type.api_type().clone()
+        }
+    }
+
+    /// Adds paths of all types and functions used by this function to `output`.
+    pub fn collect_paths(&self, output: &mut Vec<RustPath>) {
+        for arg in &self.arguments {
+            arg.argument_type.collect_paths(output);
+        }
+        self.return_type.collect_paths(output);
+        if let RustFunctionKind::FfiWrapper(data) = &self.kind {
+            output.push(data.ffi_function_path.clone());
+            for arg in &data.out_arguments {
+                arg.value_type.collect_paths(output);
+            }
+        }
+    }
+}
+
 /// Information about type of `self` argument of the function.
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
 pub enum RustFunctionSelfArgKind {
@@ -347,6 +450,128 @@ pub struct RustTraitImpl {
     pub extra_kind: RustTraitImplExtraKind,
 }
 
+impl RustTraitImpl {
+    /// Returns true if this is an implementation of trait `trait_path`
+    /// (with any generic arguments).
+    pub fn is_impl_of(&self, trait_path: &str) -> bool {
+        self.trait_type.path == trait_path
+    }
+
+    /// Returns value of the associated type `name`.
+    pub fn associated_type(&self, name: &str) -> Option<&RustType> {
+        self.associated_types
+            .iter()
+            .find(|t| t.name == name)
+            .map(|t| &t.value)
+    }
+}
+
+fn common_type_argument<'a>(t: &'a RustType, path: &str) -> Result<&'a RustType> {
+    if let RustType::Common(common) = t {
+        if common.path == path {
+            if let Some(args) = &common.generic_arguments {
+                if args.len() == 1 {
+                    return Ok(&args[0]);
+                }
+            }
+        }
+    }
+    bail!("expected {}<T>, got {:?}", path, t);
+}
+
+/// Checks that `begin_type` and `end_type` (the values returned by
+/// `begin()` and `end()` functions) are `CppBox`es of C++ iterators
+/// that can be used in a `cpp_core::CppIterator`. `trait_impls` must contain
+/// implementations of the operator traits for the iterator type.
+///
+/// Returns the iterator types.
+pub fn detect_iterator_protocol(
+    trait_impls: &[&RustTraitImpl],
+    begin_type: &RustType,
+    end_type: &RustType,
+) -> Result<(RustType, RustType)> {
+    let iterator_type = common_type_argument(begin_type, "cpp_core::CppBox")?;
+    let end_iterator_type = common_type_argument(end_type, "cpp_core::CppBox")?;
+    let find_impl = |trait_path: &str| {
+        trait_impls
+            .iter()
+            .find(|t| &t.target_type == iterator_type && t.is_impl_of(trait_path))
+    };
+
+    if find_impl("cpp_core::ops::Increment").is_none() {
+        bail!("iterator doesn't implement operator++");
+    }
+    let end_ref = RustType::Common(RustCommonType {
+        path: RustPath::from_good_str("cpp_core::Ref"),
+        generic_arguments: Some(vec![end_iterator_type.clone()]),
+    });
+    let has_eq = trait_impls.iter().any(|t| {
+        &t.target_type == iterator_type
+            && t.is_impl_of("std::cmp::PartialEq")
+            && t.trait_type.generic_arguments.as_ref() == Some(&vec![end_ref.clone()])
+    });
+    if !has_eq {
+        bail!("iterator can't be compared to the end iterator");
+    }
+    let output = find_impl("cpp_core::ops::Indirection")
+        .ok_or_else(|| err_msg("iterator doesn't implement operator*"))?
+        .associated_type("Output")
+        .ok_or_else(|| err_msg("operator* has no output type"))?;
+    // `RefIter` produces Rust references from `Ref`s and raw pointers
+    let is_reference = common_type_argument(output, "cpp_core::Ref").is_ok()
+        || matches!(
+            output,
+            RustType::PointerLike {
+                kind: RustPointerLikeTypeKind::Pointer,
+                ..
+            }
+        );
+    if !is_reference {
+        bail!("operator* doesn't return a reference: {:?}", output);
+    }
+    Ok((iterator_type.clone(), end_iterator_type.clone()))
+}
+
+/// Checks that `target_type` implements `PartialEq`, `cpp_core::cmp::Lt` and
+/// `cpp_core::cmp::Gt` with the same right hand side type that refers to
+/// `target_type` (`&T` or `cpp_core::Ref<T>`). `trait_impls` must contain
+/// implementations of the operator traits for the type.
+///
+/// Returns the right hand side type.
+pub fn detect_total_order(
+    trait_impls: &[&RustTraitImpl],
+    target_type: &RustType,
+) -> Result<RustType> {
+    let find_other_type = |trait_path: &str| {
+        trait_impls
+            .iter()
+            .filter(|t| &t.target_type == target_type && t.is_impl_of(trait_path))
+            .filter_map(|t| t.trait_type.generic_arguments.as_ref())
+            .filter_map(|args| args.get(0))
+            .find(|arg| {
+                if arg.is_ref() {
+                    arg.pointer_like_to_target().ok().as_ref() == Some(target_type)
+                } else {
+                    common_type_argument(arg, "cpp_core::Ref").ok() == Some(target_type)
+                }
+            })
+            .cloned()
+    };
+    let other_type = find_other_type("std::cmp::PartialEq")
+        .ok_or_else(|| err_msg("type doesn't implement operator=="))?;
+    for trait_path in &["cpp_core::cmp::Lt", "cpp_core::cmp::Gt"] {
+        let has_impl = trait_impls.iter().any(|t| {
+            &t.target_type == target_type
+                && t.is_impl_of(trait_path)
+                && t.trait_type.generic_arguments.as_ref() == Some(&vec![other_type.clone()])
+        });
+        if !has_impl {
+            bail!("type doesn't implement {}", trait_path);
+        }
+    }
+    Ok(other_type)
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
 pub enum RustSpecialModuleKind {
     CrateRoot,
@@ -360,6 +585,9 @@ pub enum RustModuleKind {
     Special(RustSpecialModuleKind),
     CppNamespace,
     CppNestedTypes,
+    /// Module containing top level items of a C++ header
+    /// (see `ModuleStrategy::Headers`)
+    CppHeader,
 }
 
 impl RustModuleKind {
@@ -373,6 +601,7 @@ impl RustModuleKind {
             },
             RustModuleKind::CppNamespace { .. } => true,
             RustModuleKind::CppNestedTypes { .. } => false,
+            RustModuleKind::CppHeader => true,
         }
     }
 
@@ -477,12 +706,131 @@ pub struct RustQtReceiverImpl {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RustFlagEnumImpl {
     pub enum_path: RustPath,
+    /// Generic flags type produced by `BitOr` of the enum values
+    pub flags_path: RustPath,
+}
+
+/// `Debug` implementation based on a C++ `operator<<`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RustDebugImpl {
+    /// Type that implements `Debug`.
+    pub target_path: RustPath,
+    /// FFI function that prints the value to a callback.
+    pub ffi_function_path: RustPath,
+}
+
+/// Conversion of a string type from or to Rust strings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RustStringConversionImpl {
+    /// Wrapper type of the string class.
+    pub target_path: RustPath,
+    /// FFI function performing the conversion.
+    pub ffi_function_path: RustPath,
+}
+
+/// `iter()` or `iter_mut()` method of a container that provides
+/// `begin()` and `end()` functions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RustIteratorImpl {
+    /// Wrapper type of the container.
+    pub target_path: RustPath,
+    /// Type of the C++ iterator returned by `begin()`.
+    pub begin_type: RustType,
+    /// Type of the C++ iterator returned by `end()`.
+    pub end_type: RustType,
+    /// True for `iter_mut()` based on non-const `begin()` and `end()`.
+    pub is_mut: bool,
+}
+
+/// `PartialEq`, `Eq`, `PartialOrd` and `Ord` implementations of a type
+/// based on its C++ comparison operators (see `Config::set_total_order_impls`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RustTotalOrderImpl {
+    /// Wrapper type of the class.
+    pub target_path: RustPath,
+    /// Right hand side type of the `PartialEq`, `Lt` and `Gt` implementations
+    /// of the type.
+    pub other_type: RustType,
+}
+
+/// `as_<base>()` and `as_<base>_mut()` methods of a class converting it
+/// to one of its public bases other than the `Deref` target.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RustBaseCastImpl {
+    /// Wrapper type of the derived class.
+    pub target_path: RustPath,
+    /// Wrapper type of the base class.
+    pub base_path: RustPath,
+    /// FFI function performing `static_cast` to the base class.
+    pub ffi_function_path: RustPath,
+    /// Name of the method returning a const reference.
+    pub name: String,
+}
+
+/// Generic function accepting arguments of all overloads of a C++ function
+/// (see `OverloadingStrategy::Traits`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RustOverloadedFunction {
+    /// Path of the generic function.
+    pub path: RustPath,
+    /// Path of the trait implemented for accepted argument types.
+    pub trait_path: RustPath,
+    /// Type containing the function, or `None` for a free function.
+    pub impl_type: Option<RustPath>,
+    /// Type of `self` argument (`&T` or `&mut T`), if any.
+    pub self_type: Option<RustType>,
+    /// True if the function is `unsafe`.
+    pub is_unsafe: bool,
+}
+
+/// Implementation of the trait of a `RustOverloadedFunction`
+/// for arguments of one of the overloads.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RustOverload {
+    /// Path of the implemented trait.
+    pub trait_path: RustPath,
+    /// Type of `self` argument of the overloaded function, if any.
+    pub self_type: Option<RustType>,
+    /// Types of arguments of the overload, excluding `self`.
+    pub argument_types: Vec<RustType>,
+    pub return_type: RustType,
+    /// Wrapper function of the overload.
+    pub function_path: RustPath,
+    /// True if the trait function is `unsafe`.
+    pub is_unsafe: bool,
+}
+
+impl RustOverload {
+    /// Returns the type implementing the trait: `()` if there are no arguments,
+    /// the argument type if there is one argument and a tuple otherwise.
+    pub fn arguments_type(&self) -> RustType {
+        if self.argument_types.len() == 1 {
+            self.argument_types[0].clone()
+        } else {
+            RustType::Tuple(self.argument_types.clone())
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum RustExtraImplKind {
     FlagEnum(RustFlagEnumImpl),
     QtReceiverImpl(RustQtReceiverImpl),
+    Debug(RustDebugImpl),
+    /// `cpp_core::FromUtf8` implementation
+    FromUtf8(RustStringConversionImpl),
+    /// `to_std_string()` method
+    ToStdString(RustStringConversionImpl),
+    /// `iter()` or `iter_mut()` method
+    Iterator(RustIteratorImpl),
+    /// Comparison traits for a totally ordered type
+    TotalOrder(RustTotalOrderImpl),
+    /// `as_<base>()` and `as_<base>_mut()` methods
+    BaseCast(RustBaseCastImpl),
+    /// Generic function and its trait
+    OverloadedFunction(RustOverloadedFunction),
+    /// Trait implementation for arguments of an overload
+    Overload(RustOverload),
 }
 
 impl RustExtraImplKind {
@@ -494,6 +842,23 @@ impl RustExtraImplKind {
             RustExtraImplKind::QtReceiverImpl(_) => {
                 matches!(other, RustExtraImplKind::QtReceiverImpl(_))
             }
+            RustExtraImplKind::Debug(_) => matches!(other, RustExtraImplKind::Debug(_)),
+            RustExtraImplKind::FromUtf8(_) => matches!(other, RustExtraImplKind::FromUtf8(_)),
+            RustExtraImplKind::ToStdString(_) => {
+                matches!(other, RustExtraImplKind::ToStdString(_))
+            }
+            RustExtraImplKind::Iterator(_) => matches!(other, RustExtraImplKind::Iterator(_)),
+            RustExtraImplKind::TotalOrder(_) => matches!(other, RustExtraImplKind::TotalOrder(_)),
+            RustExtraImplKind::BaseCast(_) => matches!(other, RustExtraImplKind::BaseCast(_)),
+            // generic functions don't have a source item
+            RustExtraImplKind::OverloadedFunction(data) => {
+                if let RustExtraImplKind::OverloadedFunction(other) = other {
+                    data.trait_path == other.trait_path
+                } else {
+                    false
+                }
+            }
+            RustExtraImplKind::Overload(_) => matches!(other, RustExtraImplKind::Overload(_)),
         }
     }
 }
@@ -506,7 +871,14 @@ pub struct RustExtraImpl {
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum RustReexportSource {
-    DependencyCrate { crate_name: String },
+    DependencyCrate {
+        crate_name: String,
+    },
+    /// All items of the module of the namespace are re-exported
+    /// (see `Config::set_crate_root_namespace`)
+    CppNamespace {
+        cpp_path: CppPath,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -529,6 +901,109 @@ pub enum RustItem {
 }
 
 impl RustItem {
+    /// Returns paths of all types and functions used by this item,
+    /// including the type containing this item, if any.
+    pub fn referenced_paths(&self) -> Result<Vec<RustPath>> {
+        let mut output = Vec::new();
+        match self {
+            RustItem::Module(_) => {}
+            RustItem::Struct(data) => {
+                match &data.kind {
+                    RustStructKind::WrapperType(kind) => match kind {
+                        RustWrapperTypeKind::EnumWrapper { underlying_type } => {
+                            underlying_type.collect_paths(&mut output);
+                        }
+                        RustWrapperTypeKind::MovableClassWrapper { sized_type_path } => {
+                            output.push(sized_type_path.clone());
+                        }
+                        RustWrapperTypeKind::ImmovableClassWrapper
+                        | RustWrapperTypeKind::OpaqueClassWrapper => {}
+                    },
+                    RustStructKind::QtSlotWrapper(wrapper) => {
+                        for arg in &wrapper.arguments {
+                            arg.collect_paths(&mut output);
+                        }
+                        output.push(wrapper.raw_slot_wrapper.clone());
+                    }
+                    RustStructKind::SizedType(_) | RustStructKind::FlagsType(_) => {}
+                }
+                if let Some(receiver_data) = &data.qt_receiver_data {
+                    for arg in &receiver_data.arguments {
+                        arg.collect_paths(&mut output);
+                    }
+                }
+            }
+            RustItem::EnumValue(data) => output.push(data.path.parent()?),
+            RustItem::TraitImpl(data) => {
+                data.target_type.collect_paths(&mut output);
+                data.trait_type.collect_paths(&mut output);
+                for associated_type in &data.associated_types {
+                    associated_type.value.collect_paths(&mut output);
+                }
+                for function in &data.functions {
+                    function.collect_paths(&mut output);
+                }
+            }
+            RustItem::ExtraImpl(data) => match &data.kind {
+                RustExtraImplKind::FlagEnum(data) => {
+                    output.push(data.enum_path.clone());
+                    output.push(data.flags_path.clone());
+                }
+                RustExtraImplKind::QtReceiverImpl(data) => {
+                    output.push(data.target_path.clone());
+                    data.arguments.collect_paths(&mut output);
+                }
+                RustExtraImplKind::Debug(data) => {
+                    output.push(data.target_path.clone());
+                    output.push(data.ffi_function_path.clone());
+                }
+                RustExtraImplKind::FromUtf8(data) | RustExtraImplKind::ToStdString(data) => {
+                    output.push(data.target_path.clone());
+                    output.push(data.ffi_function_path.clone());
+                }
+                RustExtraImplKind::Iterator(data) => {
+                    output.push(data.target_path.clone());
+                    data.begin_type.collect_paths(&mut output);
+                    data.end_type.collect_paths(&mut output);
+                }
+                RustExtraImplKind::TotalOrder(data) => {
+                    output.push(data.target_path.clone());
+                    data.other_type.collect_paths(&mut output);
+                }
+                RustExtraImplKind::BaseCast(data) => {
+                    output.push(data.target_path.clone());
+                    output.push(data.base_path.clone());
+                    output.push(data.ffi_function_path.clone());
+                }
+                RustExtraImplKind::OverloadedFunction(data) => {
+                    if let Some(impl_type) = &data.impl_type {
+                        output.push(impl_type.clone());
+                    }
+                    if let Some(self_type) = &data.self_type {
+                        self_type.collect_paths(&mut output);
+                    }
+                }
+                RustExtraImplKind::Overload(data) => {
+                    output.push(data.trait_path.clone());
+                    if let Some(self_type) = &data.self_type {
+                        self_type.collect_paths(&mut output);
+                    }
+                    for arg in &data.argument_types {
+                        arg.collect_paths(&mut output);
+                    }
+                    data.return_type.collect_paths(&mut output);
+                    output.push(data.function_path.clone());
+                }
+            },
+            RustItem::Function(data) => {
+                output.push(data.path.parent()?);
+                data.collect_paths(&mut output);
+            }
+            RustItem::Reexport(data) => output.push(data.target.clone()),
+        }
+        Ok(output)
+    }
+
     pub fn path(&self) -> Option<&RustPath> {
         match self {
             RustItem::Module(data) => Some(&data.path),
@@ -620,7 +1095,9 @@ impl RustItem {
         match self {
             RustItem::Module(data) => {
                 if let RustItem::Module(other) = other {
+                    // header modules don't have a source item
                     data.kind == other.kind
+                        && (data.kind != RustModuleKind::CppHeader || data.path == other.path)
                 } else {
                     false
                 }
@@ -731,11 +1208,16 @@ impl RustItem {
             ),
             RustItem::ExtraImpl(data) => format!("extra impl {:?}", data.kind),
             RustItem::Function(data) => format!("fn {}", data.path.full_name(None)),
-            RustItem::Reexport(data) => format!(
-                "use {} as {}",
-                data.path.full_name(None),
-                data.target.last()
-            ),
+            RustItem::Reexport(data) => match &data.source {
+                RustReexportSource::DependencyCrate { .. } => format!(
+                    "use {} as {}",
+                    data.path.full_name(None),
+                    data.target.last()
+                ),
+                RustReexportSource::CppNamespace { .. } => {
+                    format!("use {}::*", data.target.full_name(None))
+                }
+            },
         }
     }
 }
@@ -790,3 +1272,171 @@ impl NameType<'_> {
         matches!(self, NameType::Module { .. })
     }
 }
+
+/// Cargo features required by the items of the generated crate
+/// (see `Config::add_feature`).
+#[derive(Debug, Default)]
+pub struct RustFeatures {
+    /// Features that must be enabled for each item.
+    /// Items that are not listed here belong to the core of the crate.
+    item_features: HashMap<ItemId, BTreeSet<String>>,
+    /// Features enabled by each of the configured features.
+    dependencies: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl RustFeatures {
+    /// Assigns items to the features of their C++ headers and computes
+    /// dependencies between features.
+    ///
+    /// An item that uses items of a feature requires that feature as well.
+    /// If that item belongs to another feature, this feature is made dependent
+    /// on the used one instead. A module requires the features that all of its
+    /// items require.
+    pub fn new(db: &DatabaseClient, config: &Config) -> Result<Self> {
+        let mut output = RustFeatures::default();
+        if config.features().is_empty() {
+            return Ok(output);
+        }
+        for feature in config.features() {
+            output
+                .dependencies
+                .insert(feature.name().to_string(), BTreeSet::new());
+        }
+
+        let items = db.rust_items().collect_vec();
+        let indexes = items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| (item.id.clone(), index))
+            .collect::<HashMap<_, _>>();
+        let mut own_features = Vec::new();
+        let mut references = Vec::new();
+        for item in &items {
+            let own_feature = match item.item {
+                RustItem::Module(_) | RustItem::Reexport(_) => None,
+                _ => db
+                    .root_item_origin(&item.id)?
+                    .and_then(|origin| {
+                        config.header_feature(
+                            origin.grouping_include_file(config.group_members_by_class_header()),
+                        )
+                    })
+                    .map(String::from),
+            };
+            own_features.push(own_feature);
+
+            let mut item_references = Vec::new();
+            for path in item.item.referenced_paths()? {
+                let target = match db.find_rust_item(&path) {
+                    Some(target) => target,
+                    None => continue,
+                };
+                if target.item.as_module_ref().is_some() {
+                    continue;
+                }
+                if let Some(&index) = indexes.get(&target.id) {
+                    item_references.push(index);
+                }
+            }
+            references.push(item_references);
+        }
+
+        let mut required = own_features
+            .iter()
+            .map(|feature| feature.iter().cloned().collect::<BTreeSet<_>>())
+            .collect_vec();
+        loop {
+            let mut changed = false;
+            for index in 0..items.len() {
+                for &reference in &references[index] {
+                    if reference == index {
+                        continue;
+                    }
+                    for feature in required[reference].clone() {
+                        if let Some(own_feature) = &own_features[index] {
+                            if own_feature != &feature {
+                                output
+                                    .dependencies
+                                    .get_mut(own_feature)
+                                    .expect("all features must be listed")
+                                    .insert(feature);
+                            }
+                        } else {
+                            changed |= required[index].insert(feature);
+                        }
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        for (index, item) in items.iter().enumerate() {
+            let module = match item.item.as_module_ref() {
+                Some(module) => module,
+                None => continue,
+            };
+            let mut module_features: Option<BTreeSet<String>> = None;
+            for (other_index, other) in items.iter().enumerate() {
+                if other.item.as_module_ref().is_some() || other.item.as_reexport_ref().is_some() {
+                    continue;
+                }
+                let parent = other.item.parent_path()?;
+                if parent != module.path && !module.path.includes(&parent) {
+                    continue;
+                }
+                let features = &required[other_index];
+                module_features = Some(match module_features {
+                    Some(previous) => previous.intersection(features).cloned().collect(),
+                    None => features.clone(),
+                });
+            }
+            required[index] = module_features.unwrap_or_default();
+        }
+        for (index, item) in items.iter().enumerate() {
+            if let Some(reexport) = item.item.as_reexport_ref() {
+                if let Some(target) = db.find_rust_item(&reexport.target) {
+                    if let Some(&target_index) = indexes.get(&target.id) {
+                        required[index] = required[target_index].clone();
+                    }
+                }
+            }
+        }
+
+        for (feature, dependencies) in &output.dependencies {
+            let mut visited = BTreeSet::new();
+            let mut queue = dependencies.iter().collect_vec();
+            while let Some(dependency) = queue.pop() {
+                if dependency == feature {
+                    bail!(
+                        "feature {:?} depends on itself (through features: {:?}); \
+                         move the headers that use each other into the same feature",
+                        feature,
+                        visited
+                    );
+                }
+                if visited.insert(dependency) {
+                    queue.extend(&output.dependencies[dependency]);
+                }
+            }
+        }
+
+        for (item, features) in items.iter().zip(required) {
+            if !features.is_empty() {
+                output.item_features.insert(item.id.clone(), features);
+            }
+        }
+        Ok(output)
+    }
+
+    /// Returns features that must be enabled for the item.
+    pub fn item_features(&self, id: &ItemId) -> Option<&BTreeSet<String>> {
+        self.item_features.get(id)
+    }
+
+    /// Returns all configured features and features each of them depends on.
+    pub fn dependencies(&self) -> &BTreeMap<String, BTreeSet<String>> {
+        &self.dependencies
+    }
+}
@@ -129,6 +129,12 @@ impl RustPath {
     }
 }
 
+/// Removes the raw identifier prefix (`r#type` becomes `type`).
+/// Names with the prefix can't be used as a part of other names.
+pub fn unraw_identifier(name: &str) -> &str {
+    name.strip_prefix("r#").unwrap_or(name)
+}
+
 /// Conversion from public Rust API type to
 /// the corresponding FFI type
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -139,6 +145,11 @@ pub enum RustToFfiTypeConversion {
     RefToPtr {
         lifetime: Option<String>,
     },
+    /// `&T` to `*const T` or `&mut T` to `*mut T`
+    /// (used when borrow checking is enabled)
+    BorrowToPtr {
+        lifetime: Option<String>,
+    },
     /// `Ptr<T>` to `*const T` (or similar mutable type)
     UtilsPtrToPtr {},
     /// `Ref<T>` to `*const T` (or similar mutable types)
@@ -151,6 +162,8 @@ pub enum RustToFfiTypeConversion {
     ValueToPtr,
     /// `CppBox<T>` to `*mut T`
     CppBoxToPtr,
+    /// `Option<CppBox<T>>` to `*mut T`
+    OptionCppBoxToPtr,
     /// `QBox<T>` to `*mut T`
     QBoxToPtr,
     /// `qt_core::flags::Flags<T>` to `c_int`
@@ -166,7 +179,11 @@ pub enum RustToFfiTypeConversion {
     /// Rust public type has an additional reference (`&`)
     RefTo(Box<RustToFfiTypeConversion>),
     ImplCastInto(Box<RustToFfiTypeConversion>),
+    /// `impl Into<cpp_core::StringArg<T>>` to `*const T`
+    StringArgToPtr,
     ClosureToCallback(Box<RustClosureToCallbackConversion>),
+    /// `cpp_core::CallbackGuard` to `*mut c_void`
+    CallbackGuardToPtr,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -222,6 +239,14 @@ fn utils_ref(ffi_type: &RustType) -> Result<RustType> {
     }))
 }
 
+pub fn string_arg(ffi_type: &RustType) -> Result<RustType> {
+    let target = ffi_type.pointer_like_to_target()?;
+    Ok(RustType::Common(RustCommonType {
+        path: RustPath::from_good_str("cpp_core::StringArg"),
+        generic_arguments: Some(vec![target]),
+    }))
+}
+
 fn class_type_to_qt_core_crate_path(t: &RustType) -> Result<RustPath> {
     if let RustType::Common(t) = t {
         let name = if t.path.crate_name().starts_with("moqt") {
@@ -252,6 +277,22 @@ impl RustFinalType {
                     bail!("not a pointer like type");
                 }
             }
+            RustToFfiTypeConversion::BorrowToPtr { lifetime } => {
+                if let RustType::PointerLike {
+                    is_const, target, ..
+                } = &ffi_type
+                {
+                    RustType::PointerLike {
+                        is_const: *is_const,
+                        kind: RustPointerLikeTypeKind::Reference {
+                            lifetime: lifetime.clone(),
+                        },
+                        target: target.clone(),
+                    }
+                } else {
+                    bail!("not a pointer like type");
+                }
+            }
             RustToFfiTypeConversion::UtilsPtrToPtr {} => utils_ptr(&ffi_type)?,
             RustToFfiTypeConversion::UtilsRefToPtr {} => utils_ref(&ffi_type)?,
             RustToFfiTypeConversion::OptionUtilsRefToPtr {} => {
@@ -265,6 +306,13 @@ impl RustFinalType {
                     generic_arguments: Some(vec![target]),
                 })
             }
+            RustToFfiTypeConversion::OptionCppBoxToPtr => {
+                let target = ffi_type.pointer_like_to_target()?;
+                RustType::new_option(RustType::Common(RustCommonType {
+                    path: RustPath::from_good_str("cpp_core::CppBox"),
+                    generic_arguments: Some(vec![target]),
+                }))
+            }
             RustToFfiTypeConversion::QBoxToPtr => {
                 let target = ffi_type.pointer_like_to_target()?;
                 RustType::Common(RustCommonType {
@@ -294,9 +342,20 @@ impl RustFinalType {
                 };
                 RustType::ImplTrait(trait_type)
             }
+            RustToFfiTypeConversion::StringArgToPtr => {
+                let trait_type = RustCommonType {
+                    path: RustPath::from_good_str("std::convert::Into"),
+                    generic_arguments: Some(vec![string_arg(&ffi_type)?]),
+                };
+                RustType::ImplTrait(trait_type)
+            }
             RustToFfiTypeConversion::ClosureToCallback { .. } => {
                 RustType::GenericParameter("T".into())
             }
+            RustToFfiTypeConversion::CallbackGuardToPtr => RustType::Common(RustCommonType {
+                path: RustPath::from_good_str("cpp_core::CallbackGuard"),
+                generic_arguments: None,
+            }),
         };
         Ok(RustFinalType {
             api_type,
@@ -309,6 +368,18 @@ impl RustFinalType {
         &self.api_type
     }
 
+    /// Adds paths of all named types used in the API and FFI types to `output`.
+    pub fn collect_paths(&self, output: &mut Vec<RustPath>) {
+        self.api_type.collect_paths(output);
+        self.ffi_type.collect_paths(output);
+        if let Some(callback) = self.conversion.as_callback_ref() {
+            for arg in &callback.closure_arguments {
+                arg.collect_paths(output);
+            }
+            callback.closure_return_type.collect_paths(output);
+        }
+    }
+
     pub fn ffi_type(&self) -> &RustType {
         &self.ffi_type
     }
@@ -318,15 +389,20 @@ impl RustFinalType {
     }
 
     pub fn with_lifetime(&self, lifetime: String) -> Result<Self> {
-        if let RustToFfiTypeConversion::RefToPtr { .. } = &self.conversion {
-            RustFinalType::new(
+        match &self.conversion {
+            RustToFfiTypeConversion::RefToPtr { .. } => RustFinalType::new(
                 self.ffi_type.clone(),
                 RustToFfiTypeConversion::RefToPtr {
                     lifetime: Some(lifetime),
                 },
-            )
-        } else {
-            bail!("not a RefToPtr type");
+            ),
+            RustToFfiTypeConversion::BorrowToPtr { .. } => RustFinalType::new(
+                self.ffi_type.clone(),
+                RustToFfiTypeConversion::BorrowToPtr {
+                    lifetime: Some(lifetime),
+                },
+            ),
+            _ => bail!("not a RefToPtr type"),
         }
     }
 }
@@ -357,60 +433,69 @@ pub struct RustCommonType {
     pub generic_arguments: Option<Vec<RustType>>,
 }
 
+/// Types that are aliases of primitive types, and primitive types
+/// they can be on different platforms.
+const PRIMITIVE_ALIASES: &[(&str, &[&str])] = &[
+    ("std::os::raw::c_char", &["i8", "u8"]),
+    ("std::os::raw::c_schar", &["i8"]),
+    ("std::os::raw::c_uchar", &["u8"]),
+    ("std::os::raw::c_short", &["i16"]),
+    ("std::os::raw::c_ushort", &["u16"]),
+    ("std::os::raw::c_int", &["i32"]),
+    ("std::os::raw::c_uint", &["u32"]),
+    ("std::os::raw::c_long", &["i32", "i64"]),
+    ("std::os::raw::c_ulong", &["u32", "u64"]),
+    ("std::os::raw::c_longlong", &["i64"]),
+    ("std::os::raw::c_ulonglong", &["u64"]),
+    ("std::os::raw::c_float", &["f32"]),
+    ("std::os::raw::c_double", &["f64"]),
+    ("cpp_core::wchar_t", &["i16", "u16", "i32", "u32"]),
+    ("cpp_core::char16_t", &["u16"]),
+    ("cpp_core::char32_t", &["u32"]),
+];
+
+fn primitive_alias_targets<T>(path: &T) -> Option<&'static [&'static str]>
+where
+    T: for<'a> PartialEq<&'a str>,
+{
+    PRIMITIVE_ALIASES
+        .iter()
+        .find(|(alias, _)| path == alias)
+        .map(|(_, targets)| *targets)
+}
+
+/// Returns true if `one` and `other` can be the same type on some platform,
+/// e.g. `c_int` and `c_long`, or `c_long` and `i64`.
 pub fn paths_can_be_same<T1, T2>(one: &T1, other: &T2) -> bool
 where
     T1: for<'a> PartialEq<&'a str> + PartialEq<T2>,
     T2: for<'a> PartialEq<&'a str>,
 {
-    let colliding: &[(&[&str], &[&str])] = &[
-        (
-            &[
-                "std::os::raw::c_char",
-                "std::os::raw::c_schar",
-                "std::os::raw::c_short",
-                "std::os::raw::c_int",
-                "std::os::raw::c_long",
-                "std::os::raw::c_longlong",
-            ],
-            &["u8", "u16", "u32", "u64"],
-        ),
-        (
-            &[
-                "std::os::raw::c_char",
-                "std::os::raw::c_uchar",
-                "std::os::raw::c_ushort",
-                "std::os::raw::c_uint",
-                "std::os::raw::c_ulong",
-                "std::os::raw::c_ulonglong",
-            ],
-            &["u8", "u16", "u32", "u64"],
-        ),
-        (
-            &["std::os::raw::c_float", "std::os::raw::c_double"],
-            &["f32", "f64"],
-        ),
-    ];
-
     if one == other {
         return true;
     }
 
-    for (ambiguous, concrete) in colliding {
-        if ambiguous.iter().any(|s| one == s) && ambiguous.iter().any(|s| other == s) {
-            return true;
-        }
-        if ambiguous.iter().any(|s| one == s) && concrete.iter().any(|s| other == s) {
-            return true;
-        }
-        if concrete.iter().any(|s| one == s) && ambiguous.iter().any(|s| other == s) {
-            return true;
+    match (primitive_alias_targets(one), primitive_alias_targets(other)) {
+        (Some(one_targets), Some(other_targets)) => {
+            one_targets.iter().any(|t| other_targets.contains(t))
         }
+        (Some(one_targets), None) => one_targets.iter().any(|t| other == t),
+        (None, Some(other_targets)) => other_targets.iter().any(|t| one == t),
+        (None, None) => false,
     }
-
-    false
 }
 
 impl RustCommonType {
+    /// Adds paths of this type and its generic arguments to `output`.
+    pub fn collect_paths(&self, output: &mut Vec<RustPath>) {
+        output.push(self.path.clone());
+        if let Some(args) = &self.generic_arguments {
+            for arg in args {
+                arg.collect_paths(output);
+            }
+        }
+    }
+
     pub fn can_be_same_as(&self, other: &RustCommonType) -> bool {
         let self_args = self
             .generic_arguments
@@ -458,9 +543,35 @@ pub enum RustType {
     },
     ImplTrait(RustCommonType),
     GenericParameter(String),
+    /// Fixed-size array, like `[f32; 16]`
+    Array {
+        element_type: Box<RustType>,
+        size: usize,
+    },
 }
 
 impl RustType {
+    /// Adds paths of all named types used in this type to `output`.
+    pub fn collect_paths(&self, output: &mut Vec<RustPath>) {
+        match self {
+            RustType::Tuple(types) => {
+                for t in types {
+                    t.collect_paths(output);
+                }
+            }
+            RustType::Primitive(_) | RustType::GenericParameter(_) => {}
+            RustType::Common(t) | RustType::ImplTrait(t) => t.collect_paths(output),
+            RustType::FunctionPointer(t) => {
+                t.return_type.collect_paths(output);
+                for arg in &t.arguments {
+                    arg.collect_paths(output);
+                }
+            }
+            RustType::PointerLike { target, .. } => target.collect_paths(output),
+            RustType::Array { element_type, .. } => element_type.collect_paths(output),
+        }
+    }
+
     /// Constructs the unit type `()`, used as the replacement of C++'s `void` type.
     pub fn unit() -> Self {
         RustType::Tuple(Vec::new())
@@ -581,6 +692,9 @@ impl RustType {
                 name
             }
             RustType::FunctionPointer { .. } => "fn".to_string(),
+            RustType::Array { element_type, .. } => {
+                format!("{}_array", element_type.caption(context, strategy)?)
+            }
             RustType::ImplTrait(trait_type) => {
                 if trait_type.path == RustPath::from_good_str("cpp_core::CastInto") {
                     trait_type
@@ -669,6 +783,7 @@ impl RustType {
                 false
             }
             RustType::Tuple(types) => types.iter().any(RustType::is_unsafe_argument),
+            RustType::Array { element_type, .. } => element_type.is_unsafe_argument(),
             RustType::FunctionPointer(function) => {
                 function.return_type.is_unsafe_argument()
                     || function.arguments.iter().any(RustType::is_unsafe_argument)
@@ -775,6 +890,17 @@ impl RustType {
                 }
             }
             RustType::GenericParameter(_) => self == other,
+            RustType::Array { element_type, size } => {
+                if let RustType::Array {
+                    element_type: other_element_type,
+                    size: other_size,
+                } = other
+                {
+                    size == other_size && element_type.can_be_same_as(other_element_type)
+                } else {
+                    false
+                }
+            }
         }
     }
 }
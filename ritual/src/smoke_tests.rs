@@ -0,0 +1,277 @@
+//! Generator of smoke tests for the generated crate.
+//!
+//! The tests only use items that are present in the database and passed
+//! `cpp_checker` in all environments, so they compile regardless of which
+//! items were rejected by the previous steps.
+
+use crate::config::Config;
+use crate::cpp_data::{CppPath, CppVisibility};
+use crate::cpp_function::CppFunctionKind;
+use crate::database::DatabaseClient;
+use crate::rust_info::{RustFunctionKind, RustStructKind, RustWrapperTypeKind};
+use crate::rust_type::{unraw_identifier, RustPath};
+use itertools::Itertools;
+use ritual_common::errors::Result;
+use ritual_common::file_utils::{create_dir_all, create_file};
+use ritual_common::string_utils::CaseOperations;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::Path;
+
+/// Name of the generated file in `tests` directory of the crate.
+pub const SMOKE_TESTS_FILE_NAME: &str = "smoke_tests.rs";
+
+/// Default constructor of a class and its const getters without arguments.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ClassTest {
+    pub struct_path: RustPath,
+    pub constructor: RustPath,
+    pub getters: Vec<RustPath>,
+}
+
+/// Enum and its values.
+#[derive(Debug, PartialEq, Eq)]
+pub struct EnumTest {
+    pub enum_path: RustPath,
+    pub values: Vec<RustPath>,
+}
+
+/// Items used by the smoke tests.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SmokeTests {
+    pub classes: Vec<ClassTest>,
+    pub enums: Vec<EnumTest>,
+}
+
+struct Generator<'a> {
+    db: &'a DatabaseClient,
+    config: &'a Config,
+}
+
+impl Generator<'_> {
+    fn is_blocked(&self, cpp_path: &CppPath) -> bool {
+        let name = cpp_path.to_templateless_string();
+        self.config
+            .smoke_test_blocked_name_patterns()
+            .iter()
+            .any(|pattern| pattern.is_match(&name))
+    }
+
+    fn class_tests(&self) -> Result<Vec<ClassTest>> {
+        let mut tests = Vec::new();
+        let mut getters = HashMap::<RustPath, Vec<RustPath>>::new();
+        for item in self.db.rust_items() {
+            let function = match item.item.as_function_ref() {
+                Some(function) => function,
+                None => continue,
+            };
+            if !function.is_public
+                || !matches!(function.kind, RustFunctionKind::FfiWrapper(_))
+                || !self.db.is_reachable(&function.path)
+                || !self.db.is_always_available(&item.id)?
+            {
+                continue;
+            }
+            let cpp_function = match self
+                .db
+                .source_cpp_item(&item.id)?
+                .and_then(|cpp_item| cpp_item.item.as_function_ref())
+            {
+                Some(cpp_function) => cpp_function,
+                None => continue,
+            };
+            let member = match &cpp_function.member {
+                Some(member) if member.visibility == CppVisibility::Public => member,
+                _ => continue,
+            };
+            if !cpp_function.arguments.is_empty() || cpp_function.operator.is_some() {
+                continue;
+            }
+            let struct_path = function.path.parent()?;
+            match member.kind {
+                CppFunctionKind::Constructor => {
+                    if function.arguments.is_empty()
+                        && !self.is_blocked(&cpp_function.class_path()?)
+                    {
+                        tests.push(ClassTest {
+                            struct_path,
+                            constructor: function.path.clone(),
+                            getters: Vec::new(),
+                        });
+                    }
+                }
+                CppFunctionKind::Regular => {
+                    let is_getter = member.is_const
+                        && !member.is_static
+                        && !member.is_signal
+                        && !member.is_slot
+                        && !cpp_function.return_type.is_void()
+                        && function.arguments.len() == 1
+                        && function.arguments[0].name == "self";
+                    if is_getter {
+                        getters
+                            .entry(struct_path)
+                            .or_default()
+                            .push(function.path.clone());
+                    }
+                }
+                CppFunctionKind::Destructor => {}
+            }
+        }
+        for test in &mut tests {
+            if let Some(getters) = getters.remove(&test.struct_path) {
+                test.getters = getters;
+            }
+        }
+        Ok(tests)
+    }
+
+    /// Returns paths of enums and their values.
+    fn enum_tests(&self) -> Result<Vec<EnumTest>> {
+        let mut tests = Vec::new();
+        for item in self.db.rust_items() {
+            let rust_struct = match item.item.as_struct_ref() {
+                Some(rust_struct) => rust_struct,
+                None => continue,
+            };
+            if !matches!(
+                rust_struct.kind,
+                RustStructKind::WrapperType(RustWrapperTypeKind::EnumWrapper { .. })
+            ) || !rust_struct.is_public
+                || !self.db.is_reachable(&rust_struct.path)
+            {
+                continue;
+            }
+            let is_blocked = self
+                .db
+                .source_cpp_item(&item.id)?
+                .and_then(|cpp_item| cpp_item.item.path())
+                .map_or(true, |cpp_path| self.is_blocked(cpp_path));
+            if is_blocked {
+                continue;
+            }
+            let mut values = Vec::new();
+            for value in self.db.rust_children(&rust_struct.path) {
+                if value.item.as_enum_value_ref().is_some()
+                    && self.db.is_always_available(&value.id)?
+                {
+                    values.push(
+                        value
+                            .item
+                            .path()
+                            .expect("enum value must have path")
+                            .clone(),
+                    );
+                }
+            }
+            if !values.is_empty() {
+                tests.push(EnumTest {
+                    enum_path: rust_struct.path.clone(),
+                    values,
+                });
+            }
+        }
+        Ok(tests)
+    }
+}
+
+/// Returns a unique name of the test function for the item at `path`.
+fn test_name(prefix: &str, path: &RustPath, used_names: &mut HashSet<String>) -> String {
+    let base_name = format!(
+        "{}_{}",
+        prefix,
+        path.parts[1..]
+            .iter()
+            .map(|part| unraw_identifier(part).to_snake_case())
+            .join("_")
+    );
+    let mut name = base_name.clone();
+    let mut index = 1;
+    while !used_names.insert(name.clone()) {
+        index += 1;
+        name = format!("{}_{}", base_name, index);
+    }
+    name
+}
+
+/// Returns items used by the smoke tests.
+pub fn collect_tests(db: &DatabaseClient, config: &Config) -> Result<SmokeTests> {
+    let generator = Generator { db, config };
+    Ok(SmokeTests {
+        classes: generator.class_tests()?,
+        enums: generator.enum_tests()?,
+    })
+}
+
+/// Returns code of the smoke tests, or `None` if there is nothing to test.
+pub fn generate_code(db: &DatabaseClient, config: &Config) -> Result<Option<String>> {
+    let tests = collect_tests(db, config)?;
+    if tests.classes.is_empty() && tests.enums.is_empty() {
+        return Ok(None);
+    }
+
+    let mut code = String::new();
+    let mut used_names = HashSet::new();
+    writeln!(code, "//! Smoke tests generated by ritual.")?;
+    writeln!(code)?;
+    writeln!(code, "#![allow(unused_unsafe)]")?;
+    for test in &tests.classes {
+        writeln!(code)?;
+        writeln!(code, "#[test]")?;
+        writeln!(
+            code,
+            "fn {}() {{",
+            test_name("class", &test.struct_path, &mut used_names)
+        )?;
+        writeln!(code, "    unsafe {{")?;
+        writeln!(
+            code,
+            "        let object = {}();",
+            test.constructor.full_name(None)
+        )?;
+        for getter in &test.getters {
+            writeln!(code, "        let _ = {}(&object);", getter.full_name(None))?;
+        }
+        writeln!(code, "        drop(object);")?;
+        writeln!(code, "    }}")?;
+        writeln!(code, "}}")?;
+    }
+    for test in &tests.enums {
+        let enum_name = test.enum_path.full_name(None);
+        writeln!(code)?;
+        writeln!(code, "#[test]")?;
+        writeln!(
+            code,
+            "fn {}() {{",
+            test_name("enum", &test.enum_path, &mut used_names)
+        )?;
+        for value in &test.values {
+            writeln!(code, "    let value = {};", value.full_name(None))?;
+            writeln!(
+                code,
+                "    let converted: Result<{}, _> = \
+                 ::std::convert::TryFrom::try_from(value.to_int());",
+                enum_name
+            )?;
+            writeln!(code, "    assert_eq!(converted.ok(), Some(value));")?;
+        }
+        writeln!(code, "}}")?;
+    }
+    Ok(Some(code))
+}
+
+/// Writes smoke tests of the crate to `tests` directory in `crate_path`
+/// if they are enabled in `config`.
+pub fn generate(db: &DatabaseClient, config: &Config, crate_path: &Path) -> Result<()> {
+    if !config.write_smoke_tests() {
+        return Ok(());
+    }
+    if let Some(code) = generate_code(db, config)? {
+        let tests_path = crate_path.join("tests");
+        create_dir_all(&tests_path)?;
+        let mut file = create_file(tests_path.join(SMOKE_TESTS_FILE_NAME))?;
+        write!(file, "{}", code)?;
+    }
+    Ok(())
+}
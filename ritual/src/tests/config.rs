@@ -0,0 +1,258 @@
+use crate::config::{
+    Config, CrateProperties, InheritedMethodsStrategy, TemplateArgument, TemplateArgumentName,
+};
+use crate::cpp_data::CppPath;
+use crate::cpp_type::{CppBuiltInNumericType, CppType};
+use crate::processor;
+use crate::progress::ProgressEvent;
+use crate::tests::{try_run_steps, CollectingProgressHandler};
+use itertools::Itertools;
+use ritual_common::toml;
+use std::sync::Arc;
+
+#[test]
+fn custom_toml() {
+    let mut properties = CrateProperties::new("A", "0.1.0");
+    properties
+        .add_custom_toml(
+            r#"
+            [package.metadata.docs.rs]
+            features = ["ritual_rustdoc"]
+
+            [features]
+            extra = []
+            default = ["extra"]
+
+            [dependencies]
+            libc = "0.2"
+            serde = { version = "^1.0.84", features = ["derive"] }
+            "#,
+        )
+        .unwrap();
+    properties
+        .add_custom_toml("[features]\ndefault = [\"more\"]\nmore = []")
+        .unwrap();
+
+    let fields = properties.custom_fields();
+    let features = fields["features"].as_table().unwrap();
+    assert_eq!(
+        features["default"],
+        toml::Value::Array(vec!["extra".into(), "more".into()])
+    );
+    assert!(features.contains_key("more"));
+    assert_eq!(fields["dependencies"]["libc"].as_str(), Some("0.2"));
+
+    let mut properties = CrateProperties::new("A", "0.1.0");
+    assert!(properties.add_custom_toml("[package").is_err());
+    assert!(properties.add_custom_toml("features = 1").is_err());
+    assert!(properties.add_custom_toml("[features]\na = [1]").is_err());
+    assert!(properties
+        .add_custom_toml("[dependencies]\nlibc = \"not a version\"")
+        .is_err());
+    assert!(properties
+        .add_custom_toml("[build-dependencies.cc]\nversion = 1")
+        .is_err());
+    assert!(properties.custom_fields().is_empty());
+}
+
+#[test]
+fn crate_metadata() {
+    let mut properties = CrateProperties::new("A", "0.1.0");
+    properties.add_keyword("ffi").unwrap();
+    properties.add_keyword("c++").unwrap();
+    assert!(properties.add_keyword("two words").is_err());
+    assert!(properties.add_keyword("-ffi").is_err());
+    assert!(properties.add_keyword("a-very-long-keyword-here").is_err());
+    assert_eq!(properties.keywords(), &["ffi", "c++"]);
+
+    properties.add_category("external-ffi-bindings").unwrap();
+    properties.add_category("gui::widgets").unwrap();
+    assert!(properties.add_category("GUI").is_err());
+    assert!(properties.add_category("gui::").is_err());
+
+    properties.set_rust_version("1.52").unwrap();
+    properties.set_rust_version("1.52.1").unwrap();
+    assert!(properties.set_rust_version("1").is_err());
+    assert!(properties.set_rust_version("1.x").is_err());
+    assert_eq!(properties.rust_version(), Some("1.52.1"));
+}
+
+#[test]
+fn template_argument_names() {
+    let parse = |name: &str| name.parse::<TemplateArgumentName>();
+    assert_eq!(
+        parse("unsigned int").unwrap(),
+        TemplateArgumentName::BuiltInNumeric(CppBuiltInNumericType::UInt)
+    );
+    assert_eq!(
+        parse("ns::Item").unwrap(),
+        TemplateArgumentName::Path(CppPath::from_good_str("ns::Item"))
+    );
+    assert_eq!(
+        parse("const Item *").unwrap(),
+        TemplateArgumentName::Pointer {
+            is_const: true,
+            target: Box::new(TemplateArgumentName::Path(CppPath::from_good_str("Item"))),
+        }
+    );
+    assert!(parse("").is_err());
+    assert!(parse("Item&").is_err());
+    assert!(parse("QList<int>").is_err());
+}
+
+#[test]
+fn template_instantiations() {
+    let mut config = Config::new(CrateProperties::new("A", "0.1.0"));
+    config
+        .add_template_instantiation("QList", vec!["MyType"])
+        .unwrap();
+    config
+        .add_template_instantiation(
+            "QHash",
+            vec![
+                TemplateArgument::from("int"),
+                CppType::Class(CppPath::from_good_str("MyType")).into(),
+            ],
+        )
+        .unwrap();
+    assert!(config
+        .add_template_instantiation("QList<int>", vec!["int"])
+        .is_err());
+    assert!(config
+        .add_template_instantiation("QList", Vec::<TemplateArgument>::new())
+        .is_err());
+    assert!(config
+        .add_template_instantiation("QList", vec![CppType::Void])
+        .is_err());
+    assert!(config
+        .add_template_instantiation("QList", vec!["a b"])
+        .is_err());
+
+    let instantiations = config.template_instantiations();
+    assert_eq!(instantiations.len(), 2);
+    assert_eq!(instantiations[0].0, CppPath::from_good_str("QList"));
+    assert_eq!(
+        instantiations[1].1,
+        vec![
+            TemplateArgument::Name("int".to_string()),
+            TemplateArgument::Type(CppType::Class(CppPath::from_good_str("MyType"))),
+        ]
+    );
+}
+
+#[test]
+fn method_instantiations() {
+    let mut config = Config::new(CrateProperties::new("A", "0.1.0"));
+    config
+        .instantiate_method("QVariant::value", vec!["int", "QString", "bool"])
+        .unwrap();
+    assert!(config.instantiate_method("value", vec!["int"]).is_err());
+    assert!(config
+        .instantiate_method("QVariant::value<T>", vec!["int"])
+        .is_err());
+    assert!(config
+        .instantiate_method("QVariant::value", Vec::<TemplateArgument>::new())
+        .is_err());
+    assert!(config
+        .instantiate_method("QVariant::value", vec![CppType::Void])
+        .is_err());
+
+    let instantiations = config.method_instantiations();
+    assert_eq!(instantiations.len(), 1);
+    assert_eq!(
+        instantiations[0].0,
+        CppPath::from_good_str("QVariant::value")
+    );
+    assert_eq!(instantiations[0].1.len(), 3);
+}
+
+#[test]
+fn user_config_exclusions() {
+    let code = "class Base {
+    public:
+        void open();
+        void open(int mode, bool flag = false);
+        void close();
+        int size() const;
+    };
+    class Derived : public Base {
+    public:
+        void run();
+    };
+    class Hidden {
+    public:
+        void f();
+        int x;
+    };";
+    let handler = Arc::new(CollectingProgressHandler::default());
+    let (_dir, mut workspace, result) = try_run_steps(
+        code,
+        &["cpp_parser", "add_inherited_methods", "omitting_arguments"],
+        |config| {
+            config.set_progress_handler(Arc::clone(&handler));
+            config.set_inherited_methods_strategy(InheritedMethodsStrategy::Signatures);
+            config
+                .blacklist_method("Base", "open", Some("(int,bool)"))
+                .unwrap();
+            config.blacklist_method("Base", "size", None).unwrap();
+            config.blacklist_method("Base", "missing", None).unwrap();
+            config.blacklist_class_members("Hidden").unwrap();
+            config.add_method_filter(|f| f.path.last().name != "close");
+        },
+    );
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let functions = database
+        .cpp_items()
+        .filter_map(|item| item.item.as_function_ref())
+        .map(|f| f.signature_text())
+        .sorted()
+        .collect_vec();
+    assert_eq!(
+        functions,
+        vec!["Base::open()", "Derived::open()", "Derived::run()"]
+    );
+    assert!(database
+        .cpp_items()
+        .all(|item| item.item.as_field_ref().is_none()));
+
+    let mut rejections = database
+        .rejections()
+        .iter()
+        .filter(|r| r.step == processor::USER_CONFIG_STEP)
+        .map(|r| r.reason.as_str())
+        .collect_vec();
+    rejections.sort();
+    rejections.dedup();
+    assert_eq!(rejections.len(), 4);
+    assert!(rejections[0].starts_with("excluded by method filter registered at "));
+    assert_eq!(
+        rejections[1],
+        "excluded by user config: blacklist_class_members(Hidden)"
+    );
+    assert_eq!(
+        rejections[2],
+        "excluded by user config: blacklist_method(Base::open(int,bool))"
+    );
+    assert_eq!(
+        rejections[3],
+        "excluded by user config: blacklist_method(Base::size)"
+    );
+
+    let events = handler.0.lock().unwrap();
+    let warnings = events
+        .iter()
+        .filter_map(|event| match event {
+            ProgressEvent::Warning { message } if message.starts_with("exclusions") => {
+                Some(message.as_str())
+            }
+            _ => None,
+        })
+        .collect_vec();
+    assert_eq!(
+        warnings,
+        vec!["exclusions that don't match any item:\n    blacklist_method(Base::missing)"]
+    );
+}
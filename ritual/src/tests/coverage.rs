@@ -0,0 +1,71 @@
+use crate::coverage::{CoverageCounts, CoverageReport};
+use crate::tests::{add_fake_cpp_checker, fake_lib_config, try_run_steps, FAKE_LIB_HEADER};
+use itertools::Itertools;
+use ritual_common::file_utils::load_json;
+
+#[test]
+fn coverage_report() {
+    let code = "class Handle {
+    public:
+        int id() const;
+    };
+    class A {
+    public:
+        int f(int x, int y = 0);
+        void take(Handle handle);
+    };
+    enum E { E1 };
+    Handle* create();";
+    let steps = &[
+        "cpp_parser",
+        "omitting_arguments",
+        "type_allocation_places",
+        "cpp_ffi_generator",
+        "fake_cpp_checker",
+        "rust_generator",
+    ];
+    let (dir, mut workspace, result) = try_run_steps(code, steps, |config| {
+        add_fake_cpp_checker(config);
+        config.set_opaque("Handle").unwrap();
+    });
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let report = CoverageReport::new(&database, &fake_lib_config(dir.path())).unwrap();
+
+    let counts = |bound, unbound| CoverageCounts { bound, unbound };
+    assert_eq!(report.total.functions, counts(2, 2));
+    assert_eq!(report.total.types, counts(2, 0));
+    assert_eq!(report.total.enums, counts(1, 0));
+    assert_eq!(report.total.synthesized, counts(1, 0));
+    assert_eq!(
+        report.total.unbound_by_step.iter().collect_vec(),
+        vec![
+            (&"cpp_ffi_generator".to_string(), &1),
+            (&"other".to_string(), &1)
+        ]
+    );
+    assert_eq!(report.headers[FAKE_LIB_HEADER], report.total);
+    assert_eq!(report.classes["A"].functions, counts(1, 1));
+    assert_eq!(report.classes["A"].synthesized, counts(1, 0));
+    assert_eq!(report.classes["Handle"].functions, counts(0, 1));
+    assert_eq!(
+        report.rejections["cpp_ffi_generator"]["opaque type Handle is used by value"],
+        1
+    );
+    assert!(report
+        .to_markdown()
+        .contains("5 of 7 parsed C++ items (71.4%) are available in the Rust API."));
+
+    let saved: CoverageReport = load_json(workspace.coverage_report_path("A", "json")).unwrap();
+    assert_eq!(saved, report);
+    assert!(workspace.coverage_report_path("A", "md").exists());
+
+    let (_dir, _workspace, result) = try_run_steps(code, steps, |config| {
+        add_fake_cpp_checker(config);
+        config.set_opaque("Handle").unwrap();
+        config.set_min_coverage(Some(80.0));
+    });
+    assert!(result.unwrap_err().to_string().contains("71.4%"));
+}
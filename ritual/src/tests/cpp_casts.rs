@@ -0,0 +1,41 @@
+use crate::cpp_data::*;
+use crate::cpp_ffi_data::CppCast;
+use crate::cpp_type::*;
+use crate::tests::run_steps;
+
+#[test]
+fn casts_respect_base_specifiers() {
+    let (_dir, mut workspace) = run_steps(
+        "
+        class Base { public: virtual ~Base(); };
+        class PrivateDerived : private Base {};
+        class VirtualDerived : public virtual Base {};
+        ",
+        &["cpp_parser", "cpp_casts"],
+    );
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let casts = database
+        .cpp_items()
+        .filter_map(|item| item.item.as_function_ref())
+        .filter_map(|f| f.cast.clone().map(|cast| (cast, f.return_type.clone())))
+        .collect::<Vec<_>>();
+
+    let private_derived_ptr = CppType::new_pointer(
+        false,
+        CppType::Class(CppPath::from_good_str("PrivateDerived")),
+    );
+    assert!(!casts.iter().any(|(_, t)| t == &private_derived_ptr));
+
+    let virtual_derived_ptr = CppType::new_pointer(
+        false,
+        CppType::Class(CppPath::from_good_str("VirtualDerived")),
+    );
+    let downcasts = casts
+        .iter()
+        .filter(|(_, t)| t == &virtual_derived_ptr)
+        .map(|(cast, _)| cast.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(downcasts, vec![CppCast::Dynamic]);
+}
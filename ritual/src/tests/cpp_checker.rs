@@ -0,0 +1,140 @@
+use crate::cpp_checker::{self, LocalCppChecker};
+use crate::cpp_parser;
+use crate::tests::{fake_lib_config, try_run_steps};
+use itertools::Itertools;
+use ritual_common::cpp_lib_builder::CppLibBuilderOutput;
+use ritual_common::errors::bail;
+use ritual_common::target::{current_target, LibraryTarget};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tempdir::TempDir;
+
+#[test]
+fn cpp_checker_saves_completed_batches() {
+    let (_dir, mut workspace, result) = try_run_steps(
+        "int f1();
+        int f2();
+        int f3();",
+        &["cpp_parser", "cpp_ffi_generator", "test_cpp_checker"],
+        |config| {
+            config.set_cpp_checker_batch_size(1).unwrap();
+            config.set_cpp_checker_job_count(1).unwrap();
+            config
+                .processing_steps_mut()
+                .add_custom("test_cpp_checker", |data| {
+                    let interrupted = Arc::new(AtomicBool::new(false));
+                    cpp_checker::run_with(data, false, interrupted, |_, batch| {
+                        for task in batch {
+                            if task.snippet.code.contains("f3") {
+                                bail!("compiler not found");
+                            }
+                            task.output = Some(CppLibBuilderOutput::Success);
+                        }
+                        Ok(())
+                    })
+                });
+        },
+    );
+    let err = result.unwrap_err().to_string();
+    assert!(err.contains("compiler not found"));
+
+    // results of the batches checked before the failure are saved with the database
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let env = LibraryTarget {
+        target: current_target(),
+        cpp_library_version: None,
+    };
+    let ffi_items = database.ffi_items().collect_vec();
+    assert_eq!(ffi_items.len(), 3);
+    for ffi_item in ffi_items {
+        let checks = database.cpp_checks(&ffi_item.id).unwrap();
+        if ffi_item.item.short_text().contains("f3") {
+            assert!(!checks.has_env(&env));
+        } else {
+            assert!(checks.is_success(&env));
+        }
+    }
+}
+
+#[test]
+fn cpp_checker_interruption() {
+    let (_dir, mut workspace, result) = try_run_steps(
+        "int f1();
+        int f2();
+        int f3();",
+        &["cpp_parser", "cpp_ffi_generator", "test_cpp_checker"],
+        |config| {
+            config.set_cpp_checker_batch_size(1).unwrap();
+            config.set_cpp_checker_job_count(1).unwrap();
+            config
+                .processing_steps_mut()
+                .add_custom("test_cpp_checker", |data| {
+                    let interrupted = Arc::new(AtomicBool::new(false));
+                    let batch_count = AtomicUsize::new(0);
+                    cpp_checker::run_with(data, false, Arc::clone(&interrupted), |_, batch| {
+                        for task in batch {
+                            task.output = Some(CppLibBuilderOutput::Success);
+                        }
+                        // the flag is set while the second batch is checked
+                        if batch_count.fetch_add(1, Ordering::SeqCst) == 1 {
+                            interrupted.store(true, Ordering::SeqCst);
+                        }
+                        Ok(())
+                    })
+                });
+        },
+    );
+    let err = result.unwrap_err().to_string();
+    assert!(err.contains("cpp_checker was interrupted"));
+
+    // the first batch is saved, the second one is discarded,
+    // and the third one is not checked
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let env = LibraryTarget {
+        target: current_target(),
+        cpp_library_version: None,
+    };
+    let ffi_items = database.ffi_items().collect_vec();
+    assert_eq!(ffi_items.len(), 3);
+    let checked_count = ffi_items
+        .iter()
+        .filter(|ffi_item| database.cpp_checks(&ffi_item.id).unwrap().has_env(&env))
+        .count();
+    assert_eq!(checked_count, 1);
+}
+
+#[test]
+fn target_arguments() {
+    let dir = TempDir::new("test_target_arguments").unwrap();
+    let mut config = fake_lib_config(dir.path());
+    config.set_target_triple("aarch64-unknown-linux-gnu");
+    config.set_sysroot("/opt/sysroot");
+    config.add_cpp_parser_argument("-DA_SHARED");
+    config.add_cpp_parser_only_argument("-fcxx-exceptions");
+
+    let args = cpp_parser::clang_arguments(&config, config.cpp_build_paths()).unwrap();
+    assert!(args.iter().any(|arg| arg == "-DA_SHARED"));
+    assert!(args.iter().any(|arg| arg == "-fcxx-exceptions"));
+    assert!(args
+        .windows(2)
+        .any(|pair| pair[0] == "-target" && pair[1] == "aarch64-unknown-linux-gnu"));
+    assert!(args.iter().any(|arg| arg == "--sysroot=/opt/sysroot"));
+
+    let checker = LocalCppChecker::new(dir.path().join("cpp_checker"), &config).unwrap();
+    // parser-only arguments are not passed to the C++ compiler
+    let compiler_flags = checker.cpp_build_config().compiler_flags();
+    assert!(compiler_flags.iter().any(|flag| flag == "-DA_SHARED"));
+    assert!(!compiler_flags.iter().any(|flag| flag == "-fcxx-exceptions"));
+    let cmake_vars = checker
+        .cpp_build_config()
+        .cmake_vars()
+        .iter()
+        .map(|var| (var.name.as_str(), var.value.as_str()))
+        .collect_vec();
+    assert!(cmake_vars.contains(&("CMAKE_CXX_COMPILER_TARGET", "aarch64-unknown-linux-gnu")));
+    assert!(cmake_vars.contains(&("CMAKE_SYSROOT", "/opt/sysroot")));
+}
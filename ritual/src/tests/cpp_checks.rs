@@ -0,0 +1,80 @@
+use crate::cpp_checker::{snippet_hash, Snippet};
+use crate::cpp_checks::{Condition, CppChecks, CppChecksItem};
+use ritual_common::target::{current_target, LibraryTarget, Target, OS};
+
+#[test]
+fn up_to_date_checks() {
+    let env = LibraryTarget {
+        target: current_target(),
+        cpp_library_version: Some("1.0".into()),
+    };
+    let snippet = Snippet::new_global("void f1() {}", false);
+    let hash = snippet_hash("-std=c++11", &snippet);
+    assert_eq!(hash, snippet_hash("-std=c++11", &snippet));
+
+    let checks = CppChecks::new(
+        vec![CppChecksItem {
+            env: env.clone(),
+            is_success: true,
+            snippet_hash: Some(hash),
+            failure: None,
+        }]
+        .into_iter(),
+    );
+    assert!(checks.is_up_to_date(&env, hash));
+
+    let changed_snippet = Snippet::new_global("void f1() { }", false);
+    assert!(!checks.is_up_to_date(&env, snippet_hash("-std=c++11", &changed_snippet)));
+    assert!(!checks.is_up_to_date(&env, snippet_hash("-std=c++14", &snippet)));
+
+    let other_env = LibraryTarget {
+        target: current_target(),
+        cpp_library_version: Some("2.0".into()),
+    };
+    assert!(!checks.is_up_to_date(&other_env, hash));
+
+    let old_checks = CppChecks::new(
+        vec![CppChecksItem {
+            env: env.clone(),
+            is_success: true,
+            snippet_hash: None,
+            failure: None,
+        }]
+        .into_iter(),
+    );
+    assert!(!old_checks.is_up_to_date(&env, hash));
+}
+
+#[test]
+fn merged_targets_condition() {
+    let linux = LibraryTarget {
+        target: Target {
+            os: OS::Linux,
+            ..current_target()
+        },
+        cpp_library_version: None,
+    };
+    let windows = LibraryTarget {
+        target: Target {
+            os: OS::Windows,
+            ..current_target()
+        },
+        cpp_library_version: None,
+    };
+    let item = |env: &LibraryTarget, is_success| CppChecksItem {
+        env: env.clone(),
+        is_success,
+        snippet_hash: None,
+        failure: None,
+    };
+    let environments = vec![linux.clone(), windows.clone()];
+
+    let checks = CppChecks::new(vec![item(&linux, true), item(&windows, false)].into_iter());
+    assert_eq!(
+        checks.condition(&environments),
+        Condition::Or(vec![Condition::Target(linux.target.clone())])
+    );
+
+    let checks = CppChecks::new(vec![item(&linux, true), item(&windows, true)].into_iter());
+    assert_eq!(checks.condition(&environments), Condition::True);
+}
@@ -0,0 +1,332 @@
+use crate::config::{StringConversion, TypeAllocationPlace};
+use crate::cpp_code_generator;
+use crate::cpp_data::*;
+use crate::cpp_ffi_data::{
+    CppFfiArgumentMeaning, CppFfiFunctionKind, CppFfiItem, CppToFfiTypeConversion,
+};
+use crate::cpp_type::*;
+use crate::rust_info::{RustStructKind, RustWrapperTypeKind};
+use crate::rust_type::RustPath;
+use crate::tests::{add_fake_cpp_checker, run_steps, try_run_steps};
+
+#[test]
+fn noexcept_function_implementations() {
+    let (_dir, mut workspace) = run_steps(
+        "
+        class A {
+        public:
+            int f1() noexcept;
+            int f2();
+        };
+        ",
+        &["cpp_parser", "cpp_ffi_generator"],
+    );
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let implementation = |name: &str| {
+        let item = database
+            .ffi_items()
+            .filter_map(|item| item.filter_map(|item| item.as_function_ref()))
+            .find(|item| item.item.descriptive_name.ends_with(name))
+            .unwrap();
+        cpp_code_generator::function_implementation(&database, item).unwrap()
+    };
+
+    // exceptions can't be thrown, so no try block is needed
+    let f1 = implementation("A_f1");
+    assert!(f1.contains("this_ptr->f1()"));
+    assert!(!f1.contains("RITUAL_TRY"));
+    assert!(!f1.contains("RITUAL_CATCH_ALL"));
+
+    let f2 = implementation("A_f2");
+    let call = f2.find("this_ptr->f2()").unwrap();
+    assert!(f2.find("RITUAL_TRY {").unwrap() < call);
+    assert!(f2.find("} RITUAL_CATCH_ALL").unwrap() > call);
+}
+
+#[test]
+fn extern_c_functions() {
+    let (_dir, mut workspace) = run_steps(
+        "
+        extern \"C\" {
+            int area(int size);
+        }
+        int area(int width, int height);
+        double area(double size);
+        ",
+        &["cpp_parser", "cpp_ffi_generator"],
+    );
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let functions = database
+        .cpp_items()
+        .filter_map(|item| item.filter_map(|item| item.as_function_ref()))
+        .collect::<Vec<_>>();
+    assert_eq!(functions.len(), 3);
+    for function in functions {
+        let is_c_function = function.item.arguments.len() == 1
+            && function.item.return_type == CppType::BuiltInNumeric(CppBuiltInNumericType::Int);
+        assert_eq!(function.item.is_extern_c, is_c_function);
+
+        let ffi_function = database
+            .ffi_items()
+            .filter(|item| item.source_id.as_ref() == Some(&function.id))
+            .filter_map(|item| item.item.as_function_ref())
+            .next()
+            .unwrap();
+        assert_eq!(ffi_function.is_direct_binding, is_c_function);
+        if is_c_function {
+            assert_eq!(ffi_function.path, CppPath::from_good_str("area"));
+        } else {
+            assert_ne!(ffi_function.path.last().name, "area");
+        }
+    }
+}
+
+#[test]
+fn rvalue_references() {
+    let (_dir, mut workspace) = run_steps(
+        "
+        class Item {};
+        class List {
+        public:
+            void push_back(const Item& value);
+            void push_back(Item&& value);
+        };
+        ",
+        &["cpp_parser", "cpp_ffi_generator"],
+    );
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let functions = database
+        .cpp_items()
+        .filter_map(|item| item.filter_map(|item| item.as_function_ref()))
+        .filter(|item| item.item.path == CppPath::from_good_str("List::push_back"))
+        .collect::<Vec<_>>();
+    assert_eq!(functions.len(), 2);
+    let item_type = CppType::Class(CppPath::from_good_str("Item"));
+
+    let mut ffi_paths = Vec::new();
+    for function in functions {
+        let argument_type = &function.item.arguments[0].argument_type;
+        let ffi_function = database
+            .ffi_items()
+            .filter(|item| item.source_id.as_ref() == Some(&function.id))
+            .filter_map(|item| item.item.as_function_ref())
+            .next()
+            .unwrap();
+        let ffi_argument = ffi_function
+            .arguments
+            .iter()
+            .find(|arg| arg.meaning == CppFfiArgumentMeaning::Argument(0))
+            .unwrap();
+        if argument_type.is_rvalue_reference() {
+            assert_eq!(
+                argument_type,
+                &CppType::new_rvalue_reference(item_type.clone())
+            );
+            assert_eq!(
+                ffi_argument.argument_type.conversion(),
+                &CppToFfiTypeConversion::RValueReferenceToPointer
+            );
+            assert_eq!(
+                ffi_argument.argument_type.ffi_type(),
+                &CppType::new_pointer(false, item_type.clone())
+            );
+        } else {
+            assert_eq!(
+                argument_type,
+                &CppType::new_reference(true, item_type.clone())
+            );
+            assert_eq!(
+                ffi_argument.argument_type.conversion(),
+                &CppToFfiTypeConversion::ReferenceToPointer
+            );
+        }
+        ffi_paths.push(ffi_function.path.clone());
+    }
+    assert_ne!(ffi_paths[0], ffi_paths[1]);
+}
+
+#[test]
+fn string_types() {
+    let code = "
+        class String {
+        public:
+            String(const char* data, unsigned long size);
+            const char* data() const;
+            unsigned long size() const;
+        };
+        class Text {
+        public:
+            String toUtf8() const;
+        };
+    ";
+    let steps = ["cpp_parser", "cpp_ffi_generator"];
+    let (_dir, mut workspace, result) = try_run_steps(code, &steps, |config| {
+        let conversion = |method: Option<&str>, ctor: &str| StringConversion {
+            to_utf8_method: method.map(String::from),
+            from_utf8_ctor: ctor.to_string(),
+        };
+        config
+            .add_string_type("String", conversion(None, "String"))
+            .unwrap();
+        config
+            .add_string_type("Text", conversion(Some("toUtf8"), "Text::fromUtf8"))
+            .unwrap();
+        assert!(config
+            .add_string_type("Text", conversion(None, "Text"))
+            .is_err());
+        assert!(config
+            .add_string_type("Other", conversion(None, ""))
+            .is_err());
+    });
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let implementation = |name: &str| {
+        let item = database
+            .ffi_items()
+            .filter_map(|item| item.filter_map(|item| item.as_function_ref()))
+            .find(|item| item.item.descriptive_name.ends_with(name))
+            .unwrap();
+        cpp_code_generator::function_implementation(&database, item).unwrap()
+    };
+
+    let from_utf8 = database
+        .ffi_items()
+        .filter_map(|item| item.item.as_function_ref())
+        .find(|item| item.descriptive_name.ends_with("String_from_utf8"))
+        .unwrap();
+    assert_eq!(
+        from_utf8.kind,
+        CppFfiFunctionKind::StringFromUtf8 {
+            constructor: "String".to_string()
+        }
+    );
+    assert!(implementation("String_from_utf8").contains("return new String(String(data, len));"));
+    assert!(implementation("String_to_utf8")
+        .contains("write(data, value->data(), static_cast<size_t>(value->size()));"));
+    assert!(
+        implementation("Text_from_utf8").contains("return new Text(Text::fromUtf8(data, len));")
+    );
+    let text_to_utf8 = implementation("Text_to_utf8");
+    assert!(text_to_utf8.contains("auto bytes = value->toUtf8();"));
+    assert!(text_to_utf8.contains("write(data, bytes.data(), static_cast<size_t>(bytes.size()));"));
+}
+
+#[test]
+fn function_implementation_without_source_item() {
+    let code = "
+        class String {
+        public:
+            String(const char* data, unsigned long size);
+            const char* data() const;
+            unsigned long size() const;
+        };
+    ";
+    let steps = ["cpp_parser", "cpp_ffi_generator"];
+    let (_dir, mut workspace, result) = try_run_steps(code, &steps, |config| {
+        let conversion = StringConversion {
+            to_utf8_method: None,
+            from_utf8_ctor: "String".to_string(),
+        };
+        config.add_string_type("String", conversion).unwrap();
+    });
+    result.unwrap();
+    let mut database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let mut function = database
+        .ffi_items()
+        .filter_map(|item| item.item.as_function_ref())
+        .find(|item| item.descriptive_name.ends_with("String_to_utf8"))
+        .unwrap()
+        .clone();
+    function.path = CppPath::from_good_str("ctr_a_detached_to_utf8");
+    function.descriptive_name = "ctr_a_detached_to_utf8".to_string();
+    let id = database
+        .add_ffi_item(None, CppFfiItem::Function(function))
+        .unwrap()
+        .unwrap();
+
+    let item = database
+        .ffi_items()
+        .filter_map(|item| item.filter_map(|item| item.as_function_ref()))
+        .find(|item| item.id == id)
+        .unwrap();
+    assert!(database.source_cpp_item(&item.id).unwrap().is_none());
+    let implementation = cpp_code_generator::function_implementation(&database, item).unwrap();
+    assert!(
+        implementation.contains("write(data, value->data(), static_cast<size_t>(value->size()));")
+    );
+    assert!(!implementation.contains("RITUAL_TRY"));
+    assert!(!implementation.contains("RITUAL_CATCH_ALL"));
+}
+
+#[test]
+fn opaque_types() {
+    let (_dir, mut workspace, result) = try_run_steps(
+        "class Handle {
+        public:
+            Handle();
+            int id() const;
+            int x;
+        };
+        Handle* create_handle();
+        void use_handle(const Handle& handle);
+        Handle copy_handle(const Handle& handle);
+        void consume(Handle handle);",
+        &[
+            "cpp_parser",
+            "type_allocation_places",
+            "cpp_ffi_generator",
+            "fake_cpp_checker",
+            "rust_generator",
+        ],
+        |config| {
+            add_fake_cpp_checker(config);
+            config.set_opaque("Handle").unwrap();
+        },
+    );
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+
+    let decision = database
+        .type_allocation_places()
+        .iter()
+        .find(|d| d.path == CppPath::from_good_str("Handle"))
+        .unwrap();
+    assert_eq!(decision.place, TypeAllocationPlace::Heap);
+
+    let ffi_count = |name: &str| {
+        let views = database.lookup_cpp_name(name).unwrap();
+        assert_eq!(views.len(), 1, "{}", name);
+        views[0].ffi_items().len()
+    };
+    assert_eq!(ffi_count("Handle::Handle"), 0);
+    assert_eq!(ffi_count("Handle::id"), 0);
+    assert_eq!(ffi_count("create_handle"), 1);
+    assert_eq!(ffi_count("use_handle"), 1);
+    assert_eq!(ffi_count("copy_handle"), 0);
+    assert_eq!(ffi_count("consume"), 0);
+    for name in &["copy_handle", "consume"] {
+        let rejection = database.rejections_for(name).next().unwrap();
+        assert_eq!(rejection.step, "cpp_ffi_generator");
+        assert_eq!(rejection.reason, "opaque type Handle is used by value");
+    }
+
+    let item = database
+        .find_rust_item(&RustPath::from_good_str("A::Handle"))
+        .unwrap();
+    assert_eq!(
+        item.item.as_struct_ref().unwrap().kind,
+        RustStructKind::WrapperType(RustWrapperTypeKind::OpaqueClassWrapper)
+    );
+}
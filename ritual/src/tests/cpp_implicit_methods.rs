@@ -0,0 +1,71 @@
+use crate::cpp_data::*;
+use crate::tests::run_steps;
+
+#[test]
+fn implicit_special_members() {
+    let (_dir, mut workspace) = run_steps(
+        "class Plain {
+        public:
+            int x;
+        };
+        class WithConstructor {
+        public:
+            WithConstructor(int x);
+        };
+        class MoveOnly {
+        public:
+            MoveOnly();
+            MoveOnly(MoveOnly&& other);
+        };
+        class HasMoveOnly {
+        public:
+            MoveOnly value;
+        };
+        class WithReference {
+        public:
+            int& value;
+        };
+        class NoCopy {
+        public:
+            NoCopy(const NoCopy& other) = delete;
+        };",
+        &["cpp_parser", "add_implicit_methods"],
+    );
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let implicit = |class_name: &str| {
+        let mut methods = database
+            .cpp_items()
+            .filter_map(|item| item.item.as_function_ref())
+            .filter(|f| {
+                f.is_implicit() && f.class_path().unwrap() == CppPath::from_good_str(class_name)
+            })
+            .map(|f| {
+                if f.is_destructor() {
+                    "destructor"
+                } else if f.is_copy_constructor() {
+                    "copy"
+                } else if f.is_constructor() {
+                    "default"
+                } else {
+                    "assignment"
+                }
+            })
+            .collect::<Vec<_>>();
+        methods.sort();
+        methods
+    };
+    assert_eq!(
+        implicit("Plain"),
+        vec!["assignment", "copy", "default", "destructor"]
+    );
+    assert_eq!(
+        implicit("WithConstructor"),
+        vec!["assignment", "copy", "destructor"]
+    );
+    assert_eq!(implicit("MoveOnly"), vec!["destructor"]);
+    assert_eq!(implicit("HasMoveOnly"), vec!["default", "destructor"]);
+    assert_eq!(implicit("WithReference"), vec!["copy", "destructor"]);
+    assert_eq!(implicit("NoCopy"), vec!["assignment", "destructor"]);
+}
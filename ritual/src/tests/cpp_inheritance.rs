@@ -0,0 +1,344 @@
+use crate::config::{Config, InheritedMethodsStrategy};
+use crate::cpp_code_generator;
+use crate::cpp_data::*;
+use crate::cpp_type::*;
+use crate::database::ItemId;
+use crate::processor;
+use crate::tests::cpp_method::{empty_membership, empty_regular_method};
+use crate::tests::{fake_lib_config, run_steps, try_run_steps};
+use itertools::Itertools;
+
+#[test]
+fn inherited_method_overloads() {
+    let code = "class Base {
+    public:
+        void open();
+        void open(int mode);
+        void open(const char* name);
+        virtual void close();
+        static int count();
+    private:
+        void reset();
+    };
+    class Derived : public Base {
+    public:
+        void open(int mode);
+        void close() override;
+    };";
+    let inherited = |strategy: InheritedMethodsStrategy| {
+        let (_dir, mut workspace, result) =
+            try_run_steps(code, &["cpp_parser", "add_inherited_methods"], |config| {
+                config.set_inherited_methods_strategy(strategy)
+            });
+        result.unwrap();
+        let database = workspace
+            .get_database_client("A", &[], true, false)
+            .unwrap();
+        database
+            .cpp_items()
+            .filter_map(|item| item.item.as_function_ref())
+            .filter(|f| f.inherited_from().is_some())
+            .map(|f| {
+                assert_eq!(f.inherited_from(), Some(&CppPath::from_good_str("Base")));
+                assert_eq!(f.class_path().unwrap(), CppPath::from_good_str("Derived"));
+                let args = f
+                    .arguments
+                    .iter()
+                    .map(|arg| arg.argument_type.to_cpp_pseudo_code())
+                    .join(", ");
+                format!("{}({})", f.path.last().name, args)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert!(inherited(InheritedMethodsStrategy::Disabled).is_empty());
+    assert_eq!(
+        inherited(InheritedMethodsStrategy::Signatures),
+        vec!["open()", "open(const char*)", "count()"]
+    );
+    assert_eq!(
+        inherited(InheritedMethodsStrategy::NameHiding),
+        vec!["count()"]
+    );
+}
+
+#[test]
+fn using_declarations() {
+    let (_dir, mut workspace) = run_steps(
+        "class Base {
+        public:
+            Base(int value);
+            Base(const char* name);
+            Base(const Base& other);
+            void open();
+            void open(int mode);
+        protected:
+            void reset(bool force);
+        };
+        class Derived : public Base {
+        public:
+            using Base::Base;
+            using Base::open;
+            using Base::reset;
+            Derived(const char* name);
+            void open(int mode);
+        };",
+        &["cpp_parser", "add_inherited_methods", "cpp_ffi_generator"],
+    );
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let derived = CppPath::from_good_str("Derived");
+    let derived_type = database
+        .cpp_items()
+        .filter_map(|item| item.item.as_type_ref())
+        .find(|t| t.path == derived)
+        .unwrap();
+    let using_declarations = derived_type.kind.using_declarations();
+    assert_eq!(using_declarations.len(), 3);
+    assert!(using_declarations[0].is_inheriting_constructors());
+    assert_eq!(using_declarations[1].name, "open");
+    assert_eq!(
+        using_declarations[1].base_class_path,
+        CppPath::from_good_str("Base")
+    );
+    assert_eq!(using_declarations[2].visibility, CppVisibility::Public);
+
+    let inherited = database
+        .cpp_items()
+        .filter_map(|item| item.filter_map(|item| item.as_function_ref()))
+        .filter(|f| f.item.inherited_from().is_some())
+        .collect::<Vec<_>>();
+    let texts = inherited
+        .iter()
+        .map(|f| {
+            assert_eq!(f.item.class_path().unwrap(), derived);
+            f.item.short_text()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(inherited.len(), 3, "{:?}", texts);
+
+    // only constructors that are not redeclared or copy constructors are inherited
+    let constructor = inherited.iter().find(|f| f.item.is_constructor()).unwrap();
+    assert_eq!(
+        constructor.item.path,
+        CppPath::from_good_str("Derived::Derived")
+    );
+    assert_eq!(constructor.item.arguments.len(), 1);
+    assert_eq!(
+        constructor.item.arguments[0].argument_type,
+        CppType::BuiltInNumeric(CppBuiltInNumericType::Int)
+    );
+    // the overload hidden by `open(int)` is brought back by `using`
+    let open = inherited
+        .iter()
+        .find(|f| f.item.path.last().name == "open")
+        .unwrap();
+    assert!(open.item.arguments.is_empty());
+    // `using` changes the visibility
+    let reset = inherited
+        .iter()
+        .find(|f| f.item.path.last().name == "reset")
+        .unwrap();
+    assert!(!reset.item.is_protected());
+
+    let implementation = |id: &ItemId| {
+        let item = database
+            .ffi_items()
+            .filter_map(|item| item.filter_map(|item| item.as_function_ref()))
+            .find(|item| item.source_id.as_ref() == Some(id))
+            .unwrap();
+        cpp_code_generator::function_implementation(&database, item).unwrap()
+    };
+    let constructor_code = implementation(&constructor.id);
+    assert!(constructor_code.contains("new Derived("));
+    assert!(!constructor_code.contains("new Base("));
+    let reset_code = implementation(&reset.id);
+    assert!(reset_code.contains("->reset("));
+    // the protected method can't be called through a pointer to the base class
+    assert!(!reset_code.contains("static_cast"));
+}
+
+#[test]
+fn inherited_methods_diamond() {
+    let (_dir, mut workspace, result) = try_run_steps(
+        "class Base {
+        public:
+            void shared();
+        };
+        class Left : public virtual Base {
+        public:
+            void left();
+            void name();
+        };
+        class Right : public virtual Base {
+        public:
+            void right();
+            void name();
+        };
+        class Diamond : public Left, public Right {};",
+        &["cpp_parser", "add_inherited_methods"],
+        |config| config.set_inherited_methods_strategy(InheritedMethodsStrategy::Signatures),
+    );
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let diamond_methods = database
+        .cpp_items()
+        .filter_map(|item| item.item.as_function_ref())
+        .filter(|f| f.class_path().unwrap() == CppPath::from_good_str("Diamond"))
+        .collect::<Vec<_>>();
+
+    let shared = diamond_methods
+        .iter()
+        .filter(|f| f.path.last().name == "shared")
+        .collect::<Vec<_>>();
+    assert_eq!(shared.len(), 1);
+    assert_eq!(
+        shared[0].inherited_from(),
+        Some(&CppPath::from_good_str("Base"))
+    );
+    assert!(!shared[0].is_ambiguous());
+
+    let mut names = diamond_methods
+        .iter()
+        .filter(|f| f.path.last().name == "name")
+        .map(|f| {
+            assert!(f.is_ambiguous());
+            f.inherited_from().unwrap().to_cpp_pseudo_code()
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, vec!["Left", "Right"]);
+
+    for name in &["left", "right"] {
+        let methods = diamond_methods
+            .iter()
+            .filter(|f| f.path.last().name == *name)
+            .collect::<Vec<_>>();
+        assert_eq!(methods.len(), 1);
+        assert!(!methods[0].is_ambiguous());
+    }
+}
+
+/// Adds a step that creates `count` pairs of classes with `methods` methods each.
+/// The second class of each pair derives from the first one and redeclares
+/// the first half of its methods.
+fn add_synthetic_classes_step(config: &mut Config, count: usize, methods: usize) {
+    config
+        .processing_steps_mut()
+        .add_after(&["cpp_parser"], "synthetic_classes", move |data| {
+            let class = |path: &CppPath| {
+                CppItem::Type(CppTypeDeclaration {
+                    path: path.clone(),
+                    kind: CppTypeDeclarationKind::Class {
+                        is_final: false,
+                        layout: None,
+                        using_declarations: Vec::new(),
+                        template_argument_defaults: Vec::new(),
+                        properties: Vec::new(),
+                    },
+                    deprecated: None,
+                    doc_comment: None,
+                })
+            };
+            let method = |class_path: &CppPath, name: String| {
+                let mut function = empty_regular_method();
+                function.path = class_path.join(CppPathItem::from_good_str(&name));
+                function.member = Some(empty_membership());
+                CppItem::Function(function)
+            };
+            for i in 0..count {
+                let base = CppPath::from_good_str(&format!("Base{}", i));
+                let derived = CppPath::from_good_str(&format!("Derived{}", i));
+                data.add_cpp_item(None, class(&base))?;
+                data.add_cpp_item(None, class(&derived))?;
+                let base_specifier = CppBaseSpecifier {
+                    base_class_type: base.clone(),
+                    base_index: 0,
+                    is_virtual: false,
+                    visibility: CppVisibility::Public,
+                    derived_class_type: derived.clone(),
+                };
+                data.add_cpp_item(None, CppItem::ClassBase(base_specifier))?;
+                for j in 0..methods {
+                    data.add_cpp_item(None, method(&base, format!("f{}", j)))?;
+                    let name = if j < methods / 2 {
+                        format!("f{}", j)
+                    } else {
+                        format!("g{}", j)
+                    };
+                    data.add_cpp_item(None, method(&derived, name))?;
+                }
+            }
+            Ok(())
+        })
+        .unwrap();
+}
+
+/// Runs the implicit and inherited method passes on `count` pairs of synthetic
+/// classes and checks the results.
+fn check_class_hierarchy(count: usize, methods: usize) {
+    let configure = |config: &mut Config| {
+        add_synthetic_classes_step(config, count, methods);
+        config.set_inherited_methods_strategy(InheritedMethodsStrategy::Signatures);
+    };
+    let (dir, mut workspace, result) =
+        try_run_steps("", &["cpp_parser", "synthetic_classes"], configure);
+    result.unwrap();
+
+    let mut config = fake_lib_config(dir.path());
+    configure(&mut config);
+    let steps = ["add_implicit_methods", "add_inherited_methods"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+    processor::process(&mut workspace, &config, &steps, None).unwrap();
+
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let functions = database
+        .cpp_items()
+        .filter_map(|item| item.item.as_function_ref())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        functions.iter().filter(|f| f.is_implicit()).count(),
+        8 * count
+    );
+    let inherited = functions
+        .iter()
+        .filter(|f| f.inherited_from().is_some())
+        .collect::<Vec<_>>();
+    assert_eq!(inherited.len(), count * (methods - methods / 2));
+    assert!(inherited.iter().all(|f| !f.is_ambiguous()));
+
+    let derived = CppPath::from_good_str("Derived7");
+    let mut derived_methods = database
+        .all_cpp_methods_of(&derived)
+        .filter(|f| !f.item.is_implicit())
+        .map(|f| f.item.path.last().name.clone())
+        .collect::<Vec<_>>();
+    derived_methods.sort();
+    let mut expected = (0..methods)
+        .map(|j| format!("f{}", j))
+        .chain((methods / 2..methods).map(|j| format!("g{}", j)))
+        .collect::<Vec<_>>();
+    expected.sort();
+    assert_eq!(derived_methods, expected);
+}
+
+#[test]
+fn class_hierarchy() {
+    check_class_hierarchy(10, 20);
+}
+
+/// Takes a few seconds with indexed lookups of bases and methods, and much longer
+/// if they are quadratic. Run with `cargo test -- --ignored` after changing the passes.
+#[test]
+#[ignore]
+fn large_class_hierarchy() {
+    // 5000 classes with 100000 methods in total
+    check_class_hierarchy(2500, 20);
+}
@@ -0,0 +1,68 @@
+use crate::cpp_data::CppProperty;
+use crate::cpp_macros::{join_tokens, parse_qt_property, CppMacroInstance};
+
+fn tokens(text: &str) -> Vec<String> {
+    text.split_whitespace().map(ToString::to_string).collect()
+}
+
+#[test]
+fn macro_instance() {
+    let instance = CppMacroInstance::from_tokens(&tokens(
+        "DECLARE ( const QMap < int , f ( a , b ) > & , name , [ x , y ] )",
+    ))
+    .unwrap();
+    assert_eq!(instance.name, "DECLARE");
+    assert_eq!(
+        instance.arguments,
+        vec!["const QMap<int", "f(a,b)>&", "name", "[x,y]"]
+    );
+    assert_eq!(instance.tokens.len(), 21);
+
+    let instance = CppMacroInstance::from_tokens(&tokens("Q_OBJECT")).unwrap();
+    assert_eq!(instance.name, "Q_OBJECT");
+    assert!(instance.tokens.is_empty());
+    assert!(instance.arguments.is_empty());
+
+    let instance = CppMacroInstance::from_tokens(&tokens("EMPTY ( )")).unwrap();
+    assert!(instance.arguments.is_empty());
+
+    assert!(CppMacroInstance::from_tokens(&tokens("BROKEN ( a , b")).is_err());
+    assert!(CppMacroInstance::from_tokens(&[]).is_err());
+}
+
+#[test]
+fn joined_tokens() {
+    assert_eq!(
+        join_tokens(&tokens("const unsigned int * const &")),
+        "const unsigned int*const&"
+    );
+    assert_eq!(join_tokens(&tokens("QList < QString >")), "QList<QString>");
+    assert_eq!(join_tokens::<String>(&[]), "");
+}
+
+#[test]
+fn qt_property() {
+    assert_eq!(
+        parse_qt_property(&tokens(
+            "QString title READ title WRITE setTitle RESET resetTitle NOTIFY titleChanged \
+             DESIGNABLE true FINAL"
+        ))
+        .unwrap(),
+        CppProperty {
+            name: "title".into(),
+            property_type: "QString".into(),
+            getter: Some("title".into()),
+            setter: Some("setTitle".into()),
+            notify: Some("titleChanged".into()),
+        }
+    );
+    assert_eq!(
+        parse_qt_property(&tokens("QList < int > * items MEMBER m_items"))
+            .unwrap()
+            .property_type,
+        "QList<int>*"
+    );
+    assert!(parse_qt_property(&tokens("title READ title")).is_err());
+    assert!(parse_qt_property(&tokens("int x READ")).is_err());
+    assert!(parse_qt_property(&tokens("int x READ x UNKNOWN")).is_err());
+}
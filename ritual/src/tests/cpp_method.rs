@@ -1,6 +1,8 @@
 use crate::cpp_data::*;
 use crate::cpp_ffi_data::*;
-use crate::cpp_ffi_generator::NewFfiFunctionKind;
+use crate::cpp_ffi_generator::{
+    convert_to_closure_callback, stream_output_class, FfiNameProvider, NewFfiFunctionKind,
+};
 use crate::cpp_function::*;
 use crate::cpp_type::*;
 use itertools::Itertools;
@@ -30,6 +32,14 @@ pub fn empty_membership() -> CppFunctionMemberData {
         visibility: CppVisibility::Public,
         is_signal: false,
         is_slot: false,
+        is_deleted: false,
+        is_defaulted: false,
+        is_final: false,
+        is_override: false,
+        is_explicit: false,
+        is_implicit: false,
+        inherited_from: None,
+        is_ambiguous: false,
     }
 }
 
@@ -43,6 +53,10 @@ pub fn empty_regular_method() -> CppFunction {
         operator: None,
         declaration_code: None,
         cast: None,
+        is_noexcept: false,
+        deprecated: None,
+        doc_comment: None,
+        is_extern_c: false,
     }
 }
 
@@ -605,6 +619,14 @@ fn short_text1() {
             visibility: CppVisibility::Protected,
             is_signal: false,
             is_slot: false,
+            is_deleted: false,
+            is_defaulted: false,
+            is_final: false,
+            is_override: false,
+            is_explicit: false,
+            is_implicit: false,
+            inherited_from: None,
+            is_ambiguous: false,
         }),
         operator: None,
         return_type: CppType::BuiltInNumeric(CppBuiltInNumericType::Int),
@@ -623,9 +645,142 @@ fn short_text1() {
         allows_variadic_arguments: false,
         cast: None,
         declaration_code: None,
+        is_noexcept: false,
+        deprecated: None,
+        doc_comment: None,
+        is_extern_c: false,
     };
     assert_eq!(
         method.short_text(),
         "protected int Class1::method1(int arg1, double arg2 = …) const"
     );
 }
+
+#[test]
+fn stream_output_class() {
+    let std_stream = CppPath::from_good_str("std::basic_ostream");
+    let qdebug = CppPath::from_good_str("QDebug");
+    let class_type = CppType::Class(CppPath::from_good_str("ns::Class1"));
+
+    let mut method = empty_regular_method();
+    method.path = CppPath::from_good_str("ns").join(CppPathItem {
+        name: "operator<<".to_string(),
+        template_arguments: None,
+    });
+    method.operator = Some(CppOperator::BitwiseLeftShift);
+    method.arguments = vec![
+        CppFunctionArgument {
+            argument_type: CppType::new_reference(
+                false,
+                CppType::Class(CppPath::from_good_str("std").join(CppPathItem {
+                    name: "basic_ostream".to_string(),
+                    template_arguments: Some(vec![CppType::BuiltInNumeric(
+                        CppBuiltInNumericType::Char,
+                    )]),
+                })),
+            ),
+            name: "stream".to_string(),
+            has_default_value: false,
+        },
+        CppFunctionArgument {
+            argument_type: CppType::new_reference(true, class_type.clone()),
+            name: "value".to_string(),
+            has_default_value: false,
+        },
+    ];
+    assert_eq!(
+        stream_output_class(&method, &std_stream),
+        Some(CppPath::from_good_str("ns::Class1"))
+    );
+    assert_eq!(stream_output_class(&method, &qdebug), None);
+
+    method.arguments[0].argument_type = CppType::Class(qdebug.clone());
+    method.arguments[1].argument_type = class_type;
+    assert_eq!(
+        stream_output_class(&method, &qdebug),
+        Some(CppPath::from_good_str("ns::Class1"))
+    );
+
+    method.member = Some(empty_membership());
+    assert_eq!(stream_output_class(&method, &qdebug), None);
+}
+
+#[test]
+fn ffi_function_names_are_hashed() {
+    let mut provider = FfiNameProvider::testing();
+    let (path1, name1) = provider.create_function_path("QVector_QVector_QString_at");
+    let (path2, name2) = provider.create_function_path("QVector_QVector_QString_at");
+    assert_eq!(name1, "_QVector_QVector_QString_at");
+    assert_eq!(name2, "_QVector_QVector_QString_at1");
+    assert_ne!(path1, path2);
+    assert_eq!(path1.last().name.len(), "_".len() + 16);
+    assert!(provider.check_collisions().is_ok());
+
+    // names don't depend on the state of the provider
+    let mut provider2 = FfiNameProvider::testing();
+    let (path3, name3) = provider2.create_function_path("QVector_QVector_QString_at");
+    assert_eq!(name3, name1);
+    assert_eq!(path3, path1);
+}
+
+#[test]
+fn closure_callback_arguments() {
+    let int = CppType::BuiltInNumeric(CppBuiltInNumericType::Int);
+    let void_ptr = CppType::new_pointer(false, CppType::Void);
+    let function_type = CppFunctionPointerType {
+        return_type: Box::new(int.clone()),
+        arguments: vec![int.clone(), void_ptr.clone()],
+        allows_variadic_arguments: false,
+    };
+    let mut method1 = empty_regular_method();
+    method1.arguments = vec![
+        CppFunctionArgument {
+            name: "handler".to_string(),
+            argument_type: CppType::FunctionPointer(function_type.clone()),
+            has_default_value: false,
+        },
+        CppFunctionArgument {
+            name: "user_data".to_string(),
+            argument_type: void_ptr.clone(),
+            has_default_value: false,
+        },
+        CppFunctionArgument {
+            name: "flags".to_string(),
+            argument_type: int.clone(),
+            has_default_value: false,
+        },
+    ];
+    let callback = CppClosureCallback {
+        callback_index: 0,
+        user_data_index: 1,
+        function_type,
+        callback_user_data_index: 1,
+        unregister: Some((CppPath::from_good_str("remove_handler"), vec![])),
+    };
+    assert_eq!(callback.closure_arguments(), vec![int.clone()]);
+
+    let r = convert_to_closure_callback(to_ffi(&method1, None), callback).unwrap();
+    let names = r
+        .arguments
+        .iter()
+        .map(|arg| arg.name.as_str())
+        .collect_vec();
+    assert_eq!(names, vec!["flags", "callback", "deleter", "data"]);
+    assert_eq!(r.arguments[0].meaning, CppFfiArgumentMeaning::Argument(2));
+    assert_eq!(
+        r.arguments[1].meaning,
+        CppFfiArgumentMeaning::ClosureCallback
+    );
+    assert_eq!(
+        r.arguments[1].argument_type.ffi_type(),
+        &CppType::FunctionPointer(CppFunctionPointerType {
+            return_type: Box::new(int.clone()),
+            arguments: vec![void_ptr.clone(), int],
+            allows_variadic_arguments: false,
+        })
+    );
+    assert_eq!(r.arguments[3].argument_type.ffi_type(), &void_ptr);
+    // the closure object is returned to be released by the Rust side
+    assert_eq!(r.return_type.ffi_type(), &void_ptr);
+    assert!(matches!(r.kind, CppFfiFunctionKind::ClosureCallback { .. }));
+}
@@ -0,0 +1,46 @@
+use crate::config::{Config, DefaultArgumentsStrategy};
+use crate::tests::try_run_steps;
+
+#[test]
+fn omitted_arguments_variants() {
+    let code = "class A {
+    public:
+        void f(int a, int b = 1, int c = 2, int d = 3);
+        void g(int a, int b = 1);
+        void g(int a);
+        void h(int a, int b = 0);
+        void h(int a, double b = 0);
+    };";
+    let signatures = |configure: &dyn Fn(&mut Config)| {
+        let (_dir, mut workspace, result) =
+            try_run_steps(code, &["cpp_parser", "omitting_arguments"], |config| {
+                configure(config)
+            });
+        result.unwrap();
+        let database = workspace
+            .get_database_client("A", &[], true, false)
+            .unwrap();
+        let mut signatures = database
+            .cpp_items()
+            .filter_map(|item| item.item.as_function_ref())
+            .map(|f| format!("{}/{}", f.path.last().name, f.arguments.len()))
+            .collect::<Vec<_>>();
+        signatures.sort();
+        signatures
+    };
+
+    assert_eq!(
+        signatures(&|_| {}),
+        vec!["f/1", "f/2", "f/3", "f/4", "g/1", "g/2", "h/2", "h/2"]
+    );
+    assert_eq!(
+        signatures(&|config| config.set_omitted_arguments_variants_limit(Some(2))),
+        vec!["f/1", "f/2", "f/4", "g/1", "g/2", "h/2", "h/2"]
+    );
+    assert_eq!(
+        signatures(&|config| {
+            config.set_default_arguments_strategy(DefaultArgumentsStrategy::Keep)
+        }),
+        vec!["f/4", "g/1", "g/2", "h/2", "h/2"]
+    );
+}
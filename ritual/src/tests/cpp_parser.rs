@@ -1,14 +1,18 @@
-use crate::config::{Config, CrateProperties};
+use crate::config::{Config, CrateProperties, HeaderPathFilter};
 use crate::cpp_data::*;
 use crate::cpp_function::*;
+use crate::cpp_macros::extract_qt_property;
 use crate::cpp_operator::CppOperator;
 use crate::cpp_type::*;
 use crate::processor;
+use crate::tests::{fake_lib_config, run_steps, try_run_steps, FAKE_LIB_HEADER};
 use crate::workspace::Workspace;
+use itertools::Itertools;
 use ritual_common::cpp_build_config::CppBuildPaths;
-use ritual_common::file_utils::create_dir;
-use ritual_common::file_utils::create_file;
+use ritual_common::file_utils::{create_dir, create_file, remove_file};
 use std::io::Write;
+use std::path::Path;
+use tempdir::TempDir;
 
 struct ParserCppData {
     types: Vec<CppTypeDeclaration>,
@@ -20,29 +24,15 @@ struct ParserCppData {
 }
 
 fn run_parser(code: &'static str) -> ParserCppData {
-    let dir = tempdir::TempDir::new("test_cpp_parser_run").unwrap();
-
-    let mut workspace = Workspace::new(dir.path().into()).unwrap();
-
-    let include_dir = dir.path().join("include");
-    create_dir(&include_dir).unwrap();
-    let include_name = "myfakelib.h";
-    let include_file_path = include_dir.join(&include_name);
-    {
-        let mut include_file = create_file(&include_file_path).unwrap();
-        writeln!(include_file, "{}", code).unwrap();
-    }
-
-    let mut paths = CppBuildPaths::new();
-    paths.add_include_path(include_dir);
-
-    let mut config = Config::new(CrateProperties::new("A", "0.0.0"));
-    config.add_include_directive(include_name);
-    config.set_cpp_build_paths(paths);
-    config.add_target_include_path(include_file_path);
-
-    processor::process(&mut workspace, &config, &["cpp_parser".into()], None).unwrap();
+    run_parser_with_config(code, |_| {})
+}
 
+fn run_parser_with_config(
+    code: &'static str,
+    configure: impl FnOnce(&mut Config),
+) -> ParserCppData {
+    let (_dir, mut workspace, result) = try_run_steps(code, &["cpp_parser"], configure);
+    result.unwrap();
     let database = workspace
         .get_database_client("A", &[], true, false)
         .unwrap();
@@ -101,6 +91,10 @@ fn simple_func() {
             allows_variadic_arguments: false,
             cast: None,
             declaration_code: Some("int func1 ( int x )".to_string()),
+            is_noexcept: false,
+            deprecated: None,
+            doc_comment: None,
+            is_extern_c: false,
         }
     );
 }
@@ -131,6 +125,10 @@ fn simple_func_with_default_value() {
             allows_variadic_arguments: false,
             cast: None,
             declaration_code: Some("bool func1 ( int x = 42 )".to_string()),
+            is_noexcept: false,
+            deprecated: None,
+            doc_comment: None,
+            is_extern_c: false,
         }
     );
 }
@@ -179,6 +177,7 @@ fn functions_with_class_arg() {
     );
     assert_eq!(data.fields[2].visibility, CppVisibility::Public);
     assert_eq!(data.fields[2].is_static, true);
+    assert_eq!(data.fields[2].is_const, false);
 
     assert_eq!(data.methods.len(), 3);
     assert_eq!(
@@ -196,6 +195,10 @@ fn functions_with_class_arg() {
             allows_variadic_arguments: false,
             cast: None,
             declaration_code: Some("bool func1 ( Magic x )".to_string()),
+            is_noexcept: false,
+            deprecated: None,
+            doc_comment: None,
+            is_extern_c: false,
         }
     );
     assert_eq!(
@@ -216,6 +219,10 @@ fn functions_with_class_arg() {
             allows_variadic_arguments: false,
             cast: None,
             declaration_code: Some("bool func1 ( Magic * x )".to_string()),
+            is_noexcept: false,
+            deprecated: None,
+            doc_comment: None,
+            is_extern_c: false,
         }
     );
     assert_eq!(
@@ -236,10 +243,42 @@ fn functions_with_class_arg() {
             allows_variadic_arguments: false,
             cast: None,
             declaration_code: Some("bool func2 ( const Magic & )".to_string()),
+            is_noexcept: false,
+            deprecated: None,
+            doc_comment: None,
+            is_extern_c: false,
         }
     );
 }
 
+#[test]
+fn class_field_visibility_and_constness() {
+    let data = run_parser(
+        "
+        class Magic {
+        public:
+            const int a;
+        protected:
+            int b;
+        private:
+            const int c;
+        };
+        ",
+    );
+    assert_eq!(data.fields.len(), 3);
+    assert_eq!(data.fields[0].path, CppPath::from_good_str("Magic::a"));
+    assert_eq!(data.fields[0].visibility, CppVisibility::Public);
+    assert_eq!(data.fields[0].is_const, true);
+
+    assert_eq!(data.fields[1].path, CppPath::from_good_str("Magic::b"));
+    assert_eq!(data.fields[1].visibility, CppVisibility::Protected);
+    assert_eq!(data.fields[1].is_const, false);
+
+    assert_eq!(data.fields[2].path, CppPath::from_good_str("Magic::c"));
+    assert_eq!(data.fields[2].visibility, CppVisibility::Private);
+    assert_eq!(data.fields[2].is_const, true);
+}
+
 #[test]
 fn free_operator() {
     let data = run_parser(
@@ -289,6 +328,10 @@ fn variadic_func() {
             allows_variadic_arguments: true,
             cast: None,
             declaration_code: Some("int my_printf ( const char * format , ... )".to_string()),
+            is_noexcept: false,
+            deprecated: None,
+            doc_comment: None,
+            is_extern_c: false,
         }
     );
 }
@@ -336,6 +379,10 @@ fn free_template_func() {
             allows_variadic_arguments: false,
             cast: None,
             declaration_code: Some("template < typename T > T abs ( T value )".to_string()),
+            is_noexcept: false,
+            deprecated: None,
+            doc_comment: None,
+            is_extern_c: false,
         }
     );
 }
@@ -377,6 +424,10 @@ fn free_func_operator_sub() {
                 allows_variadic_arguments: false,
                 cast: None,
                 declaration_code: Some("C1 operator - ( C1 a , C1 b )".to_string()),
+                is_noexcept: false,
+                deprecated: None,
+                doc_comment: None,
+                is_extern_c: false,
             }
         );
     }
@@ -415,6 +466,14 @@ fn simple_class_method() {
                 visibility: CppVisibility::Public,
                 is_signal: false,
                 is_slot: false,
+                is_deleted: false,
+                is_defaulted: false,
+                is_final: false,
+                is_override: false,
+                is_explicit: false,
+                is_implicit: false,
+                inherited_from: None,
+                is_ambiguous: false,
             }),
             operator: None,
             return_type: CppType::BuiltInNumeric(CppBuiltInNumericType::Int),
@@ -426,6 +485,10 @@ fn simple_class_method() {
             allows_variadic_arguments: false,
             cast: None,
             declaration_code: Some("int func1 ( int x )".to_string()),
+            is_noexcept: false,
+            deprecated: None,
+            doc_comment: None,
+            is_extern_c: false,
         }
     );
 }
@@ -605,6 +668,14 @@ fn template_class_method() {
                 visibility: CppVisibility::Public,
                 is_signal: false,
                 is_slot: false,
+                is_deleted: false,
+                is_defaulted: false,
+                is_final: false,
+                is_override: false,
+                is_explicit: false,
+                is_implicit: false,
+                inherited_from: None,
+                is_ambiguous: false,
             }),
             operator: None,
             return_type: CppType::TemplateParameter(CppTemplateParameter {
@@ -620,6 +691,10 @@ fn template_class_method() {
             allows_variadic_arguments: false,
             cast: None,
             declaration_code: Some("T get ( int index )".to_string()),
+            is_noexcept: false,
+            deprecated: None,
+            doc_comment: None,
+            is_extern_c: false,
         }
     );
     assert_eq!(
@@ -757,7 +832,12 @@ fn simple_enum() {
     );
     assert_eq!(data.types.len(), 1);
     assert_eq!(data.types[0].path, CppPath::from_good_str("Enum1"));
-    assert_eq!(data.types[0].kind, CppTypeDeclarationKind::Enum);
+    assert_eq!(
+        data.types[0].kind,
+        CppTypeDeclarationKind::Enum {
+            underlying_type: CppType::BuiltInNumeric(CppBuiltInNumericType::Int),
+        }
+    );
     assert_eq!(
         data.enum_values,
         vec![
@@ -788,7 +868,12 @@ fn simple_enum2() {
     );
     assert_eq!(data.types.len(), 1);
     assert_eq!(data.types[0].path, CppPath::from_good_str("ns1::Enum1"));
-    assert_eq!(data.types[0].kind, CppTypeDeclarationKind::Enum);
+    assert_eq!(
+        data.types[0].kind,
+        CppTypeDeclarationKind::Enum {
+            underlying_type: CppType::BuiltInNumeric(CppBuiltInNumericType::Int),
+        }
+    );
     assert_eq!(
         data.enum_values,
         vec![
@@ -809,6 +894,35 @@ fn simple_enum2() {
     assert_eq!(data.namespaces, vec![CppPath::from_good_str("ns1")]);
 }
 
+#[test]
+fn enum_underlying_types() {
+    let data = run_parser(
+        "
+        enum class Enum1 : unsigned char { A, B = 200 };
+        enum class Enum2 { C };
+        enum Enum3 { D = 0x80000000 };
+        enum Enum4 : long long { E = -1 };
+        ",
+    );
+    let underlying_types = data
+        .types
+        .iter()
+        .map(|t| match &t.kind {
+            CppTypeDeclarationKind::Enum { underlying_type } => underlying_type.clone(),
+            _ => panic!("expected enum"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        underlying_types,
+        vec![
+            CppType::BuiltInNumeric(CppBuiltInNumericType::UChar),
+            CppType::BuiltInNumeric(CppBuiltInNumericType::Int),
+            CppType::BuiltInNumeric(CppBuiltInNumericType::UInt),
+            CppType::BuiltInNumeric(CppBuiltInNumericType::LongLong),
+        ]
+    );
+}
+
 #[test]
 fn template_instantiation() {
     let data = run_parser(
@@ -1220,3 +1334,948 @@ fn elaborated_enum_type() {
         }
     );
 }
+
+#[test]
+fn deleted_copy_constructor() {
+    let (_dir, mut workspace) = run_steps(
+        "
+        class A {
+        public:
+            A() = default;
+            A(const A& other) = delete;
+            A& operator=(const A& other) = delete;
+        };
+        ",
+        &["cpp_parser", "add_implicit_methods", "cpp_ffi_generator"],
+    );
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+
+    let functions = database
+        .cpp_items()
+        .filter_map(|item| item.filter_map(|item| item.as_function_ref()))
+        .collect::<Vec<_>>();
+
+    let default_constructor = functions
+        .iter()
+        .find(|f| f.item.is_constructor() && f.item.arguments.is_empty())
+        .unwrap();
+    assert!(default_constructor.item.is_defaulted());
+    assert!(!default_constructor.item.is_deleted());
+
+    let copy_functions = functions
+        .iter()
+        .filter(|f| {
+            f.item.is_copy_constructor() || f.item.operator == Some(CppOperator::Assignment)
+        })
+        .collect::<Vec<_>>();
+    // implicit methods must not be added for explicitly deleted ones
+    assert_eq!(copy_functions.len(), 2);
+    for function in copy_functions {
+        assert!(function.item.is_deleted());
+        assert!(!database
+            .ffi_items()
+            .any(|ffi_item| ffi_item.source_id.as_ref() == Some(&function.id)));
+    }
+}
+
+#[test]
+fn noexcept_functions() {
+    let data = run_parser(
+        "
+        class A {
+        public:
+            int f1() noexcept;
+            int f2() throw();
+            int f3() noexcept(sizeof(int) == 4);
+            int f4();
+        };
+        void f5() noexcept;
+        ",
+    );
+    let is_noexcept = |name: &str| {
+        data.methods
+            .iter()
+            .find(|m| m.path.last().name == name)
+            .unwrap()
+            .is_noexcept
+    };
+    assert!(is_noexcept("f1"));
+    assert!(is_noexcept("f2"));
+    assert!(!is_noexcept("f3"));
+    assert!(!is_noexcept("f4"));
+    assert!(is_noexcept("f5"));
+}
+
+#[test]
+fn deprecated_items() {
+    let data = run_parser(
+        "
+        class [[deprecated(\"use B instead\")]] A {};
+        class B {
+        public:
+            [[deprecated]] void f1();
+            __attribute__((deprecated(\"use f3\"))) void f2();
+            void f3();
+        };
+        ",
+    );
+    let type_a = data
+        .types
+        .iter()
+        .find(|t| t.path == CppPath::from_good_str("A"))
+        .unwrap();
+    assert_eq!(
+        type_a.deprecated.as_ref().unwrap().message,
+        Some("use B instead".to_string())
+    );
+    let type_b = data
+        .types
+        .iter()
+        .find(|t| t.path == CppPath::from_good_str("B"))
+        .unwrap();
+    assert!(type_b.deprecated.is_none());
+
+    let deprecated = |name: &str| {
+        data.methods
+            .iter()
+            .find(|m| m.path.last().name == name)
+            .unwrap()
+            .deprecated
+            .clone()
+    };
+    assert_eq!(deprecated("f1").unwrap().message, None);
+    assert_eq!(
+        deprecated("f2").unwrap().message,
+        Some("use f3".to_string())
+    );
+    assert!(deprecated("f3").is_none());
+}
+
+#[test]
+fn doc_comments() {
+    let data = run_parser(
+        "
+        /// Class A.
+        class A {
+        public:
+            /** Takes an int. */
+            void f(int x);
+            /** Takes a double. */
+            void f(double x);
+            void g();
+        };
+        ",
+    );
+    let type_a = data
+        .types
+        .iter()
+        .find(|t| t.path == CppPath::from_good_str("A"))
+        .unwrap();
+    assert_eq!(type_a.doc_comment, Some("/// Class A.".to_string()));
+
+    let f_int = data
+        .methods
+        .iter()
+        .find(|m| {
+            m.path.last().name == "f"
+                && m.arguments[0].argument_type
+                    == CppType::BuiltInNumeric(CppBuiltInNumericType::Int)
+        })
+        .unwrap();
+    assert_eq!(f_int.doc_comment, Some("/** Takes an int. */".to_string()));
+    let f_double = data
+        .methods
+        .iter()
+        .find(|m| {
+            m.path.last().name == "f"
+                && m.arguments[0].argument_type
+                    == CppType::BuiltInNumeric(CppBuiltInNumericType::Double)
+        })
+        .unwrap();
+    assert_eq!(
+        f_double.doc_comment,
+        Some("/** Takes a double. */".to_string())
+    );
+    let g = data
+        .methods
+        .iter()
+        .find(|m| m.path.last().name == "g")
+        .unwrap();
+    assert_eq!(g.doc_comment, None);
+}
+
+#[test]
+fn custom_parser_arguments() {
+    let code = "
+        struct Pair {
+            int first;
+            int second;
+        };
+        inline int sum(Pair pair) {
+            auto [first, second] = pair;
+            if constexpr (sizeof(int) == 4) {
+                return first + second;
+            }
+            return 0;
+        }
+        ";
+    let clang_errors = |workspace: &mut Workspace| {
+        let database = workspace
+            .get_database_client("A", &[], true, false)
+            .unwrap();
+        database
+            .rejections()
+            .iter()
+            .filter(|r| r.reason.starts_with("clang error"))
+            .count()
+    };
+
+    // clang accepts C++17 features in earlier standards with a warning,
+    // so `-pedantic-errors` is needed to reject them
+    let (_dir, mut workspace, result) = try_run_steps(code, &["cpp_parser"], |config| {
+        config.add_cpp_parser_arguments(&["-pedantic-errors"])
+    });
+    result.unwrap();
+    assert_ne!(clang_errors(&mut workspace), 0);
+
+    let (_dir, mut workspace, result) = try_run_steps(code, &["cpp_parser"], |config| {
+        config.add_cpp_parser_arguments(&["-pedantic-errors", "-std=c++17"])
+    });
+    result.unwrap();
+    assert_eq!(clang_errors(&mut workspace), 0);
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    assert!(database
+        .cpp_items()
+        .filter_map(|item| item.item.as_function_ref())
+        .any(|f| f.path == CppPath::from_good_str("sum")));
+}
+
+#[test]
+fn duplicate_class_definition() {
+    let (_dir, mut workspace) = run_steps(
+        "
+        class A { public: int f(); };
+        class A { public: int g(); };
+        class B {};
+        ",
+        &["cpp_parser"],
+    );
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    assert!(database
+        .cpp_items()
+        .filter_map(|item| item.item.as_type_ref())
+        .any(|t| t.path == CppPath::from_good_str("B")));
+    assert!(database
+        .rejections()
+        .iter()
+        .any(|r| r.step == "cpp_parser" && r.reason.contains("redefinition")));
+}
+
+#[test]
+fn rejections_for_item() {
+    let (_dir, mut workspace) = run_steps(
+        "
+        namespace ns { void f(volatile int* x); void g(int x); }
+        ",
+        &["cpp_parser"],
+    );
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let rejections = database.rejections_for("ns::f").collect::<Vec<_>>();
+    assert_eq!(rejections.len(), 1);
+    assert_eq!(rejections[0].step, "cpp_parser");
+    assert!(rejections[0].reason.contains("Volatile"));
+    assert!(rejections[0].include_file.is_some());
+    assert_eq!(database.rejections_for("ns::g").count(), 0);
+}
+
+#[test]
+fn blocked_name_patterns() {
+    let data = run_parser_with_config(
+        "
+        namespace Internal {
+            class Helper {};
+            int helper_func();
+        }
+        class WidgetPrivate {};
+        class Widget {
+        public:
+            void show();
+            void updatePrivate();
+        };
+        ",
+        |config| {
+            config
+                .add_cpp_parser_blocked_name_pattern("Internal::.*")
+                .unwrap();
+            config
+                .add_cpp_parser_blocked_name_pattern(".*Private")
+                .unwrap();
+        },
+    );
+    let type_names = data
+        .types
+        .iter()
+        .map(|t| t.path.to_templateless_string())
+        .collect::<Vec<_>>();
+    assert_eq!(type_names, vec!["Widget".to_string()]);
+    let method_names = data
+        .methods
+        .iter()
+        .map(|m| m.path.to_templateless_string())
+        .collect::<Vec<_>>();
+    assert_eq!(method_names, vec!["Widget::show".to_string()]);
+    assert_eq!(data.namespaces, vec![CppPath::from_good_str("Internal")]);
+}
+
+#[test]
+fn allowed_name_patterns() {
+    let data = run_parser_with_config(
+        "
+        class Base { public: int x; };
+        class Point { public: int x, y; };
+        class Unused { public: void f(); };
+        enum Mode { Mode1, Mode2 };
+        class Widget : public Base {
+        public:
+            void move(const Point& point, Mode mode);
+        };
+        int free_func(Unused* value);
+        ",
+        |config| {
+            config
+                .add_cpp_parser_allowed_name_pattern("Widget")
+                .unwrap();
+        },
+    );
+    let mut type_names = data
+        .types
+        .iter()
+        .map(|t| t.path.to_templateless_string())
+        .collect::<Vec<_>>();
+    type_names.sort();
+    assert_eq!(type_names, vec!["Base", "Mode", "Point", "Widget"]);
+    assert_eq!(data.methods.len(), 1);
+    assert_eq!(data.methods[0].path, CppPath::from_good_str("Widget::move"));
+    assert_eq!(data.bases.len(), 1);
+    assert_eq!(data.fields.len(), 3);
+    assert_eq!(data.enum_values.len(), 2);
+}
+
+#[test]
+fn forward_declared_types() {
+    let data = run_parser(
+        "
+        class Impl;
+        class Unused;
+        class Widget {
+        public:
+            Impl* impl();
+            void set_impl(const Impl& impl);
+            Impl take_impl();
+        };
+        ",
+    );
+    let impl_type = data
+        .types
+        .iter()
+        .find(|t| t.path == CppPath::from_good_str("Impl"))
+        .unwrap();
+    assert!(impl_type.kind.is_opaque());
+    assert!(!data
+        .types
+        .iter()
+        .any(|t| t.path == CppPath::from_good_str("Unused")));
+
+    let mut method_names = data
+        .methods
+        .iter()
+        .map(|m| m.path.to_templateless_string())
+        .collect::<Vec<_>>();
+    method_names.sort();
+    assert_eq!(method_names, vec!["Widget::impl", "Widget::set_impl"]);
+}
+
+#[test]
+fn final_and_override_specifiers() {
+    let data = run_parser(
+        "
+        class Base {
+        public:
+            virtual void f1();
+            virtual void f2();
+            virtual void f3();
+        };
+        class Derived final : public Base {
+        public:
+            void f1() override;
+            void f2() final;
+            void f3();
+        };
+        ",
+    );
+    let base = data
+        .types
+        .iter()
+        .find(|t| t.path == CppPath::from_good_str("Base"))
+        .unwrap();
+    assert!(!base.kind.is_final_class());
+    let derived = data
+        .types
+        .iter()
+        .find(|t| t.path == CppPath::from_good_str("Derived"))
+        .unwrap();
+    assert!(derived.kind.is_final_class());
+
+    let method = |name: &str| {
+        data.methods
+            .iter()
+            .find(|m| m.path == CppPath::from_good_str(name))
+            .unwrap()
+    };
+    assert!(!method("Base::f1").is_override());
+    assert!(!method("Base::f1").is_final());
+    assert!(method("Derived::f1").is_override());
+    assert!(!method("Derived::f1").is_final());
+    assert!(method("Derived::f2").is_override());
+    assert!(method("Derived::f2").is_final());
+    assert!(method("Derived::f3").is_override());
+    assert!(!method("Derived::f3").is_final());
+}
+
+#[test]
+fn array_types() {
+    let data = run_parser(
+        "
+        class Matrix {
+        public:
+            void setValues(const float values[16]);
+            char name[32];
+        };
+        ",
+    );
+    assert_eq!(data.methods.len(), 1);
+    assert_eq!(
+        data.methods[0].arguments[0].argument_type,
+        CppType::Array {
+            element_type: Box::new(CppType::BuiltInNumeric(CppBuiltInNumericType::Float)),
+            is_const: true,
+            size: Some(16),
+        }
+    );
+    assert_eq!(data.fields.len(), 1);
+    assert_eq!(
+        data.fields[0].field_type,
+        CppType::Array {
+            element_type: Box::new(CppType::BuiltInNumeric(CppBuiltInNumericType::Char)),
+            is_const: false,
+            size: Some(32),
+        }
+    );
+}
+
+#[test]
+fn const_pointer_levels() {
+    let data = run_parser(
+        "
+        void f1(const char** a);
+        void f2(char* const* a);
+        void f3(const char* const* a);
+        void run(int argc, const char* const argv[]);
+        ",
+    );
+    assert_eq!(data.methods.len(), 4);
+    let char1 = CppType::BuiltInNumeric(CppBuiltInNumericType::Char);
+    let expected = [
+        CppType::new_pointer(false, CppType::new_pointer(true, char1.clone())),
+        CppType::new_pointer(true, CppType::new_pointer(false, char1.clone())),
+        CppType::new_pointer(true, CppType::new_pointer(true, char1.clone())),
+    ];
+    for (method, expected) in data.methods.iter().zip(expected.iter()) {
+        assert_eq!(&method.arguments[0].argument_type, expected);
+    }
+    assert_eq!(
+        data.methods[3].arguments[1].argument_type,
+        CppType::Array {
+            element_type: Box::new(CppType::new_pointer(true, char1.clone())),
+            is_const: true,
+            size: None,
+        }
+    );
+}
+
+#[test]
+fn explicit_specifier() {
+    let data = run_parser(
+        "
+        class Angle {
+        public:
+            explicit Angle(double degrees);
+            Angle(int degrees);
+            operator double() const;
+            explicit operator bool() const;
+        };
+        ",
+    );
+    assert_eq!(data.methods.len(), 4);
+    let explicit_methods = data
+        .methods
+        .iter()
+        .filter(|method| method.is_explicit())
+        .collect::<Vec<_>>();
+    assert_eq!(explicit_methods.len(), 2);
+    assert!(explicit_methods[0].is_constructor());
+    assert_eq!(
+        explicit_methods[0].arguments[0].argument_type,
+        CppType::BuiltInNumeric(CppBuiltInNumericType::Double)
+    );
+    assert_eq!(
+        explicit_methods[1].operator,
+        Some(CppOperator::Conversion(CppType::BuiltInNumeric(
+            CppBuiltInNumericType::Bool
+        )))
+    );
+}
+
+#[test]
+fn item_origins() {
+    let (_dir, mut workspace) = run_steps(
+        "
+        class Point {
+        public:
+            int x() const;
+        };
+        ",
+        &["cpp_parser"],
+    );
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let function = database
+        .cpp_items()
+        .find(|item| item.item.as_function_ref().is_some())
+        .unwrap();
+    let origin = database.item_origin(&function.id).unwrap();
+    assert_eq!(origin.include_file, "myfakelib.h");
+    assert!(origin.location.include_file_path.ends_with("myfakelib.h"));
+    assert_eq!(origin.location.line, 4);
+    assert_eq!(origin.location.column, 17);
+}
+
+#[test]
+fn parse_only_json() {
+    let (dir, mut workspace) = run_steps(
+        "
+        class Point {
+        public:
+            int x() const;
+        };
+        ",
+        &[],
+    );
+    let data = processor::parse_only(&mut workspace, &fake_lib_config(dir.path())).unwrap();
+    assert_eq!(data.schema_version, CPP_DATA_SCHEMA_VERSION);
+    assert_eq!(data.crate_name, "A");
+    let function = data
+        .items
+        .iter()
+        .find(|item| item.item.as_function_ref().is_some())
+        .unwrap();
+    assert_eq!(
+        function.item.as_function_ref().unwrap().path,
+        CppPath::from_good_str("Point::x")
+    );
+    assert_eq!(function.origin.as_ref().unwrap().location.line, 4);
+
+    let mut json = Vec::new();
+    data.to_json(&mut json).unwrap();
+    assert_eq!(CppData::from_json(&json[..]).unwrap(), data);
+
+    let mut value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    value["schema_version"] = (CPP_DATA_SCHEMA_VERSION + 1).into();
+    let json = serde_json::to_vec(&value).unwrap();
+    assert!(CppData::from_json(&json[..]).is_err());
+}
+
+/// Writes `code` to the fake library header in `include_dir`, runs the parser
+/// with `config` and returns names of parsed functions.
+fn parsed_function_names(dir: &Path, include_dir: &Path, config: &Config) -> Vec<String> {
+    {
+        let mut include_file = create_file(include_dir.join(FAKE_LIB_HEADER)).unwrap();
+        writeln!(include_file, "int func1(int x);").unwrap();
+    }
+    let mut workspace = Workspace::new(dir.into()).unwrap();
+    processor::process(&mut workspace, config, &["cpp_parser".to_string()], None).unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    database
+        .cpp_items()
+        .filter_map(|item| item.item.as_function_ref())
+        .map(|f| f.path.to_cpp_pseudo_code())
+        .collect()
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinked_include_dir() {
+    let dir = TempDir::new("test_cpp_parser_run").unwrap();
+    let real_include_dir = dir.path().join("real_include");
+    create_dir(&real_include_dir).unwrap();
+    std::os::unix::fs::symlink(&real_include_dir, dir.path().join("include")).unwrap();
+
+    let config = fake_lib_config(dir.path());
+    assert_eq!(
+        parsed_function_names(dir.path(), &real_include_dir, &config),
+        vec!["func1"]
+    );
+}
+
+#[test]
+fn parent_dir_in_target_include_path() {
+    let dir = TempDir::new("test_cpp_parser_run").unwrap();
+    let include_dir = dir.path().join("include");
+    create_dir(&include_dir).unwrap();
+    create_dir(dir.path().join("other")).unwrap();
+
+    let mut paths = CppBuildPaths::new();
+    paths.add_include_path(dir.path().join("other/../include"));
+    let mut config = Config::new(CrateProperties::new("A", "0.0.0"));
+    config.add_include_directive(FAKE_LIB_HEADER);
+    config.set_cpp_build_paths(paths);
+    config.add_target_include_path(dir.path().join("other/../include/").join(FAKE_LIB_HEADER));
+    assert_eq!(
+        parsed_function_names(dir.path(), &include_dir, &config),
+        vec!["func1"]
+    );
+}
+
+#[test]
+fn include_directive_path_filter() {
+    let run = |filter: Option<&str>| {
+        let dir = TempDir::new("test_cpp_parser_run").unwrap();
+        let include_dir = dir.path().join("include");
+        create_dir(&include_dir).unwrap();
+        let other_dir = dir.path().join("other");
+        create_dir(&other_dir).unwrap();
+
+        let mut paths = CppBuildPaths::new();
+        paths.add_include_path(&include_dir);
+        let mut config = Config::new(CrateProperties::new("A", "0.0.0"));
+        config.add_include_directive(FAKE_LIB_HEADER);
+        config.set_cpp_build_paths(paths);
+        config.add_target_include_path(&other_dir);
+        if let Some(filter) = filter {
+            config.add_include_directive_path_filter(filter).unwrap();
+        }
+        parsed_function_names(dir.path(), &include_dir, &config)
+    };
+    assert!(run(None).is_empty());
+    assert!(run(Some("other*.h")).is_empty());
+    assert_eq!(run(Some("myfake*.h")), vec!["func1"]);
+    assert_eq!(run(Some("include/*.h")), vec!["func1"]);
+}
+
+#[test]
+fn header_path_filters() {
+    let matches = |pattern: &str, path: &str| {
+        HeaderPathFilter::new(pattern)
+            .unwrap()
+            .matches(Path::new(path))
+    };
+    assert!(matches("q*.h", "/usr/include/QtCore/qobject.h"));
+    assert!(!matches("q*.h", "/usr/include/QtCore/QObject"));
+    assert!(matches("QtCore/*.h", "/usr/include/QtCore/qobject.h"));
+    assert!(!matches("QtCore/*.h", "/usr/include/MyQtCore/qobject.h"));
+    assert!(!matches(
+        "QtCore/*.h",
+        "/usr/include/QtCore/private/qobject_p.h"
+    ));
+    assert!(matches(
+        "QtCore/**.h",
+        "/usr/include/QtCore/private/qobject_p.h"
+    ));
+    assert!(matches(
+        "/usr/**/q?bject.h",
+        "/usr/include/QtCore/qobject.h"
+    ));
+    assert!(!matches("/include/**", "/usr/include/QtCore/qobject.h"));
+    assert!(HeaderPathFilter::new("").is_err());
+}
+
+#[test]
+fn incremental_parsing() {
+    let dir = TempDir::new("test_cpp_parser_run").unwrap();
+    let mut workspace = Workspace::new(dir.path().into()).unwrap();
+    let include_dir = dir.path().join("include");
+    create_dir(&include_dir).unwrap();
+    let write_header = |name: &str, code: &str| {
+        let mut file = create_file(include_dir.join(name)).unwrap();
+        writeln!(file, "{}", code).unwrap();
+    };
+    let config = |headers: &[&str]| {
+        let mut paths = CppBuildPaths::new();
+        paths.add_include_path(&include_dir);
+        let mut config = Config::new(CrateProperties::new("A", "0.0.0"));
+        for header in headers {
+            config.add_include_directive(header);
+        }
+        config.set_cpp_build_paths(paths);
+        config.add_target_include_path(&include_dir);
+        config
+    };
+    let steps = vec!["cpp_parser".to_string()];
+    let functions = |workspace: &mut Workspace| {
+        let database = workspace
+            .get_database_client("A", &[], true, false)
+            .unwrap();
+        let mut functions = database
+            .cpp_items()
+            .filter_map(|item| {
+                item.item
+                    .as_function_ref()
+                    .map(|f| (f.path.to_cpp_pseudo_code(), item.id.clone()))
+            })
+            .collect::<Vec<_>>();
+        functions.sort();
+        functions
+    };
+
+    write_header("a.h", "int func_a(int x);");
+    write_header("b.h", "int func_b1(int x);");
+    write_header("c.h", "int func_c(int x);");
+    processor::process(
+        &mut workspace,
+        &config(&["a.h", "b.h", "c.h"]),
+        &steps,
+        None,
+    )
+    .unwrap();
+    let old_functions = functions(&mut workspace);
+    assert_eq!(
+        old_functions
+            .iter()
+            .map(|f| f.0.as_str())
+            .collect::<Vec<_>>(),
+        vec!["func_a", "func_b1", "func_c"]
+    );
+
+    write_header("b.h", "int func_b2(int x);");
+    remove_file(include_dir.join("c.h")).unwrap();
+    processor::process(&mut workspace, &config(&["a.h", "b.h"]), &steps, None).unwrap();
+    let new_functions = functions(&mut workspace);
+    assert_eq!(
+        new_functions
+            .iter()
+            .map(|f| f.0.as_str())
+            .collect::<Vec<_>>(),
+        vec!["func_a", "func_b2"]
+    );
+    // items of the unchanged header are kept as is
+    assert_eq!(new_functions[0], old_functions[0]);
+}
+
+#[test]
+fn macro_extractors() {
+    let data = run_parser_with_config(
+        "
+        #define Q_PROPERTY(...)
+        #define MYLIB_PROPERTY(type, name, getter, setter)
+        class A {
+            Q_PROPERTY(QMap<int, int> map READ map WRITE setMap NOTIFY mapChanged FINAL)
+            Q_PROPERTY(int size READ size CONSTANT)
+            MYLIB_PROPERTY(unsigned int, count, count, setCount)
+        public:
+            class Nested {
+                Q_PROPERTY(int nested READ nested)
+            };
+        };
+        class B {};
+        ",
+        |config| {
+            config.add_macro_extractor(extract_qt_property);
+            config.add_macro_extractor(|instance, declaration| {
+                if instance.name != "MYLIB_PROPERTY" {
+                    return Ok(());
+                }
+                if let CppTypeDeclarationKind::Class { properties, .. } = &mut declaration.kind {
+                    properties.push(CppProperty {
+                        property_type: instance.arguments[0].clone(),
+                        name: instance.arguments[1].clone(),
+                        getter: Some(instance.arguments[2].clone()),
+                        setter: Some(instance.arguments[3].clone()),
+                        notify: None,
+                    });
+                }
+                Ok(())
+            });
+        },
+    );
+    let properties = |name: &str| {
+        data.types
+            .iter()
+            .find(|t| t.path.last().name == name)
+            .unwrap()
+            .kind
+            .properties()
+            .to_vec()
+    };
+    assert_eq!(
+        properties("A"),
+        vec![
+            CppProperty {
+                name: "map".into(),
+                property_type: "QMap<int,int>".into(),
+                getter: Some("map".into()),
+                setter: Some("setMap".into()),
+                notify: Some("mapChanged".into()),
+            },
+            CppProperty {
+                name: "size".into(),
+                property_type: "int".into(),
+                getter: Some("size".into()),
+                setter: None,
+                notify: None,
+            },
+            CppProperty {
+                name: "count".into(),
+                property_type: "unsigned int".into(),
+                getter: Some("count".into()),
+                setter: Some("setCount".into()),
+                notify: None,
+            },
+        ]
+    );
+    assert_eq!(
+        properties("Nested")
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["nested"]
+    );
+    assert!(properties("B").is_empty());
+}
+
+#[test]
+fn signal_macro_sections() {
+    let code = "
+        #define signals public
+        #define slots
+        #define Q_SIGNALS public
+        #define Q_SLOTS
+        #define MY_SIGNALS public __attribute__((annotate(\"qt_signal\")))
+        class A {
+        public:
+            void f();
+        public slots:
+            void s1();
+        signals:
+            void sig1(int x);
+        protected Q_SLOTS:
+            void s2();
+        Q_SIGNALS:
+            void sig2();
+        MY_SIGNALS:
+            void sig3();
+        private:
+            void g();
+        };
+    ";
+    let flags = |data: &ParserCppData| {
+        data.methods
+            .iter()
+            .map(|f| (f.path.last().name.clone(), f.is_signal(), f.is_slot()))
+            .sorted()
+            .collect::<Vec<_>>()
+    };
+    let data = run_parser_with_config(code, |config| config.set_signal_macro_sections(true));
+    assert_eq!(
+        flags(&data),
+        vec![
+            ("f".to_string(), false, false),
+            ("g".to_string(), false, false),
+            ("s1".to_string(), false, true),
+            ("s2".to_string(), false, true),
+            ("sig1".to_string(), true, false),
+            ("sig2".to_string(), true, false),
+            ("sig3".to_string(), true, false),
+        ]
+    );
+    let s2 = data
+        .methods
+        .iter()
+        .find(|f| f.path.last().name == "s2")
+        .unwrap();
+    assert!(s2.is_protected());
+
+    let data = run_parser(code);
+    assert!(flags(&data)
+        .iter()
+        .all(|(_, is_signal, is_slot)| !is_signal && !is_slot));
+}
+
+#[test]
+fn class_members_in_other_headers() {
+    let dir = TempDir::new("test_cpp_parser_run").unwrap();
+    let mut workspace = Workspace::new(dir.path().into()).unwrap();
+    let include_dir = dir.path().join("include");
+    create_dir(&include_dir).unwrap();
+    let mut include_file = create_file(include_dir.join(FAKE_LIB_HEADER)).unwrap();
+    writeln!(include_file, "#include \"functions.h\"").unwrap();
+    // a part of the class declaration is in another header
+    writeln!(
+        include_file,
+        "class A {{ public: void f();\n#include \"a_members.h\"\n}};"
+    )
+    .unwrap();
+    drop(include_file);
+    let mut include_file = create_file(include_dir.join("a_members.h")).unwrap();
+    writeln!(include_file, "void g(); int x;").unwrap();
+    drop(include_file);
+    let mut include_file = create_file(include_dir.join("functions.h")).unwrap();
+    writeln!(include_file, "void h();").unwrap();
+    drop(include_file);
+
+    let mut config = fake_lib_config(dir.path());
+    config.add_target_include_path(include_dir.join("a_members.h"));
+    config.add_target_include_path(include_dir.join("functions.h"));
+    assert!(config.group_members_by_class_header());
+    processor::process(&mut workspace, &config, &["cpp_parser".to_string()], None).unwrap();
+
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let origin = |name: &str| {
+        let items = database.lookup_cpp_name(name).unwrap();
+        assert_eq!(items.len(), 1, "{}", name);
+        database.item_origin(items[0].id()).unwrap().clone()
+    };
+
+    let f = origin("A::f");
+    assert_eq!(f.include_file, FAKE_LIB_HEADER);
+    assert_eq!(f.class_include_file.as_deref(), Some(FAKE_LIB_HEADER));
+
+    for name in &["A::g", "A::x"] {
+        let member = origin(name);
+        assert_eq!(member.include_file, "a_members.h");
+        assert_eq!(member.class_include_file.as_deref(), Some(FAKE_LIB_HEADER));
+        assert_eq!(member.grouping_include_file(true), FAKE_LIB_HEADER);
+        assert_eq!(member.grouping_include_file(false), "a_members.h");
+    }
+
+    let h = origin("h");
+    assert_eq!(h.include_file, "functions.h");
+    assert_eq!(h.class_include_file, None);
+    assert_eq!(h.grouping_include_file(true), "functions.h");
+
+    let class = origin("A");
+    assert_eq!(class.include_file, FAKE_LIB_HEADER);
+    assert_eq!(class.class_include_file, None);
+}
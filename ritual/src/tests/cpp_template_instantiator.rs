@@ -0,0 +1,280 @@
+use crate::config::TemplateArgument;
+use crate::cpp_data::*;
+use crate::cpp_type::*;
+use crate::tests::try_run_steps;
+use itertools::Itertools;
+
+#[test]
+fn config_template_instantiations() {
+    let code = "template<typename T> class Box {
+    public:
+        T get() const;
+        void set(const T& value);
+    };
+    class Item {};
+    enum Kind { First, Second };";
+    let steps = &[
+        "cpp_parser",
+        "find_template_instantiations",
+        "instantiate_templates",
+    ];
+    let (_dir, mut workspace, result) = try_run_steps(code, steps, |config| {
+        config
+            .add_template_instantiation("Box", vec!["Item"])
+            .unwrap();
+        config
+            .add_template_instantiation("Box", vec!["const Kind*"])
+            .unwrap();
+        config
+            .add_template_instantiation(
+                "Box",
+                vec![CppType::BuiltInNumeric(CppBuiltInNumericType::Int)],
+            )
+            .unwrap();
+    });
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let instantiation = |argument: CppType| {
+        CppPath::from_item(CppPathItem {
+            name: "Box".into(),
+            template_arguments: Some(vec![argument]),
+        })
+    };
+    let item_type = CppType::Class(CppPath::from_good_str("Item"));
+    let kind_pointer = CppType::new_pointer(
+        true,
+        CppType::Enum {
+            path: CppPath::from_good_str("Kind"),
+        },
+    );
+    let int = CppType::BuiltInNumeric(CppBuiltInNumericType::Int);
+    for argument in &[item_type.clone(), kind_pointer, int.clone()] {
+        let path = instantiation(argument.clone());
+        assert!(database
+            .cpp_items()
+            .filter_map(|item| item.item.as_type_ref())
+            .any(|t| t.path == path));
+    }
+    let function = |class: &CppPath, name: &str| {
+        database
+            .cpp_items()
+            .filter_map(|item| item.item.as_function_ref())
+            .find(|f| f.class_path().ok().as_ref() == Some(class) && f.path.last().name == name)
+            .cloned()
+            .unwrap()
+    };
+    let get = function(&instantiation(item_type.clone()), "get");
+    assert_eq!(get.return_type, item_type);
+    let set = function(&instantiation(int.clone()), "set");
+    assert_eq!(
+        set.arguments[0].argument_type,
+        CppType::new_reference(true, int)
+    );
+
+    let check_error = |template: &'static str, argument: &'static str, message: &str| {
+        let (_dir, _workspace, result) = try_run_steps(code, steps, |config| {
+            config
+                .add_template_instantiation(template, vec![argument])
+                .unwrap();
+        });
+        let error = result.unwrap_err().iter_chain().join(": ");
+        assert!(error.contains(message), "unexpected error: {}", error);
+        assert!(error.contains(&format!("{}<{}>", template, argument)));
+    };
+    check_error("Missing", "int", "class template not found");
+    check_error("Box", "Unknown", "unknown type: Unknown");
+    check_error("Item", "int", "class template not found");
+}
+
+#[test]
+fn default_template_arguments() {
+    let code = "template<typename T> class Allocator {};
+    template<typename T, typename A = Allocator<T> >
+    class Vector {
+    public:
+        void push(const T& value);
+    };
+    class Item {};
+    Vector<int>* make_ints();
+    Vector<int, Allocator<int> >* make_ints_explicit();
+    Vector<Item>* make_items();";
+    let (_dir, mut workspace, result) = try_run_steps(
+        code,
+        &[
+            "cpp_parser",
+            "find_template_instantiations",
+            "instantiate_templates",
+        ],
+        |config| {
+            config
+                .add_template_instantiation("Vector", vec!["double"])
+                .unwrap();
+        },
+    );
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+
+    let template_param = |index, name: &str| {
+        CppType::TemplateParameter(CppTemplateParameter {
+            nested_level: 0,
+            index,
+            name: name.into(),
+        })
+    };
+    let class = |name: &str, args: Vec<CppType>| {
+        CppType::Class(CppPath::from_item(CppPathItem {
+            name: name.into(),
+            template_arguments: Some(args),
+        }))
+    };
+    let vector_template = database
+        .cpp_items()
+        .filter_map(|item| item.item.as_type_ref())
+        .find(|t| {
+            t.path.last().name == "Vector"
+                && t.path.last().template_arguments.as_ref().unwrap()[0].is_template_parameter()
+        })
+        .unwrap();
+    assert_eq!(
+        vector_template.kind.template_argument_defaults(),
+        &[None, Some(class("Allocator", vec![template_param(0, "T")]))]
+    );
+
+    let full_vector =
+        |arg: CppType| class("Vector", vec![arg.clone(), class("Allocator", vec![arg])]);
+    let int = CppType::BuiltInNumeric(CppBuiltInNumericType::Int);
+    let item = CppType::Class(CppPath::from_good_str("Item"));
+    let double = CppType::BuiltInNumeric(CppBuiltInNumericType::Double);
+    let function = |name: &str| {
+        database
+            .cpp_items()
+            .filter_map(|item| item.item.as_function_ref())
+            .find(|f| f.path == CppPath::from_good_str(name))
+            .cloned()
+            .unwrap()
+    };
+    let int_vector = CppType::new_pointer(false, full_vector(int.clone()));
+    assert_eq!(function("make_ints").return_type, int_vector);
+    assert_eq!(function("make_ints_explicit").return_type, int_vector);
+    assert_eq!(
+        function("make_items").return_type,
+        CppType::new_pointer(false, full_vector(item.clone()))
+    );
+
+    for arg in &[int, item, double] {
+        let vector = full_vector(arg.clone());
+        let path = match &vector {
+            CppType::Class(path) => path.clone(),
+            _ => unreachable!(),
+        };
+        let types = database
+            .cpp_items()
+            .filter_map(|item| item.item.as_type_ref())
+            .filter(|t| t.path == path)
+            .count();
+        assert_eq!(types, 1);
+        let push = database
+            .cpp_items()
+            .filter_map(|item| item.item.as_function_ref())
+            .find(|f| f.class_path().ok().as_ref() == Some(&path) && f.path.last().name == "push")
+            .unwrap();
+        assert_eq!(
+            push.arguments[0].argument_type,
+            CppType::new_reference(true, arg.clone())
+        );
+    }
+}
+
+#[test]
+fn configured_method_instantiations() {
+    let code = "template<typename T> class Box {};
+    class Item {};
+    class Variant {
+    public:
+        template<typename T> T value() const;
+        template<typename T> void set(const T* ptr);
+        int plain() const;
+    };";
+    let steps = &[
+        "cpp_parser",
+        "find_template_instantiations",
+        "instantiate_templates",
+    ];
+    let int = CppType::BuiltInNumeric(CppBuiltInNumericType::Int);
+    let item = CppType::Class(CppPath::from_good_str("Item"));
+    let int_box = CppType::Class(CppPath::from_item(CppPathItem {
+        name: "Box".into(),
+        template_arguments: Some(vec![int.clone()]),
+    }));
+    let int_box2 = int_box.clone();
+    let (_dir, mut workspace, result) = try_run_steps(code, steps, move |config| {
+        config
+            .add_template_instantiation("Box", vec!["int"])
+            .unwrap();
+        config
+            .instantiate_method(
+                "Variant::value",
+                vec![
+                    TemplateArgument::from("int"),
+                    "Item".into(),
+                    int_box2.into(),
+                ],
+            )
+            .unwrap();
+        config
+            .instantiate_method("Variant::set", vec!["Item"])
+            .unwrap();
+    });
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let methods = database
+        .cpp_items()
+        .filter_map(|item| item.item.as_function_ref())
+        .filter(|f| {
+            f.path
+                .last()
+                .template_arguments
+                .as_ref()
+                .map_or(true, |args| {
+                    !args.iter().any(CppType::is_or_contains_template_parameter)
+                })
+        })
+        .collect::<Vec<_>>();
+
+    for argument in &[int.clone(), item.clone(), int_box] {
+        let path = CppPath::from_good_str("Variant").join(CppPathItem {
+            name: "value".into(),
+            template_arguments: Some(vec![argument.clone()]),
+        });
+        let value = methods.iter().find(|f| f.path == path).unwrap();
+        assert_eq!(&value.return_type, argument);
+        assert!(value.member.as_ref().unwrap().is_const);
+    }
+
+    let set = methods
+        .iter()
+        .find(|f| f.path.last().name == "set")
+        .unwrap();
+    assert_eq!(
+        set.arguments[0].argument_type,
+        CppType::new_pointer(true, item)
+    );
+
+    let check_error = |path: &'static str, message: &str| {
+        let (_dir, _workspace, result) = try_run_steps(code, steps, move |config| {
+            config.instantiate_method(path, vec!["int"]).unwrap();
+        });
+        let error = result.unwrap_err().iter_chain().join(": ");
+        assert!(error.contains(message), "unexpected error: {}", error);
+        assert!(error.contains(&format!("{} with int", path)));
+    };
+    check_error("Variant::plain", "method is not a template");
+    check_error("Variant::missing", "method not found");
+    check_error("Item::value", "method not found");
+}
@@ -8,7 +8,7 @@ use crate::cpp_type::{
 
 fn assert_type_to_ffi_unchanged(t: &CppType) {
     for role in &[CppTypeRole::NotReturnType, CppTypeRole::ReturnType] {
-        let ffi1 = ffi_type(t, *role).unwrap();
+        let ffi1 = ffi_type(t, *role, &[]).unwrap();
         assert_eq!(ffi1.original_type(), t);
         assert_eq!(ffi1.ffi_type(), t);
         assert_eq!(ffi1.conversion(), &CppToFfiTypeConversion::NoChange);
@@ -124,7 +124,7 @@ fn class_value() {
     assert_eq!(type1.to_cpp_code(None).unwrap(), "QPoint");
     assert!(type1.to_cpp_code(Some(&String::new())).is_err());
 
-    let ffi_return_type = ffi_type(&type1, CppTypeRole::ReturnType).unwrap();
+    let ffi_return_type = ffi_type(&type1, CppTypeRole::ReturnType, &[]).unwrap();
     assert_eq!(ffi_return_type.original_type(), &type1);
     assert_eq!(
         ffi_return_type.ffi_type(),
@@ -141,7 +141,7 @@ fn class_value() {
         }
     );
 
-    let ffi_arg = ffi_type(&type1, CppTypeRole::NotReturnType).unwrap();
+    let ffi_arg = ffi_type(&type1, CppTypeRole::NotReturnType, &[]).unwrap();
     assert_eq!(ffi_arg.original_type(), &type1);
     assert_eq!(
         ffi_arg.ffi_type(),
@@ -167,7 +167,7 @@ fn class_const_ref() {
     assert!(type1.to_cpp_code(Some(&String::new())).is_err());
 
     for role in &[CppTypeRole::NotReturnType, CppTypeRole::ReturnType] {
-        let ffi1 = ffi_type(&type1, *role).unwrap();
+        let ffi1 = ffi_type(&type1, *role, &[]).unwrap();
         assert_eq!(ffi1.original_type(), &type1);
         assert_eq!(
             ffi1.ffi_type(),
@@ -191,7 +191,7 @@ fn class_mut_ref() {
     assert!(type1.to_cpp_code(Some(&String::new())).is_err());
 
     for role in &[CppTypeRole::NotReturnType, CppTypeRole::ReturnType] {
-        let ffi1 = ffi_type(&type1, *role).unwrap();
+        let ffi1 = ffi_type(&type1, *role, &[]).unwrap();
         assert_eq!(ffi1.original_type(), &type1);
         assert_eq!(
             ffi1.ffi_type(),
@@ -229,7 +229,7 @@ fn class_with_template_args() {
     assert_eq!(type1.to_cpp_code(None).unwrap(), "QVector< QString >");
     assert!(type1.to_cpp_code(Some(&String::new())).is_err());
 
-    let ffi_return_type = ffi_type(&type1, CppTypeRole::ReturnType).unwrap();
+    let ffi_return_type = ffi_type(&type1, CppTypeRole::ReturnType, &[]).unwrap();
     assert_eq!(ffi_return_type.original_type(), &type1);
     assert_eq!(
         ffi_return_type.ffi_type(),
@@ -252,7 +252,7 @@ fn class_with_template_args() {
         }
     );
 
-    let ffi_arg = ffi_type(&type1, CppTypeRole::NotReturnType).unwrap();
+    let ffi_arg = ffi_type(&type1, CppTypeRole::NotReturnType, &[]).unwrap();
     assert_eq!(ffi_arg.original_type(), &type1);
     assert_eq!(
         ffi_arg.ffi_type(),
@@ -312,8 +312,9 @@ fn qflags() {
     );
     assert!(type1.to_cpp_code(Some(&String::new())).is_err());
 
+    let flags_templates = [CppPath::from_good_str("QFlags")];
     for role in &[CppTypeRole::NotReturnType, CppTypeRole::ReturnType] {
-        let ffi_type = ffi_type(&type1, *role).unwrap();
+        let ffi_type = ffi_type(&type1, *role, &flags_templates).unwrap();
         assert_eq!(ffi_type.original_type(), &type1);
         assert_eq!(
             ffi_type.ffi_type(),
@@ -322,6 +323,13 @@ fn qflags() {
         assert_eq!(ffi_type.ffi_type().to_cpp_code(None).unwrap(), "int");
         assert_eq!(ffi_type.conversion(), &CppToFfiTypeConversion::QFlagsToInt);
     }
+
+    // only templates added to the config are flags types
+    let ffi_type = ffi_type(&type1, CppTypeRole::NotReturnType, &[]).unwrap();
+    assert_eq!(
+        ffi_type.conversion(),
+        &CppToFfiTypeConversion::ValueToPointer { is_ffi_const: true }
+    );
 }
 
 #[test]
@@ -339,8 +347,8 @@ fn template_parameter() {
     assert_eq!(type1.is_template_parameter(), false);
     assert!(type1.to_cpp_code(None).is_err());
     assert!(type1.to_cpp_code(Some(&String::new())).is_err());
-    assert!(ffi_type(&type1, CppTypeRole::NotReturnType).is_err());
-    assert!(ffi_type(&type1, CppTypeRole::ReturnType).is_err());
+    assert!(ffi_type(&type1, CppTypeRole::NotReturnType, &[]).is_err());
+    assert!(ffi_type(&type1, CppTypeRole::ReturnType, &[]).is_err());
 }
 
 #[test]
@@ -356,10 +364,76 @@ fn function1() {
     assert_eq!(type1.is_void(), false);
     assert_eq!(type1.is_class(), false);
     assert_eq!(type1.is_template_parameter(), false);
-    assert!(type1.to_cpp_code(None).is_err());
+    assert_eq!(type1.to_cpp_code(None).unwrap(), "int (*)(int, bool *)");
+    assert_eq!(
+        type1.to_cpp_code(Some("func")).unwrap(),
+        "int (*func)(int, bool *)"
+    );
     assert_type_to_ffi_unchanged(&type1);
 }
 
+#[test]
+fn function_with_class_reference() {
+    let type1 = CppType::FunctionPointer(CppFunctionPointerType {
+        allows_variadic_arguments: false,
+        return_type: Box::new(CppType::BuiltInNumeric(CppBuiltInNumericType::Int)),
+        arguments: vec![
+            CppType::new_reference(true, CppType::Class(CppPath::from_good_str("QString"))),
+            CppType::new_pointer(false, CppType::Void),
+        ],
+    });
+    assert_eq!(
+        type1.to_cpp_code(None).unwrap(),
+        "int (*)(QString const &, void *)"
+    );
+    assert_eq!(
+        type1.to_cpp_declaration("callback").unwrap(),
+        "int (*callback)(QString const &, void *)"
+    );
+
+    let pointer = CppType::new_pointer(true, type1.clone());
+    assert_eq!(
+        pointer.to_cpp_code(None).unwrap(),
+        "int (* const *)(QString const &, void *)"
+    );
+    assert_eq!(
+        pointer.to_cpp_declaration("callbacks").unwrap(),
+        "int (* const *callbacks)(QString const &, void *)"
+    );
+}
+
+#[test]
+fn function_returning_function() {
+    let inner = CppType::FunctionPointer(CppFunctionPointerType {
+        allows_variadic_arguments: false,
+        return_type: Box::new(CppType::BuiltInNumeric(CppBuiltInNumericType::Int)),
+        arguments: vec![CppType::BuiltInNumeric(CppBuiltInNumericType::Char)],
+    });
+    let type1 = CppType::FunctionPointer(CppFunctionPointerType {
+        allows_variadic_arguments: false,
+        return_type: Box::new(inner),
+        arguments: vec![CppType::BuiltInNumeric(CppBuiltInNumericType::Double)],
+    });
+    assert_eq!(type1.to_cpp_code(None).unwrap(), "int (*(*)(double))(char)");
+    assert_eq!(
+        type1.to_cpp_declaration("factory").unwrap(),
+        "int (*(*factory)(double))(char)"
+    );
+}
+
+#[test]
+fn array_declaration() {
+    let type1 = CppType::Array {
+        element_type: Box::new(CppType::BuiltInNumeric(CppBuiltInNumericType::Float)),
+        is_const: true,
+        size: Some(16),
+    };
+    assert_eq!(
+        type1.to_cpp_declaration("values").unwrap(),
+        "const float values[16]"
+    );
+}
+
 #[test]
 fn instantiate1() {
     let type1 = CppType::new_reference(
@@ -380,3 +454,70 @@ fn instantiate1() {
         )
     );
 }
+
+#[test]
+fn array() {
+    let float = CppType::BuiltInNumeric(CppBuiltInNumericType::Float);
+    let type1 = CppType::Array {
+        element_type: Box::new(float.clone()),
+        is_const: true,
+        size: Some(16),
+    };
+    assert_eq!(type1.is_array(), true);
+    assert_eq!(type1.is_class(), false);
+    assert_eq!(type1.to_cpp_code(None).unwrap(), "const float[16]");
+    assert_eq!(type1.ascii_caption(), "float_const_array16");
+
+    let ffi = ffi_type(&type1, CppTypeRole::NotReturnType, &[]).unwrap();
+    assert_eq!(ffi.original_type(), &type1);
+    assert_eq!(ffi.ffi_type(), &CppType::new_pointer(true, float.clone()));
+    assert!(ffi_type(&type1, CppTypeRole::ReturnType, &[]).is_err());
+
+    let type2 = CppType::Array {
+        element_type: Box::new(CppType::BuiltInNumeric(CppBuiltInNumericType::Char)),
+        is_const: false,
+        size: Some(32),
+    };
+    let reference = CppType::new_reference(true, type2.clone());
+    assert_eq!(
+        reference.to_cpp_code(None).unwrap(),
+        "std::add_lvalue_reference<const char[32]>::type"
+    );
+    let ffi = ffi_type(&reference, CppTypeRole::ReturnType, &[]).unwrap();
+    assert_eq!(ffi.ffi_type(), &CppType::new_pointer(true, type2.clone()));
+    assert_eq!(
+        ffi.ffi_type().to_cpp_code(None).unwrap(),
+        "std::add_pointer<const char[32]>::type"
+    );
+}
+
+#[test]
+fn const_per_pointer_level() {
+    let char1 = CppType::BuiltInNumeric(CppBuiltInNumericType::Char);
+    // const char**
+    let type1 = CppType::new_pointer(false, CppType::new_pointer(true, char1.clone()));
+    // char* const*
+    let type2 = CppType::new_pointer(true, CppType::new_pointer(false, char1.clone()));
+    // const char* const*
+    let type3 = CppType::new_pointer(true, CppType::new_pointer(true, char1.clone()));
+    assert_ne!(type1, type2);
+    assert_ne!(type2, type3);
+    assert_eq!(type1.to_cpp_code(None).unwrap(), "char const * *");
+    assert_eq!(type2.to_cpp_code(None).unwrap(), "char * const *");
+    assert_eq!(type3.to_cpp_code(None).unwrap(), "char const * const *");
+    assert_eq!(type1.to_cpp_pseudo_code(), "const char**");
+    assert_eq!(type2.to_cpp_pseudo_code(), "char* const*");
+    assert_eq!(type3.to_cpp_pseudo_code(), "const char* const*");
+    assert_eq!(type1.ascii_caption(), "char_const_ptr_ptr");
+    assert_eq!(type2.ascii_caption(), "char_ptr_const_ptr");
+
+    // const char* const argv[]
+    let argv = CppType::Array {
+        element_type: Box::new(CppType::new_pointer(true, char1.clone())),
+        is_const: true,
+        size: None,
+    };
+    assert_eq!(argv.to_cpp_code(None).unwrap(), "char const * const[]");
+    let ffi = ffi_type(&argv, CppTypeRole::NotReturnType, &[]).unwrap();
+    assert_eq!(ffi.ffi_type(), &type3);
+}
@@ -0,0 +1,333 @@
+use crate::config::{
+    Config, CrateDependencyKind, CrateDependencySource, CrateProperties, TypeAllocationPlace,
+};
+use crate::cpp_code_generator;
+use crate::cpp_data::*;
+use crate::crate_writer::{apply_crate_overlay, sync_crate_files, SyncStats};
+use crate::processor;
+use crate::rust_code_generator;
+use crate::rust_info::RustFeatures;
+use crate::rust_type::RustPath;
+use crate::smoke_tests::{self, ClassTest, EnumTest, SmokeTests};
+use crate::tests::{
+    fake_lib_config, run_rust_generator, run_rust_generator_with_steps, run_steps, FAKE_LIB_HEADER,
+    RUST_GENERATOR_STEPS,
+};
+use crate::type_allocation_places::layout_conditions;
+use crate::workspace::Workspace;
+use itertools::Itertools;
+use ritual_common::cpp_build_config::CppBuildPaths;
+use ritual_common::file_utils::{create_dir, create_dir_all, file_to_string};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempdir::TempDir;
+
+fn write_file(path: &Path, content: &str) {
+    create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+#[test]
+fn sync_files() {
+    let staging_dir = TempDir::new("test_sync_files").unwrap();
+    let staging = staging_dir.path();
+    let output_dir = TempDir::new("test_sync_files").unwrap();
+    let output = output_dir.path();
+    write_file(&staging.join("src/lib.rs"), "lib");
+    write_file(&staging.join("src/ops.rs"), "new ops");
+    write_file(&staging.join("src/extra/mod.rs"), "extra");
+    write_file(&output.join("src/lib.rs"), "lib");
+    write_file(&output.join("src/ops.rs"), "old ops");
+    write_file(&output.join("src/extra/mod.rs"), "extra");
+    write_file(&output.join("src/old/mod.rs"), "");
+    write_file(&output.join("README.md"), "readme");
+    write_file(&output.join("user.txt"), "");
+    let lib_modified = fs::metadata(output.join("src/lib.rs"))
+        .unwrap()
+        .modified()
+        .unwrap();
+
+    let previous_files = vec![
+        PathBuf::from("src/lib.rs"),
+        PathBuf::from("src/ops.rs"),
+        PathBuf::from("src/old/mod.rs"),
+        PathBuf::from("README.md"),
+    ];
+    let mut stats = SyncStats::default();
+    let files = sync_crate_files(staging, output, &previous_files, &mut stats).unwrap();
+    assert_eq!(
+        files,
+        vec![PathBuf::from("src/lib.rs"), PathBuf::from("src/ops.rs")]
+    );
+    assert_eq!(
+        stats,
+        SyncStats {
+            written: 1,
+            unchanged: 1,
+            deleted: 1,
+        }
+    );
+    assert_eq!(
+        file_to_string(output.join("src/ops.rs")).unwrap(),
+        "new ops"
+    );
+    assert_eq!(
+        fs::metadata(output.join("src/lib.rs"))
+            .unwrap()
+            .modified()
+            .unwrap(),
+        lib_modified
+    );
+    assert!(!output.join("src/old").exists());
+    assert!(output.join("README.md").exists());
+    assert!(output.join("user.txt").exists());
+}
+
+#[test]
+fn crate_overlay() {
+    let overlay_dir = TempDir::new("test_crate_overlay").unwrap();
+    let overlay = overlay_dir.path();
+    let output_dir = TempDir::new("test_crate_overlay").unwrap();
+    let output = output_dir.path();
+    write_file(&overlay.join("README.md"), "new readme");
+    write_file(&overlay.join("src/extra/mod.rs"), "// extra");
+    write_file(&overlay.join("examples/a.rs"), "fn main() {}");
+    write_file(&output.join("README.md"), "old readme");
+    write_file(&output.join("src/lib.rs"), "");
+
+    apply_crate_overlay(overlay, output).unwrap();
+    assert_eq!(
+        file_to_string(output.join("README.md")).unwrap(),
+        "new readme"
+    );
+    assert_eq!(
+        file_to_string(output.join("src/extra/mod.rs")).unwrap(),
+        "// extra"
+    );
+    assert!(output.join("examples/a.rs").exists());
+
+    write_file(&overlay.join("src/lib.rs"), "// lib");
+    write_file(&overlay.join("src/ops.rs"), "// ops");
+    write_file(&output.join("src/ops.rs"), "");
+    let err = apply_crate_overlay(overlay, output)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("src/lib.rs"));
+    assert!(err.contains("src/ops.rs"));
+    assert_eq!(file_to_string(output.join("src/lib.rs")).unwrap(), "");
+}
+
+#[test]
+fn smoke_tests_code() {
+    let (_dir, config, database) = run_rust_generator(
+        "class Widget {
+        public:
+            Widget();
+            int width() const;
+            void setWidth(int width);
+            int area(int scale) const;
+            static int count();
+        };
+        class Hidden { public: Hidden(); int x() const; };
+        class NoDefault { public: NoDefault(int y); int y() const; };
+        enum Color { Red, Green = 5 };",
+        |config| {
+            config.add_smoke_test_blocked_name_pattern("Hid.*").unwrap();
+        },
+    );
+    let tests = smoke_tests::collect_tests(&database, &config).unwrap();
+    assert_eq!(
+        tests,
+        SmokeTests {
+            classes: vec![ClassTest {
+                struct_path: RustPath::from_good_str("A::Widget"),
+                constructor: RustPath::from_good_str("A::Widget::new"),
+                getters: vec![RustPath::from_good_str("A::Widget::width")],
+            }],
+            enums: vec![EnumTest {
+                enum_path: RustPath::from_good_str("A::Color"),
+                values: vec![
+                    RustPath::from_good_str("A::Color::Red"),
+                    RustPath::from_good_str("A::Color::Green"),
+                ],
+            }],
+        }
+    );
+}
+
+#[test]
+fn crate_features() {
+    let (dir, config, database) = run_rust_generator(
+        "class Widget { public: Widget(); int width() const; };
+        enum Color { Red, Green };",
+        |_| {},
+    );
+    let features = RustFeatures::new(&database, &config).unwrap();
+    assert!(features.dependencies().is_empty());
+
+    let mut config = fake_lib_config(dir.path());
+    assert!(config.add_feature("default", &["other.h"]).is_err());
+    assert!(config.add_feature("ritual_rustdoc", &["other.h"]).is_err());
+    config.add_feature("widgets", &[FAKE_LIB_HEADER]).unwrap();
+    assert!(config.add_feature("widgets", &["other.h"]).is_err());
+    assert!(config
+        .add_feature("more_widgets", &[FAKE_LIB_HEADER])
+        .is_err());
+    assert_eq!(config.header_feature(FAKE_LIB_HEADER), Some("widgets"));
+    assert_eq!(config.header_feature("other.h"), None);
+
+    let features = RustFeatures::new(&database, &config).unwrap();
+    assert_eq!(features.dependencies().len(), 1);
+    assert!(features.dependencies()["widgets"].is_empty());
+
+    let expected = Some(vec!["widgets".to_string()].into_iter().collect());
+    for path in &[
+        "A::Widget",
+        "A::Widget::new",
+        "A::Widget::width",
+        "A::Color",
+    ] {
+        let item = database
+            .find_rust_item(&RustPath::from_good_str(path))
+            .unwrap();
+        assert_eq!(features.item_features(&item.id), expected.as_ref());
+    }
+}
+
+#[test]
+fn layout_assertions() {
+    let (dir, mut config, database) = run_rust_generator_with_steps(
+        "struct Point { int x; int y; };",
+        &[
+            "cpp_parser",
+            "type_allocation_places",
+            "cpp_ffi_generator",
+            "fake_cpp_checker",
+            "rust_generator",
+        ],
+        |config| {
+            config.set_type_allocation_place(
+                TypeAllocationPlace::Stack,
+                CppPath::from_good_str("Point"),
+            );
+        },
+    );
+    let point = database
+        .find_rust_item(&RustPath::from_good_str("A::Point"))
+        .unwrap();
+    let layout = database.source_class_layout(&point.id).unwrap().unwrap();
+    assert_eq!(layout.size, 8);
+    assert_eq!(layout.align, Some(4));
+    assert_eq!(
+        layout_conditions(&CppPath::from_good_str("Point"), layout).unwrap(),
+        vec!["sizeof(Point) == 8", "alignof(Point) == 4"]
+    );
+
+    let output_path = dir.path().join("generated");
+    let cpp_path = output_path.join("c_lib");
+    let generate = |config: &Config| {
+        let _ = fs::remove_dir_all(&output_path);
+        create_dir_all(&cpp_path).unwrap();
+        cpp_code_generator::generate_cpp_files(&database, &cpp_path, "A_global.h", config).unwrap();
+        rust_code_generator::generate(&database, config, &RustFeatures::default(), &output_path)
+            .unwrap();
+    };
+    generate(&config);
+    assert!(cpp_path.join("layout_assertions.cpp").exists());
+
+    config.set_write_layout_assertions(false);
+    generate(&config);
+    assert!(!cpp_path.join("layout_assertions.cpp").exists());
+}
+
+#[test]
+fn extra_module() {
+    let (dir, mut workspace) = run_steps("class A { public: int x(); };", RUST_GENERATOR_STEPS);
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let mut config = fake_lib_config(dir.path());
+    let output_path = dir.path().join("generated");
+    let generate_lib = |config: &Config| {
+        rust_code_generator::generate(&database, config, &RustFeatures::default(), &output_path)
+            .unwrap();
+        fs::read_to_string(output_path.join("lib.rs")).unwrap()
+    };
+    assert!(!generate_lib(&config).contains("pub mod extra;"));
+
+    let overlay_path = dir.path().join("overlay");
+    create_dir_all(overlay_path.join("src/extra")).unwrap();
+    fs::write(overlay_path.join("src/extra/mod.rs"), "").unwrap();
+    config.set_crate_overlay_path(&overlay_path);
+    assert!(generate_lib(&config).contains("pub mod extra;"));
+}
+
+#[test]
+fn multiple_crates() {
+    let dir = TempDir::new("test_cpp_parser_run").unwrap();
+    let mut workspace = Workspace::new(dir.path().into()).unwrap();
+    let include_dir = dir.path().join("include");
+    create_dir(&include_dir).unwrap();
+    fs::write(
+        include_dir.join("core.h"),
+        "class Core { public: void f(); };",
+    )
+    .unwrap();
+    fs::write(
+        include_dir.join("gui.h"),
+        "#include \"core.h\"\nclass Gui { public: Core* core(); };",
+    )
+    .unwrap();
+
+    let crate_config = |name: &str, header: &str| {
+        let mut paths = CppBuildPaths::new();
+        paths.add_include_path(&include_dir);
+        let mut config = Config::new(CrateProperties::new(name, "0.0.0"));
+        config.add_include_directive(header);
+        config.set_cpp_build_paths(paths);
+        config.add_target_include_path(include_dir.join(header));
+        config
+    };
+    let mut gui = crate_config("gui", "gui.h");
+    gui.crate_properties_mut()
+        .add_dependency(
+            "core",
+            CrateDependencyKind::Ritual,
+            CrateDependencySource::CurrentWorkspace,
+        )
+        .unwrap();
+    let configs = vec![gui, crate_config("core", "core.h")];
+    assert_eq!(processor::processing_order(&configs).unwrap(), vec![1, 0]);
+
+    let steps = ["cpp_parser".to_string(), "cpp_ffi_generator".to_string()];
+    processor::process_all(&mut workspace, &configs, &steps, None).unwrap();
+    let database = workspace
+        .get_database_client(
+            "gui",
+            configs[0].crate_properties().dependencies(),
+            true,
+            false,
+        )
+        .unwrap();
+    assert!(database.is_dependency_type(&CppPath::from_good_str("Core")));
+
+    let mut cycle = vec![crate_config("a", "core.h"), crate_config("b", "core.h")];
+    for (index, dependency) in [(0, "b"), (1, "a")].iter() {
+        cycle[*index]
+            .crate_properties_mut()
+            .add_dependency(
+                *dependency,
+                CrateDependencyKind::Ritual,
+                CrateDependencySource::CurrentWorkspace,
+            )
+            .unwrap();
+    }
+    assert!(processor::processing_order(&cycle).is_err());
+
+    let configs = vec![
+        crate_config("core", "core.h"),
+        crate_config("broken", "missing.h"),
+    ];
+    let err = processor::process_all(&mut workspace, &configs, &steps, None).unwrap_err();
+    assert_eq!(err.to_string(), "failed to process crate broken");
+}
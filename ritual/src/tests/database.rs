@@ -0,0 +1,186 @@
+use crate::config::{Config, CrateDependencyKind, CrateProperties};
+use crate::cpp_data::*;
+use crate::database::{Database, DatabaseFormat, DatabaseItemKind, BINARY_DATABASE_VERSION};
+use crate::processor;
+use crate::tests::{fake_lib_config, run_steps};
+use crate::workspace::Workspace;
+use itertools::Itertools;
+use ritual_common::cpp_build_config::CppBuildPaths;
+use ritual_common::file_utils::{create_dir, create_file};
+use std::fs;
+use std::io::Write;
+use tempdir::TempDir;
+
+#[test]
+fn database_queries() {
+    let (_dir, mut workspace) = run_steps(
+        "
+        namespace ns {
+            class Device {
+            public:
+                int read(char* data, int size);
+                int read(int size);
+            };
+        }
+        ",
+        &["cpp_parser", "cpp_ffi_generator"],
+    );
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+
+    assert_eq!(
+        database.lookup_cpp_name("ns::Device::read").unwrap().len(),
+        2
+    );
+    let views = database
+        .lookup_cpp_name("ns::Device::read(char *, int)")
+        .unwrap();
+    assert_eq!(views.len(), 1);
+    assert_eq!(views[0].kind(), DatabaseItemKind::CppItem);
+    assert_eq!(views[0].sources().unwrap().len(), 0);
+    let ffi_items = views[0].ffi_items();
+    assert_eq!(ffi_items.len(), 1);
+
+    let ffi_view = database.view(&ffi_items[0].id).unwrap();
+    let cpp_item = ffi_view.original_cpp_item().unwrap().unwrap();
+    assert_eq!(&cpp_item.id, views[0].id());
+    assert!(database
+        .lookup_cpp_name("ns::Device::read(int, int)")
+        .unwrap()
+        .is_empty());
+    assert!(database
+        .lookup_cpp_name("ns::Device::write")
+        .unwrap()
+        .is_empty());
+
+    assert_eq!(database.search("DEVICE::READ").count(), 2);
+    assert_eq!(
+        database.items_of_kind(DatabaseItemKind::FfiItem).count(),
+        database.ffi_items().count()
+    );
+    assert!(database.lookup_rust_path("a::Device").unwrap().is_none());
+}
+
+#[test]
+fn database_formats() {
+    let (dir, mut workspace) = run_steps(
+        "
+        namespace ns {
+            enum E { E1 = 1, E2 = -3 };
+            template<class T> class V { public: T get() const; };
+            class A {
+            public:
+                A(int x);
+                V<int> v(E e, const char* s = 0);
+                int field;
+            };
+        }
+        ",
+        &["cpp_parser"],
+    );
+    let json_path = workspace.database_path("A");
+    let database = Database::load(&json_path).unwrap();
+    assert!(database.cpp_items().count() > 5);
+    let expected = serde_json::to_string(&database).unwrap();
+
+    let bin_path = dir.path().join("A.bin");
+    database.save(&bin_path, None).unwrap();
+    assert!(bin_path.metadata().unwrap().len() < json_path.metadata().unwrap().len());
+    let loaded = Database::load(&bin_path).unwrap();
+    assert_eq!(serde_json::to_string(&loaded).unwrap(), expected);
+
+    let mut data = fs::read(&bin_path).unwrap();
+    data[8..12].copy_from_slice(&(BINARY_DATABASE_VERSION + 1).to_le_bytes());
+    fs::write(&bin_path, data).unwrap();
+    let err = Database::load(&bin_path).unwrap_err();
+    assert!(err.to_string().contains("unsupported version"));
+
+    workspace.set_database_format(DatabaseFormat::Binary);
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    assert_eq!(serde_json::to_string(database.data()).unwrap(), expected);
+    assert!(workspace.database_path("A").ends_with("A.bin"));
+    assert!(workspace.database_path("A").exists());
+    assert!(!json_path.exists());
+}
+
+#[test]
+fn database_target_triple() {
+    let (dir, mut workspace) = run_steps("class A {};", &["cpp_parser"]);
+    let process = |workspace: &mut Workspace, triple: Option<&str>| {
+        let mut config = fake_lib_config(dir.path());
+        if let Some(triple) = triple {
+            config.set_target_triple(triple);
+        }
+        processor::process(workspace, &config, &["cpp_ffi_generator".to_string()], None)
+    };
+
+    // the database was created for the host target
+    let err = process(&mut workspace, Some("aarch64-unknown-linux-gnu"))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains(
+        "database contains C++ data parsed for target \"host\", \
+         but current target is \"aarch64-unknown-linux-gnu\""
+    ));
+    process(&mut workspace, None).unwrap();
+}
+
+#[test]
+fn ritual_dependencies() {
+    let core_code = "class Core { public: void f(); };";
+    let (_dir1, workspace1) = run_steps(core_code, &["cpp_parser", "cpp_ffi_generator"]);
+
+    let dir = TempDir::new("test_cpp_parser_run").unwrap();
+    let mut workspace = Workspace::new(dir.path().into()).unwrap();
+    let include_dir = dir.path().join("include");
+    create_dir(&include_dir).unwrap();
+    let mut include_file = create_file(include_dir.join("gui.h")).unwrap();
+    // the header of the dependency is included
+    writeln!(include_file, "{}", core_code).unwrap();
+    writeln!(include_file, "class Gui {{ public: Core* core(); }};").unwrap();
+    drop(include_file);
+
+    let mut paths = CppBuildPaths::new();
+    paths.add_include_path(&include_dir);
+    let mut config = Config::new(CrateProperties::new("B", "0.0.0"));
+    config.add_include_directive("gui.h");
+    config.set_cpp_build_paths(paths);
+    config.add_target_include_path(include_dir.join("gui.h"));
+    config
+        .add_dependency(workspace1.database_path("A"))
+        .unwrap();
+    assert!(config.add_dependency(dir.path().join("missing")).is_err());
+    let dependencies = config.crate_properties().dependencies();
+    assert_eq!(dependencies.len(), 1);
+    assert_eq!(dependencies[0].name(), "A");
+    assert_eq!(dependencies[0].kind(), CrateDependencyKind::Ritual);
+
+    let steps = ["cpp_parser".to_string(), "cpp_ffi_generator".to_string()];
+    processor::process(&mut workspace, &config, &steps, None).unwrap();
+
+    let database = workspace
+        .get_database_client("B", config.crate_properties().dependencies(), true, false)
+        .unwrap();
+    let cpp_items = database
+        .cpp_items()
+        .map(|item| item.item.short_text())
+        .collect::<Vec<_>>();
+    assert!(cpp_items.iter().any(|text| text.contains("Gui::core")));
+    assert!(!cpp_items.iter().any(|text| text.contains("Core::f")));
+    assert!(!database.is_dependency_type(&CppPath::from_good_str("Gui")));
+    assert!(database.is_dependency_type(&CppPath::from_good_str("Core")));
+
+    // the FFI wrapper of `Core::f` is only present in the dependency
+    let ffi_sources = database
+        .ffi_items()
+        .map(|item| {
+            let source_id = item.source_id.unwrap();
+            database.cpp_item(&source_id).unwrap().item.short_text()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(ffi_sources.len(), 1);
+    assert!(ffi_sources[0].contains("Gui::core"));
+}
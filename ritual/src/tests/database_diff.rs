@@ -0,0 +1,131 @@
+use crate::config::Config;
+use crate::cpp_checks::CppChecksItem;
+use crate::database_diff::{self, DiffChangeKind, DiffItemKind, VersionBump};
+use crate::tests::{run_steps, try_run_steps, RUST_GENERATOR_STEPS};
+use ritual_common::target::{current_target, LibraryTarget};
+
+#[test]
+fn database_diff() {
+    let (_dir1, mut workspace1) = run_steps(
+        "
+        enum E { A = 1, B = 2 };
+        int f1(int x);
+        int f2(int x);
+        ",
+        &["cpp_parser"],
+    );
+    let (_dir2, mut workspace2) = run_steps(
+        "
+
+        enum E { A = 1, B = 3 };
+        int f1(int y);
+        int f3(int x);
+        ",
+        &["cpp_parser"],
+    );
+    let old = workspace1
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let new = workspace2
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+
+    let diff = database_diff::diff(old.data(), new.data());
+    let changes = diff
+        .entries
+        .iter()
+        .map(|entry| (entry.item_kind, entry.change, entry.key.as_str()))
+        .collect::<Vec<_>>();
+    // moved declarations and renamed arguments are not reported
+    assert_eq!(
+        changes,
+        vec![
+            (
+                DiffItemKind::CppFunction,
+                DiffChangeKind::Removed,
+                "f2(int)"
+            ),
+            (DiffItemKind::CppFunction, DiffChangeKind::Added, "f3(int)"),
+            (DiffItemKind::CppEnumValue, DiffChangeKind::Changed, "E::B"),
+        ]
+    );
+    assert_eq!(diff.version_bump(), VersionBump::Major);
+    assert!(diff.to_text().contains("+ int f3(int x)"));
+
+    let same = database_diff::diff(old.data(), old.data());
+    assert!(same.is_empty());
+    assert_eq!(same.version_bump(), VersionBump::Patch);
+}
+
+/// Adds a step that replaces `cpp_checker` and checks FFI items in two
+/// environments. Wrappers of `failing_function` fail in the second one.
+fn add_two_env_cpp_checker(config: &mut Config, failing_function: &'static str) {
+    let steps = config.processing_steps_mut();
+    steps.add_custom("fake_cpp_checker", move |data| {
+        let envs = ["1", "2"]
+            .iter()
+            .map(|version| LibraryTarget {
+                target: current_target(),
+                cpp_library_version: Some(version.to_string()),
+            })
+            .collect::<Vec<_>>();
+        for env in &envs {
+            data.db.add_environment(env.clone());
+        }
+        for id in data.db.ffi_item_ids().collect::<Vec<_>>() {
+            let is_failing = data
+                .db
+                .source_cpp_item(&id)?
+                .and_then(|item| item.item.path().cloned())
+                .map_or(false, |path| path.to_cpp_pseudo_code() == failing_function);
+            for (index, env) in envs.iter().enumerate() {
+                let item = CppChecksItem {
+                    env: env.clone(),
+                    is_success: !(is_failing && index == 1),
+                    snippet_hash: None,
+                    failure: None,
+                };
+                data.db.add_cpp_checks_item(id.clone(), item);
+            }
+        }
+        Ok(())
+    });
+    steps
+        .set_prerequisites("rust_generator", &["fake_cpp_checker"])
+        .unwrap();
+}
+
+#[test]
+fn database_diff_environments() {
+    let code = "int f1(int x); int f2(int x);";
+    let (_dir1, mut workspace1, result) = try_run_steps(code, RUST_GENERATOR_STEPS, |config| {
+        add_two_env_cpp_checker(config, "")
+    });
+    result.unwrap();
+    let (_dir2, mut workspace2, result) = try_run_steps(code, RUST_GENERATOR_STEPS, |config| {
+        add_two_env_cpp_checker(config, "f2")
+    });
+    result.unwrap();
+    let old = workspace1
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let new = workspace2
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+
+    // `f2` is still available in the first environment
+    let diff = database_diff::diff(old.data(), new.data());
+    let env_suffix = format!("(v2 on {})", current_target().short_text());
+    assert!(!diff.is_empty());
+    for entry in &diff.entries {
+        assert_eq!(entry.item_kind, DiffItemKind::RustItem);
+        assert_eq!(entry.change, DiffChangeKind::Removed);
+        assert!(entry.key.contains("f2"));
+        assert!(entry.key.ends_with(&env_suffix));
+    }
+    assert_eq!(diff.version_bump(), VersionBump::Major);
+
+    let reverse = database_diff::diff(new.data(), old.data());
+    assert_eq!(reverse.entries.len(), diff.entries.len());
+    assert_eq!(reverse.version_bump(), VersionBump::Minor);
+}
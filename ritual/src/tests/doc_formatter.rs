@@ -0,0 +1,416 @@
+use crate::database::DbItem;
+use crate::doc_formatter::*;
+use crate::rust_code_generator;
+use crate::rust_info::{RustFeatures, RustFunction};
+use crate::rust_type::RustPath;
+use crate::tests::run_rust_generator;
+use itertools::Itertools;
+use std::fs;
+
+/// Documentation of `cv::Mat::row` from OpenCV's `core/mat.hpp`.
+const MAT_ROW_COMMENT: &str = "/** @brief Creates a matrix header for the specified matrix row.
+
+    The method makes a new header for the specified matrix row and returns it. This is an O(1)
+    operation, regardless of the matrix size. The underlying data of the new matrix is shared with the
+    original matrix. Here is the example of one of the classical basic matrix processing operations,
+    axpy, used by LU and many other algorithms:
+    @code
+        inline void matrix_axpy(Mat& A, int i, int j, double alpha)
+        {
+            A.row(i) += A.row(j)*alpha;
+        }
+    @endcode
+    @note In the current implementation, the following code does not work as expected:
+    @code
+        Mat A;
+        ...
+        A.row(i) = A.row(j); // will not work
+    @endcode
+    @param y A 0-based row index.
+     */";
+
+/// Documentation of `cv::Mat::rowRange` from OpenCV's `core/mat.hpp`.
+const MAT_ROW_RANGE_COMMENT: &str = "/** @overload
+    @param startrow An inclusive 0-based start index of the row span.
+    @param endrow An exclusive 0-based ending index of the row span.
+     */";
+
+/// Documentation of `cv::saturate_cast` from OpenCV's `core/saturate.hpp`.
+const SATURATE_CAST_COMMENT: &str = "/**
+ @brief Template function for accurate conversion from one primitive type to another.
+
+ The function saturate_cast resembles the standard C++ cast operations, such as static_cast\\<T\\>()
+ and others. It perform an efficient and accurate conversion from one primitive type to another
+ (see the introduction chapter). saturate in the name means that when the input value v is out of the
+ range of the target type, the result is not formed just by taking low bits of the input, but instead
+ the value is clipped. For example:
+ \\code
+ uchar a = saturate_cast<uchar>(-100); // a = 0 (UCHAR_MIN)
+ short b = saturate_cast<short>(33333.33333); // b = 32767 (SHRT_MAX)
+ \\endcode
+ Such clipping is done when the target type is unsigned char , signed char , unsigned short or
+ signed short . For 32-bit integers, no clipping is done.
+
+ When the parameter is a floating-point value and the target type is an integer (8-, 16- or 32-bit),
+ the floating-point value is first rounded to the nearest integer and then clipped if needed (when
+ the target type is 8- or 16-bit).
+
+ @param v Function parameter.
+ @sa add, subtract, multiply, divide, Mat::convertTo
+ */";
+
+#[test]
+fn doxygen_mat_row() {
+    let comment = parse_doc_comment(MAT_ROW_COMMENT).unwrap();
+    assert_eq!(
+        comment.brief(),
+        Some("Creates a matrix header for the specified matrix row.")
+    );
+    assert_eq!(comment.description.len(), 2);
+    assert!(comment.description[1].starts_with("The method makes a new header"));
+    assert!(comment.description[1].ends_with(
+        "used by LU and many other algorithms:\n\
+         ```cpp\n\
+         inline void matrix_axpy(Mat& A, int i, int j, double alpha)\n\
+         {\n\
+         A.row(i) += A.row(j)*alpha;\n\
+         }\n\
+         ```"
+    ));
+    assert_eq!(
+        comment.notes,
+        vec![
+            "In the current implementation, the following code does not work as expected:\n\
+             ```cpp\n\
+             Mat A;\n\
+             ...\n\
+             A.row(i) = A.row(j); // will not work\n\
+             ```"
+        ]
+    );
+    assert_eq!(
+        comment.parameters,
+        vec![("y".to_string(), "A 0-based row index.".to_string())]
+    );
+    assert_eq!(comment.returns, None);
+
+    let markdown = comment.to_markdown(&[("y".to_string(), "row".to_string())]);
+    assert!(markdown.contains("Arguments:\n\n- `row`: A 0-based row index."));
+    assert!(markdown.contains(
+        "> Note: In the current implementation, the following code does not work as expected:\n\
+         > ```cpp\n\
+         > Mat A;\n"
+    ));
+}
+
+#[test]
+fn doxygen_unknown_commands() {
+    let comment = parse_doc_comment(MAT_ROW_RANGE_COMMENT).unwrap();
+    assert_eq!(comment.brief(), Some("@overload"));
+    assert_eq!(
+        comment.to_markdown(&[
+            ("startrow".to_string(), "start_row".to_string()),
+            ("endrow".to_string(), "end_row".to_string()),
+        ]),
+        "@overload\n\n\
+         Arguments:\n\n\
+         - `start_row`: An inclusive 0-based start index of the row span.\n\
+         - `end_row`: An exclusive 0-based ending index of the row span."
+    );
+
+    let comment = parse_doc_comment(SATURATE_CAST_COMMENT).unwrap();
+    assert_eq!(comment.description.len(), 3);
+    // angle brackets are escaped in text, but not in code blocks
+    assert!(comment.description[1].contains("such as static_cast&lt;T&gt;() and others."));
+    assert!(comment.description[1]
+        .contains("uchar a = saturate_cast<uchar>(-100); // a = 0 (UCHAR_MIN)"));
+    assert_eq!(
+        comment.parameters,
+        vec![(
+            "v".to_string(),
+            "Function parameter. @sa add, subtract, multiply, divide, Mat::convertTo".to_string()
+        )]
+    );
+
+    for text in &[
+        "\\",
+        "@",
+        "\\param",
+        "@param[in]",
+        "\\code",
+        "\\b",
+        "\\c `x",
+    ] {
+        let _ = DoxygenComment::parse(text).to_markdown(&[]);
+    }
+}
+
+#[test]
+fn doxygen_sections() {
+    let comment = DoxygenComment::parse(
+        "\\brief Returns the \\a index of [first] element.\n\
+         \n\
+         Uses \\c std::vector<int> internally and `Vec<T>` in Rust.\n\
+         \\param[in] value Value to \\b find.\n\
+         \\param[out] found Set to true\n\
+         if the value was found.\n\
+         \\return Index in range [0, size).\n\
+         \\deprecated Use \\c indexOf() instead.",
+    );
+    assert_eq!(
+        comment.brief(),
+        Some("Returns the `index` of \\[first\\] element.")
+    );
+    assert_eq!(
+        comment.description[1],
+        "Uses `std::vector<int>` internally and `Vec<T>` in Rust."
+    );
+    assert_eq!(
+        comment.parameters,
+        vec![
+            ("value".to_string(), "Value to **find**.".to_string()),
+            (
+                "found".to_string(),
+                "Set to true if the value was found.".to_string()
+            ),
+        ]
+    );
+    assert_eq!(
+        comment.returns,
+        Some("Index in range \\[0, size).".to_string())
+    );
+    assert_eq!(
+        comment.deprecated,
+        Some("Use `indexOf()` instead.".to_string())
+    );
+    assert_eq!(
+        comment.to_markdown(&[]),
+        "Returns the `index` of \\[first\\] element.\n\n\
+         Uses `std::vector<int>` internally and `Vec<T>` in Rust.\n\n\
+         Arguments:\n\n\
+         - `value`: Value to **find**.\n\
+         - `found`: Set to true if the value was found.\n\n\
+         Returns: Index in range \\[0, size).\n\n\
+         Deprecated: Use `indexOf()` instead."
+    );
+}
+
+#[test]
+fn doc_url_templates() {
+    let template = "https://docs.example.com/{class}.html#{method}";
+    assert_eq!(
+        fill_doc_url_template(
+            template,
+            &[("class", "ns::Vec<int>"), ("method", "operator<<")]
+        ),
+        Some("https://docs.example.com/ns%3A%3AVec%3Cint%3E.html#operator%3C%3C".to_string())
+    );
+    assert_eq!(
+        fill_doc_url_template(template, &[("class", "Widget")]),
+        Some("https://docs.example.com/Widget.html".to_string())
+    );
+    assert_eq!(fill_doc_url_template(template, &[("method", "f")]), None);
+    assert_eq!(
+        fill_doc_url_template("https://docs.example.com/{header}", &[("header", "a b.h")]),
+        Some("https://docs.example.com/a%20b.h".to_string())
+    );
+}
+
+#[test]
+fn doxygen_argument_names() {
+    let (_dir, config, database) = run_rust_generator(
+        "/**
+         * \\brief Resizes the widget.
+         * \\param newWidth Width in [pixels].
+         * \\return \\c true on success.
+         */
+        bool resizeWidget(int newWidth);",
+        |_| {},
+    );
+    let function = database
+        .find_rust_item(&RustPath::from_good_str("A::resize_widget"))
+        .unwrap()
+        .filter_map(|item| item.as_function_ref())
+        .unwrap();
+    let cpp_item = database.source_cpp_item(&function.id).unwrap().unwrap();
+    let comment = cpp_doc_comment(cpp_item.item).unwrap();
+    assert_eq!(
+        comment,
+        DoxygenComment {
+            description: vec!["Resizes the widget.".to_string()],
+            parameters: vec![("newWidth".to_string(), "Width in \\[pixels\\].".to_string())],
+            returns: Some("`true` on success.".to_string()),
+            ..DoxygenComment::default()
+        }
+    );
+
+    let names = argument_names(&function, &database).unwrap();
+    assert_eq!(
+        names,
+        vec![("newWidth".to_string(), "new_width".to_string())]
+    );
+    let markdown = comment.to_markdown(&names);
+    assert_eq!(
+        markdown,
+        "Resizes the widget.\n\n\
+         Arguments:\n\n\
+         - `new_width`: Width in \\[pixels\\].\n\n\
+         Returns: `true` on success."
+    );
+    let doc = function_doc(function, &database, &config).unwrap();
+    assert!(doc.ends_with(&format!("C++ documentation:\n\n{}", markdown)));
+}
+
+#[test]
+fn doc_links() {
+    let (_dir, _config, database) = run_rust_generator(
+        "class Layout { public: Layout(); };
+        class Widget {
+        public:
+            Widget();
+            void setLayout(Layout* layout);
+            void setLayout(int   spacing);
+        };",
+        |_| {},
+    );
+    let set_layout = database
+        .rust_items()
+        .filter_map(|item| item.filter_map(|item| item.as_function_ref()))
+        .filter(|function| {
+            function.item.is_public
+                && function.item.path.parent().unwrap() == RustPath::from_good_str("A::Widget")
+                && function.item.path.last().starts_with("set_layout")
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(set_layout.len(), 2);
+    let cpp_function = |function: &DbItem<&RustFunction>| {
+        database
+            .source_cpp_item(&function.id)
+            .unwrap()
+            .unwrap()
+            .item
+            .as_function_ref()
+            .unwrap()
+    };
+    let (pointer, int) = if cpp_function(&set_layout[0]).arguments[0].name == "layout" {
+        (&set_layout[0], &set_layout[1])
+    } else {
+        (&set_layout[1], &set_layout[0])
+    };
+    let overload = |function: &DbItem<&RustFunction>| {
+        format!(
+            "- [`{name}`](crate::Widget::{name}): {cpp}",
+            name = function.item.path.last(),
+            cpp = wrap_inline_cpp_code(&cpp_function(function).short_text())
+        )
+    };
+
+    assert_eq!(
+        related_items_doc(pointer, cpp_function(pointer), &database).unwrap(),
+        format!(
+            "Member of [`Widget`](crate::Widget).\n\n\
+             Related types: [`Layout`](crate::Layout).\n\n\
+             Overloads:\n\n{}\n\n",
+            overload(int)
+        )
+    );
+    assert_eq!(
+        related_items_doc(int, cpp_function(int), &database).unwrap(),
+        format!(
+            "Member of [`Widget`](crate::Widget).\n\n\
+             Overloads:\n\n{}\n\n",
+            overload(pointer)
+        )
+    );
+}
+
+#[test]
+fn doc_urls() {
+    let (_dir, config, database) = run_rust_generator(
+        "namespace ns {
+            class Widget { public: Widget(); int width() const; };
+            class Plain { public: int x() const; };
+            enum Color { Red, Green };
+            void update();
+        }",
+        |config| {
+            assert!(config.set_doc_url_template("https://x/{function}").is_err());
+            assert!(config.set_enum_doc_url_template("https://x/{enum").is_err());
+            config
+                .set_doc_url_template("https://docs.example.com/{class}.html#{method}")
+                .unwrap();
+            config
+                .set_function_doc_url_template("https://docs.example.com/{header}#{function}")
+                .unwrap();
+            config
+                .set_enum_doc_url_template("https://docs.example.com/{enum}.html#{value}")
+                .unwrap();
+        },
+    );
+    let url = |path: &str| {
+        let item = database
+            .find_rust_item(&RustPath::from_good_str(path))
+            .unwrap();
+        cpp_doc_url(&item.id, &database, &config).unwrap()
+    };
+    let expected = |url: &str| Some(format!("https://docs.example.com/{}", url));
+    assert_eq!(url("A::ns::Widget"), expected("ns%3A%3AWidget.html"));
+    assert_eq!(
+        url("A::ns::Widget::width"),
+        expected("ns%3A%3AWidget.html#width")
+    );
+    assert_eq!(url("A::ns::update"), expected("myfakelib.h#ns%3A%3Aupdate"));
+    assert_eq!(url("A::ns::Color"), expected("ns%3A%3AColor.html"));
+    assert_eq!(
+        url("A::ns::Color::Green"),
+        expected("ns%3A%3AColor.html#Green")
+    );
+    // the implicit constructor is not declared in the header
+    assert_eq!(url("A::ns::Plain::new"), None);
+    assert_eq!(url("A::ns::Plain::x"), expected("ns%3A%3APlain.html#x"));
+}
+
+#[test]
+fn doc_aliases() {
+    let (dir, config, database) = run_rust_generator(
+        "namespace ns {
+            class Window {
+            public:
+                Window();
+                void setWindowTitle(int title);
+                int& operator[](int index);
+                bool operator==(const Window& other) const;
+            };
+            enum Mode { Simple, DoubleClick };
+        }",
+        |_| {},
+    );
+    let aliases = |path: &str| {
+        let item = database
+            .find_rust_item(&RustPath::from_good_str(path))
+            .unwrap();
+        rust_code_generator::doc_aliases(&database, &item.id, path.rsplit("::").next().unwrap())
+            .unwrap()
+    };
+    assert_eq!(aliases("A::ns::Window"), vec!["ns::Window"]);
+    assert_eq!(
+        aliases("A::ns::Window::set_window_title"),
+        vec!["setWindowTitle"]
+    );
+    assert_eq!(aliases("A::ns::Window::index"), vec!["operator[]"]);
+    assert_eq!(aliases("A::ns::Mode"), vec!["ns::Mode"]);
+    // names equal to the Rust names are not repeated
+    assert!(aliases("A::ns::Mode::DoubleClick").is_empty());
+
+    // aliases are not allowed in trait implementations
+    let output_path = dir.path().join("generated");
+    rust_code_generator::generate(&database, &config, &RustFeatures::default(), &output_path)
+        .unwrap();
+    for entry in fs::read_dir(&output_path).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_file() {
+            let code = fs::read_to_string(path).unwrap();
+            assert!(!code.contains("#[doc(alias = \"operator==\")]"));
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use crate::logging::{parse_category_level, LogCategory, LogFormat, LogSettings, Logger};
+use log::{Level, LevelFilter, Log, Record};
+use std::collections::BTreeMap;
+
+#[test]
+fn categories_of_targets() {
+    assert_eq!(
+        LogCategory::of_target("ritual::cpp_parser"),
+        LogCategory::Parser
+    );
+    assert_eq!(
+        LogCategory::of_target("ritual::cpp_template_instantiator"),
+        LogCategory::Templates
+    );
+    assert_eq!(
+        LogCategory::of_target("ritual::rust_generator"),
+        LogCategory::RustNames
+    );
+    assert_eq!(
+        LogCategory::of_target("ritual::database::inner"),
+        LogCategory::Integrity
+    );
+    assert_eq!(
+        LogCategory::of_target("ritual::processor"),
+        LogCategory::General
+    );
+    assert_eq!(
+        LogCategory::of_target("qt_ritual::cpp_parser"),
+        LogCategory::General
+    );
+}
+
+#[test]
+fn category_level_spec() {
+    assert_eq!(
+        parse_category_level("templates=error").unwrap(),
+        (LogCategory::Templates, LevelFilter::Error)
+    );
+    assert_eq!(
+        parse_category_level("rust_names = off").unwrap(),
+        (LogCategory::RustNames, LevelFilter::Off)
+    );
+    assert!(parse_category_level("templates").is_err());
+    assert!(parse_category_level("unknown=warn").is_err());
+    assert!(parse_category_level("parser=loud").is_err());
+
+    assert_eq!("json".parse::<LogFormat>().unwrap(), LogFormat::JsonLines);
+    assert!("xml".parse::<LogFormat>().is_err());
+}
+
+#[test]
+fn hidden_warnings_are_counted() {
+    let logger = Logger::new(LogSettings {
+        stderr_level: LevelFilter::Off,
+        category_levels: vec![(LogCategory::Templates, LevelFilter::Error)],
+        ..LogSettings::default()
+    })
+    .unwrap();
+    let metadata = |level, target| log::Metadata::builder().level(level).target(target).build();
+    assert!(!logger.enabled(&metadata(Level::Info, "ritual::cpp_template_instantiator")));
+    assert!(logger.enabled(&metadata(Level::Warn, "ritual::cpp_template_instantiator")));
+    assert!(logger.enabled(&metadata(Level::Trace, "ritual::cpp_parser")));
+
+    for (level, target) in &[
+        (Level::Warn, "ritual::cpp_template_instantiator"),
+        (Level::Warn, "ritual::cpp_template_instantiator"),
+        (Level::Warn, "ritual::cpp_parser"),
+        (Level::Info, "ritual::cpp_parser"),
+    ] {
+        logger.log(
+            &Record::builder()
+                .level(*level)
+                .target(target)
+                .args(format_args!("message"))
+                .build(),
+        );
+    }
+    let mut expected = BTreeMap::new();
+    expected.insert(LogCategory::Templates, 2);
+    expected.insert(LogCategory::Parser, 1);
+    assert_eq!(logger.warning_counts(), expected);
+}
@@ -1,7 +1,179 @@
 #![allow(clippy::cognitive_complexity)]
 
+use crate::config::{Config, CrateProperties};
+use crate::cpp_checks::CppChecksItem;
+use crate::database::DatabaseClient;
+use crate::progress::{ProgressEvent, ProgressHandler};
+use crate::workspace::Workspace;
+use ritual_common::cpp_build_config::CppBuildPaths;
+use ritual_common::errors::Result;
+use ritual_common::file_utils::{create_dir, create_file};
+use ritual_common::target::{current_target, LibraryTarget};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use tempdir::TempDir;
+
+mod config;
+mod coverage;
+mod cpp_casts;
+mod cpp_checker;
+mod cpp_checks;
 mod cpp_ffi_data;
+mod cpp_ffi_generator;
+mod cpp_implicit_methods;
+mod cpp_inheritance;
+mod cpp_macros;
 mod cpp_method;
+mod cpp_omitting_arguments;
 mod cpp_operator;
 mod cpp_parser;
+mod cpp_template_instantiator;
 mod cpp_type;
+mod crate_writer;
+mod database;
+mod database_diff;
+mod doc_formatter;
+mod logging;
+mod processor;
+mod rust_generator;
+mod rust_info;
+mod type_allocation_places;
+mod user_hooks;
+mod versions;
+
+pub const FAKE_LIB_HEADER: &str = "myfakelib.h";
+
+/// Returns config of the fake library created by `try_run_steps` in `dir`.
+pub fn fake_lib_config(dir: &Path) -> Config {
+    let include_dir = dir.join("include");
+    let mut paths = CppBuildPaths::new();
+    paths.add_include_path(&include_dir);
+
+    let mut config = Config::new(CrateProperties::new("A", "0.0.0"));
+    config.add_include_directive(FAKE_LIB_HEADER);
+    config.set_cpp_build_paths(paths);
+    config.add_target_include_path(include_dir.join(FAKE_LIB_HEADER));
+    config
+}
+
+/// Runs processing `steps` on a fake library containing `code`.
+/// `configure` can adjust the config before processing.
+/// Returns the workspace containing the resulting database and the processing result.
+pub fn try_run_steps(
+    code: &'static str,
+    steps: &[&str],
+    configure: impl FnOnce(&mut Config),
+) -> (TempDir, Workspace, Result<()>) {
+    let (dir, _config, workspace, result) = process_fake_lib(code, steps, configure);
+    (dir, workspace, result)
+}
+
+/// Same as `try_run_steps`, but also returns the config used for processing.
+fn process_fake_lib(
+    code: &'static str,
+    steps: &[&str],
+    configure: impl FnOnce(&mut Config),
+) -> (TempDir, Config, Workspace, Result<()>) {
+    let dir = TempDir::new("test_cpp_parser_run").unwrap();
+
+    let mut workspace = Workspace::new(dir.path().into()).unwrap();
+
+    let include_dir = dir.path().join("include");
+    create_dir(&include_dir).unwrap();
+    {
+        let mut include_file = create_file(include_dir.join(FAKE_LIB_HEADER)).unwrap();
+        writeln!(include_file, "{}", code).unwrap();
+    }
+
+    let mut config = fake_lib_config(dir.path());
+    configure(&mut config);
+
+    let steps = steps.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    let result = crate::processor::process(&mut workspace, &config, &steps, None);
+    (dir, config, workspace, result)
+}
+
+/// Runs processing `steps` on a fake library containing `code`.
+/// Returns the workspace containing the resulting database.
+pub fn run_steps(code: &'static str, steps: &[&str]) -> (TempDir, Workspace) {
+    let (dir, workspace, result) = try_run_steps(code, steps, |_| {});
+    result.unwrap();
+    (dir, workspace)
+}
+
+/// Steps that produce Rust items without running the C++ compiler
+pub const RUST_GENERATOR_STEPS: &[&str] = &[
+    "cpp_parser",
+    "cpp_ffi_generator",
+    "fake_cpp_checker",
+    "rust_generator",
+];
+
+/// Adds a step that replaces `cpp_checker`. C++ checks require a compiler,
+/// so all FFI items are marked as valid instead.
+pub fn add_fake_cpp_checker(config: &mut Config) {
+    let steps = config.processing_steps_mut();
+    steps.add_custom("fake_cpp_checker", |data| {
+        let env = LibraryTarget {
+            target: current_target(),
+            cpp_library_version: None,
+        };
+        data.db.add_environment(env.clone());
+        for id in data.db.ffi_item_ids().collect::<Vec<_>>() {
+            // the step may run again after the second parser stage
+            if data.db.cpp_checks(&id)?.has_env(&env) {
+                continue;
+            }
+            let item = CppChecksItem {
+                env: env.clone(),
+                is_success: true,
+                snippet_hash: None,
+                failure: None,
+            };
+            data.db.add_cpp_checks_item(id, item);
+        }
+        Ok(())
+    });
+    steps
+        .set_prerequisites("rust_generator", &["fake_cpp_checker"])
+        .unwrap();
+}
+
+/// Runs `RUST_GENERATOR_STEPS` on a fake library containing `code`.
+/// `configure` can adjust the config before processing.
+/// Returns the config and the database containing the generated Rust items.
+pub fn run_rust_generator(
+    code: &'static str,
+    configure: impl FnOnce(&mut Config),
+) -> (TempDir, Config, DatabaseClient) {
+    run_rust_generator_with_steps(code, RUST_GENERATOR_STEPS, configure)
+}
+
+/// Same as `run_rust_generator`, but runs `steps` instead of `RUST_GENERATOR_STEPS`.
+/// `cpp_checker` is replaced by `add_fake_cpp_checker`.
+pub fn run_rust_generator_with_steps(
+    code: &'static str,
+    steps: &[&str],
+    configure: impl FnOnce(&mut Config),
+) -> (TempDir, Config, DatabaseClient) {
+    let (dir, config, mut workspace, result) = process_fake_lib(code, steps, |config| {
+        add_fake_cpp_checker(config);
+        configure(config);
+    });
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    (dir, config, database)
+}
+
+/// Progress handler that stores all received events.
+#[derive(Default)]
+pub struct CollectingProgressHandler(pub Mutex<Vec<ProgressEvent>>);
+
+impl ProgressHandler for CollectingProgressHandler {
+    fn handle(&self, event: &ProgressEvent) {
+        self.0.lock().unwrap().push(event.clone());
+    }
+}
@@ -0,0 +1,248 @@
+use crate::config::{Config, CrateProperties};
+use crate::database::{DatabaseCache, DocItem};
+use crate::processor;
+use crate::progress::ProgressEvent;
+use crate::tests::{
+    fake_lib_config, run_steps, try_run_steps, CollectingProgressHandler, FAKE_LIB_HEADER,
+};
+use crate::workspace::Workspace;
+use itertools::Itertools;
+use ritual_common::cpp_build_config::CppBuildPaths;
+use ritual_common::file_utils::{create_dir, create_file};
+use std::io::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use tempdir::TempDir;
+
+#[test]
+fn processing_step_states() {
+    let (dir, mut workspace) = run_steps("int f(int x);", &["cpp_parser"]);
+    let config = fake_lib_config(dir.path());
+    let mut run =
+        |step: &str| processor::process(&mut workspace, &config, &[step.to_string()], None);
+
+    run("cpp_ffi_generator").unwrap();
+    let err = run("rust_generator").unwrap_err().to_string();
+    assert!(err.contains("cpp_checker (required by rust_generator)"));
+    assert!(run("[crate_writer..]").is_err());
+
+    // running a step again invalidates the following steps
+    run("cpp_parser").unwrap();
+    let states = workspace.load_step_states("A").unwrap().unwrap();
+    assert_eq!(
+        states.completed.keys().collect::<Vec<_>>(),
+        vec!["cpp_parser"]
+    );
+}
+
+/// Adds a step that creates a documentation item for each C++ item
+/// and panics after processing `crash_after` items.
+fn add_long_step(config: &mut Config, crash_after: Option<usize>) {
+    config
+        .processing_steps_mut()
+        .add_after(&["cpp_parser"], "long_step", move |data| {
+            for (index, id) in data
+                .db
+                .cpp_item_ids()
+                .collect::<Vec<_>>()
+                .into_iter()
+                .enumerate()
+            {
+                if Some(index) == crash_after {
+                    panic!("simulated crash");
+                }
+                let html = data.db.cpp_item(&id)?.item.short_text();
+                let doc = DocItem {
+                    anchor: None,
+                    html,
+                    mismatched_declaration: None,
+                    url: None,
+                    cross_references: Vec::new(),
+                };
+                data.db.add_doc_item(id, doc);
+                data.checkpoint(index + 1)?;
+            }
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn resume_interrupted_run() {
+    let code = "int f1(); int f2(); int f3(); int f4(); int f5();";
+    let steps = ["cpp_parser".to_string(), "long_step".to_string()];
+    let items = |workspace: &mut Workspace| {
+        let database = workspace
+            .get_database_client("A", &[], true, false)
+            .unwrap();
+        database
+            .items()
+            .map(|item| {
+                let text = match item.item.as_doc_item() {
+                    Some(doc) => doc.html.clone(),
+                    None => item.item.short_text(),
+                };
+                (item.id, item.source_id, text)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let (_dir, mut workspace, result) =
+        try_run_steps(code, &["cpp_parser", "long_step"], |config| {
+            add_long_step(config, None)
+        });
+    result.unwrap();
+    let expected_items = items(&mut workspace);
+
+    let dir = TempDir::new("test_cpp_parser_run").unwrap();
+    let mut workspace = Workspace::new(dir.path().into()).unwrap();
+    create_dir(dir.path().join("include")).unwrap();
+    let mut include_file = create_file(dir.path().join("include").join(FAKE_LIB_HEADER)).unwrap();
+    writeln!(include_file, "{}", code).unwrap();
+    drop(include_file);
+
+    let mut config = fake_lib_config(dir.path());
+    add_long_step(&mut config, Some(3));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        processor::process(&mut workspace, &config, &steps, None)
+    }));
+    assert!(result.is_err());
+    // the unsaved database is dropped along with the process
+    DatabaseCache::global()
+        .lock()
+        .unwrap()
+        .remove_if_exists(workspace.database_path("A"));
+
+    let progress = workspace.load_run_progress("A").unwrap().unwrap();
+    assert_eq!(progress.remaining_steps[0].name, "long_step");
+    assert_eq!(progress.processed_items, 3);
+
+    let mut config = fake_lib_config(dir.path());
+    add_long_step(&mut config, None);
+    processor::process(&mut workspace, &config, &["resume".to_string()], None).unwrap();
+    assert!(workspace.load_run_progress("A").unwrap().is_none());
+    assert_eq!(items(&mut workspace), expected_items);
+}
+
+#[test]
+fn progress_events() {
+    let handler = Arc::new(CollectingProgressHandler::default());
+    let (_dir, _workspace, result) =
+        try_run_steps("int f1(); int f2();", &["cpp_parser"], |config| {
+            config.set_progress_handler(Arc::clone(&handler))
+        });
+    result.unwrap();
+
+    let events = handler.0.lock().unwrap();
+    assert_eq!(
+        events.first(),
+        Some(&ProgressEvent::StepStarted {
+            crate_name: "A".to_string(),
+            step: "cpp_parser".to_string(),
+            index: 0,
+            count: 1,
+        })
+    );
+    let items = events
+        .iter()
+        .filter_map(|event| match event {
+            ProgressEvent::Items {
+                step,
+                processed,
+                current_item,
+                ..
+            } => {
+                assert_eq!(step, "cpp_parser");
+                Some((*processed, current_item.clone().unwrap()))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert!(items
+        .iter()
+        .enumerate()
+        .all(|(index, item)| item.0 == index as u64 + 1));
+    assert!(items.iter().any(|item| item.1.contains("f1")));
+    assert!(items.iter().any(|item| item.1.contains("f2")));
+    assert!(events.iter().any(|event| match event {
+        ProgressEvent::StepFinished { step, success, .. } => step == "cpp_parser" && *success,
+        _ => false,
+    }));
+
+    let json = serde_json::to_string(&events[0]).unwrap();
+    assert!(json.starts_with(r#"{"event":"step_started","#));
+}
+
+#[test]
+fn multiple_cpp_lib_versions() {
+    let dir = TempDir::new("test_cpp_parser_run").unwrap();
+    let mut workspace = Workspace::new(dir.path().into()).unwrap();
+    let mut config = Config::new(CrateProperties::new("A", "0.0.0"));
+    config.add_include_directive(FAKE_LIB_HEADER);
+    let headers = [
+        ("2.0.0", "int f1(int x); int f2(int x);"),
+        ("1.0.0", "int f1(int x);"),
+    ];
+    for &(version, code) in &headers {
+        let include_dir = dir.path().join(version);
+        create_dir(&include_dir).unwrap();
+        let mut include_file = create_file(include_dir.join(FAKE_LIB_HEADER)).unwrap();
+        writeln!(include_file, "{}", code).unwrap();
+        drop(include_file);
+        let mut paths = CppBuildPaths::new();
+        paths.add_include_path(&include_dir);
+        config.add_cpp_lib_version(version, paths).unwrap();
+        config.add_target_include_path(include_dir.join(FAKE_LIB_HEADER));
+    }
+    assert!(config
+        .add_cpp_lib_version("1.0.0", CppBuildPaths::new())
+        .is_err());
+    assert!(config
+        .add_cpp_lib_version("3.0", CppBuildPaths::new())
+        .is_err());
+
+    let recorded = Arc::new(Mutex::new(Vec::new()));
+    let recorded2 = Arc::clone(&recorded);
+    let steps = config.processing_steps_mut();
+    steps.add_custom("record_cpp_lib_version", move |data| {
+        let include_paths = data.cpp_build_paths().include_paths().to_vec();
+        recorded2.lock().unwrap().push((
+            data.cpp_lib_version().map(ToString::to_string),
+            include_paths,
+        ));
+        Ok(())
+    });
+    steps
+        .set_per_cpp_lib_version("record_cpp_lib_version")
+        .unwrap();
+
+    let steps = vec![
+        "cpp_parser".to_string(),
+        "record_cpp_lib_version".to_string(),
+    ];
+    processor::process(&mut workspace, &config, &steps, None).unwrap();
+    // versions are processed from oldest to newest
+    assert_eq!(
+        *recorded.lock().unwrap(),
+        vec![
+            (Some("1.0.0".to_string()), vec![dir.path().join("1.0.0")]),
+            (Some("2.0.0".to_string()), vec![dir.path().join("2.0.0")]),
+        ]
+    );
+
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let mut functions = database
+        .cpp_items()
+        .filter_map(|item| item.item.as_function_ref())
+        .map(|f| f.path.to_cpp_pseudo_code())
+        .collect::<Vec<_>>();
+    functions.sort();
+    assert_eq!(functions, vec!["f1", "f2"]);
+    drop(database);
+
+    config.set_cpp_lib_version("1.0.0");
+    let err = processor::process(&mut workspace, &config, &steps, None).unwrap_err();
+    assert!(err.to_string().contains("add_cpp_lib_version"));
+}
@@ -0,0 +1,1022 @@
+use crate::config::{
+    Config, ModuleStrategy, NestedTypesStrategy, ReturnOwnership, ThreadSafety, TypeAllocationPlace,
+};
+use crate::cpp_code_generator;
+use crate::cpp_data::*;
+use crate::cpp_ffi_data::CppToFfiTypeConversion;
+use crate::cpp_type::*;
+use crate::processor;
+use crate::progress::ProgressEvent;
+use crate::rust_code_generator;
+use crate::rust_info::{
+    RustExtraImplKind, RustFeatures, RustFunctionKind, RustReexportSource, RustStructKind,
+    RustWrapperTypeKind,
+};
+use crate::rust_type::{RustCommonType, RustPath, RustType};
+use crate::tests::{
+    add_fake_cpp_checker, fake_lib_config, run_rust_generator, run_rust_generator_with_steps,
+    try_run_steps, CollectingProgressHandler, FAKE_LIB_HEADER, RUST_GENERATOR_STEPS,
+};
+use crate::workspace::Workspace;
+use itertools::Itertools;
+use ritual_common::file_utils::{create_dir, create_file};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::sync::Arc;
+use tempdir::TempDir;
+
+#[test]
+fn stable_rust_names() {
+    let dir = TempDir::new("test_cpp_parser_run").unwrap();
+    let mut workspace = Workspace::new(dir.path().into()).unwrap();
+    let include_dir = dir.path().join("include");
+    create_dir(&include_dir).unwrap();
+    let write_header = |code: &str| {
+        let mut file = create_file(include_dir.join(FAKE_LIB_HEADER)).unwrap();
+        writeln!(file, "{}", code).unwrap();
+    };
+
+    let mut config = fake_lib_config(dir.path());
+    add_fake_cpp_checker(&mut config);
+    let steps = RUST_GENERATOR_STEPS
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+    let function_paths = |workspace: &mut Workspace| {
+        let database = workspace
+            .get_database_client("A", &[], true, false)
+            .unwrap();
+        let mut paths = database
+            .rust_items()
+            .filter_map(|item| item.item.as_function_ref())
+            .filter(|f| matches!(f.kind, RustFunctionKind::FfiWrapper(_)))
+            .map(|f| f.path.last().to_string())
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths
+    };
+
+    write_header("void f(int x); void g(int x); void g(double x); void h(int x);");
+    processor::process(&mut workspace, &config, &steps, None).unwrap();
+    let old_paths = function_paths(&mut workspace);
+    assert!(old_paths.contains(&"f".to_string()));
+    assert!(old_paths.contains(&"h".to_string()));
+    let g_paths = old_paths
+        .iter()
+        .filter(|path| path.starts_with("g"))
+        .cloned()
+        .collect::<Vec<_>>();
+    assert_eq!(g_paths.len(), 2);
+
+    // a new overload of `f` doesn't rename the existing one,
+    // and the name of the removed `h(int)` is not reused
+    write_header(
+        "void f(int x); void f(double x); void g(int x); void g(double x); void h(double x);",
+    );
+    processor::process(&mut workspace, &config, &steps, None).unwrap();
+    let new_paths = function_paths(&mut workspace);
+    assert!(new_paths.contains(&"f".to_string()));
+    assert!(new_paths.contains(&"f_double".to_string()));
+    for path in &g_paths {
+        assert!(new_paths.contains(path));
+    }
+    assert!(!new_paths.contains(&"h".to_string()));
+    assert!(new_paths.iter().any(|path| path.starts_with("h_")));
+
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    assert_eq!(
+        database.rust_name("f(int)").map(|path| path.last()),
+        Some("f")
+    );
+    assert!(database.rust_name_changes().is_empty());
+}
+
+#[test]
+fn ownership() {
+    let (_dir, mut workspace, result) = try_run_steps(
+        "
+        class P { public: P(); static P* create(); P* parent(); };
+        class S { public: S(); };
+        ",
+        &[
+            "cpp_parser",
+            "type_allocation_places",
+            "cpp_ffi_generator",
+            "fake_cpp_checker",
+            "rust_generator",
+        ],
+        |config| {
+            add_fake_cpp_checker(config);
+            config
+                .set_type_allocation_place(TypeAllocationPlace::Heap, CppPath::from_good_str("P"));
+            config
+                .set_type_allocation_place(TypeAllocationPlace::Stack, CppPath::from_good_str("S"));
+            config
+                .set_return_ownership(CppPath::from_good_str("P::create"), ReturnOwnership::Owned);
+        },
+    );
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+
+    // destructors are only called by the owning type, exactly once
+    let mut destructor_impls = database
+        .rust_items()
+        .filter_map(|item| item.item.as_trait_impl_ref())
+        .filter(|item| {
+            item.trait_type.path == RustPath::from_good_str("std::ops::Drop")
+                || item.trait_type.path == RustPath::from_good_str("cpp_core::CppDeletable")
+        })
+        .map(|item| {
+            (
+                item.target_type
+                    .as_common()
+                    .unwrap()
+                    .path
+                    .last()
+                    .to_string(),
+                item.trait_type.path.last().to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+    destructor_impls.sort();
+    assert_eq!(
+        destructor_impls,
+        vec![
+            ("P".to_string(), "CppDeletable".to_string()),
+            ("S".to_string(), "Drop".to_string()),
+        ]
+    );
+    let wrappers = database
+        .rust_items()
+        .filter_map(|item| item.item.as_function_ref())
+        .filter(|f| matches!(f.kind, RustFunctionKind::FfiWrapper(_)))
+        .collect::<Vec<_>>();
+    assert!(wrappers
+        .iter()
+        .all(|f| !f.path.last().contains("destructor") && !f.path.last().contains("delete")));
+
+    let return_type_path = |name: &str| {
+        let function = wrappers.iter().find(|f| f.path.last() == name).unwrap();
+        let option = function.return_type.api_type().as_common().unwrap();
+        assert_eq!(option.path, RustPath::from_good_str("std::option::Option"));
+        option.generic_arguments.as_ref().unwrap()[0]
+            .as_common()
+            .unwrap()
+            .path
+            .clone()
+    };
+    assert_eq!(
+        return_type_path("create"),
+        RustPath::from_good_str("cpp_core::CppBox")
+    );
+    assert_eq!(
+        return_type_path("parent"),
+        RustPath::from_good_str("cpp_core::Ref")
+    );
+}
+
+#[test]
+fn free_operator_trait_impls() {
+    let (_dir, mut workspace, result) = try_run_steps(
+        "
+        class P { public: P(); };
+        P operator*(const P& p, double d);
+        P operator*(double d, const P& p);
+        ",
+        RUST_GENERATOR_STEPS,
+        add_fake_cpp_checker,
+    );
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let mul_impls = database
+        .rust_items()
+        .filter_map(|item| item.item.as_trait_impl_ref())
+        .filter(|item| item.trait_type.path == RustPath::from_good_str("std::ops::Mul"))
+        .collect::<Vec<_>>();
+    assert_eq!(mul_impls.len(), 2);
+    let p_type = |rust_type: &RustType| {
+        matches!(
+            rust_type.pointer_like_to_target(),
+            Ok(RustType::Common(RustCommonType { path, .. })) if path.last() == "P"
+        )
+    };
+
+    // `P * f64`
+    let direct = mul_impls
+        .iter()
+        .find(|item| p_type(&item.target_type))
+        .unwrap();
+    assert_eq!(
+        direct.trait_type.generic_arguments,
+        Some(vec![RustType::Primitive("f64".into())])
+    );
+
+    // `f64 * P` is implemented for `f64` instead of becoming a free function
+    let reflected = mul_impls
+        .iter()
+        .find(|item| item.target_type == RustType::Primitive("f64".into()))
+        .unwrap();
+    let trait_args = reflected.trait_type.generic_arguments.as_ref().unwrap();
+    assert!(p_type(&trait_args[0]));
+    assert!(database
+        .rust_items()
+        .filter_map(|item| item.item.as_function_ref())
+        .all(|f| !f.path.last().starts_with("mul")));
+}
+
+#[test]
+fn rename_rules() {
+    let (_dir, mut workspace, result) = try_run_steps(
+        "namespace ns { int qAbs(int x); int quit(); void open(int mode); }",
+        RUST_GENERATOR_STEPS,
+        |config| {
+            add_fake_cpp_checker(config);
+            config.rename("ns::open", "open_file").unwrap();
+            config.strip_prefix("q").unwrap();
+            config.rename_module("ns", "names").unwrap();
+            config.rename("ns::missing", "missing").unwrap();
+        },
+    );
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    for path in &[
+        "A::names",
+        "A::names::abs",
+        "A::names::quit",
+        "A::names::open_file",
+    ] {
+        assert!(
+            database
+                .find_rust_item(&RustPath::from_good_str(path))
+                .is_some(),
+            "{} not found",
+            path
+        );
+    }
+
+    let applied_rules = database
+        .applied_rename_rules()
+        .iter()
+        .map(|rule| (rule.cpp_path.to_cpp_pseudo_code(), rule.rule.as_str()))
+        .collect::<Vec<_>>();
+    assert!(applied_rules.contains(&("ns::open".to_string(), "rename ns::open to open_file")));
+    assert!(applied_rules.contains(&("ns".to_string(), "rename module ns to names")));
+    assert!(applied_rules
+        .iter()
+        .any(|(path, rule)| path == "ns::qAbs" && rule.starts_with("replace")));
+    assert!(!applied_rules.iter().any(|(path, _)| path == "ns::quit"));
+    assert!(!applied_rules
+        .iter()
+        .any(|(_, rule)| rule.contains("missing")));
+}
+
+#[test]
+fn conflicting_rename_rules() {
+    let (_dir, _workspace, result) =
+        try_run_steps("int f(); int g();", RUST_GENERATOR_STEPS, |config| {
+            add_fake_cpp_checker(config);
+            config.rename("f", "h").unwrap();
+            config.rename("g", "h").unwrap();
+        });
+    let err = result.unwrap_err();
+    assert!(err.iter_chain().any(|cause| cause
+        .to_string()
+        .contains("rename rules map different items")));
+}
+
+/// Runs Rust generator on `code` and returns paths of all Rust items except FFI functions.
+fn rust_item_paths(code: &'static str, configure: impl FnOnce(&mut Config)) -> Vec<String> {
+    let (_dir, mut workspace, result) = try_run_steps(code, RUST_GENERATOR_STEPS, |config| {
+        add_fake_cpp_checker(config);
+        configure(config);
+    });
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    database
+        .rust_items()
+        .filter(|item| {
+            item.item
+                .as_function_ref()
+                .map_or(true, |f| !matches!(f.kind, RustFunctionKind::FfiFunction))
+        })
+        .filter_map(|item| item.item.path().map(|path| path.full_name(None)))
+        .collect()
+}
+
+#[test]
+fn flattened_namespaces() {
+    let code = "namespace mylib { int f(int x); namespace detail { int f(int x); } }";
+    let paths = rust_item_paths(code, |_| {});
+    assert!(paths.contains(&"A::mylib::f".to_string()));
+    assert!(paths.contains(&"A::mylib::detail::f".to_string()));
+
+    let paths = rust_item_paths(code, |config| {
+        config.flatten_namespace("mylib").unwrap();
+    });
+    assert!(!paths.contains(&"A::mylib".to_string()));
+    assert!(paths.contains(&"A::f".to_string()));
+    assert!(paths.contains(&"A::detail::f".to_string()));
+}
+
+#[test]
+fn crate_root_namespace() {
+    let (_dir, mut workspace, result) = try_run_steps(
+        "namespace mylib { int f(int x); }",
+        RUST_GENERATOR_STEPS,
+        |config| {
+            add_fake_cpp_checker(config);
+            config.set_crate_root_namespace("mylib").unwrap();
+        },
+    );
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let reexport = database
+        .rust_items()
+        .filter_map(|item| item.item.as_reexport_ref())
+        .find(|reexport| matches!(reexport.source, RustReexportSource::CppNamespace { .. }))
+        .unwrap();
+    assert_eq!(reexport.target, RustPath::from_good_str("A::mylib"));
+    assert_eq!(
+        reexport.path.parent().unwrap(),
+        RustPath::from_good_str("A")
+    );
+}
+
+#[test]
+fn header_modules() {
+    let paths = rust_item_paths("namespace ns { int f(int x); enum E { E1 }; }", |config| {
+        config.set_module_strategy(ModuleStrategy::Headers);
+    });
+    assert!(!paths.iter().any(|path| path.starts_with("A::ns")));
+    assert!(paths.contains(&"A::myfakelib".to_string()));
+    assert!(paths.contains(&"A::myfakelib::f".to_string()));
+    assert!(paths.contains(&"A::myfakelib::E".to_string()));
+    assert!(paths.contains(&"A::myfakelib::E::E1".to_string()));
+}
+
+#[test]
+fn prefixed_nested_types() {
+    let code = "class Outer { public: class Inner { public: int x(); }; int y(); };";
+    let paths = rust_item_paths(code, |_| {});
+    assert!(paths.contains(&"A::outer::Inner".to_string()));
+
+    let paths = rust_item_paths(code, |config| {
+        config.set_nested_types_strategy(NestedTypesStrategy::Prefixed);
+    });
+    assert!(!paths.contains(&"A::outer".to_string()));
+    assert!(paths.contains(&"A::Outer".to_string()));
+    assert!(paths.contains(&"A::OuterInner".to_string()));
+    assert!(paths.contains(&"A::OuterInner::x".to_string()));
+}
+
+#[test]
+fn keyword_and_prelude_names() {
+    let code = "namespace type { class Option { public: int match(int ref); }; }
+                int XMLHTTPRequest();
+                int getSQLValue();";
+    let paths = rust_item_paths(code, |_| {});
+    assert!(paths.contains(&"A::type_".to_string()));
+    assert!(paths.contains(&"A::type_::Option_".to_string()));
+    assert!(paths.contains(&"A::type_::Option_::r#match".to_string()));
+    assert!(paths.contains(&"A::xml_http_request".to_string()));
+    assert!(paths.contains(&"A::get_s_q_l_value".to_string()));
+
+    let paths = rust_item_paths(code, |config| {
+        config.set_prelude_collision_suffix("Class");
+        config.add_acronym("sql");
+    });
+    assert!(paths.contains(&"A::type_::OptionClass".to_string()));
+    assert!(paths.contains(&"A::get_sql_value".to_string()));
+}
+
+#[test]
+fn enum_wrapper_underlying_types() {
+    let (_dir, mut workspace, result) = try_run_steps(
+        "enum class Small : unsigned char { A, B }; enum Plain { C, D = C };",
+        RUST_GENERATOR_STEPS,
+        add_fake_cpp_checker,
+    );
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let underlying_type = |path: &str| {
+        let item = database
+            .find_rust_item(&RustPath::from_good_str(path))
+            .unwrap();
+        match &item.item.as_struct_ref().unwrap().kind {
+            RustStructKind::WrapperType(RustWrapperTypeKind::EnumWrapper { underlying_type }) => {
+                underlying_type.clone()
+            }
+            kind => panic!("unexpected struct kind: {:?}", kind),
+        }
+    };
+    let raw_type = |name: &str| {
+        RustType::Common(RustCommonType {
+            path: RustPath::from_good_str("std::os::raw").join(name),
+            generic_arguments: None,
+        })
+    };
+    assert_eq!(underlying_type("A::Small"), raw_type("c_uchar"));
+    assert_eq!(underlying_type("A::Plain"), raw_type("c_int"));
+}
+
+#[test]
+fn thread_safety_of_class_wrappers() {
+    let handler = Arc::new(CollectingProgressHandler::default());
+    let (_dir, _config, database) = run_rust_generator(
+        "class Base {}; class Derived : public Base {}; class Other {}; class Plain {};",
+        |config| {
+            config.set_progress_handler(Arc::clone(&handler));
+            config.set_default_thread_safety(ThreadSafety::Send);
+            config.set_thread_safety("Base", ThreadSafety::None);
+            config.set_thread_safety("Derived", ThreadSafety::SendSync);
+            config.set_thread_safety("P*", ThreadSafety::None);
+        },
+    );
+    let thread_safety = |path: &str| {
+        database
+            .find_rust_item(&RustPath::from_good_str(path))
+            .unwrap()
+            .item
+            .as_struct_ref()
+            .unwrap()
+            .thread_safety
+    };
+    assert_eq!(thread_safety("A::Base"), ThreadSafety::None);
+    assert_eq!(thread_safety("A::Derived"), ThreadSafety::SendSync);
+    assert_eq!(thread_safety("A::Other"), ThreadSafety::Send);
+    assert_eq!(thread_safety("A::Plain"), ThreadSafety::None);
+
+    let events = handler.0.lock().unwrap();
+    let warning = ProgressEvent::Warning {
+        message: "Derived is marked as SendSync, but it inherits Base \
+                  that is marked as not thread-safe"
+            .to_string(),
+    };
+    assert_eq!(events.iter().filter(|event| **event == warning).count(), 1);
+}
+
+#[test]
+fn borrow_checking() {
+    let (_dir, _config, database) = run_rust_generator(
+        "class Item { public: Item(); };
+        class Holder {
+        public:
+            Holder();
+            const Item& item() const;
+            Item& mutableItem();
+            int& value();
+            void setItem(const Item& item);
+            static const Item& defaultItem();
+            const Item& globalItem() const;
+        };
+        const int& globalValue();",
+        |config| {
+            config.set_borrow_checking(true);
+            config.set_unbound_return_lifetime(CppPath::from_good_str("Holder::globalItem"));
+        },
+    );
+    let function = |path: &str| {
+        database
+            .find_rust_item(&RustPath::from_good_str(path))
+            .unwrap()
+            .item
+            .as_function_ref()
+            .unwrap()
+            .clone()
+    };
+    let item_type = RustType::Common(RustCommonType {
+        path: RustPath::from_good_str("A::Item"),
+        generic_arguments: None,
+    });
+
+    let item = function("A::Holder::item");
+    let self_type = item.arguments[0].argument_type.api_type();
+    assert!(self_type.is_ref());
+    assert!(self_type.is_const_pointer_like().unwrap());
+    assert_eq!(self_type.lifetime(), Some("l0"));
+    let return_type = item.return_type.api_type();
+    assert!(return_type.is_ref());
+    assert!(return_type.is_const_pointer_like().unwrap());
+    assert_eq!(return_type.lifetime(), Some("l0"));
+    assert_eq!(return_type.pointer_like_to_target().unwrap(), item_type);
+
+    let mutable_item = function("A::Holder::mutable_item");
+    let self_type = mutable_item.arguments[0].argument_type.api_type();
+    assert!(!self_type.is_const_pointer_like().unwrap());
+    assert_eq!(self_type.lifetime(), Some("l0"));
+    let return_type = mutable_item.return_type.api_type();
+    assert!(return_type.is_ref());
+    assert!(!return_type.is_const_pointer_like().unwrap());
+    assert_eq!(return_type.lifetime(), Some("l0"));
+
+    let value = function("A::Holder::value");
+    let return_type = value.return_type.api_type();
+    assert!(return_type.is_ref());
+    assert!(!return_type.is_const_pointer_like().unwrap());
+    assert_eq!(
+        return_type.pointer_like_to_target().unwrap(),
+        RustType::Common(RustCommonType {
+            path: RustPath::from_good_str("std::os::raw::c_int"),
+            generic_arguments: None,
+        })
+    );
+
+    let set_item = function("A::Holder::set_item");
+    let self_type = set_item.arguments[0].argument_type.api_type();
+    assert!(!self_type.is_const_pointer_like().unwrap());
+    assert_eq!(self_type.lifetime(), None);
+    let arg_type = set_item.arguments[1].argument_type.api_type();
+    assert!(arg_type.is_ref());
+    assert!(arg_type.is_const_pointer_like().unwrap());
+    assert_eq!(arg_type.pointer_like_to_target().unwrap(), item_type);
+
+    let default_item = function("A::Holder::default_item");
+    assert!(!default_item.return_type.api_type().is_ref());
+    assert_eq!(
+        default_item.return_type.api_type(),
+        default_item.return_type.ffi_type()
+    );
+
+    let global_item = function("A::Holder::global_item");
+    assert!(global_item.return_type.conversion().is_utils_ref_to_ptr());
+
+    let global_value = function("A::global_value");
+    assert_eq!(
+        global_value.return_type.api_type(),
+        global_value.return_type.ffi_type()
+    );
+}
+
+#[test]
+fn subclass_shim_protected_members() {
+    let (_dir, mut workspace, result) = try_run_steps(
+        "class A {
+        public:
+            A();
+            virtual ~A();
+            int x() const;
+        protected:
+            virtual void update(int value);
+            void reset();
+            static int counter(bool flag);
+            int m_value;
+            const char* m_name;
+            const int m_limit;
+        private:
+            void hidden();
+            int m_private;
+        };
+        class B {
+        protected:
+            void reset();
+            int m_value;
+        };",
+        &["cpp_parser", "add_subclass_shims"],
+        |config| config.enable_subclassing(CppPath::from_good_str("A")),
+    );
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let shims = database
+        .ffi_items()
+        .filter_map(|item| item.item.as_subclass_shim_ref())
+        .collect::<Vec<_>>();
+    assert_eq!(shims.len(), 1);
+    let shim = shims[0];
+    assert_eq!(shim.base_class_path, CppPath::from_good_str("A"));
+
+    let protected_functions = shim
+        .protected_functions
+        .iter()
+        .map(|f| f.path.to_cpp_pseudo_code())
+        .collect::<Vec<_>>();
+    assert_eq!(protected_functions, vec!["A::reset", "A::counter"]);
+    let protected_fields = shim
+        .protected_fields
+        .iter()
+        .map(|f| f.path.to_cpp_pseudo_code())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        protected_fields,
+        vec!["A::m_value", "A::m_name", "A::m_limit"]
+    );
+
+    let code = cpp_code_generator::subclass_shim(&database, shim).unwrap();
+    assert!(code.contains("void reset() {\n        return A::reset();\n    }"));
+    assert!(
+        code.contains("static int counter(bool arg0) {\n        return A::counter(arg0);\n    }")
+    );
+    assert!(code.contains("int m_value() const {\n        return A::m_value;\n    }"));
+    assert!(code.contains("void set_m_value(int value) {\n        A::m_value = value;\n    }"));
+    assert!(code.contains("const char* m_name() const"));
+    assert!(!code.contains("set_m_limit"));
+    assert!(!code.contains("hidden"));
+    assert!(!code.contains("m_private"));
+}
+
+#[test]
+fn flags_templates() {
+    let (dir, mut workspace, result) = try_run_steps(
+        "template<typename E> class Flags {
+        public:
+            Flags(int value) : v(value) {}
+            operator int() const { return v; }
+        private:
+            int v;
+        };
+        enum Mode { Read = 1, Write = 2 };
+        void setMode(Flags<Mode> mode);
+        Flags<Mode> mode();",
+        &[
+            "cpp_parser",
+            "find_template_instantiations",
+            "cpp_ffi_generator",
+            "fake_cpp_checker",
+            "rust_generator",
+        ],
+        |config| {
+            add_fake_cpp_checker(config);
+            config.set_flags_template("Flags").unwrap();
+            assert!(config.set_flags_template("Flags").is_err());
+            assert!(config.set_flags_template("Other<int>").is_err());
+        },
+    );
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+
+    let flags_type = database
+        .find_rust_item(&RustPath::from_good_str("A::Flags"))
+        .unwrap();
+    match &flags_type.item.as_struct_ref().unwrap().kind {
+        RustStructKind::FlagsType(flags_type) => {
+            assert_eq!(flags_type.cpp_path, CppPath::from_good_str("Flags"));
+        }
+        other => panic!("unexpected struct kind: {:?}", other),
+    }
+
+    // flags are passed as `int` across FFI
+    let ffi_functions = database
+        .ffi_items()
+        .filter_map(|item| item.item.as_function_ref())
+        .collect::<Vec<_>>();
+    let set_mode = ffi_functions
+        .iter()
+        .find(|f| f.path.last().name.contains("setMode"))
+        .unwrap();
+    assert_eq!(
+        set_mode.arguments[0].argument_type.conversion(),
+        &CppToFfiTypeConversion::QFlagsToInt
+    );
+    assert_eq!(
+        set_mode.arguments[0].argument_type.ffi_type(),
+        &CppType::BuiltInNumeric(CppBuiltInNumericType::Int)
+    );
+
+    let expected_type = RustType::Common(RustCommonType {
+        path: RustPath::from_good_str("A::Flags"),
+        generic_arguments: Some(vec![RustType::Common(RustCommonType {
+            path: RustPath::from_good_str("A::Mode"),
+            generic_arguments: None,
+        })]),
+    });
+    let rust_function = |name: &str| {
+        database
+            .find_rust_item(&RustPath::from_good_str(name))
+            .unwrap()
+            .item
+            .as_function_ref()
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(
+        rust_function("A::set_mode").arguments[0]
+            .argument_type
+            .api_type(),
+        &expected_type
+    );
+    assert_eq!(
+        rust_function("A::mode").return_type.api_type(),
+        &expected_type
+    );
+
+    let flag_enum_impl = database
+        .rust_items()
+        .filter_map(|item| item.item.as_extra_impl_ref())
+        .find_map(|item| match &item.kind {
+            RustExtraImplKind::FlagEnum(data) => Some(data.clone()),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(flag_enum_impl.enum_path, RustPath::from_good_str("A::Mode"));
+    assert_eq!(
+        flag_enum_impl.flags_path,
+        RustPath::from_good_str("A::Flags")
+    );
+
+    let mut config = fake_lib_config(dir.path());
+    config.set_flags_template("Flags").unwrap();
+    let output_path = dir.path().join("generated");
+    rust_code_generator::generate(&database, &config, &RustFeatures::default(), &output_path)
+        .unwrap();
+    let mut code = String::new();
+    for entry in fs::read_dir(&output_path).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_file() {
+            code += &fs::read_to_string(path).unwrap();
+        }
+    }
+    assert!(code.contains("pub struct Flags<E> {"));
+    assert!(code.contains("impl<E> ::std::ops::Not for Flags<E> {"));
+    assert!(code.contains("pub fn to_raw(self) -> ::std::os::raw::c_int {"));
+    assert!(code.contains("std::ops::BitOr<T> for crate::Mode {"));
+    assert!(code.contains("::std::os::raw::c_int::from(mode)"));
+}
+
+#[test]
+fn total_order_impls() {
+    let code = "
+        class Version {
+        public:
+            bool operator==(const Version& other) const;
+            bool operator!=(const Version& other) const;
+            bool operator<(const Version& other) const;
+            bool operator<=(const Version& other) const;
+            bool operator>(const Version& other) const;
+            bool operator>=(const Version& other) const;
+        };
+        class Point {};
+        bool operator==(const Point& a, const Point& b);
+        bool operator!=(const Point& a, const Point& b);
+        bool operator<(const Point& a, const Point& b);
+        bool operator<=(const Point& a, const Point& b);
+        bool operator>(const Point& a, const Point& b);
+        bool operator>=(const Point& a, const Point& b);
+        class Partial {
+        public:
+            bool operator==(const Partial& other) const;
+            bool operator<(const Partial& other) const;
+            bool operator>(const Partial& other) const;
+        };";
+    let total_order_types = |enable: bool| {
+        let (dir, mut workspace, result) = try_run_steps(code, RUST_GENERATOR_STEPS, |config| {
+            add_fake_cpp_checker(config);
+            config.set_total_order_impls(enable);
+        });
+        result.unwrap();
+        let database = workspace
+            .get_database_client("A", &[], true, false)
+            .unwrap();
+        let types = database
+            .rust_items()
+            .filter_map(|item| item.item.as_extra_impl_ref())
+            .filter_map(|item| match &item.kind {
+                RustExtraImplKind::TotalOrder(data) => Some(data.clone()),
+                _ => None,
+            })
+            .sorted_by_key(|data| data.target_path.full_name(None))
+            .collect_vec();
+        (dir, database, types)
+    };
+
+    let (_dir, _database, types) = total_order_types(false);
+    assert!(types.is_empty());
+
+    let (dir, database, types) = total_order_types(true);
+    // `Partial` doesn't declare all six operators
+    assert_eq!(
+        types
+            .iter()
+            .map(|data| data.target_path.clone())
+            .collect_vec(),
+        vec![
+            RustPath::from_good_str("A::Point"),
+            RustPath::from_good_str("A::Version"),
+        ]
+    );
+    for data in &types {
+        assert_eq!(
+            data.other_type,
+            RustType::Common(RustCommonType {
+                path: RustPath::from_good_str("cpp_core::Ref"),
+                generic_arguments: Some(vec![RustType::Common(RustCommonType {
+                    path: data.target_path.clone(),
+                    generic_arguments: None,
+                })]),
+            })
+        );
+    }
+
+    let config = fake_lib_config(dir.path());
+    let output_path = dir.path().join("generated");
+    rust_code_generator::generate(&database, &config, &RustFeatures::default(), &output_path)
+        .unwrap();
+    let mut code = String::new();
+    for entry in fs::read_dir(&output_path).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_file() {
+            code += &fs::read_to_string(path).unwrap();
+        }
+    }
+    assert!(code.contains("impl ::std::cmp::Eq for crate::Version {}"));
+    assert!(code.contains("impl ::std::cmp::Ord for crate::Point {"));
+    assert!(code.contains("<Self as ::cpp_core::cmp::Lt<::cpp_core::Ref<crate::Version>>>::lt("));
+    assert!(!code.contains("impl ::std::cmp::Ord for crate::Partial {"));
+}
+
+#[test]
+fn deref_to_first_public_base() {
+    let (_dir, _config, database) = run_rust_generator_with_steps(
+        "class Hidden {};
+        class First {};
+        class Second {};
+        class Derived : private Hidden, public First, public Second {};",
+        &[
+            "cpp_parser",
+            "cpp_casts",
+            "cpp_ffi_generator",
+            "fake_cpp_checker",
+            "rust_generator",
+        ],
+        |_| {},
+    );
+    let derived_type = RustType::Common(RustCommonType {
+        path: RustPath::from_good_str("A::Derived"),
+        generic_arguments: None,
+    });
+    let first_type = RustType::Common(RustCommonType {
+        path: RustPath::from_good_str("A::First"),
+        generic_arguments: None,
+    });
+    let trait_impls = database
+        .rust_items()
+        .filter_map(|item| item.item.as_trait_impl_ref())
+        .filter(|item| item.target_type == derived_type)
+        .cloned()
+        .collect_vec();
+
+    let deref_targets = trait_impls
+        .iter()
+        .filter(|item| item.is_impl_of("std::ops::Deref"))
+        .map(|item| item.associated_type("Target").unwrap().clone())
+        .collect_vec();
+    assert_eq!(deref_targets, vec![first_type.clone()]);
+
+    let deref_mut_functions = trait_impls
+        .iter()
+        .filter(|item| item.is_impl_of("std::ops::DerefMut"))
+        .map(|item| item.functions[0].clone())
+        .collect_vec();
+    assert_eq!(deref_mut_functions.len(), 1);
+    let deref_mut = &deref_mut_functions[0];
+    assert_eq!(
+        deref_mut.path,
+        RustPath::from_good_str("std::ops::DerefMut::deref_mut")
+    );
+    assert_eq!(
+        deref_mut.arguments[0].argument_type.api_type(),
+        &RustType::new_reference(false, derived_type.clone())
+    );
+    assert_eq!(
+        deref_mut.return_type.api_type(),
+        &RustType::new_reference(false, first_type)
+    );
+
+    let upcast_targets = trait_impls
+        .iter()
+        .filter(|item| item.is_impl_of("cpp_core::StaticUpcast"))
+        .map(|item| {
+            let args = item.trait_type.generic_arguments.as_ref().unwrap();
+            if let RustType::Common(common) = &args[0] {
+                common.path.full_name(None)
+            } else {
+                panic!("unexpected StaticUpcast argument: {:?}", args[0]);
+            }
+        })
+        .sorted()
+        .collect_vec();
+    // the private base is not accessible
+    assert_eq!(upcast_targets, vec!["::A::First", "::A::Second"]);
+
+    let base_casts = database
+        .rust_items()
+        .filter_map(|item| item.item.as_extra_impl_ref())
+        .filter_map(|item| match &item.kind {
+            RustExtraImplKind::BaseCast(data) => Some((
+                data.target_path.full_name(None),
+                data.base_path.full_name(None),
+                data.name.clone(),
+            )),
+            _ => None,
+        })
+        .collect_vec();
+    // `First` is available through `Deref` instead
+    assert_eq!(
+        base_casts,
+        vec![(
+            "::A::Derived".to_string(),
+            "::A::Second".to_string(),
+            "as_second".to_string()
+        )]
+    );
+}
+
+#[test]
+fn subclass_shim_functions() {
+    let (_dir, mut workspace, result) = try_run_steps(
+        "class Shape {
+        public:
+            Shape();
+            virtual ~Shape();
+            virtual double area() const = 0;
+            virtual void move(int dx);
+            int id() const;
+        };
+        class Plain {
+        public:
+            virtual ~Plain();
+            virtual void update();
+        };",
+        &[
+            "cpp_parser",
+            "add_subclass_shims",
+            "cpp_ffi_generator",
+            "fake_cpp_checker",
+            "cpp_parser_stage2",
+            "cpp_ffi_generator_stage2",
+            "fake_cpp_checker",
+            "rust_generator",
+        ],
+        |config| {
+            add_fake_cpp_checker(config);
+            config
+                .processing_steps_mut()
+                .set_prerequisites("cpp_parser_stage2", &["fake_cpp_checker"])
+                .unwrap();
+            config.enable_subclassing(CppPath::from_good_str("Shape"));
+        },
+    );
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+
+    // only classes enabled with `enable_subclassing` get a shim
+    let shims = database
+        .ffi_items()
+        .filter_map(|item| item.item.as_subclass_shim_ref())
+        .collect_vec();
+    assert_eq!(shims.len(), 1);
+    let shim = shims[0];
+    assert_eq!(shim.base_class_path, CppPath::from_good_str("Shape"));
+    assert_eq!(shim.class_path, CppPath::from_good_str("ShapeSubclass"));
+    assert!(!database
+        .cpp_items()
+        .filter_map(|item| item.item.as_type_ref())
+        .any(|t| t.path == CppPath::from_good_str("PlainSubclass")));
+
+    let ffi_sources = database
+        .ffi_items()
+        .filter(|item| item.item.as_function_ref().is_some())
+        .filter_map(|item| database.source_cpp_item(&item.id).unwrap())
+        .filter_map(|item| item.item.as_function_ref())
+        .map(|function| function.path.to_cpp_pseudo_code())
+        .collect::<HashSet<_>>();
+    assert!(ffi_sources.contains("ShapeSubclass::set_area_override"));
+    assert!(ffi_sources.contains("ShapeSubclass::set_move_override"));
+    assert!(ffi_sources.contains("ShapeSubclass::base_move"));
+    // a pure virtual function has no base implementation
+    assert!(!ffi_sources.contains("ShapeSubclass::base_area"));
+    // non-virtual functions can't be overridden
+    assert!(!ffi_sources.contains("ShapeSubclass::set_id_override"));
+
+    // a pure virtual function without an override terminates the process
+    let code = cpp_code_generator::subclass_shim(&database, shim).unwrap();
+    assert!(code.contains("ritual::pure_virtual_called(\"area\");"));
+    assert!(code.contains("return Shape::move(arg0);"));
+
+    let rust_functions = database
+        .rust_items()
+        .filter_map(|item| item.item.as_function_ref())
+        .map(|function| function.path.full_name(None))
+        .collect::<HashSet<_>>();
+    assert!(rust_functions.contains("::A::ShapeSubclass::set_area_override"));
+    assert!(rust_functions.contains("::A::ShapeSubclass::base_move"));
+}
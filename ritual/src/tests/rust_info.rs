@@ -0,0 +1,96 @@
+use crate::rust_info::*;
+use crate::rust_type::*;
+
+fn c_int() -> RustType {
+    RustType::Common(RustCommonType {
+        path: RustPath::from_good_str("std::os::raw::c_int"),
+        generic_arguments: None,
+    })
+}
+
+fn out_argument(name: &str, ffi_index: usize) -> RustOutArgument {
+    RustOutArgument {
+        name: name.to_string(),
+        value_type: c_int(),
+        ffi_index,
+    }
+}
+
+#[test]
+fn api_return_type_out_arguments() {
+    let mut data = RustFfiWrapperData::new(RustPath::from_good_str("A::__ffi::f"));
+    assert_eq!(data.api_return_type(&RustType::bool()), RustType::bool());
+
+    data.out_arguments.push(out_argument("x", 1));
+    assert_eq!(data.api_return_type(&RustType::unit()), c_int());
+    assert_eq!(
+        data.api_return_type(&RustType::bool()),
+        RustType::Tuple(vec![RustType::bool(), c_int()])
+    );
+
+    data.out_arguments.push(out_argument("y", 2));
+    assert_eq!(
+        data.api_return_type(&RustType::unit()),
+        RustType::Tuple(vec![c_int(), c_int()])
+    );
+
+    data.returns_success_flag = true;
+    assert_eq!(
+        data.api_return_type(&RustType::bool()),
+        RustType::new_option(RustType::Tuple(vec![c_int(), c_int()]))
+    );
+}
+
+fn os_raw(name: &str) -> RustType {
+    RustType::Common(RustCommonType {
+        path: RustPath::from_good_str("std::os::raw").join(name),
+        generic_arguments: None,
+    })
+}
+
+fn primitive(name: &str) -> RustType {
+    RustType::Primitive(name.to_string())
+}
+
+#[test]
+fn integer_types_can_be_same() {
+    assert!(c_int().can_be_same_as(&primitive("i32")));
+    assert!(os_raw("c_long").can_be_same_as(&primitive("i64")));
+    assert!(os_raw("c_long").can_be_same_as(&c_int()));
+    assert!(os_raw("c_ulong").can_be_same_as(&os_raw("c_uint")));
+    assert!(os_raw("c_char").can_be_same_as(&os_raw("c_uchar")));
+
+    assert!(!c_int().can_be_same_as(&os_raw("c_short")));
+    assert!(!c_int().can_be_same_as(&os_raw("c_uint")));
+    assert!(!c_int().can_be_same_as(&primitive("u32")));
+    // `c_long` is `i64` on some platforms
+    assert!(os_raw("c_long").can_be_same_as(&os_raw("c_longlong")));
+    assert!(!os_raw("c_double").can_be_same_as(&primitive("f32")));
+}
+
+#[test]
+fn overload_arguments_type() {
+    let mut overload = RustOverload {
+        trait_path: RustPath::from_good_str("A::ArgsForF"),
+        self_type: None,
+        argument_types: Vec::new(),
+        return_type: RustType::unit(),
+        function_path: RustPath::from_good_str("A::f"),
+        is_unsafe: true,
+    };
+    assert_eq!(overload.arguments_type(), RustType::unit());
+
+    overload.argument_types.push(c_int());
+    assert_eq!(overload.arguments_type(), c_int());
+
+    overload.argument_types.push(RustType::bool());
+    assert_eq!(
+        overload.arguments_type(),
+        RustType::Tuple(vec![c_int(), RustType::bool()])
+    );
+
+    let other = RustType::Tuple(vec![os_raw("c_long"), RustType::bool()]);
+    assert!(overload.arguments_type().can_be_same_as(&other));
+    let other = RustType::Tuple(vec![os_raw("c_short"), RustType::bool()]);
+    assert!(!overload.arguments_type().can_be_same_as(&other));
+}
@@ -0,0 +1,169 @@
+use crate::config::{PassBy, RustTypeMapping, TypeAllocationPlace};
+use crate::cpp_data::*;
+use crate::cpp_type::*;
+use crate::database::TypeAllocationSource;
+use crate::rust_type::RustPath;
+use crate::tests::{run_steps, try_run_steps};
+
+#[test]
+fn type_allocation_place_overrides() {
+    let code = "
+        class Point { public: int x; int y; };
+        class Widget { public: virtual ~Widget(); };
+        template<class T> class Box { public: T value; };
+        typedef Box<int> IntBox;
+    ";
+    let (_dir, mut workspace, result) =
+        try_run_steps(code, &["cpp_parser", "type_allocation_places"], |config| {
+            config.set_type_allocation_place(
+                TypeAllocationPlace::Heap,
+                CppPath::from_good_str("Point"),
+            );
+            config.set_type_allocation_place(
+                TypeAllocationPlace::Stack,
+                CppPath::from_good_str("Box"),
+            );
+        });
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+
+    assert_eq!(
+        database.type_allocation_place(&CppPath::from_good_str("Point")),
+        TypeAllocationPlace::Heap
+    );
+    assert_eq!(
+        database.type_allocation_place(&CppPath::from_good_str("Widget")),
+        TypeAllocationPlace::Heap
+    );
+    // the layout of a class template is unknown
+    let box_decision = database
+        .type_allocation_places()
+        .iter()
+        .find(|d| d.path.last().name == "Box")
+        .unwrap();
+    assert_eq!(box_decision.place, TypeAllocationPlace::Heap);
+    assert!(box_decision.reason.contains("layout is unknown"));
+
+    let box_of = |arg: CppBuiltInNumericType| {
+        CppPath::from_item(CppPathItem {
+            name: "Box".into(),
+            template_arguments: Some(vec![CppType::BuiltInNumeric(arg)]),
+        })
+    };
+    let box_int = box_of(CppBuiltInNumericType::Int);
+    let box_double = box_of(CppBuiltInNumericType::Double);
+    assert!(crate::type_allocation_places::override_matches(
+        &CppPath::from_good_str("Box"),
+        &box_int,
+    ));
+    assert!(crate::type_allocation_places::override_matches(
+        &box_int, &box_int,
+    ));
+    assert!(!crate::type_allocation_places::override_matches(
+        &box_int,
+        &box_double,
+    ));
+}
+
+#[test]
+fn type_allocation_heuristic() {
+    let (_dir, mut workspace) = run_steps(
+        "
+        struct Pod { int x; double y; };
+        struct Large { char data[128]; };
+        class Polymorphic { public: virtual ~Polymorphic(); int x; };
+        class Derived : public Polymorphic { public: int y; };
+        class NoCopy {
+        public:
+            NoCopy();
+            NoCopy(const NoCopy& other) = delete;
+            int x;
+        };
+        ",
+        &["cpp_parser", "type_allocation_places"],
+    );
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let decision = |name: &str| {
+        database
+            .type_allocation_places()
+            .iter()
+            .find(|d| d.path == CppPath::from_good_str(name))
+            .unwrap()
+            .clone()
+    };
+
+    let pod = decision("Pod");
+    assert_eq!(pod.place, TypeAllocationPlace::Stack);
+    assert_eq!(pod.source, TypeAllocationSource::Heuristic);
+    assert!(pod.reason.starts_with("trivially copyable"));
+    assert_eq!(pod.layout_check, None);
+
+    let large = decision("Large");
+    assert_eq!(large.place, TypeAllocationPlace::Heap);
+    assert!(large.reason.contains("exceeds the limit"));
+
+    let polymorphic = decision("Polymorphic");
+    assert_eq!(polymorphic.place, TypeAllocationPlace::Heap);
+    assert!(polymorphic.reason.starts_with("has virtual function"));
+
+    let derived = decision("Derived");
+    assert_eq!(derived.place, TypeAllocationPlace::Heap);
+    assert!(derived.reason.contains("inherited from Polymorphic"));
+
+    let no_copy = decision("NoCopy");
+    assert_eq!(no_copy.place, TypeAllocationPlace::Heap);
+    assert_eq!(no_copy.reason, "copy constructor is deleted");
+}
+
+#[test]
+fn type_mappings() {
+    let code = "
+        struct Vector3f {
+            float x, y, z;
+            float length() const;
+        };
+        Vector3f add(Vector3f a, const Vector3f& b);
+    ";
+    let mapping = |size| RustTypeMapping {
+        rust_path: RustPath::from_good_str("mymath::Vec3"),
+        pass_by: PassBy::ByValue,
+        size,
+    };
+    let steps = ["cpp_parser", "type_allocation_places", "cpp_ffi_generator"];
+    let (_dir, mut workspace, result) = try_run_steps(code, &steps, |config| {
+        config.map_type("Vector3f", mapping(Some(12))).unwrap();
+        assert!(config.map_type("Vector3f", mapping(None)).is_err());
+    });
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+    let decision = database
+        .type_allocation_places()
+        .iter()
+        .find(|d| d.path == CppPath::from_good_str("Vector3f"))
+        .unwrap();
+    assert_eq!(decision.place, TypeAllocationPlace::Stack);
+    assert_eq!(decision.source, TypeAllocationSource::TypeMapping);
+    assert_eq!(decision.reason, "mapped to ::mymath::Vec3");
+
+    let ffi_paths = database
+        .ffi_items()
+        .filter_map(|item| item.item.as_function_ref())
+        .map(|function| function.path.to_cpp_pseudo_code())
+        .collect::<Vec<_>>();
+    assert_eq!(ffi_paths.len(), 1);
+    assert!(ffi_paths[0].contains("add"));
+    drop(database);
+
+    let (_dir, _workspace, result) = try_run_steps(code, &steps, |config| {
+        config.map_type("Vector3f", mapping(Some(16))).unwrap();
+    });
+    let message = result.unwrap_err().to_string();
+    assert!(message.contains("mapped to ::mymath::Vec3 of size 16"));
+    assert!(message.contains("size of the C++ class is 12"));
+}
@@ -0,0 +1,81 @@
+use crate::cpp_data::*;
+use crate::tests::try_run_steps;
+
+#[test]
+fn user_hooks() {
+    let (_dir, mut workspace, result) = try_run_steps(
+        "
+        struct Old {};
+        class A {
+        public:
+            void f();
+            void g(int x);
+        };
+        ",
+        &["cpp_parser", "cpp_ffi_generator"],
+        |config| {
+            // delete a method
+            config.add_cpp_data_filter(|editor| {
+                let ids = editor
+                    .db()
+                    .lookup_cpp_name("A::g")?
+                    .iter()
+                    .map(|view| view.id().clone())
+                    .collect::<Vec<_>>();
+                for id in ids {
+                    editor.delete_item(&id)?;
+                }
+                Ok(())
+            });
+            // rename a type
+            config.add_cpp_data_filter(|editor| {
+                let ids = editor
+                    .db()
+                    .lookup_cpp_name("Old")?
+                    .iter()
+                    .map(|view| view.id().clone())
+                    .collect::<Vec<_>>();
+                for id in ids {
+                    editor.modify_cpp_item(&id, |item| {
+                        if let CppItem::Type(data) = item {
+                            data.path = CppPath::from_good_str("New");
+                        }
+                    })?;
+                }
+                Ok(())
+            });
+        },
+    );
+    result.unwrap();
+    let database = workspace
+        .get_database_client("A", &[], true, false)
+        .unwrap();
+
+    assert!(database.lookup_cpp_name("A::g").unwrap().is_empty());
+    assert_eq!(database.lookup_cpp_name("A::f").unwrap().len(), 1);
+    let rejection = database
+        .rejections_for("A::g")
+        .next()
+        .expect("deleted item should be recorded");
+    assert_eq!(rejection.step, "cpp_parser");
+    assert!(rejection.reason.contains("cpp_parser.rs"));
+
+    assert!(database.lookup_cpp_name("Old").unwrap().is_empty());
+    let views = database.lookup_cpp_name("New").unwrap();
+    assert_eq!(views.len(), 1);
+    let modification = views[0].user_modification().unwrap();
+    assert_eq!(modification.step, "cpp_parser");
+    assert!(modification.hook_site.contains("cpp_parser.rs"));
+    assert!(database.lookup_cpp_name("A::f").unwrap()[0]
+        .user_modification()
+        .is_none());
+    drop(database);
+
+    let (_dir, _workspace, result) = try_run_steps("void f();", &["cpp_parser"], |config| {
+        config.add_cpp_data_filter(|_| Ok(()));
+        config.add_cpp_data_filter(|_| panic!("hook failure"));
+    });
+    let message = result.unwrap_err().to_string();
+    assert!(message.contains("panicked: hook failure"));
+    assert!(message.contains("cpp_parser.rs"));
+}
@@ -0,0 +1,42 @@
+use crate::database_diff::{DatabaseDiff, VersionBump};
+use crate::versions::{bump_version, parse_version, VersionSuggestion};
+
+fn bump(version: &str, bump: VersionBump) -> String {
+    bump_version(&parse_version(version).unwrap(), bump).to_string()
+}
+
+#[test]
+fn bump_versions() {
+    assert_eq!(bump("1.2.3", VersionBump::Major), "2.0.0");
+    assert_eq!(bump("1.2.3", VersionBump::Minor), "1.3.0");
+    assert_eq!(bump("1.2.3", VersionBump::Patch), "1.2.4");
+
+    assert_eq!(bump("0.2.3", VersionBump::Major), "0.3.0");
+    assert_eq!(bump("0.2.3", VersionBump::Minor), "0.2.4");
+    assert_eq!(bump("0.2.3", VersionBump::Patch), "0.2.4");
+
+    assert_eq!(bump("0.0.3", VersionBump::Major), "0.0.4");
+    assert_eq!(bump("0.0.3", VersionBump::Minor), "0.0.4");
+
+    assert_eq!(bump("1.0.0-beta.1", VersionBump::Minor), "1.1.0");
+    assert!(parse_version("1.0").is_err());
+}
+
+#[test]
+fn check_version() {
+    let suggestion = VersionSuggestion {
+        previous: parse_version("0.2.3").unwrap(),
+        suggested: parse_version("0.3.0").unwrap(),
+        diff: DatabaseDiff::default(),
+        is_documentation_changed: false,
+    };
+    suggestion.check("0.3.0").unwrap();
+    suggestion.check("1.0.0").unwrap();
+    let err = suggestion.check("0.2.4").unwrap_err().to_string();
+    assert!(err.contains("0.3.0"));
+    assert!(suggestion.check("0.3").is_err());
+
+    let text = suggestion.to_text();
+    assert!(text.contains("Previous version: 0.2.3"));
+    assert!(text.contains("Suggested version: 0.3.0"));
+}
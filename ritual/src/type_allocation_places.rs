@@ -1,15 +1,24 @@
 #![allow(dead_code)]
 
-use crate::config::MovableTypesHookOutput;
-use crate::cpp_data::{CppItem, CppPath};
+use crate::config::{
+    Config, MovableTypesHookOutput, PassBy, RustTypeMapping, TypeAllocationPlace,
+    TypeLayoutCheckMode, TypeLayoutMismatchAction,
+};
+use crate::cpp_checker::{LocalCppChecker, Snippet, SnippetTask};
+use crate::cpp_data::{
+    CppClassLayout, CppItem, CppPath, CppTypeDeclaration, CppTypeDeclarationKind,
+};
 use crate::cpp_type::{CppPointerLikeTypeKind, CppType};
+use crate::database::{TypeAllocationDecision, TypeAllocationSource};
 use crate::processor::ProcessorData;
-use log::{info, trace};
-use ritual_common::errors::Result;
-use std::collections::HashMap;
+use itertools::Itertools;
+use log::{debug, info, trace, warn};
+use ritual_common::errors::{bail, Result};
+use std::collections::{HashMap, HashSet};
 
 #[derive(Default, Debug)]
 struct TypeStats {
+    has_deleted_copy_constructor: bool,
     virtual_functions: Vec<String>,
     pointer_encounters: Vec<String>,
     non_pointer_encounters: Vec<String>,
@@ -21,7 +30,9 @@ fn log_results(data_map: &HashMap<CppPath, TypeStats>) {
     }
 
     for (path, stats) in data_map {
-        let suggestion = if stats.virtual_functions.is_empty() {
+        let suggestion = if stats.has_deleted_copy_constructor {
+            "immovable (copy constructor is deleted)"
+        } else if stats.virtual_functions.is_empty() {
             if stats.pointer_encounters.is_empty() {
                 if stats.non_pointer_encounters.len() == MAX_ITEMS {
                     "movable (no pointers, no virtual functions)"
@@ -128,6 +139,13 @@ pub fn suggest_allocation_places(data: &mut ProcessorData<'_>) -> Result<()> {
             continue;
         }
         if let CppItem::Function(function) = &item.item {
+            if function.is_deleted() && function.is_copy_constructor() {
+                let good_path = function.class_path()?.deinstantiate();
+                if let Some(stats) = data_map.get_mut(&good_path) {
+                    stats.has_deleted_copy_constructor = true;
+                }
+                continue;
+            }
             if function.is_private() {
                 continue;
             }
@@ -152,3 +170,451 @@ pub fn suggest_allocation_places(data: &mut ProcessorData<'_>) -> Result<()> {
 
     Ok(())
 }
+
+/// Checks if an override set for `override_path` applies to class `path`.
+pub fn override_matches(override_path: &CppPath, path: &CppPath) -> bool {
+    if override_path == path {
+        return true;
+    }
+    let has_template_arguments = override_path
+        .items()
+        .iter()
+        .any(|item| item.template_arguments.is_some());
+    !has_template_arguments
+        && override_path.to_templateless_string() == path.to_templateless_string()
+}
+
+/// Returns the allocation place override for class `path`, if any.
+fn allocation_place_override(config: &Config, path: &CppPath) -> Option<TypeAllocationPlace> {
+    config
+        .type_allocation_places()
+        .iter()
+        .find(|(override_path, _)| override_matches(override_path, path))
+        .map(|(_, place)| *place)
+}
+
+/// Properties of a class relevant for relocating its objects
+#[derive(Default, Debug)]
+struct RelocationInfo {
+    /// Description of the first virtual function of the class
+    virtual_function: Option<String>,
+    /// Description of a problem with copy or move constructors of the class
+    constructor_problem: Option<String>,
+    /// Base classes and whether they are virtual
+    bases: Vec<(CppPath, bool)>,
+}
+
+/// Collects relocation info of all known classes.
+fn collect_relocation_info(data: &ProcessorData<'_>) -> HashMap<CppPath, RelocationInfo> {
+    let mut map = HashMap::<CppPath, RelocationInfo>::new();
+    for item in data.db.all_cpp_items() {
+        match &item.item {
+            CppItem::Function(function) => {
+                let class_path = match function.class_path() {
+                    Ok(path) => path,
+                    Err(_) => continue,
+                };
+                let info = map.entry(class_path).or_default();
+                if function.is_virtual() && info.virtual_function.is_none() {
+                    info.virtual_function = Some(function.short_text());
+                }
+                let constructor = if function.is_copy_constructor() {
+                    "copy constructor"
+                } else if function.is_move_constructor() {
+                    "move constructor"
+                } else {
+                    continue;
+                };
+                if info.constructor_problem.is_none() {
+                    if function.is_deleted() {
+                        info.constructor_problem = Some(format!("{} is deleted", constructor));
+                    } else if function.is_private() {
+                        info.constructor_problem = Some(format!("{} is private", constructor));
+                    }
+                }
+            }
+            CppItem::ClassBase(base) => {
+                map.entry(base.derived_class_type.clone())
+                    .or_default()
+                    .bases
+                    .push((base.base_class_type.clone(), base.is_virtual));
+            }
+            _ => {}
+        }
+    }
+    map
+}
+
+/// Returns the reason why objects of class `path` can't be moved in memory
+/// by a plain byte copy, or `None` if no such reason was found.
+fn relocation_blocker(
+    info_map: &HashMap<CppPath, RelocationInfo>,
+    path: &CppPath,
+) -> Option<String> {
+    if let Some(problem) = info_map
+        .get(path)
+        .and_then(|i| i.constructor_problem.clone())
+    {
+        return Some(problem);
+    }
+    let mut visited = HashSet::new();
+    let mut pending = vec![path.clone()];
+    while let Some(class_path) = pending.pop() {
+        if !visited.insert(class_path.clone()) {
+            continue;
+        }
+        let info = match info_map.get(&class_path) {
+            Some(info) => info,
+            None => continue,
+        };
+        let inherited = if &class_path == path {
+            String::new()
+        } else {
+            format!(" (inherited from {})", class_path.to_cpp_pseudo_code())
+        };
+        if let Some(function) = &info.virtual_function {
+            return Some(format!("has virtual function {}{}", function, inherited));
+        }
+        for (base, is_virtual) in &info.bases {
+            if *is_virtual {
+                return Some(format!(
+                    "has virtual base {}{}",
+                    base.to_cpp_pseudo_code(),
+                    inherited
+                ));
+            }
+            pending.push(base.clone());
+        }
+    }
+    None
+}
+
+/// Chooses the allocation place of class `type1` when no override applies.
+/// Returns the place and the explanation of the decision.
+fn heuristic_allocation_place(
+    config: &Config,
+    info_map: &HashMap<CppPath, RelocationInfo>,
+    type1: &CppTypeDeclaration,
+) -> (TypeAllocationPlace, String) {
+    if let Some(reason) = relocation_blocker(info_map, &type1.path) {
+        return (TypeAllocationPlace::Heap, reason);
+    }
+    let layout = match &type1.kind {
+        CppTypeDeclarationKind::Class {
+            layout: Some(layout),
+            ..
+        } => layout,
+        _ => {
+            return (TypeAllocationPlace::Heap, "layout is unknown".into());
+        }
+    };
+    if !layout.is_pod {
+        return (
+            TypeAllocationPlace::Heap,
+            "not known to be trivially copyable".into(),
+        );
+    }
+    let max_size = config.max_stack_allocated_size();
+    if layout.size > max_size {
+        return (
+            TypeAllocationPlace::Heap,
+            format!(
+                "size ({} bytes) exceeds the limit ({} bytes)",
+                layout.size, max_size
+            ),
+        );
+    }
+    (
+        TypeAllocationPlace::Stack,
+        format!("trivially copyable, {} bytes", layout.size),
+    )
+}
+
+/// Records the allocation place of each class of the current crate.
+/// `Config::set_type_allocation_place` overrides take priority; other
+/// classes are stack-allocated only if they are small trivially copyable types.
+pub fn run(data: &mut ProcessorData<'_>) -> Result<()> {
+    let classes = data
+        .db
+        .cpp_items()
+        .filter_map(|item| item.item.as_type_ref())
+        .filter(|t| t.kind.is_class())
+        .cloned()
+        .collect_vec();
+    let info_map = collect_relocation_info(data);
+    let layouts: HashMap<CppPath, CppClassLayout> = classes
+        .iter()
+        .filter_map(|type1| match &type1.kind {
+            CppTypeDeclarationKind::Class {
+                layout: Some(layout),
+                ..
+            } => Some((type1.path.clone(), layout.clone())),
+            _ => None,
+        })
+        .collect();
+
+    let mut decisions = Vec::new();
+    for type1 in classes {
+        if let Some(mapping) = data.config.type_mapping(&type1.path) {
+            let decision = type_mapping_decision(&info_map, &layouts, &type1.path, mapping)?;
+            decisions.push(decision);
+            continue;
+        }
+        let override_place = allocation_place_override(data.config, &type1.path);
+        if data.config.is_opaque(&type1.path) {
+            // opaque types are only used through pointers
+            if override_place == Some(TypeAllocationPlace::Stack) {
+                warn!(
+                    "{} is configured as stack-allocated, but it's opaque, \
+                     using heap allocation",
+                    type1.path.to_cpp_pseudo_code()
+                );
+            }
+            decisions.push(TypeAllocationDecision {
+                path: type1.path,
+                place: TypeAllocationPlace::Heap,
+                source: TypeAllocationSource::Override,
+                reason: "opaque type".into(),
+                layout_check: None,
+                env_layouts: Vec::new(),
+            });
+            continue;
+        }
+        if override_place.is_none() {
+            // decisions made when merging databases can't be recomputed
+            // because layouts of other environments are unknown here
+            let is_conflict = data.db.type_allocation_places().iter().any(|decision| {
+                decision.path == type1.path
+                    && decision.source == TypeAllocationSource::EnvironmentConflict
+            });
+            if is_conflict {
+                continue;
+            }
+        }
+        let decision = match override_place {
+            Some(TypeAllocationPlace::Stack) if !layouts.contains_key(&type1.path) => {
+                // the size of the wrapper type can't be verified
+                warn!(
+                    "{} is configured as stack-allocated, but its layout is unknown, \
+                     using heap allocation",
+                    type1.path.to_cpp_pseudo_code()
+                );
+                TypeAllocationDecision {
+                    path: type1.path,
+                    place: TypeAllocationPlace::Heap,
+                    source: TypeAllocationSource::Override,
+                    reason: "set in config, but layout is unknown".into(),
+                    layout_check: None,
+                    env_layouts: Vec::new(),
+                }
+            }
+            Some(place) => {
+                if place == TypeAllocationPlace::Stack {
+                    if let Some(reason) = relocation_blocker(&info_map, &type1.path) {
+                        warn!(
+                            "{} is configured as stack-allocated, but it {}",
+                            type1.path.to_cpp_pseudo_code(),
+                            reason
+                        );
+                    }
+                }
+                TypeAllocationDecision {
+                    path: type1.path,
+                    place,
+                    source: TypeAllocationSource::Override,
+                    reason: "set in config".into(),
+                    layout_check: None,
+                    env_layouts: Vec::new(),
+                }
+            }
+            None => {
+                let (place, reason) = heuristic_allocation_place(data.config, &info_map, &type1);
+                TypeAllocationDecision {
+                    path: type1.path,
+                    place,
+                    source: TypeAllocationSource::Heuristic,
+                    reason,
+                    layout_check: None,
+                    env_layouts: Vec::new(),
+                }
+            }
+        };
+        trace!(
+            "allocation place of {}: {:?} ({:?}: {})",
+            decision.path.to_cpp_pseudo_code(),
+            decision.place,
+            decision.source,
+            decision.reason
+        );
+        decisions.push(decision);
+    }
+
+    let mismatches = if let Some((mode, on_mismatch)) = data.config.type_layout_check() {
+        check_layouts(data, &layouts, &mut decisions, mode, on_mismatch)?
+    } else {
+        Vec::new()
+    };
+
+    for decision in decisions {
+        data.db.set_type_allocation_place(decision);
+    }
+    if !mismatches.is_empty() {
+        bail!(
+            "layouts of stack-allocated classes don't match the C++ compiler: {}",
+            mismatches.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Returns the allocation place of class `path` mapped to a Rust type.
+/// Fails if the size of the class doesn't match the size declared in the mapping.
+fn type_mapping_decision(
+    info_map: &HashMap<CppPath, RelocationInfo>,
+    layouts: &HashMap<CppPath, CppClassLayout>,
+    path: &CppPath,
+    mapping: &RustTypeMapping,
+) -> Result<TypeAllocationDecision> {
+    let rust_name = mapping.rust_path.full_name(None);
+    if let Some(size) = mapping.size {
+        match layouts.get(path) {
+            Some(layout) if layout.size == size => {}
+            Some(layout) => bail!(
+                "{} is mapped to {} of size {}, but the size of the C++ class is {}",
+                path.to_cpp_pseudo_code(),
+                rust_name,
+                size,
+                layout.size
+            ),
+            None => bail!(
+                "{} is mapped to {} of size {}, but the size of the C++ class is unknown",
+                path.to_cpp_pseudo_code(),
+                rust_name,
+                size
+            ),
+        }
+    }
+    let place = match mapping.pass_by {
+        PassBy::ByValue => {
+            if let Some(reason) = relocation_blocker(info_map, path) {
+                warn!(
+                    "{} is mapped to {} and passed by value, but it {}",
+                    path.to_cpp_pseudo_code(),
+                    rust_name,
+                    reason
+                );
+            }
+            TypeAllocationPlace::Stack
+        }
+        PassBy::ByPointer => TypeAllocationPlace::Heap,
+    };
+    Ok(TypeAllocationDecision {
+        path: path.clone(),
+        place,
+        source: TypeAllocationSource::TypeMapping,
+        reason: format!("mapped to {}", rust_name),
+        layout_check: None,
+        env_layouts: Vec::new(),
+    })
+}
+
+/// Returns C++ expressions that are true if the C++ compiler agrees
+/// with `layout` of class `path`.
+pub fn layout_conditions(path: &CppPath, layout: &CppClassLayout) -> Result<Vec<String>> {
+    let name = path.to_cpp_code()?;
+    let mut conditions = vec![format!("sizeof({}) == {}", name, layout.size)];
+    if let Some(align) = layout.align {
+        conditions.push(format!("alignof({}) == {}", name, align));
+    }
+    Ok(conditions)
+}
+
+/// Returns the code that verifies that the C++ compiler agrees with `layout`
+/// of class `path`.
+fn layout_check_snippet(
+    path: &CppPath,
+    layout: &CppClassLayout,
+    mode: TypeLayoutCheckMode,
+) -> Result<Snippet> {
+    let conditions = layout_conditions(path, layout)?;
+    let snippet = match mode {
+        TypeLayoutCheckMode::Run => Snippet::new_in_main(
+            conditions
+                .iter()
+                .map(|condition| format!("ritual_assert({});", condition))
+                .join("\n"),
+            false,
+        ),
+        TypeLayoutCheckMode::StaticAssert => Snippet::new_global(
+            conditions
+                .iter()
+                .map(|condition| format!("static_assert({}, \"layout mismatch\");", condition))
+                .join("\n"),
+            false,
+        ),
+    };
+    Ok(snippet)
+}
+
+/// Compares layouts of stack-allocated classes with the C++ compiler
+/// and records the results in `decisions`. Returns the classes
+/// that failed the check and were not moved to the heap.
+fn check_layouts(
+    data: &mut ProcessorData<'_>,
+    layouts: &HashMap<CppPath, CppClassLayout>,
+    decisions: &mut [TypeAllocationDecision],
+    mode: TypeLayoutCheckMode,
+    on_mismatch: TypeLayoutMismatchAction,
+) -> Result<Vec<String>> {
+    let mut tasks = Vec::new();
+    for (index, decision) in decisions.iter().enumerate() {
+        if decision.place != TypeAllocationPlace::Stack {
+            continue;
+        }
+        if let Some(layout) = layouts.get(&decision.path) {
+            tasks.push(SnippetTask {
+                snippet: layout_check_snippet(&decision.path, layout, mode)?,
+                output: None,
+                data: index,
+            });
+        } else {
+            debug!(
+                "layout of {} is unknown, skipping layout check",
+                decision.path.to_cpp_pseudo_code()
+            );
+        }
+    }
+    if tasks.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    info!("Checking layouts of {} classes", tasks.len());
+    let mut checker =
+        LocalCppChecker::new(data.workspace.tmp_path().join("cpp_checker"), data.config)?;
+    checker.set_run_programs(mode == TypeLayoutCheckMode::Run);
+    let mut instance = checker.get("layouts")?;
+    instance.binary_check(&mut tasks, None)?;
+
+    let mut mismatches = Vec::new();
+    for task in tasks {
+        let decision = &mut decisions[task.data];
+        let is_success = task.output.map_or(false, |output| output.is_success());
+        decision.layout_check = Some(is_success);
+        if !is_success {
+            match on_mismatch {
+                TypeLayoutMismatchAction::Fail => {
+                    mismatches.push(decision.path.to_cpp_pseudo_code());
+                }
+                TypeLayoutMismatchAction::UseHeap => {
+                    warn!(
+                        "layout of {} doesn't match the C++ compiler, using heap allocation",
+                        decision.path.to_cpp_pseudo_code()
+                    );
+                    decision.place = TypeAllocationPlace::Heap;
+                    decision.reason = "layout check failed".into();
+                }
+            }
+        }
+    }
+    Ok(mismatches)
+}
@@ -0,0 +1,190 @@
+//! User-defined hooks that make small changes to the processed data,
+//! e.g. delete an overload or rename a type.
+//!
+//! Hooks are registered in `Config` and run by the processing steps,
+//! so their changes are applied again on each regeneration.
+
+use crate::cpp_data::CppItem;
+use crate::cpp_ffi_data::CppFfiItem;
+use crate::database::{DatabaseClient, DatabaseItemData, ItemId, Rejection, UserModification};
+use crate::processor::ProcessorData;
+use crate::rust_info::RustItem;
+use log::trace;
+use ritual_common::errors::{bail, err_msg, Result, ResultExt};
+use std::any::Any;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe, Location};
+
+/// Function of a user-defined hook
+pub type UserHookFunction = dyn Fn(&mut ItemEditor<'_>) -> Result<()> + 'static;
+
+/// A user-defined hook and the place where it was registered
+pub struct UserHook {
+    function: Box<UserHookFunction>,
+    site: &'static Location<'static>,
+}
+
+impl UserHook {
+    pub fn new(
+        function: impl Fn(&mut ItemEditor<'_>) -> Result<()> + 'static,
+        site: &'static Location<'static>,
+    ) -> Self {
+        UserHook {
+            function: Box::new(function),
+            site,
+        }
+    }
+
+    /// Returns the source location where the hook was registered.
+    pub fn site(&self) -> &'static Location<'static> {
+        self.site
+    }
+}
+
+impl fmt::Debug for UserHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserHook")
+            .field("site", &self.site)
+            .finish()
+    }
+}
+
+/// Allows a hook to change items of the current crate.
+/// All changes are recorded in the database, so that the changed items
+/// can be told apart from the items produced by ritual.
+pub struct ItemEditor<'a> {
+    db: &'a mut DatabaseClient,
+    step: &'a str,
+    site: &'static Location<'static>,
+}
+
+impl ItemEditor<'_> {
+    /// Returns the database. Items should only be changed
+    /// with the methods of `ItemEditor`.
+    pub fn db(&self) -> &DatabaseClient {
+        self.db
+    }
+
+    /// Returns name of the processing step running the hook.
+    pub fn step(&self) -> &str {
+        self.step
+    }
+
+    fn modify(
+        &mut self,
+        id: &ItemId,
+        function: impl FnOnce(&mut DatabaseItemData) -> Result<()>,
+    ) -> Result<()> {
+        function(self.db.item_mut(id)?.item)?;
+        trace!("[{}] item modified by hook: {}", self.step, id);
+        self.db.set_user_modification(
+            id,
+            UserModification {
+                step: self.step.to_string(),
+                hook_site: self.site.to_string(),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn modify_cpp_item(
+        &mut self,
+        id: &ItemId,
+        function: impl FnOnce(&mut CppItem),
+    ) -> Result<()> {
+        self.modify(id, |item| {
+            let item = item
+                .as_cpp_item_mut()
+                .ok_or_else(|| err_msg("not a cpp item"))?;
+            function(item);
+            Ok(())
+        })
+    }
+
+    pub fn modify_ffi_item(
+        &mut self,
+        id: &ItemId,
+        function: impl FnOnce(&mut CppFfiItem),
+    ) -> Result<()> {
+        self.modify(id, |item| {
+            let item = item
+                .as_ffi_item_mut()
+                .ok_or_else(|| err_msg("not a ffi item"))?;
+            function(item);
+            Ok(())
+        })
+    }
+
+    pub fn modify_rust_item(
+        &mut self,
+        id: &ItemId,
+        function: impl FnOnce(&mut RustItem),
+    ) -> Result<()> {
+        self.modify(id, |item| {
+            let item = item
+                .as_rust_item_mut()
+                .ok_or_else(|| err_msg("not a rust item"))?;
+            function(item);
+            Ok(())
+        })
+    }
+
+    /// Deletes item `id` and all items generated from it.
+    /// The deletion is recorded as a rejection of the current step.
+    pub fn delete_item(&mut self, id: &ItemId) -> Result<()> {
+        if id.crate_name() != self.db.crate_name() {
+            bail!("can't delete item of dependency");
+        }
+        let cpp_name = self.db.item(id)?.item.short_text();
+        let include_file = self
+            .db
+            .root_item_origin(id)?
+            .map(|origin| origin.include_file.clone());
+        self.db.add_rejection(Rejection {
+            cpp_name,
+            step: self.step.to_string(),
+            reason: format!("deleted by hook registered at {}", self.site),
+            include_file,
+        });
+        self.db.delete_items(|item| &item.id == id);
+        Ok(())
+    }
+}
+
+/// Runs `hooks` in the order of registration.
+/// Errors and panics are reported along with the place where the failed hook
+/// was registered.
+pub fn run(data: &mut ProcessorData<'_>, hooks: &[UserHook]) -> Result<()> {
+    let step = data.progress.step().to_string();
+    for hook in hooks {
+        let mut editor = ItemEditor {
+            db: &mut *data.db,
+            step: &step,
+            site: hook.site,
+        };
+        let result = panic::catch_unwind(AssertUnwindSafe(|| (hook.function)(&mut editor)));
+        // hooks may change paths of the items
+        data.db.refresh_indexes();
+        match result {
+            Ok(result) => {
+                result.with_context(|_| format!("hook registered at {} failed", hook.site))?
+            }
+            Err(payload) => bail!(
+                "hook registered at {} panicked: {}",
+                hook.site,
+                panic_message(&*payload)
+            ),
+        }
+    }
+    Ok(())
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
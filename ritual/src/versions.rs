@@ -0,0 +1,144 @@
+//! Suggestion of the crate version based on changes of the generated API.
+
+use crate::database::Database;
+use crate::database_diff::{self, DatabaseDiff, VersionBump};
+use itertools::Itertools;
+use ritual_common::errors::{bail, Result, ResultExt};
+use ritual_common::target::LibraryTarget;
+use semver::Version;
+use std::fmt::Write;
+
+/// Parses a version of a crate or a C++ library.
+pub fn parse_version(version: &str) -> Result<Version> {
+    Ok(Version::parse(version).with_context(|_| format!("invalid version: {:?}", version))?)
+}
+
+/// Returns `version` incremented according to `bump`.
+/// As in Cargo, the leftmost non-zero component of the version is
+/// incremented for breaking changes, so `0.x.y` versions increment the minor
+/// version instead of the major version, and `0.0.z` versions always
+/// increment the patch version.
+pub fn bump_version(version: &Version, bump: VersionBump) -> Version {
+    let bump = if version.major > 0 {
+        bump
+    } else if version.minor > 0 && bump == VersionBump::Major {
+        VersionBump::Minor
+    } else {
+        VersionBump::Patch
+    };
+    let mut result = version.clone();
+    match bump {
+        VersionBump::Major => result.increment_major(),
+        VersionBump::Minor => result.increment_minor(),
+        VersionBump::Patch => result.increment_patch(),
+    }
+    result
+}
+
+/// Version of a crate suggested by comparing its API with
+/// the previously generated crate.
+#[derive(Debug, Clone)]
+pub struct VersionSuggestion {
+    /// Version of the previously generated crate
+    pub previous: Version,
+    /// Suggested version of the new crate
+    pub suggested: Version,
+    /// Changes of the API
+    pub diff: DatabaseDiff,
+    /// True if the documentation changed
+    pub is_documentation_changed: bool,
+}
+
+/// Returns sorted HTML content of all documentation items of `database`.
+fn documentation(database: &Database) -> Vec<&str> {
+    database
+        .doc_items()
+        .map(|item| item.item.html.as_str())
+        .sorted()
+        .collect()
+}
+
+impl VersionSuggestion {
+    /// Compares the API of the `new` database with the `old` database
+    /// of the previously generated crate. Changes that don't affect the API
+    /// (e.g. changes of the documentation) result in a patch bump.
+    /// If nothing changed, the previous version is suggested.
+    pub fn new(old: &Database, new: &Database) -> Result<Self> {
+        let previous = parse_version(old.crate_version())?;
+        let diff = database_diff::diff(old, new);
+        let is_documentation_changed = documentation(old) != documentation(new);
+        let suggested = if diff.is_empty() && !is_documentation_changed {
+            previous.clone()
+        } else {
+            bump_version(&previous, diff.version_bump())
+        };
+        Ok(VersionSuggestion {
+            previous,
+            suggested,
+            diff,
+            is_documentation_changed,
+        })
+    }
+
+    /// Returns an error if `version` is lower than the suggested version.
+    pub fn check(&self, version: &str) -> Result<()> {
+        if parse_version(version)? < self.suggested {
+            bail!(
+                "crate version {} is lower than the suggested version {} \
+                 (previous version: {}, required bump: {:?})",
+                version,
+                self.suggested,
+                self.previous,
+                self.diff.version_bump()
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns a human-readable report listing the changes
+    /// the suggestion is based on.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        writeln!(text, "Previous version: {}", self.previous).unwrap();
+        writeln!(text, "Suggested version: {}", self.suggested).unwrap();
+        if self.is_documentation_changed {
+            writeln!(text, "Documentation changed").unwrap();
+        }
+        text.push_str(&self.diff.to_text());
+        text
+    }
+}
+
+/// Returns the key for sorting library targets from the oldest
+/// to the newest version of the C++ library.
+pub fn library_target_sort_key(item: &LibraryTarget) -> impl Ord {
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    enum Key {
+        Semver(Version),
+        String(String),
+    }
+
+    item.cpp_library_version.as_ref().map(|version| {
+        if let Ok(x) = Version::parse(version) {
+            Key::Semver(x)
+        } else {
+            Key::String(version.clone())
+        }
+    })
+}
+
+/// Returns true if an item available in `current` library targets
+/// is not available in a later version. `all` must be sorted
+/// with `library_target_sort_key`.
+pub fn is_breaking_change(current: &[LibraryTarget], all: &[LibraryTarget]) -> bool {
+    for x in current {
+        let index = all
+            .iter()
+            .position(|i| x.cpp_library_version == i.cpp_library_version)
+            .unwrap();
+        if all[index + 1..].iter().any(|i| !current.contains(i)) {
+            return true;
+        }
+    }
+    false
+}
@@ -1,19 +1,67 @@
 use crate::config::{CrateDependency, CrateDependencyKind, CrateDependencySource};
-use crate::database::{DatabaseCache, DatabaseClient, CRATE_DB_FILE_NAME};
+use crate::coverage::CoverageReport;
+use crate::database::{
+    Database, DatabaseCache, DatabaseClient, DatabaseFormat, CRATE_DB_FILE_NAME,
+};
 use crate::download_db::download_db;
 use log::info;
 use ritual_common::errors::{bail, Result};
 use ritual_common::file_utils::{
-    create_dir_all, load_json, os_string_into_string, read_dir, remove_file, save_json,
-    save_toml_table,
+    create_dir_all, create_file, load_json, os_string_into_string, read_dir, remove_file,
+    save_json, save_toml_table,
 };
 use ritual_common::utils::MapIfOk;
 use ritual_common::{toml, ReadOnly};
 use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Default, Serialize, Deserialize)]
-pub struct WorkspaceConfig {}
+pub struct WorkspaceConfig {
+    /// Format of the database files of the workspace
+    #[serde(default)]
+    pub database_format: DatabaseFormat,
+}
+
+/// Completion marker of a processing step
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompletedStep {
+    /// Hash of the step's inputs that are not stored in the database
+    pub input_hash: Option<u64>,
+    /// Local time of completion
+    pub completed_at: String,
+}
+
+/// Processing steps completed for a crate, indexed by step name
+/// (steps included in the main procedure several times get a numbered suffix).
+/// A step loses its marker when any step preceding it in the main procedure
+/// runs again.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepStates {
+    pub completed: BTreeMap<String, CompletedStep>,
+}
+
+/// A step of a requested processing sequence
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedStep {
+    pub name: String,
+    /// Index of the step in the main procedure (`None` for custom steps)
+    pub main_index: Option<usize>,
+    /// Skip the step if it was completed and its inputs didn't change since then
+    pub only_if_changed: bool,
+}
+
+/// Progress of a processing run. It's saved before each step
+/// and removed when the run completes, so that an interrupted run
+/// can be resumed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunProgress {
+    /// Steps that were not completed yet, starting with the current step
+    pub remaining_steps: Vec<PlannedStep>,
+    /// Number of items processed by the current step before the last checkpoint
+    pub processed_items: usize,
+}
 
 /// Provides access to data stored in the user's project directory.
 /// The directory contains a subdirectory for each crate the user wants
@@ -30,10 +78,28 @@ fn config_path(path: &Path) -> PathBuf {
     path.join("config.json")
 }
 
-fn database_path(workspace_path: &Path, crate_name: &str) -> PathBuf {
+fn step_states_path(workspace_path: &Path, crate_name: &str) -> PathBuf {
+    workspace_path
+        .join("db")
+        .join(format!("{}.steps.json", crate_name))
+}
+
+fn run_progress_path(workspace_path: &Path, crate_name: &str) -> PathBuf {
+    workspace_path
+        .join("db")
+        .join(format!("{}.progress.json", crate_name))
+}
+
+fn generated_files_path(workspace_path: &Path, crate_name: &str) -> PathBuf {
+    workspace_path
+        .join("db")
+        .join(format!("{}.files.json", crate_name))
+}
+
+fn database_path(workspace_path: &Path, crate_name: &str, format: DatabaseFormat) -> PathBuf {
     workspace_path
         .join("db")
-        .join(format!("{}.json", crate_name))
+        .join(format!("{}.{}", crate_name, format.extension()))
 }
 
 impl Workspace {
@@ -42,7 +108,15 @@ impl Workspace {
             bail!("No such directory: {}", path.display());
         }
         let config_path = config_path(&path);
-        for &dir in &["tmp", "out", "log", "backup", "db", "external_db"] {
+        for &dir in &[
+            "tmp",
+            "out",
+            "log",
+            "backup",
+            "db",
+            "external_db",
+            "reports",
+        ] {
             create_dir_all(path.join(dir))?;
         }
         let w = Workspace {
@@ -57,7 +131,37 @@ impl Workspace {
     }
 
     pub fn database_path(&self, crate_name: &str) -> PathBuf {
-        database_path(&self.path, crate_name)
+        database_path(&self.path, crate_name, self.config.database_format)
+    }
+
+    /// Sets format of the database files. Existing databases are converted
+    /// to this format when they are loaded.
+    pub fn set_database_format(&mut self, format: DatabaseFormat) {
+        self.config.database_format = format;
+    }
+
+    /// Converts the database of `crate_name` to the current format
+    /// if it was saved in another format.
+    fn convert_database_if_needed(&self, crate_name: &str) -> Result<()> {
+        let path = self.database_path(crate_name);
+        if path.exists() {
+            return Ok(());
+        }
+        for &format in &[DatabaseFormat::Json, DatabaseFormat::Binary] {
+            let old_path = database_path(&self.path, crate_name, format);
+            if old_path != path && old_path.exists() {
+                info!(
+                    "Converting database for {} to {:?} format",
+                    crate_name, self.config.database_format
+                );
+                let mut cache = DatabaseCache::global().lock().unwrap();
+                cache.remove_if_exists(&old_path);
+                Database::load(&old_path)?.save(&path, None)?;
+                remove_file(old_path)?;
+                break;
+            }
+        }
+        Ok(())
     }
 
     pub fn path(&self) -> &Path {
@@ -80,16 +184,99 @@ impl Workspace {
         self.path.join("out").join(crate_name)
     }
 
+    /// Returns the path of the coverage report of `crate_name`
+    /// with the specified file `extension`.
+    pub fn coverage_report_path(&self, crate_name: &str, extension: &str) -> PathBuf {
+        self.path
+            .join("reports")
+            .join(format!("{}.coverage.{}", crate_name, extension))
+    }
+
+    /// Saves the coverage report of `crate_name` as Markdown and JSON files.
+    pub fn save_coverage_report(&self, report: &CoverageReport) -> Result<()> {
+        let mut file = create_file(self.coverage_report_path(&report.crate_name, "md"))?;
+        write!(file, "{}", report.to_markdown())?;
+        save_json(
+            self.coverage_report_path(&report.crate_name, "json"),
+            report,
+            None,
+        )
+    }
+
     pub fn delete_database_if_exists(&mut self, crate_name: &str) -> Result<()> {
-        let path = database_path(&self.path, crate_name);
         let mut cache = DatabaseCache::global().lock().unwrap();
-        cache.remove_if_exists(&path);
+        for &format in &[DatabaseFormat::Json, DatabaseFormat::Binary] {
+            let path = database_path(&self.path, crate_name, format);
+            cache.remove_if_exists(&path);
+            if path.exists() {
+                remove_file(path)?;
+            }
+        }
+        for path in &[
+            step_states_path(&self.path, crate_name),
+            run_progress_path(&self.path, crate_name),
+        ] {
+            if path.exists() {
+                remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads completion markers of processing steps of `crate_name`.
+    /// Returns `None` if they were never saved.
+    pub fn load_step_states(&self, crate_name: &str) -> Result<Option<StepStates>> {
+        let path = step_states_path(&self.path, crate_name);
+        if path.exists() {
+            Ok(Some(load_json(path)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn save_step_states(&self, crate_name: &str, states: &StepStates) -> Result<()> {
+        save_json(step_states_path(&self.path, crate_name), states, None)
+    }
+
+    /// Loads progress of the last processing run of `crate_name`.
+    /// Returns `None` if there is no interrupted run.
+    pub fn load_run_progress(&self, crate_name: &str) -> Result<Option<RunProgress>> {
+        let path = run_progress_path(&self.path, crate_name);
+        if path.exists() {
+            Ok(Some(load_json(path)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn save_run_progress(&self, crate_name: &str, progress: &RunProgress) -> Result<()> {
+        save_json(run_progress_path(&self.path, crate_name), progress, None)
+    }
+
+    pub fn remove_run_progress(&self, crate_name: &str) -> Result<()> {
+        let path = run_progress_path(&self.path, crate_name);
         if path.exists() {
             remove_file(path)?;
         }
         Ok(())
     }
 
+    /// Loads paths of the files generated by the last run of `crate_writer`
+    /// for `crate_name`, relative to the crate's directory.
+    /// Returns `None` if they were never saved.
+    pub fn load_generated_files(&self, crate_name: &str) -> Result<Option<Vec<PathBuf>>> {
+        let path = generated_files_path(&self.path, crate_name);
+        if path.exists() {
+            Ok(Some(load_json(path)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn save_generated_files(&self, crate_name: &str, files: &[PathBuf]) -> Result<()> {
+        save_json(generated_files_path(&self.path, crate_name), &files, None)
+    }
+
     pub fn get_database_client(
         &mut self,
         crate_name: &str,
@@ -97,6 +284,14 @@ impl Workspace {
         allow_load: bool,
         allow_create: bool,
     ) -> Result<DatabaseClient> {
+        if allow_load {
+            self.convert_database_if_needed(crate_name)?;
+            for dependency in dependencies {
+                if let CrateDependencySource::CurrentWorkspace = dependency.source() {
+                    self.convert_database_if_needed(dependency.name())?;
+                }
+            }
+        }
         let mut cache = DatabaseCache::global().lock().unwrap();
 
         let current_database = cache.get(
@@ -114,6 +309,7 @@ impl Workspace {
                         self.external_db_path(dependency.name(), version)?
                     }
                     CrateDependencySource::Local { path } => path.join(CRATE_DB_FILE_NAME),
+                    CrateDependencySource::Database { path } => path.clone(),
                     CrateDependencySource::CurrentWorkspace => {
                         self.database_path(dependency.name())
                     }
@@ -130,9 +326,10 @@ impl Workspace {
     fn database_backup_path(&self, crate_name: &str) -> PathBuf {
         let date = chrono::Local::now();
         self.path.join("backup").join(format!(
-            "db_{}_{}.json",
+            "db_{}_{}.{}",
             crate_name,
-            date.format("%Y-%m-%d_%H-%M-%S")
+            date.format("%Y-%m-%d_%H-%M-%S"),
+            self.config.database_format.extension()
         ))
     }
 
@@ -140,9 +337,8 @@ impl Workspace {
         if database.is_modified() {
             info!("Saving data");
             let backup_path = self.database_backup_path(database.crate_name());
-            save_json(
-                database_path(&self.path, database.crate_name()),
-                database.data(),
+            database.data().save(
+                self.database_path(database.crate_name()),
                 Some(&backup_path),
             )?;
             database.set_saved();
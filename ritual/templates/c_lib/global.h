@@ -17,12 +17,42 @@
 // for exit()
 #include <cstdlib>
 
-#ifdef _WIN32
+// for std::terminate()
+#include <exception>
+
+// for pointers and references to arrays
+#include <type_traits>
+
+// for std::move() of rvalue reference arguments
+#include <utility>
+
+// for printing values with operator<<
+#include <sstream>
+#include <string>
+
+// Exports FFI wrappers from the shared library. The Rust crate is the only
+// user of the wrappers, so they are never imported by other C++ code.
+// RITUAL_STATIC_LIBRARY is defined when the library is built as a static library.
+#if defined(RITUAL_STATIC_LIBRARY)
+    #define RITUAL_EXPORT
+#elif defined(_WIN32) || defined(__CYGWIN__)
     #define RITUAL_EXPORT __declspec(dllexport)
+#elif defined(__GNUC__)
+    #define RITUAL_EXPORT __attribute__((visibility("default")))
 #else
     #define RITUAL_EXPORT
 #endif
 
+// FFI wrappers of potentially throwing functions catch all exceptions
+// because unwinding into Rust code is undefined behavior.
+#if defined(__cpp_exceptions) || defined(__EXCEPTIONS) || defined(_CPPUNWIND)
+    #define RITUAL_TRY try
+    #define RITUAL_CATCH_ALL catch (...) { ritual::unhandled_exception(); }
+#else
+    #define RITUAL_TRY
+    #define RITUAL_CATCH_ALL
+#endif
+
 #define ritual_assert(x) \
     if (!(x)) { \
         std::cout << "assertion failed: " << #x << "\n"; \
@@ -30,6 +60,19 @@
     }
 
 namespace ritual {
+    // Called when a C++ exception reaches an FFI wrapper.
+    [[noreturn]] inline void unhandled_exception() {
+        std::cout << "ritual: unhandled C++ exception in FFI function\n";
+        std::terminate();
+    }
+
+    // Called when a pure virtual function of a subclass shim
+    // is called before an override is set.
+    [[noreturn]] inline void pure_virtual_called(const char* name) {
+        std::cout << "ritual: pure virtual function called without an override: " << name << "\n";
+        std::terminate();
+    }
+
     // Calls destructor of `T` class. This template function
     // is necessary because it's not possible to use `x->~T()`
     // syntax directly if `T` contains `::`.
@@ -67,6 +110,25 @@ namespace ritual {
         void (*m_deleter)(void*);
         T m_callback;
     };
+
+    // Owns a closure passed to a C++ function as a function pointer and
+    // a user data pointer. `release` must be the first member because
+    // it's called by `cpp_core::CallbackGuard`.
+    template<class T>
+    struct Closure {
+        Closure(T callback, void (*deleter)(void*), void* data)
+            : release(nullptr), callback(callback), deleter(deleter), data(data) {}
+        ~Closure() {
+            if (deleter) {
+                deleter(data);
+            }
+        }
+
+        void (*release)(void*);
+        T callback;
+        void (*deleter)(void*);
+        void* data;
+    };
 }
 
 #endif // RITUAL_GLOBAL_H
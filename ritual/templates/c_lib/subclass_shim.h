@@ -0,0 +1,9 @@
+// Class generated by ritual.
+// See the template at "ritual/templates/c_lib/subclass_shim.h".
+class {class_name} : public {base_class} {{
+public:
+{constructors}
+{functions}
+private:
+{fields}
+}};
@@ -12,6 +12,7 @@
 //! - Apply extra build configuration passed via environment variables
 //!   (`RITUAL_LIBRARY_PATH`, `RITUAL_FRAMEWORK_PATH`, `RITUAL_INCLUDE_PATH`);
 //! - Select active build configuration based on current target;
+//! - Locate the target C++ library;
 //! - Build the C++ wrapper library;
 //! - Generate `ffi.rs` file with actual linking attributes;
 //! - Determine sizes of C++ types with stack allocation place;
@@ -26,10 +27,14 @@
 
 use log::info;
 pub use ritual_common as common;
-use ritual_common::cpp_build_config::{CppBuildConfig, CppBuildPaths, CppLibraryType};
+use ritual_common::cpp_build_config::{
+    closest_known_version, CppBuildConfig, CppBuildPaths, CppLibraryType, STATIC_LINK_FEATURE,
+};
 use ritual_common::cpp_lib_builder::{BuildType, CMakeConfigData, CppLibBuilder};
 use ritual_common::errors::{bail, err_msg, FancyUnwrap, Result, ResultExt};
-use ritual_common::file_utils::{create_file, file_to_string, load_json, path_to_str};
+use ritual_common::file_utils::{
+    create_file, file_to_string, load_json, os_string_into_string, path_to_str,
+};
 use ritual_common::target::{current_target, LibraryTarget, OS};
 use ritual_common::{env_var_names, BuildScriptData};
 use std::env;
@@ -73,10 +78,59 @@ impl Config {
         })
     }
 
+    /// Sets version of the C++ library the crate is built against.
+    /// If the version is not set and the crate was generated for multiple
+    /// versions of the library, the version is detected using the library
+    /// discovery settings of the crate.
     pub fn set_current_cpp_library_version(&mut self, version: Option<String>) {
         self.current_cpp_library_version = version;
     }
 
+    /// Detects the installed version of the C++ library and returns
+    /// the closest version the crate was generated for.
+    fn detect_cpp_library_version(&self) -> Result<Option<String>> {
+        let mut known_versions = self
+            .build_script_data
+            .known_targets
+            .iter()
+            .filter_map(|target| target.cpp_library_version.as_deref())
+            .collect::<Vec<_>>();
+        known_versions.sort();
+        known_versions.dedup();
+        if known_versions.is_empty() {
+            return Ok(None);
+        }
+        let discovery = match &self.build_script_data.cpp_library_discovery {
+            Some(discovery) => discovery,
+            None => return Ok(None),
+        };
+        let installed = match discovery.detect_version() {
+            Some(version) => version,
+            None => bail!(
+                "failed to detect version of C++ library `{}`. Set {} environment variable \
+                 to one of the supported versions: {}",
+                discovery.lib_name(),
+                discovery.version_env_var(),
+                known_versions.join(", ")
+            ),
+        };
+        match closest_known_version(&installed, &known_versions) {
+            Some(version) => {
+                info!(
+                    "Using API of C++ library version {} (installed version: {})",
+                    version, installed
+                );
+                Ok(Some(version.to_string()))
+            }
+            None => bail!(
+                "C++ library `{}` version {} is not supported. Supported versions: {}",
+                discovery.lib_name(),
+                installed,
+                known_versions.join(", ")
+            ),
+        }
+    }
+
     /// Returns version of the native C++ library used for generating this crate.
     /// This is the value set with `Config::set_cpp_lib_version` during generation,
     /// or `None` if the version was not set.
@@ -111,6 +165,9 @@ impl Config {
 
     /// Same as `run()`, but result of the operation is returned to the caller.
     pub fn try_run(mut self) -> Result<()> {
+        if self.current_cpp_library_version.is_none() {
+            self.current_cpp_library_version = self.detect_cpp_library_version()?;
+        }
         let current_target = LibraryTarget {
             target: current_target(),
             cpp_library_version: self.current_cpp_library_version.clone(),
@@ -132,6 +189,29 @@ impl Config {
         }
 
         self.cpp_build_paths.apply_env();
+        for links in &self.build_script_data.dependency_links {
+            let prefix = format!("DEP_{}", links.to_uppercase().replace('-', "_"));
+            if let Some(paths) = env::var_os(format!("{}_INCLUDE", prefix)) {
+                for path in env::split_paths(&paths) {
+                    self.cpp_build_paths.add_include_path(path);
+                }
+            }
+            if let Some(paths) = env::var_os(format!("{}_LIB_DIR", prefix)) {
+                for path in env::split_paths(&paths) {
+                    self.cpp_build_paths.add_lib_path(path);
+                }
+            }
+        }
+        if let Some(discovery) = &self.build_script_data.cpp_library_discovery {
+            info!("Locating C++ library");
+            let paths = discovery.locate()?;
+            for path in paths.lib_paths() {
+                self.cpp_build_paths.add_lib_path(path.clone());
+            }
+            for path in paths.include_paths() {
+                self.cpp_build_paths.add_include_path(path.clone());
+            }
+        }
         let cpp_build_config_data = self
             .build_script_data
             .cpp_build_config
@@ -152,6 +232,7 @@ impl Config {
             cpp_build_paths: &self.cpp_build_paths,
             library_type: Some(library_type),
             cpp_library_version: self.current_cpp_library_version.clone(),
+            target: Some(current_target.target.clone()),
         };
 
         let cmake_vars = cmake_config.cmake_vars()?.into_iter().collect();
@@ -169,6 +250,7 @@ impl Config {
             capture_output: false,
             skip_cmake: false,
             skip_cmake_after_first_run: false,
+            timeout: None,
         }
         .run()?;
         {
@@ -197,6 +279,15 @@ impl Config {
         for name in cpp_build_config_data.linked_libs() {
             println!("cargo:rustc-link-lib={}", name);
         }
+        if let Some(discovery) = &self.build_script_data.cpp_library_discovery {
+            let feature_env_var = format!("CARGO_FEATURE_{}", STATIC_LINK_FEATURE.to_uppercase());
+            let kind = if env::var_os(feature_env_var).is_some() {
+                "static"
+            } else {
+                "dylib"
+            };
+            println!("cargo:rustc-link-lib={}={}", kind, discovery.lib_name());
+        }
 
         // TODO: make it configurable
         if crate::common::target::current_os() == OS::MacOS {
@@ -219,6 +310,17 @@ impl Config {
             path_to_str(&c_lib_install_dir)?
         );
 
+        // Paths are exposed to dependent crates as `DEP_<LINKS>_INCLUDE`
+        // and `DEP_<LINKS>_LIB_DIR` environment variables.
+        if !self.cpp_build_paths.include_paths().is_empty() {
+            let paths = env::join_paths(self.cpp_build_paths.include_paths())?;
+            println!("cargo:include={}", os_string_into_string(paths)?);
+        }
+        if !self.cpp_build_paths.lib_paths().is_empty() {
+            let paths = env::join_paths(self.cpp_build_paths.lib_paths())?;
+            println!("cargo:lib_dir={}", os_string_into_string(paths)?);
+        }
+
         if let Some(version) = self.current_cpp_library_version {
             println!("cargo:rustc-cfg=cpp_lib_version={:?}", version);
         }
@@ -229,6 +331,17 @@ impl Config {
         ] {
             println!("cargo:rerun-if-env-changed={}", var);
         }
+        if let Some(discovery) = &self.build_script_data.cpp_library_discovery {
+            println!("cargo:rerun-if-env-changed={}", discovery.lib_dir_env_var());
+            println!(
+                "cargo:rerun-if-env-changed={}",
+                discovery.include_dir_env_var()
+            );
+            println!("cargo:rerun-if-env-changed={}", discovery.version_env_var());
+            if discovery.pkg_config_name().is_some() {
+                println!("cargo:rerun-if-env-changed=PKG_CONFIG_PATH");
+            }
+        }
         info!("ritual build script finished.");
         Ok(())
     }
@@ -236,6 +349,7 @@ impl Config {
     /// Starts build script with current configuration.
     /// The build script performs the following operations:
     ///
+    /// - Locate the target C++ library;
     /// - Build the C++ wrapper library;
     /// - Generate `ffi.rs` file with actual link attributes;
     /// - Determine C++ type sizes on current platform and generate `sized_types.rs`;
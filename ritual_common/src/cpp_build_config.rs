@@ -52,6 +52,12 @@ pub struct CppBuildConfigData {
     compiler_flags: Vec<String>,
     library_type: Option<CppLibraryType>,
     cmake_vars: Vec<CMakeVar>,
+    #[serde(default)]
+    cmake_packages: Vec<String>,
+    #[serde(default)]
+    linked_cmake_targets: Vec<String>,
+    #[serde(default)]
+    cpp_standard: Option<u32>,
 }
 
 impl CppBuildConfigData {
@@ -90,6 +96,24 @@ impl CppBuildConfigData {
         self.cmake_vars.push(var);
     }
 
+    /// Adds a CMake package that should be found with `find_package`
+    /// before building the C++ wrapper library (e.g. `Qt5Core`).
+    pub fn add_cmake_package<P: Into<String>>(&mut self, package: P) {
+        self.cmake_packages.push(package.into());
+    }
+
+    /// Adds a CMake target for linking (e.g. `Qt5::Core`).
+    /// Targets are usually provided by packages added with `add_cmake_package`.
+    pub fn add_linked_cmake_target<P: Into<String>>(&mut self, target: P) {
+        self.linked_cmake_targets.push(target.into());
+    }
+
+    /// Sets the C++ standard (e.g. 11 or 17) required to build the C++ wrapper library.
+    /// If not set, the compiler's default is used.
+    pub fn set_cpp_standard(&mut self, standard: u32) {
+        self.cpp_standard = Some(standard);
+    }
+
     /// Sets library type. C++ wrapper is static by default.
     /// Shared library can be used to work around MSVC linker's limitations.
     pub fn set_library_type(&mut self, t: CppLibraryType) {
@@ -111,6 +135,21 @@ impl CppBuildConfigData {
         &self.compiler_flags
     }
 
+    /// Returns names of CMake packages required by the C++ wrapper library.
+    pub fn cmake_packages(&self) -> &[String] {
+        &self.cmake_packages
+    }
+
+    /// Returns names of linked CMake targets.
+    pub fn linked_cmake_targets(&self) -> &[String] {
+        &self.linked_cmake_targets
+    }
+
+    /// Returns the required C++ standard.
+    pub fn cpp_standard(&self) -> Option<u32> {
+        self.cpp_standard
+    }
+
     /// Returns type of C++ wrapper libary (shared or static).
     pub fn library_type(&self) -> Option<CppLibraryType> {
         self.library_type
@@ -129,6 +168,15 @@ impl CppBuildConfigData {
             self.library_type = other.library_type;
         }
         self.cmake_vars.extend_from_slice(&other.cmake_vars);
+        self.cmake_packages.extend_from_slice(&other.cmake_packages);
+        self.linked_cmake_targets
+            .extend_from_slice(&other.linked_cmake_targets);
+        if let Some(standard) = other.cpp_standard {
+            if self.cpp_standard.map_or(false, |s| s != standard) {
+                bail!("conflicting C++ standards specified");
+            }
+            self.cpp_standard = Some(standard);
+        }
         Ok(())
     }
 
@@ -160,7 +208,12 @@ impl CppBuildConfig {
 }
 
 use crate::env_var_names;
-use std::path::PathBuf;
+use crate::utils::get_command_output;
+use log::info;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
 /// Machine-specific information required to build the C++ wrapper library.
 /// This type holds configuration properties that cannot be determined
@@ -218,7 +271,6 @@ impl CppBuildPaths {
     /// `RITUAL_INCLUDE_PATH` environment variables are present,
     /// their values override current values of the object.
     pub fn apply_env(&mut self) {
-        use std::env;
         if let Ok(paths) = env::var(env_var_names::LIBRARY_PATH) {
             self.lib_paths = env::split_paths(&paths)
                 .filter(|s| !s.as_os_str().is_empty())
@@ -251,3 +303,254 @@ impl CppBuildPaths {
         &self.include_paths
     }
 }
+
+/// Name of the Cargo feature of the generated crate that enables
+/// static linking of the target C++ library.
+pub const STATIC_LINK_FEATURE: &str = "static_link";
+
+/// Information required to locate the target C++ library
+/// on the machine that builds the generated crate.
+///
+/// The build script tries the following sources in order of priority:
+///
+/// - `<PREFIX>_LIB_DIR` environment variable;
+/// - `pkg-config` package, if its name was set with `set_pkg_config_name`;
+/// - directories added with `add_lib_path`, in order of addition.
+///
+/// `<PREFIX>_INCLUDE_DIR` environment variable, if present, overrides
+/// include directories found by any of these sources.
+/// The located directories are added to `CppBuildPaths`, so they are used for building
+/// the C++ wrapper library and linking the crate. If the library was not found,
+/// the build script fails with a message naming the environment variables to set.
+///
+/// The library is linked dynamically by default. Static linking is enabled
+/// by the `static_link` feature of the generated crate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CppLibraryDiscovery {
+    lib_name: String,
+    env_var_prefix: String,
+    pkg_config_name: Option<String>,
+    lib_paths: Vec<PathBuf>,
+    include_paths: Vec<PathBuf>,
+}
+
+impl CppLibraryDiscovery {
+    /// Constructs an object for the library linked as `lib_name`
+    /// (e.g. `Qt5Core` for `libQt5Core.so`). Environment variables
+    /// overriding the search are named `<env_var_prefix>_LIB_DIR`
+    /// and `<env_var_prefix>_INCLUDE_DIR`.
+    pub fn new<S1: Into<String>, S2: Into<String>>(lib_name: S1, env_var_prefix: S2) -> Self {
+        CppLibraryDiscovery {
+            lib_name: lib_name.into(),
+            env_var_prefix: env_var_prefix.into(),
+            pkg_config_name: None,
+            lib_paths: Vec::new(),
+            include_paths: Vec::new(),
+        }
+    }
+
+    /// Sets name of the `pkg-config` package of the library.
+    pub fn set_pkg_config_name<S: Into<String>>(&mut self, name: S) {
+        self.pkg_config_name = Some(name.into());
+    }
+
+    /// Adds a directory that may contain the library.
+    pub fn add_lib_path<P: Into<PathBuf>>(&mut self, path: P) {
+        let path = path.into();
+        if !self.lib_paths.contains(&path) {
+            self.lib_paths.push(path);
+        }
+    }
+
+    /// Adds an include directory used if the library is found
+    /// in one of directories added with `add_lib_path`.
+    pub fn add_include_path<P: Into<PathBuf>>(&mut self, path: P) {
+        let path = path.into();
+        if !self.include_paths.contains(&path) {
+            self.include_paths.push(path);
+        }
+    }
+
+    /// Returns name of the library.
+    pub fn lib_name(&self) -> &str {
+        &self.lib_name
+    }
+
+    /// Returns name of the `pkg-config` package.
+    pub fn pkg_config_name(&self) -> Option<&str> {
+        self.pkg_config_name.as_deref()
+    }
+
+    /// Returns paths added via `add_lib_path`.
+    pub fn lib_paths(&self) -> &[PathBuf] {
+        &self.lib_paths
+    }
+
+    /// Returns paths added via `add_include_path`.
+    pub fn include_paths(&self) -> &[PathBuf] {
+        &self.include_paths
+    }
+
+    /// Returns name of the environment variable overriding the library directory.
+    pub fn lib_dir_env_var(&self) -> String {
+        format!("{}_LIB_DIR", self.env_var_prefix)
+    }
+
+    /// Returns name of the environment variable overriding the include directory.
+    pub fn include_dir_env_var(&self) -> String {
+        format!("{}_INCLUDE_DIR", self.env_var_prefix)
+    }
+
+    /// Returns name of the environment variable overriding the detected
+    /// version of the library.
+    pub fn version_env_var(&self) -> String {
+        format!("{}_VERSION", self.env_var_prefix)
+    }
+
+    /// Returns true if `dir` contains a file of the library.
+    pub fn is_in_dir(&self, dir: &Path) -> bool {
+        let name = &self.lib_name;
+        let file_names = [
+            format!("lib{}.so", name),
+            format!("lib{}.a", name),
+            format!("lib{}.dylib", name),
+            format!("lib{}.dll.a", name),
+            format!("{}.lib", name),
+            format!("{}.framework", name),
+        ];
+        let versioned_prefix = format!("lib{}.so.", name);
+
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return false,
+        };
+        entries.filter_map(|entry| entry.ok()).any(|entry| {
+            let file_name = entry.file_name();
+            let file_name = file_name.to_string_lossy();
+            file_names.iter().any(|candidate| candidate == &file_name)
+                || file_name.starts_with(&versioned_prefix)
+        })
+    }
+
+    /// Locates the library and returns its library and include directories.
+    pub fn locate(&self) -> Result<CppBuildPaths> {
+        let lib_dir_env_var = self.lib_dir_env_var();
+        let mut paths = if let Some(dir) = env::var_os(&lib_dir_env_var) {
+            let dir = PathBuf::from(dir);
+            if !self.is_in_dir(&dir) {
+                bail!(
+                    "{} is set to {:?}, but C++ library `{}` was not found in this directory",
+                    lib_dir_env_var,
+                    dir,
+                    self.lib_name
+                );
+            }
+            info!("Using C++ library directory from {}", lib_dir_env_var);
+            let mut paths = CppBuildPaths::new();
+            paths.add_lib_path(dir);
+            paths
+        } else if let Some(paths) = self.locate_with_pkg_config() {
+            paths
+        } else if let Some(dir) = self.lib_paths.iter().find(|dir| self.is_in_dir(dir)) {
+            info!("Found C++ library `{}` in {:?}", self.lib_name, dir);
+            let mut paths = CppBuildPaths::new();
+            paths.add_lib_path(dir.clone());
+            for path in &self.include_paths {
+                paths.add_include_path(path.clone());
+            }
+            paths
+        } else {
+            let mut message = format!("C++ library `{}` was not found.", self.lib_name);
+            if let Some(name) = &self.pkg_config_name {
+                message += &format!(" pkg-config package `{}` is not available.", name);
+            }
+            if !self.lib_paths.is_empty() {
+                message += &format!(" Searched in: {:?}.", self.lib_paths);
+            }
+            bail!(
+                "{} Set {} environment variable to the directory containing the library \
+                 and {} to its include directory.",
+                message,
+                lib_dir_env_var,
+                self.include_dir_env_var()
+            );
+        };
+
+        if let Some(dir) = env::var_os(self.include_dir_env_var()) {
+            paths.include_paths = vec![PathBuf::from(dir)];
+        }
+        Ok(paths)
+    }
+
+    /// Detects the version of the installed library. The version is taken
+    /// from the environment variable returned by `version_env_var`
+    /// or from `pkg-config`. Returns `None` if the version is unknown.
+    pub fn detect_version(&self) -> Option<String> {
+        if let Ok(version) = env::var(self.version_env_var()) {
+            info!("Using C++ library version from {}", self.version_env_var());
+            return Some(version);
+        }
+        let name = self.pkg_config_name.as_ref()?;
+        match get_command_output(Command::new("pkg-config").arg("--modversion").arg(name)) {
+            Ok(output) => Some(output.trim().to_string()),
+            Err(err) => {
+                info!("pkg-config failed to detect version of `{}`: {}", name, err);
+                None
+            }
+        }
+    }
+
+    fn locate_with_pkg_config(&self) -> Option<CppBuildPaths> {
+        let name = self.pkg_config_name.as_ref()?;
+        let output = get_command_output(
+            Command::new("pkg-config")
+                .arg("--libs-only-L")
+                .arg("--cflags-only-I")
+                .arg(name),
+        );
+        match output.and_then(|output| pkg_config_paths(&output)) {
+            Ok(paths) => {
+                info!("Found C++ library `{}` with pkg-config", self.lib_name);
+                Some(paths)
+            }
+            Err(err) => {
+                info!("pkg-config failed to find `{}`: {}", name, err);
+                None
+            }
+        }
+    }
+}
+
+/// Returns the newest of `known_versions` that is not newer than `version`,
+/// so that a crate generated for some versions of a library can be built
+/// against a release in between. Versions that are not valid semver versions
+/// only match exactly.
+pub fn closest_known_version<'a>(version: &str, known_versions: &[&'a str]) -> Option<&'a str> {
+    if let Some(known) = known_versions.iter().find(|known| **known == version) {
+        return Some(*known);
+    }
+    let version = semver::Version::parse(version).ok()?;
+    known_versions
+        .iter()
+        .filter_map(|known| {
+            semver::Version::parse(known)
+                .ok()
+                .filter(|parsed| parsed <= &version)
+                .map(|parsed| (parsed, *known))
+        })
+        .max()
+        .map(|(_, known)| known)
+}
+
+/// Parses `-L` and `-I` arguments from `pkg-config` output.
+pub fn pkg_config_paths(output: &str) -> Result<CppBuildPaths> {
+    let mut paths = CppBuildPaths::new();
+    for arg in shell_words::split(output)? {
+        if let Some(path) = arg.strip_prefix("-L") {
+            paths.add_lib_path(path);
+        } else if let Some(path) = arg.strip_prefix("-I") {
+            paths.add_include_path(path);
+        }
+    }
+    Ok(paths)
+}
@@ -3,7 +3,10 @@
 use crate::cpp_build_config::{CppBuildConfigData, CppBuildPaths, CppLibraryType};
 use crate::errors::{err_msg, Result};
 use crate::file_utils::{create_dir_all, file_to_string, path_to_str};
-use crate::utils::{run_command, run_command_and_capture_output, CommandOutput, MapIfOk};
+use crate::utils::{
+    run_command, run_command_and_capture_output, run_command_and_capture_output_with_timeout,
+    CommandOutput, MapIfOk,
+};
 use crate::{env_var_names, target};
 use itertools::Itertools;
 use serde_derive::{Deserialize, Serialize};
@@ -11,6 +14,7 @@ use std::env;
 use std::fmt::Display;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::Duration;
 
 /// A CMake variable with a name and a value.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -93,6 +97,9 @@ pub struct CppLibBuilder {
     pub capture_output: bool,
     pub skip_cmake: bool,
     pub skip_cmake_after_first_run: bool,
+    /// Maximal duration of each captured command. Commands exceeding it
+    /// are killed and reported as failed.
+    pub timeout: Option<Duration>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -108,6 +115,14 @@ impl CppLibBuilderOutput {
 }
 
 impl CppLibBuilder {
+    fn run_and_capture_output(&self, command: &mut Command) -> Result<CommandOutput> {
+        if let Some(timeout) = self.timeout {
+            run_command_and_capture_output_with_timeout(command, timeout)
+        } else {
+            run_command_and_capture_output(command)
+        }
+    }
+
     /// Builds the library.
     pub fn run(&mut self) -> Result<CppLibBuilderOutput> {
         if !self.build_dir.exists() {
@@ -160,7 +175,7 @@ impl CppLibBuilder {
             }
 
             if self.capture_output {
-                let output = run_command_and_capture_output(&mut cmake_command)?;
+                let output = self.run_and_capture_output(&mut cmake_command)?;
                 if !output.is_success() {
                     return Ok(CppLibBuilderOutput::Fail(output));
                 }
@@ -232,6 +247,7 @@ impl CppLibBuilder {
             if let Some(capture_output_file) = capture_output_file {
                 if let Err(err) = run_command(&mut make_command) {
                     let output = CommandOutput {
+                        abnormal_termination: None,
                         status: 0,
                         stderr: format!(
                             "{}\n{}",
@@ -243,7 +259,7 @@ impl CppLibBuilder {
                     return Ok(CppLibBuilderOutput::Fail(output));
                 }
             } else {
-                let output = run_command_and_capture_output(&mut make_command)?;
+                let output = self.run_and_capture_output(&mut make_command)?;
                 if !output.is_success() {
                     return Ok(CppLibBuilderOutput::Fail(output));
                 }
@@ -260,6 +276,9 @@ pub struct CMakeConfigData<'a, 'b> {
     pub cpp_build_paths: &'b CppBuildPaths,
     pub library_type: Option<CppLibraryType>,
     pub cpp_library_version: Option<String>,
+    /// Target the library is built for. Enables the preprocessor macros
+    /// used in conditions of platform-specific items.
+    pub target: Option<target::Target>,
 }
 
 pub fn version_to_number(version: &str) -> Result<u32> {
@@ -311,6 +330,23 @@ impl<'a, 'b> CMakeConfigData<'a, 'b> {
             "RITUAL_COMPILER_FLAGS",
             self.cpp_build_config_data.compiler_flags().join(" "),
         ));
+        cmake_vars.push(CMakeVar::new_list(
+            "RITUAL_CMAKE_PACKAGES",
+            self.cpp_build_config_data.cmake_packages(),
+        )?);
+        cmake_vars.push(CMakeVar::new_list(
+            "RITUAL_LINKED_CMAKE_TARGETS",
+            self.cpp_build_config_data.linked_cmake_targets(),
+        )?);
+        if let Some(target) = &self.target {
+            cmake_vars.push(CMakeVar::new_list(
+                "RITUAL_TARGET_DEFINITIONS",
+                &target.cpp_definitions(),
+            )?);
+        }
+        if let Some(standard) = self.cpp_build_config_data.cpp_standard() {
+            cmake_vars.push(CMakeVar::new("RITUAL_CXX_STANDARD", standard.to_string()));
+        }
         cmake_vars.extend_from_slice(self.cpp_build_config_data.cmake_vars());
         Ok(cmake_vars)
     }
@@ -5,7 +5,7 @@ use log::trace;
 use std::ffi::{OsStr, OsString};
 use std::fs;
 use std::io::{self, BufRead, BufReader, BufWriter, Lines, Read, Write};
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 /// Move file or directory `src` to `dst` recursively,
 /// overwriting previous contents of `dst`. If corresponding
@@ -206,29 +206,51 @@ pub fn save_json<P: AsRef<Path>, T: ::serde::Serialize>(
     value: &T,
     backup_path: Option<&Path>,
 ) -> Result<()> {
+    save_atomically(path.as_ref(), backup_path, |file| {
+        ::serde_json::to_writer(file, value).with_context(|_| {
+            format!(
+                "failed to serialize to JSON file: {}",
+                path.as_ref().display()
+            )
+        })?;
+        Ok(())
+    })
+}
+
+/// Writes file `path` using `write` and replaces the old file only when writing
+/// is completed, so that an interrupted run can't leave a corrupted file.
+/// If `backup_path` is specified, the old content of the file is preserved there.
+pub fn save_atomically<P: AsRef<Path>>(
+    path: P,
+    backup_path: Option<&Path>,
+    write: impl FnOnce(&mut BufWriter<fs::File>) -> Result<()>,
+) -> Result<()> {
+    let path = path.as_ref();
     let tmp_path = {
-        let mut buf = path.as_ref().to_path_buf();
+        let mut buf = path.to_path_buf();
         let tmp_file_name = format!("{}.new", os_str_to_str(&buf.file_name().unwrap())?);
         buf.set_file_name(tmp_file_name);
         buf
     };
     {
-        let file = create_file(&tmp_path)?;
-        ::serde_json::to_writer(&mut file.into_inner(), value).with_context(|_| {
-            format!(
-                "failed to serialize to JSON file: {}",
-                path.as_ref().display()
-            )
-        })?;
+        let mut file = create_file(&tmp_path)?.into_inner();
+        write(&mut file)?;
+        file.flush()
+            .with_context(|_| format!("Failed to flush file: {:?}", tmp_path))?;
+        file.get_ref()
+            .sync_all()
+            .with_context(|_| format!("Failed to sync file: {:?}", tmp_path))?;
     }
-    if path.as_ref().exists() {
+    if path.exists() {
         if let Some(backup_path) = backup_path {
-            rename_file(path.as_ref(), backup_path)?;
-        } else {
-            remove_file(path.as_ref())?;
+            // `rename` replaces the file atomically, so the old file
+            // must stay in place until then
+            if fs::hard_link(path, backup_path).is_err() {
+                copy_file(path, backup_path)?;
+            }
         }
     }
-    rename_file(&tmp_path, path.as_ref())?;
+    rename_file(&tmp_path, path)?;
     Ok(())
 }
 
@@ -260,8 +282,8 @@ pub fn load_toml_table<P: AsRef<Path>>(path: P) -> Result<toml::value::Table> {
     }
 }
 
-pub fn crate_version(path: impl AsRef<Path>) -> Result<String> {
-    let cargo_toml_path = path.as_ref().join("Cargo.toml");
+fn cargo_package_field(path: &Path, field: &str) -> Result<String> {
+    let cargo_toml_path = path.join("Cargo.toml");
     let table = load_toml_table(cargo_toml_path)?;
     let package = table
         .get("package")
@@ -269,13 +291,21 @@ pub fn crate_version(path: impl AsRef<Path>) -> Result<String> {
     let package = package
         .as_table()
         .ok_or_else(|| err_msg("invalid Cargo.toml: package is not a table"))?;
-    let version = package
-        .get("version")
-        .ok_or_else(|| err_msg("Cargo.toml doesn't contain package.version field"))?;
-    let version = version
+    let value = package
+        .get(field)
+        .ok_or_else(|| format_err!("Cargo.toml doesn't contain package.{} field", field))?;
+    let value = value
         .as_str()
-        .ok_or_else(|| err_msg("invalid Cargo.toml: package.version is not a string"))?;
-    Ok(version.into())
+        .ok_or_else(|| format_err!("invalid Cargo.toml: package.{} is not a string", field))?;
+    Ok(value.into())
+}
+
+pub fn crate_version(path: impl AsRef<Path>) -> Result<String> {
+    cargo_package_field(path.as_ref(), "version")
+}
+
+pub fn crate_name(path: impl AsRef<Path>) -> Result<String> {
+    cargo_package_field(path.as_ref(), "name")
 }
 
 /// Save `data` to a TOML file
@@ -384,6 +414,42 @@ pub fn canonicalize<P: AsRef<Path>>(path: P) -> Result<PathBuf> {
         .with_context(|_| format!("failed to canonicalize {}", path.as_ref().display()))?)
 }
 
+/// Returns the canonical form of `path` for comparing paths.
+/// If `path` can't be canonicalized (e.g. it doesn't exist),
+/// `.` and `..` components are resolved without accessing the file system
+/// and the drive letter is converted to upper case on Windows.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    if let Ok(path) = canonicalize(path) {
+        return path;
+    }
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                let can_pop = match result.components().next_back() {
+                    Some(Component::Normal(_)) => true,
+                    Some(Component::ParentDir) | None => false,
+                    _ => {
+                        // `..` of the root is the root
+                        continue;
+                    }
+                };
+                if can_pop {
+                    result.pop();
+                } else {
+                    result.push(component);
+                }
+            }
+            Component::Prefix(prefix) => {
+                result.push(prefix.as_os_str().to_string_lossy().to_uppercase());
+            }
+            _ => result.push(component),
+        }
+    }
+    result
+}
+
 /// A wrapper over `Path::to_str` with better error reporting
 pub fn path_to_str(path: &Path) -> Result<&str> {
     path.to_str()
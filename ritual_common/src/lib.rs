@@ -31,6 +31,12 @@ pub struct BuildScriptData {
     pub cpp_wrapper_lib_name: String,
     /// Environments the generator was used in
     pub known_targets: Vec<LibraryTarget>,
+    /// Information required to locate the target C++ library
+    #[serde(default)]
+    pub cpp_library_discovery: Option<cpp_build_config::CppLibraryDiscovery>,
+    /// Values of `links` manifest key of ritual-generated dependencies
+    #[serde(default)]
+    pub dependency_links: Vec<String>,
 }
 
 #[derive(Debug)]
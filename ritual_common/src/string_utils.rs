@@ -50,12 +50,21 @@ impl<'a> Iterator for WordIterator<'a> {
     }
 }
 
+/// Acronyms that are kept together by `CaseOperations::to_snake_case`
+/// (e.g. `QIODevice` becomes `q_io_device` instead of `q_i_o_device`).
+pub const DEFAULT_ACRONYMS: &[&str] = &["IO", "GL", "ES", "XML", "HTTP"];
+
 /// Convert to string with different cases
 pub trait CaseOperations {
     /// Convert to class-case string ("WordWordWord")
     fn to_class_case(&self) -> String;
     /// Convert to snake-case string ("word_word_word")
-    fn to_snake_case(&self) -> String;
+    fn to_snake_case(&self) -> String {
+        self.to_snake_case_with_acronyms(DEFAULT_ACRONYMS)
+    }
+    /// Convert to snake-case string ("word_word_word"), keeping `acronyms`
+    /// (e.g. "XML") together as a single word
+    fn to_snake_case_with_acronyms<S: AsRef<str>>(&self, acronyms: &[S]) -> String;
     /// Convert to upper-case string ("WORD_WORD_WORD")
     fn to_upper_case_words(&self) -> String;
 }
@@ -88,24 +97,103 @@ pub fn ends_with_digit<S: AsRef<str>>(s: S) -> bool {
     }
 }
 
-fn iterator_to_snake_case<S: AsRef<str>, T: Iterator<Item = S>>(it: T) -> String {
-    let mut parts = it.map(|x| x.as_ref().to_lowercase()).collect_vec();
+/// Splits `letters` into `acronyms` and single letters and adds them to `result`.
+/// Returns true if the last letter is part of an acronym.
+fn push_letters<A: AsRef<str>>(
+    result: &mut Vec<(String, bool)>,
+    letters: &str,
+    acronyms: &[A],
+) -> bool {
+    let mut last_is_acronym = false;
+    let mut index = 0;
+    while index < letters.len() {
+        let acronym = acronyms
+            .iter()
+            .map(AsRef::as_ref)
+            .filter(|acronym| !acronym.is_empty() && letters[index..].starts_with(acronym))
+            .max_by_key(|acronym| acronym.len());
+        if let Some(acronym) = acronym {
+            result.push((acronym.to_string(), true));
+            index += acronym.len();
+            last_is_acronym = true;
+        } else {
+            result.push((letters[index..=index].to_string(), false));
+            index += 1;
+            last_is_acronym = false;
+        }
+    }
+    last_is_acronym
+}
+
+/// Joins sequences of single uppercase letters that form one of `acronyms`.
+/// Returns the words and whether each of them is an acronym.
+fn join_acronyms<S: AsRef<str>, A: AsRef<str>>(
+    words: Vec<S>,
+    acronyms: &[A],
+) -> Vec<(String, bool)> {
+    // `WordIterator` keeps digits with the preceding letter ("GLES2" becomes "G", "L", "E", "S2")
+    let is_letter_with_digits = |word: &str| {
+        word.chars()
+            .next()
+            .map_or(false, |c| c.is_ascii_uppercase())
+            && word[1..].chars().all(|c| c.is_digit(10))
+    };
+    let mut result = Vec::new();
+    let mut letters = String::new();
+    let mut words = words.into_iter().peekable();
+    while let Some(word) = words.next() {
+        let word = word.as_ref();
+        if !is_letter_with_digits(word) {
+            result.push((word.to_string(), false));
+            continue;
+        }
+        letters.push_str(&word[..1]);
+        let digits = &word[1..];
+        if digits.is_empty()
+            && words
+                .peek()
+                .map_or(false, |next| is_letter_with_digits(next.as_ref()))
+        {
+            continue;
+        }
+        let last_is_acronym = push_letters(&mut result, &letters, acronyms);
+        letters.clear();
+        if !digits.is_empty() {
+            if last_is_acronym {
+                result.push((digits.to_string(), false));
+            } else {
+                result.last_mut().unwrap().0.push_str(digits);
+            }
+        }
+    }
+    result
+}
+
+fn iterator_to_snake_case<S: AsRef<str>, T: Iterator<Item = S>, A: AsRef<str>>(
+    it: T,
+    acronyms: &[A],
+) -> String {
+    let mut parts = join_acronyms(it.collect_vec(), acronyms)
+        .into_iter()
+        .map(|(word, is_acronym)| (word.to_lowercase(), is_acronym))
+        .collect_vec();
     replace_all_sub_vecs(&mut parts, &["na", "n"]);
-    replace_all_sub_vecs(&mut parts, &["open", "g", "l"]);
-    replace_all_sub_vecs(&mut parts, &["i", "o"]);
     replace_all_sub_vecs(&mut parts, &["2", "d"]);
     replace_all_sub_vecs(&mut parts, &["3", "d"]);
     replace_all_sub_vecs(&mut parts, &["4", "d"]);
     let mut string = String::new();
-    for (i, part) in parts.into_iter().enumerate() {
+    let mut previous_is_acronym = false;
+    for (i, (part, is_acronym)) in parts.into_iter().enumerate() {
         if part.is_empty() {
             continue;
         }
         let all_digits = part.chars().all(|c| c.is_digit(10));
-        if i > 0 && (!all_digits || ends_with_digit(&string)) {
+        // "ES2" becomes "es_2", but "Base64" becomes "base64"
+        if i > 0 && (!all_digits || ends_with_digit(&string) || previous_is_acronym) {
             string.push('_');
         }
         string.push_str(&part);
+        previous_is_acronym = is_acronym;
     }
     string
 }
@@ -114,18 +202,22 @@ fn iterator_to_upper_case_words<S: AsRef<str>, T: Iterator<Item = S>>(it: T) ->
     it.map(|x| x.as_ref().to_uppercase()).join("_")
 }
 
-fn replace_all_sub_vecs(parts: &mut Vec<String>, needle: &[&str]) {
+fn replace_all_sub_vecs(parts: &mut Vec<(String, bool)>, needle: &[&str]) {
     let mut any_found = true;
     while any_found {
         any_found = false;
         if parts.len() + 1 >= needle.len() {
             // TODO: maybe rewrite this
             for i in 0..parts.len() + 1 - needle.len() {
-                if parts[i..i + needle.len()] == needle[..] {
+                if parts[i..i + needle.len()]
+                    .iter()
+                    .map(|(part, _)| part.as_str())
+                    .eq(needle.iter().cloned())
+                {
                     for _ in 0..needle.len() - 1 {
                         parts.remove(i + 1);
                     }
-                    parts[i] = needle.join("");
+                    parts[i] = (needle.join(""), false);
                     any_found = true;
                     break;
                 }
@@ -138,8 +230,8 @@ impl CaseOperations for str {
     fn to_class_case(&self) -> String {
         iterator_to_class_case(WordIterator::new(self))
     }
-    fn to_snake_case(&self) -> String {
-        iterator_to_snake_case(WordIterator::new(self))
+    fn to_snake_case_with_acronyms<S: AsRef<str>>(&self, acronyms: &[S]) -> String {
+        iterator_to_snake_case(WordIterator::new(self), acronyms)
     }
     fn to_upper_case_words(&self) -> String {
         iterator_to_upper_case_words(WordIterator::new(self))
@@ -150,8 +242,8 @@ impl<'a> CaseOperations for [&'a str] {
     fn to_class_case(&self) -> String {
         iterator_to_class_case(self.iter())
     }
-    fn to_snake_case(&self) -> String {
-        iterator_to_snake_case(self.iter())
+    fn to_snake_case_with_acronyms<S: AsRef<str>>(&self, acronyms: &[S]) -> String {
+        iterator_to_snake_case(self.iter(), acronyms)
     }
     fn to_upper_case_words(&self) -> String {
         iterator_to_upper_case_words(self.iter())
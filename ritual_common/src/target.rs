@@ -1,5 +1,6 @@
 //! Types for expressing properties of different target platforms and platform-based conditions
 
+use itertools::Itertools;
 use serde_derive::{Deserialize, Serialize};
 
 /// CPU architecture, as reported by `target_arch`.
@@ -88,6 +89,121 @@ impl Target {
         )
         .to_lowercase()
     }
+
+    /// Returns names and values of `cfg` options describing this target,
+    /// e.g. `("target_os", "linux")`.
+    pub fn cfg_values(&self) -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("target_arch", self.arch.cfg_value()),
+            ("target_os", self.os.cfg_value()),
+            ("target_family", self.family.cfg_value()),
+            ("target_env", self.env.cfg_value()),
+            ("target_pointer_width", self.pointer_width.cfg_value()),
+            ("target_endian", self.endian.cfg_value()),
+        ]
+    }
+
+    /// Returns a `cfg` expression that is true only on this target.
+    pub fn cfg_expression(&self) -> String {
+        let values = self
+            .cfg_values()
+            .into_iter()
+            .map(|(name, value)| format!("{} = {:?}", name, value))
+            .join(", ");
+        format!("all({})", values)
+    }
+
+    /// Returns names of the preprocessor macros defined in the C++ wrapper library
+    /// when it's built for this target.
+    pub fn cpp_definitions(&self) -> Vec<String> {
+        self.cfg_values()
+            .into_iter()
+            .map(|(name, value)| cpp_definition(name, value))
+            .collect()
+    }
+}
+
+/// Returns name of the preprocessor macro that is defined in the C++ wrapper library
+/// if `cfg` option `name` has `value` on the current target,
+/// e.g. `RITUAL_TARGET_OS_LINUX`.
+pub fn cpp_definition(name: &str, value: &str) -> String {
+    let value = if value.is_empty() { "none" } else { value };
+    format!("RITUAL_{}_{}", name, value).to_uppercase()
+}
+
+impl Arch {
+    /// Returns value of `target_arch` for this architecture.
+    pub fn cfg_value(self) -> &'static str {
+        match self {
+            Arch::X86 => "x86",
+            Arch::X86_64 => "x86_64",
+            Arch::Mips => "mips",
+            Arch::PowerPC => "powerpc",
+            Arch::PowerPC64 => "powerpc64",
+            Arch::Arm => "arm",
+            Arch::AArch64 => "aarch64",
+        }
+    }
+}
+
+impl OS {
+    /// Returns value of `target_os` for this operating system.
+    pub fn cfg_value(self) -> &'static str {
+        match self {
+            OS::Windows => "windows",
+            OS::MacOS => "macos",
+            OS::IOS => "ios",
+            OS::Linux => "linux",
+            OS::Android => "android",
+            OS::FreeBSD => "freebsd",
+            OS::DragonFly => "dragonfly",
+            OS::Bitrig => "bitrig",
+            OS::OpenBSD => "openbsd",
+            OS::NetBSD => "netbsd",
+        }
+    }
+}
+
+impl Family {
+    /// Returns value of `target_family` for this family.
+    pub fn cfg_value(self) -> &'static str {
+        match self {
+            Family::Windows => "windows",
+            Family::Unix => "unix",
+        }
+    }
+}
+
+impl Env {
+    /// Returns value of `target_env` for this environment.
+    pub fn cfg_value(self) -> &'static str {
+        match self {
+            Env::Gnu => "gnu",
+            Env::Msvc => "msvc",
+            Env::Musl => "musl",
+            Env::None => "",
+        }
+    }
+}
+
+impl PointerWidth {
+    /// Returns value of `target_pointer_width` for this pointer width.
+    pub fn cfg_value(self) -> &'static str {
+        match self {
+            PointerWidth::P64 => "64",
+            PointerWidth::P32 => "32",
+        }
+    }
+}
+
+impl Endian {
+    /// Returns value of `target_endian` for this endianness.
+    pub fn cfg_value(self) -> &'static str {
+        match self {
+            Endian::Little => "little",
+            Endian::Big => "big",
+        }
+    }
 }
 
 /// Condition on properties of the target. Simple conditions
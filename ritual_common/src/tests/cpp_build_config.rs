@@ -0,0 +1,59 @@
+use crate::cpp_build_config::{closest_known_version, pkg_config_paths, CppLibraryDiscovery};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+#[test]
+fn pkg_config_output() {
+    let paths = pkg_config_paths(
+        "-I/usr/include/qt -I'/opt/my libs/include' -L/usr/lib/qt -lQt5Core -pthread\n",
+    )
+    .unwrap();
+    assert_eq!(
+        paths.include_paths(),
+        &[
+            PathBuf::from("/usr/include/qt"),
+            PathBuf::from("/opt/my libs/include")
+        ]
+    );
+    assert_eq!(paths.lib_paths(), &[PathBuf::from("/usr/lib/qt")]);
+}
+
+#[test]
+fn library_discovery() {
+    let dir = env::temp_dir().join(format!("ritual_discovery_test_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("libfoo.so.5"), "").unwrap();
+
+    let mut foo = CppLibraryDiscovery::new("foo", "RITUAL_TEST_FOO");
+    assert_eq!(foo.lib_dir_env_var(), "RITUAL_TEST_FOO_LIB_DIR");
+    assert_eq!(foo.include_dir_env_var(), "RITUAL_TEST_FOO_INCLUDE_DIR");
+    assert_eq!(foo.version_env_var(), "RITUAL_TEST_FOO_VERSION");
+    assert_eq!(foo.detect_version(), None);
+    assert!(foo.is_in_dir(&dir));
+    assert!(!CppLibraryDiscovery::new("fo", "RITUAL_TEST_FO").is_in_dir(&dir));
+
+    let err = foo.locate().unwrap_err().to_string();
+    assert!(err.contains("RITUAL_TEST_FOO_LIB_DIR"));
+
+    foo.add_lib_path(dir.join("missing"));
+    foo.add_lib_path(&dir);
+    foo.add_include_path(dir.join("include"));
+    let paths = foo.locate().unwrap();
+    assert_eq!(paths.lib_paths(), &[dir.clone()]);
+    assert_eq!(paths.include_paths(), &[dir.join("include")]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn closest_known_versions() {
+    let known = ["5.12.0", "5.15.2", "5.9.0", "custom"];
+    assert_eq!(closest_known_version("5.12.0", &known), Some("5.12.0"));
+    assert_eq!(closest_known_version("5.13.1", &known), Some("5.12.0"));
+    assert_eq!(closest_known_version("5.15.2", &known), Some("5.15.2"));
+    assert_eq!(closest_known_version("6.0.0", &known), Some("5.15.2"));
+    assert_eq!(closest_known_version("5.8.0", &known), None);
+    assert_eq!(closest_known_version("custom", &known), Some("custom"));
+    assert_eq!(closest_known_version("5.13", &known), None);
+}
@@ -1,6 +1,7 @@
+use crate::file_utils::normalize_path;
 use crate::string_utils::{CaseOperations, WordIterator};
 use itertools::Itertools;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[test]
 fn join() {
@@ -19,6 +20,17 @@ fn join() {
     assert_eq!(a4.iter().map(|x| x.to_uppercase()).join("!"), "ONE!TWO");
 }
 
+#[test]
+fn normalize_missing_path() {
+    let check = |path: &str, expected: &str| {
+        assert_eq!(normalize_path(Path::new(path)), PathBuf::from(expected));
+    };
+    check("/ritual_missing/a/../b/./c.h", "/ritual_missing/b/c.h");
+    check("/ritual_missing/../../c.h", "/c.h");
+    check("ritual_missing/../../c.h", "../c.h");
+    check("./ritual_missing/a/..", "ritual_missing");
+}
+
 #[test]
 fn path_buf_with_added() {
     let x = PathBuf::from("/tmp");
@@ -92,7 +104,7 @@ fn case_operations() {
 
     let s8 = "OpenGLFunctions".to_string();
     assert_eq!(s8.to_class_case(), "OpenGLFunctions");
-    assert_eq!(s8.to_snake_case(), "opengl_functions");
+    assert_eq!(s8.to_snake_case(), "open_gl_functions");
 
     let s9 = "QIODevice".to_string();
     assert_eq!(s9.to_class_case(), "QIODevice");
@@ -104,7 +116,7 @@ fn case_operations() {
 
     let s11 = "QOpenGLFunctions".to_string();
     assert_eq!(s11.to_class_case(), "QOpenGLFunctions");
-    assert_eq!(s11.to_snake_case(), "q_opengl_functions");
+    assert_eq!(s11.to_snake_case(), "q_open_gl_functions");
 
     let s12 = "QVector2D".to_string();
     assert_eq!(s12.to_class_case(), "QVector2D");
@@ -114,6 +126,22 @@ fn case_operations() {
     assert_eq!(s13.to_class_case(), "OneTwo2Three");
     assert_eq!(s13.to_snake_case(), "one_two2_three");
 
+    let s14 = "QXMLHTTPRequest".to_string();
+    assert_eq!(s14.to_class_case(), "QXMLHTTPRequest");
+    assert_eq!(s14.to_snake_case(), "q_xml_http_request");
+    assert_eq!(
+        s14.to_snake_case_with_acronyms(&["XML"]),
+        "q_xml_h_t_t_p_request"
+    );
+
+    let s15 = "OpenGLES2".to_string();
+    assert_eq!(s15.to_snake_case(), "open_gl_es_2");
+    assert_eq!("RGB32".to_snake_case(), "r_g_b32");
+
+    let s16 = "QDBusMessage".to_string();
+    assert_eq!(s16.to_snake_case(), "q_d_bus_message");
+    assert_eq!(s16.to_snake_case_with_acronyms(&["QD"]), "qd_bus_message");
+
     let v1 = vec!["from_utf8", "0"];
     assert_eq!(v1.to_snake_case(), "from_utf8_0");
     let v2 = vec!["from_utf8", "1"];
@@ -124,3 +152,35 @@ fn case_operations() {
     let v4 = vec!["count", "1"];
     assert_eq!(v4.to_snake_case(), "count1");
 }
+
+#[cfg(unix)]
+#[test]
+fn command_timeout() {
+    use crate::utils::{run_command_and_capture_output_with_timeout, AbnormalTermination};
+    use std::process::Command;
+    use std::time::{Duration, Instant};
+
+    let output = run_command_and_capture_output_with_timeout(
+        Command::new("sh")
+            .arg("-c")
+            .arg("echo started; sleep 10 & wait"),
+        Duration::from_millis(200),
+    )
+    .unwrap();
+    assert!(!output.is_success());
+    assert_eq!(
+        output.abnormal_termination,
+        Some(AbnormalTermination::Timeout)
+    );
+    assert_eq!(output.stdout, "started\n");
+
+    let started_time = Instant::now();
+    let output = run_command_and_capture_output_with_timeout(
+        Command::new("sh").arg("-c").arg("echo done"),
+        Duration::from_secs(10),
+    )
+    .unwrap();
+    assert!(output.is_success());
+    assert_eq!(output.stdout, "done\n");
+    assert!(started_time.elapsed() < Duration::from_secs(10));
+}
@@ -1,16 +1,17 @@
 //! Various utilities.
 
-use crate::errors::{bail, Result, ResultExt};
+use crate::errors::{bail, err_msg, Result, ResultExt};
 use log::trace;
 use serde_derive::{Deserialize, Serialize};
 use std::collections::hash_map::{Entry, HashMap};
 use std::ffi::OsString;
-use std::fmt::{Debug, Display};
 use std::hash::{BuildHasher, Hash};
-use std::io::{stderr, stdout, Write};
+use std::io::{stderr, stdout, Read, Write};
 use std::path::PathBuf;
-use std::process::Command;
+use std::process::{Child, Command, ExitStatus};
 use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 use std::{env, iter, process};
 
 #[cfg(windows)]
@@ -58,16 +59,40 @@ pub fn run_command(command: &mut Command) -> Result<()> {
     }
 }
 
+/// Reason of an abnormal termination of a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AbnormalTermination {
+    /// The command didn't finish in time and was killed
+    Timeout,
+    /// The command was terminated by a signal
+    Signal,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CommandOutput {
     pub status: i32,
     pub stdout: String,
     pub stderr: String,
+    #[serde(default)]
+    pub abnormal_termination: Option<AbnormalTermination>,
 }
 
 impl CommandOutput {
+    fn new(status: ExitStatus, stdout: &[u8], stderr: &[u8]) -> Self {
+        CommandOutput {
+            stdout: String::from_utf8_lossy(stdout).to_string(),
+            stderr: String::from_utf8_lossy(stderr).to_string(),
+            status: status.code().unwrap_or(-1),
+            abnormal_termination: if status.code().is_none() {
+                Some(AbnormalTermination::Signal)
+            } else {
+                None
+            },
+        }
+    }
+
     pub fn is_success(&self) -> bool {
-        self.status == 0
+        self.status == 0 && self.abnormal_termination.is_none()
     }
 }
 
@@ -80,13 +105,106 @@ pub fn run_command_and_capture_output(command: &mut Command) -> Result<CommandOu
     let output = command
         .output()
         .with_context(|_| format!("failed to run command: {:?}", command))?;
-    Ok(CommandOutput {
-        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-        status: output.status.code().unwrap_or(-1),
+    Ok(CommandOutput::new(
+        output.status,
+        &output.stdout,
+        &output.stderr,
+    ))
+}
+
+#[cfg(unix)]
+fn set_new_process_group(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setpgid(0, 0) == 0 {
+                Ok(())
+            } else {
+                Err(std::io::Error::last_os_error())
+            }
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn set_new_process_group(_command: &mut Command) {}
+
+/// Kills `child` and all processes it started.
+#[cfg(unix)]
+fn kill_process_group(child: &mut Child) -> Result<()> {
+    if unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) } != 0 {
+        child.kill()?;
+    }
+    Ok(())
+}
+
+/// Kills `child`. Processes started by the child are not affected.
+#[cfg(not(unix))]
+fn kill_process_group(child: &mut Child) -> Result<()> {
+    child.kill()?;
+    Ok(())
+}
+
+fn read_in_background(mut pipe: impl Read + Send + 'static) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut data = Vec::new();
+        let _ = pipe.read_to_end(&mut data);
+        data
     })
 }
 
+/// Runs a command and returns its output regardless of
+/// whether it was successful. If the command doesn't finish in `timeout`,
+/// it's killed along with all processes it started, and the output
+/// captured so far is returned.
+pub fn run_command_and_capture_output_with_timeout(
+    command: &mut Command,
+    timeout: Duration,
+) -> Result<CommandOutput> {
+    trace!("Executing command (timeout: {:?}): {:?}", timeout, command);
+    command.stdout(process::Stdio::piped());
+    command.stderr(process::Stdio::piped());
+    set_new_process_group(command);
+    let mut child = command
+        .spawn()
+        .with_context(|_| format!("failed to run command: {:?}", command))?;
+    let stdout_reader = read_in_background(child.stdout.take().unwrap());
+    let stderr_reader = read_in_background(child.stderr.take().unwrap());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if Instant::now() >= deadline {
+            kill_process_group(&mut child)?;
+            child.wait()?;
+            break None;
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+
+    let stdout = stdout_reader
+        .join()
+        .map_err(|_| err_msg("failed to read command output"))?;
+    let stderr = stderr_reader
+        .join()
+        .map_err(|_| err_msg("failed to read command output"))?;
+    match status {
+        Some(status) => Ok(CommandOutput::new(status, &stdout, &stderr)),
+        None => Ok(CommandOutput {
+            stdout: String::from_utf8_lossy(&stdout).to_string(),
+            stderr: format!(
+                "{}\ncommand timed out after {:?}",
+                String::from_utf8_lossy(&stderr),
+                timeout
+            ),
+            status: -1,
+            abnormal_termination: Some(AbnormalTermination::Timeout),
+        }),
+    }
+}
+
 /// Runs a command and returns its stdout if it was successful
 pub fn get_command_output(command: &mut Command) -> Result<String> {
     trace!("Executing command: {:?}", command);
@@ -144,17 +262,6 @@ pub fn add_env_path_item(env_var_name: &str, mut new_paths: Vec<PathBuf>) -> Res
     Ok(env::join_paths(new_paths).with_context(|_| "env::join_paths failed")?)
 }
 
-pub trait Inspect {
-    fn inspect(self, text: impl Display) -> Self;
-}
-
-impl<T: Debug> Inspect for T {
-    fn inspect(self, text: impl Display) -> Self {
-        println!("{} {:?}", text, self);
-        self
-    }
-}
-
 #[derive(Debug)]
 struct ProgressBarInner {
     message: String,
@@ -204,3 +311,34 @@ impl ProgressBarInner {
         self.print();
     }
 }
+
+/// Merges `a` and `b` recursively. `b` take precedence over `a`.
+pub fn recursive_merge_toml(a: toml::Value, b: toml::Value) -> toml::Value {
+    if a.same_type(&b) {
+        if let toml::Value::Array(mut a_array) = a {
+            if let toml::Value::Array(mut b_array) = b {
+                a_array.append(&mut b_array);
+                toml::Value::Array(a_array)
+            } else {
+                unreachable!()
+            }
+        } else if let toml::Value::Table(mut a_table) = a {
+            if let toml::Value::Table(b_table) = b {
+                for (key, value) in b_table {
+                    if let Some(old_value) = a_table.remove(&key) {
+                        a_table.insert(key, recursive_merge_toml(old_value, value));
+                    } else {
+                        a_table.insert(key, value);
+                    }
+                }
+                toml::Value::Table(a_table)
+            } else {
+                unreachable!()
+            }
+        } else {
+            b
+        }
+    } else {
+        b
+    }
+}